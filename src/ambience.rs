@@ -0,0 +1,157 @@
+use macroquad::audio::{
+    PlaySoundParams, Sound, load_sound_from_bytes, play_sound, set_sound_volume,
+};
+
+use crate::audio::{SAMPLE_RATE, apply_envelope, frequency_sweep, generate_wav, noise_burst};
+use crate::tile_map::TILE_SIZE;
+
+const LOOP_LENGTH: f32 = 4.0; // Seconds; whole seconds, so whole-hertz tones loop cleanly
+pub const AMBIENT_RANGE: f32 = TILE_SIZE * 8.0; // A source fades out to nothing by here
+
+/// Looping background sound tied to a kind of spot in the world
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ambience {
+    Machinery, // Terminals
+    Lava,
+    Water,
+}
+
+impl Ambience {
+    pub const ALL: [Ambience; 3] = [Ambience::Machinery, Ambience::Lava, Ambience::Water];
+
+    fn samples(self) -> Vec<f32> {
+        match self {
+            Ambience::Machinery => machinery_samples(),
+            Ambience::Lava => lava_samples(),
+            Ambience::Water => water_samples(),
+        }
+    }
+}
+
+/// Level of an ambient loop for the nearest source `distance` pixels from
+/// the listener: full on top of it, silent from AMBIENT_RANGE on
+pub fn proximity(distance: f32) -> f32 {
+    let level = (1.0 - distance / AMBIENT_RANGE).clamp(0.0, 1.0);
+    level * level
+}
+
+fn loop_len() -> usize {
+    (SAMPLE_RATE as f32 * LOOP_LENGTH) as usize
+}
+
+/// Add a sound into the loop at a sample offset, wrapping past the end
+fn add_at(track: &mut [f32], at: usize, sound: &[f32]) {
+    let len = track.len();
+    for (i, &sample) in sound.iter().enumerate() {
+        track[(at + i) % len] += sample;
+    }
+}
+
+/// Mains hum with a few harmonics, throbbing twice a second
+fn machinery_samples() -> Vec<f32> {
+    (0..loop_len())
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let tone = |freq: f32| (t * freq * std::f32::consts::TAU).sin();
+            let throb = 0.8 + 0.2 * tone(2.0);
+            (tone(60.0) * 0.25 + tone(120.0) * 0.12 + tone(180.0) * 0.05) * throb
+        })
+        .collect()
+}
+
+/// Low rumble with bubbles popping up through it
+fn lava_samples() -> Vec<f32> {
+    // Smoothed noise for the rumble
+    let mut level = 0.0;
+    let mut samples: Vec<f32> = noise_burst(LOOP_LENGTH, 1.0)
+        .into_iter()
+        .map(|s| {
+            level += (s - level) * 0.02;
+            level * 0.6
+        })
+        .collect();
+    for _ in 0..10 {
+        let at = macroquad::rand::gen_range(0, samples.len());
+        let start = macroquad::rand::gen_range(80.0, 160.0);
+        let mut bubble = frequency_sweep(start, start * 2.5, 0.08, 0.25);
+        apply_envelope(&mut bubble, 0.005, 0.06);
+        add_at(&mut samples, at, &bubble);
+    }
+    samples
+}
+
+/// Drops falling at odd moments, each with a faint echo
+fn water_samples() -> Vec<f32> {
+    let mut samples = vec![0.0; loop_len()];
+    for _ in 0..7 {
+        let at = macroquad::rand::gen_range(0, samples.len());
+        let start = macroquad::rand::gen_range(1400.0, 2200.0);
+        let mut drop = frequency_sweep(start, start * 0.5, 0.06, 0.3);
+        apply_envelope(&mut drop, 0.002, 0.05);
+        add_at(&mut samples, at, &drop);
+        let echo: Vec<f32> = drop.iter().map(|s| s * 0.3).collect();
+        add_at(&mut samples, at + SAMPLE_RATE as usize / 8, &echo);
+    }
+    samples
+}
+
+/// Keeps every ambient loop running, each at its own level
+#[derive(Default)]
+pub struct AmbiencePlayer {
+    loops: Vec<Option<Sound>>, // By Ambience; empty without audio
+    started: bool,
+}
+
+impl AmbiencePlayer {
+    /// Synthesize every loop
+    pub async fn load() -> Self {
+        let mut loops = Vec::new();
+        for ambience in Ambience::ALL {
+            let wav = generate_wav(&ambience.samples());
+            loops.push(load_sound_from_bytes(&wav).await.ok());
+        }
+        Self {
+            loops,
+            started: false,
+        }
+    }
+
+    /// Set each loop's volume (by Ambience), starting them all the first time
+    pub fn update(&mut self, levels: [f32; 3]) {
+        for (sound, level) in self.loops.iter().zip(levels) {
+            let Some(sound) = sound else {
+                continue;
+            };
+            if !self.started {
+                play_sound(
+                    sound,
+                    PlaySoundParams {
+                        looped: true,
+                        volume: level,
+                    },
+                );
+            }
+            set_sound_volume(sound, level);
+        }
+        self.started = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ambience() {
+        assert_eq!(proximity(0.0), 1.0);
+        assert!(proximity(AMBIENT_RANGE / 2.0) < 0.5);
+        assert_eq!(proximity(AMBIENT_RANGE), 0.0);
+        assert_eq!(proximity(AMBIENT_RANGE * 2.0), 0.0);
+
+        for ambience in Ambience::ALL {
+            let samples = ambience.samples();
+            assert_eq!(samples.len(), loop_len());
+            assert!(samples.iter().any(|&s| s != 0.0));
+        }
+    }
+}
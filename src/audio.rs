@@ -1,28 +1,282 @@
-use macroquad::audio::{Sound, load_sound_from_bytes, play_sound_once};
+use std::cell::{Cell, RefCell};
+
+use macroquad::audio::{PlaySoundParams, Sound, load_sound_from_bytes, play_sound};
+use macroquad::file::load_file;
+use macroquad::time::get_time;
+
+use crate::ambience::AmbiencePlayer;
+use crate::mixer::{Mixer, Priority};
+use crate::music::{MusicPlayer, Theme};
+use crate::tile_map::TILE_SIZE;
+
+pub const SAMPLE_RATE: u32 = 44100;
+const SOUNDS_DIR: &str = "data/sounds";
+const SOUND_EXTENSIONS: [&str; 2] = ["ogg", "wav"];
+const PAN_STEPS: usize = 5; // Stereo positions each world sound is rendered at, left to right
+const TAKE_PITCHES: [f32; 4] = [1.0, 0.95, 1.05, 0.98]; // Playback rates of a varied sound's takes
+const HEARING_RANGE: f32 = TILE_SIZE * 25.0; // World sounds fade out to nothing by here
+const PAN_WIDTH: f32 = TILE_SIZE * 12.0; // Sideways offset that puts a sound fully to one side
+pub const SPACE_RADIUS: i32 = 4; // Tiles around a shot that decide how much it echoes
+pub const LOW_HEALTH: f32 = 0.25; // Share of max health below which the heart starts pounding
+const HEARTBEAT_RATES: (f32, f32) = (1.0, 2.5); // Beats per second at LOW_HEALTH and near 0
+
+/// Sound that comes from somewhere in the world, so it is played quieter
+/// with distance and panned towards its side
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldSound {
+    BotShot,
+    Explosion,
+    BotDeath,
+    BotAlert,
+    BotInfect,
+}
+
+impl WorldSound {
+    const ALL: [WorldSound; 5] = [
+        WorldSound::BotShot,
+        WorldSound::Explosion,
+        WorldSound::BotDeath,
+        WorldSound::BotAlert,
+        WorldSound::BotInfect,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            WorldSound::BotShot => "bot_shot",
+            WorldSound::Explosion => "explosion",
+            WorldSound::BotDeath => "bot_death",
+            WorldSound::BotAlert => "bot_alert",
+            WorldSound::BotInfect => "bot_infect",
+        }
+    }
+
+    fn priority(self) -> Priority {
+        match self {
+            WorldSound::BotShot | WorldSound::BotAlert => Priority::Low,
+            WorldSound::Explosion | WorldSound::BotDeath | WorldSound::BotInfect => {
+                Priority::Normal
+            }
+        }
+    }
+
+    fn samples(self) -> Vec<f32> {
+        match self {
+            WorldSound::BotShot => pistol_shot_samples(),
+            WorldSound::Explosion => explosion_samples(),
+            WorldSound::BotDeath => bot_death_samples(),
+            WorldSound::BotAlert => bot_alert_samples(),
+            WorldSound::BotInfect => bot_infect_samples(),
+        }
+    }
+}
+
+/// A player weapon whose shot echoes with the space around it
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Gun {
+    Pistol,
+    Shotgun,
+    MachinePistol,
+    Rifle,
+}
+
+impl Gun {
+    const ALL: [Gun; 4] = [Gun::Pistol, Gun::Shotgun, Gun::MachinePistol, Gun::Rifle];
+
+    fn name(self) -> &'static str {
+        match self {
+            Gun::Pistol => "pistol",
+            Gun::Shotgun => "shotgun",
+            Gun::MachinePistol => "machine_pistol",
+            Gun::Rifle => "rifle",
+        }
+    }
+
+    fn samples(self) -> Vec<f32> {
+        match self {
+            Gun::Pistol => pistol_shot_samples(),
+            Gun::Shotgun => shotgun_blast_samples(),
+            Gun::MachinePistol => machine_pistol_samples(),
+            Gun::Rifle => rifle_shot_samples(),
+        }
+    }
+
+    /// Playback rates of its takes; only the rapid-fire gun gets several
+    fn pitches(self) -> &'static [f32] {
+        match self {
+            Gun::MachinePistol => &TAKE_PITCHES,
+            _ => &[1.0],
+        }
+    }
+}
+
+/// How the surroundings answer a gunshot: dry in tight corridors, a light
+/// echo in rooms, a longer one out in the open
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Space {
+    Tight,
+    Room,
+    Hall,
+}
+
+impl Space {
+    const ALL: [Space; 3] = [Space::Tight, Space::Room, Space::Hall];
+
+    /// The space around a shot with `open` of the tiles within SPACE_RADIUS
+    /// free (see `TileMap::open_tiles_around`)
+    pub fn from_open_tiles(open: usize) -> Space {
+        let side = (SPACE_RADIUS * 2 + 1) as f32;
+        let share = open as f32 / (side * side);
+        if share < 0.4 {
+            Space::Tight
+        } else if share < 0.75 {
+            Space::Room
+        } else {
+            Space::Hall
+        }
+    }
+
+    /// Samples as heard in this space
+    fn apply(self, samples: &[f32]) -> Vec<f32> {
+        match self {
+            Space::Tight => samples.to_vec(),
+            Space::Room => add_echo(samples, 0.07, 0.3, 2),
+            Space::Hall => add_echo(samples, 0.13, 0.4, 4),
+        }
+    }
+}
+
+/// Volume and pan step of a sound at `at` heard from `listener` (world
+/// pixels); None out of earshot
+fn placement(at: (f32, f32), listener: (f32, f32)) -> Option<(f32, usize)> {
+    let (dx, dy) = (at.0 - listener.0, at.1 - listener.1);
+    let gain = 1.0 - dx.hypot(dy) / HEARING_RANGE;
+    if gain <= 0.0 {
+        return None;
+    }
+    let pan = (dx / PAN_WIDTH).clamp(-1.0, 1.0);
+    let step = ((pan + 1.0) / 2.0 * (PAN_STEPS - 1) as f32).round() as usize;
+    Some((gain, step))
+}
+
+/// A sound rendered as a few takes at slightly different pitches and
+/// lengths, played in turn so rapid repeats don't all sound the same
+#[derive(Default)]
+struct Takes {
+    sounds: Vec<Sound>,
+    next: Cell<usize>,
+}
+
+impl Takes {
+    /// The take to play now; the one after it is up next time
+    fn next(&self) -> Option<&Sound> {
+        let sound = self.sounds.get(self.next.get())?;
+        self.next.set((self.next.get() + 1) % self.sounds.len());
+        Some(sound)
+    }
+}
+
+/// Heartbeat tempo in beats per second for the player's health, speeding
+/// up as it falls; None at LOW_HEALTH and above, or once dead
+pub fn heartbeat_rate(health: i32, max_health: i32) -> Option<f32> {
+    let share = health as f32 / max_health.max(1) as f32;
+    if health <= 0 || share >= LOW_HEALTH {
+        return None;
+    }
+    let (slow, fast) = HEARTBEAT_RATES;
+    Some(fast + (slow - fast) * share / LOW_HEALTH)
+}
+
+/// A short sound looped at a rate that can change every frame. macroquad
+/// can't speed up a playing sound, so each repeat is started on its own
+/// when the previous one's period is up
+#[derive(Default)]
+struct Pulse {
+    sound: Option<Sound>,
+    wait: Option<f32>, // Seconds to the next repeat; None while stopped
+}
+
+impl Pulse {
+    /// Advance by `dt` at `rate` repeats per second (None stops the loop);
+    /// true when a repeat is due now. A stopped loop starts on a repeat
+    fn tick(&mut self, dt: f32, rate: Option<f32>) -> bool {
+        let Some(rate) = rate.filter(|&r| r > 0.0) else {
+            self.wait = None;
+            return false;
+        };
+        let wait = self.wait.map_or(0.0, |w| w - dt);
+        // A faster rate shortens the wait right away
+        let wait = wait.min(1.0 / rate);
+        if wait <= 0.0 {
+            self.wait = Some(wait + 1.0 / rate);
+            true
+        } else {
+            self.wait = Some(wait);
+            false
+        }
+    }
+}
 
-const SAMPLE_RATE: u32 = 44100;
+/// Volume control on the settings screen; the master level scales the others
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Channel {
+    Master,
+    Sfx,
+    Music,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Sfx, Channel::Music];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Master => "Master Volume",
+            Channel::Sfx => "SFX Volume",
+            Channel::Music => "Music Volume",
+        }
+    }
+
+    /// Settings file key
+    pub fn key(self) -> &'static str {
+        match self {
+            Channel::Master => "volume",
+            Channel::Sfx => "sfx_volume",
+            Channel::Music => "music_volume",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Channel> {
+        Channel::ALL.into_iter().find(|c| c.key() == key)
+    }
+}
 
 /// Audio manager that gracefully handles missing audio support.
 /// All sounds are optional - if audio init fails, game continues silently.
 pub struct AudioManager {
     enabled: bool,
-    muted: bool,
+    levels: [f32; 3], // 0.0 to 1.0, by Channel
+    muted: bool,      // Silenced while the window is away
+    mixer: RefCell<Mixer>,
+    music: MusicPlayer,
+    ambience: AmbiencePlayer,
+    world: Vec<Vec<Option<Sound>>>, // By WorldSound, then pan step
+    heartbeat: Pulse,
     // Combat
     knife_swing: Option<Sound>,
-    pistol_shot: Option<Sound>,
-    shotgun_blast: Option<Sound>,
-    machine_pistol: Option<Sound>,
-    rifle_shot: Option<Sound>,
-    hit: Option<Sound>,
+    shots: Vec<Vec<Takes>>, // By Gun, then Space
+    hit: Takes,
     player_hit: Option<Sound>,
     player_death: Option<Sound>,
+    shield_hit: Option<Sound>,
+    dash: Option<Sound>,
+    roll: Option<Sound>,
+    shove: Option<Sound>,
     // Pickups
     pickup: Option<Sound>,
     health: Option<Sound>,
     powerup: Option<Sound>,
     // Hacking
     hack_start: Option<Sound>,
-    hack_blip: Option<Sound>,
+    hack_blip: Takes,
     hack_success: Option<Sound>,
     hack_fail: Option<Sound>,
     game_win: Option<Sound>,
@@ -32,30 +286,87 @@ async fn try_load_sound(data: &[u8]) -> Option<Sound> {
     load_sound_from_bytes(data).await.ok()
 }
 
+/// Channel level from 0.0 to 1.0 for a volume in percent
+fn level(percent: u32) -> f32 {
+    percent.min(100) as f32 / 100.0
+}
+
+/// A sound file from data/sounds (`<name>.ogg`, then `<name>.wav`), if one
+/// is there and decodes
+async fn load_sound_file(name: &str) -> Option<Sound> {
+    for extension in SOUND_EXTENSIONS {
+        let path = format!("{SOUNDS_DIR}/{name}.{extension}");
+        let Ok(bytes) = load_file(&path).await else {
+            continue;
+        };
+        match load_sound_from_bytes(&bytes).await {
+            Ok(sound) => return Some(sound),
+            Err(err) => eprintln!("Could not load {path} ({err}) - using the built-in sound"),
+        }
+    }
+    None
+}
+
+/// The sound file for `name` when there is one, otherwise the synthesized
+/// sound
+async fn load_or_synth(name: &str, synth: fn() -> Vec<u8>) -> Option<Sound> {
+    match load_sound_file(name).await {
+        Some(sound) => Some(sound),
+        None => try_load_sound(&synth()).await,
+    }
+}
+
+/// The sound file for `name` as a single take, otherwise one synthesized
+/// take per TAKE_PITCHES entry
+async fn load_takes(name: &str, synth: fn() -> Vec<f32>) -> Takes {
+    let mut sounds = Vec::new();
+    if let Some(sound) = load_sound_file(name).await {
+        sounds.push(sound);
+    } else {
+        for pitch in TAKE_PITCHES {
+            let wav = generate_wav(&repitch(&synth(), pitch));
+            sounds.extend(try_load_sound(&wav).await);
+        }
+    }
+    Takes {
+        sounds,
+        next: Cell::new(0),
+    }
+}
+
 impl AudioManager {
-    pub async fn load() -> Self {
+    /// Load every sound, at volume levels in percent by channel
+    pub async fn load(volumes: [u32; 3]) -> Self {
+        let levels = volumes.map(level);
+
         // Try to load the first sound to test if audio works
-        let test_sound = try_load_sound(&generate_pistol_shot()).await;
-        let enabled = test_sound.is_some();
+        let enabled = try_load_sound(&generate_pistol_shot()).await.is_some();
 
         if !enabled {
             eprintln!("Audio initialization failed - running without sound");
             return Self {
                 enabled: false,
+                levels,
                 muted: false,
+                mixer: RefCell::default(),
+                music: MusicPlayer::default(),
+                ambience: AmbiencePlayer::default(),
+                world: Vec::new(),
+                heartbeat: Pulse::default(),
                 knife_swing: None,
-                pistol_shot: None,
-                shotgun_blast: None,
-                machine_pistol: None,
-                rifle_shot: None,
-                hit: None,
+                shots: Vec::new(),
+                hit: Takes::default(),
                 player_hit: None,
                 player_death: None,
+                shield_hit: None,
+                dash: None,
+                roll: None,
+                shove: None,
                 pickup: None,
                 health: None,
                 powerup: None,
                 hack_start: None,
-                hack_blip: None,
+                hack_blip: Takes::default(),
                 hack_success: None,
                 hack_fail: None,
                 game_win: None,
@@ -64,109 +375,289 @@ impl AudioManager {
 
         Self {
             enabled: true,
+            levels,
             muted: false,
+            mixer: RefCell::default(),
+            music: MusicPlayer::load().await,
+            ambience: AmbiencePlayer::load().await,
+            world: load_world_sounds().await,
+            heartbeat: Pulse {
+                sound: load_or_synth("heartbeat", generate_heartbeat).await,
+                wait: None,
+            },
             // Combat sounds
-            knife_swing: try_load_sound(&generate_knife_swing()).await,
-            pistol_shot: test_sound, // Reuse the test sound
-            shotgun_blast: try_load_sound(&generate_shotgun_blast()).await,
-            machine_pistol: try_load_sound(&generate_machine_pistol()).await,
-            rifle_shot: try_load_sound(&generate_rifle_shot()).await,
-            hit: try_load_sound(&generate_hit()).await,
-            player_hit: try_load_sound(&generate_player_hit()).await,
-            player_death: try_load_sound(&generate_player_death()).await,
+            knife_swing: load_or_synth("knife", generate_knife_swing).await,
+            shots: load_shots().await,
+            hit: load_takes("hit", hit_samples).await,
+            player_hit: load_or_synth("player_hit", generate_player_hit).await,
+            player_death: load_or_synth("player_death", generate_player_death).await,
+            shield_hit: load_or_synth("shield_hit", generate_shield_hit).await,
+            dash: load_or_synth("dash", generate_dash).await,
+            roll: load_or_synth("roll", generate_roll).await,
+            shove: load_or_synth("shove", generate_shove).await,
             // Pickup sounds
-            pickup: try_load_sound(&generate_pickup()).await,
-            health: try_load_sound(&generate_health()).await,
-            powerup: try_load_sound(&generate_powerup()).await,
+            pickup: load_or_synth("pickup", generate_pickup).await,
+            health: load_or_synth("health", generate_health).await,
+            powerup: load_or_synth("powerup", generate_powerup).await,
             // Hacking sounds
-            hack_start: try_load_sound(&generate_hack_start()).await,
-            hack_blip: try_load_sound(&generate_hack_blip()).await,
-            hack_success: try_load_sound(&generate_hack_success()).await,
-            hack_fail: try_load_sound(&generate_hack_fail()).await,
-            game_win: try_load_sound(&generate_game_win()).await,
+            hack_start: load_or_synth("hack_start", generate_hack_start).await,
+            hack_blip: load_takes("hack_blip", hack_blip_samples).await,
+            hack_success: load_or_synth("hack_success", generate_hack_success).await,
+            hack_fail: load_or_synth("hack_fail", generate_hack_fail).await,
+            game_win: load_or_synth("game_win", generate_game_win).await,
         }
     }
 
-    fn play(&self, sound: &Option<Sound>) {
-        if self.muted {
+    fn play(&self, name: &'static str, sound: Option<&Sound>, priority: Priority) {
+        self.play_on(name, sound, priority, Channel::Sfx, 1.0);
+    }
+
+    /// Play at a channel's level, scaled by the master level and `gain`,
+    /// unless the mixer turns it away because too much is playing
+    fn play_on(
+        &self,
+        name: &'static str,
+        sound: Option<&Sound>,
+        priority: Priority,
+        channel: Channel,
+        gain: f32,
+    ) {
+        let volume = self.volume(channel) * gain;
+        if volume <= 0.0 {
             return;
         }
-        if let Some(s) = sound {
-            play_sound_once(s);
+        if let Some(s) = sound
+            && self.mixer.borrow_mut().admit(name, priority, get_time())
+        {
+            play_sound(
+                s,
+                PlaySoundParams {
+                    looped: false,
+                    volume,
+                },
+            );
+        }
+    }
+
+    /// Effective volume of a channel; the master channel on its own
+    fn volume(&self, channel: Channel) -> f32 {
+        if self.muted {
+            return 0.0;
         }
+        let master = self.levels[Channel::Master as usize];
+        match channel {
+            Channel::Master => master,
+            _ => master * self.levels[channel as usize],
+        }
+    }
+
+    /// Silence everything, loops included, until unmuted; the levels are
+    /// kept
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Set every channel's level, in percent (indexed by Channel)
+    pub fn set_levels(&mut self, percents: [u32; 3]) {
+        self.levels = percents.map(level);
     }
 
-    pub fn toggle_mute(&mut self) {
-        self.muted = !self.muted;
+    /// Play a world sound from where it happened, as heard at `listener`
+    /// (world pixels, the middle of the view)
+    pub fn play_at(&self, sound: WorldSound, at: (f32, f32), listener: (f32, f32)) {
+        let Some((gain, step)) = placement(at, listener) else {
+            return;
+        };
+        let take = self
+            .world
+            .get(sound as usize)
+            .and_then(|pans| pans[step].as_ref());
+        self.play_on(sound.name(), take, sound.priority(), Channel::Sfx, gain);
+    }
+
+    /// Keep the background music on a theme, at the music level, with its
+    /// layers following the tension (0 safe to 1)
+    pub fn update_music(&mut self, dt: f32, theme: Theme, tension: f32) {
+        let volume = self.volume(Channel::Music);
+        self.music.update(dt, theme, tension, volume);
+    }
+
+    /// Keep the ambient loops playing at their levels (by `Ambience`, 0 to
+    /// 1), on the sound effect channel
+    pub fn update_ambience(&mut self, levels: [f32; 3]) {
+        let volume = self.volume(Channel::Sfx);
+        self.ambience.update(levels.map(|level| level * volume));
     }
 
-    pub fn is_muted(&self) -> bool {
-        self.muted
+    /// Keep the heartbeat going at `rate` beats per second (see
+    /// `heartbeat_rate`); None stops it
+    pub fn update_heartbeat(&mut self, dt: f32, rate: Option<f32>) {
+        if self.heartbeat.tick(dt, rate) {
+            self.play("heartbeat", self.heartbeat.sound.as_ref(), Priority::High);
+        }
+    }
+
+    /// Short blip at a channel's new level; master previews sound effects
+    pub fn play_preview(&self, channel: Channel) {
+        let channel = if channel == Channel::Master {
+            Channel::Sfx
+        } else {
+            channel
+        };
+        self.play_on(
+            "preview",
+            self.hack_blip.next(),
+            Priority::High,
+            channel,
+            1.0,
+        );
     }
 
-    pub fn play_shoot(&self, weapon_index: usize) {
-        if !self.enabled || self.muted {
+    /// Fire a weapon (by slot), echoing as it would in `space`
+    pub fn play_shoot(&self, weapon_index: usize, space: Space) {
+        if !self.enabled {
             return;
         }
-        let sound = match weapon_index {
-            0 => &self.knife_swing,
-            1 => &self.pistol_shot,
-            2 => &self.shotgun_blast,
-            3 => &self.machine_pistol,
-            4 => &self.rifle_shot,
-            _ => &self.pistol_shot,
+        let gun = match weapon_index {
+            0 => {
+                self.play("knife", self.knife_swing.as_ref(), Priority::Normal);
+                return;
+            }
+            2 => Gun::Shotgun,
+            3 => Gun::MachinePistol,
+            4 => Gun::Rifle,
+            _ => Gun::Pistol,
         };
-        self.play(sound);
+        let take = self
+            .shots
+            .get(gun as usize)
+            .and_then(|spaces| spaces[space as usize].next());
+        self.play(gun.name(), take, Priority::Normal);
     }
 
     pub fn play_hit(&self) {
-        self.play(&self.hit);
+        self.play("hit", self.hit.next(), Priority::Normal);
     }
 
     pub fn play_player_hit(&self) {
-        self.play(&self.player_hit);
+        self.play("player_hit", self.player_hit.as_ref(), Priority::High);
     }
 
     pub fn play_player_death(&self) {
-        self.play(&self.player_death);
+        self.play("player_death", self.player_death.as_ref(), Priority::High);
+    }
+
+    pub fn play_shield_hit(&self) {
+        self.play("shield_hit", self.shield_hit.as_ref(), Priority::High);
+    }
+
+    pub fn play_dash(&self) {
+        self.play("dash", self.dash.as_ref(), Priority::Normal);
+    }
+
+    pub fn play_roll(&self) {
+        self.play("roll", self.roll.as_ref(), Priority::Normal);
+    }
+
+    pub fn play_shove(&self) {
+        self.play("shove", self.shove.as_ref(), Priority::Normal);
     }
 
     pub fn play_pickup(&self) {
-        self.play(&self.pickup);
+        self.play("pickup", self.pickup.as_ref(), Priority::Low);
     }
 
     pub fn play_health(&self) {
-        self.play(&self.health);
+        self.play("health", self.health.as_ref(), Priority::Normal);
     }
 
     pub fn play_powerup(&self) {
-        self.play(&self.powerup);
+        self.play("powerup", self.powerup.as_ref(), Priority::Normal);
     }
 
     pub fn play_hack_start(&self) {
-        self.play(&self.hack_start);
+        self.play("hack_start", self.hack_start.as_ref(), Priority::Normal);
     }
 
     pub fn play_hack_blip(&self) {
-        self.play(&self.hack_blip);
+        self.play("hack_blip", self.hack_blip.next(), Priority::Low);
     }
 
     pub fn play_hack_success(&self) {
-        self.play(&self.hack_success);
+        self.play("hack_success", self.hack_success.as_ref(), Priority::High);
     }
 
     pub fn play_hack_fail(&self) {
-        self.play(&self.hack_fail);
+        self.play("hack_fail", self.hack_fail.as_ref(), Priority::High);
     }
 
     pub fn play_game_win(&self) {
-        self.play(&self.game_win);
+        self.play("game_win", self.game_win.as_ref(), Priority::High);
     }
 }
 
+/// Every gun in every space: its sound file in each, or its synthesized
+/// takes with that space's echo baked in
+async fn load_shots() -> Vec<Vec<Takes>> {
+    let mut shots = Vec::new();
+    for gun in Gun::ALL {
+        let file = load_sound_file(gun.name()).await;
+        let mut spaces = Vec::new();
+        for space in Space::ALL {
+            let mut sounds = Vec::new();
+            if let Some(sound) = &file {
+                sounds.push(sound.clone());
+            } else {
+                for &pitch in gun.pitches() {
+                    let wav = generate_wav(&space.apply(&repitch(&gun.samples(), pitch)));
+                    sounds.extend(try_load_sound(&wav).await);
+                }
+            }
+            spaces.push(Takes {
+                sounds,
+                next: Cell::new(0),
+            });
+        }
+        shots.push(spaces);
+    }
+    shots
+}
+
+/// Every world sound at every pan step
+async fn load_world_sounds() -> Vec<Vec<Option<Sound>>> {
+    let mut world = Vec::new();
+    for sound in WorldSound::ALL {
+        let samples = sound.samples();
+        let mut pans = Vec::new();
+        for step in 0..PAN_STEPS {
+            let pan = step as f32 / (PAN_STEPS - 1) as f32 * 2.0 - 1.0;
+            pans.push(try_load_sound(&generate_stereo_wav(&samples, pan)).await);
+        }
+        world.push(pans);
+    }
+    world
+}
+
 // ============ WAV Generation ============
 
-fn generate_wav(samples: &[f32]) -> Vec<u8> {
+pub fn generate_wav(samples: &[f32]) -> Vec<u8> {
+    encode_wav(samples, 1)
+}
+
+/// Stereo WAV of a mono sound panned from -1 (left) to 1 (right), keeping
+/// the same loudness across the field
+fn generate_stereo_wav(samples: &[f32], pan: f32) -> Vec<u8> {
+    let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+    let (left, right) = (angle.cos(), angle.sin());
+    let frames: Vec<f32> = samples
+        .iter()
+        .flat_map(|&s| [s * left, s * right])
+        .collect();
+    encode_wav(&frames, 2)
+}
+
+/// WAV file of interleaved samples for `channels` channels
+fn encode_wav(samples: &[f32], channels: u16) -> Vec<u8> {
     let num_samples = samples.len();
     let data_size = num_samples * 2; // 16-bit samples
     let file_size = 36 + data_size;
@@ -182,10 +673,11 @@ fn generate_wav(samples: &[f32]) -> Vec<u8> {
     wav.extend_from_slice(b"fmt ");
     wav.extend_from_slice(&16u32.to_le_bytes()); // chunk size
     wav.extend_from_slice(&1u16.to_le_bytes()); // PCM format
-    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&channels.to_le_bytes());
     wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes()); // sample rate
-    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
-    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    let block_align = channels * 2;
+    wav.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&block_align.to_le_bytes());
     wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
 
     // data chunk
@@ -203,7 +695,7 @@ fn generate_wav(samples: &[f32]) -> Vec<u8> {
 
 // ============ Sound Synthesis Primitives ============
 
-fn sine_wave(freq: f32, duration: f32, volume: f32) -> Vec<f32> {
+pub fn sine_wave(freq: f32, duration: f32, volume: f32) -> Vec<f32> {
     let num_samples = (SAMPLE_RATE as f32 * duration) as usize;
     let mut samples = Vec::with_capacity(num_samples);
 
@@ -216,7 +708,7 @@ fn sine_wave(freq: f32, duration: f32, volume: f32) -> Vec<f32> {
     samples
 }
 
-fn noise_burst(duration: f32, volume: f32) -> Vec<f32> {
+pub fn noise_burst(duration: f32, volume: f32) -> Vec<f32> {
     let num_samples = (SAMPLE_RATE as f32 * duration) as usize;
     let mut samples = Vec::with_capacity(num_samples);
 
@@ -228,7 +720,7 @@ fn noise_burst(duration: f32, volume: f32) -> Vec<f32> {
     samples
 }
 
-fn frequency_sweep(start_freq: f32, end_freq: f32, duration: f32, volume: f32) -> Vec<f32> {
+pub fn frequency_sweep(start_freq: f32, end_freq: f32, duration: f32, volume: f32) -> Vec<f32> {
     let num_samples = (SAMPLE_RATE as f32 * duration) as usize;
     let mut samples = Vec::with_capacity(num_samples);
     let mut phase = 0.0f32;
@@ -244,7 +736,7 @@ fn frequency_sweep(start_freq: f32, end_freq: f32, duration: f32, volume: f32) -
     samples
 }
 
-fn apply_envelope(samples: &mut [f32], attack: f32, decay: f32) {
+pub fn apply_envelope(samples: &mut [f32], attack: f32, decay: f32) {
     let attack_samples = (SAMPLE_RATE as f32 * attack) as usize;
     let decay_samples = (SAMPLE_RATE as f32 * decay) as usize;
     let len = samples.len();
@@ -265,6 +757,49 @@ fn apply_envelope(samples: &mut [f32], attack: f32, decay: f32) {
     }
 }
 
+/// Samples played back `pitch` times as fast: higher and shorter above 1,
+/// lower and longer below
+fn repitch(samples: &[f32], pitch: f32) -> Vec<f32> {
+    let len = (samples.len() as f32 / pitch) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f32 * pitch;
+            let index = pos as usize;
+            let a = samples.get(index).copied().unwrap_or(0.0);
+            let b = samples.get(index + 1).copied().unwrap_or(0.0);
+            a + (b - a) * pos.fract()
+        })
+        .collect()
+}
+
+/// Samples followed by `count` echoes `delay` seconds apart, each `level`
+/// times as loud as the one before and a little duller
+fn add_echo(samples: &[f32], delay: f32, level: f32, count: usize) -> Vec<f32> {
+    let step = (SAMPLE_RATE as f32 * delay) as usize;
+    let mut result = vec![0.0; samples.len() + step * count];
+    result[..samples.len()].copy_from_slice(samples);
+
+    let mut echo = samples.to_vec();
+    let mut gain = 1.0;
+    for n in 1..=count {
+        // One-pole low-pass: walls swallow the highs first
+        let mut smooth = 0.0;
+        for sample in &mut echo {
+            smooth += (*sample - smooth) * 0.5;
+            *sample = smooth;
+        }
+        gain *= level;
+        for (i, &sample) in echo.iter().enumerate() {
+            result[n * step + i] += sample * gain;
+        }
+    }
+
+    for sample in &mut result {
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    result
+}
+
 fn mix(a: &[f32], b: &[f32]) -> Vec<f32> {
     let len = a.len().max(b.len());
     let mut result = vec![0.0; len];
@@ -289,43 +824,94 @@ fn generate_knife_swing() -> Vec<u8> {
 }
 
 fn generate_pistol_shot() -> Vec<u8> {
+    generate_wav(&pistol_shot_samples())
+}
+
+fn pistol_shot_samples() -> Vec<f32> {
     let mut sine = sine_wave(180.0, 0.08, 0.5);
     let noise = noise_burst(0.03, 0.6);
     apply_envelope(&mut sine, 0.001, 0.06);
-    let samples = mix(&sine, &noise);
-    generate_wav(&samples)
+    mix(&sine, &noise)
 }
 
-fn generate_shotgun_blast() -> Vec<u8> {
+fn shotgun_blast_samples() -> Vec<f32> {
     let mut low = sine_wave(80.0, 0.15, 0.6);
     let noise = noise_burst(0.1, 0.7);
     apply_envelope(&mut low, 0.001, 0.12);
-    let samples = mix(&low, &noise);
-    generate_wav(&samples)
+    mix(&low, &noise)
 }
 
-fn generate_machine_pistol() -> Vec<u8> {
+fn machine_pistol_samples() -> Vec<f32> {
     let mut sine = sine_wave(350.0, 0.04, 0.4);
     let noise = noise_burst(0.02, 0.3);
     apply_envelope(&mut sine, 0.001, 0.03);
-    let samples = mix(&sine, &noise);
-    generate_wav(&samples)
+    mix(&sine, &noise)
 }
 
-fn generate_rifle_shot() -> Vec<u8> {
+fn rifle_shot_samples() -> Vec<f32> {
     let mut sine = sine_wave(150.0, 0.12, 0.5);
     let crack = noise_burst(0.02, 0.8);
     apply_envelope(&mut sine, 0.001, 0.1);
-    let samples = mix(&sine, &crack);
-    generate_wav(&samples)
+    mix(&sine, &crack)
 }
 
-fn generate_hit() -> Vec<u8> {
+fn hit_samples() -> Vec<f32> {
     let mut thud = sine_wave(120.0, 0.1, 0.5);
     let sweep = frequency_sweep(200.0, 80.0, 0.08, 0.3);
     apply_envelope(&mut thud, 0.001, 0.08);
-    let samples = mix(&thud, &sweep);
-    generate_wav(&samples)
+    mix(&thud, &sweep)
+}
+
+fn explosion_samples() -> Vec<f32> {
+    // Deep falling boom under a long burst of rubble noise
+    let boom = frequency_sweep(90.0, 30.0, 0.6, 0.8);
+    let rubble = noise_burst(0.45, 0.5);
+    let mut samples = mix(&boom, &rubble);
+    apply_envelope(&mut samples, 0.002, 0.4);
+    samples
+}
+
+fn bot_alert_samples() -> Vec<f32> {
+    // Two rising chirps, the second higher: the bot has spotted someone
+    let mut first = frequency_sweep(900.0, 1500.0, 0.05, 0.35);
+    let mut second = frequency_sweep(1300.0, 2100.0, 0.06, 0.35);
+    apply_envelope(&mut first, 0.002, 0.02);
+    apply_envelope(&mut second, 0.002, 0.03);
+    let pause = vec![0.0; (SAMPLE_RATE as f32 * 0.03) as usize];
+
+    let mut samples = first;
+    samples.extend_from_slice(&pause);
+    samples.extend_from_slice(&second);
+    samples
+}
+
+fn bot_death_samples() -> Vec<f32> {
+    // Power-down: a hum sliding into the floor, with a crackle as it goes out
+    let hum = frequency_sweep(600.0, 40.0, 0.5, 0.45);
+    let undertone = frequency_sweep(300.0, 20.0, 0.5, 0.25);
+    let crackle = noise_burst(0.05, 0.3);
+    let mut samples = mix(&mix(&hum, &undertone), &crackle);
+    apply_envelope(&mut samples, 0.002, 0.25);
+    samples
+}
+
+fn bot_infect_samples() -> Vec<f32> {
+    // Glitchy screech: a shrill sweep over static, crushed by holding
+    // every sample for a few more, and cut out in stutters
+    let screech = frequency_sweep(1800.0, 2600.0, 0.3, 0.35);
+    let fuzz = noise_burst(0.3, 0.15);
+    let mut samples = mix(&screech, &fuzz);
+    const HOLD: usize = 6;
+    const STUTTER: usize = (SAMPLE_RATE / 40) as usize;
+    for i in 0..samples.len() {
+        samples[i] = if (i / STUTTER) % 3 == 2 {
+            0.0
+        } else {
+            samples[i - i % HOLD]
+        };
+    }
+    apply_envelope(&mut samples, 0.002, 0.08);
+    samples
 }
 
 fn generate_player_hit() -> Vec<u8> {
@@ -352,6 +938,56 @@ fn generate_player_death() -> Vec<u8> {
     generate_wav(&samples)
 }
 
+fn generate_heartbeat() -> Vec<u8> {
+    // Lub-dub: a low thump and a softer, slightly higher one right after
+    let mut lub = frequency_sweep(70.0, 45.0, 0.1, 0.7);
+    let mut dub = frequency_sweep(85.0, 55.0, 0.08, 0.5);
+    apply_envelope(&mut lub, 0.005, 0.07);
+    apply_envelope(&mut dub, 0.005, 0.06);
+    let pause = vec![0.0; (SAMPLE_RATE as f32 * 0.06) as usize];
+
+    let mut samples = lub;
+    samples.extend_from_slice(&pause);
+    samples.extend_from_slice(&dub);
+    generate_wav(&samples)
+}
+
+fn generate_shield_hit() -> Vec<u8> {
+    // Bright electric zap, higher than the flesh hit
+    let sweep = frequency_sweep(1400.0, 700.0, 0.1, 0.35);
+    let buzz = noise_burst(0.04, 0.2);
+    let mut samples = mix(&sweep, &buzz);
+    apply_envelope(&mut samples, 0.002, 0.06);
+    generate_wav(&samples)
+}
+
+fn generate_dash() -> Vec<u8> {
+    // Airy whoosh: falling noise over a quick downward sweep
+    let sweep = frequency_sweep(900.0, 250.0, 0.15, 0.2);
+    let air = noise_burst(0.15, 0.25);
+    let mut samples = mix(&sweep, &air);
+    apply_envelope(&mut samples, 0.01, 0.1);
+    generate_wav(&samples)
+}
+
+fn generate_roll() -> Vec<u8> {
+    // Low scuff: short noise over a soft thump
+    let thump = frequency_sweep(160.0, 90.0, 0.12, 0.35);
+    let scuff = noise_burst(0.12, 0.15);
+    let mut samples = mix(&thump, &scuff);
+    apply_envelope(&mut samples, 0.005, 0.08);
+    generate_wav(&samples)
+}
+
+fn generate_shove() -> Vec<u8> {
+    // Heavy shoulder bump: a deep thud with a short crack of noise
+    let thud = frequency_sweep(120.0, 60.0, 0.1, 0.5);
+    let crack = noise_burst(0.04, 0.25);
+    let mut samples = mix(&thud, &crack);
+    apply_envelope(&mut samples, 0.002, 0.06);
+    generate_wav(&samples)
+}
+
 fn generate_pickup() -> Vec<u8> {
     // Rising arpeggio: three quick notes
     let note1 = sine_wave(440.0, 0.06, 0.4);
@@ -396,10 +1032,10 @@ fn generate_hack_start() -> Vec<u8> {
     generate_wav(&samples)
 }
 
-fn generate_hack_blip() -> Vec<u8> {
+fn hack_blip_samples() -> Vec<f32> {
     let mut blip = sine_wave(600.0, 0.05, 0.3);
     apply_envelope(&mut blip, 0.005, 0.03);
-    generate_wav(&blip)
+    blip
 }
 
 fn generate_hack_success() -> Vec<u8> {
@@ -447,3 +1083,81 @@ fn generate_game_win() -> Vec<u8> {
     apply_envelope(&mut samples, 0.02, 0.2);
     generate_wav(&samples)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_sound_placement() {
+        let listener = (1000.0, 1000.0);
+        let (gain, step) = placement(listener, listener).unwrap();
+        assert_eq!((gain, step), (1.0, PAN_STEPS / 2));
+
+        // Off to the sides, and quieter further away
+        let (near, left) = placement((1000.0 - PAN_WIDTH, 1000.0), listener).unwrap();
+        assert_eq!(left, 0);
+        let (far, right) = placement((1000.0 + PAN_WIDTH * 1.5, 1000.0), listener).unwrap();
+        assert_eq!(right, PAN_STEPS - 1);
+        assert!(far < near && near < 1.0);
+        assert_eq!(placement((1000.0, 1000.0 + HEARING_RANGE), listener), None);
+
+        // A stereo sound holds two samples per frame
+        let mono = generate_wav(&[0.5; 10]);
+        let stereo = generate_stereo_wav(&[0.5; 10], 0.0);
+        assert_eq!(stereo.len() - 44, (mono.len() - 44) * 2);
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        assert_eq!(heartbeat_rate(100, 100), None);
+        assert_eq!(heartbeat_rate(25, 100), None);
+        assert_eq!(heartbeat_rate(0, 100), None);
+        let slow = heartbeat_rate(24, 100).unwrap();
+        let fast = heartbeat_rate(2, 100).unwrap();
+        assert!(slow < fast && fast <= HEARTBEAT_RATES.1);
+
+        // Beats on starting, then once per period
+        let mut pulse = Pulse::default();
+        assert!(pulse.tick(0.1, Some(2.0)));
+        assert!(!pulse.tick(0.3, Some(2.0)));
+        assert!(pulse.tick(0.3, Some(2.0)));
+        // A faster rate takes over before the old period is up
+        assert!(!pulse.tick(0.1, Some(4.0)));
+        assert!(pulse.tick(0.3, Some(4.0)));
+        // Stopping forgets the wait
+        assert!(!pulse.tick(0.1, None));
+        assert!(pulse.tick(0.1, Some(1.0)));
+    }
+
+    #[test]
+    fn test_space() {
+        assert_eq!(Space::from_open_tiles(9), Space::Tight);
+        assert_eq!(Space::from_open_tiles(45), Space::Room);
+        assert_eq!(Space::from_open_tiles(81), Space::Hall);
+
+        // Corridors stay dry; bigger spaces ring on for longer, quieter each time
+        let click = [1.0; 10];
+        assert_eq!(Space::Tight.apply(&click), click);
+        let room = Space::Room.apply(&click);
+        let hall = Space::Hall.apply(&click);
+        assert!(room.len() > click.len() && hall.len() > room.len());
+        assert_eq!(room[..click.len()], click);
+        let step = (SAMPLE_RATE as f32 * 0.07) as usize;
+        assert!(room[step + 5] > 0.0 && room[step + 5] < room[5]);
+        assert!(room[2 * step + 5] < room[step + 5]);
+    }
+
+    #[test]
+    fn test_repitch() {
+        let ramp: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        assert_eq!(repitch(&ramp, 1.0), ramp);
+        // Faster is shorter and steps further per sample
+        let up = repitch(&ramp, 1.25);
+        assert_eq!(up.len(), 80);
+        assert_eq!(up[4], 5.0);
+        let down = repitch(&ramp, 0.5);
+        assert_eq!(down.len(), 200);
+        assert_eq!(down[3], 1.5);
+    }
+}
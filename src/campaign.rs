@@ -0,0 +1,179 @@
+use macroquad::prelude::*;
+
+use crate::sprites::SpriteSheet;
+use crate::tile_map::TileMap;
+use crate::ui::{ui_height, ui_width};
+
+const CAMPAIGN_FILE: &str = "data/campaign.txt";
+/// Built-in copy of the campaign, used when the file can't be read
+const DEFAULT_CAMPAIGN: &str = include_str!("../data/campaign.txt");
+
+/// One authored campaign map with its briefing and spawn markers
+pub struct Level {
+    pub title: String,
+    pub intro: Vec<String>,
+    pub map: TileMap,
+    pub start: (i32, i32),
+    pub terminals: Vec<(i32, i32)>,
+    pub bots: Vec<(i32, i32)>,
+    pub hostiles: Vec<(i32, i32)>,
+    pub boss: Option<(i32, i32)>,
+}
+
+impl Level {
+    /// Build a level from its map rows, placing the markers
+    fn new(title: String, intro: Vec<String>, rows: &[&str]) -> Result<Self, String> {
+        let (map, markers) = TileMap::from_rows(rows)?;
+        let mut start = None;
+        let mut level = Self {
+            title,
+            intro,
+            map,
+            start: (0, 0),
+            terminals: Vec::new(),
+            bots: Vec::new(),
+            hostiles: Vec::new(),
+            boss: None,
+        };
+        for (marker, x, y) in markers {
+            match marker {
+                '@' if start.is_none() => start = Some((x, y)),
+                'T' => level.terminals.push((x, y)),
+                'b' => level.bots.push((x, y)),
+                'h' => level.hostiles.push((x, y)),
+                'B' if level.boss.is_none() => level.boss = Some((x, y)),
+                '@' | 'B' => return Err(format!("more than one '{marker}'")),
+                _ => return Err(format!("unknown map symbol '{marker}'")),
+            }
+        }
+        level.start = start.ok_or("no player start '@'")?;
+        if level.terminals.is_empty() {
+            return Err("no terminal 'T'".to_string());
+        }
+        Ok(level)
+    }
+}
+
+/// The fixed sequence of campaign levels
+pub struct Campaign {
+    pub levels: Vec<Level>,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Self::parse(DEFAULT_CAMPAIGN).expect("built-in campaign is valid")
+    }
+}
+
+impl Campaign {
+    /// Load levels from the campaign file, falling back to the built-in copy
+    pub async fn load() -> Self {
+        match load_string(CAMPAIGN_FILE).await {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|err| {
+                eprintln!("Invalid {CAMPAIGN_FILE} ({err}) - using built-in campaign");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse the campaign file format (see data/campaign.txt)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut levels = Vec::new();
+        let mut lines = text.lines().enumerate();
+        let mut current: Option<(String, Vec<String>)> = None;
+
+        while let Some((number, raw)) = lines.next() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", number + 1, msg);
+
+            if let Some(title) = line.strip_prefix("level ") {
+                current = Some((title.trim().to_string(), Vec::new()));
+            } else if let Some(text) = line.strip_prefix("intro ") {
+                let (_, intro) = current.as_mut().ok_or_else(|| err("intro before level"))?;
+                intro.push(text.trim().to_string());
+            } else if line == "map" {
+                let (title, intro) = current.take().ok_or_else(|| err("map before level"))?;
+                let rows: Vec<&str> = lines
+                    .by_ref()
+                    .map(|(_, row)| row.trim_end())
+                    .take_while(|row| *row != "end")
+                    .collect();
+                levels.push(Level::new(title, intro, &rows).map_err(|msg| err(&msg))?);
+            } else {
+                return Err(err("expected 'level', 'intro' or 'map'"));
+            }
+        }
+
+        if levels.is_empty() {
+            return Err("no levels".to_string());
+        }
+        Ok(Self { levels })
+    }
+}
+
+/// Briefing shown before each campaign level; Enter starts it
+pub fn draw_level_intro(level: &Level, number: usize, total: usize, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let center = |text: &str, y: f32, size: f32, color: Color| {
+        let width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(text, (ui_width() - width) / 2.0, y, size, color);
+    };
+
+    let y = ui_height() / 2.0 - 110.0;
+    let heading = if level.boss.is_some() {
+        "FINAL LEVEL".to_string()
+    } else {
+        format!("LEVEL {} / {}", number, total)
+    };
+    center(&heading, y, 22.0, Color::from_rgba(180, 140, 255, 255));
+    center(
+        &level.title.to_uppercase(),
+        y + 50.0,
+        44.0,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+    for (i, line) in level.intro.iter().enumerate() {
+        center(line, y + 100.0 + i as f32 * 28.0, 20.0, WHITE);
+    }
+
+    center(
+        "Enter/Space: Start",
+        ui_height() - 40.0,
+        16.0,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_campaign() {
+        let campaign = Campaign::default();
+        assert!(campaign.levels.len() >= 2);
+        for level in &campaign.levels {
+            assert!(!level.intro.is_empty());
+        }
+        // Only the final level has a boss
+        let (last, rest) = campaign.levels.split_last().unwrap();
+        assert!(last.boss.is_some());
+        assert!(rest.iter().all(|level| level.boss.is_none()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let level = |map: &str| format!("level Test\nintro Hi\nmap\n{map}\nend\n");
+        assert!(Campaign::parse(&level("####\n#@T#\n####")).is_ok());
+        assert!(Campaign::parse(&level("####\n#.T#\n####")).is_err());
+        assert!(Campaign::parse(&level("####\n#@.#\n####")).is_err());
+        assert!(Campaign::parse(&level("####\n#@T?\n####")).is_err());
+        assert!(Campaign::parse("intro Hi\n").is_err());
+        assert!(Campaign::parse("").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use macroquad::prelude::*;
+
+use crate::meta::{Profile, Unlock};
+use crate::save::persist_enum;
+use crate::sprites::{SpriteSheet, direction};
+use crate::ui::{ui_height, ui_width};
+
+pub const SOLDIER_BONUS_HEALTH: i32 = 25;
+pub const HACKER_HACK_CUT: f32 = 0.3; // Fraction off hack duration
+pub const SCOUT_SPEED_MULT: f32 = 1.15;
+
+/// Character class picked when starting a new game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayerClass {
+    Soldier,
+    Hacker,
+    Scout,
+}
+
+persist_enum!(PlayerClass {
+    Soldier,
+    Hacker,
+    Scout
+});
+
+impl PlayerClass {
+    pub const ALL: [PlayerClass; 3] = [
+        PlayerClass::Soldier,
+        PlayerClass::Hacker,
+        PlayerClass::Scout,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerClass::Soldier => "SOLDIER",
+            PlayerClass::Hacker => "HACKER",
+            PlayerClass::Scout => "SCOUT",
+        }
+    }
+
+    pub fn description(self) -> [&'static str; 2] {
+        match self {
+            PlayerClass::Soldier => ["Starts with a pistol", "+25 max health"],
+            PlayerClass::Hacker => ["30% faster hacks", "Knife only"],
+            PlayerClass::Scout => ["+15% move speed", "Knife only"],
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PlayerClass::Soldier => Color::from_rgba(230, 120, 80, 255),
+            PlayerClass::Hacker => Color::from_rgba(100, 200, 255, 255),
+            PlayerClass::Scout => Color::from_rgba(130, 230, 110, 255),
+        }
+    }
+
+    /// Multiplier on terminal hack duration
+    pub fn hack_duration_mult(self) -> f32 {
+        if self == PlayerClass::Hacker {
+            1.0 - HACKER_HACK_CUT
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A choice under the class cards on the New Game screen (rules, mode, ...)
+pub struct SetupRow {
+    pub label: &'static str,
+    pub value: &'static str,
+    pub description: &'static str,
+    pub color: Color,
+}
+
+/// New Game setup screen: class cards with the option rows below. `focus`
+/// 0 is the cards, then one per row.
+pub fn draw_class_select(
+    selected: usize,
+    rows: &[SetupRow],
+    focus: usize,
+    profile: &Profile,
+    sprites: &SpriteSheet,
+) {
+    sprites.draw_splash(0.6);
+
+    let title = "CHOOSE YOUR CLASS";
+    let title_size = 40.0;
+    let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+    draw_text(
+        title,
+        (ui_width() - title_width) / 2.0,
+        80.0,
+        title_size,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+
+    let card_width = 200.0;
+    let card_height = 200.0;
+    let gap = 24.0;
+    let total = card_width * 3.0 + gap * 2.0;
+    let start_x = (ui_width() - total) / 2.0;
+    let y = 110.0;
+
+    for (i, class) in PlayerClass::ALL.iter().enumerate() {
+        let x = start_x + i as f32 * (card_width + gap);
+        let color = class.color();
+        let is_selected = i == selected;
+        let border = if is_selected && focus == 0 { 3.0 } else { 1.5 };
+        draw_rectangle(
+            x,
+            y,
+            card_width,
+            card_height,
+            Color::from_rgba(15, 15, 25, 230),
+        );
+        if is_selected {
+            draw_rectangle(x, y, card_width, card_height, Color { a: 0.15, ..color });
+        }
+        draw_rectangle_lines(
+            x,
+            y,
+            card_width,
+            card_height,
+            border,
+            if is_selected { color } else { DARKGRAY },
+        );
+
+        let name_width = measure_text(class.name(), None, 26, 1.0).width;
+        draw_text(
+            class.name(),
+            x + (card_width - name_width) / 2.0,
+            y + 36.0,
+            26.0,
+            color,
+        );
+        sprites.draw_player_tinted(
+            x + card_width / 2.0 - 16.0,
+            y + 56.0,
+            direction::DOWN,
+            color,
+        );
+        if let Some(unlock) = Unlock::for_class(*class)
+            && !profile.is_unlocked(unlock)
+        {
+            let locked = format!("LOCKED - {} tokens", unlock.cost());
+            draw_text(
+                &locked,
+                x + 16.0,
+                y + 130.0,
+                18.0,
+                Color::from_rgba(255, 210, 90, 255),
+            );
+            continue;
+        }
+        for (line, text) in class.description().iter().enumerate() {
+            draw_text(
+                text,
+                x + 16.0,
+                y + 130.0 + line as f32 * 24.0,
+                18.0,
+                if is_selected { WHITE } else { GRAY },
+            );
+        }
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let row_y = y + card_height + 40.0 + i as f32 * 48.0;
+        let focused = focus == i + 1;
+        let label = if focused {
+            format!("{}:  < {} >", row.label, row.value)
+        } else {
+            format!("{}:  {}", row.label, row.value)
+        };
+        let label_width = measure_text(&label, None, 24, 1.0).width;
+        draw_text(
+            &label,
+            (ui_width() - label_width) / 2.0,
+            row_y,
+            24.0,
+            if focused { row.color } else { GRAY },
+        );
+        let text_width = measure_text(row.description, None, 16, 1.0).width;
+        draw_text(
+            row.description,
+            (ui_width() - text_width) / 2.0,
+            row_y + 20.0,
+            16.0,
+            if focused { LIGHTGRAY } else { DARKGRAY },
+        );
+    }
+
+    let hint = "W/S: Choose row  |  A/D: Change  |  Enter/Space: Start  |  ESC: Back";
+    let hint_size = 16.0;
+    let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+    draw_text(
+        hint,
+        (ui_width() - hint_width) / 2.0,
+        ui_height() - 40.0,
+        hint_size,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
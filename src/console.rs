@@ -0,0 +1,162 @@
+use macroquad::prelude::*;
+
+use crate::ui::ui_height;
+
+const CHARS_PER_SECOND: f32 = 45.0;
+const LINGER_TIME: f32 = 3.0; // Seconds the console stays up after typing finishes
+const FADE_TIME: f32 = 0.5;
+
+/// Flavor for compromised terminals: (subsystem, status line)
+const LORE: [(&str, &str); 8] = [
+    ("POWER GRID", "Sector lighting rerouted to emergency bus."),
+    (
+        "FABRICATOR CONTROL",
+        "Bot assembly queue halted. Infection vector isolated.",
+    ),
+    (
+        "PATROL ROUTING",
+        "Hostile path tables corrupted. Expect confusion.",
+    ),
+    ("COOLANT LOOP", "Core temperature climbing. Not my problem."),
+    (
+        "ARCHIVE NODE",
+        "Recovered memo: 'Do NOT let the bots talk to each other.'",
+    ),
+    (
+        "DOOR CONTROLLER",
+        "Override tokens harvested from door bus.",
+    ),
+    (
+        "SENSOR ARRAY",
+        "Camera feeds now loop 30 seconds of empty corridor.",
+    ),
+    ("COMMS RELAY", "Distress signal queued. Nobody is coming."),
+];
+
+/// Terminal-style overlay that types out its lines one character at a time,
+/// lingers, then fades away.
+pub struct Console {
+    lines: Vec<String>,
+    elapsed: f32,
+}
+
+impl Console {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Status report for a freshly hacked terminal
+    pub fn hack_report(node: usize, remaining: usize) -> Self {
+        let (subsystem, status) = LORE[rand::gen_range(0, LORE.len())];
+        let progress = match remaining {
+            0 => "> All nodes compromised.".to_string(),
+            1 => "> 1 node remaining.".to_string(),
+            n => format!("> {} nodes remaining.", n),
+        };
+        Self::new(vec![
+            format!("> ACCESS GRANTED: NODE-{:02}", node + 1),
+            format!("> Subsystem compromised: {}", subsystem),
+            format!("> {}", status),
+            progress,
+        ])
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    fn total_chars(&self) -> usize {
+        self.lines.iter().map(|l| l.chars().count()).sum()
+    }
+
+    fn typing_time(&self) -> f32 {
+        self.total_chars() as f32 / CHARS_PER_SECOND
+    }
+
+    /// Finished typing, lingered and faded out
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.typing_time() + LINGER_TIME + FADE_TIME
+    }
+
+    /// Lines typed so far, the last one possibly partial
+    fn visible_lines(&self) -> Vec<String> {
+        let mut budget = (self.elapsed * CHARS_PER_SECOND) as usize;
+        let mut visible = Vec::new();
+        for line in &self.lines {
+            if budget == 0 {
+                break;
+            }
+            let len = line.chars().count();
+            visible.push(line.chars().take(budget).collect());
+            budget = budget.saturating_sub(len);
+        }
+        visible
+    }
+
+    pub fn draw(&self) {
+        let fade_start = self.typing_time() + LINGER_TIME;
+        let alpha = 1.0 - ((self.elapsed - fade_start) / FADE_TIME).clamp(0.0, 1.0);
+        let font_size = 18.0;
+        let line_height = 22.0;
+        let width = 520.0;
+        let height = self.lines.len() as f32 * line_height + 24.0;
+        let x = 16.0;
+        let y = ui_height() - height - 16.0;
+
+        draw_rectangle(
+            x,
+            y,
+            width,
+            height,
+            Color::new(0.0, 0.06, 0.03, 0.85 * alpha),
+        );
+        draw_rectangle_lines(x, y, width, height, 1.0, Color::new(0.3, 1.0, 0.45, alpha));
+
+        let text_color = Color::new(0.3, 1.0, 0.45, alpha);
+        let visible = self.visible_lines();
+        for (i, line) in visible.iter().enumerate() {
+            let ty = y + 24.0 + i as f32 * line_height;
+            draw_text(line, x + 12.0, ty, font_size, text_color);
+
+            // Blinking cursor after the line being typed
+            let typing = i + 1 == visible.len() && self.elapsed < self.typing_time();
+            if typing && (self.elapsed * 4.0) as i32 % 2 == 0 {
+                let w = measure_text(line, None, font_size as u16, 1.0).width;
+                draw_rectangle(x + 14.0 + w, ty - 13.0, 8.0, 15.0, text_color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typewriter_reveals_gradually() {
+        let mut console = Console::new(vec!["abcd".to_string(), "efgh".to_string()]);
+        assert!(console.visible_lines().is_empty());
+
+        console.update(2.0 / CHARS_PER_SECOND);
+        assert_eq!(console.visible_lines(), vec!["ab"]);
+
+        console.update(4.0 / CHARS_PER_SECOND);
+        assert_eq!(console.visible_lines(), vec!["abcd", "ef"]);
+
+        console.update(10.0);
+        assert_eq!(console.visible_lines(), vec!["abcd", "efgh"]);
+    }
+
+    #[test]
+    fn test_console_expires() {
+        let mut console = Console::hack_report(0, 2);
+        assert!(!console.is_done());
+        console.update(console.typing_time() + LINGER_TIME);
+        assert!(!console.is_done());
+        console.update(FADE_TIME);
+        assert!(console.is_done());
+    }
+}
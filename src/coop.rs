@@ -0,0 +1,250 @@
+use macroquad::prelude::*;
+
+use crate::skin::Skin;
+use crate::tile_map::TILE_SIZE;
+
+const REVIVE_TIME: f32 = 3.0; // Seconds of holding interact beside a downed partner
+pub const REVIVE_HEALTH: f32 = 0.5; // Share of max health a revived player gets back
+const MIN_ZOOM: f32 = 0.6; // Furthest the shared camera zooms out
+const VIEW_MARGIN: f32 = TILE_SIZE * 3.0; // Room kept around each player at the view edge
+
+/// Second player's look: a blue suit, or red when the lead already wears blue
+pub fn partner_skin(lead: Skin) -> Skin {
+    if lead == Skin::Cobalt {
+        Skin::Crimson
+    } else {
+        Skin::Cobalt
+    }
+}
+
+/// Shared co-op camera: the midpoint of both players (pixel centers) and a
+/// zoom that keeps them on screen, never closer than 1 or further than
+/// MIN_ZOOM, so a partner who runs off far enough can still leave the view
+pub fn shared_view(a: (f32, f32), b: (f32, f32), screen: (f32, f32)) -> ((f32, f32), f32) {
+    let mid = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let span_x = (a.0 - b.0).abs() + VIEW_MARGIN * 2.0;
+    let span_y = (a.1 - b.1).abs() + VIEW_MARGIN * 2.0;
+    let zoom = (screen.0 / span_x)
+        .min(screen.1 / span_y)
+        .clamp(MIN_ZOOM, 1.0);
+    (mid, zoom)
+}
+
+/// Whether two players (pixel centers) are too far apart for the shared
+/// camera even at MIN_ZOOM, so each needs a view of their own
+pub fn needs_split(a: (f32, f32), b: (f32, f32), screen: (f32, f32)) -> bool {
+    let span_x = (a.0 - b.0).abs() + VIEW_MARGIN * 2.0;
+    let span_y = (a.1 - b.1).abs() + VIEW_MARGIN * 2.0;
+    span_x > screen.0 / MIN_ZOOM || span_y > screen.1 / MIN_ZOOM
+}
+
+/// Screen halves for split-screen: the lead on the left, the partner on
+/// the right
+pub fn split_rects(screen: (f32, f32)) -> [Rect; 2] {
+    let half = (screen.0 / 2.0).floor();
+    [
+        Rect::new(0.0, 0.0, half, screen.1),
+        Rect::new(half, 0.0, screen.0 - half, screen.1),
+    ]
+}
+
+/// One camera onto the world: the world pixel at its top-left corner, its
+/// zoom, and the part of the screen it draws into
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct View {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    pub rect: Rect, // Screen pixels
+}
+
+impl View {
+    /// View of `rect` centered on a world pixel, kept inside a map `bounds`
+    /// pixels large
+    pub fn centered(focus: (f32, f32), zoom: f32, rect: Rect, bounds: (f32, f32)) -> Self {
+        let (w, h) = (rect.w / zoom, rect.h / zoom);
+        Self {
+            x: focus.0 - w / 2.0,
+            y: focus.1 - h / 2.0,
+            zoom,
+            rect,
+        }
+        .clamped(bounds)
+    }
+
+    /// The same view moved back inside a map `bounds` pixels large
+    pub fn clamped(self, bounds: (f32, f32)) -> Self {
+        let (w, h) = self.size();
+        Self {
+            x: self.x.clamp(0.0, (bounds.0 - w).max(0.0)),
+            y: self.y.clamp(0.0, (bounds.1 - h).max(0.0)),
+            ..self
+        }
+    }
+
+    /// World area shown, in pixels
+    pub fn size(&self) -> (f32, f32) {
+        (self.rect.w / self.zoom, self.rect.h / self.zoom)
+    }
+
+    /// World pixel under a screen pixel
+    pub fn world_at(&self, (sx, sy): (f32, f32)) -> (f32, f32) {
+        (
+            (sx - self.rect.x) / self.zoom + self.x,
+            (sy - self.rect.y) / self.zoom + self.y,
+        )
+    }
+
+    /// Screen pixel showing a world pixel
+    pub fn screen_at(&self, (wx, wy): (f32, f32)) -> Vec2 {
+        vec2(
+            (wx - self.x) * self.zoom + self.rect.x,
+            (wy - self.y) * self.zoom + self.rect.y,
+        )
+    }
+
+    /// Whether a world pixel position is in view (with a one-tile margin)
+    pub fn shows(&self, x: f32, y: f32) -> bool {
+        let (w, h) = self.size();
+        x >= self.x - TILE_SIZE
+            && x <= self.x + w + TILE_SIZE
+            && y >= self.y - TILE_SIZE
+            && y <= self.y + h + TILE_SIZE
+    }
+
+    /// Draw in view space from here on: world pixels less (x, y), scaled
+    /// into the view's part of the screen. Views only split side by side,
+    /// so the viewport's vertical origin never matters.
+    pub fn apply(&self) {
+        let (w, h) = self.size();
+        set_camera(&Camera2D {
+            target: vec2(w / 2.0, h / 2.0),
+            zoom: vec2(2.0 / w, 2.0 / h),
+            viewport: Some((
+                self.rect.x as i32,
+                self.rect.y as i32,
+                self.rect.w as i32,
+                self.rect.h as i32,
+            )),
+            ..Default::default()
+        });
+    }
+}
+
+/// A downed player being helped up by their partner
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Revive {
+    progress: f32, // Seconds held so far
+}
+
+impl Revive {
+    /// Whether the reviver stands close enough (tiles) to help
+    pub fn in_reach(reviver: (i32, i32), downed: (i32, i32)) -> bool {
+        (reviver.0 - downed.0).abs() <= 1 && (reviver.1 - downed.1).abs() <= 1
+    }
+
+    /// Hold to revive; letting go starts over. Returns true when the downed
+    /// player gets back up.
+    pub fn update(&mut self, dt: f32, holding: bool) -> bool {
+        if !holding {
+            self.progress = 0.0;
+            return false;
+        }
+        self.progress += dt;
+        if self.progress < REVIVE_TIME {
+            return false;
+        }
+        self.progress = 0.0;
+        true
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.progress / REVIVE_TIME
+    }
+
+    /// DOWN tag and revive progress over the downed player (pixel position
+    /// on screen)
+    pub fn draw(&self, screen_x: f32, screen_y: f32) {
+        let color = Color::from_rgba(120, 255, 140, 255);
+        draw_text("DOWN", screen_x - 16.0, screen_y - 24.0, 16.0, RED);
+        if self.progress > 0.0 {
+            draw_rectangle(
+                screen_x - 16.0,
+                screen_y + 20.0,
+                32.0,
+                4.0,
+                Color::from_rgba(0, 0, 0, 180),
+            );
+            draw_rectangle(
+                screen_x - 16.0,
+                screen_y + 20.0,
+                32.0 * self.fraction(),
+                4.0,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_view() {
+        let screen = (800.0, 600.0);
+        // Close together: no zoom, centered between them
+        let (mid, zoom) = shared_view((100.0, 100.0), (200.0, 140.0), screen);
+        assert_eq!(mid, (150.0, 120.0));
+        assert_eq!(zoom, 1.0);
+        // Spread out: zoomed out, but only so far
+        let (_, zoom) = shared_view((0.0, 0.0), (900.0, 0.0), screen);
+        assert!((MIN_ZOOM..1.0).contains(&zoom));
+        let (_, zoom) = shared_view((0.0, 0.0), (5000.0, 3000.0), screen);
+        assert_eq!(zoom, MIN_ZOOM);
+    }
+
+    #[test]
+    fn test_split_views() {
+        let screen = (800.0, 600.0);
+        assert!(!needs_split((0.0, 0.0), (900.0, 0.0), screen));
+        assert!(needs_split((0.0, 0.0), (5000.0, 0.0), screen));
+
+        let [left, right] = split_rects(screen);
+        assert_eq!(left.w + right.w, screen.0);
+        assert_eq!(right.x, left.w);
+
+        // Each half follows its own player, clamped to the map
+        let bounds = (3200.0, 1600.0);
+        let view = View::centered((1000.0, 800.0), 1.0, right, bounds);
+        assert_eq!((view.x, view.y), (800.0, 500.0));
+        assert!(view.shows(1000.0, 800.0));
+        assert!(!view.shows(200.0, 800.0));
+        let corner = View::centered((3190.0, 10.0), 1.0, left, bounds);
+        assert_eq!((corner.x, corner.y), (2800.0, 0.0));
+        let pushed = View { x: -50.0, ..view }.clamped(bounds);
+        assert_eq!((pushed.x, pushed.y), (0.0, 500.0));
+
+        // Screen and world pixels convert through the view's corner, zoom
+        // and place on the screen
+        let zoomed = View::centered((1000.0, 800.0), 2.0, right, bounds);
+        let screen = zoomed.screen_at((1000.0, 800.0));
+        assert_eq!(screen, right.center());
+        assert_eq!(zoomed.world_at((screen.x, screen.y)), (1000.0, 800.0));
+    }
+
+    #[test]
+    fn test_revive() {
+        assert!(Revive::in_reach((3, 3), (4, 4)));
+        assert!(!Revive::in_reach((3, 3), (5, 3)));
+
+        let mut revive = Revive::default();
+        assert!(!revive.update(REVIVE_TIME - 1.0, true));
+        assert!(!revive.update(0.5, false)); // Letting go starts over
+        assert_eq!(revive.fraction(), 0.0);
+        assert!(!revive.update(REVIVE_TIME - 1.0, true));
+        assert!(revive.update(1.0, true));
+
+        assert_ne!(partner_skin(Skin::Cobalt), Skin::Cobalt);
+    }
+}
@@ -0,0 +1,270 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, persist_enum};
+use crate::sprites::SpriteSheet;
+use crate::ui::{ui_height, ui_width};
+
+const RECORDS_FILE: &str = "daily.txt";
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// Rule change of a daily challenge; each day picks two
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DailyModifier {
+    ShotgunsOnly, // Every weapon drop is a shotgun
+    DoubleBots,
+    ScarceLoot, // Half the drop chances
+    Hardcore,   // A single life
+}
+
+persist_enum!(DailyModifier {
+    ShotgunsOnly,
+    DoubleBots,
+    ScarceLoot,
+    Hardcore
+});
+
+impl DailyModifier {
+    pub const ALL: [DailyModifier; 4] = [
+        DailyModifier::ShotgunsOnly,
+        DailyModifier::DoubleBots,
+        DailyModifier::ScarceLoot,
+        DailyModifier::Hardcore,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DailyModifier::ShotgunsOnly => "SHOTGUNS ONLY",
+            DailyModifier::DoubleBots => "DOUBLE BOTS",
+            DailyModifier::ScarceLoot => "SCARCE LOOT",
+            DailyModifier::Hardcore => "HARDCORE",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            DailyModifier::ShotgunsOnly => "Every weapon you find is a shotgun",
+            DailyModifier::DoubleBots => "Twice as many bots on the map",
+            DailyModifier::ScarceLoot => "Half the usual drops",
+            DailyModifier::Hardcore => "One life, no second chances",
+        }
+    }
+}
+
+/// The challenge for one calendar day (UTC): the same map seed and
+/// modifiers for everyone playing on that date
+#[derive(Clone, Debug, PartialEq)]
+pub struct Daily {
+    pub date: String,
+    pub seed: u64,
+    pub modifiers: [DailyModifier; 2],
+}
+
+impl Daily {
+    pub fn today() -> Self {
+        let day = (macroquad::miniquad::date::now() / SECONDS_PER_DAY).floor() as i64;
+        Self::for_day(day)
+    }
+
+    /// Challenge for a day counted from 1970-01-01
+    pub fn for_day(day: i64) -> Self {
+        let seed = mix(day as u64);
+        let count = DailyModifier::ALL.len();
+        let first = (seed % count as u64) as usize;
+        let second = (first + 1 + (seed / count as u64 % (count as u64 - 1)) as usize) % count;
+        let (year, month, date) = civil_date(day);
+        Self {
+            date: format!("{year:04}-{month:02}-{date:02}"),
+            seed,
+            modifiers: [DailyModifier::ALL[first], DailyModifier::ALL[second]],
+        }
+    }
+
+    pub fn has(&self, modifier: DailyModifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+}
+
+impl Persist for Daily {
+    fn write(&self, out: &mut String) {
+        self.date.write(out);
+        self.seed.write(out);
+        self.modifiers.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            date: fields.read()?,
+            seed: fields.read()?,
+            modifiers: fields.read()?,
+        })
+    }
+}
+
+/// Scramble a day number into a well spread seed (splitmix64)
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Year, month and day of a day counted from 1970-01-01
+fn civil_date(day: i64) -> (i64, u32, u32) {
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let date = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, date)
+}
+
+/// Best daily challenge score per date, kept on this machine
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DailyRecords {
+    scores: Vec<(String, u32)>,
+}
+
+impl DailyRecords {
+    /// Read the records file; a missing or unreadable file starts empty
+    pub fn load() -> Self {
+        std::fs::read_to_string(RECORDS_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = std::fs::write(RECORDS_FILE, self.serialize()) {
+            eprintln!("Could not save {RECORDS_FILE}: {err}");
+        }
+    }
+
+    /// Parse `<date> <score>` lines; malformed lines are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut records = Self::default();
+        for line in text.lines() {
+            if let [date, score] = line.split_whitespace().collect::<Vec<_>>().as_slice()
+                && let Ok(score) = score.parse()
+            {
+                records.record(date, score);
+            }
+        }
+        records
+    }
+
+    pub fn serialize(&self) -> String {
+        self.scores
+            .iter()
+            .map(|(date, score)| format!("{date} {score}\n"))
+            .collect()
+    }
+
+    pub fn best(&self, date: &str) -> Option<u32> {
+        self.scores
+            .iter()
+            .find(|(d, _)| d == date)
+            .map(|&(_, score)| score)
+    }
+
+    /// Keep `score` if it beats the date's best. Returns true if it did.
+    pub fn record(&mut self, date: &str, score: u32) -> bool {
+        match self.scores.iter_mut().find(|(d, _)| d == date) {
+            Some((_, best)) if *best >= score => false,
+            Some((_, best)) => {
+                *best = score;
+                true
+            }
+            None => {
+                self.scores.push((date.to_string(), score));
+                true
+            }
+        }
+    }
+}
+
+/// Daily challenge screen: today's date, modifiers and best score
+pub fn draw_daily(daily: &Daily, best: Option<u32>, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let center = |text: &str, y: f32, size: f32, color: Color| {
+        let width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(text, (ui_width() - width) / 2.0, y, size, color);
+    };
+
+    let y = ui_height() / 2.0 - 130.0;
+    center(
+        "DAILY CHALLENGE",
+        y,
+        40.0,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+    center(&daily.date, y + 40.0, 24.0, WHITE);
+    for (i, modifier) in daily.modifiers.iter().enumerate() {
+        let row = y + 100.0 + i as f32 * 56.0;
+        center(
+            modifier.name(),
+            row,
+            26.0,
+            Color::from_rgba(255, 170, 60, 255),
+        );
+        center(modifier.description(), row + 22.0, 18.0, LIGHTGRAY);
+    }
+    let record = match best {
+        Some(score) => format!("Today's best: {}", score),
+        None => "No score yet today".to_string(),
+    };
+    center(
+        &record,
+        y + 230.0,
+        22.0,
+        Color::from_rgba(255, 210, 90, 255),
+    );
+
+    center(
+        "Enter/Space: Start  |  ESC: Back",
+        ui_height() - 40.0,
+        16.0,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_from_date() {
+        assert_eq!(Daily::for_day(0).date, "1970-01-01");
+        assert_eq!(Daily::for_day(19_723).date, "2024-01-01");
+        assert_eq!(Daily::for_day(19_782).date, "2024-02-29");
+
+        // Same day, same challenge; the two modifiers always differ
+        assert_eq!(Daily::for_day(20_000), Daily::for_day(20_000));
+        assert_ne!(Daily::for_day(20_000).seed, Daily::for_day(20_001).seed);
+        for day in 20_000..20_100 {
+            let daily = Daily::for_day(day);
+            assert_ne!(daily.modifiers[0], daily.modifiers[1]);
+        }
+    }
+
+    #[test]
+    fn test_records_keep_best() {
+        let mut records = DailyRecords::default();
+        assert!(records.record("2024-01-01", 40));
+        assert!(!records.record("2024-01-01", 30));
+        assert!(records.record("2024-01-01", 55));
+        assert!(records.record("2024-01-02", 10));
+        assert_eq!(records.best("2024-01-01"), Some(55));
+        assert_eq!(records.best("2024-01-03"), None);
+
+        let loaded = DailyRecords::parse(&records.serialize());
+        assert_eq!(loaded, records);
+        assert_eq!(
+            DailyRecords::parse("junk\n2024-01-01 x\n"),
+            DailyRecords::default()
+        );
+    }
+}
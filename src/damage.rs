@@ -0,0 +1,103 @@
+use crate::save::{Fields, Persist};
+
+/// What dealt a hit, so resistances can be tuned per source
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageType {
+    Ballistic, // Bullets
+    Fire,      // Lava
+    Explosive, // Grenades, traps, barrels
+}
+
+const MAX_RESISTANCE: f32 = 0.8; // Nothing shrugs off more than this share of a hit
+
+/// Flat armor (taken off every discrete hit) plus a percentage resistance
+/// per damage type. Bots have no health, so for them a resistance is the
+/// chance to survive a hit of that type.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Resistances {
+    pub flat: i32,
+    ballistic: f32,
+    fire: f32,
+    explosive: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, kind: DamageType) -> f32 {
+        match kind {
+            DamageType::Ballistic => self.ballistic,
+            DamageType::Fire => self.fire,
+            DamageType::Explosive => self.explosive,
+        }
+    }
+
+    /// Raise a resistance, capped at MAX_RESISTANCE
+    pub fn add(&mut self, kind: DamageType, amount: f32) {
+        let value = match kind {
+            DamageType::Ballistic => &mut self.ballistic,
+            DamageType::Fire => &mut self.fire,
+            DamageType::Explosive => &mut self.explosive,
+        };
+        *value = (*value + amount).min(MAX_RESISTANCE);
+    }
+
+    pub fn with(mut self, kind: DamageType, amount: f32) -> Self {
+        self.add(kind, amount);
+        self
+    }
+
+    /// Damage left of a discrete hit; anything that lands does at least 1
+    pub fn mitigate(&self, amount: i32, kind: DamageType) -> i32 {
+        if amount <= 0 {
+            return 0;
+        }
+        let scaled = (amount as f32 * (1.0 - self.get(kind))).round() as i32;
+        (scaled - self.flat).max(1)
+    }
+
+    /// Multiplier for continuous damage (lava), where flat armor doesn't apply
+    pub fn scale(&self, kind: DamageType) -> f32 {
+        1.0 - self.get(kind)
+    }
+}
+
+impl Persist for Resistances {
+    fn write(&self, out: &mut String) {
+        self.flat.write(out);
+        self.ballistic.write(out);
+        self.fire.write(out);
+        self.explosive.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            flat: fields.read()?,
+            ballistic: fields.read()?,
+            fire: fields.read()?,
+            explosive: fields.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mitigation() {
+        let none = Resistances::default();
+        assert_eq!(none.mitigate(10, DamageType::Ballistic), 10);
+        assert_eq!(none.mitigate(0, DamageType::Fire), 0);
+
+        let mut res = Resistances {
+            flat: 2,
+            ..Resistances::default()
+        }
+        .with(DamageType::Explosive, 0.5);
+        assert_eq!(res.mitigate(40, DamageType::Explosive), 18);
+        assert_eq!(res.mitigate(10, DamageType::Ballistic), 8);
+        assert_eq!(res.mitigate(2, DamageType::Ballistic), 1);
+
+        res.add(DamageType::Fire, 5.0);
+        assert_eq!(res.get(DamageType::Fire), MAX_RESISTANCE);
+    }
+}
@@ -0,0 +1,169 @@
+use macroquad::prelude::*;
+
+use crate::ui::ui_width;
+
+const MAX_OUTPUT: usize = 12; // Lines of earlier output kept on screen
+const TOGGLE_CHARS: [char; 2] = ['`', '~']; // Typed by the toggle key, never part of a command
+
+/// Runs a console command on `T` with the words after its name; returns the
+/// line to print, or why the command failed
+pub type CommandFn<T> = fn(&mut T, &[&str]) -> Result<String, String>;
+
+struct Command<T> {
+    name: &'static str,
+    usage: &'static str, // Arguments, shown by `help`
+    run: CommandFn<T>,
+}
+
+/// Commands the debug console can run, looked up by name; each subsystem
+/// adds its own with `register`
+pub struct CommandRegistry<T> {
+    commands: Vec<Command<T>>,
+}
+
+impl<T> Default for CommandRegistry<T> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl<T> CommandRegistry<T> {
+    /// Add a command; a later registration under the same name replaces it
+    pub fn register(&mut self, name: &'static str, usage: &'static str, run: CommandFn<T>) {
+        self.commands.retain(|c| c.name != name);
+        self.commands.push(Command { name, usage, run });
+    }
+
+    /// Run one line of input. `help` lists every command.
+    pub fn execute(&self, target: &mut T, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return Ok(String::new());
+        };
+        if name == "help" {
+            return Ok(self.help());
+        }
+        let command = self
+            .commands
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| format!("unknown command '{name}' (try help)"))?;
+        (command.run)(target, args).map_err(|err| format!("{err} - usage: {}", command.usage))
+    }
+
+    fn help(&self) -> String {
+        let usages: Vec<&str> = self.commands.iter().map(|c| c.usage).collect();
+        usages.join(" | ")
+    }
+}
+
+/// Text prompt toggled with the tilde key, with the output of earlier commands
+pub struct DebugConsole {
+    pub open: bool,
+    input: String,
+    output: Vec<(String, bool)>, // Line and whether it reports a success
+}
+
+impl DebugConsole {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        // Drop what was typed while closed
+        while get_char_pressed().is_some() {}
+    }
+
+    /// Collect the characters typed this frame; returns the line once Enter is pressed
+    pub fn read_line(&mut self) -> Option<String> {
+        while let Some(c) = get_char_pressed() {
+            if !c.is_control() && !TOGGLE_CHARS.contains(&c) {
+                self.input.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Enter) && !self.input.trim().is_empty() {
+            let line = std::mem::take(&mut self.input);
+            self.print(format!("> {line}"), true);
+            return Some(line);
+        }
+        None
+    }
+
+    pub fn print(&mut self, line: String, ok: bool) {
+        if line.is_empty() {
+            return;
+        }
+        self.output.push((line, ok));
+        if self.output.len() > MAX_OUTPUT {
+            self.output.remove(0);
+        }
+    }
+
+    /// Console panel over the top of the screen
+    pub fn draw(&self) {
+        let line_height = 18.0;
+        let height = 24.0 + (MAX_OUTPUT + 1) as f32 * line_height;
+        draw_rectangle(0.0, 0.0, ui_width(), height, Color::from_rgba(0, 0, 0, 210));
+        for (i, (line, ok)) in self.output.iter().enumerate() {
+            let color = if *ok {
+                Color::from_rgba(120, 255, 140, 255)
+            } else {
+                Color::from_rgba(255, 110, 90, 255)
+            };
+            draw_text(line, 10.0, 20.0 + i as f32 * line_height, 16.0, color);
+        }
+        let cursor = if get_time().fract() < 0.5 { "_" } else { "" };
+        draw_text(
+            &format!("] {}{}", self.input, cursor),
+            10.0,
+            height - 10.0,
+            18.0,
+            WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(total: &mut i32, args: &[&str]) -> Result<String, String> {
+        let amount: i32 = args
+            .first()
+            .and_then(|a| a.parse().ok())
+            .ok_or("need a number")?;
+        *total += amount;
+        Ok(format!("total {total}"))
+    }
+
+    #[test]
+    fn test_command_registry() {
+        let mut registry = CommandRegistry::default();
+        registry.register("add", "add <n>", add);
+
+        let mut total = 0;
+        assert_eq!(registry.execute(&mut total, "add 5"), Ok("total 5".into()));
+        assert_eq!(
+            registry.execute(&mut total, "  add   2 "),
+            Ok("total 7".into())
+        );
+        assert_eq!(
+            registry.execute(&mut total, "add x"),
+            Err("need a number - usage: add <n>".into())
+        );
+        assert!(registry.execute(&mut total, "jump").is_err());
+        assert_eq!(registry.execute(&mut total, "help"), Ok("add <n>".into()));
+        assert_eq!(registry.execute(&mut total, ""), Ok(String::new()));
+        assert_eq!(total, 7);
+    }
+}
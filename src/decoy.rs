@@ -0,0 +1,108 @@
+use macroquad::prelude::*;
+
+use crate::sprites::SpriteSheet;
+use crate::tile_map::TILE_SIZE;
+
+const DECOY_DURATION: f32 = 6.0; // Seconds before the hologram flickers out
+const FLICKER_TIME: f32 = 1.5; // Final seconds spent flickering
+pub const DECOY_LURE_RANGE: i32 = 12; // Tiles within which hostile bots go for the decoy
+
+/// Holographic copy of the player that hostile bots chase and shoot at
+pub struct Decoy {
+    pub x: i32, // Tile coordinates
+    pub y: i32,
+    facing: u32,
+    timer: f32,
+}
+
+impl Decoy {
+    pub fn new(x: i32, y: i32, facing: u32) -> Self {
+        Self {
+            x,
+            y,
+            facing,
+            timer: DECOY_DURATION,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.timer -= dt;
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.timer > 0.0
+    }
+
+    pub fn center_pixel(&self) -> (f32, f32) {
+        (
+            self.x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            self.y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        )
+    }
+
+    /// Whether a hostile bot at (x, y) is close enough to be fooled
+    pub fn lures(&self, x: i32, y: i32) -> bool {
+        (self.x - x).abs() + (self.y - y).abs() <= DECOY_LURE_RANGE
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
+        // Skip frames at random while flickering out. The dice are a hash of
+        // the timer, so drawing never touches the game's generator.
+        if self.timer < FLICKER_TIME && flicker_roll(self.timer) * FLICKER_TIME > self.timer {
+            return;
+        }
+        let screen_x = self.x as f32 * TILE_SIZE - camera_x;
+        let screen_y = self.y as f32 * TILE_SIZE - camera_y;
+        sprites.draw_player_tinted(
+            screen_x,
+            screen_y,
+            self.facing,
+            Color::from_rgba(140, 220, 255, 170),
+        );
+        // Scanlines sell the hologram
+        for i in 0..4 {
+            let line_y = screen_y + 4.0 + i as f32 * 8.0 + (get_time() as f32 * 20.0) % 8.0;
+            draw_line(
+                screen_x + 6.0,
+                line_y,
+                screen_x + TILE_SIZE - 6.0,
+                line_y,
+                1.0,
+                Color::from_rgba(140, 220, 255, 90),
+            );
+        }
+    }
+}
+
+/// Pseudo-random 0..1 for a flicker step (30 per second) of the timer
+fn flicker_roll(timer: f32) -> f32 {
+    let step = (timer * 30.0) as u32;
+    (step.wrapping_mul(2_654_435_761) >> 16) as f32 / 65_536.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoy_lifetime_and_lure() {
+        let mut decoy = Decoy::new(5, 5, 0);
+        assert!(decoy.lures(5 + DECOY_LURE_RANGE, 5));
+        assert!(!decoy.lures(5 + DECOY_LURE_RANGE, 6));
+
+        decoy.update(DECOY_DURATION - 0.1);
+        assert!(decoy.is_alive());
+        decoy.update(0.2);
+        assert!(!decoy.is_alive());
+    }
+
+    #[test]
+    fn test_flicker_roll() {
+        // Same timer, same roll; rolls stay in 0..1
+        assert_eq!(flicker_roll(1.0), flicker_roll(1.0));
+        for i in 0..45 {
+            let roll = flicker_roll(i as f32 / 30.0);
+            assert!((0.0..1.0).contains(&roll));
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::entity::Bot;
+use crate::input::{Action, InputFrame, KeyBindings};
+use crate::terminal::HACK_RANGE;
+use crate::tile_map::{TILE_SIZE, TileMap};
+use crate::ui::{ui_height, ui_width};
+
+pub const IDLE_TIME: f32 = 30.0; // Seconds idle on the main menu before the demo starts
+const DEMO_LENGTH: f32 = 90.0; // Seconds of play before the demo returns to the menu
+const SIGHT_RANGE: f32 = TILE_SIZE * 8.0; // How far off the pilot opens fire
+const REPATH_INTERVAL: f32 = 0.5;
+
+/// What a controller sees of the game when it makes the lead's frame
+pub struct Situation<'a> {
+    pub dt: f32,
+    pub map: &'a TileMap,
+    pub tile: (i32, i32),                // Lead's tile
+    pub position: (f32, f32),            // Lead's pixel center
+    pub cursor: (f32, f32),              // Mouse cursor in world pixels
+    pub terminals: Vec<(i32, i32)>,      // Tiles of terminals ready to hack
+    pub hostiles: Vec<(f32, f32)>,       // Pixel centers of hostile bots
+    pub hacking: bool,                   // A hack is running
+    pub countermeasure: Option<KeyCode>, // Key the countermeasure prompt asks for
+    pub prompt: bool,                    // A level-up choice is waiting
+}
+
+/// Source of the lead player's input. The game reads a controller's frames
+/// through `input::set_feed`, just as it reads online ticks.
+pub trait Controller {
+    fn frame(&mut self, situation: &Situation) -> InputFrame;
+}
+
+/// The person at the keyboard and mouse
+pub struct Keyboard<'a>(pub &'a KeyBindings);
+
+impl Controller for Keyboard<'_> {
+    fn frame(&mut self, situation: &Situation) -> InputFrame {
+        InputFrame::capture(self.0, situation.cursor)
+    }
+}
+
+/// Attract-mode player: walks a bot's path to the nearest terminal, hacks
+/// it, and shoots any hostile bot in sight on the way
+#[derive(Default)]
+pub struct DemoPilot {
+    path: VecDeque<(i32, i32)>,
+    repath_timer: f32,
+    elapsed: f32,
+}
+
+impl DemoPilot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the demo has played long enough
+    pub fn is_over(&self) -> bool {
+        self.elapsed >= DEMO_LENGTH
+    }
+
+    /// Shortest path to a tile beside any terminal ready to hack
+    fn plan(&mut self, situation: &Situation) {
+        let (x, y) = situation.tile;
+        self.path = situation
+            .terminals
+            .iter()
+            .flat_map(|&(tx, ty)| [(tx, ty - 1), (tx, ty + 1), (tx - 1, ty), (tx + 1, ty)])
+            .map(|(gx, gy)| Bot::find_path(x, y, gx, gy, situation.map))
+            .filter(|path| !path.is_empty())
+            .min_by_key(|path| path.len())
+            .unwrap_or_default();
+        self.repath_timer = REPATH_INTERVAL;
+    }
+}
+
+impl Controller for DemoPilot {
+    fn frame(&mut self, situation: &Situation) -> InputFrame {
+        self.elapsed += situation.dt;
+        let mut frame = InputFrame::default();
+        frame.aim = situation.position;
+        if situation.prompt {
+            return frame.press(KeyCode::Enter);
+        }
+
+        // Fire at the nearest hostile bot in sight
+        let (px, py) = situation.position;
+        let target = situation
+            .hostiles
+            .iter()
+            .map(|&(bx, by)| ((bx - px).hypot(by - py), (bx, by)))
+            .filter(|&(dist, (bx, by))| {
+                dist <= SIGHT_RANGE && situation.map.has_line_of_sight(px, py, bx, by)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, aim)) = target {
+            frame.aim = aim;
+            frame.shooting = true;
+        }
+        if let Some(key) = situation.countermeasure {
+            frame = frame.press(key);
+        }
+
+        // Hack a terminal in reach
+        let (x, y) = situation.tile;
+        let in_reach = situation
+            .terminals
+            .iter()
+            .any(|&(tx, ty)| (tx - x).abs() + (ty - y).abs() <= HACK_RANGE);
+        if in_reach {
+            self.path.clear();
+            return if situation.hacking {
+                frame.hold_action(Action::Interact)
+            } else {
+                frame.press_action(Action::Interact)
+            };
+        }
+
+        // Otherwise walk on towards the nearest one
+        self.repath_timer -= situation.dt;
+        if self.path.is_empty() || self.repath_timer <= 0.0 {
+            self.plan(situation);
+        }
+        while self.path.front() == Some(&situation.tile) {
+            self.path.pop_front();
+        }
+        let Some(&(nx, ny)) = self.path.front() else {
+            return frame;
+        };
+        let key = match ((nx - x).signum(), (ny - y).signum()) {
+            (0, -1) => KeyCode::W,
+            (0, 1) => KeyCode::S,
+            (-1, _) => KeyCode::A,
+            _ => KeyCode::D,
+        };
+        frame.hold(key)
+    }
+}
+
+/// Attract-mode caption along the bottom of the screen
+pub fn draw_demo_banner() {
+    if get_time().fract() > 0.7 {
+        return;
+    }
+    let text = "DEMO - press any key";
+    let font_size = 28.0;
+    let width = measure_text(text, None, font_size as u16, 1.0).width;
+    let x = (ui_width() - width) / 2.0;
+    let y = ui_height() - 40.0;
+    draw_text(text, x + 2.0, y + 2.0, font_size, BLACK);
+    draw_text(text, x, y, font_size, YELLOW);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROWS: [&str; 5] = [
+        "##########",
+        "#........#",
+        "#.####...#",
+        "#........#",
+        "##########",
+    ];
+
+    fn situation(map: &TileMap, tile: (i32, i32)) -> Situation<'_> {
+        let center = |x: i32| x as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+        Situation {
+            dt: 0.1,
+            map,
+            tile,
+            position: (center(tile.0), center(tile.1)),
+            cursor: (0.0, 0.0),
+            terminals: vec![(8, 3)],
+            hostiles: Vec::new(),
+            hacking: false,
+            countermeasure: None,
+            prompt: false,
+        }
+    }
+
+    /// Frame without its aim, to compare keys alone
+    fn keys(mut frame: InputFrame) -> InputFrame {
+        frame.aim = (0.0, 0.0);
+        frame
+    }
+
+    #[test]
+    fn test_pilot_walks_and_hacks() {
+        let (map, _) = TileMap::from_rows(&ROWS).unwrap();
+        let mut pilot = DemoPilot::new();
+
+        // Heads for the terminal the short way round
+        let frame = pilot.frame(&situation(&map, (1, 2)));
+        assert_eq!(keys(frame), InputFrame::default().hold(KeyCode::S));
+        let frame = pilot.frame(&situation(&map, (1, 3)));
+        assert_eq!(keys(frame), InputFrame::default().hold(KeyCode::D));
+
+        // Starts the hack in reach, then keeps holding and answers prompts
+        let mut near = situation(&map, (7, 3));
+        let expected = InputFrame::default().press_action(Action::Interact);
+        assert_eq!(keys(pilot.frame(&near)), expected);
+        near.hacking = true;
+        near.countermeasure = Some(KeyCode::R);
+        let expected = InputFrame::default()
+            .press(KeyCode::R)
+            .hold_action(Action::Interact);
+        assert_eq!(keys(pilot.frame(&near)), expected);
+
+        assert!(!pilot.is_over());
+        near.dt = DEMO_LENGTH;
+        pilot.frame(&near);
+        assert!(pilot.is_over());
+    }
+
+    #[test]
+    fn test_pilot_shoots_in_sight() {
+        let (map, _) = TileMap::from_rows(&ROWS).unwrap();
+        let mut pilot = DemoPilot::new();
+        let mut seen = situation(&map, (1, 1));
+        let bot = (5.5 * TILE_SIZE, 1.5 * TILE_SIZE);
+        seen.hostiles = vec![bot];
+        let frame = pilot.frame(&seen);
+        assert!(frame.shooting);
+        assert_eq!(frame.aim, bot);
+
+        // Not through the wall
+        seen.hostiles = vec![(3.5 * TILE_SIZE, 3.5 * TILE_SIZE)];
+        seen.tile = (3, 1);
+        seen.position = (3.5 * TILE_SIZE, 1.5 * TILE_SIZE);
+        assert!(!pilot.frame(&seen).shooting);
+    }
+}
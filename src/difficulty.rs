@@ -0,0 +1,109 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, push_word};
+
+/// Preset picked on New Game; multipliers on the base tuning constants
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Difficulty {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub color: Color,
+    pub bot_count: f32,   // Bots placed on the map
+    pub bot_damage: f32,  // Damage of bot bullets
+    pub hack_window: f32, // Time before a hack fails
+    pub drop_rate: f32,   // Loot drop chances
+}
+
+impl Difficulty {
+    pub const EASY: Difficulty = Difficulty {
+        name: "EASY",
+        description: "Fewer, weaker bots - longer hacks, more loot",
+        color: Color::new(0.6, 0.9, 0.6, 1.0),
+        bot_count: 0.7,
+        bot_damage: 0.6,
+        hack_window: 1.4,
+        drop_rate: 1.3,
+    };
+    pub const NORMAL: Difficulty = Difficulty {
+        name: "NORMAL",
+        description: "The intended balance",
+        color: Color::new(0.9, 0.9, 0.9, 1.0),
+        bot_count: 1.0,
+        bot_damage: 1.0,
+        hack_window: 1.0,
+        drop_rate: 1.0,
+    };
+    pub const HARD: Difficulty = Difficulty {
+        name: "HARD",
+        description: "More bots hitting harder, tighter hacks",
+        color: Color::new(1.0, 0.6, 0.3, 1.0),
+        bot_count: 1.3,
+        bot_damage: 1.4,
+        hack_window: 0.8,
+        drop_rate: 0.8,
+    };
+    pub const NIGHTMARE: Difficulty = Difficulty {
+        name: "NIGHTMARE",
+        description: "A swarm of bots, double damage, scarce loot",
+        color: Color::new(0.9, 0.2, 0.3, 1.0),
+        bot_count: 1.6,
+        bot_damage: 2.0,
+        hack_window: 0.65,
+        drop_rate: 0.6,
+    };
+
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::EASY,
+        Difficulty::NORMAL,
+        Difficulty::HARD,
+        Difficulty::NIGHTMARE,
+    ];
+
+    /// Number of bots to place where the base tuning places `base`
+    pub fn bot_count(&self, base: usize) -> usize {
+        (base as f32 * self.bot_count).round() as usize
+    }
+
+    /// Damage of a bot hit that does `base` on Normal (at least 1)
+    pub fn bot_damage(&self, base: i32) -> i32 {
+        ((base as f32 * self.bot_damage).round() as i32).max(1)
+    }
+}
+
+/// Saved by preset name
+impl Persist for Difficulty {
+    fn write(&self, out: &mut String) {
+        push_word(out, self.name);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let word = fields.word()?;
+        Difficulty::ALL
+            .into_iter()
+            .find(|d| d.name == word)
+            .ok_or_else(|| fields.error(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_scale() {
+        assert_eq!(Difficulty::NORMAL.bot_count(10), 10);
+        assert_eq!(Difficulty::NORMAL.bot_damage(10), 10);
+        assert_eq!(Difficulty::EASY.bot_count(10), 7);
+        assert_eq!(Difficulty::NIGHTMARE.bot_damage(10), 20);
+        assert_eq!(Difficulty::EASY.bot_damage(1), 1);
+
+        // Each step up is harder on every axis
+        for pair in Difficulty::ALL.windows(2) {
+            let (easier, harder) = (pair[0], pair[1]);
+            assert!(harder.bot_count > easier.bot_count);
+            assert!(harder.bot_damage > easier.bot_damage);
+            assert!(harder.hack_window < easier.hack_window);
+            assert!(harder.drop_rate < easier.drop_rate);
+        }
+    }
+}
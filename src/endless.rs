@@ -0,0 +1,207 @@
+use macroquad::prelude::*;
+
+use crate::save::persist_enum;
+use crate::tile_map::{TILE_SIZE, TileMap, TileType};
+use crate::ui::{ui_height, ui_width};
+
+const MAP_GROWTH: (usize, usize) = (10, 7); // Tiles added per depth
+const MAX_MAP_SIZE: (usize, usize) = (120, 90);
+const BOTS_PER_DEPTH: f32 = 0.25; // Extra share of the base bot count per depth
+const LAVA_POOLS: usize = 14;
+const LAVA_POOL_RADIUS: i32 = 2;
+const LAVA_SAFE_DISTANCE: i32 = 6; // No lava this close to where the player starts
+const DARKNESS_RADIUS: f32 = TILE_SIZE * 4.0; // Fully lit around the player
+const DARKNESS_FALLOFF: f32 = TILE_SIZE * 3.0; // Fades to black over this distance
+
+/// Twist applied to an endless mode map, rolled when the player descends
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DepthModifier {
+    Darkness,  // Only the area around the player is lit
+    LavaFlood, // Pools of lava spill over the floor
+    EliteOnly, // Every bot spawns as a hostile elite
+}
+
+persist_enum!(DepthModifier {
+    Darkness,
+    LavaFlood,
+    EliteOnly
+});
+
+impl DepthModifier {
+    pub const ALL: [DepthModifier; 3] = [
+        DepthModifier::Darkness,
+        DepthModifier::LavaFlood,
+        DepthModifier::EliteOnly,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DepthModifier::Darkness => "DARKNESS",
+            DepthModifier::LavaFlood => "LAVA FLOOD",
+            DepthModifier::EliteOnly => "ELITES ONLY",
+        }
+    }
+}
+
+/// Map size at a depth, growing from the `base` size at depth 1
+pub fn map_size(base: (usize, usize), depth: u32) -> (usize, usize) {
+    let steps = depth.saturating_sub(1) as usize;
+    (
+        (base.0 + MAP_GROWTH.0 * steps).min(MAX_MAP_SIZE.0),
+        (base.1 + MAP_GROWTH.1 * steps).min(MAX_MAP_SIZE.1),
+    )
+}
+
+/// Multiplier on the bot counts at a depth
+pub fn bot_scale(depth: u32) -> f32 {
+    1.0 + BOTS_PER_DEPTH * depth.saturating_sub(1) as f32
+}
+
+/// Modifiers for a new depth: none at first, one from depth 2, two from depth 5
+pub fn roll_modifiers(depth: u32) -> Vec<DepthModifier> {
+    let count = match depth {
+        0 | 1 => 0,
+        2..=4 => 1,
+        _ => 2,
+    };
+    let mut pool = DepthModifier::ALL.to_vec();
+    (0..count)
+        .map(|_| pool.swap_remove(rand::gen_range(0, pool.len())))
+        .collect()
+}
+
+/// One more modifier for a New Game+ loop, if any are left
+pub fn roll_loop_modifier(active: &[DepthModifier]) -> Option<DepthModifier> {
+    let pool: Vec<DepthModifier> = DepthModifier::ALL
+        .into_iter()
+        .filter(|m| !active.contains(m))
+        .collect();
+    (!pool.is_empty()).then(|| pool[rand::gen_range(0, pool.len())])
+}
+
+/// Pour lava pools over open ground, away from the player's start
+pub fn flood_lava(map: &mut TileMap, start: (i32, i32)) {
+    for _ in 0..LAVA_POOLS {
+        let cx = rand::gen_range(1, map.width - 1) as i32;
+        let cy = rand::gen_range(1, map.height - 1) as i32;
+        if (cx - start.0).abs() + (cy - start.1).abs() < LAVA_SAFE_DISTANCE {
+            continue;
+        }
+        for dy in -LAVA_POOL_RADIUS..=LAVA_POOL_RADIUS {
+            for dx in -LAVA_POOL_RADIUS..=LAVA_POOL_RADIUS {
+                let (x, y) = (cx + dx, cy + dy);
+                if dx.abs() + dy.abs() > LAVA_POOL_RADIUS || x < 0 || y < 0 {
+                    continue;
+                }
+                let (x, y) = (x as usize, y as usize);
+                if matches!(map.get_tile(x, y), Some(TileType::Floor | TileType::Sand)) {
+                    map.set_tile(x, y, TileType::Lava);
+                }
+            }
+        }
+    }
+}
+
+/// Darkness modifier: black out every tile in view beyond the light around
+/// the players (pixel centers)
+pub fn draw_darkness(
+    map: &TileMap,
+    camera_x: f32,
+    camera_y: f32,
+    view_w: f32,
+    view_h: f32,
+    lights: &[(f32, f32)],
+) {
+    let (cols, rows) = map.visible_range(camera_x, camera_y, view_w, view_h);
+    for y in rows {
+        for x in cols.clone() {
+            let cx = (x as f32 + 0.5) * TILE_SIZE;
+            let cy = (y as f32 + 0.5) * TILE_SIZE;
+            let dist = lights
+                .iter()
+                .map(|&(lx, ly)| (cx - lx).hypot(cy - ly))
+                .fold(f32::INFINITY, f32::min);
+            let alpha = ((dist - DARKNESS_RADIUS) / DARKNESS_FALLOFF).clamp(0.0, 1.0) * 0.95;
+            if alpha > 0.0 {
+                draw_rectangle(
+                    x as f32 * TILE_SIZE - camera_x,
+                    y as f32 * TILE_SIZE - camera_y,
+                    TILE_SIZE,
+                    TILE_SIZE,
+                    Color::new(0.0, 0.0, 0.0, alpha),
+                );
+            }
+        }
+    }
+}
+
+/// Depth counter at the top center, with the active modifiers below it;
+/// a large banner announces a new depth while `flash` runs down
+pub fn draw_depth(depth: u32, modifiers: &[DepthModifier], flash: f32) {
+    draw_counter(&format!("DEPTH {}", depth), 36.0, modifiers, flash);
+}
+
+/// New Game+ loop counter, below the time attack clock
+pub fn draw_loop(ng_loop: u32, modifiers: &[DepthModifier], flash: f32) {
+    draw_counter(&format!("NG+{}", ng_loop), 84.0, modifiers, flash);
+}
+
+fn draw_counter(text: &str, y: f32, modifiers: &[DepthModifier], flash: f32) {
+    let width = measure_text(text, None, 32, 1.0).width;
+    let x = (ui_width() - width) / 2.0;
+    draw_text(text, x + 2.0, y + 2.0, 32.0, BLACK);
+    draw_text(text, x, y, 32.0, Color::from_rgba(120, 220, 200, 255));
+
+    let names: Vec<&str> = modifiers.iter().map(|m| m.name()).collect();
+    if !names.is_empty() {
+        let line = names.join("  |  ");
+        let width = measure_text(&line, None, 16, 1.0).width;
+        draw_text(
+            &line,
+            (ui_width() - width) / 2.0,
+            y + 20.0,
+            16.0,
+            Color::from_rgba(255, 150, 80, 255),
+        );
+    }
+
+    if flash > 0.0 {
+        let alpha = flash.min(1.0);
+        let width = measure_text(text, None, 64, 1.0).width;
+        draw_text(
+            text,
+            (ui_width() - width) / 2.0,
+            ui_height() / 2.0 - 60.0,
+            64.0,
+            Color::new(0.5, 0.9, 0.8, alpha),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_scaling() {
+        assert_eq!(map_size((60, 45), 1), (60, 45));
+        assert_eq!(map_size((60, 45), 2), (70, 52));
+        assert_eq!(map_size((60, 45), 50), MAX_MAP_SIZE);
+        assert_eq!(bot_scale(1), 1.0);
+        assert!(bot_scale(3) > bot_scale(2));
+
+        assert!(roll_modifiers(1).is_empty());
+        assert_eq!(roll_modifiers(3).len(), 1);
+        let deep = roll_modifiers(9);
+        assert_eq!(deep.len(), 2);
+        assert_ne!(deep[0], deep[1]);
+
+        // Loops add modifiers until every one is active
+        let mut active = Vec::new();
+        while let Some(modifier) = roll_loop_modifier(&active) {
+            assert!(!active.contains(&modifier));
+            active.push(modifier);
+        }
+        assert_eq!(active.len(), DepthModifier::ALL.len());
+    }
+}
@@ -1,12 +1,75 @@
 use macroquad::prelude::*;
 use std::collections::VecDeque;
 
+use crate::class::{PlayerClass, SCOUT_SPEED_MULT, SOLDIER_BONUS_HEALTH};
+use crate::damage::{DamageType, Resistances};
+use crate::grenade::GrenadeSlot;
 use crate::input::MoveDirection;
-use crate::sprites::{SpriteSheet, direction, direction_to_vector, movement_to_direction};
+use crate::inventory::{INVENTORY_SLOTS, Inventory};
+use crate::item::{KeycardColor, SHIELD_COLOR};
+use crate::perk::{
+    ARMOR_PERK, FIRE_RATE_PERK, INSULATION_PERK, MAX_HEALTH_PERK, MOVE_SPEED_PERK, Perk, xp_to_next,
+};
+use crate::save::{Fields, Persist, SaveReader, SaveWriter, persist_enum};
+use crate::skills::SkillTree;
+use crate::skin::Skin;
+use crate::sprites::{
+    SpriteSheet, WALK_FRAMES, angle_to_direction, direction, direction_to_vector,
+    movement_to_direction,
+};
 use crate::tile_map::{EntityType, TILE_SIZE, TileMap};
-use crate::weapon::Weapon;
+use crate::ultimate::UltimateMeter;
+use crate::weapon::{AmmoKind, Weapon};
 
 const MOVE_SPEED: f32 = 1.5;
+const FREE_MOVE_SPEED: f32 = 5.0; // Tiles per second in free movement
+const FREE_HALF_EXTENT: f32 = 0.35; // Half the player's collision box, in tiles
+const SWEEP_EPSILON: f32 = 0.001; // Gap kept between a box and the wall it stopped at
+
+/// How the player gets around: hopping tile to tile, or gliding freely
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementMode {
+    Grid,
+    Free,
+}
+
+persist_enum!(MovementMode { Grid, Free });
+
+/// Move a box center `along` one axis by `delta`, checking each tile row or
+/// column its leading edge crosses. `can_enter(tile, cross, dir)` is asked
+/// for every tile the box spans on the other axis; the box stops flush
+/// against the first one refused.
+fn sweep_axis(
+    along: f32,
+    across: f32,
+    delta: f32,
+    half: f32,
+    can_enter: impl Fn(i32, i32, i32) -> bool,
+) -> f32 {
+    if delta == 0.0 {
+        return along;
+    }
+    let dir = delta.signum() as i32;
+    let lead_start = along + dir as f32 * half;
+    let lead_end = along + delta + dir as f32 * half;
+    let rows = (across - half).floor() as i32..=(across + half).floor() as i32;
+    let blocked = |t: i32| rows.clone().any(|r| !can_enter(t, r, dir));
+
+    if dir > 0 {
+        for t in lead_start.floor() as i32 + 1..=lead_end.floor() as i32 {
+            if blocked(t) {
+                return t as f32 - half - SWEEP_EPSILON;
+            }
+        }
+    } else {
+        for t in (lead_end.floor() as i32..lead_start.floor() as i32).rev() {
+            if blocked(t) {
+                return (t + 1) as f32 + half + SWEEP_EPSILON;
+            }
+        }
+    }
+    along + delta
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct Position {
@@ -43,6 +106,48 @@ impl Position {
         }
     }
 
+    /// Shove the grid position up to `tiles` steps along (dx, dy), stopping
+    /// at anything solid. Pits stop the shove too - the entity falls in.
+    pub fn knockback(&mut self, dx: i32, dy: i32, tiles: i32, map: &TileMap, entity: EntityType) {
+        for _ in 0..tiles {
+            let (nx, ny) = (self.x + dx, self.y + dy);
+            if map.is_pit_at(nx, ny) {
+                self.x = nx;
+                self.y = ny;
+                return;
+            }
+            if !map.can_enter(nx, ny, dx, dy, entity) {
+                return;
+            }
+            self.x = nx;
+            self.y = ny;
+        }
+    }
+
+    /// Tile under the center of the visual position
+    pub fn visual_tile(&self) -> (i32, i32) {
+        (
+            (self.visual_x + 0.5).floor() as i32,
+            (self.visual_y + 0.5).floor() as i32,
+        )
+    }
+
+    /// Free movement: slide the visual position by (dx, dy) tiles with a
+    /// box of `half` extent, one axis at a time so it glides along walls.
+    /// The grid position follows the tile under the center.
+    pub fn slide(&mut self, dx: f32, dy: f32, half: f32, map: &TileMap, entity: EntityType) {
+        let (cx, cy) = (self.visual_x + 0.5, self.visual_y + 0.5);
+        let cx = sweep_axis(cx, cy, dx, half, |t, row, dir| {
+            map.can_enter(t, row, dir, 0, entity)
+        });
+        let cy = sweep_axis(cy, cx, dy, half, |t, col, dir| {
+            map.can_enter(col, t, 0, dir, entity)
+        });
+        self.visual_x = cx - 0.5;
+        self.visual_y = cy - 0.5;
+        (self.x, self.y) = self.visual_tile();
+    }
+
     pub fn is_at_target(&self) -> bool {
         (self.visual_x - self.x as f32).abs() < 0.1 && (self.visual_y - self.y as f32).abs() < 0.1
     }
@@ -56,16 +161,121 @@ impl Position {
 }
 
 const PLAYER_MAX_HEALTH: i32 = 100;
+const PLAYER_MAX_ARMOR: i32 = 100;
+const ARMOR_ABSORPTION: f32 = 0.6; // Fraction of incoming damage armor soaks up
+pub const MAX_MEDKITS: u32 = 3; // Health packs stored for later
+const WEAPON_SLOTS: usize = 3; // Firearms carried besides the knife
+pub const MAX_WEAPON_SLOTS: usize = 4; // Backpacks stop here (number keys 1-5)
+pub const SHIELD_CAPACITY: f32 = 50.0;
+const SHIELD_DURATION: f32 = 25.0; // Seconds the shield powerup lasts
+const SHIELD_RECHARGE_DELAY: f32 = 3.0; // Seconds without a hit before recharging
+const SHIELD_RECHARGE_RATE: f32 = 15.0; // Shield points per second
+const DASH_TILES: i32 = 3;
+pub const DASH_COOLDOWN: f32 = 2.5;
+const DASH_IFRAMES: f32 = 0.25; // Seconds of invulnerability after dashing
+const DASH_TRAIL_TIME: f32 = 0.2; // Afterimages fade over this long
+pub const MAX_STAMINA: f32 = 100.0;
+const SPRINT_SPEED_MULT: f32 = 1.6;
+const SPRINT_DRAIN: f32 = 30.0; // Stamina per second while sprinting
+const STAMINA_REGEN: f32 = 20.0; // Stamina per second otherwise
+const SPRINT_RESUME: f32 = 30.0; // Stamina needed to sprint again after running dry
+const CROUCH_SPEED_MULT: f32 = 0.5;
+const ELITE_BALLISTIC_RESIST: f32 = 0.25; // Keycard carriers survive a share of bullets
+const ROLL_TILES: i32 = 2;
+const ROLL_DURATION: f32 = 0.3; // Seconds of projectile immunity, and the tumble animation
+pub const ROLL_COOLDOWN: f32 = 1.2;
+const ROLL_SPEED_MULT: f32 = 2.5; // Visual travel burst while rolling
+const SHOVE_CREDIT_TIME: f32 = 1.5; // Seconds a shoved bot's environmental death is credited
+pub const BOSS_HEALTH: u32 = 25; // Hits the campaign boss takes to go down
+const HIT_FLASH_TIME: f32 = 0.1; // Seconds a bot flashes white after a hit it survives
+const WALK_FRAME_TIME: f32 = 0.1; // Seconds per frame of the walk cycle
+const WALK_GRACE: f32 = 0.1; // Standing still this long ends the walk cycle
+const IDLE_BOB_PERIOD: f32 = 1.6; // Seconds per breath while standing
+const BOB_HEIGHT: f32 = 1.0; // Pixels an entity rises on a step or breath
+
+/// Animation clock for an entity's sprite: walk frames while its visual
+/// position is moving, a slow bob while it stands
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Animation {
+    clock: f32,
+    still: f32, // Seconds since the visual position last moved
+}
+
+impl Animation {
+    pub fn update(&mut self, dt: f32, moving: bool) {
+        self.clock += dt;
+        self.still = if moving { 0.0 } else { self.still + dt };
+    }
+
+    fn walking(&self) -> bool {
+        self.still < WALK_GRACE
+    }
+
+    /// Frame of the walk cycle to draw (0 when standing)
+    pub fn frame(&self) -> u32 {
+        if self.walking() {
+            (self.clock / WALK_FRAME_TIME) as u32 % WALK_FRAMES
+        } else {
+            0
+        }
+    }
+
+    /// Pixels to draw the sprite raised by: on each step while walking,
+    /// breathing while standing
+    pub fn bob(&self) -> f32 {
+        if self.walking() {
+            BOB_HEIGHT * (self.frame() % 2) as f32
+        } else {
+            let phase = self.clock / IDLE_BOB_PERIOD * std::f32::consts::TAU;
+            BOB_HEIGHT * (phase.sin() * 0.5 + 0.5)
+        }
+    }
+}
 
 pub struct Player {
     pub pos: Position,
     pub weapons: Vec<Weapon>,
     pub current_weapon: usize,
+    pub weapon_slots: usize,
     pub health: i32,
     pub max_health: i32,
+    pub armor: i32,
+    pub max_armor: i32,
+    pub resistances: Resistances,
+    pub lava_damage: f32, // Lava burn built up toward the next whole point
     pub speed_boost_timer: f32,
     pub invulnerability_timer: f32,
+    pub god_mode: bool, // Debug console: no damage at all
+    pub magnet_timer: f32,
+    pub scanner_timer: f32,
+    pub shield: f32,
+    pub shield_timer: f32, // Remaining shield powerup time
+    shield_recharge_delay: f32,
+    pub dash_cooldown: f32,
+    dash_max_cooldown: f32, // Cooldown of the last dash, for the HUD
+    dash_iframes: f32,
+    dash_trail: Option<(f32, f32, f32)>, // Start tile (visual) and fade timer
+    pub roll_cooldown: f32,
+    roll_timer: f32,
+    pub stamina: f32,
+    pub sprinting: bool,
+    winded: bool, // Ran out of stamina; no sprinting until SPRINT_RESUME
+    pub crouching: bool,
+    pub movement: MovementMode,
+    pub skin: Skin,
+    pub ultimate: UltimateMeter,
+    pub inventory: Inventory,
+    pub grenades: GrenadeSlot,
+    pub credits: u32,
+    pub medkits: u32,
+    pub xp: u32, // Progress toward the next level
+    pub level: u32,
+    pub fire_rate_mult: f32, // From perks
+    pub move_speed_mult: f32,
+    pub skills: SkillTree,
+    ammo: [u32; AmmoKind::ALL.len()], // Reserve per ammo kind
     facing: u32,
+    pub animation: Animation,
 }
 
 impl Player {
@@ -74,33 +284,123 @@ impl Player {
             pos: Position::new(x, y),
             weapons: vec![Weapon::knife()], // Start with only knife
             current_weapon: 0,
+            weapon_slots: WEAPON_SLOTS,
             health: PLAYER_MAX_HEALTH,
             max_health: PLAYER_MAX_HEALTH,
+            armor: 0,
+            max_armor: PLAYER_MAX_ARMOR,
+            resistances: Resistances::default(),
+            lava_damage: 0.0,
             speed_boost_timer: 0.0,
             invulnerability_timer: 0.0,
+            god_mode: false,
+            magnet_timer: 0.0,
+            scanner_timer: 0.0,
+            shield: 0.0,
+            shield_timer: 0.0,
+            shield_recharge_delay: 0.0,
+            dash_cooldown: 0.0,
+            dash_max_cooldown: DASH_COOLDOWN,
+            dash_iframes: 0.0,
+            dash_trail: None,
+            roll_cooldown: 0.0,
+            roll_timer: 0.0,
+            stamina: MAX_STAMINA,
+            sprinting: false,
+            winded: false,
+            crouching: false,
+            movement: MovementMode::Grid,
+            skin: Skin::Standard,
+            ultimate: UltimateMeter::new(),
+            inventory: Inventory::new(INVENTORY_SLOTS),
+            grenades: GrenadeSlot::new(),
+            credits: 0,
+            medkits: 0,
+            xp: 0,
+            level: 1,
+            fire_rate_mult: 1.0,
+            move_speed_mult: 1.0,
+            skills: SkillTree::new(),
+            ammo: [0; AmmoKind::ALL.len()],
             facing: direction::DOWN,
+            animation: Animation::default(),
         }
     }
 
     pub fn take_damage(&mut self, amount: i32) {
-        // Invulnerability prevents all damage
-        if self.invulnerability_timer > 0.0 {
+        // Invulnerability (powerup or dash i-frames) prevents all damage
+        if self.is_invulnerable() {
             return;
         }
-        self.health = (self.health - amount).max(0);
+        // Armor absorbs its share of the hit until it is worn through
+        let absorbed = ((amount as f32 * ARMOR_ABSORPTION).round() as i32).min(self.armor);
+        self.armor -= absorbed;
+        self.health = (self.health - (amount - absorbed)).max(0);
+    }
+
+    /// Take a typed hit: resistances and flat armor first, then armor plates
+    pub fn take_hit(&mut self, amount: i32, kind: DamageType) {
+        self.take_damage(self.resistances.mitigate(amount, kind));
+    }
+
+    /// Start (or refresh) the shield powerup at full charge
+    pub fn activate_shield(&mut self) {
+        self.shield_timer = SHIELD_DURATION;
+        self.shield = SHIELD_CAPACITY;
+        self.shield_recharge_delay = 0.0;
+    }
+
+    /// Let the shield soak up a projectile hit; returns the damage that
+    /// gets through. Any hit holds off recharging for a while.
+    pub fn absorb_with_shield(&mut self, amount: i32) -> i32 {
+        if self.shield_timer <= 0.0 || self.is_invulnerable() {
+            return amount;
+        }
+        self.shield_recharge_delay = SHIELD_RECHARGE_DELAY;
+        let absorbed = (amount as f32).min(self.shield);
+        self.shield -= absorbed;
+        amount - absorbed.ceil() as i32
+    }
+
+    /// Add armor, capped at the maximum. Returns false if already full.
+    pub fn add_armor(&mut self, amount: i32) -> bool {
+        if self.armor >= self.max_armor {
+            return false;
+        }
+        self.armor = (self.armor + amount).min(self.max_armor);
+        true
     }
 
     pub fn heal(&mut self, amount: i32) {
         self.health = (self.health + amount).min(self.max_health);
     }
 
+    /// Stash a health pack for later. Returns false if the stash is full.
+    pub fn store_medkit(&mut self) -> bool {
+        if self.medkits >= MAX_MEDKITS {
+            return false;
+        }
+        self.medkits += 1;
+        true
+    }
+
+    /// Heal with a stored medkit. Returns false if there is none or health
+    /// is already full.
+    pub fn use_medkit(&mut self, amount: i32) -> bool {
+        if self.medkits == 0 || self.health >= self.max_health {
+            return false;
+        }
+        self.medkits -= 1;
+        self.heal(amount);
+        true
+    }
+
     pub fn is_alive(&self) -> bool {
         self.health > 0
     }
 
-    #[allow(dead_code)]
     pub fn is_invulnerable(&self) -> bool {
-        self.invulnerability_timer > 0.0
+        self.invulnerability_timer > 0.0 || self.dash_iframes > 0.0 || self.god_mode
     }
 
     #[allow(dead_code)]
@@ -111,17 +411,374 @@ impl Player {
     pub fn respawn(&mut self, x: i32, y: i32) {
         self.pos = Position::new(x, y);
         self.health = self.max_health;
+        self.armor = 0;
+        self.lava_damage = 0.0;
         self.speed_boost_timer = 0.0;
         self.invulnerability_timer = 0.0;
+        self.magnet_timer = 0.0;
+        self.scanner_timer = 0.0;
+        self.shield = 0.0;
+        self.shield_timer = 0.0;
+        self.dash_cooldown = 0.0;
+        self.dash_iframes = 0.0;
+        self.dash_trail = None;
+        self.roll_cooldown = 0.0;
+        self.roll_timer = 0.0;
+        self.stamina = MAX_STAMINA;
+        self.sprinting = false;
+        self.winded = false;
+        self.crouching = false;
+    }
+
+    /// Write what a resumed run needs; short-lived timers (dash, roll,
+    /// stamina) start fresh on load
+    pub fn save(&self, out: &mut SaveWriter) {
+        out.record(
+            "player",
+            &[
+                &self.pos.x,
+                &self.pos.y,
+                &self.facing,
+                &self.health,
+                &self.max_health,
+                &self.armor,
+                &self.max_armor,
+                &self.resistances,
+                &self.movement,
+            ],
+        );
+        out.record(
+            "powerups",
+            &[
+                &self.speed_boost_timer,
+                &self.invulnerability_timer,
+                &self.magnet_timer,
+                &self.scanner_timer,
+                &self.shield,
+                &self.shield_timer,
+            ],
+        );
+        out.record(
+            "progress",
+            &[
+                &self.credits,
+                &self.medkits,
+                &self.xp,
+                &self.level,
+                &self.fire_rate_mult,
+                &self.move_speed_mult,
+                &self.skills,
+                &self.ultimate,
+            ],
+        );
+        out.record(
+            "weapons",
+            &[
+                &self.weapon_slots,
+                &self.current_weapon,
+                &self.weapons,
+                &self.ammo,
+            ],
+        );
+        out.record("gear", &[&self.grenades, &self.inventory]);
+    }
+
+    /// Read back the state written by `save` over this player
+    pub fn restore(&mut self, save: &mut SaveReader) -> Result<(), String> {
+        let mut fields = save.record("player")?;
+        let (x, y) = fields.read()?;
+        self.respawn(x, y);
+        self.facing = fields.read()?;
+        self.health = fields.read()?;
+        self.max_health = fields.read()?;
+        self.armor = fields.read()?;
+        self.max_armor = fields.read()?;
+        self.resistances = fields.read()?;
+        // Online, both machines must move the same way on the same input
+        self.movement = fields.read()?;
+
+        let mut fields = save.record("powerups")?;
+        self.speed_boost_timer = fields.read()?;
+        self.invulnerability_timer = fields.read()?;
+        self.magnet_timer = fields.read()?;
+        self.scanner_timer = fields.read()?;
+        self.shield = fields.read()?;
+        self.shield_timer = fields.read()?;
+
+        let mut fields = save.record("progress")?;
+        self.credits = fields.read()?;
+        self.medkits = fields.read()?;
+        self.xp = fields.read()?;
+        self.level = fields.read()?;
+        self.fire_rate_mult = fields.read()?;
+        self.move_speed_mult = fields.read()?;
+        self.skills = fields.read()?;
+        self.ultimate = fields.read()?;
+
+        let mut fields = save.record("weapons")?;
+        self.weapon_slots = fields.read()?;
+        self.current_weapon = fields.read()?;
+        self.weapons = fields.read()?;
+        self.ammo = fields.read()?;
+        if self.current_weapon >= self.weapons.len() {
+            return Err("current weapon out of range".to_string());
+        }
+
+        let mut fields = save.record("gear")?;
+        self.grenades = fields.read()?;
+        self.inventory = fields.read()?;
+        Ok(())
+    }
+
+    /// Dash up to DASH_TILES in the input direction, stopping at walls and
+    /// short of pits. Returns false while on cooldown or standing still.
+    pub fn try_dash(&mut self, input: MoveDirection, map: &TileMap) -> bool {
+        if self.dash_cooldown > 0.0 || !input.is_moving() {
+            return false;
+        }
+        let start = (self.pos.visual_x, self.pos.visual_y);
+        let (x, y) = self.clear_run(input, DASH_TILES, map);
+        if (x, y) == (self.pos.x, self.pos.y) {
+            return false;
+        }
+        self.pos = Position::new(x, y);
+        self.facing = movement_to_direction(input.dx, input.dy);
+        self.dash_max_cooldown = DASH_COOLDOWN * self.skills.dash_cooldown_mult();
+        self.dash_cooldown = self.dash_max_cooldown;
+        self.dash_iframes = DASH_IFRAMES;
+        self.dash_trail = Some((start.0, start.1, DASH_TRAIL_TIME));
+        true
+    }
+
+    /// Roll up to ROLL_TILES in the input direction. Unlike the dash the
+    /// player travels there visibly, dodging projectiles on the way.
+    /// Returns false while on cooldown or standing still.
+    pub fn try_roll(&mut self, input: MoveDirection, map: &TileMap) -> bool {
+        if self.roll_cooldown > 0.0 || !input.is_moving() {
+            return false;
+        }
+        let (x, y) = self.clear_run(input, ROLL_TILES, map);
+        if (x, y) == (self.pos.x, self.pos.y) {
+            return false;
+        }
+        self.pos.x = x;
+        self.pos.y = y;
+        self.facing = movement_to_direction(input.dx, input.dy);
+        self.roll_cooldown = ROLL_COOLDOWN;
+        self.roll_timer = ROLL_DURATION;
+        true
+    }
+
+    /// Last tile reachable within `tiles` steps in the input direction,
+    /// stopping at walls and short of pits
+    fn clear_run(&self, input: MoveDirection, tiles: i32, map: &TileMap) -> (i32, i32) {
+        let (mut x, mut y) = (self.pos.x, self.pos.y);
+        for _ in 0..tiles {
+            let (nx, ny) = (x + input.dx, y + input.dy);
+            if map.is_pit_at(nx, ny) || !map.can_step(x, y, input.dx, input.dy, EntityType::Player)
+            {
+                break;
+            }
+            (x, y) = (nx, ny);
+        }
+        (x, y)
+    }
+
+    /// Free movement step. A grid position moved from outside (roll,
+    /// knockback) is glided to first, the same way grid movement does.
+    fn move_free(&mut self, dt: f32, input: MoveDirection, speed_mult: f32, map: &TileMap) {
+        if self.pos.visual_tile() != (self.pos.x, self.pos.y) {
+            self.pos.update_visual(dt, speed_mult);
+            return;
+        }
+        if !input.is_moving() {
+            return;
+        }
+        self.facing = movement_to_direction(input.dx, input.dy);
+        let (dx, dy) = (input.dx as f32, input.dy as f32);
+        let step = FREE_MOVE_SPEED * speed_mult * dt / dx.hypot(dy);
+        self.pos.slide(
+            dx * step,
+            dy * step,
+            FREE_HALF_EXTENT,
+            map,
+            EntityType::Player,
+        );
+    }
+
+    /// Standing on a tile rather than partway into the next one. Free
+    /// movement is always on the tile under the player's center.
+    pub fn is_settled(&self) -> bool {
+        self.movement == MovementMode::Free || self.pos.is_at_target()
+    }
+
+    /// Mid-roll: projectiles pass straight through
+    pub fn is_rolling(&self) -> bool {
+        self.roll_timer > 0.0
+    }
+
+    /// Roll readiness from 0 (just used) to 1 (ready)
+    pub fn roll_charge(&self) -> f32 {
+        1.0 - self.roll_cooldown / ROLL_COOLDOWN
+    }
+
+    /// Sprint while the modifier is held and there's stamina to burn;
+    /// stamina comes back whenever the player isn't sprinting. Crouching
+    /// rules out sprinting.
+    fn update_stamina(&mut self, dt: f32, input: MoveDirection) {
+        self.sprinting = input.sprint && input.is_moving() && !self.winded && !self.crouching;
+        if self.sprinting {
+            self.stamina -= SPRINT_DRAIN * dt;
+            if self.stamina <= 0.0 {
+                self.stamina = 0.0;
+                self.winded = true;
+                self.sprinting = false;
+            }
+        } else {
+            self.stamina = (self.stamina + STAMINA_REGEN * dt).min(MAX_STAMINA);
+            if self.stamina >= SPRINT_RESUME {
+                self.winded = false;
+            }
+        }
+    }
+
+    /// Dash readiness from 0 (just used) to 1 (ready)
+    pub fn dash_charge(&self) -> f32 {
+        1.0 - self.dash_cooldown / self.dash_max_cooldown
+    }
+
+    /// Gain XP, returning how many levels that was worth
+    pub fn add_xp(&mut self, amount: u32) -> u32 {
+        self.xp += amount;
+        let mut levels = 0;
+        while self.xp >= xp_to_next(self.level) {
+            self.xp -= xp_to_next(self.level);
+            self.level += 1;
+            levels += 1;
+        }
+        levels
+    }
+
+    pub fn apply_perk(&mut self, perk: Perk) {
+        match perk {
+            Perk::MaxHealth => {
+                self.max_health += MAX_HEALTH_PERK;
+                self.heal(MAX_HEALTH_PERK);
+            }
+            Perk::FireRate => self.fire_rate_mult += FIRE_RATE_PERK,
+            Perk::MoveSpeed => self.move_speed_mult += MOVE_SPEED_PERK,
+            Perk::Armor => {
+                self.max_armor += ARMOR_PERK;
+                self.add_armor(ARMOR_PERK);
+            }
+            Perk::Insulation => {
+                self.resistances.add(DamageType::Fire, INSULATION_PERK);
+                self.resistances.add(DamageType::Explosive, INSULATION_PERK);
+            }
+        }
+    }
+
+    /// Starting bonuses of a class; hacking speed lives in the game's hack timing
+    pub fn apply_class(&mut self, class: PlayerClass) {
+        match class {
+            PlayerClass::Soldier => {
+                self.max_health += SOLDIER_BONUS_HEALTH;
+                self.health = self.max_health;
+                let pistol = Weapon::pistol();
+                if let Some(ammo) = pistol.ammo {
+                    self.add_ammo(ammo, ammo.pickup_amount());
+                }
+                self.add_weapon(pistol);
+                self.current_weapon = self.weapons.len() - 1;
+            }
+            PlayerClass::Hacker => {}
+            PlayerClass::Scout => self.move_speed_mult *= SCOUT_SPEED_MULT,
+        }
     }
 
     pub fn has_weapon(&self, name: &str) -> bool {
         self.weapons.iter().any(|w| w.name == name)
     }
 
-    pub fn add_weapon(&mut self, weapon: Weapon) {
-        if !self.has_weapon(weapon.name) {
-            self.weapons.push(weapon);
+    /// Firearms carried (the knife doesn't take a slot)
+    pub fn firearm_count(&self) -> usize {
+        self.weapons.iter().filter(|w| !w.is_melee).count()
+    }
+
+    /// Add a weapon, or swap in a better-equipped copy of one already carried.
+    /// Returns false if it is a new weapon and every slot is taken.
+    pub fn add_weapon(&mut self, weapon: Weapon) -> bool {
+        let has_room = self.firearm_count() < self.weapon_slots;
+        match self.weapons.iter_mut().find(|w| w.name == weapon.name) {
+            Some(existing) => {
+                if existing.attachments.len() < weapon.attachments.len() {
+                    *existing = weapon;
+                }
+                true
+            }
+            None if has_room => {
+                self.weapons.push(weapon);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the held firearm with `weapon`, returning the one put down.
+    /// The knife can't be swapped out.
+    pub fn swap_weapon(&mut self, weapon: Weapon) -> Option<Weapon> {
+        if self.weapon().is_melee {
+            return None;
+        }
+        Some(std::mem::replace(self.weapon_mut(), weapon))
+    }
+
+    /// Backpack upgrade: one more weapon slot. Returns false at the cap.
+    pub fn add_weapon_slot(&mut self) -> bool {
+        if self.weapon_slots >= MAX_WEAPON_SLOTS {
+            return false;
+        }
+        self.weapon_slots += 1;
+        true
+    }
+
+    pub fn ammo(&self, kind: AmmoKind) -> u32 {
+        self.ammo[kind.index()]
+    }
+
+    /// Add rounds to a reserve, capped at its maximum.
+    /// Returns false if the reserve was already full.
+    pub fn add_ammo(&mut self, kind: AmmoKind, amount: u32) -> bool {
+        let reserve = &mut self.ammo[kind.index()];
+        if *reserve >= kind.max_reserve() {
+            return false;
+        }
+        *reserve = (*reserve + amount).min(kind.max_reserve());
+        true
+    }
+
+    /// Whether the current weapon has a round to fire
+    pub fn has_ammo(&self) -> bool {
+        self.weapon().ammo.is_none_or(|kind| self.ammo(kind) > 0)
+    }
+
+    /// Spend one round of the current weapon's ammo
+    pub fn use_ammo(&mut self) {
+        if let Some(kind) = self.weapon().ammo {
+            let reserve = &mut self.ammo[kind.index()];
+            *reserve = reserve.saturating_sub(1);
+        }
+    }
+
+    pub fn facing(&self) -> u32 {
+        self.facing
+    }
+
+    /// Face along the aim angle (radians, 0 = right). A roll keeps its own
+    /// facing until it ends.
+    pub fn aim_at(&mut self, angle: f32) {
+        if !self.is_rolling() {
+            self.facing = angle_to_direction(angle);
         }
     }
 
@@ -147,37 +804,146 @@ impl Player {
         if self.invulnerability_timer > 0.0 {
             self.invulnerability_timer -= dt;
         }
+        if self.magnet_timer > 0.0 {
+            self.magnet_timer -= dt;
+        }
+        if self.scanner_timer > 0.0 {
+            self.scanner_timer -= dt;
+        }
+        self.dash_cooldown = (self.dash_cooldown - dt).max(0.0);
+        self.dash_iframes = (self.dash_iframes - dt).max(0.0);
+        self.roll_cooldown = (self.roll_cooldown - dt).max(0.0);
+        self.roll_timer = (self.roll_timer - dt).max(0.0);
+        if let Some((_, _, timer)) = &mut self.dash_trail {
+            *timer -= dt;
+            if *timer <= 0.0 {
+                self.dash_trail = None;
+            }
+        }
+        self.update_stamina(dt, input);
+        if self.shield_timer > 0.0 {
+            self.shield_timer -= dt;
+            self.shield_recharge_delay -= dt;
+            if self.shield_recharge_delay <= 0.0 {
+                self.shield = (self.shield + SHIELD_RECHARGE_RATE * dt).min(SHIELD_CAPACITY);
+            }
+        } else {
+            self.shield = 0.0;
+        }
 
-        // Only allow new movement when at target position
-        if self.pos.is_at_target() && input.is_moving() {
+        // Grid movement: only allow a new step when at target position
+        if self.movement == MovementMode::Grid && self.pos.is_at_target() && input.is_moving() {
             let new_x = self.pos.x + input.dx;
             let new_y = self.pos.y + input.dy;
 
             // Update facing direction
             self.facing = movement_to_direction(input.dx, input.dy);
 
-            if map.is_walkable_by(new_x, new_y, EntityType::Player) {
+            if map.can_step(
+                self.pos.x,
+                self.pos.y,
+                input.dx,
+                input.dy,
+                EntityType::Player,
+            ) {
                 self.pos.x = new_x;
                 self.pos.y = new_y;
+            } else if input.dx != 0 && input.dy != 0 {
+                // Blocked diagonal: slide along whichever axis is open
+                if map.can_step(self.pos.x, self.pos.y, input.dx, 0, EntityType::Player) {
+                    self.pos.x = new_x;
+                } else if map.can_step(self.pos.x, self.pos.y, 0, input.dy, EntityType::Player) {
+                    self.pos.y = new_y;
+                }
             }
         }
 
-        // Apply speed multiplier (tile speed * boost)
-        let mut speed_mult = map.get_speed_at(self.pos.x, self.pos.y);
+        // Apply speed multiplier (tile speed * boost * perks and skills)
+        let tile_speed = self
+            .skills
+            .terrain_speed(map.get_speed_at(self.pos.x, self.pos.y));
+        let mut speed_mult = tile_speed * self.move_speed_mult * self.skills.move_speed_mult();
         if self.speed_boost_timer > 0.0 {
             speed_mult *= 2.0;
         }
-        self.pos.update_visual(dt, speed_mult);
+        if self.sprinting {
+            speed_mult *= SPRINT_SPEED_MULT;
+        }
+        if self.crouching {
+            speed_mult *= CROUCH_SPEED_MULT;
+        }
+        if self.is_rolling() {
+            speed_mult *= ROLL_SPEED_MULT;
+        }
+        let before = (self.pos.visual_x, self.pos.visual_y);
+        match self.movement {
+            MovementMode::Grid => self.pos.update_visual(dt, speed_mult),
+            MovementMode::Free => self.move_free(dt, input, speed_mult, map),
+        }
+        let moving = (self.pos.visual_x, self.pos.visual_y) != before;
+        self.animation.update(dt, moving);
 
+        // Fire rate perks make cooldowns run down faster
         for weapon in &mut self.weapons {
-            weapon.update(dt);
+            weapon.update(dt * self.fire_rate_mult);
         }
     }
 
     pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
         let screen_x = self.pos.visual_x * TILE_SIZE - camera_x;
         let screen_y = self.pos.visual_y * TILE_SIZE - camera_y;
-        sprites.draw_player(screen_x, screen_y, self.facing);
+
+        // Fading afterimages along the dash path
+        if let Some((from_x, from_y, timer)) = self.dash_trail {
+            let fade = timer / DASH_TRAIL_TIME;
+            for i in 0..DASH_TILES {
+                let t = i as f32 / DASH_TILES as f32;
+                let ix = (from_x + (self.pos.visual_x - from_x) * t) * TILE_SIZE - camera_x;
+                let iy = (from_y + (self.pos.visual_y - from_y) * t) * TILE_SIZE - camera_y;
+                let alpha = fade * (0.2 + 0.3 * t);
+                sprites.draw_player_tinted(ix, iy, self.facing, Color::new(0.6, 0.9, 1.0, alpha));
+            }
+        }
+        if self.is_rolling() {
+            // Tumble: one full turn through the facings over the roll
+            let turn = ((1.0 - self.roll_timer / ROLL_DURATION) * 8.0) as u32;
+            let facing = (self.facing + turn) % 8;
+            self.skin
+                .draw(sprites, screen_x, screen_y, facing, 0, WHITE);
+        } else if !self.is_alive() {
+            // Downed in co-op: slumped and faded until the partner revives them
+            let downed = Color::new(0.8, 0.35, 0.35, 0.6);
+            self.skin
+                .draw(sprites, screen_x, screen_y, self.facing, 0, downed);
+        } else {
+            // Crouched players keep to the shadows
+            let shade = if self.crouching {
+                Color::new(0.55, 0.55, 0.7, 0.85)
+            } else {
+                WHITE
+            };
+            let (frame, bob) = (self.animation.frame(), self.animation.bob());
+            self.skin
+                .draw(sprites, screen_x, screen_y - bob, self.facing, frame, shade);
+        }
+
+        // Shield bubble fades as it drains
+        if self.shield > 0.0 {
+            let strength = self.shield / SHIELD_CAPACITY;
+            let color = Color::new(
+                SHIELD_COLOR.r,
+                SHIELD_COLOR.g,
+                SHIELD_COLOR.b,
+                0.3 + strength * 0.5,
+            );
+            draw_circle_lines(
+                screen_x + TILE_SIZE / 2.0,
+                screen_y + TILE_SIZE / 2.0,
+                TILE_SIZE * 0.65,
+                1.0 + strength * 2.0,
+                color,
+            );
+        }
     }
 }
 
@@ -191,6 +957,20 @@ pub struct Bot {
     respawn_timer: f32,
     pub hostile: bool,
     pub shoot_cooldown: f32,
+    pub keycard: Option<KeycardColor>, // Elite carrier: drops this keycard when killed
+    pub allied: bool,                  // Hacked by the player: fights hostile bots
+    pub loot_pending: bool,            // Killed and not yet checked for drops
+    pub stun_timer: f32,               // EMP stun: no moving or shooting while > 0
+    pub resistances: Resistances,
+    ally_timer: f32,    // Temporary allegiance left; turns hostile again at 0
+    shoved_timer: f32,  // Recently shoved: an environmental death now counts for the player
+    pub boss: bool,     // Campaign boss: never respawns or switches sides
+    pub kamikaze: bool, // Rushes players and blows up when it dies
+    pub paid_out: bool, // Already dropped its credits; respawns don't pay again
+    pub health: u32,    // Hits left to go down; only the boss takes more than one
+    aggro: bool,        // Chasing a player it has noticed
+    hit_flash: f32,     // Flashing white while > 0
+    pub animation: Animation,
     // Pathfinding
     path: VecDeque<(i32, i32)>,
     path_target: Option<(i32, i32)>,
@@ -209,6 +989,20 @@ impl Bot {
             respawn_timer: 0.0,
             hostile: false,
             shoot_cooldown: 0.0,
+            keycard: None,
+            allied: false,
+            loot_pending: false,
+            stun_timer: 0.0,
+            resistances: Resistances::default(),
+            ally_timer: 0.0,
+            shoved_timer: 0.0,
+            boss: false,
+            kamikaze: false,
+            paid_out: false,
+            health: 1,
+            aggro: false,
+            hit_flash: 0.0,
+            animation: Animation::default(),
             path: VecDeque::new(),
             path_target: None,
             path_recalc_timer: 0.0,
@@ -226,28 +1020,154 @@ impl Bot {
             respawn_timer: 0.0,
             hostile: true,
             shoot_cooldown: rand::gen_range(0.0, 1.0), // Stagger initial shots
+            keycard: None,
+            allied: false,
+            loot_pending: false,
+            stun_timer: 0.0,
+            resistances: Resistances::default(),
+            ally_timer: 0.0,
+            shoved_timer: 0.0,
+            boss: false,
+            kamikaze: false,
+            paid_out: false,
+            health: 1,
+            aggro: false,
+            hit_flash: 0.0,
+            animation: Animation::default(),
             path: VecDeque::new(),
             path_target: None,
             path_recalc_timer: 0.0,
         }
     }
 
+    /// Hostile elite bot that survives a share of bullets
+    pub fn new_elite(x: i32, y: i32) -> Self {
+        Self {
+            resistances: Resistances::default().with(DamageType::Ballistic, ELITE_BALLISTIC_RESIST),
+            ..Self::new_hostile(x, y)
+        }
+    }
+
+    /// Hostile elite bot carrying a keycard for a locked terminal or door
+    pub fn new_keycard_carrier(x: i32, y: i32, color: KeycardColor) -> Self {
+        Self {
+            keycard: Some(color),
+            ..Self::new_elite(x, y)
+        }
+    }
+
+    /// Hostile bot that doesn't shoot but blows up on reaching a player
+    pub fn new_kamikaze(x: i32, y: i32) -> Self {
+        Self {
+            kamikaze: true,
+            ..Self::new_hostile(x, y)
+        }
+    }
+
+    /// Hostile boss ending the campaign, resistant to explosives
+    pub fn new_boss(x: i32, y: i32) -> Self {
+        Self {
+            boss: true,
+            health: BOSS_HEALTH,
+            move_interval: 0.35,
+            resistances: Resistances::default().with(DamageType::Explosive, 0.5),
+            ..Self::new_hostile(x, y)
+        }
+    }
+
+    /// Take a hit that downs a normal bot; true if the bot still stands
+    pub fn absorb_hit(&mut self) -> bool {
+        if self.health > 1 {
+            self.health -= 1;
+            self.hit_flash = HIT_FLASH_TIME;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take the bot off the map for `seconds` without a kill (no loot)
+    pub fn despawn(&mut self, seconds: f32) {
+        self.alive = false;
+        self.respawn_timer = seconds;
+    }
+
     pub fn kill(&mut self) {
         self.alive = false;
+        self.aggro = false;
+        self.loot_pending = true;
         self.respawn_timer = rand::gen_range(5.0, 15.0);
     }
 
+    /// Whether (x, y) is adjacent to the bot and behind its facing direction
+    pub fn is_behind(&self, x: i32, y: i32) -> bool {
+        let (dx, dy) = (x - self.pos.x, y - self.pos.y);
+        if (dx, dy) == (0, 0) || dx.abs() > 1 || dy.abs() > 1 {
+            return false;
+        }
+        let (fx, fy) = direction_to_vector(self.facing);
+        fx * dx as f32 + fy * (dy as f32) < 0.0
+    }
+
+    /// Reprogram a hostile bot to fight for the player
+    pub fn convert_to_ally(&mut self) {
+        self.hostile = false;
+        self.allied = true;
+        self.path.clear();
+        self.path_target = None;
+    }
+
+    /// Roll the bot's resistance against a hit; true if it survives
+    pub fn shrugs_off(&self, kind: DamageType) -> bool {
+        rand::gen_range(0.0, 1.0) < self.resistances.get(kind)
+    }
+
+    /// Fight for the player for `duration` seconds, then turn hostile again
+    pub fn convert_for(&mut self, duration: f32) {
+        self.convert_to_ally();
+        self.ally_timer = duration;
+    }
+
+    /// Push the bot `tiles` steps along (dx, dy), into whatever waits there
+    pub fn shove(&mut self, dx: i32, dy: i32, tiles: i32, map: &TileMap) {
+        self.pos.knockback(dx, dy, tiles, map, EntityType::Bot);
+        self.path.clear();
+        self.path_target = None;
+        self.shoved_timer = SHOVE_CREDIT_TIME;
+    }
+
+    pub fn was_shoved(&self) -> bool {
+        self.shoved_timer > 0.0
+    }
+
+    /// Knock the bot out for a while (EMP)
+    pub fn stun(&mut self, duration: f32) {
+        self.stun_timer = self.stun_timer.max(duration);
+    }
+
     /// Turn this bot hostile (infected by another hostile bot)
     pub fn infect(&mut self) {
         self.hostile = true;
         self.move_interval = 0.3 + rand::gen_range(0.0, 0.2);
     }
 
+    /// Record whether the bot is chasing a player; true when it has only
+    /// just noticed one
+    pub fn set_aggro(&mut self, aggro: bool) -> bool {
+        let noticed = aggro && !self.aggro;
+        self.aggro = aggro;
+        noticed
+    }
+
     pub fn update(&mut self, dt: f32, map: &TileMap, target_pos: Option<(i32, i32)>) {
         if !self.alive {
+            if self.boss {
+                return;
+            }
             self.respawn_timer -= dt;
             if self.respawn_timer <= 0.0 {
                 self.alive = true;
+                self.allied = false;
                 self.pos = self.spawn_pos;
                 // 50% chance to respawn as hostile
                 if rand::gen_range(0.0, 1.0) < 0.5 {
@@ -258,6 +1178,9 @@ impl Bot {
                     self.move_interval = 0.5 + rand::gen_range(0.0, 0.5);
                 }
                 self.shoot_cooldown = rand::gen_range(0.0, 1.0);
+                self.stun_timer = 0.0;
+                self.ally_timer = 0.0;
+                self.shoved_timer = 0.0;
                 // Reset pathfinding
                 self.path.clear();
                 self.path_target = None;
@@ -266,6 +1189,23 @@ impl Bot {
             return;
         }
 
+        if self.ally_timer > 0.0 {
+            self.ally_timer -= dt;
+            if self.ally_timer <= 0.0 {
+                self.allied = false;
+                self.hostile = true;
+                self.path.clear();
+                self.path_target = None;
+            }
+        }
+
+        self.shoved_timer = (self.shoved_timer - dt).max(0.0);
+        self.hit_flash = (self.hit_flash - dt).max(0.0);
+        if self.stun_timer > 0.0 {
+            self.stun_timer -= dt;
+            return;
+        }
+
         // Update shoot cooldown
         if self.shoot_cooldown > 0.0 {
             self.shoot_cooldown -= dt;
@@ -279,8 +1219,8 @@ impl Bot {
         if self.pos.is_at_target() && self.move_timer >= self.move_interval {
             self.move_timer = 0.0;
 
-            // Hostile bots stop moving when close to target (stand and shoot)
-            let should_stand = if self.hostile {
+            // Hostile and allied bots stop moving when close to target (stand and shoot)
+            let should_stand = if self.hostile || self.allied {
                 if let Some((tx, ty)) = target_pos {
                     let dist = (tx - self.pos.x).abs() + (ty - self.pos.y).abs();
                     dist <= 3 // Stand and shoot when within 3 tiles
@@ -300,8 +1240,8 @@ impl Bot {
                         self.facing = movement_to_direction(dx, dy);
                     }
                 }
-            } else if self.hostile {
-                // Use BFS pathfinding for hostile bots
+            } else if self.hostile || self.allied {
+                // Use BFS pathfinding for hostile and allied bots
                 self.move_with_pathfinding(map, target_pos);
             } else {
                 // Random direction for non-hostile bots
@@ -314,7 +1254,7 @@ impl Bot {
                     self.facing = movement_to_direction(dx, dy);
                 }
 
-                if map.is_walkable_by(new_x, new_y, EntityType::Bot) {
+                if map.can_enter(new_x, new_y, dx, dy, EntityType::Bot) {
                     self.pos.x = new_x;
                     self.pos.y = new_y;
                 }
@@ -322,7 +1262,10 @@ impl Bot {
         }
 
         let speed_mult = map.get_speed_at(self.pos.x, self.pos.y);
+        let before = (self.pos.visual_x, self.pos.visual_y);
         self.pos.update_visual(dt, speed_mult);
+        let moving = (self.pos.visual_x, self.pos.visual_y) != before;
+        self.animation.update(dt, moving);
     }
 
     /// Move hostile bot using BFS pathfinding
@@ -333,7 +1276,7 @@ impl Bot {
             let (dx, dy) = directions[rand::gen_range(0, 4)];
             let new_x = self.pos.x + dx;
             let new_y = self.pos.y + dy;
-            if map.is_walkable_by(new_x, new_y, EntityType::Bot) {
+            if map.can_enter(new_x, new_y, dx, dy, EntityType::Bot) {
                 self.pos.x = new_x;
                 self.pos.y = new_y;
                 self.facing = movement_to_direction(dx, dy);
@@ -357,7 +1300,7 @@ impl Bot {
             let dx = next_x - self.pos.x;
             let dy = next_y - self.pos.y;
 
-            if map.is_walkable_by(next_x, next_y, EntityType::Bot) {
+            if map.can_enter(next_x, next_y, dx, dy, EntityType::Bot) {
                 self.pos.x = next_x;
                 self.pos.y = next_y;
                 self.path.pop_front();
@@ -373,7 +1316,7 @@ impl Bot {
     }
 
     /// BFS pathfinding to find shortest path from (sx, sy) to (tx, ty)
-    fn find_path(sx: i32, sy: i32, tx: i32, ty: i32, map: &TileMap) -> VecDeque<(i32, i32)> {
+    pub fn find_path(sx: i32, sy: i32, tx: i32, ty: i32, map: &TileMap) -> VecDeque<(i32, i32)> {
         const MAX_SEARCH: usize = 2000; // Limit search to prevent lag
 
         if sx == tx && sy == ty {
@@ -410,7 +1353,7 @@ impl Bot {
                     continue;
                 }
 
-                if map.is_walkable_by(nx, ny, EntityType::Bot) {
+                if map.can_enter(nx, ny, dx, dy, EntityType::Bot) {
                     came_from.insert((nx, ny), (cx, cy));
                     queue.push_back((nx, ny));
                 }
@@ -433,15 +1376,21 @@ impl Bot {
         path
     }
 
-    /// Check if hostile bot can shoot and return target direction if so
-    pub fn try_shoot(&mut self, player_x: i32, player_y: i32) -> Option<(f32, f32)> {
-        if !self.hostile || !self.alive || self.shoot_cooldown > 0.0 {
+    /// Check if a hostile or allied bot can shoot at the target tile and
+    /// return the direction if so
+    pub fn try_shoot(&mut self, target_x: i32, target_y: i32) -> Option<(f32, f32)> {
+        if !(self.hostile || self.allied)
+            || !self.alive
+            || self.kamikaze
+            || self.shoot_cooldown > 0.0
+            || self.stun_timer > 0.0
+        {
             return None;
         }
 
         let (bx, by) = (self.pos.x, self.pos.y);
-        let dx = player_x - bx;
-        let dy = player_y - by;
+        let dx = target_x - bx;
+        let dy = target_y - by;
         let dist_sq = dx * dx + dy * dy;
 
         // Only shoot if within range (8 tiles)
@@ -470,19 +1419,107 @@ impl Bot {
         let screen_x = self.pos.visual_x * TILE_SIZE - camera_x;
         let screen_y = self.pos.visual_y * TILE_SIZE - camera_y;
 
-        if self.hostile {
-            // Hostile bots get a red tint
-            let tint = Color::from_rgba(255, 100, 100, 255);
-            sprites.draw_bot_tinted(screen_x, screen_y, self.facing, tint);
+        // Bosses glow purple, kamikazes orange, hostile bots red and allies green
+        let tint = if self.boss {
+            Color::from_rgba(200, 110, 255, 255)
+        } else if self.kamikaze && self.hostile {
+            Color::from_rgba(255, 170, 60, 255)
+        } else if self.hostile {
+            Color::from_rgba(255, 100, 100, 255)
+        } else if self.allied {
+            Color::from_rgba(120, 255, 140, 255)
         } else {
-            sprites.draw_bot(screen_x, screen_y, self.facing);
+            WHITE
+        };
+        let (frame, bob) = (self.animation.frame(), self.animation.bob());
+        let flash = (self.hit_flash > 0.0).then_some(WHITE);
+        sprites.draw_bot_frame(screen_x, screen_y - bob, self.facing, frame, tint, flash);
+
+        if self.boss {
+            // Bosses show how much they have left
+            let pct = self.health as f32 / BOSS_HEALTH as f32;
+            draw_rectangle(
+                screen_x - 4.0,
+                screen_y - 8.0,
+                TILE_SIZE + 8.0,
+                4.0,
+                DARKGRAY,
+            );
+            draw_rectangle(
+                screen_x - 4.0,
+                screen_y - 8.0,
+                (TILE_SIZE + 8.0) * pct,
+                4.0,
+                Color::from_rgba(200, 110, 255, 255),
+            );
+        }
+
+        // Elite keycard carriers are ringed in their card's color
+        if let Some(color) = self.keycard {
+            draw_circle_lines(
+                screen_x + TILE_SIZE / 2.0,
+                screen_y + TILE_SIZE / 2.0,
+                TILE_SIZE * 0.6,
+                2.0,
+                color.color(),
+            );
         }
     }
 }
 
+/// Saved without its path; bots replan on the next update
+impl Persist for Bot {
+    fn write(&self, out: &mut String) {
+        self.pos.x.write(out);
+        self.pos.y.write(out);
+        self.spawn_pos.x.write(out);
+        self.spawn_pos.y.write(out);
+        self.facing.write(out);
+        self.move_interval.write(out);
+        self.alive.write(out);
+        self.respawn_timer.write(out);
+        self.hostile.write(out);
+        self.keycard.write(out);
+        self.allied.write(out);
+        self.ally_timer.write(out);
+        self.stun_timer.write(out);
+        self.resistances.write(out);
+        self.boss.write(out);
+        self.health.write(out);
+        self.kamikaze.write(out);
+        self.paid_out.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let (x, y) = fields.read()?;
+        let (spawn_x, spawn_y) = fields.read()?;
+        Ok(Self {
+            pos: Position::new(x, y),
+            spawn_pos: Position::new(spawn_x, spawn_y),
+            facing: fields.read()?,
+            move_interval: fields.read()?,
+            alive: fields.read()?,
+            respawn_timer: fields.read()?,
+            hostile: fields.read()?,
+            keycard: fields.read()?,
+            allied: fields.read()?,
+            ally_timer: fields.read()?,
+            stun_timer: fields.read()?,
+            resistances: fields.read()?,
+            boss: fields.read()?,
+            health: fields.read()?,
+            kamikaze: fields.read()?,
+            paid_out: fields.read()?,
+            ..Self::new(x, y)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::item::ItemType;
+    use crate::weapon::Attachment;
 
     #[test]
     fn test_position_new() {
@@ -503,6 +1540,25 @@ mod tests {
         assert!(!pos2.is_at_target());
     }
 
+    #[test]
+    fn test_position_knockback() {
+        use crate::tile_map::TileType;
+
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(8, 5, TileType::Wall);
+        map.set_tile(5, 2, TileType::Pit);
+
+        // Stops in front of walls
+        let mut pos = Position::new(5, 5);
+        pos.knockback(1, 0, 5, &map, EntityType::Bot);
+        assert_eq!((pos.x, pos.y), (7, 5));
+
+        // Ends up in the pit even though bots would never walk into it
+        let mut pos = Position::new(5, 5);
+        pos.knockback(0, -1, 5, &map, EntityType::Bot);
+        assert_eq!((pos.x, pos.y), (5, 2));
+    }
+
     #[test]
     fn test_player_creation() {
         let player = Player::new(3, 4);
@@ -527,6 +1583,16 @@ mod tests {
         assert!(!player.is_alive());
     }
 
+    #[test]
+    fn test_player_resistances() {
+        let mut player = Player::new(0, 0);
+        player.apply_perk(Perk::Insulation);
+        player.take_hit(40, DamageType::Explosive);
+        assert_eq!(player.health, 70);
+        player.take_hit(10, DamageType::Ballistic);
+        assert_eq!(player.health, 60);
+    }
+
     #[test]
     fn test_player_invulnerability() {
         let mut player = Player::new(0, 0);
@@ -549,6 +1615,300 @@ mod tests {
         // Adding same weapon again should not duplicate
         player.add_weapon(Weapon::pistol());
         assert_eq!(player.weapons.len(), 2);
+
+        // A copy with attachments replaces the plain one
+        player.add_weapon(Weapon::pistol().with_random_attachments(1));
+        assert_eq!(player.weapons.len(), 2);
+        assert_eq!(player.weapons[1].attachments.len(), 1);
+        player.add_weapon(Weapon::pistol());
+        assert_eq!(player.weapons[1].attachments.len(), 1);
+    }
+
+    #[test]
+    fn test_player_weapon_slots() {
+        let mut player = Player::new(0, 0);
+        assert!(player.add_weapon(Weapon::pistol()));
+        assert!(player.add_weapon(Weapon::shotgun()));
+        assert!(player.add_weapon(Weapon::machine_pistol()));
+        assert!(!player.add_weapon(Weapon::rifle()));
+        // Copies of carried weapons never need a slot
+        assert!(player.add_weapon(Weapon::pistol()));
+
+        // The knife stays; a firearm is swapped out for the new weapon
+        assert!(player.swap_weapon(Weapon::rifle()).is_none());
+        player.switch_weapon(2);
+        let dropped = player.swap_weapon(Weapon::rifle()).unwrap();
+        assert_eq!(dropped.name, "Shotgun");
+        assert_eq!(player.weapon().name, "Rifle");
+
+        assert!(player.add_weapon_slot());
+        assert!(player.add_weapon(Weapon::shotgun()));
+        assert!(!player.add_weapon_slot());
+        assert_eq!(player.firearm_count(), MAX_WEAPON_SLOTS);
+    }
+
+    #[test]
+    fn test_player_armor() {
+        let mut player = Player::new(0, 0);
+        assert!(player.add_armor(10));
+
+        // 60% of 10 is soaked by armor, the rest hits health
+        player.take_damage(10);
+        assert_eq!(player.armor, 4);
+        assert_eq!(player.health, 96);
+
+        // Worn-through armor only absorbs what is left
+        player.take_damage(20);
+        assert_eq!(player.armor, 0);
+        assert_eq!(player.health, 80);
+
+        assert!(player.add_armor(500));
+        assert_eq!(player.armor, player.max_armor);
+        assert!(!player.add_armor(1));
+    }
+
+    #[test]
+    fn test_player_shield() {
+        let map = TileMap::new(5, 5);
+        let mut player = Player::new(1, 1);
+        assert_eq!(player.absorb_with_shield(10), 10);
+
+        player.activate_shield();
+        assert_eq!(player.absorb_with_shield(30), 0);
+        assert_eq!(player.absorb_with_shield(30), 10);
+        assert_eq!(player.shield, 0.0);
+
+        // No recharge until a few seconds pass without hits
+        player.update(1.0, MoveDirection::default(), &map);
+        assert_eq!(player.shield, 0.0);
+        for _ in 0..3 {
+            player.update(1.0, MoveDirection::default(), &map);
+        }
+        assert!(player.shield > 0.0);
+
+        // The shield drops away when the powerup runs out
+        for _ in 0..30 {
+            player.update(1.0, MoveDirection::default(), &map);
+        }
+        assert_eq!(player.shield, 0.0);
+        assert_eq!(player.absorb_with_shield(10), 10);
+    }
+
+    #[test]
+    fn test_player_medkits() {
+        let mut player = Player::new(0, 0);
+        assert!(!player.use_medkit(25));
+        for _ in 0..MAX_MEDKITS {
+            assert!(player.store_medkit());
+        }
+        assert!(!player.store_medkit());
+
+        // Medkits are kept while health is full
+        assert!(!player.use_medkit(25));
+        assert_eq!(player.medkits, MAX_MEDKITS);
+
+        player.take_damage(40);
+        assert!(player.use_medkit(25));
+        assert_eq!(player.health, 85);
+        assert_eq!(player.medkits, MAX_MEDKITS - 1);
+    }
+
+    #[test]
+    fn test_player_classes() {
+        let mut soldier = Player::new(0, 0);
+        soldier.apply_class(PlayerClass::Soldier);
+        assert_eq!(soldier.max_health, PLAYER_MAX_HEALTH + SOLDIER_BONUS_HEALTH);
+        assert_eq!(soldier.health, soldier.max_health);
+        assert_eq!(soldier.weapon().name, Weapon::pistol().name);
+        assert!(soldier.has_ammo());
+
+        let mut scout = Player::new(0, 0);
+        scout.apply_class(PlayerClass::Scout);
+        assert!(scout.move_speed_mult > 1.0);
+        assert_eq!(scout.weapons.len(), 1);
+    }
+
+    #[test]
+    fn test_player_xp_and_perks() {
+        let mut player = Player::new(0, 0);
+        assert_eq!(player.add_xp(xp_to_next(1) - 1), 0);
+        assert_eq!(player.add_xp(1), 1);
+        assert_eq!((player.level, player.xp), (2, 0));
+        // One big award can be worth several levels
+        assert_eq!(player.add_xp(xp_to_next(2) + xp_to_next(3) + 5), 2);
+        assert_eq!((player.level, player.xp), (4, 5));
+
+        player.take_damage(10);
+        player.apply_perk(Perk::MaxHealth);
+        assert_eq!(player.max_health, PLAYER_MAX_HEALTH + MAX_HEALTH_PERK);
+        assert_eq!(player.health, PLAYER_MAX_HEALTH - 10 + MAX_HEALTH_PERK);
+        player.apply_perk(Perk::Armor);
+        assert_eq!(player.armor, ARMOR_PERK);
+        player.apply_perk(Perk::FireRate);
+        assert!(player.fire_rate_mult > 1.0);
+    }
+
+    #[test]
+    fn test_player_dash() {
+        let mut map = TileMap::new(10, 5);
+        map.set_tile(7, 2, crate::tile_map::TileType::Wall);
+        let mut player = Player::new(2, 2);
+        let right = MoveDirection {
+            dx: 1,
+            ..Default::default()
+        };
+        assert!(!player.try_dash(MoveDirection::default(), &map));
+
+        assert!(player.try_dash(right, &map));
+        assert_eq!((player.pos.x, player.pos.y), (5, 2));
+        assert!(player.is_invulnerable());
+        assert!(!player.try_dash(right, &map)); // Cooling down
+
+        // Stops short of the wall
+        player.update(DASH_COOLDOWN, MoveDirection::default(), &map);
+        assert!(!player.is_invulnerable());
+        assert!(player.try_dash(right, &map));
+        assert_eq!(player.pos.x, 6);
+    }
+
+    #[test]
+    fn test_free_movement_slides_along_walls() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(6, 5, crate::tile_map::TileType::Wall);
+        let mut pos = Position::new(5, 5);
+
+        // Stops flush against the wall to the right
+        pos.slide(2.0, 0.0, 0.35, &map, EntityType::Player);
+        assert!((pos.visual_x + 0.5 + 0.35 - 6.0).abs() < 0.01);
+        assert_eq!((pos.x, pos.y), (5, 5));
+
+        // Moving down is unaffected, and the grid tile follows the center
+        pos.slide(0.0, 1.0, 0.35, &map, EntityType::Player);
+        assert_eq!((pos.x, pos.y), (5, 6));
+        pos.slide(-0.7, 0.0, 0.35, &map, EntityType::Player);
+        assert_eq!(pos.x, 4);
+    }
+
+    #[test]
+    fn test_player_diagonal_movement() {
+        let mut map = TileMap::new(10, 10);
+        let mut player = Player::new(5, 5);
+        let down_right = MoveDirection {
+            dx: 1,
+            dy: 1,
+            ..Default::default()
+        };
+        player.update(0.0, down_right, &map);
+        assert_eq!((player.pos.x, player.pos.y), (6, 6));
+        assert_eq!(player.facing, direction::DOWN_RIGHT);
+
+        // A wall on the corner turns the step into a slide down
+        let mut player = Player::new(5, 5);
+        map.set_tile(6, 5, crate::tile_map::TileType::Wall);
+        player.update(0.0, down_right, &map);
+        assert_eq!((player.pos.x, player.pos.y), (5, 6));
+    }
+
+    #[test]
+    fn test_player_faces_aim() {
+        let map = TileMap::new(10, 10);
+        let mut player = Player::new(5, 5);
+        player.aim_at(0.0);
+        assert_eq!(player.facing(), direction::RIGHT);
+        player.aim_at(-std::f32::consts::FRAC_PI_2);
+        assert_eq!(player.facing(), direction::UP);
+
+        // Rolling keeps the roll direction
+        let left = MoveDirection {
+            dx: -1,
+            ..Default::default()
+        };
+        assert!(player.try_roll(left, &map));
+        player.aim_at(0.0);
+        assert_eq!(player.facing(), direction::LEFT);
+    }
+
+    #[test]
+    fn test_player_roll() {
+        let mut map = TileMap::new(10, 5);
+        map.set_tile(6, 2, crate::tile_map::TileType::Wall);
+        let mut player = Player::new(2, 2);
+        let right = MoveDirection {
+            dx: 1,
+            ..Default::default()
+        };
+        assert!(!player.try_roll(MoveDirection::default(), &map));
+
+        assert!(player.try_roll(right, &map));
+        assert_eq!((player.pos.x, player.pos.y), (4, 2));
+        assert!(player.is_rolling());
+        // Rolling dodges projectiles but isn't general invulnerability
+        assert!(!player.is_invulnerable());
+        assert!(!player.try_roll(right, &map));
+
+        // The roll has its own cooldown, separate from the dash
+        assert!(player.try_dash(right, &map));
+        player.update(ROLL_COOLDOWN, MoveDirection::default(), &map);
+        assert!(!player.is_rolling());
+        assert!(!player.try_roll(right, &map)); // Against the wall already
+    }
+
+    #[test]
+    fn test_player_sprint_stamina() {
+        let map = TileMap::new(10, 10);
+        let mut player = Player::new(5, 5);
+        let sprint = MoveDirection {
+            dx: 0,
+            dy: 0,
+            sprint: true,
+        };
+        // Holding the key in place doesn't sprint
+        player.update(0.5, sprint, &map);
+        assert!(!player.sprinting);
+        assert_eq!(player.stamina, MAX_STAMINA);
+
+        let run = MoveDirection { dx: 1, ..sprint };
+        player.update(1.0, run, &map);
+        assert!(player.sprinting);
+        assert_eq!(player.stamina, MAX_STAMINA - SPRINT_DRAIN);
+
+        // Running dry leaves the player winded until stamina recovers
+        player.update(MAX_STAMINA / SPRINT_DRAIN, run, &map);
+        assert!(!player.sprinting);
+        player.update(0.1, run, &map);
+        assert!(!player.sprinting);
+        player.update(
+            SPRINT_RESUME / STAMINA_REGEN,
+            MoveDirection::default(),
+            &map,
+        );
+        player.update(0.1, run, &map);
+        assert!(player.sprinting);
+
+        // No sprinting while crouched
+        player.crouching = true;
+        player.update(0.1, run, &map);
+        assert!(!player.sprinting);
+    }
+
+    #[test]
+    fn test_player_ammo() {
+        let mut player = Player::new(0, 0);
+        assert!(player.has_ammo()); // Knife never runs dry
+
+        player.add_weapon(Weapon::shotgun());
+        player.switch_weapon(1);
+        assert!(!player.has_ammo());
+
+        assert!(player.add_ammo(AmmoKind::Shell, 1));
+        assert!(player.has_ammo());
+        player.use_ammo();
+        assert!(!player.has_ammo());
+
+        // Reserves are capped, and a full reserve refuses more
+        assert!(player.add_ammo(AmmoKind::Shell, 1000));
+        assert_eq!(player.ammo(AmmoKind::Shell), AmmoKind::Shell.max_reserve());
+        assert!(!player.add_ammo(AmmoKind::Shell, 1));
     }
 
     #[test]
@@ -564,10 +1924,189 @@ mod tests {
         assert_eq!(player.pos.y, 5);
     }
 
+    #[test]
+    fn test_player_save_round_trip() {
+        let mut player = Player::new(4, 6);
+        player.apply_class(PlayerClass::Soldier);
+        player.add_weapon(Weapon::rifle().with_attachment(Attachment::Scope));
+        player.add_ammo(AmmoKind::Heavy, 7);
+        player.inventory.add(ItemType::Keycard {
+            color: KeycardColor::Yellow,
+        });
+        player.apply_perk(Perk::Insulation);
+        player.take_damage(30);
+        player.credits = 12;
+        player.movement = MovementMode::Free;
+
+        let mut out = SaveWriter::new();
+        player.save(&mut out);
+        let text = out.finish();
+        let mut loaded = Player::new(0, 0);
+        loaded
+            .restore(&mut SaveReader::new(&text).unwrap())
+            .unwrap();
+
+        assert_eq!((loaded.pos.x, loaded.pos.y), (4, 6));
+        assert_eq!(loaded.health, player.health);
+        assert_eq!(loaded.max_health, player.max_health);
+        assert_eq!(loaded.resistances, player.resistances);
+        assert_eq!(loaded.credits, 12);
+        assert_eq!(loaded.movement, MovementMode::Free);
+        assert_eq!(loaded.ammo(AmmoKind::Heavy), 7);
+        assert_eq!(loaded.weapon().label(), player.weapon().label());
+        assert_eq!(loaded.weapon().range, player.weapon().range);
+        let labels = |p: &Player| p.weapons.iter().map(|w| w.label()).collect::<Vec<_>>();
+        assert_eq!(labels(&loaded), labels(&player));
+        let card = ItemType::Keycard {
+            color: KeycardColor::Yellow,
+        };
+        assert_eq!(loaded.inventory.count(card), 1);
+    }
+
     #[test]
     fn test_bot_creation() {
         let bot = Bot::new(7, 8);
         assert_eq!(bot.pos.x, 7);
         assert_eq!(bot.pos.y, 8);
     }
+
+    #[test]
+    fn test_bot_is_behind() {
+        let mut bot = Bot::new_hostile(5, 5);
+        bot.facing = direction::RIGHT;
+
+        assert!(bot.is_behind(4, 5));
+        assert!(bot.is_behind(4, 4));
+        assert!(!bot.is_behind(6, 5)); // In front
+        assert!(!bot.is_behind(5, 4)); // Beside
+        assert!(!bot.is_behind(3, 5)); // Too far
+    }
+
+    #[test]
+    fn test_bot_convert_to_ally() {
+        let mut bot = Bot::new_hostile(5, 5);
+        bot.convert_to_ally();
+        assert!(bot.allied);
+        assert!(!bot.hostile);
+        // Allies still shoot (at hostile bots)
+        bot.shoot_cooldown = 0.0;
+        assert_eq!(bot.try_shoot(8, 5), Some((1.0, 0.0)));
+    }
+
+    #[test]
+    fn test_animation() {
+        let mut anim = Animation::default();
+        anim.update(WALK_FRAME_TIME * 1.5, true);
+        assert_eq!(anim.frame(), 1);
+        assert_eq!(anim.bob(), BOB_HEIGHT);
+        anim.update(WALK_FRAME_TIME, true);
+        assert_eq!(anim.frame(), 2);
+        assert_eq!(anim.bob(), 0.0);
+
+        // A moment's pause between tiles keeps the cycle going
+        anim.update(WALK_GRACE / 2.0, false);
+        assert_ne!(anim.frame(), 0);
+        // Standing still returns to frame 0 and breathes
+        anim.update(WALK_GRACE, false);
+        assert_eq!(anim.frame(), 0);
+        assert!((0.0..=BOB_HEIGHT).contains(&anim.bob()));
+    }
+
+    #[test]
+    fn test_bot_aggro() {
+        let mut bot = Bot::new_hostile(5, 5);
+        // Only the moment of noticing counts, once per chase
+        assert!(bot.set_aggro(true));
+        assert!(!bot.set_aggro(true));
+        assert!(!bot.set_aggro(false));
+        assert!(bot.set_aggro(true));
+        // A bot comes back from the dead unaware
+        bot.kill();
+        assert!(bot.set_aggro(true));
+    }
+
+    #[test]
+    fn test_bot_shove() {
+        use crate::tile_map::TileType;
+
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(8, 5, TileType::Lava);
+        let mut bot = Bot::new_hostile(6, 5);
+        assert!(!bot.was_shoved());
+
+        bot.shove(1, 0, 2, &map);
+        assert_eq!((bot.pos.x, bot.pos.y), (8, 5));
+        assert!(bot.was_shoved());
+
+        // Credit for the shove runs out
+        bot.update(SHOVE_CREDIT_TIME + 0.1, &map, None);
+        assert!(!bot.was_shoved());
+    }
+
+    #[test]
+    fn test_bot_save_round_trip() {
+        let mut bot = Bot::new_boss(3, 4);
+        bot.paid_out = true;
+        let mut out = SaveWriter::new();
+        out.record("bot", &[&bot]);
+        let text = out.finish();
+        let loaded: Bot = SaveReader::new(&text)
+            .unwrap()
+            .record("bot")
+            .unwrap()
+            .read()
+            .unwrap();
+        assert_eq!((loaded.pos.x, loaded.pos.y), (3, 4));
+        assert!(loaded.boss && loaded.hostile);
+        assert_eq!(loaded.health, BOSS_HEALTH);
+        assert_eq!(loaded.resistances, bot.resistances);
+        assert!(!loaded.kamikaze);
+        assert!(loaded.paid_out);
+    }
+
+    #[test]
+    fn test_kamikaze_never_shoots() {
+        let mut bot = Bot::new_kamikaze(5, 5);
+        bot.shoot_cooldown = 0.0;
+        assert!(bot.hostile && bot.kamikaze);
+        assert_eq!(bot.try_shoot(6, 5), None);
+    }
+
+    #[test]
+    fn test_bot_despawn() {
+        let map = TileMap::new(10, 10);
+        let mut bot = Bot::new_keycard_carrier(5, 5, KeycardColor::Red);
+        bot.despawn(2.0);
+        assert!(!bot.alive);
+        assert!(!bot.loot_pending);
+        assert_eq!(bot.keycard, Some(KeycardColor::Red));
+
+        bot.update(1.0, &map, None);
+        assert!(!bot.alive);
+        bot.update(1.1, &map, None);
+        assert!(bot.alive);
+    }
+
+    #[test]
+    fn test_boss_takes_hits() {
+        let map = TileMap::new(10, 10);
+        let mut bot = Bot::new_hostile(5, 5);
+        assert!(!bot.absorb_hit());
+
+        let mut boss = Bot::new_boss(5, 5);
+        for _ in 1..BOSS_HEALTH {
+            assert!(boss.absorb_hit());
+        }
+        assert!(!boss.absorb_hit());
+
+        // Each hit it survives flashes it briefly
+        assert!(boss.hit_flash > 0.0);
+        boss.update(HIT_FLASH_TIME, &map, None);
+        assert_eq!(boss.hit_flash, 0.0);
+
+        // Down for good
+        boss.kill();
+        boss.update(60.0, &map, None);
+        assert!(!boss.alive);
+    }
 }
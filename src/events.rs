@@ -0,0 +1,126 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist};
+
+const FIRST_EVENT_DELAY: f32 = 90.0; // Seconds into a map before the first event
+const EVENT_INTERVAL: (f32, f32) = (75.0, 150.0); // Seconds between later events
+const OUTAGE_DURATION: f32 = 30.0;
+pub const SURGE_BOTS: usize = 6;
+pub const SUPPLY_CRATES: usize = 4;
+
+/// Map-wide event the scheduler can trigger mid-run
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapEvent {
+    PowerOutage, // Darkness, and the security cameras go offline
+    BotSurge,    // A wave of hostile bots away from the player
+    SupplyDrop,  // Crates around the player
+}
+
+impl MapEvent {
+    pub const ALL: [MapEvent; 3] = [
+        MapEvent::PowerOutage,
+        MapEvent::BotSurge,
+        MapEvent::SupplyDrop,
+    ];
+
+    pub fn message(self) -> &'static str {
+        match self {
+            MapEvent::PowerOutage => "POWER OUTAGE - CAMERAS OFFLINE",
+            MapEvent::BotSurge => "WARNING: BOT SURGE INCOMING",
+            MapEvent::SupplyDrop => "SUPPLY DROP NEARBY",
+        }
+    }
+
+    /// Console name (`event <name>`)
+    pub fn key(self) -> &'static str {
+        match self {
+            MapEvent::PowerOutage => "outage",
+            MapEvent::BotSurge => "surge",
+            MapEvent::SupplyDrop => "supply",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<MapEvent> {
+        MapEvent::ALL.into_iter().find(|e| e.key() == key)
+    }
+}
+
+/// Counts down to the next random event and times the running outage
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EventScheduler {
+    next: f32,
+    pub outage: f32, // Seconds of power outage left
+}
+
+impl EventScheduler {
+    /// Fresh schedule for a new map
+    pub fn new() -> Self {
+        Self {
+            next: FIRST_EVENT_DELAY,
+            outage: 0.0,
+        }
+    }
+
+    pub fn is_outage(&self) -> bool {
+        self.outage > 0.0
+    }
+
+    /// Start an event's timed part (only the outage lasts)
+    pub fn start(&mut self, event: MapEvent) {
+        if event == MapEvent::PowerOutage {
+            self.outage = OUTAGE_DURATION;
+        }
+    }
+
+    /// Returns the event due this frame, if any
+    pub fn update(&mut self, dt: f32) -> Option<MapEvent> {
+        self.outage = (self.outage - dt).max(0.0);
+        self.next -= dt;
+        if self.next > 0.0 {
+            return None;
+        }
+        self.next = rand::gen_range(EVENT_INTERVAL.0, EVENT_INTERVAL.1);
+        let event = MapEvent::ALL[rand::gen_range(0, MapEvent::ALL.len())];
+        self.start(event);
+        Some(event)
+    }
+}
+
+impl Persist for EventScheduler {
+    fn write(&self, out: &mut String) {
+        self.next.write(out);
+        self.outage.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            next: fields.read()?,
+            outage: fields.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_schedule() {
+        let mut events = EventScheduler::new();
+        assert_eq!(events.update(FIRST_EVENT_DELAY - 1.0), None);
+        let event = events.update(1.0).unwrap();
+        assert_eq!(events.is_outage(), event == MapEvent::PowerOutage);
+        assert_eq!(events.update(1.0), None); // Rescheduled
+
+        events.start(MapEvent::PowerOutage);
+        events.update(OUTAGE_DURATION - 1.0);
+        assert!(events.is_outage());
+        events.update(1.0);
+        assert!(!events.is_outage());
+
+        for event in MapEvent::ALL {
+            assert_eq!(MapEvent::from_key(event.key()), Some(event));
+        }
+        assert_eq!(MapEvent::from_key("meteor"), None);
+    }
+}
@@ -1,16 +1,87 @@
 use macroquad::prelude::*;
-
-use crate::audio::AudioManager;
-use crate::entity::{Bot, Player};
+use std::collections::HashMap;
+
+use crate::ambience::{AMBIENT_RANGE, Ambience, proximity};
+use crate::audio::{AudioManager, SPACE_RADIUS, Space, WorldSound, heartbeat_rate};
+use crate::campaign::{Campaign, draw_level_intro};
+use crate::class::{PlayerClass, SetupRow, draw_class_select};
+use crate::console::Console;
+use crate::coop::{
+    REVIVE_HEALTH, Revive, View, needs_split, partner_skin, shared_view, split_rects,
+};
+use crate::daily::{Daily, DailyModifier, DailyRecords, draw_daily};
+use crate::damage::DamageType;
+use crate::debug::{CommandRegistry, DebugConsole};
+use crate::decoy::Decoy;
+use crate::demo::{Controller, DemoPilot, IDLE_TIME, Keyboard, Situation, draw_demo_banner};
+use crate::difficulty::Difficulty;
+use crate::endless::{
+    DepthModifier, bot_scale, draw_darkness, draw_depth, draw_loop, flood_lava, map_size,
+    roll_loop_modifier, roll_modifiers,
+};
+use crate::entity::{
+    Bot, MAX_MEDKITS, MAX_STAMINA, MovementMode, Player, Position, SHIELD_CAPACITY,
+};
+use crate::events::{EventScheduler, MapEvent, SUPPLY_CRATES, SURGE_BOTS};
+use crate::grenade::{
+    EMP_RADIUS, EMP_STUN_DURATION, FRAG_RADIUS, Grenade, GrenadeCloud, GrenadeKind,
+};
 use crate::input::{
-    get_mouse_position, get_player_input, get_weapon_switch, is_interact_held, is_interact_pressed,
-    is_menu_down, is_menu_escape, is_menu_select, is_menu_up, is_shooting,
+    self, Action, Focus, InputFrame, MoveDirection, bindable_key, get_countermeasure_input,
+    get_minigame_input, get_mouse_position, get_partner_input, get_player_input, get_weapon_switch,
+    get_zoom_input, is_any_key_pressed, is_bypass_pressed, is_console_pressed, is_crouch_pressed,
+    is_dash_pressed, is_drop_pressed, is_grenade_pressed, is_grenade_switch_pressed,
+    is_interact_held, is_interact_pressed, is_inventory_pressed, is_medkit_pressed, is_menu_down,
+    is_menu_escape, is_menu_left, is_menu_right, is_menu_select, is_menu_up, is_overview_held,
+    is_partner_interact_held, is_partner_interact_pressed, is_partner_shooting,
+    is_partner_weapon_cycle_pressed, is_roll_pressed, is_shooting, is_shove_pressed,
+    is_skill_tree_pressed, is_ultimate_pressed, key_label,
+};
+use crate::item::{
+    ARMOR_COLOR, CREDITS_COLOR, DATA_CHIP_COLOR, Item, ItemType, KeycardColor, Rarity,
+    SCANNER_COLOR, SHIELD_COLOR, TELEPORT_COLOR, WeaponKind, ammo_color, draw_credits,
+    draw_data_chip, draw_data_fragment, draw_grenade, draw_keycard, draw_medkit,
+};
+use crate::loot::{LootSource, LootTables};
+use crate::meta::{MapTheme, Profile, Unlock, draw_unlocks, run_reward};
+use crate::mode::{GameMode, TIME_ATTACK_BONUS, draw_countdown, time_bonus_score};
+use crate::music::{THREAT_RANGE, Theme, tension};
+use crate::net::{NetEvent, NetSession, OnlineMenu, TICK, draw_online};
+use crate::objective::{
+    NEST_COUNT, NEST_HIT_RADIUS, Objective, ObjectiveKind, SpawnerNest, draw_objectives,
+};
+use crate::particles::{Burst, Particles};
+use crate::perk::{
+    EXPLORE_TILES_PER_XP, LEVEL_UP_COLOR, Perk, XP_MINOR_HACK, XP_TERMINAL_HACK,
+    draw_level_up_menu, kill_xp, xp_to_next,
 };
-use crate::item::{Item, ItemType};
 use crate::projectile::Projectile;
-use crate::sprites::SpriteSheet;
-use crate::terminal::{FAIL_BOT_SPAWN, HACK_DURATION, HACK_WINDOW, HackState, Terminal};
-use crate::tile_map::{EntityType, TILE_SIZE, TileMap, TileType};
+use crate::pylon::{AlarmPylon, PYLON_HIT_RADIUS};
+use crate::rules::LifeRules;
+use crate::save::{self, SaveReader, SaveWriter};
+use crate::security::{SecurityCamera, SecurityPanel};
+use crate::settings::{LEVEL_STEP, Settings, SettingsRow, VOLUME_STEP, draw_settings};
+use crate::shake::{Shake, TRAUMA_BOSS_ATTACK, TRAUMA_EXPLOSION, TRAUMA_HIT, TRAUMA_SHOTGUN};
+use crate::shop::{
+    Goods, MAX_HEALTH_UPGRADE, OFFERS, SHOP_COLOR, ShopTerminal, bot_credit_drop, draw_shop_menu,
+};
+use crate::skills::{COOL_HEAD_CUT, FAST_FINGERS_CUT, LONG_WINDOW_BONUS, Skill, SkillBranch};
+use crate::skin::{Skin, draw_skin_select};
+use crate::speedrun::{self, PersonalBests, category, draw_timer};
+use crate::sprites::{SpriteSheet, direction_to_vector, tiles};
+use crate::stats::{LifetimeStats, RunStats, draw_lifetime};
+use crate::terminal::{
+    COUNTERMEASURE_PROGRESS_LOSS, COUNTERMEASURE_WAVE, Countermeasure, HackMinigame, HackState,
+    HackTiming, KEYCARD_LOCK_CHANCE, MinigameResult, Terminal, TerminalTier, is_encrypted,
+    link_dependencies,
+};
+use crate::thrown::{CRATE_HIT_RADIUS, ThrownCrate};
+use crate::tile_map::{EntityType, PlateEffect, TILE_SIZE, TileMap, TileType};
+use crate::turret::Turret;
+use crate::ui::{SCALE_RANGE, SCALE_STEP, begin_ui, set_scale, ui_height, ui_scale, ui_width};
+use crate::ultimate::{PULSE_DURATION, ULTIMATE_CHARGE_MAX, Ultimate, UltimateWave};
+use crate::versus::{PVP_DAMAGE, Versus, kill_message};
+use crate::weapon::{AmmoKind, Weapon};
 
 const BOT_HITBOX_SIZE: f32 = TILE_SIZE - 8.0;
 const PLAYER_HITBOX_SIZE: f32 = TILE_SIZE - 8.0;
@@ -22,10 +93,56 @@ const NUM_FLOOR_ITEMS: usize = 15;
 const BOT_PROJECTILE_DAMAGE: i32 = 10;
 const LAVA_DAMAGE_PER_SECOND: i32 = 25;
 const HEALTH_PACK_AMOUNT: i32 = 25;
+const ARMOR_PLATE_AMOUNT: i32 = 50;
 const SPEED_BOOST_DURATION: f32 = 5.0;
 const INVULNERABILITY_DURATION: f32 = 3.0;
+const MAGNET_DURATION: f32 = 10.0;
+const MAGNET_RADIUS: f32 = TILE_SIZE * 5.0; // Pull range for floor items
+const SCANNER_DURATION: f32 = 8.0;
+const SCANNER_RADIUS: f32 = TILE_SIZE * 18.0; // Range at which bots and terminals show up
+const EXPLOSION_DAMAGE: i32 = 40; // Player damage at the center of a blast
+const TRAP_BLAST_RADIUS: f32 = 1.5; // Tiles
+const KAMIKAZE_BLAST_RADIUS: f32 = 1.5; // Tiles
+const KAMIKAZE_CHANCE: f32 = 0.15; // Share of hostile bots that are kamikazes
+const EXPLOSION_KNOCKBACK: i32 = 1; // Tiles the player is shoved away from a blast
+const PELLET_KNOCKBACK: i32 = 1; // Tiles a shotgun pellet shoves a bot it doesn't kill
+const PIT_FALL_DAMAGE: i32 = 20;
+const SHOVE_TILES: i32 = 2;
+const SHOVE_COOLDOWN: f32 = 0.6;
+const ENVIRONMENT_KILL_BONUS: u32 = 5; // Extra score when a shoved bot dies to the map
 const MELEE_SWING_DURATION: f32 = 0.15;
 const MELEE_SWING_ARC: f32 = std::f32::consts::PI * 0.6; // ~108 degrees
+const EXPLORE_RADIUS: i32 = 4; // Tiles revealed around the player for the overview map
+const OVERVIEW_TIME_SCALE: f32 = 0.1; // Simulation slows to a crawl while the overview is open
+const NUM_CAMERAS: usize = 8;
+const LOCAL_ALARM_DURATION: f32 = 8.0; // Seconds bots keep converging on a sighting or noise
+const LOCAL_ALARM_RADIUS: i32 = 12; // Tiles from the alarm within which bots respond
+const CAMERA_HIT_RADIUS: f32 = 8.0;
+const CHECKPOINT_CLEAR_RADIUS: i32 = 7; // Tiles around a checkpoint cleared of hostiles on respawn
+const CHECKPOINT_CLEAR_TIME: f32 = 10.0; // Seconds before the cleared bots return
+const LOCKDOWN_DURATION: f32 = 20.0;
+const LOCKDOWN_SPAWN_INTERVAL: f32 = 3.0; // Seconds between reinforcements during lockdown
+const LOCKDOWN_SPAWN_MIN_DIST: i32 = 10; // Reinforcements appear at least this far away
+const LOCKDOWN_MAX_BOTS: usize = 40;
+const NUM_SHOPS: usize = 2;
+const TELEPORT_WARMUP: f32 = 0.8; // Seconds the player stands rooted before teleporting
+const NUM_SECURITY_PANELS: usize = 3;
+const BOT_HACK_TIME: f32 = 1.5; // Seconds of holding E behind a hostile bot to convert it
+const MAX_ALLIES: usize = 3;
+const ALLY_ENGAGE_RANGE: i32 = 8; // Tiles within which allies go after hostile bots
+const DOOR_HACK_DURATION: f32 = 3.0; // Seconds of holding E to open a bot door for good
+const FRAGMENTS_NEEDED: u32 = 3; // Data fragments to upload for the alternate win
+const NUM_DEAD_END_CHIPS: usize = 4; // Data chips hidden in dead ends (more sit in secret walls)
+const CHIP_MIN_DISTANCE: i32 = 10; // Tiles between the player's start and any chip
+const DATA_CHIP_BONUS: u32 = 100; // Score for collecting every chip in a run
+const CROUCH_VISIBLE_RANGE: i32 = 3; // Tiles within which bots still notice a crouched player
+const GHOST_HACK_BONUS: u32 = 10; // Score for a hack that went unnoticed start to finish
+const GHOST_HACK_XP: u32 = 20;
+const INVENTORY_FULL_MESSAGE: &str = "INVENTORY FULL";
+const OUT_OF_AMMO_MESSAGE: &str = "OUT OF AMMO";
+const ENVIRONMENT_KILL_MESSAGE: &str = "ENVIRONMENTAL KILL - +5 score";
+const ARMOR_BAR_HEIGHT: f32 = 6.0;
+const BOT_AMMO_DROP_CHANCE: f32 = 0.35; // Chance a destroyed hostile bot drops ammo
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum GameScreen {
@@ -33,49 +150,84 @@ pub enum GameScreen {
     Playing,
     Paused,
     Controls,
+    ClassSelect,
+    Stats,
+    Unlocks,
+    Skins,
+    LevelIntro, // Campaign briefing before each level
+    Daily,
+    GameOver,
+    Settings,
+    Lifetime, // Statistics over every finished run
+    Online,   // Host or join an online co-op run
 }
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum MenuItem {
+    Continue,
     Resume,
+    Stats,
     NewGame,
+    Daily,
+    Online,
+    Lifetime,
+    Unlocks,
+    Skins,
     Controls,
-    Audio,
+    Settings,
+    Movement,
     Quit,
 }
 
 impl MenuItem {
-    fn main_menu_items() -> &'static [MenuItem] {
-        &[
+    /// Continue leads the main menu while a saved run is waiting
+    fn main_menu_items(can_continue: bool) -> &'static [MenuItem] {
+        let items = &[
+            MenuItem::Continue,
             MenuItem::NewGame,
+            MenuItem::Daily,
+            MenuItem::Online,
+            MenuItem::Lifetime,
+            MenuItem::Unlocks,
+            MenuItem::Skins,
             MenuItem::Controls,
-            MenuItem::Audio,
+            MenuItem::Settings,
+            MenuItem::Movement,
             MenuItem::Quit,
-        ]
+        ];
+        if can_continue { items } else { &items[1..] }
     }
 
     fn pause_menu_items() -> &'static [MenuItem] {
         &[
             MenuItem::Resume,
+            MenuItem::Stats,
             MenuItem::NewGame,
+            MenuItem::Skins,
             MenuItem::Controls,
-            MenuItem::Audio,
+            MenuItem::Settings,
+            MenuItem::Movement,
             MenuItem::Quit,
         ]
     }
 
-    fn label(&self, audio_enabled: bool) -> &'static str {
+    fn label(&self, movement: MovementMode) -> &'static str {
         match self {
+            MenuItem::Continue => "Continue",
             MenuItem::Resume => "Resume",
+            MenuItem::Stats => "Stats",
             MenuItem::NewGame => "New Game",
+            MenuItem::Daily => "Daily Challenge",
+            MenuItem::Online => "Online Co-op",
+            MenuItem::Lifetime => "Statistics",
+            MenuItem::Unlocks => "Unlocks",
+            MenuItem::Skins => "Skins",
             MenuItem::Controls => "Controls",
-            MenuItem::Audio => {
-                if audio_enabled {
-                    "Audio: ON"
-                } else {
-                    "Audio: OFF"
-                }
-            }
+            MenuItem::Settings => "Settings",
+            MenuItem::Movement => match movement {
+                MovementMode::Grid => "Movement: GRID",
+                MovementMode::Free => "Movement: FREE",
+            },
             MenuItem::Quit => "Quit",
         }
     }
@@ -173,22 +325,85 @@ impl MeleeSwing {
 
 const DAMAGE_FLASH_DURATION: f32 = 0.35;
 const MESSAGE_DURATION: f32 = 3.0;
+const CAMERA_FOLLOW: f32 = 8.0; // How quickly the camera catches up, per second
+const LOOK_AHEAD_SHARE: f32 = 0.35; // Share of the way to the cursor the camera leans
+const LOOK_AHEAD_MAX: f32 = 128.0; // Pixels the camera leans at 100% look-ahead
+const ZOOM_RANGE: (f32, f32) = (0.75, 2.0); // The player's camera zoom, out and in
+const ZOOM_STEP: f32 = 0.25;
 
 pub struct GameState {
     screen: GameScreen,
     menu_selection: usize,
+    // Class for the next new game, and where ESC on the class or skins screen leads
+    player_class: PlayerClass,
+    class_selection: usize,
+    menu_return: GameScreen,
+    life_rules: LifeRules,
+    rules_selection: usize,
+    game_mode: GameMode,
+    mode_selection: usize,
+    difficulty: Difficulty,
+    difficulty_selection: usize,
+    coop: bool, // Two-player co-op, local or online
+    coop_selection: bool,
+    net: Option<NetSession>, // Online co-op link, while hosting, joining or playing
+    online_menu: OnlineMenu,
+    idle_time: f32,          // Seconds on the main menu without a key pressed
+    focus: Focus,            // Whether the window has the player's attention
+    demo: Option<DemoPilot>, // Attract-mode pilot while the demo plays
+    setup_focus: usize,      // New Game screen: 0 is the class cards, then the option rows
+    movement_mode: MovementMode,
+    // Account progress kept on disk between runs
+    profile: Profile,
+    unlock_selection: usize,
+    settings: Settings,
+    settings_selection: usize,
+    rebinding: bool, // Settings screen is waiting for the key of the selected binding
+    skin_selection: usize,
     audio: AudioManager,
+    loot: LootTables,
+    // Authored campaign levels, and the one being played
+    campaign: Campaign,
+    campaign_level: usize,
+    // Endless mode: maps cleared so far plus one, this map's twists, and the
+    // banner shown after descending
+    depth: u32,
+    depth_modifiers: Vec<DepthModifier>,
+    depth_flash: f32,
+    // New Game+: wins carried over so far, the score multiplier they earn,
+    // and the score already banked by earlier loops
+    ng_loop: u32,
+    score_multiplier: u32,
+    banked_score: u32,
+    // Daily challenge being played (or previewed), and the best scores per date
+    daily: Option<Daily>,
+    daily_records: DailyRecords,
+    lifetime: LifetimeStats,
+    debug_console: DebugConsole,
+    commands: CommandRegistry<GameState>,
+    splits: Vec<f32>, // Run clock at each hacked terminal, for the speedrun timer
+    personal_bests: PersonalBests,
     map: TileMap,
-    player: Player,
+    // The lead (first) carries the run's inventory, credits and progression;
+    // a co-op partner follows with their own health, weapons and ammo
+    players: Vec<Player>,
+    revive: Revive,         // A downed player being helped up
+    versus: Option<Versus>, // Kill counts and weapon pads in versus mode
+    stats: RunStats,
     bots: Vec<Bot>,
     projectiles: Vec<Projectile>,
+    particles: Particles,
     melee_swings: Vec<MeleeSwing>,
     items: Vec<Item>,
     score: u32,
     camera_x: f32,
     camera_y: f32,
-    lava_damage_accumulator: f32,
+    view_zoom: f32,                  // Below 1 while the co-op camera zooms out
+    camera_rest: Option<(f32, f32)>, // Smoothed view center before shake; None snaps it
+    zoom: f32,                       // The player's own zoom, on top of the co-op zoom
+    partner_view: Option<View>,      // The partner's own camera while the screen is split
     damage_flash_timer: f32,
+    shake: Shake,
     // Infection tracking
     initial_non_hostile: usize,
     shown_half_infected: bool,
@@ -197,20 +412,117 @@ pub struct GameState {
     message_text: &'static str,
     // Terminal hacking system
     terminals: Vec<Terminal>,
+    turrets: Vec<Turret>,
+    // This map's side objectives, the nests a destroy objective placed, and
+    // the damage taken when the current no-damage streak started
+    objectives: Vec<Objective>,
+    nests: Vec<SpawnerNest>,
+    objective_damage: u32,
+    // Alarm pylons listening for gunfire
+    pylons: Vec<AlarmPylon>,
+    // Random map events: next one due and the running power outage
+    events: EventScheduler,
+    // Thrown grenades and the smoke/EMP clouds they leave behind
+    grenades: Vec<Grenade>,
+    grenade_clouds: Vec<GrenadeCloud>,
+    decoys: Vec<Decoy>,
+    ultimate_waves: Vec<UltimateWave>,
+    shove_cooldown: f32,
+    // A lifted crate held over the head, and crates in flight
+    carrying_crate: bool,
+    thrown_crates: Vec<ThrownCrate>,
+    // Portable teleporter: beacon tile, and warm-up left on a pending jump
+    teleport_beacon: Option<(i32, i32)>,
+    teleport_timer: f32,
     active_hack: Option<usize>,
     hack_alert: bool,
+    // Lives left under the run's rules (None: unlimited), and whether they ran out
+    lives: Option<u32>,
+    game_over: bool,
+    // A run has started and not ended yet; quitting saves it for Continue
+    run_active: bool,
+    can_continue: bool,
+    game_over_selection: usize,
+    // Most recently hacked terminal, where Standard rules respawn the player
+    checkpoint: Option<(i32, i32)>,
+    // What last hurt the player, shown as the cause of death
+    last_hit: &'static str,
+    // Time attack countdown (None when untimed), and the flash after a time bonus
+    time_left: Option<f32>,
+    time_bonus_flash: f32,
+    time_bonus: u32, // Score paid for the seconds left at the win
+    // Tokens banked when this run ended (None while it is still going)
+    run_reward: Option<u32>,
+    // Current hack started crouched and hasn't been noticed yet
+    ghost_hack: bool,
+    ghost_hacks: u32,
     game_won: bool,
+    // Alternate win path: data fragments uploaded at any terminal
+    won_by_upload: bool,
+    // Hidden data chips collected this run, out of the total placed
+    data_chips: u32,
+    data_chips_total: u32,
+    // Cosmetic gold trim around the player, unlocked by finding every chip
+    gold_trim: bool,
+    // XP earned this frame, and revealed tiles not yet worth a point
+    pending_xp: u32,
+    explore_tiles: u32,
+    // Level-up overlay: perks on offer, and further level-ups queued behind it
+    level_up_choices: Option<[Perk; 3]>,
+    pending_level_ups: u32,
+    perk_selection: usize,
     // Hacking sound timer
     hack_blip_timer: f32,
+    // Full-map overview (held with Tab)
+    overview_open: bool,
+    // Inventory overlay (toggled with I), pauses the game while open
+    inventory_open: bool,
+    // Skill tree overlay (toggled with K) and its highlighted branch
+    skill_tree_open: bool,
+    skill_selection: usize,
+    // Security cameras, and the local alarm raised by sightings or hacking noise
+    cameras: Vec<SecurityCamera>,
+    local_alarm: Option<(i32, i32)>,
+    local_alarm_timer: f32,
+    // Alarm lockdown (hack failure or camera sighting), lifted at a security panel
+    lockdown_timer: f32,
+    lockdown_spawn_timer: f32,
+    panels: Vec<SecurityPanel>,
+    // Supply terminals, and the purchase menu (pauses the game while open)
+    shops: Vec<ShopTerminal>,
+    shop_open: bool,
+    shop_selection: usize,
+    // Reprogramming a hostile bot from behind: (bot index, progress)
+    bot_hack: Option<(usize, f32)>,
+    // Hack state of bot-only doors the player has worked on
+    door_hacks: HashMap<(i32, i32), HackState>,
+    // Typewriter report shown after a terminal is hacked
+    console: Option<Console>,
+    // Per-run hack timing (improved by overclock modules)
+    hack_timing: HackTiming,
 }
 
 impl GameState {
-    pub fn new(audio: AudioManager) -> Self {
+    pub fn new(
+        mut settings: Settings,
+        audio: AudioManager,
+        loot: LootTables,
+        campaign: Campaign,
+    ) -> Self {
         let map = TileMap::create_labyrinth(MAP_WIDTH, MAP_HEIGHT);
 
         // Place player at a walkable spot
         let (px, py) = Self::find_walkable_spot(&map);
-        let player = Player::new(px, py);
+        let mut player = Player::new(px, py);
+        let profile = Profile::load();
+        if !profile.has_skin(settings.skin) {
+            settings.skin = Skin::Standard;
+        }
+        player.skin = settings.skin;
+        let difficulty_selection = Difficulty::ALL
+            .iter()
+            .position(|&d| d == settings.difficulty)
+            .unwrap_or(1);
 
         // Add bots at random walkable positions
         let mut bots = Vec::with_capacity(NUM_BOTS + NUM_HOSTILE_BOTS);
@@ -228,46 +540,241 @@ impl GameState {
         let mut items = Vec::new();
         for _ in 0..NUM_FLOOR_ITEMS {
             let (x, y) = Self::find_walkable_spot(&map);
-            items.push(Item::random_floor_item(x, y));
+            items.extend(loot.roll(LootSource::Floor, x, y));
         }
+        let data_chips_total = Self::spawn_data_chips(&map, (px, py), &mut items);
 
         // Count initial non-hostile bots for infection tracking
         let initial_non_hostile = bots.iter().filter(|b| !b.hostile).count();
 
-        // Spawn 1-3 terminals at random floor positions
-        let num_terminals = rand::gen_range(1, 4);
-        let mut terminals = Vec::with_capacity(num_terminals);
-        for _ in 0..num_terminals {
-            let (x, y) = Self::find_walkable_spot(&map);
-            terminals.push(Terminal::new(x, y));
-        }
+        let terminals = Self::spawn_terminals(&map, (px, py));
+        bots.extend(Self::spawn_keycard_carriers(&terminals, &map));
+        let cameras = Self::spawn_cameras(&map);
+        let panels = Self::spawn_panels(&map);
+        let shops = Self::spawn_shops(&map);
 
         Self {
             screen: GameScreen::MainMenu,
             menu_selection: 0,
+            player_class: PlayerClass::Soldier,
+            class_selection: 0,
+            menu_return: GameScreen::MainMenu,
+            life_rules: LifeRules::Standard,
+            rules_selection: 0,
+            game_mode: GameMode::Standard,
+            mode_selection: 0,
+            difficulty: Difficulty::NORMAL,
+            difficulty_selection,
+            coop: false,
+            coop_selection: false,
+            net: None,
+            online_menu: OnlineMenu::default(),
+            idle_time: 0.0,
+            focus: Focus::default(),
+            demo: None,
+            setup_focus: 0,
+            movement_mode: MovementMode::Grid,
+            profile,
+            unlock_selection: 0,
+            settings,
+            settings_selection: 0,
+            rebinding: false,
+            skin_selection: 0,
             audio,
+            loot,
+            campaign,
+            campaign_level: 0,
+            depth: 1,
+            depth_modifiers: Vec::new(),
+            ng_loop: 0,
+            score_multiplier: 1,
+            banked_score: 0,
+            depth_flash: 0.0,
+            daily: None,
+            daily_records: DailyRecords::load(),
+            lifetime: LifetimeStats::load(),
+            debug_console: DebugConsole::new(),
+            commands: Self::debug_commands(),
+            splits: Vec::new(),
+            personal_bests: PersonalBests::load(),
             map,
-            player,
+            players: vec![player],
+            revive: Revive::default(),
+            versus: None,
+            stats: RunStats::new(),
             bots,
             projectiles: Vec::new(),
+            particles: Particles::default(),
             melee_swings: Vec::new(),
             items,
             score: 0,
             camera_x: 0.0,
             camera_y: 0.0,
-            lava_damage_accumulator: 0.0,
+            view_zoom: 1.0,
+            camera_rest: None,
+            zoom: 1.0,
+            partner_view: None,
             damage_flash_timer: 0.0,
+            shake: Shake::default(),
             initial_non_hostile,
             shown_half_infected: false,
             shown_all_infected: false,
             message_timer: 0.0,
             message_text: "",
             terminals,
+            turrets: Vec::new(),
+            objectives: Vec::new(),
+            nests: Vec::new(),
+            objective_damage: 0,
+            pylons: Vec::new(),
+            events: EventScheduler::new(),
+            grenades: Vec::new(),
+            grenade_clouds: Vec::new(),
+            decoys: Vec::new(),
+            ultimate_waves: Vec::new(),
+            shove_cooldown: 0.0,
+            carrying_crate: false,
+            thrown_crates: Vec::new(),
+            teleport_beacon: None,
+            teleport_timer: 0.0,
             active_hack: None,
             hack_alert: false,
+            lives: None,
+            game_over: false,
+            run_active: false,
+            can_continue: save::exists(),
+            game_over_selection: 0,
+            checkpoint: None,
+            last_hit: "",
+            time_left: None,
+            time_bonus_flash: 0.0,
+            time_bonus: 0,
+            run_reward: None,
+            ghost_hack: false,
+            ghost_hacks: 0,
             game_won: false,
+            won_by_upload: false,
+            data_chips: 0,
+            data_chips_total,
+            gold_trim: false,
+            pending_xp: 0,
+            explore_tiles: 0,
+            level_up_choices: None,
+            pending_level_ups: 0,
+            perk_selection: 0,
             hack_blip_timer: 0.0,
+            overview_open: false,
+            inventory_open: false,
+            skill_tree_open: false,
+            skill_selection: 0,
+            cameras,
+            local_alarm: None,
+            local_alarm_timer: 0.0,
+            lockdown_timer: 0.0,
+            lockdown_spawn_timer: 0.0,
+            panels,
+            shops,
+            shop_open: false,
+            shop_selection: 0,
+            bot_hack: None,
+            door_hacks: HashMap::new(),
+            console: None,
+            hack_timing: HackTiming::default(),
+        }
+    }
+
+    /// Spawn 1-3 terminals at random floor positions, some encrypted behind others
+    /// Tiers rise with distance from the player's spawn
+    fn spawn_terminals(map: &TileMap, spawn: (i32, i32)) -> Vec<Terminal> {
+        let num_terminals = rand::gen_range(1, 4);
+        let max_dist = (map.width as f32).hypot(map.height as f32);
+        let mut terminals: Vec<Terminal> = (0..num_terminals)
+            .map(|_| {
+                let (x, y) = Self::find_walkable_spot(map);
+                let mut terminal = Terminal::new(x, y);
+                terminal.locked =
+                    (rand::gen_range(0.0, 1.0) < KEYCARD_LOCK_CHANCE).then(KeycardColor::random);
+                let dist = ((x - spawn.0) as f32).hypot((y - spawn.1) as f32);
+                terminal.tier = TerminalTier::from_distance(dist, max_dist);
+                terminal
+            })
+            .collect();
+        link_dependencies(&mut terminals);
+        terminals
+    }
+
+    /// One elite bot per keycard-locked terminal (terminals use up their
+    /// card), plus one for each door color no terminal carrier covers
+    fn spawn_keycard_carriers(terminals: &[Terminal], map: &TileMap) -> Vec<Bot> {
+        let mut colors: Vec<KeycardColor> = terminals.iter().filter_map(|t| t.locked).collect();
+        for color in map.lock_colors() {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+        colors
+            .into_iter()
+            .map(|color| {
+                let (x, y) = Self::find_walkable_spot(map);
+                Bot::new_keycard_carrier(x, y, color)
+            })
+            .collect()
+    }
+
+    /// Mount cameras on random wall-adjacent floor tiles
+    fn spawn_cameras(map: &TileMap) -> Vec<SecurityCamera> {
+        let mut cameras: Vec<SecurityCamera> = Vec::with_capacity(NUM_CAMERAS);
+        for _ in 0..NUM_CAMERAS * 20 {
+            if cameras.len() >= NUM_CAMERAS {
+                break;
+            }
+            let (x, y) = Self::find_walkable_spot(map);
+            if cameras.iter().any(|c| c.tile_position() == (x, y)) {
+                continue;
+            }
+            if let Some(camera) = SecurityCamera::try_mount(map, x, y) {
+                cameras.push(camera);
+            }
+        }
+        cameras
+    }
+
+    fn spawn_panels(map: &TileMap) -> Vec<SecurityPanel> {
+        (0..NUM_SECURITY_PANELS)
+            .map(|_| {
+                let (x, y) = Self::find_walkable_spot(map);
+                SecurityPanel::new(x, y)
+            })
+            .collect()
+    }
+
+    fn spawn_shops(map: &TileMap) -> Vec<ShopTerminal> {
+        (0..NUM_SHOPS)
+            .map(|_| {
+                let (x, y) = Self::find_walkable_spot(map);
+                ShopTerminal::new(x, y)
+            })
+            .collect()
+    }
+
+    /// Hide data chips in dead ends away from the player's start. Returns the
+    /// run's chip total, counting the ones stashed in secret walls.
+    fn spawn_data_chips(map: &TileMap, player_pos: (i32, i32), items: &mut Vec<Item>) -> u32 {
+        let mut dead_ends: Vec<(i32, i32)> = map
+            .dead_ends()
+            .into_iter()
+            .filter(|&(x, y)| {
+                (x - player_pos.0).abs() + (y - player_pos.1).abs() >= CHIP_MIN_DISTANCE
+                    && !items.iter().any(|i| i.tile_position() == (x, y))
+            })
+            .collect();
+        let mut placed = 0;
+        while placed < NUM_DEAD_END_CHIPS && !dead_ends.is_empty() {
+            let (x, y) = dead_ends.swap_remove(rand::gen_range(0, dead_ends.len()));
+            items.push(Item::new(x, y, ItemType::DataChip));
+            placed += 1;
         }
+        (placed + map.chip_stash_count()) as u32
     }
 
     fn find_walkable_spot(map: &TileMap) -> (i32, i32) {
@@ -294,22 +801,73 @@ impl GameState {
         if is_menu_select() {
             let selected = items[self.menu_selection];
             match selected {
+                MenuItem::Continue => {
+                    self.can_continue = false;
+                    self.menu_selection = 0;
+                    match save::load().map(|text| self.restore_run(&text)) {
+                        Some(Ok(())) => {
+                            save::remove();
+                            self.screen = GameScreen::Playing;
+                        }
+                        Some(Err(err)) => {
+                            eprintln!("Could not continue the saved run: {err}");
+                            self.run_active = false;
+                        }
+                        None => {}
+                    }
+                }
                 MenuItem::Resume => {
                     self.screen = GameScreen::Playing;
                 }
+                MenuItem::Stats => {
+                    self.screen = GameScreen::Stats;
+                }
                 MenuItem::NewGame => {
-                    self.reset_game();
-                    self.screen = GameScreen::Playing;
+                    self.menu_return = self.screen;
+                    self.screen = GameScreen::ClassSelect;
+                }
+                MenuItem::Daily => {
+                    self.daily = Some(Daily::today());
+                    self.screen = GameScreen::Daily;
+                }
+                MenuItem::Online => {
+                    self.online_menu.status.clear();
+                    // Drop what was typed before the screen opened
+                    while get_char_pressed().is_some() {}
+                    self.screen = GameScreen::Online;
+                }
+                MenuItem::Lifetime => {
+                    self.screen = GameScreen::Lifetime;
+                }
+                MenuItem::Unlocks => {
+                    self.unlock_selection = 0;
+                    self.screen = GameScreen::Unlocks;
+                }
+                MenuItem::Skins => {
+                    self.menu_return = self.screen;
+                    self.skin_selection = Skin::ALL
+                        .iter()
+                        .position(|&s| s == self.settings.skin)
+                        .unwrap_or(0);
+                    self.screen = GameScreen::Skins;
                 }
                 MenuItem::Controls => {
                     self.screen = GameScreen::Controls;
                 }
-                MenuItem::Audio => {
-                    self.audio.toggle_mute();
+                MenuItem::Settings => {
+                    self.menu_return = self.screen;
+                    self.settings_selection = 0;
+                    self.rebinding = false;
+                    self.screen = GameScreen::Settings;
                 }
-                MenuItem::Quit => {
-                    std::process::exit(0);
+                MenuItem::Movement => {
+                    self.movement_mode = match self.movement_mode {
+                        MovementMode::Grid => MovementMode::Free,
+                        MovementMode::Free => MovementMode::Grid,
+                    };
+                    self.players[0].movement = self.movement_mode;
                 }
+                MenuItem::Quit => self.quit(),
             }
         }
 
@@ -319,833 +877,5482 @@ impl GameState {
         }
     }
 
-    fn reset_game(&mut self) {
-        // Generate new map
-        self.map = TileMap::create_labyrinth(MAP_WIDTH, MAP_HEIGHT);
-
-        // Reset player
-        let (px, py) = Self::find_walkable_spot(&self.map);
-        self.player = Player::new(px, py);
+    /// Option rows under the class cards on the New Game screen
+    fn setup_rows(&self) -> [SetupRow; 4] {
+        let rules = LifeRules::ALL[self.rules_selection];
+        let mode = GameMode::ALL[self.mode_selection];
+        let difficulty = Difficulty::ALL[self.difficulty_selection];
+        [
+            SetupRow {
+                label: "RULES",
+                value: rules.name(),
+                description: rules.description(),
+                color: rules.color(),
+            },
+            SetupRow {
+                label: "MODE",
+                value: mode.name(),
+                description: mode.description(),
+                color: mode.color(),
+            },
+            SetupRow {
+                label: "DIFFICULTY",
+                value: difficulty.name,
+                description: difficulty.description,
+                color: difficulty.color,
+            },
+            if mode == GameMode::Versus {
+                SetupRow {
+                    label: "PLAYERS",
+                    value: "2 (VERSUS)",
+                    description: "Player 2: arrows move, / fires, , switches weapon",
+                    color: mode.color(),
+                }
+            } else if self.coop_selection {
+                SetupRow {
+                    label: "PLAYERS",
+                    value: "2 (CO-OP)",
+                    description: "Player 2: arrows move, / fires, . hacks and revives, , switches weapon",
+                    color: Color::from_rgba(140, 170, 255, 255),
+                }
+            } else {
+                SetupRow {
+                    label: "PLAYERS",
+                    value: "1",
+                    description: "Solo run",
+                    color: WHITE,
+                }
+            },
+        ]
+    }
 
-        // Reset bots
-        self.bots.clear();
-        for _ in 0..NUM_BOTS {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.bots.push(Bot::new(x, y));
+    /// New Game screen input: W/S moves between the class cards and the
+    /// option rows, A/D changes the focused choice
+    fn update_class_select(&mut self) {
+        if is_menu_up() && self.setup_focus > 0 {
+            self.setup_focus -= 1;
         }
-        for _ in 0..NUM_HOSTILE_BOTS {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.bots.push(Bot::new_hostile(x, y));
+        if is_menu_down() && self.setup_focus < self.setup_rows().len() {
+            self.setup_focus += 1;
         }
-
-        // Reset items
-        self.items.clear();
-        for _ in 0..NUM_FLOOR_ITEMS {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.items.push(Item::random_floor_item(x, y));
+        let delta = is_menu_right() as isize - is_menu_left() as isize;
+        if delta != 0 {
+            let cycle = |index: usize, len: usize| {
+                (index as isize + delta).rem_euclid(len as isize) as usize
+            };
+            match self.setup_focus {
+                0 => {
+                    let last = PlayerClass::ALL.len() as isize - 1;
+                    self.class_selection =
+                        (self.class_selection as isize + delta).clamp(0, last) as usize;
+                }
+                1 => self.rules_selection = cycle(self.rules_selection, LifeRules::ALL.len()),
+                2 => self.mode_selection = cycle(self.mode_selection, GameMode::ALL.len()),
+                3 => {
+                    self.difficulty_selection =
+                        cycle(self.difficulty_selection, Difficulty::ALL.len());
+                }
+                _ => self.coop_selection = !self.coop_selection,
+            }
         }
-
-        // Reset terminals
-        let num_terminals = rand::gen_range(1, 4);
-        self.terminals.clear();
-        for _ in 0..num_terminals {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.terminals.push(Terminal::new(x, y));
+        let class = PlayerClass::ALL[self.class_selection];
+        if is_menu_select() && !self.profile.has_class(class) {
+            self.audio.play_hack_fail();
+        } else if is_menu_select() {
+            self.player_class = class;
+            self.life_rules = LifeRules::ALL[self.rules_selection];
+            self.game_mode = GameMode::ALL[self.mode_selection];
+            self.difficulty = Difficulty::ALL[self.difficulty_selection];
+            self.coop = self.coop_selection;
+            self.daily = None;
+            self.start_run();
+        } else if is_menu_escape() {
+            self.screen = self.menu_return;
         }
-
-        // Reset game state
-        self.projectiles.clear();
-        self.melee_swings.clear();
-        self.score = 0;
-        self.camera_x = 0.0;
-        self.camera_y = 0.0;
-        self.lava_damage_accumulator = 0.0;
-        self.damage_flash_timer = 0.0;
-        self.initial_non_hostile = self.bots.iter().filter(|b| !b.hostile).count();
-        self.shown_half_infected = false;
-        self.shown_all_infected = false;
-        self.message_timer = 0.0;
-        self.message_text = "";
-        self.active_hack = None;
-        self.hack_alert = false;
-        self.game_won = false;
-        self.hack_blip_timer = 0.0;
-    }
-
-    fn random_death_message() -> &'static str {
-        const MESSAGES: &[&str] = &[
-            "YOU DIED! Git gud, scrub.",
-            "WASTED! Maybe try a different career?",
-            "ELIMINATED! The bots send their regards.",
-            "DESTROYED! That was embarrassing.",
-            "TERMINATED! Have you tried not dying?",
-            "FLATLINED! Your score has been reset to match your skill level.",
-            "GAME OVER! The respawn of shame awaits.",
-            "OBLITERATED! Even the bots are laughing.",
-        ];
-        MESSAGES[rand::gen_range(0, MESSAGES.len())]
     }
 
-    fn update_hacking(&mut self, dt: f32) {
-        let player_pos = (self.player.pos.x, self.player.pos.y);
-        let e_held = is_interact_held();
-
-        // Check for E key press to start hacking a new terminal
-        if is_interact_pressed() {
-            for (idx, terminal) in self.terminals.iter_mut().enumerate() {
-                if terminal.state == HackState::Complete {
-                    continue;
-                }
-                if terminal.is_player_nearby(player_pos.0, player_pos.1) {
-                    if terminal.state == HackState::Idle {
-                        // Start hacking
-                        terminal.state = HackState::InProgress {
-                            progress: 0.0,
-                            elapsed: 0.0,
-                        };
-                        self.active_hack = Some(idx);
-                        self.hack_alert = true;
-                        self.hack_blip_timer = 0.0;
-                        self.message_timer = MESSAGE_DURATION;
-                        self.message_text = "HACKING INITIATED - BOTS ALERTED!";
-                        self.audio.play_hack_start();
-                    }
-                    break;
+    /// Unlock screen input: buy with tokens, or switch between owned map themes
+    fn update_unlocks(&mut self) {
+        if is_menu_up() && self.unlock_selection > 0 {
+            self.unlock_selection -= 1;
+        }
+        if is_menu_down() && self.unlock_selection + 1 < Unlock::ALL.len() {
+            self.unlock_selection += 1;
+        }
+        if is_menu_select() {
+            let unlock = Unlock::ALL[self.unlock_selection];
+            if self.profile.is_unlocked(unlock) {
+                if let Some(theme) = unlock.theme() {
+                    self.profile.theme = if self.profile.theme == theme {
+                        MapTheme::Standard
+                    } else {
+                        theme
+                    };
+                    self.profile.save();
                 }
+            } else if self.profile.buy(unlock) {
+                self.audio.play_powerup();
+                self.profile.save();
+            } else {
+                self.audio.play_hack_fail();
             }
+        } else if is_menu_escape() {
+            self.screen = GameScreen::MainMenu;
         }
+    }
 
-        // Update active hack progress (only while E is held, but elapsed always ticks)
-        if let Some(terminal_idx) = self.active_hack {
-            // Check if player is nearby before mutable borrow
-            let player_nearby =
-                self.terminals[terminal_idx].is_player_nearby(player_pos.0, player_pos.1);
-            let terminal = &mut self.terminals[terminal_idx];
-
-            if let HackState::InProgress { progress, elapsed } = &mut terminal.state {
-                // Elapsed time always ticks (real-time window)
-                *elapsed += dt;
-
-                // Progress only when E is held AND player is nearby
-                if e_held && player_nearby {
-                    *progress += dt / HACK_DURATION;
-
-                    // Play periodic blip sound while hacking
-                    self.hack_blip_timer -= dt;
-                    if self.hack_blip_timer <= 0.0 {
-                        self.audio.play_hack_blip();
-                        self.hack_blip_timer = 0.4; // Blip every 0.4 seconds
-                    }
-                }
+    /// Skins screen input: equip any owned skin
+    fn update_skins(&mut self) {
+        if is_menu_left() && self.skin_selection > 0 {
+            self.skin_selection -= 1;
+        }
+        if is_menu_right() && self.skin_selection + 1 < Skin::ALL.len() {
+            self.skin_selection += 1;
+        }
+        if is_menu_select() {
+            let skin = Skin::ALL[self.skin_selection];
+            if self.profile.has_skin(skin) {
+                self.settings.skin = skin;
+                self.players[0].skin = skin;
+                self.settings.save();
+                self.audio.play_pickup();
+            } else {
+                self.audio.play_hack_fail();
+            }
+        } else if is_menu_escape() {
+            self.screen = self.menu_return;
+        }
+    }
 
-                // Check for completion
-                if *progress >= 1.0 {
-                    terminal.state = HackState::Complete;
-                    self.active_hack = None;
+    /// Settings screen input: A/D changes an option, Enter starts rebinding
+    /// the selected action; every change is saved right away
+    fn update_settings(&mut self) {
+        let rows = SettingsRow::all();
+        if self.rebinding {
+            if is_menu_escape() {
+                self.rebinding = false;
+            } else if let Some(key) = get_last_key_pressed().and_then(bindable_key)
+                && let SettingsRow::Key(action) = rows[self.settings_selection]
+            {
+                self.settings.keys.set(action, key);
+                self.settings.save();
+                self.rebinding = false;
+            }
+            return;
+        }
 
-                    // Check if all terminals are hacked
-                    let all_complete = self
-                        .terminals
-                        .iter()
-                        .all(|t| t.state == HackState::Complete);
+        if is_menu_up() && self.settings_selection > 0 {
+            self.settings_selection -= 1;
+        }
+        if is_menu_down() && self.settings_selection + 1 < rows.len() {
+            self.settings_selection += 1;
+        }
+        if is_menu_escape() {
+            self.screen = self.menu_return;
+            return;
+        }
 
-                    if all_complete {
-                        self.game_won = true;
-                        self.hack_alert = false;
-                        self.audio.play_game_win();
-                    } else {
-                        self.message_timer = MESSAGE_DURATION;
-                        self.message_text = "TERMINAL HACKED!";
-                        // Reset alert if no active hack
-                        self.hack_alert = false;
-                        self.audio.play_hack_success();
-                    }
-                }
-                // Check for failure (window expired)
-                else if *elapsed >= HACK_WINDOW {
-                    self.handle_hack_failure(terminal_idx);
-                }
+        let delta = is_menu_right() as i32 - is_menu_left() as i32;
+        let toggle = delta != 0 || is_menu_select();
+        match rows[self.settings_selection] {
+            SettingsRow::Volume(channel) if delta != 0 => {
+                let level = &mut self.settings.volumes[channel as usize];
+                *level = (*level as i32 + delta * VOLUME_STEP as i32).clamp(0, 100) as u32;
+                self.audio.set_levels(self.settings.volumes);
+                self.audio.play_preview(channel);
+            }
+            SettingsRow::LookAhead if delta != 0 => {
+                let level = &mut self.settings.look_ahead;
+                *level = (*level as i32 + delta * LEVEL_STEP as i32).clamp(0, 100) as u32;
+            }
+            SettingsRow::ScreenShake if delta != 0 => {
+                let level = &mut self.settings.screen_shake;
+                *level = (*level as i32 + delta * LEVEL_STEP as i32).clamp(0, 100) as u32;
+            }
+            // Auto below the smallest scale, then 25% steps
+            SettingsRow::UiScale if delta != 0 => {
+                let (lowest, highest) = SCALE_RANGE;
+                self.settings.ui_scale = match self.settings.ui_scale {
+                    None if delta > 0 => Some(lowest),
+                    None => None,
+                    Some(percent) if percent <= lowest && delta < 0 => None,
+                    Some(percent) => Some(
+                        (percent as i32 + delta * SCALE_STEP as i32)
+                            .clamp(lowest as i32, highest as i32) as u32,
+                    ),
+                };
+            }
+            SettingsRow::Difficulty if delta != 0 => {
+                let len = Difficulty::ALL.len() as i32;
+                self.difficulty_selection =
+                    (self.difficulty_selection as i32 + delta).rem_euclid(len) as usize;
+                self.settings.difficulty = Difficulty::ALL[self.difficulty_selection];
+            }
+            SettingsRow::Fullscreen if toggle => {
+                self.settings.fullscreen = !self.settings.fullscreen;
+                set_fullscreen(self.settings.fullscreen);
+            }
+            SettingsRow::SpeedrunTimer if toggle => {
+                self.settings.speedrun_timer = !self.settings.speedrun_timer;
+            }
+            SettingsRow::MuteUnfocused if toggle => {
+                self.settings.mute_unfocused = !self.settings.mute_unfocused;
+            }
+            SettingsRow::PauseUnfocused if toggle => {
+                self.settings.pause_unfocused = !self.settings.pause_unfocused;
+            }
+            SettingsRow::Key(_) if is_menu_select() => {
+                self.rebinding = true;
+                return;
             }
+            _ => return,
         }
+        self.settings.save();
     }
 
-    fn handle_hack_failure(&mut self, terminal_idx: usize) {
-        // Relocate terminal to new position
-        let (new_x, new_y) = Self::find_walkable_spot(&self.map);
-        self.terminals[terminal_idx].relocate(new_x, new_y);
-
-        // Spawn extra hostile bots
-        for _ in 0..FAIL_BOT_SPAWN {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.bots.push(Bot::new_hostile(x, y));
+    /// Permanently earn a skin (achievements and collectibles)
+    fn unlock_skin(&mut self, skin: Skin) {
+        if self.profile.unlock_skin(skin) {
+            self.profile.save();
         }
+    }
 
-        // Clear hacking state
-        self.active_hack = None;
+    /// End the run in victory; time attack turns the seconds left into score
+    fn win_run(&mut self) {
+        self.game_won = true;
+        self.run_active = false;
         self.hack_alert = false;
-
-        // Show mocking message
-        self.message_timer = MESSAGE_DURATION;
-        self.message_text = "HACK FAILED! Terminal relocated. Reinforcements incoming!";
-        self.audio.play_hack_fail();
+        if let Some(time_left) = self.time_left {
+            self.time_bonus = time_bonus_score(time_left);
+            self.time_bonus *= self.score_multiplier;
+            self.score += self.time_bonus;
+        }
+        self.audio.play_game_win();
+        self.bank_run();
+        if self.settings.speedrun_timer && self.demo.is_none() {
+            self.record_splits();
+        }
     }
 
-    fn update_camera(&mut self) {
-        let (px, py) = self.player.pos.center_pixel();
-        let screen_w = screen_width();
-        let screen_h = screen_height();
+    /// Export the won run's splits and keep them if they beat the personal best
+    fn record_splits(&mut self) {
+        let category = category(self.game_mode, &self.difficulty);
+        let total = self.stats.time;
+        speedrun::export(
+            &category,
+            total,
+            &self.splits,
+            self.personal_bests.best(&category),
+        );
+        if self.personal_bests.record(&category, total, &self.splits) {
+            self.personal_bests.save();
+        }
+    }
 
-        // Target camera position (centered on player)
-        let target_x = px - screen_w / 2.0;
-        let target_y = py - screen_h / 2.0;
+    /// Out of lives or out of time
+    fn lose_run(&mut self) {
+        self.game_over = true;
+        self.run_active = false;
+        self.hack_alert = false;
+        self.bank_run();
+        self.game_over_selection = 0;
+        self.screen = GameScreen::GameOver;
+    }
 
-        // Clamp to map bounds
-        let max_x = (self.map.width as f32 * TILE_SIZE - screen_w).max(0.0);
-        let max_y = (self.map.height as f32 * TILE_SIZE - screen_h).max(0.0);
+    /// Game over screen input: play the same setup again, or leave
+    fn update_game_over(&mut self) {
+        if is_menu_up() || is_menu_down() {
+            self.game_over_selection = 1 - self.game_over_selection;
+        }
+        if is_menu_select() && self.game_over_selection == 0 {
+            if self.daily.is_some() {
+                self.start_daily();
+            } else {
+                self.start_run();
+            }
+        } else if is_menu_select() || is_menu_escape() {
+            self.menu_selection = 0;
+            self.screen = GameScreen::MainMenu;
+        }
+    }
 
-        self.camera_x = target_x.clamp(0.0, max_x);
-        self.camera_y = target_y.clamp(0.0, max_y);
+    /// Start a run with the chosen setup
+    fn start_run(&mut self) {
+        self.reset_game();
+        self.screen = self.run_screen();
     }
 
-    fn handle_melee_attack(&mut self, target_x: f32, target_y: f32) {
-        let (px, py) = self.player.pos.center_pixel();
-        let range = self.player.weapon().range;
+    /// First screen of a fresh run; campaigns open with a briefing
+    fn run_screen(&self) -> GameScreen {
+        if self.game_mode == GameMode::Campaign {
+            GameScreen::LevelIntro
+        } else {
+            GameScreen::Playing
+        }
+    }
 
-        // Direction to target
-        let dx = target_x - px;
-        let dy = target_y - py;
-        let len = (dx * dx + dy * dy).sqrt();
-        if len == 0.0 {
+    /// Online screen input: Enter hosts, or joins the typed address; ESC
+    /// backs out and drops a connection still being made
+    fn update_online_menu(&mut self) {
+        if is_menu_escape() {
+            self.net = None;
+            self.screen = GameScreen::MainMenu;
             return;
         }
-        let dx = dx / len;
-        let dy = dy / len;
-
-        // Check bots in melee range in the direction of attack
-        for bot in &mut self.bots {
-            if !bot.alive {
-                continue;
+        if self.net.is_some() {
+            return;
+        }
+        if let Some(address) = self.online_menu.read_line() {
+            let net = if address.is_empty() {
+                NetSession::host()
+            } else {
+                NetSession::join(&address)
+            };
+            match net {
+                Ok(net) => {
+                    self.online_menu.status.clear();
+                    self.net = Some(net);
+                }
+                Err(err) => self.online_menu.status = format!("Network error: {err}"),
             }
-            let (bx, by) = bot.pos.center_pixel();
+        }
+    }
 
-            // Vector from player to bot
-            let to_bot_x = bx - px;
-            let to_bot_y = by - py;
-            let dist = (to_bot_x * to_bot_x + to_bot_y * to_bot_y).sqrt();
+    /// Host: a partner joined, so start a co-op run with the New Game
+    /// screen's choices (the last class played if the chosen one is locked)
+    fn start_online_run(&mut self) {
+        let class = PlayerClass::ALL[self.class_selection];
+        if self.profile.has_class(class) {
+            self.player_class = class;
+        }
+        self.life_rules = LifeRules::ALL[self.rules_selection];
+        self.game_mode = GameMode::ALL[self.mode_selection];
+        self.difficulty = Difficulty::ALL[self.difficulty_selection];
+        self.coop = true;
+        self.daily = None;
+        self.start_run();
+    }
 
-            if dist > range {
-                continue;
+    /// Drive an online session: connect, hand over the run, then step the
+    /// game at the fixed tick with both players' input. Returns true while
+    /// the lockstep runs the game, false when it should update as usual.
+    fn update_online(&mut self, dt: f32) -> bool {
+        let Some(mut net) = self.net.take() else {
+            return false;
+        };
+        match net.poll(dt) {
+            Some(NetEvent::Joined) => {
+                // The host plays on from the snapshot too, so both games
+                // start out exactly alike
+                self.start_online_run();
+                let snapshot = self.save_run();
+                if let Err(err) = self.restore_run(&snapshot) {
+                    eprintln!("Could not snapshot the online run: {err}");
+                }
+                net.send_run(&snapshot);
+            }
+            Some(NetEvent::Run(snapshot)) => {
+                if let Err(err) = self.restore_run(&snapshot) {
+                    self.online_menu.status = format!("Could not load the host's run: {err}");
+                    return false;
+                }
+                self.screen = self.run_screen();
+            }
+            None => {}
+        }
+        if net.is_lost() {
+            if net.is_running() {
+                self.message_text = "PARTNER DISCONNECTED";
+                self.message_timer = MESSAGE_DURATION;
+            } else {
+                self.online_menu.status = "No answer from the host".to_string();
             }
+            return false;
+        }
+        if !net.is_running() {
+            self.net = Some(net);
+            return false;
+        }
 
-            // Check if bot is roughly in the direction of attack
-            let dot = (to_bot_x * dx + to_bot_y * dy) / dist;
-            if dot > 0.5 {
-                bot.kill();
-                self.score += 1;
-                self.audio.play_hit();
+        let local = Keyboard(&self.settings.keys).frame(&self.situation(dt));
+        let local = if net.is_host {
+            local
+        } else {
+            local.as_partner()
+        };
+        for frames in net.advance(dt, local) {
+            input::set_feed(Some(frames));
+            self.step(TICK);
+        }
+        input::set_feed(None);
+        // The session lasts while the run does: the main menu and the New
+        // Game screen show each machine's own profile
+        if !matches!(self.screen, GameScreen::MainMenu | GameScreen::ClassSelect) {
+            self.net = Some(net);
+        }
+        true
+    }
+
+    /// Attract mode: a standard solo run on normal for the pilot to play,
+    /// kept off the save slot
+    fn start_demo(&mut self) {
+        self.player_class = PlayerClass::Soldier;
+        self.life_rules = LifeRules::Standard;
+        self.game_mode = GameMode::Standard;
+        self.difficulty = Difficulty::NORMAL;
+        self.coop = false;
+        self.daily = None;
+        self.start_run();
+        self.run_active = false;
+        self.demo = Some(DemoPilot::new());
+    }
+
+    /// Let the demo pilot play until any key is pressed, the run ends or
+    /// the demo has gone on long enough. Returns true while the demo runs.
+    fn update_demo(&mut self, dt: f32) -> bool {
+        let Some(mut pilot) = self.demo.take() else {
+            return false;
+        };
+        if is_any_key_pressed() || pilot.is_over() || self.screen != GameScreen::Playing {
+            self.screen = GameScreen::MainMenu;
+            self.menu_selection = 0;
+            return true;
+        }
+        let frame = pilot.frame(&self.situation(dt));
+        self.demo = Some(pilot);
+        input::set_feed(Some([frame, InputFrame::default()]));
+        self.step(dt);
+        input::set_feed(None);
+        true
+    }
+
+    /// What a controller gets to see of the run for the lead's next frame
+    fn situation(&self, dt: f32) -> Situation<'_> {
+        let lead = &self.players[0];
+        let countermeasure = self
+            .active_hack
+            .and_then(|idx| self.terminals[idx].countermeasure)
+            .map(|cm| cm.key);
+        Situation {
+            dt,
+            map: &self.map,
+            tile: (lead.pos.x, lead.pos.y),
+            position: lead.pos.center_pixel(),
+            cursor: self.mouse_world(),
+            terminals: (0..self.terminals.len())
+                .filter(|&idx| {
+                    let terminal = &self.terminals[idx];
+                    terminal.state != HackState::Complete
+                        && terminal.locked.is_none()
+                        && !is_encrypted(&self.terminals, idx)
+                })
+                .map(|idx| self.terminals[idx].tile_position())
+                .collect(),
+            hostiles: self
+                .bots
+                .iter()
+                .filter(|bot| bot.alive && bot.hostile)
+                .map(|bot| bot.pos.center_pixel())
+                .collect(),
+            hacking: self.active_hack.is_some(),
+            countermeasure,
+            prompt: self.level_up_choices.is_some(),
+        }
+    }
+
+    /// How tense the run is for the music: hostile bots closing in on the
+    /// lead, a running hack, and a lockdown or sounding pylon
+    fn tension(&self) -> f32 {
+        let (px, py) = self.players[0].pos.center_pixel();
+        let threats = self
+            .bots
+            .iter()
+            .filter(|bot| bot.alive && bot.hostile)
+            .filter(|bot| {
+                let (bx, by) = bot.pos.center_pixel();
+                (bx - px).hypot(by - py) <= THREAT_RANGE
+            })
+            .count();
+        let alarm = self.is_lockdown() || self.pylons.iter().any(|p| p.is_sounding());
+        tension(threats, self.active_hack.is_some(), alarm)
+    }
+
+    /// Tempo of the low-health heartbeat for the lead, while playing
+    fn heartbeat_rate(&self) -> Option<f32> {
+        if self.screen != GameScreen::Playing {
+            return None;
+        }
+        let lead = &self.players[0];
+        heartbeat_rate(lead.health, lead.max_health)
+    }
+
+    /// Level of each ambient loop (by `Ambience`) from the nearest terminal,
+    /// lava and water tile to the listener
+    fn ambience(&self) -> [f32; 3] {
+        let (lx, ly) = self.listener();
+        let mut nearest = [f32::MAX; 3];
+        for terminal in &self.terminals {
+            let dist = (terminal.x - lx).hypot(terminal.y - ly);
+            let machinery = &mut nearest[Ambience::Machinery as usize];
+            *machinery = machinery.min(dist);
+        }
+        let reach = (AMBIENT_RANGE / TILE_SIZE).ceil() as i32;
+        let (cx, cy) = ((lx / TILE_SIZE) as i32, (ly / TILE_SIZE) as i32);
+        for y in (cy - reach).max(0)..=cy + reach {
+            for x in (cx - reach).max(0)..=cx + reach {
+                let ambience = match self.map.get_tile(x as usize, y as usize) {
+                    Some(TileType::Lava) => Ambience::Lava,
+                    Some(TileType::Water) => Ambience::Water,
+                    _ => continue,
+                };
+                let (tx, ty) = (
+                    x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                    y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                );
+                let dist = (tx - lx).hypot(ty - ly);
+                let near = &mut nearest[ambience as usize];
+                *near = near.min(dist);
             }
         }
+        nearest.map(proximity)
     }
 
-    fn create_projectiles(&mut self, target_x: f32, target_y: f32) {
-        let (px, py) = self.player.pos.center_pixel();
-        let weapon = self.player.weapon();
+    /// Pay out the run's tokens to the profile, once, when it ends; the
+    /// demo banks nothing
+    fn bank_run(&mut self) {
+        if self.run_reward.is_some() || self.demo.is_some() {
+            return;
+        }
+        let won = self.game_won;
+        if won {
+            self.unlock_skin(Skin::Android);
+        }
+        if self.game_mode == GameMode::Endless {
+            self.profile.best_depth = self.profile.best_depth.max(self.depth);
+        }
+        if let Some(daily) = &self.daily
+            && self.daily_records.record(&daily.date, self.score)
+        {
+            self.daily_records.save();
+        }
+        self.lifetime.add_run(&self.stats);
+        self.lifetime.save();
+        let reward = run_reward(
+            self.score - self.banked_score,
+            self.stats.terminals_hacked,
+            won,
+        );
+        self.profile.tokens += reward;
+        self.profile.save();
+        self.run_reward = Some(reward);
+    }
 
-        // Calculate base direction
-        let dx = target_x - px;
-        let dy = target_y - py;
-        let base_angle = dy.atan2(dx);
+    /// Whether the win screen offers New Game+: daily runs keep comparable
+    /// scores and endless runs never end in a win
+    fn can_new_game_plus(&self) -> bool {
+        self.game_won && self.daily.is_none() && self.game_mode != GameMode::Endless
+    }
 
-        let pellets = weapon.pellets.max(1);
-        let spread = weapon.spread;
-        let speed = weapon.bullet_speed;
-        let range = weapon.range;
+    /// Carry the won run into a harder loop: the player keeps their gear,
+    /// perks and score, the map grows and one more modifier is added
+    fn start_new_game_plus(&mut self) {
+        self.ng_loop += 1;
+        self.score_multiplier = self.ng_loop + 1;
+        self.depth_modifiers
+            .extend(roll_loop_modifier(&self.depth_modifiers));
+        self.banked_score = self.score;
+
+        self.stats = RunStats::new();
+        self.splits.clear();
+        self.run_active = true;
+        self.last_hit = "";
+        self.time_left = self.game_mode.starting_clock();
+        self.time_bonus_flash = 0.0;
+        self.time_bonus = 0;
+        self.run_reward = None;
+        self.ghost_hacks = 0;
+        self.game_won = false;
+        self.won_by_upload = false;
+        self.campaign_level = 0;
+        self.load_level();
+        self.depth_flash = 2.0;
+        self.screen = if self.game_mode == GameMode::Campaign {
+            GameScreen::LevelIntro
+        } else {
+            GameScreen::Playing
+        };
+    }
 
-        for i in 0..pellets {
-            // Calculate spread angle for this pellet
-            let angle_offset = if pellets > 1 {
-                let spread_range = spread * 2.0;
-                -spread + spread_range * (i as f32 / (pellets - 1) as f32)
-            } else if spread > 0.0 {
-                // Single pellet with spread (machine pistol) - random spread
-                rand::gen_range(-spread, spread)
-            } else {
-                0.0
+    /// Start today's challenge with the fixed setup everyone plays it with
+    fn start_daily(&mut self) {
+        let Some(daily) = &self.daily else {
+            return;
+        };
+        self.player_class = PlayerClass::Soldier;
+        self.life_rules = if daily.has(DailyModifier::Hardcore) {
+            LifeRules::Hardcore
+        } else {
+            LifeRules::Standard
+        };
+        self.game_mode = GameMode::Standard;
+        self.difficulty = Difficulty::NORMAL;
+        self.coop = false;
+
+        // Same seed, same map; everything after that is up to the player
+        rand::srand(daily.seed);
+        self.reset_game();
+        rand::srand(macroquad::miniquad::date::now() as u64);
+        self.screen = GameScreen::Playing;
+    }
+
+    /// Whether the current run is a daily challenge with this modifier
+    fn daily_has(&self, modifier: DailyModifier) -> bool {
+        self.daily.as_ref().is_some_and(|d| d.has(modifier))
+    }
+
+    fn reset_game(&mut self) {
+        // Reset players; a co-op partner or versus rival starts out geared
+        // like the lead
+        self.players = vec![self.new_player(self.settings.skin)];
+        self.versus = None;
+        if self.coop || self.game_mode == GameMode::Versus {
+            let partner = self.new_player(partner_skin(self.settings.skin));
+            self.players.push(partner);
+        }
+        self.stats = RunStats::new();
+        self.splits.clear();
+
+        // Reset run progress
+        self.gold_trim = false;
+        self.pending_xp = 0;
+        self.explore_tiles = 0;
+        self.level_up_choices = None;
+        self.pending_level_ups = 0;
+        self.score = 0;
+        self.damage_flash_timer = 0.0;
+        self.shake.clear();
+        self.lives = self.life_rules.starting_lives();
+        self.game_over = false;
+        self.run_active = true;
+        self.last_hit = "";
+        self.time_left = self.game_mode.starting_clock();
+        self.time_bonus_flash = 0.0;
+        self.time_bonus = 0;
+        self.run_reward = None;
+        self.ghost_hacks = 0;
+        self.game_won = false;
+        self.won_by_upload = false;
+        self.hack_timing = HackTiming::default();
+        self.hack_timing.duration *= self.player_class.hack_duration_mult();
+        self.hack_timing.window *= self.difficulty.hack_window;
+        self.campaign_level = 0;
+        self.depth = 1;
+        self.depth_modifiers.clear();
+        self.depth_flash = 0.0;
+        self.ng_loop = 0;
+        self.score_multiplier = 1;
+        self.banked_score = 0;
+
+        self.load_level();
+    }
+
+    /// A fresh player of the run's class (daily runs skip unlocked gear to
+    /// keep scores comparable)
+    fn new_player(&self, skin: Skin) -> Player {
+        let mut player = Player::new(0, 0);
+        player.apply_class(self.player_class);
+        let starting_weapons = match self.daily {
+            Some(_) => Vec::new(),
+            None => self.profile.starting_weapons(),
+        };
+        for weapon in starting_weapons {
+            if let Some(ammo) = weapon.ammo {
+                player.add_ammo(ammo, ammo.pickup_amount());
+            }
+            player.add_weapon(weapon);
+        }
+        player.movement = self.movement_mode;
+        player.skin = skin;
+        player
+    }
+
+    /// Put the lead on a tile and any co-op partner on a walkable tile beside them
+    fn respawn_players(players: &mut [Player], map: &TileMap, (x, y): (i32, i32)) {
+        players[0].respawn(x, y);
+        let (bx, by) = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .find(|&(bx, by)| map.is_walkable_by(bx, by, EntityType::Player))
+            .unwrap_or((x, y));
+        for partner in &mut players[1..] {
+            partner.respawn(bx, by);
+        }
+    }
+
+    /// Build the map for the next stretch of the run: the current campaign
+    /// level, or a fresh labyrinth. The player keeps their gear.
+    fn load_level(&mut self) {
+        let level = match self.game_mode {
+            GameMode::Campaign => self.campaign.levels.get(self.campaign_level),
+            _ => None,
+        };
+
+        // Generate new map; endless depth and New Game+ loops both grow it
+        let depth = match self.game_mode {
+            GameMode::Endless => self.depth,
+            _ => self.ng_loop + 1,
+        };
+        let (width, height) = map_size((MAP_WIDTH, MAP_HEIGHT), depth);
+        self.map = match level {
+            Some(level) => level.map.clone(),
+            None => TileMap::create_labyrinth(width, height),
+        };
+        let (px, py) = match level {
+            Some(level) => level.start,
+            None => Self::find_walkable_spot(&self.map),
+        };
+        Self::respawn_players(&mut self.players, &self.map, (px, py));
+        self.revive = Revive::default();
+        if self.depth_modifiers.contains(&DepthModifier::LavaFlood) {
+            flood_lava(&mut self.map, (px, py));
+        }
+
+        // Reset bots
+        self.bots.clear();
+        if let Some(level) = level {
+            self.bots
+                .extend(level.bots.iter().map(|&(x, y)| Bot::new(x, y)));
+            self.bots
+                .extend(level.hostiles.iter().map(|&(x, y)| Bot::new_hostile(x, y)));
+            self.bots
+                .extend(level.boss.map(|(x, y)| Bot::new_boss(x, y)));
+        } else {
+            let mut scale = bot_scale(depth);
+            if self.daily_has(DailyModifier::DoubleBots) {
+                scale *= 2.0;
+            }
+            let count = |base: usize| {
+                self.difficulty
+                    .bot_count((base as f32 * scale).round() as usize)
             };
+            let elites = self.depth_modifiers.contains(&DepthModifier::EliteOnly);
+            for _ in 0..count(NUM_BOTS) {
+                let (x, y) = Self::find_walkable_spot(&self.map);
+                self.bots.push(if elites {
+                    Bot::new_elite(x, y)
+                } else {
+                    Bot::new(x, y)
+                });
+            }
+            for _ in 0..count(NUM_HOSTILE_BOTS) {
+                let (x, y) = Self::find_walkable_spot(&self.map);
+                self.bots.push(if elites {
+                    Bot::new_elite(x, y)
+                } else if rand::gen_range(0.0, 1.0) < KAMIKAZE_CHANCE {
+                    Bot::new_kamikaze(x, y)
+                } else {
+                    Bot::new_hostile(x, y)
+                });
+            }
+        }
 
-            let angle = base_angle + angle_offset;
-            let proj_dx = angle.cos();
-            let proj_dy = angle.sin();
+        // Reset items
+        self.loot.drop_rate = self.difficulty.drop_rate;
+        if self.daily_has(DailyModifier::ScarceLoot) {
+            self.loot.drop_rate *= 0.5;
+        }
+        self.loot.only_weapon = self
+            .daily_has(DailyModifier::ShotgunsOnly)
+            .then_some(WeaponKind::Shotgun);
+        self.items.clear();
+        for _ in 0..NUM_FLOOR_ITEMS {
+            let (x, y) = Self::find_walkable_spot(&self.map);
+            self.items.extend(self.loot.roll(LootSource::Floor, x, y));
+        }
+        self.data_chips_total = Self::spawn_data_chips(&self.map, (px, py), &mut self.items);
+        self.data_chips = 0;
 
-            let projectile = Projectile::new_player(px, py, proj_dx, proj_dy, speed, range);
-            self.projectiles.push(projectile);
+        // Reset terminals
+        self.terminals = match level {
+            Some(level) => level
+                .terminals
+                .iter()
+                .map(|&(x, y)| Terminal::new(x, y))
+                .collect(),
+            None => Self::spawn_terminals(&self.map, (px, py)),
+        };
+        let carriers = Self::spawn_keycard_carriers(&self.terminals, &self.map);
+        self.bots.extend(carriers);
+
+        // Roll side objectives; a destroy objective brings its nests
+        self.objectives = Objective::roll();
+        self.nests.clear();
+        if self
+            .objectives
+            .iter()
+            .any(|o| o.kind == ObjectiveKind::DestroyNests)
+        {
+            self.nests = Self::spawn_nests(&self.map, (px, py));
+        }
+        self.objective_damage = self.stats.damage_taken;
+        self.pylons.clear();
+        if AlarmPylon::roll() {
+            let (x, y) = Self::find_spot_away_from(&self.map, (px, py));
+            self.pylons.push(AlarmPylon::new(x, y));
+        }
+        self.events = EventScheduler::new();
+
+        // Reset game state
+        self.turrets.clear();
+        self.grenades.clear();
+        self.grenade_clouds.clear();
+        self.decoys.clear();
+        self.ultimate_waves.clear();
+        self.shove_cooldown = 0.0;
+        self.carrying_crate = false;
+        self.thrown_crates.clear();
+        self.teleport_beacon = None;
+        self.teleport_timer = 0.0;
+        self.projectiles.clear();
+        self.particles.clear();
+        self.melee_swings.clear();
+        self.camera_x = 0.0;
+        self.camera_y = 0.0;
+        self.view_zoom = 1.0;
+        self.camera_rest = None;
+        self.partner_view = None;
+        self.initial_non_hostile = self.bots.iter().filter(|b| !b.hostile).count();
+        self.shown_half_infected = false;
+        self.shown_all_infected = false;
+        self.message_timer = 0.0;
+        self.message_text = "";
+        self.active_hack = None;
+        self.hack_alert = false;
+        self.ghost_hack = false;
+        self.hack_blip_timer = 0.0;
+        self.overview_open = false;
+        self.inventory_open = false;
+        self.skill_tree_open = false;
+        self.cameras = Self::spawn_cameras(&self.map);
+        self.local_alarm = None;
+        self.local_alarm_timer = 0.0;
+        self.lockdown_timer = 0.0;
+        self.lockdown_spawn_timer = 0.0;
+        self.panels = Self::spawn_panels(&self.map);
+        self.shops = Self::spawn_shops(&self.map);
+        self.shop_open = false;
+        self.bot_hack = None;
+        self.door_hacks.clear();
+        self.console = None;
+        self.checkpoint = None;
+
+        if self.game_mode == GameMode::Versus {
+            self.load_arena();
+        }
+    }
+
+    /// Versus: the arena in place of the labyrinth, with nothing on it but
+    /// the two players at their spawns
+    fn load_arena(&mut self) {
+        let (map, versus) = Versus::arena();
+        self.map = map;
+        for (player, &(x, y)) in self.players.iter_mut().zip(&versus.spawns) {
+            player.respawn(x, y);
+        }
+        self.bots.clear();
+        self.items.clear();
+        self.terminals.clear();
+        self.data_chips_total = 0;
+        self.objectives.clear();
+        self.nests.clear();
+        self.pylons.clear();
+        self.cameras.clear();
+        self.panels.clear();
+        self.shops.clear();
+        self.initial_non_hostile = 0;
+        self.versus = Some(versus);
+    }
+
+    /// Versus: a fallen player scores for their rival and is back at
+    /// their spawn with a fresh loadout; empty pads get new weapons on a
+    /// timer
+    fn update_versus(&mut self, dt: f32) {
+        let Some(versus) = &mut self.versus else {
+            return;
+        };
+        let restock = versus.update(dt);
+        let pads = versus.pads.clone();
+        for victim in 0..self.players.len() {
+            if self.players[victim].is_alive() {
+                continue;
+            }
+            let Some(versus) = &mut self.versus else {
+                return;
+            };
+            let killer = 1 - victim;
+            let (x, y) = versus.spawns[victim];
+            if versus.score_kill(killer) {
+                self.game_over = true;
+                self.run_active = false;
+                self.game_over_selection = 0;
+                self.screen = GameScreen::GameOver;
+                self.audio.play_game_win();
+                return;
+            }
+            let skin = self.players[victim].skin;
+            self.players[victim] = self.new_player(skin);
+            self.players[victim].respawn(x, y);
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = kill_message(killer);
+        }
+        if restock {
+            for (x, y) in pads {
+                if !self
+                    .items
+                    .iter()
+                    .any(|i| i.alive && i.tile_position() == (x, y))
+                {
+                    let kind = Versus::roll_weapon();
+                    self.items.push(Item::new(x, y, ItemType::Weapon(kind)));
+                }
+            }
+        }
+    }
+
+    /// Exit the game, saving a run in progress so it can be continued
+    pub fn quit(&self) -> ! {
+        if self.run_active {
+            save::store(&self.save_run());
+        }
+        std::process::exit(0);
+    }
+
+    /// Snapshot the run for the save slot. Shots, grenades and other
+    /// effects in flight are not kept, and a hack in progress is suspended.
+    /// The RNG is re-seeded with a stored seed so a continued run rolls the
+    /// same as it would have.
+    fn save_run(&self) -> String {
+        let seed = u64::from(rand::rand());
+        rand::srand(seed);
+
+        let mut out = SaveWriter::new();
+        out.record(
+            "setup",
+            &[
+                &self.player_class,
+                &self.life_rules,
+                &self.game_mode,
+                &self.difficulty,
+                &self.daily,
+                &self.coop,
+                &seed,
+            ],
+        );
+        out.record(
+            "run",
+            &[
+                &self.campaign_level,
+                &self.depth,
+                &self.depth_modifiers,
+                &self.ng_loop,
+                &self.banked_score,
+                &self.score,
+                &self.lives,
+                &self.time_left,
+                &self.ghost_hacks,
+                &self.data_chips,
+                &self.data_chips_total,
+                &self.gold_trim,
+                &self.explore_tiles,
+                &self.pending_level_ups,
+                &self.hack_timing,
+                &self.stats,
+                &self.splits,
+            ],
+        );
+        out.record(
+            "state",
+            &[
+                &self.initial_non_hostile,
+                &self.shown_half_infected,
+                &self.shown_all_infected,
+                &self.checkpoint,
+                &self.teleport_beacon,
+                &self.local_alarm,
+                &self.local_alarm_timer,
+                &self.lockdown_timer,
+                &self.events,
+                &self.versus,
+            ],
+        );
+        self.map.save(&mut out);
+        for player in &self.players {
+            player.save(&mut out);
+        }
+        out.records("bot", &self.bots);
+        out.records("item", self.items.iter().filter(|item| item.alive));
+        out.records("terminal", &self.terminals);
+        out.records("turret", &self.turrets);
+        out.records("camera", &self.cameras);
+        out.records("panel", &self.panels);
+        out.records("shop", &self.shops);
+        out.records("objective", &self.objectives);
+        out.records("nest", &self.nests);
+        out.records("pylon", &self.pylons);
+        let mut doors: Vec<_> = self
+            .door_hacks
+            .iter()
+            .map(|(&pos, &state)| (pos, state))
+            .collect();
+        doors.sort_by_key(|&(pos, _)| pos);
+        out.records("door", &doors);
+        out.finish()
+    }
+
+    /// Resume a run written by `save_run`
+    fn restore_run(&mut self, text: &str) -> Result<(), String> {
+        let mut save = SaveReader::new(text)?;
+        let mut setup = save.record("setup")?;
+        self.player_class = setup.read()?;
+        self.life_rules = setup.read()?;
+        self.game_mode = setup.read()?;
+        self.difficulty = setup.read()?;
+        self.daily = setup.read()?;
+        self.coop = setup.read()?;
+        let seed = setup.read()?;
+
+        // Everything the save leaves out starts fresh, as on a new run
+        self.reset_game();
+
+        let mut run = save.record("run")?;
+        self.campaign_level = run.read()?;
+        self.depth = run.read()?;
+        self.depth_modifiers = run.read()?;
+        self.ng_loop = run.read()?;
+        self.score_multiplier = self.ng_loop + 1;
+        self.banked_score = run.read()?;
+        self.score = run.read()?;
+        self.lives = run.read()?;
+        self.time_left = run.read()?;
+        self.ghost_hacks = run.read()?;
+        self.data_chips = run.read()?;
+        self.data_chips_total = run.read()?;
+        self.gold_trim = run.read()?;
+        self.explore_tiles = run.read()?;
+        self.pending_level_ups = run.read()?;
+        self.level_up_choices = (self.pending_level_ups > 0).then(Perk::roll_choices);
+        self.hack_timing = run.read()?;
+        self.stats = run.read()?;
+        self.splits = run.read()?;
+
+        let mut state = save.record("state")?;
+        self.initial_non_hostile = state.read()?;
+        self.shown_half_infected = state.read()?;
+        self.shown_all_infected = state.read()?;
+        self.checkpoint = state.read()?;
+        self.teleport_beacon = state.read()?;
+        self.local_alarm = state.read()?;
+        self.local_alarm_timer = state.read()?;
+        self.lockdown_timer = state.read()?;
+        self.events = state.read()?;
+        self.versus = state.read()?;
+
+        self.map = TileMap::restore(&mut save)?;
+        for player in &mut self.players {
+            player.restore(&mut save)?;
+        }
+        self.movement_mode = self.players[0].movement;
+        self.bots = save.read_all("bot")?;
+        self.items = save.read_all("item")?;
+        self.terminals = save.read_all("terminal")?;
+        self.turrets = save.read_all("turret")?;
+        self.cameras = save.read_all("camera")?;
+        self.panels = save.read_all("panel")?;
+        self.shops = save.read_all("shop")?;
+        self.objectives = save.read_all("objective")?;
+        self.nests = save.read_all("nest")?;
+        self.pylons = save.read_all("pylon")?;
+        self.objective_damage = self.stats.damage_taken;
+        self.door_hacks = save.read_all("door")?.into_iter().collect();
+        rand::srand(seed);
+        Ok(())
+    }
+
+    /// Console commands for testing new content
+    fn debug_commands() -> CommandRegistry<GameState> {
+        let mut commands = CommandRegistry::default();
+        commands.register("give", "give <item>", Self::command_give);
+        commands.register(
+            "spawn",
+            "spawn <bot|hostile|elite|kamikaze> [count]",
+            Self::command_spawn,
+        );
+        commands.register("tp", "tp <x> <y>", Self::command_tp);
+        commands.register("god", "god", Self::command_god);
+        commands.register("reveal", "reveal", Self::command_reveal);
+        commands.register("setseed", "setseed <seed>", Self::command_setseed);
+        commands.register("event", "event <outage|surge|supply>", Self::command_event);
+        commands
+    }
+
+    fn run_command(&mut self, line: &str) {
+        // The registry is set aside so its commands can borrow the game
+        let commands = std::mem::take(&mut self.commands);
+        let result = commands.execute(self, line);
+        self.commands = commands;
+        match result {
+            Ok(text) => self.debug_console.print(text, true),
+            Err(err) => self.debug_console.print(err, false),
+        }
+    }
+
+    fn command_give(&mut self, args: &[&str]) -> Result<String, String> {
+        let name = args.join("_");
+        let item = ItemType::from_name(&name).ok_or(format!("no item called '{name}'"))?;
+        if !self.collect_item(item, Rarity::Common) {
+            return Err("no room for it".to_string());
+        }
+        Ok(format!("gave {}", item.name()))
+    }
+
+    fn command_spawn(&mut self, args: &[&str]) -> Result<String, String> {
+        let spawn: fn(i32, i32) -> Bot = match args.first() {
+            Some(&"bot") => Bot::new,
+            Some(&"hostile") => Bot::new_hostile,
+            Some(&"elite") => Bot::new_elite,
+            Some(&"kamikaze") => Bot::new_kamikaze,
+            _ => return Err("unknown bot kind".to_string()),
+        };
+        let count: usize = match args.get(1) {
+            Some(count) => count.parse().map_err(|_| "bad count")?,
+            None => 1,
+        };
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        for _ in 0..count.min(LOCKDOWN_MAX_BOTS) {
+            // Somewhere close to the player, or anywhere if that area is packed
+            let (x, y) = (0..50)
+                .map(|_| (px + rand::gen_range(-6, 7), py + rand::gen_range(-6, 7)))
+                .find(|&(x, y)| {
+                    (x, y) != (px, py) && self.map.is_walkable_by(x, y, EntityType::Bot)
+                })
+                .unwrap_or_else(|| Self::find_walkable_spot(&self.map));
+            self.bots.push(spawn(x, y));
+        }
+        Ok(format!(
+            "spawned {} {}",
+            count.min(LOCKDOWN_MAX_BOTS),
+            args[0]
+        ))
+    }
+
+    fn command_tp(&mut self, args: &[&str]) -> Result<String, String> {
+        let [x, y] = args else {
+            return Err("need two coordinates".to_string());
+        };
+        let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+            return Err("bad coordinates".to_string());
+        };
+        if !self.map.is_walkable_by(x, y, EntityType::Player) {
+            return Err(format!("can't stand at {x},{y}"));
+        }
+        self.players[0].pos = Position::new(x, y);
+        Ok(format!("teleported to {x},{y}"))
+    }
+
+    fn command_god(&mut self, _args: &[&str]) -> Result<String, String> {
+        self.players[0].god_mode = !self.players[0].god_mode;
+        Ok(format!(
+            "god mode {}",
+            if self.players[0].god_mode {
+                "on"
+            } else {
+                "off"
+            }
+        ))
+    }
+
+    fn command_reveal(&mut self, _args: &[&str]) -> Result<String, String> {
+        self.map.reveal_all();
+        Ok("map revealed".to_string())
+    }
+
+    /// Trigger a random map event now
+    fn command_event(&mut self, args: &[&str]) -> Result<String, String> {
+        let event = args
+            .first()
+            .and_then(|key| MapEvent::from_key(key))
+            .ok_or("unknown event")?;
+        self.events.start(event);
+        self.trigger_event(event);
+        Ok(format!("triggered {}", event.key()))
+    }
+
+    /// Restart the run on the map this seed generates
+    fn command_setseed(&mut self, args: &[&str]) -> Result<String, String> {
+        let seed: u64 = args
+            .first()
+            .and_then(|s| s.parse().ok())
+            .ok_or("need a number")?;
+        rand::srand(seed);
+        self.reset_game();
+        Ok(format!("new run from seed {seed}"))
+    }
+
+    /// Map done: every terminal hacked and the boss (if any) down
+    fn level_cleared(&self) -> bool {
+        self.terminals
+            .iter()
+            .all(|t| t.state == HackState::Complete)
+            && !self.bots.iter().any(|b| b.boss && b.alive)
+    }
+
+    /// Move on to the next campaign level (winning after the last one), or
+    /// descend to a fresh endless map
+    fn next_level(&mut self) {
+        if self.game_mode == GameMode::Endless {
+            self.depth += 1;
+            self.depth_modifiers = roll_modifiers(self.depth);
+            self.load_level();
+            self.depth_flash = 2.0;
+            return;
+        }
+        self.campaign_level += 1;
+        if self.campaign_level >= self.campaign.levels.len() {
+            self.win_run();
+            return;
+        }
+        self.load_level();
+        self.screen = GameScreen::LevelIntro;
+    }
+
+    /// Lose a life and respawn, or end the run on the game over screen
+    fn handle_player_death(&mut self) {
+        self.audio.play_player_death();
+        self.stats.deaths += 1;
+        if let Some(lives) = &mut self.lives {
+            *lives = lives.saturating_sub(1);
+            if *lives == 0 {
+                self.lose_run();
+                return;
+            }
+        }
+        let (x, y) = match self.checkpoint {
+            Some(spot) if self.life_rules == LifeRules::Standard => {
+                self.clear_checkpoint(spot);
+                spot
+            }
+            _ => Self::find_walkable_spot(&self.map),
+        };
+        Self::respawn_players(&mut self.players, &self.map, (x, y));
+        self.revive = Revive::default();
+        self.carrying_crate = false;
+        self.teleport_timer = 0.0;
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = match self.lives {
+            // Casual: reset score and show death message
+            None => {
+                self.score = 0;
+                Self::random_death_message()
+            }
+            Some(1) => "LIFE LOST - LAST LIFE!",
+            Some(_) => "LIFE LOST!",
+        };
+    }
+
+    /// Send hostile bots near a checkpoint away for a while so a respawn
+    /// there isn't an instant ambush
+    fn clear_checkpoint(&mut self, (x, y): (i32, i32)) {
+        for bot in &mut self.bots {
+            let dist = (bot.pos.x - x).abs().max((bot.pos.y - y).abs());
+            if bot.alive && bot.hostile && !bot.boss && dist <= CHECKPOINT_CLEAR_RADIUS {
+                bot.despawn(CHECKPOINT_CLEAR_TIME);
+            }
+        }
+    }
+
+    fn random_death_message() -> &'static str {
+        const MESSAGES: &[&str] = &[
+            "YOU DIED! Git gud, scrub.",
+            "WASTED! Maybe try a different career?",
+            "ELIMINATED! The bots send their regards.",
+            "DESTROYED! That was embarrassing.",
+            "TERMINATED! Have you tried not dying?",
+            "FLATLINED! Your score has been reset to match your skill level.",
+            "GAME OVER! The respawn of shame awaits.",
+            "OBLITERATED! Even the bots are laughing.",
+        ];
+        MESSAGES[rand::gen_range(0, MESSAGES.len())]
+    }
+
+    /// Inventory overlay input: select, use or drop the selected item
+    fn update_inventory(&mut self) {
+        let inventory = &mut self.players[0].inventory;
+        if is_menu_up() {
+            inventory.select_prev();
+        }
+        if is_menu_down() {
+            inventory.select_next();
+        }
+
+        if is_interact_pressed(&self.settings.keys)
+            && let Some(item_type) = inventory.selected_item()
+        {
+            self.use_item(item_type);
+        }
+
+        if is_drop_pressed(&self.settings.keys)
+            && self.players[0].inventory.selected_item().is_some()
+        {
+            self.drop_selected_item();
+        }
+    }
+
+    /// Hand the XP earned this frame to the player, queueing a perk pick for
+    /// every level gained
+    fn apply_pending_xp(&mut self) {
+        if self.pending_xp == 0 {
+            return;
+        }
+        let levels = self.players[0].add_xp(std::mem::take(&mut self.pending_xp));
+        if levels == 0 {
+            return;
+        }
+        self.pending_level_ups += levels;
+        self.players[0].skills.points += levels;
+        if self.level_up_choices.is_none() {
+            self.level_up_choices = Some(Perk::roll_choices());
+            self.perk_selection = 0;
+        }
+        self.audio.play_powerup();
+    }
+
+    /// Level-up overlay input: select and take a perk
+    fn update_level_up(&mut self) {
+        let Some(choices) = self.level_up_choices else {
+            return;
+        };
+        if is_menu_up() && self.perk_selection > 0 {
+            self.perk_selection -= 1;
+        }
+        if is_menu_down() && self.perk_selection + 1 < choices.len() {
+            self.perk_selection += 1;
+        }
+        if !(is_interact_pressed(&self.settings.keys) || is_menu_select()) {
+            return;
+        }
+
+        self.players[0].apply_perk(choices[self.perk_selection]);
+        self.pending_level_ups -= 1;
+        self.level_up_choices = (self.pending_level_ups > 0).then(Perk::roll_choices);
+        self.perk_selection = 0;
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "PERK ACQUIRED - skill point ready [K]";
+    }
+
+    /// Skill tree input: pick a branch and unlock its next node
+    fn update_skill_tree(&mut self) {
+        if is_menu_left() && self.skill_selection > 0 {
+            self.skill_selection -= 1;
+        }
+        if is_menu_right() && self.skill_selection + 1 < SkillBranch::ALL.len() {
+            self.skill_selection += 1;
+        }
+        if !(is_interact_pressed(&self.settings.keys) || is_menu_select()) {
+            return;
+        }
+
+        let branch = SkillBranch::ALL[self.skill_selection];
+        self.message_timer = MESSAGE_DURATION;
+        let Some(skill) = self.players[0].skills.unlock_next(branch) else {
+            self.message_text = if self.players[0].skills.points == 0 {
+                "No skill points - level up to earn more"
+            } else {
+                "Branch complete"
+            };
+            return;
+        };
+        // Hacking skills retune the run's hack timing; the rest are read
+        // from the tree where they apply
+        match skill {
+            Skill::FastFingers => self.hack_timing.duration *= 1.0 - FAST_FINGERS_CUT,
+            Skill::LongWindow => self.hack_timing.window *= 1.0 + LONG_WINDOW_BONUS,
+            Skill::CoolHead => self.hack_timing.mistake_penalty *= 1.0 - COOL_HEAD_CUT,
+            _ => {}
+        }
+        self.message_text = "SKILL UNLOCKED";
+        self.audio.play_powerup();
+    }
+
+    /// Shop menu input: select and buy offers
+    fn update_shop(&mut self) {
+        if is_menu_up() && self.shop_selection > 0 {
+            self.shop_selection -= 1;
+        }
+        if is_menu_down() && self.shop_selection + 1 < OFFERS.len() {
+            self.shop_selection += 1;
+        }
+        if !(is_interact_pressed(&self.settings.keys) || is_menu_select()) {
+            return;
+        }
+
+        let offer = &OFFERS[self.shop_selection];
+        self.message_timer = MESSAGE_DURATION;
+        if self.players[0].credits < offer.price {
+            self.message_text = "NOT ENOUGH CREDITS";
+            return;
+        }
+        if let Goods::Item(ItemType::Weapon(kind)) = offer.goods
+            && self.loot.only_weapon.is_some_and(|only| only != kind)
+        {
+            self.message_text = "NOT ALLOWED IN TODAY'S CHALLENGE";
+            return;
+        }
+        let bought = match offer.goods {
+            Goods::Item(item_type) => self.collect_item(item_type, Rarity::Common),
+            Goods::MaxHealth => {
+                self.players[0].max_health += MAX_HEALTH_UPGRADE;
+                self.players[0].heal(MAX_HEALTH_UPGRADE);
+                self.audio.play_powerup();
+                true
+            }
+        };
+        self.message_timer = MESSAGE_DURATION;
+        if bought {
+            self.players[0].credits -= offer.price;
+            self.message_text = "PURCHASE COMPLETE";
+        } else {
+            self.message_text = "No room for that";
+        }
+    }
+
+    /// Apply a consumable from the inventory; key items stay put
+    fn use_item(&mut self, item_type: ItemType) {
+        match item_type {
+            ItemType::SpeedBoost => {
+                self.players[0].speed_boost_timer = SPEED_BOOST_DURATION;
+                self.audio.play_powerup();
+            }
+            ItemType::Invulnerability => {
+                self.players[0].invulnerability_timer = INVULNERABILITY_DURATION;
+                self.audio.play_powerup();
+            }
+            ItemType::Magnet => {
+                self.players[0].magnet_timer = MAGNET_DURATION;
+                self.audio.play_powerup();
+            }
+            ItemType::Scanner => {
+                self.players[0].scanner_timer = SCANNER_DURATION;
+                self.audio.play_powerup();
+            }
+            ItemType::Shield => {
+                self.players[0].activate_shield();
+                self.audio.play_powerup();
+            }
+            ItemType::Decoy => {
+                let (x, y) = (self.players[0].pos.x, self.players[0].pos.y);
+                self.decoys.push(Decoy::new(x, y, self.players[0].facing()));
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "DECOY DEPLOYED";
+                self.audio.play_powerup();
+            }
+            ItemType::Teleporter => {
+                if self.teleport_timer > 0.0 {
+                    return;
+                }
+                self.message_timer = MESSAGE_DURATION;
+                if self.teleport_beacon.is_none() {
+                    // First use only plants the beacon; the teleporter is kept
+                    self.teleport_beacon = Some((self.players[0].pos.x, self.players[0].pos.y));
+                    self.message_text = "BEACON PLACED - use again to teleport back";
+                    self.audio.play_pickup();
+                    return;
+                }
+                self.teleport_timer = TELEPORT_WARMUP;
+                self.message_text = "TELEPORTING...";
+                self.audio.play_powerup();
+            }
+            ItemType::Keycard { .. } => {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "Keycards open doors and terminals of their color";
+                return;
+            }
+            ItemType::DataFragment => {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "Upload fragments with E at any terminal";
+                return;
+            }
+            ItemType::Weapon(_)
+            | ItemType::HealthPack
+            | ItemType::Overclock
+            | ItemType::Ammo(_)
+            | ItemType::ArmorPlate
+            | ItemType::Grenade(_)
+            | ItemType::Backpack
+            | ItemType::DataChip
+            | ItemType::Credits(_) => return,
+        }
+        self.players[0].inventory.take_selected();
+    }
+
+    /// Drop the selected item on a free neighbouring tile
+    fn drop_selected_item(&mut self) {
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        let free = [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .into_iter()
+            .map(|(dx, dy)| (px + dx, py + dy))
+            .find(|&(x, y)| {
+                self.map.is_walkable_by(x, y, EntityType::Player)
+                    && !self.map.is_pit_at(x, y)
+                    && !self.items.iter().any(|i| i.tile_position() == (x, y))
+            });
+        match free {
+            Some((x, y)) => {
+                if let Some(item_type) = self.players[0].inventory.take_selected() {
+                    self.items.push(Item::new(x, y, item_type));
+                }
+            }
+            None => {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "No room to drop that here";
+            }
+        }
+    }
+
+    /// Interact pressed this frame by a player still standing (the co-op
+    /// partner has their own key)
+    fn interact_pressed(&self, idx: usize) -> bool {
+        self.players[idx].is_alive()
+            && if idx == 0 {
+                is_interact_pressed(&self.settings.keys)
+            } else {
+                is_partner_interact_pressed()
+            }
+    }
+
+    fn interact_held(&self, idx: usize) -> bool {
+        self.players[idx].is_alive()
+            && if idx == 0 {
+                is_interact_held(&self.settings.keys)
+            } else {
+                is_partner_interact_held()
+            }
+    }
+
+    /// The player working the terminals: whoever stands at one with interact
+    /// held, or the lead
+    fn hacker(&self) -> usize {
+        (0..self.players.len())
+            .find(|&idx| {
+                let (x, y) = (self.players[idx].pos.x, self.players[idx].pos.y);
+                self.interact_held(idx) && self.terminals.iter().any(|t| t.is_player_nearby(x, y))
+            })
+            .unwrap_or(0)
+    }
+
+    fn update_hacking(&mut self, dt: f32) {
+        let hacker = self.hacker();
+        let player_pos = (self.players[hacker].pos.x, self.players[hacker].pos.y);
+        let e_pressed = self.interact_pressed(hacker);
+        let e_held = self.interact_held(hacker);
+
+        // Enough data fragments: E at any terminal uploads them and wins
+        if e_pressed
+            && self.players[0].inventory.count(ItemType::DataFragment) >= FRAGMENTS_NEEDED
+            && self
+                .terminals
+                .iter()
+                .any(|t| t.is_player_nearby(player_pos.0, player_pos.1))
+        {
+            self.won_by_upload = true;
+            self.win_run();
+            return;
+        }
+
+        // Check for E key press to start hacking a new terminal
+        if e_pressed {
+            for idx in 0..self.terminals.len() {
+                let encrypted = is_encrypted(&self.terminals, idx);
+                let terminal = &mut self.terminals[idx];
+                if terminal.state == HackState::Complete {
+                    continue;
+                }
+                if terminal.is_player_nearby(player_pos.0, player_pos.1) {
+                    if encrypted {
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = "ENCRYPTED - hack the linked terminal first";
+                    } else if let Some(color) = terminal.locked
+                        && !terminal.try_unlock(&mut self.players[0].inventory)
+                    {
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = color.locked_message();
+                    } else if matches!(
+                        terminal.state,
+                        HackState::Idle | HackState::Suspended { .. }
+                    ) {
+                        // Start hacking, or resume with retained progress.
+                        // Crouched hacks stay quiet until the player stands up.
+                        let quiet = self.players[hacker].crouching;
+                        let fresh = terminal.state == HackState::Idle;
+                        self.message_text = match (fresh, quiet) {
+                            (true, false) => "HACKING INITIATED - BOTS ALERTED!",
+                            (false, false) => "HACK RESUMED - BOTS ALERTED!",
+                            (true, true) => "HACKING QUIETLY - STAY LOW",
+                            (false, true) => "HACK RESUMED QUIETLY",
+                        };
+                        self.ghost_hack = quiet && (fresh || self.ghost_hack);
+                        terminal.start_hack();
+                        self.active_hack = Some(idx);
+                        self.hack_alert = !quiet;
+                        self.hack_blip_timer = 0.0;
+                        self.message_timer = MESSAGE_DURATION;
+                        self.audio.play_hack_start();
+                    }
+                    break;
+                }
+            }
+        }
+
+        // Progress not being actively hacked slowly drains away
+        let hacking = e_held && !self.is_minigame_open();
+        for (idx, terminal) in self.terminals.iter_mut().enumerate() {
+            if !(hacking && self.active_hack == Some(idx)) {
+                terminal.decay_progress(dt);
+            }
+        }
+
+        // Update active hack progress (only while E is held, but elapsed always ticks)
+        if let Some(terminal_idx) = self.active_hack {
+            // Check if player is nearby before mutable borrow
+            let player_nearby =
+                self.terminals[terminal_idx].is_player_nearby(player_pos.0, player_pos.1);
+            let timing = self.hack_timing.for_tier(self.terminals[terminal_idx].tier);
+            let terminal = &mut self.terminals[terminal_idx];
+
+            // Walking away suspends the hack, keeping its progress for later
+            if !player_nearby {
+                terminal.suspend();
+                self.active_hack = None;
+                self.hack_alert = false;
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "HACK SUSPENDED - progress retained";
+                return;
+            }
+
+            // Standing up gives a quiet hack away
+            if !self.hack_alert && !self.players[hacker].crouching {
+                self.hack_alert = true;
+                self.ghost_hack = false;
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "STOOD UP - BOTS ALERTED!";
+            }
+
+            let mut countermeasure_failed = false;
+            if let HackState::InProgress { progress, elapsed } = &mut terminal.state {
+                // Elapsed time always ticks (real-time window)
+                *elapsed += dt;
+
+                // Bypass minigame: open with B
+                if terminal.minigame.is_none() && is_bypass_pressed(&self.settings.keys) {
+                    terminal.minigame = Some(HackMinigame::new());
+                } else if let Some(minigame) = &mut terminal.minigame
+                    && let Some(dir) = get_minigame_input()
+                {
+                    match minigame.input(dir) {
+                        MinigameResult::Correct => self.audio.play_hack_blip(),
+                        MinigameResult::Mistake => {
+                            *elapsed += timing.mistake_penalty;
+                            self.audio.play_player_hit();
+                        }
+                        MinigameResult::Solved => {
+                            *progress = 1.0;
+                            terminal.minigame = None;
+                        }
+                    }
+                }
+
+                // Progress only when E is held (player is nearby at this point)
+                if e_held && terminal.minigame.is_none() {
+                    *progress += dt / timing.duration;
+
+                    // Play periodic blip sound while hacking
+                    self.hack_blip_timer -= dt;
+                    if self.hack_blip_timer <= 0.0 {
+                        self.audio.play_hack_blip();
+                        self.hack_blip_timer = 0.4; // Blip every 0.4 seconds
+                    }
+                }
+
+                // Countermeasures: answer the prompt in time or lose progress
+                let answer = terminal
+                    .countermeasure
+                    .as_mut()
+                    .and_then(|cm| cm.update(dt, get_countermeasure_input()));
+                match answer {
+                    Some(true) => {
+                        terminal.countermeasure = None;
+                        self.audio.play_hack_blip();
+                    }
+                    Some(false) => {
+                        terminal.countermeasure = None;
+                        *progress = (*progress - COUNTERMEASURE_PROGRESS_LOSS).max(0.0);
+                        countermeasure_failed = true;
+                    }
+                    None if terminal.countermeasure.is_none()
+                        && e_held
+                        && terminal.minigame.is_none() =>
+                    {
+                        terminal.countermeasure_timer -= dt;
+                        if terminal.countermeasure_timer <= 0.0 {
+                            terminal.countermeasure = Some(Countermeasure::new());
+                            terminal.countermeasure_timer = Countermeasure::next_interval();
+                            self.audio.play_hack_start();
+                        }
+                    }
+                    None => {}
+                }
+
+                // Check for completion
+                if *progress >= 1.0 {
+                    terminal.state = HackState::Complete;
+                    terminal.minigame = None;
+                    terminal.countermeasure = None;
+
+                    self.pending_xp += XP_TERMINAL_HACK;
+                    self.stats.terminals_hacked += 1;
+                    self.splits.push(self.stats.time);
+                    if let Some(time_left) = &mut self.time_left {
+                        *time_left += TIME_ATTACK_BONUS;
+                        self.time_bonus_flash = 1.5;
+                    }
+                    if self.ghost_hack {
+                        self.ghost_hack = false;
+                        self.ghost_hacks += 1;
+                        self.score += GHOST_HACK_BONUS * self.score_multiplier;
+                        self.pending_xp += GHOST_HACK_XP;
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = "GHOST HACK - +10 score";
+                        if self.profile.unlock_skin(Skin::Phantom) {
+                            self.profile.save();
+                        }
+                    }
+
+                    // Hacked terminals turn into allied turrets guarding the area
+                    let (tx, ty) = terminal.tile_position();
+                    self.turrets.push(Turret::new(tx, ty));
+                    self.checkpoint = Some((tx, ty));
+                    self.active_hack = None;
+
+                    // Check if all terminals are hacked
+                    let all_complete = self
+                        .terminals
+                        .iter()
+                        .all(|t| t.state == HackState::Complete);
+
+                    if all_complete && !self.game_mode.has_levels() {
+                        self.win_run();
+                    } else {
+                        let remaining = self
+                            .terminals
+                            .iter()
+                            .filter(|t| t.state != HackState::Complete)
+                            .count();
+                        self.console = Some(Console::hack_report(terminal_idx, remaining));
+                        if all_complete && self.bots.iter().any(|b| b.boss && b.alive) {
+                            self.message_timer = MESSAGE_DURATION;
+                            self.message_text = "ALL NODES DOWN - DESTROY THE BOSS";
+                        }
+                        // Reset alert if no active hack
+                        self.hack_alert = false;
+                        self.audio.play_hack_success();
+                    }
+                }
+                // Check for failure (window expired)
+                else if *elapsed >= timing.window {
+                    self.handle_hack_failure(terminal_idx);
+                }
+            }
+
+            // A missed countermeasure sends an extra alert wave
+            if countermeasure_failed {
+                for _ in 0..COUNTERMEASURE_WAVE {
+                    self.spawn_reinforcement();
+                }
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "COUNTERMEASURE TRIPPED - progress lost!";
+                self.audio.play_player_hit();
+            }
+        }
+    }
+
+    fn is_minigame_open(&self) -> bool {
+        self.active_hack
+            .is_some_and(|idx| self.terminals[idx].minigame.is_some())
+    }
+
+    /// Whether a console, menu or map over the game has the keyboard
+    fn is_overlay_open(&self) -> bool {
+        self.debug_console.open
+            || self.shop_open
+            || self.inventory_open
+            || self.skill_tree_open
+            || self.overview_open
+            || self.level_up_choices.is_some()
+            || self.is_minigame_open()
+    }
+
+    fn handle_hack_failure(&mut self, terminal_idx: usize) {
+        // Relocate terminal to new position
+        let (new_x, new_y) = Self::find_walkable_spot(&self.map);
+        self.terminals[terminal_idx].relocate(new_x, new_y);
+
+        // Spawn extra hostile bots
+        for _ in 0..self.terminals[terminal_idx].tier.fail_bot_spawn() {
+            let (x, y) = Self::find_walkable_spot(&self.map);
+            self.bots.push(Bot::new_hostile(x, y));
+        }
+
+        // Clear hacking state
+        self.active_hack = None;
+        self.hack_alert = false;
+        self.trigger_lockdown();
+
+        // Show mocking message
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "HACK FAILED! Terminal relocated. Reinforcements incoming!";
+        self.audio.play_hack_fail();
+    }
+
+    /// Hostile bot the player could reprogram: standing right behind it,
+    /// undetected, and not busy with a terminal or panel
+    fn bot_hack_target(&self) -> Option<usize> {
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        if self.hack_alert
+            || self
+                .terminals
+                .iter()
+                .any(|t| t.state != HackState::Complete && t.is_player_nearby(px, py))
+            || (self.is_lockdown() && self.panels.iter().any(|p| p.is_player_nearby(px, py)))
+            || self.shops.iter().any(|s| s.is_player_nearby(px, py))
+        {
+            return None;
+        }
+        self.bots
+            .iter()
+            .position(|b| b.alive && b.hostile && !b.boss && b.is_behind(px, py))
+    }
+
+    /// Closed bot-only door next to the player, if E isn't claimed by
+    /// a terminal, panel or bot
+    fn door_hack_target(&self) -> Option<(i32, i32)> {
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        if self
+            .terminals
+            .iter()
+            .any(|t| t.state != HackState::Complete && t.is_player_nearby(px, py))
+            || (self.is_lockdown() && self.panels.iter().any(|p| p.is_player_nearby(px, py)))
+            || self.bot_hack_target().is_some()
+        {
+            return None;
+        }
+        [(0, -1), (0, 1), (-1, 0), (1, 0)]
+            .into_iter()
+            .map(|(dx, dy)| (px + dx, py + dy))
+            .find(|&(x, y)| {
+                x >= 0
+                    && y >= 0
+                    && self.map.get_tile(x as usize, y as usize) == Some(TileType::DoorBot)
+                    && !self.map.is_walkable_by(x, y, EntityType::Player)
+            })
+    }
+
+    /// Bot doors open permanently after a noisy hack; progress is kept per
+    /// door when the player lets go
+    fn update_door_hacking(&mut self, dt: f32) {
+        let target = if self.interact_held(0) {
+            self.door_hack_target()
+        } else {
+            None
+        };
+
+        for (&pos, state) in &mut self.door_hacks {
+            if Some(pos) != target
+                && let HackState::InProgress { progress, elapsed } = *state
+            {
+                *state = HackState::Suspended { progress, elapsed };
+            }
+        }
+
+        let Some(pos) = target else {
+            return;
+        };
+        let state = self.door_hacks.entry(pos).or_insert(HackState::Idle);
+        let (progress, elapsed) = match *state {
+            HackState::InProgress { progress, elapsed } => (progress, elapsed),
+            // Doors make their racket again on every restart
+            HackState::Suspended { progress, .. } => (progress, 0.0),
+            _ => (0.0, 0.0),
+        };
+        if elapsed == 0.0 {
+            // The racket of forcing a door draws nearby hostile bots
+            self.local_alarm = Some(pos);
+            self.local_alarm_timer = LOCAL_ALARM_DURATION;
+            self.audio.play_hack_start();
+        }
+
+        let progress = progress + dt / DOOR_HACK_DURATION;
+        if progress >= 1.0 {
+            *state = HackState::Complete;
+            self.pending_xp += XP_MINOR_HACK;
+            self.map.open_door(pos.0 as usize, pos.1 as usize);
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "DOOR HACKED OPEN";
+            self.audio.play_hack_success();
+        } else {
+            *state = HackState::InProgress {
+                progress,
+                elapsed: elapsed + dt,
+            };
+        }
+    }
+
+    fn update_bot_hacking(&mut self, dt: f32) {
+        let target = if self.interact_held(0) {
+            self.bot_hack_target()
+        } else {
+            None
+        };
+        let Some(idx) = target else {
+            self.bot_hack = None;
+            return;
+        };
+
+        let allies = self.bots.iter().filter(|b| b.alive && b.allied).count();
+        if allies >= MAX_ALLIES {
+            if is_interact_pressed(&self.settings.keys) {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "ALLY LIMIT REACHED";
+            }
+            self.bot_hack = None;
+            return;
+        }
+
+        let progress = match self.bot_hack {
+            Some((i, progress)) if i == idx => progress + dt / BOT_HACK_TIME,
+            _ => dt / BOT_HACK_TIME,
+        };
+        if progress < 1.0 {
+            self.bot_hack = Some((idx, progress));
+            return;
+        }
+
+        self.bot_hack = None;
+        self.advance_objective(ObjectiveKind::RescueBots, 1.0);
+        let bot = &mut self.bots[idx];
+        bot.convert_to_ally();
+        self.pending_xp += XP_MINOR_HACK;
+        if let Some(color) = bot.keycard.take() {
+            // Reprogrammed carriers hand over their keycard
+            let (x, y) = (bot.pos.x, bot.pos.y);
+            self.items
+                .push(Item::new(x, y, ItemType::Keycard { color }));
+        }
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "BOT REPROGRAMMED - ALLY ONLINE";
+        self.audio.play_hack_success();
+    }
+
+    fn is_lockdown(&self) -> bool {
+        self.lockdown_timer > 0.0
+    }
+
+    /// Seal shared doors and start pouring in reinforcements (extends a running lockdown)
+    fn trigger_lockdown(&mut self) {
+        if !self.is_lockdown() {
+            self.lockdown_spawn_timer = LOCKDOWN_SPAWN_INTERVAL;
+        }
+        self.lockdown_timer = LOCKDOWN_DURATION;
+        self.map.set_lockdown(true);
+    }
+
+    fn end_lockdown(&mut self) {
+        self.lockdown_timer = 0.0;
+        self.map.set_lockdown(false);
+    }
+
+    fn update_lockdown(&mut self, dt: f32) {
+        if !self.is_lockdown() {
+            return;
+        }
+
+        // Security panels lift the lockdown when hacked, by either player
+        let hackers: Vec<(i32, i32)> = (0..self.players.len())
+            .filter(|&idx| self.interact_held(idx))
+            .map(|idx| (self.players[idx].pos.x, self.players[idx].pos.y))
+            .collect();
+        let mut overridden = false;
+        for panel in &mut self.panels {
+            let hacking = hackers.iter().any(|&(x, y)| panel.is_player_nearby(x, y));
+            overridden |= panel.update(dt, hacking);
+        }
+        if overridden {
+            self.end_lockdown();
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "LOCKDOWN LIFTED";
+            self.audio.play_hack_success();
+            return;
+        }
+
+        // Reinforcements arrive away from the player
+        self.lockdown_spawn_timer -= dt;
+        if self.lockdown_spawn_timer <= 0.0 {
+            self.lockdown_spawn_timer = LOCKDOWN_SPAWN_INTERVAL;
+            self.spawn_reinforcement();
+        }
+
+        self.lockdown_timer -= dt;
+        if self.lockdown_timer <= 0.0 {
+            self.end_lockdown();
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "Lockdown expired";
+        }
+    }
+
+    /// A walkable spot out of reach of the player's start, if one turns up
+    /// quickly (any walkable spot otherwise)
+    fn find_spot_away_from(map: &TileMap, start: (i32, i32)) -> (i32, i32) {
+        (0..20)
+            .map(|_| Self::find_walkable_spot(map))
+            .find(|&(x, y)| (x - start.0).abs() + (y - start.1).abs() >= LOCKDOWN_SPAWN_MIN_DIST)
+            .unwrap_or_else(|| Self::find_walkable_spot(map))
+    }
+
+    /// Place the nests of a destroy objective away from the player's start
+    fn spawn_nests(map: &TileMap, start: (i32, i32)) -> Vec<SpawnerNest> {
+        (0..NEST_COUNT)
+            .map(|_| {
+                let (x, y) = Self::find_spot_away_from(map, start);
+                SpawnerNest::new(x, y)
+            })
+            .collect()
+    }
+
+    /// Count progress toward this map's objective of a kind, paying out its
+    /// reward when that completes it
+    fn advance_objective(&mut self, kind: ObjectiveKind, amount: f32) {
+        let Some(objective) = self.objectives.iter_mut().find(|o| o.kind == kind) else {
+            return;
+        };
+        if !objective.advance(amount) {
+            return;
+        }
+        self.score += kind.reward() * self.score_multiplier;
+        let (x, y) = (self.players[0].pos.x, self.players[0].pos.y);
+        self.items.extend(self.loot.reward(x, y));
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "OBJECTIVE COMPLETE";
+        self.audio.play_hack_success();
+    }
+
+    /// Nests and sounding pylons bring in hostile bots (up to the bot cap);
+    /// the no-damage streak grows while the player stays unhurt and
+    /// restarts on any hit
+    fn update_objectives(&mut self, dt: f32) {
+        for i in 0..self.nests.len() {
+            if self.nests[i].update(dt) && self.bots.len() < LOCKDOWN_MAX_BOTS {
+                let (x, y) = self.nests[i].tile();
+                self.bots.push(Bot::new_hostile(x, y));
+            }
+        }
+        for i in 0..self.pylons.len() {
+            if self.pylons[i].update(dt) && self.bots.len() < LOCKDOWN_MAX_BOTS {
+                let (x, y) = self.pylons[i].tile();
+                self.bots.push(Bot::new_hostile(x, y));
+            }
+        }
+        if self.stats.damage_taken > self.objective_damage {
+            self.objective_damage = self.stats.damage_taken;
+            for objective in &mut self.objectives {
+                if objective.kind == ObjectiveKind::NoDamage {
+                    objective.reset();
+                }
+            }
+        } else {
+            self.advance_objective(ObjectiveKind::NoDamage, dt);
+        }
+    }
+
+    /// Announce a map event and set it off; the outage itself is timed by
+    /// the scheduler
+    fn trigger_event(&mut self, event: MapEvent) {
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = event.message();
+        match event {
+            MapEvent::PowerOutage => self.audio.play_hack_start(),
+            MapEvent::BotSurge => {
+                for _ in 0..SURGE_BOTS {
+                    self.spawn_reinforcement();
+                }
+                self.audio.play_hack_start();
+            }
+            MapEvent::SupplyDrop => self.drop_supplies(),
+        }
+    }
+
+    /// Stack crates on open floor a few tiles around the player, leaving the
+    /// tiles next to them free
+    fn drop_supplies(&mut self) {
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        let mut dropped = 0;
+        for _ in 0..40 {
+            if dropped == SUPPLY_CRATES {
+                break;
+            }
+            let (x, y) = (px + rand::gen_range(-4, 5), py + rand::gen_range(-4, 5));
+            if ((x - px).abs() <= 1 && (y - py).abs() <= 1) || x < 0 || y < 0 {
+                continue;
+            }
+            if self.map.get_tile(x as usize, y as usize) == Some(TileType::Floor) {
+                self.map.set_tile(x as usize, y as usize, TileType::Crate);
+                dropped += 1;
+            }
+        }
+    }
+
+    /// Spawn a hostile bot out of the player's sight, up to the bot cap
+    fn spawn_reinforcement(&mut self) {
+        if self.bots.len() >= LOCKDOWN_MAX_BOTS {
+            return;
+        }
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        let spot = (0..20)
+            .map(|_| Self::find_walkable_spot(&self.map))
+            .find(|&(x, y)| (x - px).abs() + (y - py).abs() >= LOCKDOWN_SPAWN_MIN_DIST);
+        if let Some((x, y)) = spot {
+            self.bots.push(Bot::new_hostile(x, y));
+        }
+    }
+
+    /// Co-op partner's turn: their own movement, weapon and pickups on the
+    /// second key cluster. They fire the way they face.
+    fn update_partner(&mut self, dt: f32) {
+        let frozen = self.overview_open || self.is_minigame_open();
+        let partner = &mut self.players[1];
+        let up = partner.is_alive();
+        if up && is_partner_weapon_cycle_pressed() {
+            let next = (partner.current_weapon + 1) % partner.weapons.len();
+            partner.switch_weapon(next);
+        }
+        let input = if up && !frozen {
+            get_partner_input()
+        } else {
+            Default::default()
+        };
+        partner.update(dt, input, &self.map);
+        if input.is_moving() {
+            partner.aim_at((input.dy as f32).atan2(input.dx as f32));
+        }
+
+        if up
+            && !frozen
+            && is_partner_shooting()
+            && partner.weapon().can_fire()
+            && partner.has_ammo()
+        {
+            let (px, py) = partner.pos.center_pixel();
+            let (dx, dy) = direction_to_vector(partner.facing());
+            self.fire_weapon(1, px + dx * TILE_SIZE, py + dy * TILE_SIZE);
+        }
+
+        // Health, ammo and armor underfoot; everything else (keys, credits,
+        // gear) waits for the lead, who carries the run's inventory
+        let tile = (self.players[1].pos.x, self.players[1].pos.y);
+        for idx in 0..self.items.len() {
+            let item = &self.items[idx];
+            let underfoot = item.alive && item.tile_position() == tile;
+            if up && underfoot && self.partner_pickup(self.items[idx].item_type) {
+                self.items[idx].alive = false;
+                self.stats.items_collected += 1;
+            }
+        }
+    }
+
+    /// Apply a floor item to the co-op partner; returns false if they have
+    /// no use for it
+    fn partner_pickup(&mut self, item_type: ItemType) -> bool {
+        let partner = &mut self.players[1];
+        let taken = match item_type {
+            ItemType::HealthPack if partner.health < partner.max_health => {
+                partner.heal(HEALTH_PACK_AMOUNT);
+                true
+            }
+            ItemType::Ammo(kind) => partner.add_ammo(kind, kind.pickup_amount()),
+            ItemType::ArmorPlate => partner.add_armor(ARMOR_PLATE_AMOUNT),
+            // Versus rivals both need the arena's weapons
+            ItemType::Weapon(kind) if self.versus.is_some() => {
+                let weapon = kind.to_weapon();
+                let ammo = weapon.ammo;
+                let taken = partner.add_weapon(weapon);
+                if taken && let Some(ammo) = ammo {
+                    partner.add_ammo(ammo, ammo.pickup_amount());
+                }
+                taken
+            }
+            _ => false,
+        };
+        if taken {
+            self.audio.play_pickup();
+        }
+        taken
+    }
+
+    /// A player standing next to their downed partner with interact held
+    /// gets them back up at part health
+    fn update_revive(&mut self, dt: f32) {
+        let Some(downed) = self.players.iter().position(|p| !p.is_alive()) else {
+            self.revive = Revive::default();
+            return;
+        };
+        let reviver = 1 - downed;
+        let (rx, ry) = (self.players[reviver].pos.x, self.players[reviver].pos.y);
+        let (dx, dy) = (self.players[downed].pos.x, self.players[downed].pos.y);
+        let holding = self.interact_held(reviver) && Revive::in_reach((rx, ry), (dx, dy));
+        if self.revive.update(dt, holding) {
+            let player = &mut self.players[downed];
+            player.heal((player.max_health as f32 * REVIVE_HEALTH) as i32);
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "PARTNER REVIVED";
+            self.audio.play_health();
+        }
+    }
+
+    /// Lava burns (speed boost grants immunity), building up per player
+    /// until it makes a whole point of damage
+    fn burn_in_lava(&mut self, idx: usize, dt: f32) {
+        let player = &mut self.players[idx];
+        if !self.map.is_lava_at(player.pos.x, player.pos.y) || player.speed_boost_timer > 0.0 {
+            player.lava_damage = 0.0;
+            return;
+        }
+        player.lava_damage +=
+            LAVA_DAMAGE_PER_SECOND as f32 * player.resistances.scale(DamageType::Fire) * dt;
+        let damage = player.lava_damage as i32;
+        if damage <= 0 {
+            return;
+        }
+        let prev_health = player.health;
+        player.take_damage(damage);
+        player.lava_damage -= damage as f32;
+        let health = player.health;
+        self.last_hit = "Burned in lava";
+        self.stats.damage_taken += (prev_health - health) as u32;
+        // Only start a new flash if the previous one has faded
+        if health < prev_health && self.damage_flash_timer <= 0.0 {
+            self.damage_flash_timer = DAMAGE_FLASH_DURATION;
+            self.audio.play_player_hit();
+        }
+    }
+
+    /// Cursor position in the world, through the camera and its zoom
+    fn mouse_world(&self) -> (f32, f32) {
+        // Online or in the demo, the lead aims where their frame points
+        if let Some(aim) = input::fed_aim() {
+            return aim;
+        }
+        self.lead_view().world_at(get_mouse_position())
+    }
+
+    /// How far the solo camera leans toward where the lead aims: a share of
+    /// the way to the cursor, up to the look-ahead setting
+    fn look_ahead(&self) -> (f32, f32) {
+        let (dx, dy) = match input::fed_aim() {
+            Some((ax, ay)) => {
+                let (px, py) = self.players[0].pos.center_pixel();
+                (ax - px, ay - py)
+            }
+            // From the middle of the screen, so the lean doesn't feed on itself
+            None => {
+                let (mx, my) = get_mouse_position();
+                (
+                    (mx - screen_width() / 2.0) / self.view_zoom,
+                    (my - screen_height() / 2.0) / self.view_zoom,
+                )
+            }
+        };
+        let max = LOOK_AHEAD_MAX * self.settings.look_ahead as f32 / 100.0;
+        let (ax, ay) = (dx * LOOK_AHEAD_SHARE, dy * LOOK_AHEAD_SHARE);
+        let len = ax.hypot(ay);
+        if len > max {
+            (ax / len * max, ay / len * max)
+        } else {
+            (ax, ay)
+        }
+    }
+
+    /// Where world sounds are heard from: the middle of the lead's view
+    fn listener(&self) -> (f32, f32) {
+        let view = self.lead_view();
+        let (w, h) = view.size();
+        (view.x + w / 2.0, view.y + h / 2.0)
+    }
+
+    /// The lead's camera: the whole screen, or its left half while split
+    fn lead_view(&self) -> View {
+        let screen = (screen_width(), screen_height());
+        let rect = if self.partner_view.is_some() {
+            split_rects(screen)[0]
+        } else {
+            Rect::new(0.0, 0.0, screen.0, screen.1)
+        };
+        View {
+            x: self.camera_x,
+            y: self.camera_y,
+            zoom: self.view_zoom,
+            rect,
+        }
+    }
+
+    /// Top-left world pixel of every lava tile in the lead's view
+    fn lava_in_view(&self) -> Vec<(f32, f32)> {
+        let view = self.lead_view();
+        let (w, h) = view.size();
+        let (xs, ys) = self.map.visible_range(view.x, view.y, w, h);
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| self.map.get_tile(x, y) == Some(TileType::Lava))
+            .map(|(x, y)| (x as f32 * TILE_SIZE, y as f32 * TILE_SIZE))
+            .collect()
+    }
+
+    fn update_camera(&mut self, dt: f32) {
+        // Solo: centered on the player, leaning toward the cursor. Co-op:
+        // between both players, zooming out as they spread apart, and
+        // splitting the screen in two once they can't share it. Online,
+        // each machine has a screen of its own to share.
+        let screen = (screen_width(), screen_height());
+        let full = Rect::new(0.0, 0.0, screen.0, screen.1);
+        let bounds = (
+            self.map.width as f32 * TILE_SIZE,
+            self.map.height as f32 * TILE_SIZE,
+        );
+        // The world area the screen would show at the player's zoom
+        let reach = (screen.0 / self.zoom, screen.1 / self.zoom);
+        self.partner_view = None;
+        let mut view = match self.players.as_slice() {
+            [lead, partner]
+                if self.net.is_none()
+                    && needs_split(lead.pos.center_pixel(), partner.pos.center_pixel(), reach) =>
+            {
+                let [left, right] = split_rects(screen);
+                self.partner_view = Some(View::centered(
+                    partner.pos.center_pixel(),
+                    self.zoom,
+                    right,
+                    bounds,
+                ));
+                View::centered(lead.pos.center_pixel(), self.zoom, left, bounds)
+            }
+            [lead, partner] => {
+                let (focus, zoom) =
+                    shared_view(lead.pos.center_pixel(), partner.pos.center_pixel(), reach);
+                View::centered(focus, zoom * self.zoom, full, bounds)
+            }
+            _ => {
+                let (px, py) = self.players[0].pos.center_pixel();
+                let (ax, ay) = self.look_ahead();
+                View::centered((px + ax, py + ay), self.zoom, full, bounds)
+            }
+        };
+
+        // Glide the view's center toward where it should be rather than
+        // jumping there; a zoom change then zooms about the center
+        let (w, h) = view.size();
+        let center = (view.x + w / 2.0, view.y + h / 2.0);
+        let center = match self.camera_rest {
+            Some((x, y)) => {
+                let follow = 1.0 - (-CAMERA_FOLLOW * dt).exp();
+                (x + (center.0 - x) * follow, y + (center.1 - y) * follow)
+            }
+            None => center,
+        };
+        view.x = center.0 - w / 2.0;
+        view.y = center.1 - h / 2.0;
+        view = view.clamped(bounds);
+        self.camera_rest = Some((view.x + w / 2.0, view.y + h / 2.0));
+
+        // Shake jolts the view around the player, fading as it runs out
+        let (shake_x, shake_y) = self.shake.offset(self.settings.screen_shake as f32 / 100.0);
+        view.x += shake_x;
+        view.y += shake_y;
+        self.camera_x = view.x;
+        self.camera_y = view.y;
+        self.view_zoom = view.zoom;
+    }
+
+    fn handle_melee_attack(&mut self, attacker: usize, target_x: f32, target_y: f32) {
+        let player = &self.players[attacker];
+        let (px, py) = player.pos.center_pixel();
+        let range = player.weapon().range * player.skills.melee_range_mult();
+        let arc_cos = player.skills.melee_arc_cos();
+
+        // Direction to target
+        let dx = target_x - px;
+        let dy = target_y - py;
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return;
+        }
+        let dx = dx / len;
+        let dy = dy / len;
+
+        // Check bots in melee range in the direction of attack
+        let melee_weapon = self.players[attacker].weapon().name;
+        for bot in &mut self.bots {
+            if !bot.alive || bot.allied {
+                continue;
+            }
+            let (bx, by) = bot.pos.center_pixel();
+
+            // Vector from player to bot
+            let to_bot_x = bx - px;
+            let to_bot_y = by - py;
+            let dist = (to_bot_x * to_bot_x + to_bot_y * to_bot_y).sqrt();
+
+            if dist > range {
+                continue;
+            }
+
+            // Check if bot is roughly in the direction of attack
+            let dot = (to_bot_x * dx + to_bot_y * dy) / dist;
+            if dot > arc_cos {
+                if bot.absorb_hit() {
+                    continue;
+                }
+                bot.kill();
+                self.stats.add_kill(melee_weapon);
+                self.score += self.score_multiplier;
+                self.pending_xp += kill_xp(bot.hostile);
+                self.players[attacker].ultimate.add_kill(bot.hostile);
+                self.audio.play_hit();
+            }
+        }
+
+        // Cameras in reach are smashed too
+        for camera in self.cameras.iter_mut().filter(|c| c.alive) {
+            let (to_x, to_y) = (camera.x - px, camera.y - py);
+            let dist = to_x.hypot(to_y);
+            if dist <= range && (to_x * dx + to_y * dy) / dist > 0.5 {
+                camera.alive = false;
+                self.audio.play_hit();
+            }
+        }
+
+        // Versus: so is the rival
+        if self.versus.is_some() {
+            let rival = &mut self.players[1 - attacker];
+            let (rx, ry) = rival.pos.center_pixel();
+            let (to_x, to_y) = (rx - px, ry - py);
+            let dist = to_x.hypot(to_y);
+            if rival.is_alive() && dist <= range && (to_x * dx + to_y * dy) / dist > arc_cos {
+                let damage = rival.absorb_with_shield(PVP_DAMAGE);
+                rival.take_hit(damage, DamageType::Ballistic);
+                self.last_hit = "Cut down by the rival";
+                self.audio.play_player_hit();
+            }
+        }
+    }
+
+    /// Count down a pending teleport and jump to the beacon when it is done.
+    /// The player stays exposed for the whole warm-up.
+    fn update_teleport(&mut self, dt: f32) {
+        if self.teleport_timer <= 0.0 {
+            return;
+        }
+        self.teleport_timer -= dt;
+        if self.teleport_timer > 0.0 {
+            return;
+        }
+        if let Some((x, y)) = self.teleport_beacon.take() {
+            self.players[0].pos = Position::new(x, y);
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "TELEPORTED";
+            self.audio.play_powerup();
+        }
+    }
+
+    /// Walking into a locked door opens it if the player carries a keycard
+    /// of its color. Doors only check the card, so it is kept.
+    fn try_open_locked_door(&mut self, input: MoveDirection) {
+        if !input.is_moving() || !self.players[0].is_settled() {
+            return;
+        }
+        let (x, y) = (
+            self.players[0].pos.x + input.dx,
+            self.players[0].pos.y + input.dy,
+        );
+        let Some(color) = self.map.lock_at(x, y) else {
+            return;
+        };
+        if self.players[0].inventory.count(ItemType::Keycard { color }) > 0 {
+            self.map.unlock_door(x as usize, y as usize);
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "ACCESS GRANTED - DOOR UNLOCKED";
+            self.audio.play_pickup();
+        } else if self.message_timer <= 0.0 || self.message_text != color.locked_message() {
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = color.locked_message();
+        }
+    }
+
+    /// Q: unleash the chosen ultimate with a full meter, otherwise switch
+    /// to the next one
+    fn use_ultimate(&mut self) {
+        let Some(ultimate) = self.players[0].ultimate.spend() else {
+            self.players[0].ultimate.cycle();
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = match self.players[0].ultimate.chosen {
+                Ultimate::Shockwave => "ULTIMATE: SHOCKWAVE",
+                Ultimate::ConversionPulse => "ULTIMATE: CONVERSION PULSE",
+                Ultimate::FullHeal => "ULTIMATE: FULL HEAL",
+            };
+            return;
+        };
+        let (px, py) = self.players[0].pos.center_pixel();
+        let radius = ultimate.radius() * TILE_SIZE;
+        let in_reach = |bot: &Bot| {
+            let (bx, by) = bot.pos.center_pixel();
+            bot.alive && bot.hostile && (bx - px).hypot(by - py) <= radius
+        };
+        match ultimate {
+            Ultimate::Shockwave => {
+                for bot in self.bots.iter_mut().filter(|b| in_reach(b)) {
+                    if bot.absorb_hit() {
+                        continue;
+                    }
+                    bot.kill();
+                    self.stats.add_kill("Shockwave");
+                    self.score += 3 * self.score_multiplier;
+                    self.pending_xp += kill_xp(true);
+                }
+                self.audio.play_hit();
+            }
+            Ultimate::ConversionPulse => {
+                for bot in self.bots.iter_mut().filter(|b| in_reach(b) && !b.boss) {
+                    bot.convert_for(PULSE_DURATION);
+                }
+                self.audio.play_hack_success();
+            }
+            Ultimate::FullHeal => {
+                self.players[0].health = self.players[0].max_health;
+                self.audio.play_health();
+            }
+        }
+        self.ultimate_waves
+            .push(UltimateWave::new(px, py, ultimate));
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = match ultimate {
+            Ultimate::Shockwave => "SHOCKWAVE!",
+            Ultimate::ConversionPulse => "CONVERSION PULSE - bots fight for you!",
+            Ultimate::FullHeal => "FULL HEAL!",
+        };
+    }
+
+    /// Pull the player out of a pit onto the nearest safe tile, with damage
+    fn handle_player_fall(&mut self, idx: usize) {
+        let player = &mut self.players[idx];
+        let (x, y) = self
+            .map
+            .nearest_walkable(player.pos.x, player.pos.y, EntityType::Player)
+            .unwrap_or_else(|| Self::find_walkable_spot(&self.map));
+        player.pos = Position::new(x, y);
+        let prev_health = player.health;
+        player.take_damage(player.skills.fall_damage(PIT_FALL_DAMAGE));
+        let health = player.health;
+        self.last_hit = "Fell into a pit";
+        self.stats.damage_taken += (prev_health - health) as u32;
+        if health < prev_health {
+            self.damage_flash_timer = DAMAGE_FLASH_DURATION;
+            self.shake.add(TRAUMA_HIT);
+            self.audio.play_player_hit();
+        }
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "You fell into a pit!";
+    }
+
+    /// Damage a destructible tile, rolling for an item drop if it breaks
+    fn damage_tile_at(&mut self, tile_x: i32, tile_y: i32) {
+        if !self.map.is_destructible_at(tile_x, tile_y) {
+            return;
+        }
+        let tile = self.map.get_tile(tile_x as usize, tile_y as usize);
+        let is_crate = tile == Some(TileType::Crate);
+        let has_cache = self.map.has_cache_at(tile_x, tile_y);
+        let has_chip = self.map.has_chip_at(tile_x, tile_y);
+        let destroyed = self.map.damage_tile(tile_x as usize, tile_y as usize);
+        if is_crate {
+            let (x, y) = (
+                (tile_x as f32 + 0.5) * TILE_SIZE,
+                (tile_y as f32 + 0.5) * TILE_SIZE,
+            );
+            self.particles.burst(Burst::Splinters, x, y);
+        }
+        if destroyed && has_cache {
+            self.items
+                .push(Item::new(tile_x, tile_y, ItemType::DataFragment));
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "SECRET CACHE FOUND!";
+        } else if destroyed && has_chip {
+            self.items
+                .push(Item::new(tile_x, tile_y, ItemType::DataChip));
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "SECRET STASH FOUND!";
+        } else if destroyed {
+            // Roll for item drop
+            let source = if is_crate {
+                LootSource::Crate
+            } else {
+                LootSource::Wall
+            };
+            self.items.extend(self.loot.roll(source, tile_x, tile_y));
+        }
+    }
+
+    /// Detonate an explosion at a world pixel position. Kills bots and breaks
+    /// destructibles in the blast, hurts the player with falloff, and leaves
+    /// scorched floor and the occasional crater behind.
+    /// Push the adjacent bot the player faces (or the closest to it) back a
+    /// couple of tiles. No damage - the map does the rest.
+    fn try_shove(&mut self) {
+        if self.shove_cooldown > 0.0 {
+            return;
+        }
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        let (fx, fy) = direction_to_vector(self.players[0].facing());
+        let alignment = |bot: &Bot| (bot.pos.x - px) as f32 * fx + (bot.pos.y - py) as f32 * fy;
+        let target = self
+            .bots
+            .iter_mut()
+            .filter(|b| {
+                let (dx, dy) = (b.pos.x - px, b.pos.y - py);
+                b.alive && (dx, dy) != (0, 0) && dx.abs() <= 1 && dy.abs() <= 1
+            })
+            .max_by(|a, b| alignment(a).total_cmp(&alignment(b)));
+        let Some(bot) = target else {
+            return;
+        };
+        let (dx, dy) = (bot.pos.x - px, bot.pos.y - py);
+        bot.shove(dx, dy, SHOVE_TILES, &self.map);
+        self.shove_cooldown = SHOVE_COOLDOWN;
+        self.audio.play_shove();
+    }
+
+    /// Blast at a world pixel position, shared by frag grenades and trap
+    /// plates: kills bots, breaks targets and walls, shoves and hurts
+    /// players, and scorches the floor within the radius
+    fn detonate(&mut self, x: f32, y: f32, radius_tiles: f32) {
+        let radius = radius_tiles * TILE_SIZE;
+        self.shake.add(TRAUMA_EXPLOSION);
+        self.particles.burst(Burst::Explosion, x, y);
+        let (cx, cy) = ((x / TILE_SIZE) as i32, (y / TILE_SIZE) as i32);
+
+        for bot in &mut self.bots {
+            if !bot.alive {
+                continue;
+            }
+            let (bx, by) = bot.pos.center_pixel();
+            if ((bx - x).powi(2) + (by - y).powi(2)).sqrt() <= radius
+                && !bot.shrugs_off(DamageType::Explosive)
+                && !bot.absorb_hit()
+            {
+                self.stats.add_kill("Explosions");
+                self.score += (if bot.hostile { 3 } else { 1 }) * self.score_multiplier;
+                // Shoved onto a trap plate, or into a blast
+                if bot.was_shoved() {
+                    self.score += ENVIRONMENT_KILL_BONUS * self.score_multiplier;
+                    self.message_timer = MESSAGE_DURATION;
+                    self.message_text = ENVIRONMENT_KILL_MESSAGE;
+                }
+                self.pending_xp += kill_xp(bot.hostile);
+                self.players[0].ultimate.add_kill(bot.hostile);
+                bot.kill();
+            }
+        }
+        for camera in &mut self.cameras {
+            if (camera.x - x).hypot(camera.y - y) <= radius {
+                camera.alive = false;
+            }
+        }
+        for nest in &mut self.nests {
+            if (nest.x - x).hypot(nest.y - y) <= radius {
+                nest.hit();
+            }
+        }
+        for pylon in &mut self.pylons {
+            if (pylon.x - x).hypot(pylon.y - y) <= radius {
+                pylon.hit();
+            }
+        }
+        self.clear_destroyed_targets();
+
+        for player in &mut self.players {
+            let (px, py) = player.pos.center_pixel();
+            let player_dist = ((px - x).powi(2) + (py - y).powi(2)).sqrt();
+            if player_dist > radius {
+                continue;
+            }
+            // Shove the player away from the blast along the dominant axis
+            let (dx, dy) = if (px - x).abs() >= (py - y).abs() {
+                (((px - x).signum()) as i32, 0)
+            } else {
+                (0, ((py - y).signum()) as i32)
+            };
+            player
+                .pos
+                .knockback(dx, dy, EXPLOSION_KNOCKBACK, &self.map, EntityType::Player);
+
+            let falloff = 1.0 - player_dist / radius;
+            let prev_health = player.health;
+            player.take_hit(
+                (EXPLOSION_DAMAGE as f32 * falloff).ceil() as i32,
+                DamageType::Explosive,
+            );
+            self.last_hit = "Caught in an explosion";
+            self.stats.damage_taken += (prev_health - player.health) as u32;
+            if player.health < prev_health {
+                self.damage_flash_timer = DAMAGE_FLASH_DURATION;
+                self.shake.add(TRAUMA_HIT);
+                self.audio.play_player_hit();
+            }
+        }
+
+        let r = radius_tiles.ceil() as i32;
+        for ty in (cy - r)..=(cy + r) {
+            for tx in (cx - r)..=(cx + r) {
+                if ((tx - cx).pow(2) + (ty - cy).pow(2)) as f32 <= radius_tiles * radius_tiles {
+                    self.damage_tile_at(tx, ty);
+                }
+            }
+        }
+
+        self.map.scorch(cx, cy, radius_tiles);
+        self.audio
+            .play_at(WorldSound::Explosion, (x, y), self.listener());
+    }
+
+    /// Remove nests and pylons shot apart; nests count toward the destroy
+    /// objective, and a destroyed pylon ends its horde
+    fn clear_destroyed_targets(&mut self) {
+        let before = self.nests.len();
+        self.nests.retain(|n| !n.is_destroyed());
+        let destroyed = before - self.nests.len();
+        if destroyed > 0 {
+            self.advance_objective(ObjectiveKind::DestroyNests, destroyed as f32);
+        }
+
+        if self.pylons.iter().any(|p| p.is_destroyed()) {
+            self.pylons.retain(|p| !p.is_destroyed());
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "ALARM PYLON DESTROYED";
+        }
+    }
+
+    /// Add a weapon to the player's slots. Fresh weapons come with a clip of
+    /// ammo; ones the player dropped earlier don't. Returns false when every
+    /// slot is taken.
+    fn take_weapon(&mut self, weapon: Weapon, fresh: bool) -> bool {
+        let ammo = weapon.ammo;
+        if !self.players[0].add_weapon(weapon) {
+            return false;
+        }
+        if fresh && let Some(ammo) = ammo {
+            self.players[0].add_ammo(ammo, ammo.pickup_amount());
+        }
+        self.audio.play_pickup();
+        true
+    }
+
+    /// Whether taking a weapon of this kind means giving up the held one
+    fn needs_swap(&self, kind: WeaponKind) -> bool {
+        !self.players[0].has_weapon(kind.name())
+            && self.players[0].firearm_count() >= self.players[0].weapon_slots
+    }
+
+    /// Index of a weapon lying underfoot; weapons wait for E instead of
+    /// being picked up on contact
+    fn weapon_item_underfoot(&self) -> Option<usize> {
+        let player_tile = (self.players[0].pos.x, self.players[0].pos.y);
+        self.items.iter().position(|item| {
+            item.alive
+                && item.tile_position() == player_tile
+                && matches!(item.item_type, ItemType::Weapon(_))
+        })
+    }
+
+    /// Crate next to the player, preferring the one in the facing direction
+    fn adjacent_crate(&self) -> Option<(i32, i32)> {
+        let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+        let (fx, fy) = direction_to_vector(self.players[0].facing());
+        let ahead = (px + fx.round() as i32, py + fy.round() as i32);
+        let is_crate = |(x, y): (i32, i32)| {
+            x >= 0 && y >= 0 && self.map.get_tile(x as usize, y as usize) == Some(TileType::Crate)
+        };
+        if is_crate(ahead) {
+            return Some(ahead);
+        }
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (px + dx, py + dy)))
+            .find(|&tile| is_crate(tile))
+    }
+
+    /// Throw the lifted crate toward the cursor
+    fn throw_crate(&mut self) {
+        let (px, py) = self.players[0].pos.center_pixel();
+        let (mx, my) = self.mouse_world();
+        self.thrown_crates.push(ThrownCrate::throw(px, py, mx, my));
+        self.carrying_crate = false;
+        self.audio.play_shove();
+    }
+
+    /// Fly thrown crates; each smashes on the first bot or wall it meets,
+    /// taking the bot out and spilling crate loot
+    fn update_thrown_crates(&mut self, dt: f32) {
+        let mut broken = Vec::new();
+        self.thrown_crates.retain_mut(|thrown| {
+            let mut smashed = thrown.update(dt, &self.map);
+            for bot in &mut self.bots {
+                let (bx, by) = bot.pos.center_pixel();
+                if bot.alive
+                    && !bot.allied
+                    && (bx - thrown.x).hypot(by - thrown.y) <= CRATE_HIT_RADIUS
+                {
+                    smashed = true;
+                    if bot.absorb_hit() {
+                        break;
+                    }
+                    self.stats.add_kill("Crates");
+                    self.score += (if bot.hostile { 3 } else { 1 }) * self.score_multiplier;
+                    self.pending_xp += kill_xp(bot.hostile);
+                    self.players[0].ultimate.add_kill(bot.hostile);
+                    bot.kill();
+                    break;
+                }
+            }
+            if smashed {
+                broken.push(thrown.tile());
+            }
+            !smashed
+        });
+
+        for (x, y) in broken {
+            self.items.extend(self.loot.roll(LootSource::Crate, x, y));
+            self.audio.play_hit();
+        }
+    }
+
+    /// Pick up the weapon underfoot into a free slot, or swap it for the
+    /// held weapon when every slot is taken, leaving the old one on the
+    /// floor in its place
+    fn pick_up_weapon_item(&mut self, idx: usize) {
+        let item = &self.items[idx];
+        let ItemType::Weapon(kind) = item.item_type else {
+            return;
+        };
+        if !self.needs_swap(kind) {
+            let taken = match item.weapon.clone() {
+                Some(weapon) => self.take_weapon(weapon, false),
+                None => self.collect_item(item.item_type, item.rarity),
+            };
+            if taken {
+                let item = &mut self.items[idx];
+                item.alive = false;
+                self.particles.burst(Burst::Sparkle, item.x, item.y);
+                self.stats.items_collected += 1;
+            }
+            return;
+        }
+        if self.players[0].weapon().is_melee {
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "Select the weapon to swap out first";
+            return;
+        }
+        let (weapon, fresh) = match &item.weapon {
+            Some(weapon) => (weapon.clone(), false),
+            None => (
+                kind.to_weapon()
+                    .with_random_attachments(item.rarity.attachment_count()),
+                true,
+            ),
+        };
+        if fresh && let Some(ammo) = weapon.ammo {
+            self.players[0].add_ammo(ammo, ammo.pickup_amount());
+        }
+        let (tx, ty) = item.tile_position();
+        if let Some(old) = self.players[0].swap_weapon(weapon)
+            && let Some(old_kind) = WeaponKind::of(&old)
+        {
+            self.items[idx] = Item::dropped_weapon(tx, ty, old_kind, old);
+        }
+        self.message_timer = MESSAGE_DURATION;
+        self.message_text = "WEAPON SWAPPED";
+        self.stats.items_collected += 1;
+        self.audio.play_pickup();
+    }
+
+    /// Give the player an item (picked up or bought). Returns false if there
+    /// is no room for it, in which case it stays where it was.
+    fn collect_item(&mut self, item_type: ItemType, rarity: Rarity) -> bool {
+        match item_type {
+            ItemType::Weapon(kind) => {
+                let weapon = kind
+                    .to_weapon()
+                    .with_random_attachments(rarity.attachment_count());
+                if !self.take_weapon(weapon, true) {
+                    return false;
+                }
+                if rarity > Rarity::Common {
+                    self.message_timer = MESSAGE_DURATION;
+                    self.message_text = if rarity == Rarity::Epic {
+                        "EPIC WEAPON - two attachments installed"
+                    } else {
+                        "RARE WEAPON - attachment installed"
+                    };
+                }
+            }
+            ItemType::DataChip => {
+                self.data_chips += 1;
+                self.message_timer = MESSAGE_DURATION;
+                if self.data_chips == self.data_chips_total {
+                    self.score += DATA_CHIP_BONUS * self.score_multiplier;
+                    self.gold_trim = true;
+                    self.unlock_skin(Skin::Gold);
+                    self.message_text = "ALL DATA CHIPS FOUND - +100 score, gold trim unlocked";
+                    self.audio.play_powerup();
+                } else {
+                    self.message_text = "DATA CHIP FOUND";
+                    self.audio.play_pickup();
+                }
+            }
+            ItemType::Backpack => {
+                if !self.players[0].add_weapon_slot() {
+                    return false;
+                }
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "BACKPACK - room for another weapon";
+                self.audio.play_powerup();
+            }
+            ItemType::Credits(amount) => {
+                self.players[0].credits += amount;
+                self.audio.play_pickup();
+            }
+            // Health packs heal on the spot, or go into the medkit stash
+            // when health is already full
+            ItemType::HealthPack => {
+                if self.players[0].health < self.players[0].max_health {
+                    self.players[0].heal(HEALTH_PACK_AMOUNT);
+                    self.audio.play_health();
+                } else if self.players[0].store_medkit() {
+                    self.message_timer = MESSAGE_DURATION;
+                    self.message_text = "MEDKIT STORED - press H to use";
+                    self.audio.play_pickup();
+                } else {
+                    return false;
+                }
+            }
+            ItemType::Grenade(kind) => {
+                if !self.players[0].grenades.add(kind) {
+                    // Already carrying the maximum of this kind
+                    return false;
+                }
+                self.audio.play_pickup();
+            }
+            ItemType::ArmorPlate => {
+                if !self.players[0].add_armor(ARMOR_PLATE_AMOUNT) {
+                    return false;
+                }
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "ARMOR PLATE EQUIPPED";
+                self.audio.play_powerup();
+            }
+            ItemType::Ammo(kind) => {
+                if !self.players[0].add_ammo(kind, kind.pickup_amount()) {
+                    return false;
+                }
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = kind.pickup_message();
+                self.audio.play_pickup();
+            }
+            // Consumables and key items go into the inventory
+            kind @ (ItemType::SpeedBoost
+            | ItemType::Invulnerability
+            | ItemType::Magnet
+            | ItemType::Shield
+            | ItemType::Decoy
+            | ItemType::Teleporter
+            | ItemType::Scanner
+            | ItemType::Keycard { .. }
+            | ItemType::DataFragment) => {
+                if !self.players[0].inventory.add(kind) {
+                    if self.message_timer <= 0.0 || self.message_text != INVENTORY_FULL_MESSAGE {
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = INVENTORY_FULL_MESSAGE;
+                    }
+                    return false;
+                }
+                match kind {
+                    ItemType::Keycard { .. } => {
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = "KEYCARD ACQUIRED";
+                    }
+                    ItemType::DataFragment => {
+                        let fragments = self.players[0].inventory.count(kind);
+                        self.message_timer = MESSAGE_DURATION;
+                        self.message_text = if fragments >= FRAGMENTS_NEEDED {
+                            "DATA COMPLETE - upload at any terminal!"
+                        } else {
+                            "DATA FRAGMENT RECOVERED"
+                        };
+                    }
+                    _ => {}
+                }
+                self.audio.play_pickup();
+            }
+            ItemType::Overclock => {
+                self.hack_timing.overclock();
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "OVERCLOCK MODULE INSTALLED - hacking faster";
+                self.audio.play_powerup();
+            }
+        }
+        true
+    }
+
+    /// Fly thrown grenades and set off the ones whose fuse ran out
+    fn update_grenades(&mut self, dt: f32) {
+        let mut exploded = Vec::new();
+        self.grenades.retain_mut(|grenade| {
+            let done = grenade.update(dt, &self.map);
+            if done {
+                exploded.push((grenade.x, grenade.y, grenade.kind));
+            }
+            !done
+        });
+
+        for (x, y, kind) in exploded {
+            match kind {
+                GrenadeKind::Frag => self.detonate(x, y, FRAG_RADIUS),
+                GrenadeKind::Emp => {
+                    let radius = EMP_RADIUS * TILE_SIZE;
+                    for bot in &mut self.bots {
+                        let (bx, by) = bot.pos.center_pixel();
+                        if bot.alive && !bot.allied && (bx - x).hypot(by - y) <= radius {
+                            bot.stun(EMP_STUN_DURATION);
+                        }
+                    }
+                    for camera in &mut self.cameras {
+                        if (camera.x - x).hypot(camera.y - y) <= radius {
+                            camera.alive = false;
+                        }
+                    }
+                    self.grenade_clouds.push(GrenadeCloud::new(x, y, kind));
+                    self.audio
+                        .play_at(WorldSound::Explosion, (x, y), self.listener());
+                }
+                GrenadeKind::Smoke => {
+                    self.grenade_clouds.push(GrenadeCloud::new(x, y, kind));
+                }
+            }
+        }
+
+        for cloud in &mut self.grenade_clouds {
+            cloud.update(dt);
+        }
+        self.grenade_clouds.retain(|c| c.is_alive());
+    }
+
+    /// Whether a player is hidden inside a smoke cloud
+    fn is_player_hidden(&self, idx: usize) -> bool {
+        let (px, py) = self.players[idx].pos.center_pixel();
+        self.grenade_clouds.iter().any(|c| c.hides(px, py))
+    }
+
+    /// Fire a player's current weapon toward a world position
+    fn fire_weapon(&mut self, shooter: usize, target_x: f32, target_y: f32) {
+        let player = &mut self.players[shooter];
+        let weapon_index = player.current_weapon;
+        player.weapon_mut().fire();
+        player.use_ammo();
+        let space = Space::from_open_tiles(self.map.open_tiles_around(
+            player.pos.x,
+            player.pos.y,
+            SPACE_RADIUS,
+        ));
+        self.audio.play_shoot(weapon_index, space);
+        if self.players[shooter].weapon().pellets > 1 {
+            self.shake.add(TRAUMA_SHOTGUN);
+        }
+
+        if self.players[shooter].weapon().is_melee {
+            let (px, py) = self.players[shooter].pos.center_pixel();
+            let range = self.players[shooter].weapon().range;
+            self.melee_swings
+                .push(MeleeSwing::new(px, py, target_x, target_y, range));
+            self.handle_melee_attack(shooter, target_x, target_y);
+        } else {
+            self.create_projectiles(shooter, target_x, target_y);
+        }
+    }
+
+    fn create_projectiles(&mut self, shooter: usize, target_x: f32, target_y: f32) {
+        let (px, py) = self.players[shooter].pos.center_pixel();
+        let weapon = self.players[shooter].weapon();
+
+        // Calculate base direction
+        let dx = target_x - px;
+        let dy = target_y - py;
+        let base_angle = dy.atan2(dx);
+        self.particles.muzzle_flash(px, py, base_angle);
+
+        let pellets = weapon.pellets.max(1);
+        let spread = weapon.spread * self.players[shooter].skills.spread_mult();
+        let speed = weapon.bullet_speed;
+        let range = weapon.range;
+
+        for i in 0..pellets {
+            // Calculate spread angle for this pellet
+            let angle_offset = if pellets > 1 {
+                let spread_range = spread * 2.0;
+                -spread + spread_range * (i as f32 / (pellets - 1) as f32)
+            } else if spread > 0.0 {
+                // Single pellet with spread (machine pistol) - random spread
+                rand::gen_range(-spread, spread)
+            } else {
+                0.0
+            };
+
+            let angle = base_angle + angle_offset;
+            let proj_dx = angle.cos();
+            let proj_dy = angle.sin();
+
+            let knockback = if pellets > 1 { PELLET_KNOCKBACK } else { 0 };
+            let projectile = Projectile::new_player(px, py, proj_dx, proj_dy, speed, range)
+                .with_source(weapon.name)
+                .with_owner(shooter)
+                .with_knockback(knockback);
+            self.projectiles.push(projectile);
+        }
+
+        // Pylons in earshot count the shot
+        let mut alarm = false;
+        for pylon in &mut self.pylons {
+            alarm |= pylon.hear_shot(px, py);
+        }
+        if alarm {
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "ALARM PYLON TRIGGERED - HORDE INBOUND!";
+            self.audio.play_hack_start();
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        // Away from the window: go quiet, and pause a local run if asked to
+        let focused = self.focus.poll();
+        self.audio
+            .set_muted(!focused && self.settings.mute_unfocused);
+        if !focused
+            && self.settings.pause_unfocused
+            && self.screen == GameScreen::Playing
+            && self.net.is_none()
+            && self.demo.is_none()
+        {
+            self.screen = GameScreen::Paused;
+        }
+
+        let (theme, tension) = match self.screen {
+            GameScreen::Playing | GameScreen::Paused | GameScreen::LevelIntro => {
+                (Theme::Game, self.tension())
+            }
+            _ => (Theme::Menu, 1.0),
+        };
+        self.audio.update_music(dt, theme, tension);
+        let ambience = if self.screen == GameScreen::Playing {
+            self.ambience()
+        } else {
+            [0.0; 3]
+        };
+        self.audio.update_ambience(ambience);
+        self.audio.update_heartbeat(dt, self.heartbeat_rate());
+        if self.screen == GameScreen::Playing && !self.is_overlay_open() {
+            let step = get_zoom_input() as f32 * ZOOM_STEP;
+            self.zoom = (self.zoom + step).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+        }
+
+        if self.update_online(dt) || self.update_demo(dt) {
+            return;
+        }
+
+        // Idling on the main menu starts the attract-mode demo
+        if self.screen == GameScreen::MainMenu && !is_any_key_pressed() {
+            self.idle_time += dt;
+        } else {
+            self.idle_time = 0.0;
+        }
+        if self.idle_time >= IDLE_TIME {
+            self.idle_time = 0.0;
+            self.start_demo();
+            return;
+        }
+        self.step(dt);
+    }
+
+    /// One frame of the game with whatever input is current
+    fn step(&mut self, dt: f32) {
+        // Handle screen-specific updates
+        match self.screen {
+            GameScreen::MainMenu => {
+                self.update_menu(MenuItem::main_menu_items(self.can_continue));
+                return;
+            }
+            GameScreen::Paused => {
+                self.update_menu(MenuItem::pause_menu_items());
+                return;
+            }
+            GameScreen::Controls => {
+                if is_menu_escape() || is_menu_select() {
+                    // Go back to previous menu (pause if game started, main menu otherwise)
+                    self.screen = if self.game_won {
+                        GameScreen::MainMenu
+                    } else {
+                        GameScreen::Paused
+                    };
+                }
+                return;
+            }
+            GameScreen::ClassSelect => {
+                self.update_class_select();
+                return;
+            }
+            GameScreen::Stats => {
+                if is_menu_escape() || is_menu_select() {
+                    self.screen = GameScreen::Paused;
+                }
+                return;
+            }
+            GameScreen::Unlocks => {
+                self.update_unlocks();
+                return;
+            }
+            GameScreen::Skins => {
+                self.update_skins();
+                return;
+            }
+            GameScreen::Settings => {
+                self.update_settings();
+                return;
+            }
+            GameScreen::Lifetime => {
+                if is_menu_escape() || is_menu_select() {
+                    self.screen = GameScreen::MainMenu;
+                }
+                return;
+            }
+            GameScreen::Online => {
+                self.update_online_menu();
+                return;
+            }
+            GameScreen::LevelIntro => {
+                if is_menu_select() {
+                    self.screen = GameScreen::Playing;
+                }
+                return;
+            }
+            GameScreen::GameOver => {
+                self.update_game_over();
+                return;
+            }
+            GameScreen::Daily => {
+                if is_menu_select() {
+                    self.start_daily();
+                } else if is_menu_escape() {
+                    self.daily = None;
+                    self.screen = GameScreen::MainMenu;
+                }
+                return;
+            }
+            GameScreen::Playing => {
+                // The debug console takes all input and holds the game while open
+                if is_console_pressed() {
+                    self.debug_console.toggle();
+                    return;
+                }
+                if self.debug_console.open {
+                    if is_menu_escape() {
+                        self.debug_console.toggle();
+                    } else if let Some(line) = self.debug_console.read_line() {
+                        self.run_command(&line);
+                    }
+                    return;
+                }
+
+                // ESC aborts the bypass minigame before it pauses
+                if is_menu_escape()
+                    && let Some(idx) = self.active_hack
+                    && self.terminals[idx].minigame.is_some()
+                {
+                    self.terminals[idx].minigame = None;
+                    return;
+                }
+
+                // A level-up pauses the game until a perk is picked
+                if self.level_up_choices.is_some() {
+                    self.update_level_up();
+                    return;
+                }
+
+                // The shop menu pauses the game until ESC closes it
+                if self.shop_open {
+                    if is_menu_escape() {
+                        self.shop_open = false;
+                    } else {
+                        self.update_shop();
+                    }
+                    return;
+                }
+                // A terminal in reach keeps E for hacking
+                let (px, py) = (self.players[0].pos.x, self.players[0].pos.y);
+                if self.interact_pressed(0)
+                    && !self.is_minigame_open()
+                    && self.shops.iter().any(|s| s.is_player_nearby(px, py))
+                    && !self.terminals.iter().any(|t| t.is_player_nearby(px, py))
+                {
+                    self.shop_open = true;
+                    self.overview_open = false;
+                    return;
+                }
+                if self.interact_pressed(0)
+                    && !self.is_minigame_open()
+                    && let Some(idx) = self.weapon_item_underfoot()
+                {
+                    self.pick_up_weapon_item(idx);
+                    return;
+                }
+                if self.interact_pressed(0)
+                    && !self.is_minigame_open()
+                    && !self.carrying_crate
+                    && self.players[0].weapon().is_melee
+                    && !self.terminals.iter().any(|t| t.is_player_nearby(px, py))
+                    && let Some((cx, cy)) = self.adjacent_crate()
+                    && self.map.lift_crate(cx, cy)
+                {
+                    self.carrying_crate = true;
+                    self.message_timer = MESSAGE_DURATION;
+                    self.message_text = "CRATE LIFTED - click to throw";
+                    self.audio.play_pickup();
+                    return;
+                }
+
+                // ESC and I close the inventory; the game stays paused while it is open
+                if self.inventory_open {
+                    if is_menu_escape() || is_inventory_pressed(&self.settings.keys) {
+                        self.inventory_open = false;
+                    } else {
+                        self.update_inventory();
+                    }
+                    return;
+                }
+                if is_inventory_pressed(&self.settings.keys)
+                    && !self.is_minigame_open()
+                    && self.players[0].is_alive()
+                {
+                    self.inventory_open = true;
+                    self.overview_open = false;
+                    return;
+                }
+
+                // ESC and K close the skill tree; the game stays paused while it is open
+                if self.skill_tree_open {
+                    if is_menu_escape() || is_skill_tree_pressed(&self.settings.keys) {
+                        self.skill_tree_open = false;
+                    } else {
+                        self.update_skill_tree();
+                    }
+                    return;
+                }
+                if is_skill_tree_pressed(&self.settings.keys) && !self.is_minigame_open() {
+                    self.skill_tree_open = true;
+                    self.overview_open = false;
+                    return;
+                }
+
+                if self.can_new_game_plus() && is_menu_select() {
+                    self.start_new_game_plus();
+                    return;
+                }
+
+                // Handle ESC to pause
+                if is_menu_escape() {
+                    self.screen = GameScreen::Paused;
+                    self.menu_selection = 0;
+                    self.overview_open = false;
+                    return;
+                }
+
+                if !self.game_won {
+                    self.stats.time += dt;
+                    self.time_bonus_flash = (self.time_bonus_flash - dt).max(0.0);
+                    if let Some(time_left) = &mut self.time_left {
+                        *time_left = (*time_left - dt).max(0.0);
+                        if *time_left <= 0.0 {
+                            self.lose_run();
+                            return;
+                        }
+                    }
+                    // Campaign and endless: on to the next map once this one is cleared
+                    self.depth_flash = (self.depth_flash - dt).max(0.0);
+                    if self.game_mode.has_levels() && self.level_cleared() {
+                        self.next_level();
+                        return;
+                    }
+                }
+            }
+        }
+
+        // Overview map slows the simulation and takes over player input
+        self.overview_open = is_overview_held(&self.settings.keys);
+        let dt = if self.overview_open {
+            dt * OVERVIEW_TIME_SCALE
+        } else {
+            dt
+        };
+
+        self.update_versus(dt);
+        if self.game_over {
+            return;
+        }
+
+        // Co-op: a downed player waits for a revive; the run only loses a
+        // life once nobody is left standing
+        if !self.players.iter().any(|p| p.is_alive()) {
+            self.handle_player_death();
+            if self.game_over {
+                return;
+            }
+        }
+        let lead_up = self.players[0].is_alive();
+
+        // Handle weapon switching
+        if let Some(weapon_index) = get_weapon_switch() {
+            self.players[0].switch_weapon(weapon_index);
+        }
+
+        self.update_teleport(dt);
+
+        // The bypass minigame takes over the direction keys; a pending
+        // teleport roots the player in place
+        let input = if !lead_up
+            || self.overview_open
+            || self.is_minigame_open()
+            || self.teleport_timer > 0.0
+        {
+            Default::default()
+        } else {
+            get_player_input(&self.settings.keys, self.players.len() == 1)
+        };
+        self.try_open_locked_door(input);
+        // C doubles as a countermeasure key while one is being answered
+        let countermeasure_up = self
+            .active_hack
+            .is_some_and(|idx| self.terminals[idx].countermeasure.is_some());
+        if is_crouch_pressed(&self.settings.keys) && !countermeasure_up {
+            self.players[0].crouching = !self.players[0].crouching;
+        }
+        if lead_up && is_ultimate_pressed(&self.settings.keys) {
+            self.use_ultimate();
+        }
+        self.shove_cooldown = (self.shove_cooldown - dt).max(0.0);
+        if lead_up && is_shove_pressed(&self.settings.keys) && !self.is_minigame_open() {
+            self.try_shove();
+        }
+        if is_dash_pressed(&self.settings.keys) && self.players[0].try_dash(input, &self.map) {
+            self.audio.play_dash();
+        } else if is_roll_pressed(&self.settings.keys) && self.players[0].try_roll(input, &self.map)
+        {
+            self.audio.play_roll();
+        }
+        let (prev_x, prev_y) = (self.players[0].pos.visual_x, self.players[0].pos.visual_y);
+        self.players[0].update(dt, input, &self.map);
+        // Twin-stick facing: the sprite looks toward the cursor, even while strafing
+        let (mx, my) = self.mouse_world();
+        let (px, py) = self.players[0].pos.center_pixel();
+        if lead_up {
+            self.players[0].aim_at((my - py).atan2(mx - px));
+        }
+        // Walked distance; jumps (dash, teleport, respawn) don't count
+        let moved =
+            (self.players[0].pos.visual_x - prev_x).hypot(self.players[0].pos.visual_y - prev_y);
+        if moved < 1.0 {
+            self.stats.distance += moved;
+        }
+        if self.players.len() > 1 {
+            self.update_partner(dt);
+            self.update_revive(dt);
+        }
+
+        // Falling into a pit hurts and drops the player back on solid ground
+        for idx in 0..self.players.len() {
+            let player = &self.players[idx];
+            if self.map.is_pit_at(player.pos.x, player.pos.y) && player.is_settled() {
+                self.handle_player_fall(idx);
+            }
+            let (x, y) = (self.players[idx].pos.x, self.players[idx].pos.y);
+            self.explore_tiles += self.map.reveal_around(x, y, EXPLORE_RADIUS);
+        }
+        self.pending_xp += self.explore_tiles / EXPLORE_TILES_PER_XP;
+        self.explore_tiles %= EXPLORE_TILES_PER_XP;
+        self.apply_pending_xp();
+
+        // Update damage flash timer
+        if self.damage_flash_timer > 0.0 {
+            self.damage_flash_timer -= dt;
+        }
+        self.shake.update(dt);
+
+        for idx in 0..self.players.len() {
+            self.burn_in_lava(idx, dt);
+        }
+
+        self.update_camera(dt);
+
+        // Handle shooting - convert screen mouse pos to world pos
+        let wants_shot = lead_up
+            && !self.overview_open
+            && !self.is_minigame_open()
+            && self.teleport_timer <= 0.0
+            && !self.carrying_crate
+            && is_shooting()
+            && self.players[0].weapon().can_fire();
+        if wants_shot && !self.players[0].has_ammo() {
+            if self.message_timer <= 0.0 || self.message_text != OUT_OF_AMMO_MESSAGE {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = OUT_OF_AMMO_MESSAGE;
+            }
+        } else if wants_shot {
+            let (world_mx, world_my) = self.mouse_world();
+            self.fire_weapon(0, world_mx, world_my);
+        }
+
+        if lead_up && is_medkit_pressed(&self.settings.keys) {
+            if self.players[0].use_medkit(HEALTH_PACK_AMOUNT) {
+                self.audio.play_health();
+            } else {
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = if self.players[0].medkits == 0 {
+                    "NO MEDKITS"
+                } else {
+                    "Health already full"
+                };
+            }
+        }
+
+        // A lifted crate takes the place of the weapon until it is thrown
+        if self.carrying_crate
+            && lead_up
+            && !self.overview_open
+            && !self.is_minigame_open()
+            && is_shooting()
+        {
+            self.throw_crate();
+        }
+
+        // Throw the selected grenade toward the cursor
+        if is_grenade_switch_pressed(&self.settings.keys) {
+            self.players[0].grenades.cycle();
+        }
+        if lead_up
+            && !self.overview_open
+            && !self.is_minigame_open()
+            && is_grenade_pressed(&self.settings.keys)
+            && let Some(kind) = self.players[0].grenades.take()
+        {
+            let (px, py) = self.players[0].pos.center_pixel();
+            let (mx, my) = self.mouse_world();
+            self.grenades.push(Grenade::throw(px, py, mx, my, kind));
+        }
+        self.update_grenades(dt);
+        self.update_thrown_crates(dt);
+        for decoy in &mut self.decoys {
+            decoy.update(dt);
+        }
+        self.decoys.retain(|d| d.is_alive());
+
+        // Update projectiles and handle collisions with tiles
+        let mut tile_hits = Vec::new();
+        for projectile in &mut self.projectiles {
+            if let Some(hit) = projectile.update(dt, &self.map) {
+                tile_hits.push(hit);
+                self.particles
+                    .burst(Burst::Impact, projectile.x, projectile.y);
+            }
+        }
+        for (tile_x, tile_y) in tile_hits {
+            // Projectile hit a tile - damage it if destructible
+            self.damage_tile_at(tile_x, tile_y);
+        }
+
+        // Check projectile-bot collisions: player-side shots hit non-allied bots,
+        // bot shots hit allies
+        let listener = self.listener();
+        for projectile in &mut self.projectiles {
+            if !projectile.alive {
+                continue;
+            }
+            for bot in &mut self.bots {
+                if !bot.alive || bot.allied == projectile.from_player {
+                    continue;
+                }
+                let (bx, by) = bot.pos.center_pixel();
+                let half_size = BOT_HITBOX_SIZE / 2.0;
+                if projectile.x >= bx - half_size
+                    && projectile.x <= bx + half_size
+                    && projectile.y >= by - half_size
+                    && projectile.y <= by + half_size
+                {
+                    projectile.alive = false;
+                    self.particles
+                        .burst(Burst::Impact, projectile.x, projectile.y);
+                    let survives = if bot.shrugs_off(DamageType::Ballistic) {
+                        self.audio.play_shield_hit();
+                        true
+                    } else if bot.absorb_hit() {
+                        self.audio.play_hit();
+                        true
+                    } else {
+                        false
+                    };
+                    if survives {
+                        // Pellets knock the bot back, maybe into a pit
+                        if projectile.knockback > 0 {
+                            let (dx, dy) = projectile.push_direction();
+                            bot.shove(dx, dy, projectile.knockback, &self.map);
+                        }
+                        continue;
+                    }
+                    // Hostile bots give more points
+                    if projectile.from_player {
+                        self.stats.add_kill(projectile.source.unwrap_or("Other"));
+                        self.score += (if bot.hostile { 3 } else { 1 }) * self.score_multiplier;
+                        self.pending_xp += kill_xp(bot.hostile);
+                        self.players[0].ultimate.add_kill(bot.hostile);
+                    }
+                    bot.kill();
+                    self.audio.play_at(WorldSound::BotDeath, (bx, by), listener);
+                }
+            }
+            if !projectile.from_player {
+                continue;
+            }
+            for camera in self.cameras.iter_mut().filter(|c| c.alive) {
+                if (projectile.x - camera.x).hypot(projectile.y - camera.y) <= CAMERA_HIT_RADIUS {
+                    projectile.alive = false;
+                    camera.alive = false;
+                    self.audio.play_hit();
+                }
+            }
+            for nest in &mut self.nests {
+                if projectile.alive
+                    && (projectile.x - nest.x).hypot(projectile.y - nest.y) <= NEST_HIT_RADIUS
+                {
+                    projectile.alive = false;
+                    nest.hit();
+                    self.audio.play_hit();
+                }
+            }
+            for pylon in &mut self.pylons {
+                if projectile.alive
+                    && (projectile.x - pylon.x).hypot(projectile.y - pylon.y) <= PYLON_HIT_RADIUS
+                {
+                    projectile.alive = false;
+                    pylon.hit();
+                    self.audio.play_hit();
+                }
+            }
+        }
+        self.cameras.retain(|c| c.alive);
+        self.clear_destroyed_targets();
+
+        // Remove dead projectiles
+        self.projectiles.retain(|p| p.alive);
+
+        // Sparks, splinters and embers off lava in sight
+        let lava = self.lava_in_view();
+        self.particles.emit_embers(dt, &lava, TILE_SIZE);
+        self.particles.update(dt);
+
+        // Update melee swings
+        for swing in &mut self.melee_swings {
+            swing.update(dt);
+        }
+        self.melee_swings.retain(|s| s.is_alive());
+        self.ultimate_waves.retain_mut(|w| w.update(dt));
+
+        // An active magnet pulls floor items in sight toward the player
+        let (px, py) = self.players[0].pos.center_pixel();
+        let magnet = self.players[0].magnet_timer > 0.0;
+        for item in &mut self.items {
+            let pulled = magnet
+                && (item.x - px).hypot(item.y - py) <= MAGNET_RADIUS
+                && self.map.has_line_of_sight(item.x, item.y, px, py);
+            item.update(dt, pulled.then_some((px, py)), &self.map);
+        }
+
+        // Check item pickups (weapons wait for E, see pick_up_weapon_item)
+        let player_tile = (self.players[0].pos.x, self.players[0].pos.y);
+        let underfoot: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                lead_up
+                    && item.alive
+                    && item.tile_position() == player_tile
+                    && !matches!(item.item_type, ItemType::Weapon(_))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        for idx in underfoot {
+            if self.collect_item(self.items[idx].item_type, self.items[idx].rarity) {
+                let (x, y) = (self.items[idx].x, self.items[idx].y);
+                self.particles.burst(Burst::Sparkle, x, y);
+                self.items[idx].alive = false;
+                self.stats.items_collected += 1;
+            }
+        }
+        self.items.retain(|i| i.alive);
+
+        // Update terminal hacking
+        if !self.game_won {
+            self.update_hacking(dt);
+        }
+
+        self.update_lockdown(dt);
+        if !self.game_won {
+            self.update_objectives(dt);
+        }
+        if !self.game_won
+            && self.versus.is_none()
+            && let Some(event) = self.events.update(dt)
+        {
+            self.trigger_event(event);
+        }
+
+        if let Some(console) = &mut self.console {
+            console.update(dt);
+            if console.is_done() {
+                self.console = None;
+            }
+        }
+        self.update_bot_hacking(dt);
+        self.update_door_hacking(dt);
+
+        // Cameras that spot the player raise a local alarm and a lockdown
+        let (px, py) = self.players[0].pos.center_pixel();
+        let hidden = self.is_player_hidden(0);
+        let mut spotted = false;
+        if !self.events.is_outage() {
+            for camera in &mut self.cameras {
+                spotted |= camera.update(dt, px, py, &self.map) && !hidden;
+            }
+        }
+        if spotted {
+            self.ghost_hack = false;
+            self.local_alarm = Some((self.players[0].pos.x, self.players[0].pos.y));
+            self.local_alarm_timer = LOCAL_ALARM_DURATION;
+            self.trigger_lockdown();
+            self.message_timer = MESSAGE_DURATION;
+            self.message_text = "SPOTTED BY CAMERA - LOCKDOWN!";
+            self.audio.play_hack_start();
+        }
+        if self.local_alarm_timer > 0.0 {
+            self.local_alarm_timer -= dt;
+            if self.local_alarm_timer <= 0.0 {
+                self.local_alarm = None;
+            }
+        }
+
+        // Collect non-hostile bot positions for hostile bots to target
+        let non_hostile_positions: Vec<(i32, i32)> = self
+            .bots
+            .iter()
+            .filter(|b| b.alive && !b.hostile)
+            .map(|b| (b.pos.x, b.pos.y))
+            .collect();
+
+        const PLAYER_AGGRO_RANGE: i32 = 6; // Switch to player when this close
+        const SPRINT_HEARING_RANGE: i32 = 11; // Sprinting footsteps carry this far, even through smoke
+
+        // Players still standing, as the bots see them; each bot goes after
+        // the closest one
+        struct Quarry {
+            pos: (i32, i32),
+            crouching: bool,
+            sprinting: bool,
+            hidden: bool, // Smoke hides a player from hostile aggro and fire
+        }
+        let quarries: Vec<Quarry> = (0..self.players.len())
+            .filter(|&idx| self.players[idx].is_alive())
+            .map(|idx| {
+                let player = &self.players[idx];
+                Quarry {
+                    pos: (player.pos.x, player.pos.y),
+                    crouching: player.crouching,
+                    sprinting: player.sprinting,
+                    hidden: self.is_player_hidden(idx),
+                }
+            })
+            .collect();
+        let lead_pos = (self.players[0].pos.x, self.players[0].pos.y);
+
+        // Allies hunt hostile bots; hostile bots shoot at the player or allies
+        let hostile_positions: Vec<(i32, i32)> = self
+            .bots
+            .iter()
+            .filter(|b| b.alive && b.hostile)
+            .map(|b| (b.pos.x, b.pos.y))
+            .collect();
+        let ally_positions: Vec<(i32, i32)> = self
+            .bots
+            .iter()
+            .filter(|b| b.alive && b.allied)
+            .map(|b| (b.pos.x, b.pos.y))
+            .collect();
+        let nearest = |positions: &[(i32, i32)], (bx, by): (i32, i32)| {
+            positions
+                .iter()
+                .min_by_key(|(x, y)| (x - bx).abs() + (y - by).abs())
+                .copied()
+        };
+        let listener = self.listener();
+
+        // Get terminal position if actively hacking
+        let hack_target: Option<(i32, i32)> = self
+            .active_hack
+            .map(|idx| self.terminals[idx].tile_position());
+
+        for bot in &mut self.bots {
+            // A decoy in range takes priority over everything else for hostile bots
+            let (bx, by) = (bot.pos.x, bot.pos.y);
+            let lure = self
+                .decoys
+                .iter()
+                .filter(|d| bot.hostile && d.lures(bx, by))
+                .min_by_key(|d| (d.x - bx).abs() + (d.y - by).abs())
+                .map(|d| (d.x, d.y));
+            let quarry = quarries
+                .iter()
+                .min_by_key(|q| (q.pos.0 - bx).abs() + (q.pos.1 - by).abs());
+            let player_pos = quarry.map_or(lead_pos, |q| q.pos);
+            let player_hidden = quarry.is_some_and(|q| q.hidden);
+            let sprinting = quarry.is_some_and(|q| q.sprinting);
+            // Crouching shrinks how far away bots notice the player
+            let aggro_range = if quarry.is_some_and(|q| q.crouching) {
+                CROUCH_VISIBLE_RANGE
+            } else {
+                PLAYER_AGGRO_RANGE
+            };
+
+            // Hostile bots target player if close, otherwise hunt non-hostile bots
+            // During hack alert, ALL hostile bots swarm the terminal being hacked
+            let mut chasing = false;
+            let target = if lure.is_some() {
+                lure
+            } else if bot.hostile {
+                if self.hack_alert {
+                    // During active hack, all hostile bots swarm the terminal
+                    hack_target.or(Some(player_pos))
+                } else {
+                    let (bx, by) = (bot.pos.x, bot.pos.y);
+                    let player_dist = (player_pos.0 - bx).abs() + (player_pos.1 - by).abs();
+
+                    // Chase player if within aggro range
+                    if (player_dist <= aggro_range && !player_hidden)
+                        || (sprinting && player_dist <= SPRINT_HEARING_RANGE)
+                    {
+                        chasing = true;
+                        Some(player_pos)
+                    } else if let Some((ax, ay)) = self.local_alarm
+                        && (ax - bx).abs() + (ay - by).abs() <= LOCAL_ALARM_RADIUS
+                    {
+                        // Converge on the alarm (camera sighting or door hacking noise)
+                        Some((ax, ay))
+                    } else if !non_hostile_positions.is_empty() {
+                        // Otherwise find nearest non-hostile bot to infect
+                        let nearest = non_hostile_positions
+                            .iter()
+                            .min_by_key(|(x, y)| (x - bx).abs() + (y - by).abs());
+                        nearest.copied()
+                    } else {
+                        Some(player_pos)
+                    }
+                }
+            } else if bot.allied {
+                // Allies engage nearby hostile bots, otherwise stick with the player
+                nearest(&hostile_positions, (bot.pos.x, bot.pos.y))
+                    .filter(|&(hx, hy)| {
+                        (hx - bot.pos.x).abs() + (hy - bot.pos.y).abs() <= ALLY_ENGAGE_RANGE
+                    })
+                    .or(Some(player_pos))
+            } else {
+                Some(player_pos)
+            };
+
+            bot.update(dt, &self.map, target);
+            if bot.set_aggro(bot.alive && chasing) {
+                self.audio
+                    .play_at(WorldSound::BotAlert, bot.pos.center_pixel(), listener);
+            }
+
+            // Hostile bots shoot at a luring decoy or the nearest of the
+            // player and allies; allies shoot at the nearest hostile bot
+            let shoot_target = if bot.allied {
+                nearest(&hostile_positions, (bot.pos.x, bot.pos.y))
+            } else {
+                // A crouched player is out of sight beyond a few tiles
+                let mut targets = ally_positions.clone();
+                targets.extend(
+                    quarries
+                        .iter()
+                        .filter(|q| {
+                            let dist = (q.pos.0 - bot.pos.x).abs() + (q.pos.1 - bot.pos.y).abs();
+                            !(q.hidden || q.crouching && dist > CROUCH_VISIBLE_RANGE)
+                        })
+                        .map(|q| q.pos),
+                );
+                lure.or_else(|| nearest(&targets, (bot.pos.x, bot.pos.y)))
+            };
+            if let Some((tx, ty)) = shoot_target
+                && let Some((dx, dy)) = bot.try_shoot(tx, ty)
+            {
+                let (bx, by) = bot.pos.center_pixel();
+                let speed = 300.0; // Bot projectile speed
+                let range = TILE_SIZE * 10.0; // Bot projectile range
+                let projectile = if bot.allied {
+                    Projectile::new_player(bx, by, dx, dy, speed, range).with_source("Allies")
+                } else {
+                    Projectile::new_bot(bx, by, dx, dy, speed, range)
+                };
+                self.projectiles.push(projectile);
+                self.audio.play_at(WorldSound::BotShot, (bx, by), listener);
+                if bot.boss {
+                    self.shake.add(TRAUMA_BOSS_ATTACK);
+                }
+            }
+        }
+
+        // Allied turrets on hacked terminals shoot hostile bots
+        for turret in &mut self.turrets {
+            if let Some(projectile) = turret.update(dt, &self.bots, &self.map) {
+                self.projectiles.push(projectile);
+            }
+        }
+
+        // Bots knocked into pits (or caught by a new crater) fall to their doom;
+        // shoved bots burn up on lava they would otherwise wade through
+        for bot in &mut self.bots {
+            if !bot.alive {
+                continue;
+            }
+            let in_pit = self.map.is_pit_at(bot.pos.x, bot.pos.y);
+            if in_pit || (bot.was_shoved() && self.map.is_lava_at(bot.pos.x, bot.pos.y)) {
+                self.stats.add_kill(if in_pit { "Pits" } else { "Lava" });
+                self.score += (if bot.hostile { 3 } else { 1 }) * self.score_multiplier;
+                if bot.was_shoved() {
+                    self.score += ENVIRONMENT_KILL_BONUS * self.score_multiplier;
+                    self.message_timer = MESSAGE_DURATION;
+                    self.message_text = ENVIRONMENT_KILL_MESSAGE;
+                }
+                self.pending_xp += kill_xp(bot.hostile);
+                self.players[0].ultimate.add_kill(bot.hostile);
+                bot.kill();
+                self.audio
+                    .play_at(WorldSound::BotDeath, bot.pos.center_pixel(), listener);
+            }
+        }
+
+        // Pressure plates react to anything standing on them
+        let mut occupied: Vec<(i32, i32)> = self
+            .bots
+            .iter()
+            .filter(|b| b.alive)
+            .map(|b| (b.pos.x, b.pos.y))
+            .collect();
+        occupied.extend(self.players.iter().map(|p| (p.pos.x, p.pos.y)));
+        for ((x, y), effect) in self.map.update_plates(dt, &occupied) {
+            match effect {
+                PlateEffect::Trap => {
+                    let cx = x as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+                    let cy = y as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+                    self.detonate(cx, cy, TRAP_BLAST_RADIUS);
+                }
+                PlateEffect::SpawnLoot => {
+                    let (x, y) = (x as i32, y as i32);
+                    let item = self
+                        .loot
+                        .roll(LootSource::Crate, x, y)
+                        .or_else(|| self.loot.roll(LootSource::Floor, x, y));
+                    self.items.extend(item);
+                    self.audio.play_pickup();
+                }
+                PlateEffect::OpenDoor => {}
+            }
+        }
+
+        // Kamikaze bots go off on reaching a player
+        for bot in self
+            .bots
+            .iter_mut()
+            .filter(|b| b.alive && b.kamikaze && b.hostile)
+        {
+            let reached = self.players.iter().any(|p| {
+                p.is_alive() && (p.pos.x - bot.pos.x).abs() <= 1 && (p.pos.y - bot.pos.y).abs() <= 1
+            });
+            if reached {
+                bot.kill();
+            }
+        }
+
+        // Dead bots drop loot where they fell (or beside a pit): credits the
+        // first time only, so respawning bots can't be farmed, plus keycard
+        // carriers their card and a data fragment, hostiles sometimes ammo.
+        // Kamikazes blow up however they died.
+        let mut blasts = Vec::new();
+        for bot in &mut self.bots {
+            if bot.alive || !bot.loot_pending {
+                continue;
+            }
+            bot.loot_pending = false;
+            let (bx, by) = bot.pos.center_pixel();
+            self.particles.burst(Burst::Explosion, bx, by);
+            if bot.kamikaze {
+                blasts.push((bx, by));
+            }
+            let (x, y) = self
+                .map
+                .nearest_walkable(bot.pos.x, bot.pos.y, EntityType::Player)
+                .unwrap_or((bot.pos.x, bot.pos.y));
+            if !bot.paid_out {
+                bot.paid_out = true;
+                self.items.push(Item::new(
+                    x,
+                    y,
+                    ItemType::Credits(bot_credit_drop(bot.hostile)),
+                ));
+            }
+            if let Some(color) = bot.keycard.take() {
+                self.items
+                    .push(Item::new(x, y, ItemType::Keycard { color }));
+                self.items.push(Item::new(x, y, ItemType::DataFragment));
+            } else if bot.hostile
+                && rand::gen_range(0.0, 1.0) < BOT_AMMO_DROP_CHANCE * self.difficulty.drop_rate
+            {
+                self.items
+                    .push(Item::new(x, y, ItemType::Ammo(AmmoKind::random())));
+            }
+        }
+        for (x, y) in blasts {
+            self.detonate(x, y, KAMIKAZE_BLAST_RADIUS);
+        }
+
+        // Hostile bots infect non-hostile bots by touching them (allies are immune)
+        let mut to_infect = Vec::new();
+        for (i, bot) in self.bots.iter().enumerate() {
+            if !bot.alive || bot.hostile || bot.allied {
+                continue;
+            }
+            // Check if any hostile bot is on the same tile
+            for other in &self.bots {
+                if !other.alive || !other.hostile {
+                    continue;
+                }
+                if bot.pos.x == other.pos.x && bot.pos.y == other.pos.y {
+                    to_infect.push(i);
+                    break;
+                }
+            }
+        }
+        for i in to_infect {
+            self.bots[i].infect();
+            self.audio.play_at(
+                WorldSound::BotInfect,
+                self.bots[i].pos.center_pixel(),
+                listener,
+            );
+        }
+
+        // Update message timer
+        if self.message_timer > 0.0 {
+            self.message_timer -= dt;
+        }
+
+        // Check infection progress and show warning messages
+        if self.initial_non_hostile > 0 {
+            let current_non_hostile = self.bots.iter().filter(|b| b.alive && !b.hostile).count();
+            // Use saturating_sub to handle case where bots respawn as non-hostile
+            let infected_count = self.initial_non_hostile.saturating_sub(current_non_hostile);
+            let infection_ratio = infected_count as f32 / self.initial_non_hostile as f32;
+
+            if !self.shown_all_infected && current_non_hostile == 0 {
+                self.shown_all_infected = true;
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "ALL BOTS HAVE BEEN CORRUPTED!";
+            } else if !self.shown_half_infected && infection_ratio >= 0.5 {
+                self.shown_half_infected = true;
+                self.message_timer = MESSAGE_DURATION;
+                self.message_text = "WARNING: The infection is spreading...";
+            }
+        }
+
+        // Check projectile-player collision (bot projectiles hit players, and
+        // in versus so do the rival's; downed ones are left alone)
+        let half_size = PLAYER_HITBOX_SIZE / 2.0;
+        let versus = self.versus.is_some();
+        for projectile in &mut self.projectiles {
+            // Turret shots stay out of the duel
+            let rival_shot = versus && projectile.owner.is_some();
+            if !projectile.alive || (projectile.from_player && !rival_shot) {
+                continue;
+            }
+            // Decoys soak up the shots aimed at them
+            if self.decoys.iter().any(|d| {
+                let (dx, dy) = d.center_pixel();
+                (projectile.x - dx).abs() <= half_size && (projectile.y - dy).abs() <= half_size
+            }) {
+                projectile.alive = false;
+                continue;
+            }
+            // Rolling players let shots fly past, and nobody is hit by their own
+            let Some((_, player)) = self.players.iter_mut().enumerate().find(|(idx, p)| {
+                let (px, py) = p.pos.center_pixel();
+                p.is_alive()
+                    && !p.is_rolling()
+                    && projectile.owner.is_none_or(|owner| owner != *idx)
+                    && (projectile.x - px).abs() <= half_size
+                    && (projectile.y - py).abs() <= half_size
+            }) else {
+                continue;
+            };
+            projectile.alive = false;
+            let prev_health = player.health;
+            let hit = if projectile.from_player {
+                PVP_DAMAGE
+            } else {
+                self.difficulty.bot_damage(BOT_PROJECTILE_DAMAGE)
+            };
+            let damage = player.absorb_with_shield(hit);
+            if damage < hit {
+                self.audio.play_shield_hit();
+            }
+            player.take_hit(damage, DamageType::Ballistic);
+            let health = player.health;
+            self.last_hit = if projectile.from_player {
+                "Shot by the rival"
+            } else {
+                "Shot by a hostile bot"
+            };
+            self.stats.damage_taken += (prev_health - health) as u32;
+            self.ghost_hack = false;
+            if health < prev_health && self.damage_flash_timer <= 0.0 {
+                self.damage_flash_timer = DAMAGE_FLASH_DURATION;
+                self.shake.add(TRAUMA_HIT);
+                self.audio.play_player_hit();
+            }
+        }
+    }
+
+    pub fn draw(&self, sprites: &SpriteSheet) {
+        clear_background(self.profile.theme.background());
+        set_scale(self.settings.ui_scale);
+        begin_ui();
+
+        // Handle menu screens
+        match self.screen {
+            GameScreen::MainMenu => {
+                self.draw_menu(
+                    "RSTGME",
+                    MenuItem::main_menu_items(self.can_continue),
+                    sprites,
+                    false,
+                );
+                return;
+            }
+            GameScreen::Paused => {
+                // Draw game in background (dimmed)
+                self.draw_game(sprites);
+                self.draw_menu("PAUSED", MenuItem::pause_menu_items(), sprites, true);
+                return;
+            }
+            GameScreen::Controls => {
+                self.draw_controls(sprites);
+                return;
+            }
+            GameScreen::Stats => {
+                self.draw_game(sprites);
+                draw_rectangle(
+                    0.0,
+                    0.0,
+                    ui_width(),
+                    ui_height(),
+                    Color::from_rgba(0, 0, 0, 160),
+                );
+                self.stats.draw();
+                return;
+            }
+            GameScreen::ClassSelect => {
+                draw_class_select(
+                    self.class_selection,
+                    &self.setup_rows(),
+                    self.setup_focus,
+                    &self.profile,
+                    sprites,
+                );
+                return;
+            }
+            GameScreen::Unlocks => {
+                draw_unlocks(&self.profile, self.unlock_selection, sprites);
+                return;
+            }
+            GameScreen::GameOver => {
+                self.draw_game(sprites);
+                self.draw_game_over_screen();
+                return;
+            }
+            GameScreen::Daily => {
+                if let Some(daily) = &self.daily {
+                    draw_daily(daily, self.daily_records.best(&daily.date), sprites);
+                }
+                return;
+            }
+            GameScreen::LevelIntro => {
+                let level = &self.campaign.levels[self.campaign_level];
+                draw_level_intro(
+                    level,
+                    self.campaign_level + 1,
+                    self.campaign.levels.len(),
+                    sprites,
+                );
+                return;
+            }
+            GameScreen::Skins => {
+                draw_skin_select(
+                    &self.profile,
+                    self.skin_selection,
+                    self.settings.skin,
+                    sprites,
+                );
+                return;
+            }
+            GameScreen::Settings => {
+                draw_settings(
+                    &self.settings,
+                    self.settings_selection,
+                    self.rebinding,
+                    sprites,
+                );
+                return;
+            }
+            GameScreen::Lifetime => {
+                draw_lifetime(&self.lifetime, sprites);
+                return;
+            }
+            GameScreen::Online => {
+                draw_online(&self.online_menu, self.net.as_ref(), sprites);
+                return;
+            }
+            GameScreen::Playing => {}
+        }
+
+        self.draw_game(sprites);
+        if self.demo.is_some() {
+            draw_demo_banner();
+        }
+        if self.debug_console.open {
+            self.debug_console.draw();
+        }
+    }
+
+    /// Check if a world pixel position is within the lead's view (with a one-tile margin)
+    fn is_on_screen(&self, x: f32, y: f32) -> bool {
+        self.lead_view().shows(x, y)
+    }
+
+    fn draw_game(&self, sprites: &SpriteSheet) {
+        // Each view draws the world in its own part of the screen; split-screen
+        // gives the partner the right half
+        let lead_view = self.lead_view();
+        self.draw_world(&lead_view, sprites);
+        if let Some(partner_view) = &self.partner_view {
+            self.draw_world(partner_view, sprites);
+        }
+        begin_ui();
+        if self.partner_view.is_some() {
+            let x = lead_view.rect.w / ui_scale();
+            draw_rectangle(x - 1.0, 0.0, 2.0, ui_height(), BLACK);
+        }
+
+        if self.is_lockdown() {
+            self.draw_lockdown_overlay();
+        }
+        self.draw_low_health_vignette();
+
+        // Draw damage flash overlay
+        if self.damage_flash_timer > 0.0 {
+            let alpha = (self.damage_flash_timer / DAMAGE_FLASH_DURATION * 100.0) as u8;
+            draw_rectangle(
+                0.0,
+                0.0,
+                ui_width(),
+                ui_height(),
+                Color::from_rgba(255, 0, 0, alpha),
+            );
+        }
+
+        // Draw HUD (fixed on screen)
+        draw_text(&format!("Score: {}", self.score), 10.0, 30.0, 30.0, WHITE);
+
+        // Level and XP toward the next one
+        let (xp_x, xp_y) = (200.0, 14.0);
+        draw_text(
+            &format!("Lv {}", self.players[0].level),
+            xp_x,
+            xp_y + 14.0,
+            20.0,
+            LEVEL_UP_COLOR,
+        );
+        let xp_pct = self.players[0].xp as f32 / xp_to_next(self.players[0].level) as f32;
+        draw_rectangle(
+            xp_x + 44.0,
+            xp_y + 4.0,
+            90.0,
+            8.0,
+            Color::from_rgba(60, 60, 60, 255),
+        );
+        draw_rectangle(xp_x + 44.0, xp_y + 4.0, 90.0 * xp_pct, 8.0, LEVEL_UP_COLOR);
+        draw_text(
+            self.player_class.name(),
+            xp_x,
+            xp_y + 30.0,
+            14.0,
+            self.player_class.color(),
+        );
+        if let Some(lives) = self.lives {
+            draw_text(
+                &format!("Lives: {}", lives),
+                xp_x + 80.0,
+                xp_y + 30.0,
+                14.0,
+                self.life_rules.color(),
+            );
+        }
+        if self.players[0].skills.points > 0 {
+            draw_text(
+                &format!("+{} SP [K]", self.players[0].skills.points),
+                xp_x + 142.0,
+                xp_y + 13.0,
+                16.0,
+                YELLOW,
+            );
+        }
+
+        // Health bar
+        let health_bar_width = 150.0;
+        let health_bar_height = 16.0;
+        let health_x = 10.0;
+        let health_y = 40.0;
+        let health_pct = self.players[0].health as f32 / self.players[0].max_health as f32;
+
+        // Background (empty health)
+        draw_rectangle(
+            health_x,
+            health_y,
+            health_bar_width,
+            health_bar_height,
+            Color::from_rgba(60, 60, 60, 255),
+        );
+        // Filled health
+        let health_color = if health_pct > 0.5 {
+            Color::from_rgba(80, 200, 80, 255)
+        } else if health_pct > 0.25 {
+            Color::from_rgba(200, 200, 80, 255)
+        } else {
+            Color::from_rgba(200, 80, 80, 255)
+        };
+        draw_rectangle(
+            health_x,
+            health_y,
+            health_bar_width * health_pct,
+            health_bar_height,
+            health_color,
+        );
+        // Health text
+        draw_text(
+            &format!("{}/{}", self.players[0].health, self.players[0].max_health),
+            health_x + 5.0,
+            health_y + 13.0,
+            16.0,
+            WHITE,
+        );
+
+        // Armor bar, just under the health bar
+        let armor_y = health_y + health_bar_height + 2.0;
+        let armor_pct = self.players[0].armor as f32 / self.players[0].max_armor as f32;
+        draw_rectangle(
+            health_x,
+            armor_y,
+            health_bar_width,
+            ARMOR_BAR_HEIGHT,
+            Color::from_rgba(40, 40, 50, 255),
+        );
+        draw_rectangle(
+            health_x,
+            armor_y,
+            health_bar_width * armor_pct,
+            ARMOR_BAR_HEIGHT,
+            ARMOR_COLOR,
+        );
+        if self.players[0].armor > 0 {
+            draw_text(
+                &self.players[0].armor.to_string(),
+                health_x + health_bar_width + 6.0,
+                armor_y + ARMOR_BAR_HEIGHT,
+                14.0,
+                ARMOR_COLOR,
+            );
+        }
+
+        // Dash cooldown ring between the health and shield bars
+        let (dash_x, dash_y) = (health_x + health_bar_width + 18.0, health_y + 8.0);
+        let charge = self.players[0].dash_charge();
+        draw_circle(dash_x, dash_y, 8.0, Color::from_rgba(40, 40, 50, 255));
+        draw_arc(
+            dash_x,
+            dash_y,
+            24,
+            6.0,
+            -90.0,
+            3.0,
+            360.0 * charge,
+            if charge >= 1.0 {
+                Color::from_rgba(150, 230, 255, 255)
+            } else {
+                GRAY
+            },
+        );
+
+        // Roll cooldown pip under the dash ring
+        let roll_charge = self.players[0].roll_charge();
+        draw_rectangle(
+            dash_x - 8.0,
+            dash_y + 11.0,
+            16.0,
+            3.0,
+            Color::from_rgba(40, 40, 50, 255),
+        );
+        draw_rectangle(
+            dash_x - 8.0,
+            dash_y + 11.0,
+            16.0 * roll_charge,
+            3.0,
+            if roll_charge >= 1.0 {
+                Color::from_rgba(255, 200, 120, 255)
+            } else {
+                GRAY
+            },
+        );
+
+        // Stamina bar beside the armor bar while not full
+        if self.players[0].stamina < MAX_STAMINA {
+            let stamina_x = health_x + health_bar_width + 36.0;
+            draw_rectangle(
+                stamina_x,
+                armor_y,
+                80.0,
+                ARMOR_BAR_HEIGHT,
+                Color::from_rgba(40, 40, 50, 255),
+            );
+            draw_rectangle(
+                stamina_x,
+                armor_y,
+                80.0 * self.players[0].stamina / MAX_STAMINA,
+                ARMOR_BAR_HEIGHT,
+                Color::from_rgba(240, 200, 80, 255),
+            );
+        }
+
+        // Shield bar beside the health bar while the powerup is running
+        if self.players[0].shield_timer > 0.0 {
+            let shield_x = health_x + health_bar_width + 36.0;
+            let shield_width = 80.0;
+            draw_rectangle(
+                shield_x,
+                health_y,
+                shield_width,
+                health_bar_height,
+                Color::from_rgba(30, 50, 60, 255),
+            );
+            draw_rectangle(
+                shield_x,
+                health_y,
+                shield_width * self.players[0].shield / SHIELD_CAPACITY,
+                health_bar_height,
+                SHIELD_COLOR,
+            );
+            draw_text(
+                &format!("SHIELD {:.0}s", self.players[0].shield_timer),
+                shield_x + 4.0,
+                health_y + 13.0,
+                14.0,
+                BLACK,
+            );
+        }
+
+        let weapon_text = format!(
+            "[{}] {}",
+            self.players[0].current_weapon + 1,
+            self.players[0].weapon().label()
+        );
+        draw_text(&weapon_text, 10.0, 80.0, 24.0, YELLOW);
+        if let Some(kind) = self.players[0].weapon().ammo {
+            let count = self.players[0].ammo(kind);
+            let ammo_x = 20.0 + measure_text(&weapon_text, None, 24, 1.0).width;
+            let color = if count == 0 { RED } else { ammo_color(kind) };
+            draw_text(&count.to_string(), ammo_x, 80.0, 20.0, color);
+        }
+
+        // Show available weapons
+        let weapon_list: String = self.players[0]
+            .weapons
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                format!(
+                    "{}:{}",
+                    i + 1,
+                    w.name.split_whitespace().next().unwrap_or(w.name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let weapon_list = format!(
+            "{}  [{}/{}]",
+            weapon_list,
+            self.players[0].firearm_count(),
+            self.players[0].weapon_slots
+        );
+        draw_text(&weapon_list, 10.0, 105.0, 16.0, GRAY);
+
+        // Show active buffs
+        let mut buff_y = 125.0;
+        let meter = &self.players[0].ultimate;
+        let ultimate_color = if meter.is_ready() {
+            meter.chosen.color()
+        } else {
+            GRAY
+        };
+        draw_rectangle(
+            10.0,
+            buff_y - 9.0,
+            60.0,
+            8.0,
+            Color::from_rgba(40, 40, 50, 255),
+        );
+        draw_rectangle(
+            10.0,
+            buff_y - 9.0,
+            60.0 * meter.charge / ULTIMATE_CHARGE_MAX,
+            8.0,
+            meter.chosen.color(),
+        );
+        draw_text(
+            &format!(
+                "[Q] {}{}",
+                meter.chosen.name(),
+                if meter.is_ready() { " READY" } else { "" }
+            ),
+            76.0,
+            buff_y,
+            16.0,
+            ultimate_color,
+        );
+        buff_y += 20.0;
+        if self.players[0].medkits > 0 {
+            for i in 0..MAX_MEDKITS {
+                let x = 18.0 + i as f32 * 18.0;
+                if i < self.players[0].medkits {
+                    draw_medkit(x, buff_y - 5.0);
+                } else {
+                    draw_rectangle_lines(x - 7.0, buff_y - 11.0, 14.0, 12.0, 1.0, DARKGRAY);
+                }
+            }
+            draw_text("[H]", 18.0 + MAX_MEDKITS as f32 * 18.0, buff_y, 16.0, GRAY);
+            buff_y += 20.0;
+        }
+        let grenades = &self.players[0].grenades;
+        let grenade_count = grenades.count(grenades.selected);
+        if grenade_count > 0 {
+            draw_grenade(grenades.selected, 18.0, buff_y - 6.0);
+            draw_text(
+                &format!("{} x{} [G]", grenades.selected.name(), grenade_count),
+                30.0,
+                buff_y,
+                16.0,
+                grenades.selected.color(),
+            );
+            buff_y += 20.0;
+        }
+        if self.players[0].crouching {
+            draw_text(
+                if self.ghost_hack {
+                    "CROUCHED - GHOST HACK"
+                } else {
+                    "CROUCHED"
+                },
+                10.0,
+                buff_y,
+                16.0,
+                Color::from_rgba(150, 150, 200, 255),
+            );
+            buff_y += 18.0;
+        }
+        if self.players[0].speed_boost_timer > 0.0 {
+            draw_text(
+                &format!("SPEED {:.1}s", self.players[0].speed_boost_timer),
+                10.0,
+                buff_y,
+                16.0,
+                Color::from_rgba(60, 150, 220, 255),
+            );
+            buff_y += 18.0;
+        }
+        if self.players[0].invulnerability_timer > 0.0 {
+            draw_text(
+                &format!("INVULN {:.1}s", self.players[0].invulnerability_timer),
+                10.0,
+                buff_y,
+                16.0,
+                Color::from_rgba(220, 200, 60, 255),
+            );
+            buff_y += 18.0;
+        }
+        if self.players[0].magnet_timer > 0.0 {
+            draw_text(
+                &format!("MAGNET {:.1}s", self.players[0].magnet_timer),
+                10.0,
+                buff_y,
+                16.0,
+                Color::from_rgba(220, 80, 80, 255),
+            );
+            buff_y += 18.0;
+        }
+        if self.players[0].scanner_timer > 0.0 {
+            draw_text(
+                &format!("SCANNER {:.1}s", self.players[0].scanner_timer),
+                10.0,
+                buff_y,
+                16.0,
+                SCANNER_COLOR,
+            );
+            buff_y += 18.0;
+        }
+        if self.hack_timing.overclocks > 0 {
+            draw_text(
+                &format!(
+                    "OVERCLOCK x{} (-{:.0}% hack time)",
+                    self.hack_timing.overclocks,
+                    self.hack_timing.duration_bonus_percent()
+                ),
+                10.0,
+                buff_y,
+                16.0,
+                Color::from_rgba(80, 255, 200, 255),
+            );
+            buff_y += 18.0;
+        }
+        let mut card_x = 18.0;
+        for color in KeycardColor::ALL {
+            let keycards = self.players[0].inventory.count(ItemType::Keycard { color });
+            if keycards > 0 {
+                draw_keycard(card_x, buff_y - 5.0, color);
+                draw_text(
+                    &format!("x{}", keycards),
+                    card_x + 12.0,
+                    buff_y,
+                    16.0,
+                    color.color(),
+                );
+                card_x += 44.0;
+            }
+        }
+
+        self.draw_keycard_markers();
+        self.draw_scanner_markers();
+        if let Some(partner) = self.players.get(1) {
+            Self::draw_partner_hud(partner);
+        }
+
+        // Draw infection warning message
+        if self.message_timer > 0.0 {
+            let alpha = if self.message_timer > MESSAGE_DURATION - 0.3 {
+                // Fade in
+                ((MESSAGE_DURATION - self.message_timer) / 0.3 * 255.0) as u8
+            } else if self.message_timer < 0.5 {
+                // Fade out
+                (self.message_timer / 0.5 * 255.0) as u8
+            } else {
+                255
+            };
+
+            let text = self.message_text;
+            let font_size = 32.0;
+            let text_width = measure_text(text, None, font_size as u16, 1.0).width;
+            let x = (ui_width() - text_width) / 2.0;
+            let y = ui_height() / 3.0;
+
+            // Draw shadow
+            draw_text(
+                text,
+                x + 2.0,
+                y + 2.0,
+                font_size,
+                Color::from_rgba(0, 0, 0, alpha / 2),
+            );
+            // Draw text in warning red/orange color
+            let color = if self.shown_all_infected {
+                Color::from_rgba(255, 50, 50, alpha) // Red for all infected
+            } else {
+                Color::from_rgba(255, 180, 50, alpha) // Orange for half infected
+            };
+            draw_text(text, x, y, font_size, color);
+        }
+
+        if let Some(time_left) = self.time_left {
+            draw_countdown(time_left, self.time_bonus_flash);
+        }
+        if let Some(versus) = &self.versus {
+            versus.draw_scoreboard();
+        }
+        if self.game_mode == GameMode::Endless {
+            draw_depth(self.depth, &self.depth_modifiers, self.depth_flash);
+        } else if self.ng_loop > 0 {
+            draw_loop(self.ng_loop, &self.depth_modifiers, self.depth_flash);
+        }
+
+        // Draw terminal counter (top right)
+        let terminals_complete = self
+            .terminals
+            .iter()
+            .filter(|t| t.state == HackState::Complete)
+            .count();
+        let terminal_text = format!("Terminals: {}/{}", terminals_complete, self.terminals.len());
+        draw_text(
+            &terminal_text,
+            ui_width() - 150.0,
+            30.0,
+            20.0,
+            Color::from_rgba(100, 200, 255, 255),
+        );
+        if self.game_mode == GameMode::Campaign {
+            draw_text(
+                &format!(
+                    "Level {}/{}",
+                    self.campaign_level + 1,
+                    self.campaign.levels.len()
+                ),
+                ui_width() - 250.0,
+                30.0,
+                20.0,
+                GameMode::Campaign.color(),
+            );
+        }
+
+        // Data fragment counter (alternate win path)
+        draw_data_fragment(ui_width() - 142.0, 48.0);
+        let fragments = self.players[0].inventory.count(ItemType::DataFragment);
+        let data_color = if fragments >= FRAGMENTS_NEEDED {
+            Color::from_rgba(100, 255, 100, 255)
+        } else {
+            Color::from_rgba(120, 200, 255, 255)
+        };
+        draw_text(
+            &format!("Data: {}/{}", fragments, FRAGMENTS_NEEDED),
+            ui_width() - 128.0,
+            54.0,
+            18.0,
+            data_color,
+        );
+        // Credits counter
+        draw_credits(ui_width() - 142.0, 70.0);
+        draw_text(
+            &format!("Credits: {}", self.players[0].credits),
+            ui_width() - 128.0,
+            76.0,
+            18.0,
+            CREDITS_COLOR,
+        );
+
+        // Hidden data chip counter
+        draw_data_chip(ui_width() - 142.0, 86.0);
+        draw_text(
+            &format!("Chips: {}/{}", self.data_chips, self.data_chips_total),
+            ui_width() - 128.0,
+            92.0,
+            18.0,
+            DATA_CHIP_COLOR,
+        );
+
+        if fragments >= FRAGMENTS_NEEDED {
+            draw_text(
+                "[E] Upload at any terminal",
+                ui_width() - 200.0,
+                112.0,
+                16.0,
+                data_color,
+            );
+            draw_objectives(&self.objectives, 132.0);
+        } else {
+            draw_objectives(&self.objectives, 112.0);
+        }
+
+        if self.settings.speedrun_timer {
+            draw_timer(
+                self.stats.time,
+                &self.splits,
+                self.personal_bests
+                    .best(&category(self.game_mode, &self.difficulty)),
+            );
         }
-    }
 
-    pub fn update(&mut self, dt: f32) {
-        // Handle screen-specific updates
-        match self.screen {
-            GameScreen::MainMenu => {
-                self.update_menu(MenuItem::main_menu_items());
-                return;
-            }
-            GameScreen::Paused => {
-                self.update_menu(MenuItem::pause_menu_items());
-                return;
-            }
-            GameScreen::Controls => {
-                if is_menu_escape() || is_menu_select() {
-                    // Go back to previous menu (pause if game started, main menu otherwise)
-                    self.screen = if self.game_won {
-                        GameScreen::MainMenu
-                    } else {
-                        GameScreen::Paused
-                    };
-                }
-                return;
+        // Draw hack progress bar if actively hacking
+        if let Some(terminal_idx) = self.active_hack
+            && let HackState::InProgress { progress, elapsed } = self.terminals[terminal_idx].state
+        {
+            let timing = self.hack_timing.for_tier(self.terminals[terminal_idx].tier);
+            self.draw_hack_progress(progress, elapsed, timing.window);
+            if let Some(countermeasure) = &self.terminals[terminal_idx].countermeasure {
+                countermeasure.draw();
             }
-            GameScreen::Playing => {
-                // Handle ESC to pause
-                if is_menu_escape() {
-                    self.screen = GameScreen::Paused;
-                    self.menu_selection = 0;
-                    return;
-                }
+            if let Some(minigame) = &self.terminals[terminal_idx].minigame {
+                minigame.draw(timing.window - elapsed, timing.mistake_penalty);
             }
         }
 
-        // Check if player is dead and respawn
-        if !self.player.is_alive() {
-            let (x, y) = Self::find_walkable_spot(&self.map);
-            self.player.respawn(x, y);
-            self.lava_damage_accumulator = 0.0;
-            // Reset score and show death message
-            self.score = 0;
-            self.message_timer = MESSAGE_DURATION;
-            self.message_text = Self::random_death_message();
-            self.audio.play_player_death();
+        if let Some(console) = &self.console {
+            console.draw();
         }
 
-        // Handle weapon switching
-        if let Some(weapon_index) = get_weapon_switch() {
-            self.player.switch_weapon(weapon_index);
+        if self.overview_open {
+            self.draw_overview();
         }
 
-        let input = get_player_input();
-        self.player.update(dt, input, &self.map);
-
-        // Update damage flash timer
-        if self.damage_flash_timer > 0.0 {
-            self.damage_flash_timer -= dt;
+        if self.inventory_open {
+            self.players[0].inventory.draw(sprites);
         }
 
-        // Apply lava damage (speed boost grants lava immunity)
-        if self.map.is_lava_at(self.player.pos.x, self.player.pos.y)
-            && self.player.speed_boost_timer <= 0.0
-        {
-            self.lava_damage_accumulator += LAVA_DAMAGE_PER_SECOND as f32 * dt;
-            let damage = self.lava_damage_accumulator as i32;
-            if damage > 0 {
-                let prev_health = self.player.health;
-                self.player.take_damage(damage);
-                // Only start a new flash if the previous one has faded
-                if self.player.health < prev_health && self.damage_flash_timer <= 0.0 {
-                    self.damage_flash_timer = DAMAGE_FLASH_DURATION;
-                    self.audio.play_player_hit();
-                }
-                self.lava_damage_accumulator -= damage as f32;
-            }
-        } else {
-            self.lava_damage_accumulator = 0.0;
+        if self.skill_tree_open {
+            self.players[0].skills.draw(self.skill_selection);
         }
 
-        self.update_camera();
+        if self.shop_open {
+            draw_shop_menu(self.shop_selection, self.players[0].credits);
+        }
 
-        // Handle shooting - convert screen mouse pos to world pos
-        if is_shooting() && self.player.weapon().can_fire() {
-            let (mx, my) = get_mouse_position();
-            let world_mx = mx + self.camera_x;
-            let world_my = my + self.camera_y;
-
-            let weapon_index = self.player.current_weapon;
-            self.player.weapon_mut().fire();
-            self.audio.play_shoot(weapon_index);
-
-            if self.player.weapon().is_melee {
-                let (px, py) = self.player.pos.center_pixel();
-                let range = self.player.weapon().range;
-                self.melee_swings
-                    .push(MeleeSwing::new(px, py, world_mx, world_my, range));
-                self.handle_melee_attack(world_mx, world_my);
-            } else {
-                self.create_projectiles(world_mx, world_my);
-            }
+        if let Some(choices) = &self.level_up_choices {
+            let level = self.players[0].level + 1 - self.pending_level_ups;
+            draw_level_up_menu(level, choices, self.perk_selection);
         }
 
-        // Update projectiles and handle collisions with tiles
-        for projectile in &mut self.projectiles {
-            if let Some((tile_x, tile_y)) = projectile.update(dt, &self.map) {
-                // Projectile hit a tile - damage it if destructible
-                if self.map.is_destructible_at(tile_x, tile_y) {
-                    let tile = self.map.get_tile(tile_x as usize, tile_y as usize);
-                    let is_crate = tile == Some(TileType::Crate);
-                    let destroyed = self.map.damage_tile(tile_x as usize, tile_y as usize);
-                    if destroyed {
-                        // Roll for item drop
-                        let drop = if is_crate {
-                            Item::random_crate_drop(tile_x, tile_y)
-                        } else {
-                            Item::random_wall_drop(tile_x, tile_y)
-                        };
-                        if let Some(item) = drop {
-                            self.items.push(item);
-                        }
-                    }
-                }
-            }
+        // Draw win screen if game won
+        if self.game_won {
+            self.draw_win_screen();
         }
+    }
 
-        // Check projectile-bot collisions (only player projectiles hit bots)
-        for projectile in &mut self.projectiles {
-            if !projectile.alive || !projectile.from_player {
-                continue;
-            }
-            for bot in &mut self.bots {
-                if !bot.alive {
-                    continue;
-                }
-                let (bx, by) = bot.pos.center_pixel();
-                let half_size = BOT_HITBOX_SIZE / 2.0;
-                if projectile.x >= bx - half_size
-                    && projectile.x <= bx + half_size
-                    && projectile.y >= by - half_size
-                    && projectile.y <= by + half_size
-                {
-                    projectile.alive = false;
-                    // Hostile bots give more points
-                    self.score += if bot.hostile { 3 } else { 1 };
-                    bot.kill();
-                    self.audio.play_hit();
-                }
-            }
+    /// The world as one view sees it, drawn in view space and scaled down to
+    /// the view's part of the screen while the co-op camera zooms out
+    fn draw_world(&self, view: &View, sprites: &SpriteSheet) {
+        let (view_w, view_h) = view.size();
+        view.apply();
+        self.map.draw(view.x, view.y, view_w, view_h, sprites);
+        if let Some(wash) = self.profile.theme.wash() {
+            draw_rectangle(0.0, 0.0, view_w, view_h, wash);
         }
+        self.draw_scanner_silhouettes(view);
 
-        // Remove dead projectiles
-        self.projectiles.retain(|p| p.alive);
+        // Draw aim line (in view space)
+        let (px, py) = self.players[0].pos.center_pixel();
+        let (mx, my) = self.mouse_world();
+        draw_line(
+            px - view.x,
+            py - view.y,
+            mx - view.x,
+            my - view.y,
+            1.0,
+            Color::from_rgba(255, 255, 255, 80),
+        );
 
-        // Update melee swings
-        for swing in &mut self.melee_swings {
-            swing.update(dt);
+        for decoy in &self.decoys {
+            decoy.draw(view.x, view.y, sprites);
         }
-        self.melee_swings.retain(|s| s.is_alive());
 
-        // Check item pickups
-        for item in &mut self.items {
-            if !item.alive {
-                continue;
+        self.draw_teleport_effects(view);
+
+        for player in &self.players {
+            player.draw(view.x, view.y, sprites);
+            if !player.is_alive() {
+                let (x, y) = player.pos.center_pixel();
+                self.revive.draw(x - view.x, y - view.y);
             }
-            let (ix, iy) = item.tile_position();
-            if self.player.pos.x == ix && self.player.pos.y == iy {
-                // Pick up the item
-                item.alive = false;
-                match item.item_type {
-                    ItemType::Weapon(kind) => {
-                        let weapon = kind.to_weapon();
-                        self.player.add_weapon(weapon);
-                        self.audio.play_pickup();
-                    }
-                    ItemType::HealthPack => {
-                        self.player.heal(HEALTH_PACK_AMOUNT);
-                        self.audio.play_health();
-                    }
-                    ItemType::SpeedBoost => {
-                        self.player.speed_boost_timer = SPEED_BOOST_DURATION;
-                        self.audio.play_powerup();
-                    }
-                    ItemType::Invulnerability => {
-                        self.player.invulnerability_timer = INVULNERABILITY_DURATION;
-                        self.audio.play_powerup();
-                    }
-                }
+        }
+        if self.gold_trim {
+            self.draw_gold_trim(view);
+        }
+        if self.carrying_crate {
+            sprites.draw_tile(
+                tiles::CRATE,
+                self.players[0].pos.visual_x * TILE_SIZE - view.x,
+                self.players[0].pos.visual_y * TILE_SIZE - view.y - 20.0,
+            );
+        }
+        for thrown in &self.thrown_crates {
+            thrown.draw(view.x, view.y, sprites);
+        }
+
+        for bot in &self.bots {
+            let (bx, by) = bot.pos.center_pixel();
+            if view.shows(bx, by) {
+                bot.draw(view.x, view.y, sprites);
             }
         }
-        self.items.retain(|i| i.alive);
 
-        // Update terminal hacking
-        if !self.game_won {
-            self.update_hacking(dt);
+        // Door hacking prompt and progress
+        if let Some((dx, dy)) = self.door_hack_target() {
+            let sx = dx as f32 * TILE_SIZE - view.x;
+            let sy = dy as f32 * TILE_SIZE - view.y;
+            draw_text(
+                "[E] Hack door",
+                sx - 20.0,
+                sy - 6.0,
+                16.0,
+                Color::from_rgba(255, 255, 100, 255),
+            );
+            let progress = match self.door_hacks.get(&(dx, dy)) {
+                Some(
+                    HackState::InProgress { progress, .. } | HackState::Suspended { progress, .. },
+                ) => *progress,
+                _ => 0.0,
+            };
+            if progress > 0.0 {
+                draw_rectangle(sx, sy + TILE_SIZE + 2.0, TILE_SIZE, 4.0, BLACK);
+                draw_rectangle(
+                    sx,
+                    sy + TILE_SIZE + 2.0,
+                    TILE_SIZE * progress,
+                    4.0,
+                    Color::from_rgba(255, 255, 100, 255),
+                );
+            }
         }
 
-        // Collect non-hostile bot positions for hostile bots to target
-        let non_hostile_positions: Vec<(i32, i32)> = self
-            .bots
-            .iter()
-            .filter(|b| b.alive && !b.hostile)
-            .map(|b| (b.pos.x, b.pos.y))
-            .collect();
+        // Reprogram prompt and progress over the targeted bot
+        if let Some(idx) = self.bot_hack_target() {
+            let (bx, by) = self.bots[idx].pos.center_pixel();
+            let (sx, sy) = (bx - view.x, by - view.y);
+            draw_text(
+                "[E] Reprogram",
+                sx - 40.0,
+                sy - 22.0,
+                16.0,
+                Color::from_rgba(120, 255, 140, 255),
+            );
+            if let Some((_, progress)) = self.bot_hack.filter(|&(i, _)| i == idx) {
+                draw_rectangle(
+                    sx - 16.0,
+                    sy + 18.0,
+                    32.0,
+                    4.0,
+                    Color::from_rgba(0, 0, 0, 180),
+                );
+                draw_rectangle(
+                    sx - 16.0,
+                    sy + 18.0,
+                    32.0 * progress,
+                    4.0,
+                    Color::from_rgba(120, 255, 140, 255),
+                );
+            }
+        }
 
-        let player_pos = (self.player.pos.x, self.player.pos.y);
-        const PLAYER_AGGRO_RANGE: i32 = 6; // Switch to player when this close
+        for projectile in &self.projectiles {
+            if view.shows(projectile.x, projectile.y) {
+                projectile.draw(view.x, view.y, sprites);
+            }
+        }
+        self.particles.draw(view.x, view.y);
 
-        // Get terminal position if actively hacking
-        let hack_target: Option<(i32, i32)> = self
-            .active_hack
-            .map(|idx| self.terminals[idx].tile_position());
+        for swing in &self.melee_swings {
+            swing.draw(view.x, view.y);
+        }
+        for wave in &self.ultimate_waves {
+            wave.draw(view.x, view.y);
+        }
 
-        for bot in &mut self.bots {
-            // Hostile bots target player if close, otherwise hunt non-hostile bots
-            // During hack alert, ALL hostile bots swarm the terminal being hacked
-            let target = if bot.hostile {
-                if self.hack_alert {
-                    // During active hack, all hostile bots swarm the terminal
-                    hack_target.or(Some(player_pos))
-                } else {
-                    let (bx, by) = (bot.pos.x, bot.pos.y);
-                    let player_dist = (player_pos.0 - bx).abs() + (player_pos.1 - by).abs();
+        // Draw items
+        for item in &self.items {
+            if view.shows(item.x, item.y) {
+                item.draw(view.x, view.y, sprites);
+            }
+        }
 
-                    // Chase player if within aggro range
-                    if player_dist <= PLAYER_AGGRO_RANGE {
-                        Some(player_pos)
-                    } else if !non_hostile_positions.is_empty() {
-                        // Otherwise find nearest non-hostile bot to infect
-                        let nearest = non_hostile_positions
-                            .iter()
-                            .min_by_key(|(x, y)| (x - bx).abs() + (y - by).abs());
-                        nearest.copied()
-                    } else {
-                        Some(player_pos)
-                    }
+        if let Some(idx) = self.weapon_item_underfoot() {
+            let item = &self.items[idx];
+            let prompt = match item.item_type {
+                ItemType::Weapon(kind) if self.needs_swap(kind) => {
+                    format!(
+                        "[E] Swap {} -> {}",
+                        self.players[0].weapon().name,
+                        kind.name()
+                    )
                 }
-            } else {
-                Some(player_pos)
+                _ => format!("[E] Pick up {}", item.item_type.name()),
             };
+            let width = measure_text(&prompt, None, 16, 1.0).width;
+            draw_text(
+                &prompt,
+                item.x - view.x - width / 2.0,
+                item.y - view.y - 22.0,
+                16.0,
+                Color::from_rgba(255, 255, 100, 255),
+            );
+        }
 
-            bot.update(dt, &self.map, target);
-
-            // Check if hostile bot wants to shoot (always target player)
-            if let Some((dx, dy)) = bot.try_shoot(self.player.pos.x, self.player.pos.y) {
-                let (bx, by) = bot.pos.center_pixel();
-                let projectile = Projectile::new_bot(
-                    bx,
-                    by,
-                    dx,
-                    dy,
-                    300.0,            // Bot projectile speed
-                    TILE_SIZE * 10.0, // Bot projectile range
-                );
-                self.projectiles.push(projectile);
-                self.audio.play_shoot(1); // Bots use pistol sound
-            }
+        for grenade in &self.grenades {
+            grenade.draw(view.x, view.y);
+        }
+        for cloud in &self.grenade_clouds {
+            cloud.draw(view.x, view.y);
         }
 
-        // Hostile bots infect non-hostile bots by touching them
-        let mut to_infect = Vec::new();
-        for (i, bot) in self.bots.iter().enumerate() {
-            if !bot.alive || bot.hostile {
+        // Draw terminals
+        let player_pos = (self.players[0].pos.x, self.players[0].pos.y);
+        for (idx, terminal) in self.terminals.iter().enumerate() {
+            if !view.shows(terminal.x, terminal.y) {
                 continue;
             }
-            // Check if any hostile bot is on the same tile
-            for other in &self.bots {
-                if !other.alive || !other.hostile {
-                    continue;
-                }
-                if bot.pos.x == other.pos.x && bot.pos.y == other.pos.y {
-                    to_infect.push(i);
-                    break;
-                }
-            }
-        }
-        for i in to_infect {
-            self.bots[i].infect();
+            let encrypted = is_encrypted(&self.terminals, idx);
+            terminal.draw(view.x, view.y, sprites, encrypted);
+            terminal.draw_prompt(view.x, view.y, player_pos.0, player_pos.1, encrypted);
         }
 
-        // Update message timer
-        if self.message_timer > 0.0 {
-            self.message_timer -= dt;
+        for turret in &self.turrets {
+            if view.shows(turret.x, turret.y) {
+                turret.draw(view.x, view.y);
+            }
         }
 
-        // Check infection progress and show warning messages
-        if self.initial_non_hostile > 0 {
-            let current_non_hostile = self.bots.iter().filter(|b| b.alive && !b.hostile).count();
-            // Use saturating_sub to handle case where bots respawn as non-hostile
-            let infected_count = self.initial_non_hostile.saturating_sub(current_non_hostile);
-            let infection_ratio = infected_count as f32 / self.initial_non_hostile as f32;
-
-            if !self.shown_all_infected && current_non_hostile == 0 {
-                self.shown_all_infected = true;
-                self.message_timer = MESSAGE_DURATION;
-                self.message_text = "ALL BOTS HAVE BEEN CORRUPTED!";
-            } else if !self.shown_half_infected && infection_ratio >= 0.5 {
-                self.shown_half_infected = true;
-                self.message_timer = MESSAGE_DURATION;
-                self.message_text = "WARNING: The infection is spreading...";
+        for nest in &self.nests {
+            if view.shows(nest.x, nest.y) {
+                nest.draw(view.x, view.y);
             }
         }
 
-        // Check projectile-player collision (only bot projectiles hit player)
-        let (px, py) = self.player.pos.center_pixel();
-        let half_size = PLAYER_HITBOX_SIZE / 2.0;
-        for projectile in &mut self.projectiles {
-            if !projectile.alive || projectile.from_player {
-                continue;
-            }
-            if projectile.x >= px - half_size
-                && projectile.x <= px + half_size
-                && projectile.y >= py - half_size
-                && projectile.y <= py + half_size
-            {
-                projectile.alive = false;
-                let prev_health = self.player.health;
-                self.player.take_damage(BOT_PROJECTILE_DAMAGE);
-                if self.player.health < prev_health && self.damage_flash_timer <= 0.0 {
-                    self.damage_flash_timer = DAMAGE_FLASH_DURATION;
-                    self.audio.play_player_hit();
-                }
+        for pylon in &self.pylons {
+            if view.shows(pylon.x, pylon.y) {
+                pylon.draw(view.x, view.y);
             }
         }
-    }
-
-    pub fn draw(&self, sprites: &SpriteSheet) {
-        clear_background(Color::from_rgba(30, 30, 40, 255));
 
-        // Handle menu screens
-        match self.screen {
-            GameScreen::MainMenu => {
-                self.draw_menu("RSTGME", MenuItem::main_menu_items(), sprites, false);
-                return;
+        for camera in &self.cameras {
+            if view.shows(camera.x, camera.y) {
+                camera.draw(view.x, view.y, &self.map);
             }
-            GameScreen::Paused => {
-                // Draw game in background (dimmed)
-                self.draw_game(sprites);
-                self.draw_menu("PAUSED", MenuItem::pause_menu_items(), sprites, true);
-                return;
+        }
+
+        for shop in &self.shops {
+            if view.shows(shop.x, shop.y) {
+                let nearby = shop.is_player_nearby(self.players[0].pos.x, self.players[0].pos.y);
+                shop.draw(view.x, view.y, nearby);
             }
-            GameScreen::Controls => {
-                self.draw_controls(sprites);
-                return;
+        }
+
+        for panel in &self.panels {
+            if view.shows(panel.x, panel.y) {
+                let nearby = panel.is_player_nearby(self.players[0].pos.x, self.players[0].pos.y);
+                panel.draw(view.x, view.y, self.is_lockdown(), nearby);
             }
-            GameScreen::Playing => {}
         }
 
-        self.draw_game(sprites);
+        if self.depth_modifiers.contains(&DepthModifier::Darkness) || self.events.is_outage() {
+            let lights: Vec<(f32, f32)> =
+                self.players.iter().map(|p| p.pos.center_pixel()).collect();
+            draw_darkness(&self.map, view.x, view.y, view_w, view_h, &lights);
+        }
     }
 
-    fn draw_game(&self, sprites: &SpriteSheet) {
-        self.map.draw(self.camera_x, self.camera_y, sprites);
-
-        // Draw aim line (in screen space)
-        let (px, py) = self.player.pos.center_pixel();
-        let screen_px = px - self.camera_x;
-        let screen_py = py - self.camera_y;
-        let (mx, my) = get_mouse_position();
-        draw_line(
-            screen_px,
-            screen_py,
-            mx,
-            my,
-            1.0,
-            Color::from_rgba(255, 255, 255, 80),
+    /// Cosmetic reward for finding every data chip: a gold ring with a
+    /// glint orbiting the player
+    /// Co-op partner's health and weapon in the bottom-left corner
+    fn draw_partner_hud(partner: &Player) {
+        let (x, y) = (10.0, ui_height() - 36.0);
+        let width = 120.0;
+        let color = partner.skin.tint();
+        draw_text("P2", x, y + 12.0, 18.0, color);
+        draw_rectangle(x + 26.0, y, width, 14.0, Color::from_rgba(60, 60, 60, 255));
+        let pct = partner.health as f32 / partner.max_health as f32;
+        draw_rectangle(
+            x + 26.0,
+            y,
+            width * pct,
+            14.0,
+            Color::from_rgba(80, 200, 80, 255),
         );
+        let status = if partner.is_alive() {
+            format!("{}/{}", partner.health, partner.max_health)
+        } else {
+            "DOWN".to_string()
+        };
+        draw_text(&status, x + 30.0, y + 11.0, 14.0, WHITE);
+
+        let mut weapon = partner.weapon().label().to_string();
+        if let Some(kind) = partner.weapon().ammo {
+            weapon.push_str(&format!("  {}", partner.ammo(kind)));
+        }
+        draw_text(&weapon, x + 26.0, y + 28.0, 16.0, YELLOW);
+    }
 
-        self.player.draw(self.camera_x, self.camera_y, sprites);
+    fn draw_gold_trim(&self, view: &View) {
+        let (px, py) = self.players[0].pos.center_pixel();
+        let (sx, sy) = (px - view.x, py - view.y);
+        draw_circle_lines(sx, sy, 17.0, 1.5, DATA_CHIP_COLOR);
+        let angle = get_time() as f32 * 2.5;
+        draw_circle(sx + angle.cos() * 17.0, sy + angle.sin() * 17.0, 2.5, WHITE);
+    }
 
-        for bot in &self.bots {
-            bot.draw(self.camera_x, self.camera_y, sprites);
+    /// Faint red creeping in from the screen edges, throbbing with the
+    /// heartbeat while the lead is low on health
+    fn draw_low_health_vignette(&self) {
+        let Some(rate) = self.heartbeat_rate() else {
+            return;
+        };
+        let beat = ((get_time() as f32 * rate * std::f32::consts::TAU).sin() * 0.5 + 0.5) * 0.3;
+        let (w, h) = (ui_width(), ui_height());
+        let depth = w.min(h) * 0.12;
+        for i in 0..12 {
+            let inset = i as f32 / 12.0 * depth;
+            let alpha = ((1.0 - i as f32 / 12.0) * (0.7 + beat) * 40.0) as u8;
+            draw_rectangle_lines(
+                inset,
+                inset,
+                w - inset * 2.0,
+                h - inset * 2.0,
+                depth / 12.0 + 1.0,
+                Color::from_rgba(200, 0, 0, alpha),
+            );
         }
+    }
 
-        for projectile in &self.projectiles {
-            projectile.draw(self.camera_x, self.camera_y, sprites);
+    /// Pulsing red screen edges and countdown while a lockdown is active
+    fn draw_lockdown_overlay(&self) {
+        let pulse = ((get_time() * 6.0).sin() * 0.5 + 0.5) as f32;
+        let (w, h) = (ui_width(), ui_height());
+        for i in 0..6 {
+            let inset = i as f32 * 4.0;
+            let alpha = ((1.0 - i as f32 / 6.0) * pulse * 160.0) as u8;
+            draw_rectangle_lines(
+                inset,
+                inset,
+                w - inset * 2.0,
+                h - inset * 2.0,
+                4.0,
+                Color::from_rgba(255, 0, 0, alpha),
+            );
         }
 
-        for swing in &self.melee_swings {
-            swing.draw(self.camera_x, self.camera_y);
+        let text = format!(
+            "LOCKDOWN {:.0}s - override at a security panel",
+            self.lockdown_timer.ceil()
+        );
+        let text_width = measure_text(&text, None, 20, 1.0).width;
+        draw_text(
+            &text,
+            (w - text_width) / 2.0,
+            60.0,
+            20.0,
+            Color::from_rgba(255, 80, 80, 255),
+        );
+    }
+
+    /// Bots and terminals picked up by an active scanner, as pixel centers
+    /// with the color they are outlined in
+    fn scanner_contacts(&self) -> Vec<(f32, f32, Color)> {
+        if self.players[0].scanner_timer <= 0.0 {
+            return Vec::new();
         }
+        let (px, py) = self.players[0].pos.center_pixel();
+        let in_range = |x: f32, y: f32| (x - px).hypot(y - py) <= SCANNER_RADIUS;
+        let bots = self.bots.iter().filter(|b| b.alive).map(|b| {
+            let (x, y) = b.pos.center_pixel();
+            let color = if b.hostile {
+                Color::from_rgba(255, 80, 80, 255)
+            } else if b.allied {
+                Color::from_rgba(120, 255, 140, 255)
+            } else {
+                Color::from_rgba(200, 200, 200, 255)
+            };
+            (x, y, color)
+        });
+        let terminals = self.terminals.iter().map(|t| {
+            let color = if t.state == HackState::Complete {
+                Color::from_rgba(100, 255, 100, 255)
+            } else {
+                Color::from_rgba(100, 200, 255, 255)
+            };
+            (t.x, t.y, color)
+        });
+        bots.chain(terminals)
+            .filter(|&(x, y, _)| in_range(x, y))
+            .collect()
+    }
 
-        // Draw items
-        for item in &self.items {
-            item.draw(self.camera_x, self.camera_y, sprites);
+    /// Scanner silhouettes, drawn over the tile layer so they show through walls
+    fn draw_scanner_silhouettes(&self, view: &View) {
+        for (x, y, color) in self.scanner_contacts() {
+            if !view.shows(x, y) {
+                continue;
+            }
+            let (sx, sy) = (x - view.x, y - view.y);
+            draw_circle(
+                sx,
+                sy,
+                TILE_SIZE * 0.55,
+                Color::new(color.r, color.g, color.b, 0.25),
+            );
+            draw_circle_lines(sx, sy, TILE_SIZE * 0.55, 2.0, color);
         }
+    }
 
-        // Draw terminals
-        let player_pos = (self.player.pos.x, self.player.pos.y);
-        for terminal in &self.terminals {
-            terminal.draw(self.camera_x, self.camera_y, sprites);
-            terminal.draw_prompt(self.camera_x, self.camera_y, player_pos.0, player_pos.1);
+    /// Scanner pips at the screen edge for contacts outside the view
+    fn draw_scanner_markers(&self) {
+        let center = self.lead_view().rect.center() / ui_scale();
+        for (x, y, color) in self.scanner_contacts() {
+            if self.is_on_screen(x, y) {
+                continue;
+            }
+            let on_screen = self.lead_view().screen_at((x, y)) / ui_scale();
+            let dir = (on_screen - center).normalize();
+            let margin = 10.0;
+            let scale = ((center.x - margin) / dir.x.abs()).min((center.y - margin) / dir.y.abs());
+            let pip = center + dir * scale;
+            draw_circle(pip.x, pip.y, 4.0, color);
         }
+    }
 
-        // Draw damage flash overlay
-        if self.damage_flash_timer > 0.0 {
-            let alpha = (self.damage_flash_timer / DAMAGE_FLASH_DURATION * 100.0) as u8;
-            draw_rectangle(
-                0.0,
-                0.0,
-                screen_width(),
-                screen_height(),
-                Color::from_rgba(255, 0, 0, alpha),
+    /// Pulsing beacon marker, and a shimmer around the player while a
+    /// teleport warms up
+    fn draw_teleport_effects(&self, view: &View) {
+        let pulse = (get_time() as f32 * 4.0).sin() * 0.5 + 0.5;
+        if let Some((bx, by)) = self.teleport_beacon {
+            let x = bx as f32 * TILE_SIZE + TILE_SIZE / 2.0 - view.x;
+            let y = by as f32 * TILE_SIZE + TILE_SIZE / 2.0 - view.y;
+            draw_circle(x, y, 5.0, TELEPORT_COLOR);
+            draw_circle_lines(x, y, 8.0 + pulse * 6.0, 1.5, TELEPORT_COLOR);
+        }
+        if self.teleport_timer > 0.0 {
+            let (px, py) = self.players[0].pos.center_pixel();
+            let t = 1.0 - self.teleport_timer / TELEPORT_WARMUP;
+            draw_circle_lines(
+                px - view.x,
+                py - view.y,
+                TILE_SIZE * (1.0 - t * 0.6),
+                2.0 + pulse * 2.0,
+                TELEPORT_COLOR,
             );
         }
+    }
 
-        // Draw HUD (fixed on screen)
-        draw_text(&format!("Score: {}", self.score), 10.0, 30.0, 30.0, WHITE);
-
-        // Health bar
-        let health_bar_width = 150.0;
-        let health_bar_height = 16.0;
-        let health_x = 10.0;
-        let health_y = 40.0;
-        let health_pct = self.player.health as f32 / self.player.max_health as f32;
+    /// Point at off-screen keycard carriers from the screen edge
+    fn draw_keycard_markers(&self) {
+        let center = self.lead_view().rect.center() / ui_scale();
+        for bot in self.bots.iter().filter(|b| b.alive) {
+            let Some(card) = bot.keycard else {
+                continue;
+            };
+            let (bx, by) = bot.pos.center_pixel();
+            if self.is_on_screen(bx, by) {
+                continue;
+            }
+            let on_screen = self.lead_view().screen_at((bx, by)) / ui_scale();
+            let dir = (on_screen - center).normalize();
+            // Scale the direction so the marker sits just inside the nearest edge
+            let margin = 24.0;
+            let scale = ((center.x - margin) / dir.x.abs()).min((center.y - margin) / dir.y.abs());
+            let tip = center + dir * scale;
+            let side = Vec2::new(-dir.y, dir.x) * 7.0;
+            let color = card.color();
+            draw_triangle(tip, tip - dir * 14.0 + side, tip - dir * 14.0 - side, color);
+            draw_keycard(tip.x - dir.x * 26.0, tip.y - dir.y * 26.0, card);
+        }
+    }
 
-        // Background (empty health)
-        draw_rectangle(
-            health_x,
-            health_y,
-            health_bar_width,
-            health_bar_height,
-            Color::from_rgba(60, 60, 60, 255),
-        );
-        // Filled health
-        let health_color = if health_pct > 0.5 {
-            Color::from_rgba(80, 200, 80, 255)
-        } else if health_pct > 0.25 {
-            Color::from_rgba(200, 200, 80, 255)
-        } else {
-            Color::from_rgba(200, 80, 80, 255)
-        };
+    fn draw_overview(&self) {
         draw_rectangle(
-            health_x,
-            health_y,
-            health_bar_width * health_pct,
-            health_bar_height,
-            health_color,
-        );
-        // Health text
-        draw_text(
-            &format!("{}/{}", self.player.health, self.player.max_health),
-            health_x + 5.0,
-            health_y + 13.0,
-            16.0,
-            WHITE,
+            0.0,
+            0.0,
+            ui_width(),
+            ui_height(),
+            Color::from_rgba(0, 0, 0, 220),
         );
 
-        draw_text(
-            &format!(
-                "[{}] {}",
-                self.player.current_weapon + 1,
-                self.player.weapon().name
-            ),
-            10.0,
-            80.0,
-            24.0,
-            YELLOW,
+        // Fit the whole map on screen with a margin for the title and legend
+        let margin = 40.0;
+        let cell = ((ui_width() - margin * 2.0) / self.map.width as f32)
+            .min((ui_height() - margin * 3.0) / self.map.height as f32)
+            .floor()
+            .max(1.0);
+        let map_w = self.map.width as f32 * cell;
+        let map_h = self.map.height as f32 * cell;
+        let origin_x = (ui_width() - map_w) / 2.0;
+        let origin_y = margin * 1.5;
+
+        draw_text("MAP OVERVIEW", origin_x, origin_y - 12.0, 24.0, WHITE);
+        self.map.draw_overview(origin_x, origin_y, cell);
+        draw_rectangle_lines(
+            origin_x - 1.0,
+            origin_y - 1.0,
+            map_w + 2.0,
+            map_h + 2.0,
+            1.0,
+            Color::from_rgba(150, 150, 150, 255),
         );
 
-        // Show available weapons
-        let weapon_list: String = self
-            .player
-            .weapons
-            .iter()
-            .enumerate()
-            .map(|(i, w)| {
-                format!(
-                    "{}:{}",
-                    i + 1,
-                    w.name.split_whitespace().next().unwrap_or(w.name)
-                )
-            })
-            .collect::<Vec<_>>()
-            .join(" ");
-        draw_text(&weapon_list, 10.0, 105.0, 16.0, GRAY);
-
-        // Show active buffs
-        let mut buff_y = 125.0;
-        if self.player.speed_boost_timer > 0.0 {
-            draw_text(
-                &format!("SPEED {:.1}s", self.player.speed_boost_timer),
-                10.0,
-                buff_y,
-                16.0,
-                Color::from_rgba(60, 150, 220, 255),
-            );
-            buff_y += 18.0;
-        }
-        if self.player.invulnerability_timer > 0.0 {
-            draw_text(
-                &format!("INVULN {:.1}s", self.player.invulnerability_timer),
-                10.0,
-                buff_y,
-                16.0,
-                Color::from_rgba(220, 200, 60, 255),
-            );
-        }
+        let to_screen = |tx: i32, ty: i32| {
+            (
+                origin_x + tx as f32 * cell + cell / 2.0,
+                origin_y + ty as f32 * cell + cell / 2.0,
+            )
+        };
 
-        // Draw infection warning message
-        if self.message_timer > 0.0 {
-            let alpha = if self.message_timer > MESSAGE_DURATION - 0.3 {
-                // Fade in
-                ((MESSAGE_DURATION - self.message_timer) / 0.3 * 255.0) as u8
-            } else if self.message_timer < 0.5 {
-                // Fade out
-                (self.message_timer / 0.5 * 255.0) as u8
-            } else {
-                255
+        // Items (only in explored areas)
+        for item in &self.items {
+            let (ix, iy) = item.tile_position();
+            if !self.map.is_explored(ix, iy) {
+                continue;
+            }
+            let color = match item.item_type {
+                ItemType::Weapon(_) => Color::from_rgba(255, 255, 100, 255),
+                ItemType::HealthPack => Color::from_rgba(220, 60, 60, 255),
+                ItemType::SpeedBoost | ItemType::Invulnerability => {
+                    Color::from_rgba(60, 150, 220, 255)
+                }
+                ItemType::Keycard { color } => color.color(),
+                ItemType::Overclock => Color::from_rgba(80, 255, 200, 255),
+                ItemType::DataFragment => Color::from_rgba(120, 200, 255, 255),
+                ItemType::Ammo(kind) => ammo_color(kind),
+                ItemType::ArmorPlate => ARMOR_COLOR,
+                ItemType::Grenade(kind) => kind.color(),
+                ItemType::Credits(_) => CREDITS_COLOR,
+                ItemType::Magnet => Color::from_rgba(60, 150, 220, 255),
+                ItemType::Shield => SHIELD_COLOR,
+                ItemType::Decoy => Color::from_rgba(60, 150, 220, 255),
+                ItemType::Teleporter => TELEPORT_COLOR,
+                ItemType::Scanner => SCANNER_COLOR,
+                ItemType::Backpack => Color::from_rgba(110, 130, 70, 255),
+                ItemType::DataChip => DATA_CHIP_COLOR,
             };
+            let (sx, sy) = to_screen(ix, iy);
+            draw_circle(sx, sy, (cell * 0.3).max(1.5), color);
+        }
 
-            let text = self.message_text;
-            let font_size = 32.0;
-            let text_width = measure_text(text, None, font_size as u16, 1.0).width;
-            let x = (screen_width() - text_width) / 2.0;
-            let y = screen_height() / 3.0;
+        // Dependency links: prerequisite -> dependent, green once satisfied
+        for terminal in &self.terminals {
+            let (tx, ty) = terminal.tile_position();
+            let (ex, ey) = to_screen(tx, ty);
+            for &req in &terminal.requires {
+                let (rx, ry) = self.terminals[req].tile_position();
+                let (sx, sy) = to_screen(rx, ry);
+                let color = if self.terminals[req].state == HackState::Complete {
+                    Color::from_rgba(100, 255, 100, 200)
+                } else {
+                    Color::from_rgba(255, 100, 100, 200)
+                };
+                draw_line(sx, sy, ex, ey, 2.0, color);
+            }
+        }
 
-            // Draw shadow
-            draw_text(
-                text,
-                x + 2.0,
-                y + 2.0,
-                font_size,
-                Color::from_rgba(0, 0, 0, alpha / 2),
-            );
-            // Draw text in warning red/orange color
-            let color = if self.shown_all_infected {
-                Color::from_rgba(255, 50, 50, alpha) // Red for all infected
+        // Terminals are always shown so the objective can be planned
+        for (idx, terminal) in self.terminals.iter().enumerate() {
+            let (tx, ty) = terminal.tile_position();
+            let (sx, sy) = to_screen(tx, ty);
+            let color = if terminal.state == HackState::Complete {
+                Color::from_rgba(100, 255, 100, 255)
+            } else if is_encrypted(&self.terminals, idx) {
+                Color::from_rgba(255, 100, 100, 255)
             } else {
-                Color::from_rgba(255, 180, 50, alpha) // Orange for half infected
+                Color::from_rgba(100, 200, 255, 255)
             };
-            draw_text(text, x, y, font_size, color);
+            let size = (cell * 1.2).max(4.0);
+            draw_rectangle(sx - size / 2.0, sy - size / 2.0, size, size, color);
+            draw_rectangle_lines(sx - size / 2.0, sy - size / 2.0, size, size, 1.0, BLACK);
         }
 
-        // Draw terminal counter (top right)
-        let terminals_complete = self
-            .terminals
-            .iter()
-            .filter(|t| t.state == HackState::Complete)
-            .count();
-        let terminal_text = format!("Terminals: {}/{}", terminals_complete, self.terminals.len());
-        draw_text(
-            &terminal_text,
-            screen_width() - 150.0,
-            30.0,
-            20.0,
-            Color::from_rgba(100, 200, 255, 255),
-        );
+        // Shops once discovered
+        for shop in &self.shops {
+            let (tx, ty) = shop.tile_position();
+            if self.map.is_explored(tx, ty) {
+                let (sx, sy) = to_screen(tx, ty);
+                let size = (cell * 1.2).max(4.0);
+                draw_rectangle(sx - size / 2.0, sy - size / 2.0, size, size, SHOP_COLOR);
+            }
+        }
 
-        // Draw hack progress bar if actively hacking
-        if let Some(terminal_idx) = self.active_hack
-            && let HackState::InProgress { progress, elapsed } = self.terminals[terminal_idx].state
-        {
-            self.draw_hack_progress(progress, elapsed);
+        // Keycard carriers are tracked like objectives
+        for bot in self.bots.iter().filter(|b| b.alive) {
+            if let Some(card) = bot.keycard {
+                let (sx, sy) = to_screen(bot.pos.x, bot.pos.y);
+                draw_circle(sx, sy, (cell * 0.6).max(2.5), card.color());
+            }
         }
 
-        // Draw win screen if game won
-        if self.game_won {
-            self.draw_win_screen();
+        // Player
+        let (sx, sy) = to_screen(self.players[0].pos.x, self.players[0].pos.y);
+        draw_circle(
+            sx,
+            sy,
+            (cell * 0.7).max(3.0),
+            Color::from_rgba(80, 180, 80, 255),
+        );
+        draw_circle_lines(sx, sy, (cell * 0.7).max(3.0) + 1.0, 1.0, WHITE);
+
+        // Legend
+        let legend_y = origin_y + map_h + 24.0;
+        let legend = [
+            ("You", Color::from_rgba(80, 180, 80, 255)),
+            ("Terminal", Color::from_rgba(100, 200, 255, 255)),
+            ("Hacked", Color::from_rgba(100, 255, 100, 255)),
+            ("Encrypted", Color::from_rgba(255, 100, 100, 255)),
+            ("Keycard", Color::from_rgba(255, 210, 60, 255)),
+            ("Shop", SHOP_COLOR),
+            ("Weapon", Color::from_rgba(255, 255, 100, 255)),
+            ("Health", Color::from_rgba(220, 60, 60, 255)),
+            ("Powerup", Color::from_rgba(60, 150, 220, 255)),
+        ];
+        let mut x = origin_x;
+        for (label, color) in legend {
+            draw_rectangle(x, legend_y - 10.0, 10.0, 10.0, color);
+            draw_text(label, x + 14.0, legend_y, 16.0, WHITE);
+            x += 22.0 + measure_text(label, None, 16, 1.0).width;
         }
     }
 
-    fn draw_hack_progress(&self, progress: f32, elapsed: f32) {
+    fn draw_hack_progress(&self, progress: f32, elapsed: f32, window: f32) {
         let bar_width = 250.0;
         let bar_height = 24.0;
-        let x = (screen_width() - bar_width) / 2.0;
-        let y = screen_height() - 100.0;
+        let x = (ui_width() - bar_width) / 2.0;
+        let y = ui_height() - 100.0;
 
         // Background
         draw_rectangle(
@@ -1157,7 +6364,7 @@ impl GameState {
         );
 
         // Progress bar color based on time remaining
-        let time_ratio = elapsed / HACK_WINDOW;
+        let time_ratio = elapsed / window;
         let color = if time_ratio < 0.5 {
             Color::from_rgba(80, 200, 80, 255) // Green
         } else if time_ratio < 0.75 {
@@ -1175,7 +6382,7 @@ impl GameState {
         draw_text("HACKING...", x, y - 8.0, 20.0, WHITE);
 
         // Time remaining
-        let time_left = HACK_WINDOW - elapsed;
+        let time_left = window - elapsed;
         let time_color = if time_ratio > 0.75 {
             Color::from_rgba(255, 80, 80, 255)
         } else {
@@ -1190,21 +6397,114 @@ impl GameState {
         );
     }
 
+    /// Game over screen: what ended the run, its stats, and Retry/Menu
+    fn draw_game_over_screen(&self) {
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui_width(),
+            ui_height(),
+            Color::from_rgba(50, 0, 0, 190),
+        );
+        let center = |text: &str, y: f32, size: f32, color: Color| {
+            let width = measure_text(text, None, size as u16, 1.0).width;
+            draw_text(text, (ui_width() - width) / 2.0, y, size, color);
+        };
+
+        let winner = self.versus.as_ref().and_then(Versus::winner);
+        let text = if let Some(winner) = winner {
+            ["PLAYER 1 WINS", "PLAYER 2 WINS"][winner]
+        } else if self.time_left == Some(0.0) {
+            "TIME UP"
+        } else if self.life_rules == LifeRules::Hardcore {
+            "HARDCORE RUN OVER"
+        } else {
+            "GAME OVER"
+        };
+        let y = 110.0;
+        let font_size = 48.0;
+        let text_width = measure_text(text, None, font_size as u16, 1.0).width;
+        let x = (ui_width() - text_width) / 2.0;
+        draw_text(text, x + 3.0, y + 3.0, font_size, BLACK);
+        draw_text(text, x, y, font_size, Color::from_rgba(255, 80, 80, 255));
+
+        let cause = if winner.is_some() {
+            "Versus match over"
+        } else if self.time_left == Some(0.0) {
+            "Ran out of time"
+        } else {
+            self.last_hit
+        };
+        center(cause, y + 36.0, 22.0, Color::from_rgba(255, 170, 150, 255));
+
+        // Endless runs are measured by how deep they got, versus matches
+        // by kills
+        let score = if let Some(versus) = &self.versus {
+            format!("Kills: {} - {}", versus.kills[0], versus.kills[1])
+        } else if self.game_mode == GameMode::Endless {
+            format!(
+                "Depth reached: {} (best {})  -  score {}",
+                self.depth, self.profile.best_depth, self.score
+            )
+        } else {
+            format!("Final score: {}", self.score)
+        };
+        center(&score, y + 80.0, 26.0, WHITE);
+        self.draw_run_reward(y + 108.0);
+
+        // Run stats in two columns
+        let stats = self.stats.general();
+        let col_width = 230.0;
+        let left = (ui_width() - col_width * 2.0) / 2.0;
+        for (i, (label, value)) in stats.iter().enumerate() {
+            let col_x = left + (i % 2) as f32 * col_width;
+            let row_y = y + 150.0 + (i / 2) as f32 * 26.0;
+            draw_text(label, col_x, row_y, 18.0, GRAY);
+            draw_text(value, col_x + 150.0, row_y, 18.0, WHITE);
+        }
+
+        let options = ["Retry", "Main Menu"];
+        for (i, option) in options.iter().enumerate() {
+            let selected = i == self.game_over_selection;
+            let label = if selected {
+                format!("> {} <", option)
+            } else {
+                option.to_string()
+            };
+            let color = if selected {
+                Color::from_rgba(255, 210, 90, 255)
+            } else {
+                LIGHTGRAY
+            };
+            center(&label, y + 260.0 + i as f32 * 36.0, 28.0, color);
+        }
+        center(
+            "W/S: Select  |  Enter/Space: Confirm",
+            ui_height() - 30.0,
+            16.0,
+            GRAY,
+        );
+    }
+
     fn draw_win_screen(&self) {
         // Semi-transparent overlay
         draw_rectangle(
             0.0,
             0.0,
-            screen_width(),
-            screen_height(),
+            ui_width(),
+            ui_height(),
             Color::from_rgba(0, 50, 0, 180),
         );
 
-        let text = "SYSTEM HACKED - YOU WIN!";
+        let text = if self.won_by_upload {
+            "DATA UPLOADED - YOU WIN!"
+        } else {
+            "SYSTEM HACKED - YOU WIN!"
+        };
         let font_size = 48.0;
         let text_width = measure_text(text, None, font_size as u16, 1.0).width;
-        let x = (screen_width() - text_width) / 2.0;
-        let y = screen_height() / 2.0;
+        let x = (ui_width() - text_width) / 2.0;
+        let y = ui_height() / 2.0;
 
         // Shadow
         draw_text(text, x + 3.0, y + 3.0, font_size, BLACK);
@@ -1213,11 +6513,95 @@ impl GameState {
 
         draw_text(
             "Press ESC to quit",
-            (screen_width() - 140.0) / 2.0,
+            (ui_width() - 140.0) / 2.0,
             y + 50.0,
             24.0,
             WHITE,
         );
+        if self.can_new_game_plus() {
+            let next = self.ng_loop + 1;
+            let text = format!("Press ENTER for New Game+{} (score x{})", next, next + 1);
+            let width = measure_text(&text, None, 20, 1.0).width;
+            draw_text(
+                &text,
+                (ui_width() - width) / 2.0,
+                y + 200.0,
+                20.0,
+                Color::from_rgba(120, 220, 200, 255),
+            );
+        }
+
+        // Side objective report
+        let chips = format!("Data chips: {}/{}", self.data_chips, self.data_chips_total);
+        let chips_width = measure_text(&chips, None, 22, 1.0).width;
+        draw_text(
+            &chips,
+            (ui_width() - chips_width) / 2.0,
+            y + 90.0,
+            22.0,
+            DATA_CHIP_COLOR,
+        );
+        if self.gold_trim {
+            let bonus = format!("+{} score - gold trim unlocked", DATA_CHIP_BONUS);
+            let bonus_width = measure_text(&bonus, None, 18, 1.0).width;
+            draw_text(
+                &bonus,
+                (ui_width() - bonus_width) / 2.0,
+                y + 115.0,
+                18.0,
+                DATA_CHIP_COLOR,
+            );
+        }
+        if self.ghost_hacks > 0 {
+            let ghosts = format!("Ghost hacks: {}", self.ghost_hacks);
+            let ghosts_width = measure_text(&ghosts, None, 18, 1.0).width;
+            draw_text(
+                &ghosts,
+                (ui_width() - ghosts_width) / 2.0,
+                y + 140.0,
+                18.0,
+                Color::from_rgba(150, 150, 200, 255),
+            );
+        }
+        if let Some(time_left) = self.time_left {
+            let bonus = format!(
+                "Time left: {:.0}s  (+{} score)",
+                time_left.floor(),
+                self.time_bonus
+            );
+            let bonus_width = measure_text(&bonus, None, 20, 1.0).width;
+            draw_text(
+                &bonus,
+                (ui_width() - bonus_width) / 2.0,
+                y - 60.0,
+                20.0,
+                GameMode::TimeAttack.color(),
+            );
+        }
+        self.draw_run_reward(y + 170.0);
+    }
+
+    /// Tokens this run banked for the unlock screen
+    fn draw_run_reward(&self, y: f32) {
+        let Some(reward) = self.run_reward else {
+            return;
+        };
+        let mut text = format!("+{} tokens  (total {})", reward, self.profile.tokens);
+        if let Some(best) = self
+            .daily
+            .as_ref()
+            .and_then(|daily| self.daily_records.best(&daily.date))
+        {
+            text.push_str(&format!("  -  today's best {}", best));
+        }
+        let width = measure_text(&text, None, 20, 1.0).width;
+        draw_text(
+            &text,
+            (ui_width() - width) / 2.0,
+            y,
+            20.0,
+            Color::from_rgba(255, 210, 90, 255),
+        );
     }
 
     fn draw_menu(&self, title: &str, items: &[MenuItem], sprites: &SpriteSheet, is_pause: bool) {
@@ -1226,8 +6610,8 @@ impl GameState {
             draw_rectangle(
                 0.0,
                 0.0,
-                screen_width(),
-                screen_height(),
+                ui_width(),
+                ui_height(),
                 Color::from_rgba(0, 0, 0, 180),
             );
         } else {
@@ -1235,8 +6619,11 @@ impl GameState {
             sprites.draw_splash(0.3);
         }
 
-        let center_x = screen_width() / 2.0;
-        let start_y = screen_height() / 3.0;
+        let center_x = ui_width() / 2.0;
+        // Long menus tighten up and move the title higher to stay on screen
+        let item_spacing = 45.0_f32.min((ui_height() - 200.0) / items.len() as f32);
+        let start_y =
+            (ui_height() / 3.0).min(ui_height() - 130.0 - items.len() as f32 * item_spacing);
 
         // Draw title with classic game style
         let title_size = 64.0;
@@ -1261,12 +6648,9 @@ impl GameState {
 
         // Draw menu items
         let item_size = 32.0;
-        let item_spacing = 45.0;
         let items_start_y = start_y + 80.0;
-        let audio_muted = self.audio.is_muted();
-
         for (i, item) in items.iter().enumerate() {
-            let label = item.label(!audio_muted);
+            let label = item.label(self.movement_mode);
             let text_width = measure_text(label, None, item_size as u16, 1.0).width;
             let x = center_x - text_width / 2.0;
             let y = items_start_y + i as f32 * item_spacing;
@@ -1327,7 +6711,7 @@ impl GameState {
         draw_text(
             hint,
             center_x - hint_width / 2.0,
-            screen_height() - 40.0,
+            ui_height() - 40.0,
             hint_size,
             Color::from_rgba(150, 150, 150, 255),
         );
@@ -1337,7 +6721,7 @@ impl GameState {
         // Draw splash background with heavier dimming for readability
         sprites.draw_splash(0.6);
 
-        let center_x = screen_width() / 2.0;
+        let center_x = ui_width() / 2.0;
         let start_y = 80.0;
 
         // Title
@@ -1352,24 +6736,70 @@ impl GameState {
             Color::from_rgba(200, 50, 50, 255),
         );
 
-        // Controls list
+        // Controls list, showing the keys bound on the settings screen
+        let key = |action| key_label(self.settings.keys.get(action));
         let controls = [
-            ("Movement", "W A S D  or  Arrow Keys"),
-            ("Aim", "Mouse"),
-            ("Shoot", "Left Mouse Button"),
-            ("Switch Weapon", "1-5 Keys"),
-            ("Interact/Hack", "E (hold for hacking)"),
-            ("Pause", "ESC"),
+            ("Movement", "W A S D  or  Arrow Keys".to_string()),
+            ("Aim", "Mouse".to_string()),
+            ("Zoom", "Mouse Wheel  or  + -".to_string()),
+            ("Shoot", "Left Mouse Button".to_string()),
+            ("Switch Weapon", "1-5 Keys".to_string()),
+            (
+                "Interact/Hack",
+                format!("{} (hold for hacking)", key(Action::Interact)),
+            ),
+            (
+                "Bypass Hack",
+                format!("{}, then arrow keys", key(Action::Bypass)),
+            ),
+            ("Map Overview", format!("{} (hold)", key(Action::Overview))),
+            (
+                "Inventory",
+                format!(
+                    "{} ({} use, {} drop)",
+                    key(Action::Inventory),
+                    key(Action::Interact),
+                    key(Action::Drop)
+                ),
+            ),
+            ("Shop", format!("{} at a $ terminal", key(Action::Interact))),
+            (
+                "Grenade",
+                format!(
+                    "{} throw, {} switch type",
+                    key(Action::Grenade),
+                    key(Action::GrenadeSwitch)
+                ),
+            ),
+            ("Medkit", key(Action::Medkit)),
+            ("Skill Tree", key(Action::SkillTree)),
+            ("Dash", key(Action::Dash)),
+            ("Dodge Roll", key(Action::Roll)),
+            ("Shove", key(Action::Shove)),
+            ("Sprint", format!("{} (hold)", key(Action::Sprint))),
+            ("Crouch", key(Action::Crouch)),
+            (
+                "Ultimate",
+                format!("{} (switch while charging)", key(Action::Ultimate)),
+            ),
+            ("Co-op P2", "Arrows, / fire, . use".to_string()),
+            ("Pause", "ESC".to_string()),
         ];
 
-        let line_size = 24.0;
-        let line_spacing = 35.0;
+        // Two columns, filled top to bottom, so the list fits the window
+        let line_size = 20.0;
+        let line_spacing = 26.0;
         let controls_start_y = start_y + 60.0;
-        let label_x = center_x - 200.0;
-        let value_x = center_x + 20.0;
+        let rows = controls.len().div_ceil(2);
+        let value_offset = 140.0;
 
         for (i, (label, value)) in controls.iter().enumerate() {
-            let y = controls_start_y + i as f32 * line_spacing;
+            let label_x = if i < rows {
+                center_x - 370.0
+            } else {
+                center_x + 20.0
+            };
+            let y = controls_start_y + (i % rows) as f32 * line_spacing;
             draw_text(
                 label,
                 label_x,
@@ -1377,11 +6807,11 @@ impl GameState {
                 line_size,
                 Color::from_rgba(150, 150, 150, 255),
             );
-            draw_text(value, value_x, y, line_size, WHITE);
+            draw_text(value, label_x + value_offset, y, line_size, WHITE);
         }
 
         // Objective section
-        let objective_y = controls_start_y + controls.len() as f32 * line_spacing + 40.0;
+        let objective_y = controls_start_y + rows as f32 * line_spacing + 40.0;
         draw_text(
             "OBJECTIVE",
             center_x - 60.0,
@@ -1415,7 +6845,7 @@ impl GameState {
         draw_text(
             hint,
             center_x - hint_width / 2.0,
-            screen_height() - 40.0,
+            ui_height() - 40.0,
             hint_size,
             Color::from_rgba(150, 150, 150, 255),
         );
@@ -0,0 +1,285 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, persist_enum};
+use crate::tile_map::{TILE_SIZE, TileMap};
+
+const THROW_SPEED: f32 = 320.0;
+const THROW_RANGE: f32 = TILE_SIZE * 7.0;
+const FUSE_TIME: f32 = 1.2; // Seconds from throw to detonation
+const MAX_PER_KIND: u32 = 5;
+pub const FRAG_RADIUS: f32 = 2.0; // Tiles
+pub const EMP_RADIUS: f32 = 3.5; // Tiles
+pub const EMP_STUN_DURATION: f32 = 4.0;
+const EMP_PULSE_TIME: f32 = 0.4;
+const SMOKE_RADIUS: f32 = TILE_SIZE * 2.5;
+const SMOKE_DURATION: f32 = 8.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GrenadeKind {
+    Frag,  // Explodes like a barrel
+    Emp,   // Stuns bots and fries cameras
+    Smoke, // Cloud that hides the player
+}
+
+persist_enum!(GrenadeKind { Frag, Emp, Smoke });
+
+impl GrenadeKind {
+    pub const ALL: [GrenadeKind; 3] = [GrenadeKind::Frag, GrenadeKind::Emp, GrenadeKind::Smoke];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GrenadeKind::Frag => "Frag Grenade",
+            GrenadeKind::Emp => "EMP Grenade",
+            GrenadeKind::Smoke => "Smoke Grenade",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            GrenadeKind::Frag => Color::from_rgba(110, 140, 70, 255),
+            GrenadeKind::Emp => Color::from_rgba(90, 180, 255, 255),
+            GrenadeKind::Smoke => Color::from_rgba(180, 180, 180, 255),
+        }
+    }
+}
+
+/// Dedicated grenade slot, kept apart from the weapon list
+pub struct GrenadeSlot {
+    counts: [u32; GrenadeKind::ALL.len()],
+    pub selected: GrenadeKind,
+}
+
+impl GrenadeSlot {
+    pub fn new() -> Self {
+        Self {
+            counts: [0; GrenadeKind::ALL.len()],
+            selected: GrenadeKind::Frag,
+        }
+    }
+
+    pub fn count(&self, kind: GrenadeKind) -> u32 {
+        self.counts[kind as usize]
+    }
+
+    /// Add a grenade; returns false if that kind is already at capacity.
+    /// An empty slot switches to the new kind.
+    pub fn add(&mut self, kind: GrenadeKind) -> bool {
+        if self.count(kind) >= MAX_PER_KIND {
+            return false;
+        }
+        if self.count(self.selected) == 0 {
+            self.selected = kind;
+        }
+        self.counts[kind as usize] += 1;
+        true
+    }
+
+    /// Take one of the selected kind, moving on to the next stocked kind
+    /// when it runs out
+    pub fn take(&mut self) -> Option<GrenadeKind> {
+        let kind = self.selected;
+        if self.count(kind) == 0 {
+            return None;
+        }
+        self.counts[kind as usize] -= 1;
+        if self.count(kind) == 0 {
+            self.cycle();
+        }
+        Some(kind)
+    }
+
+    /// Select the next kind that has grenades in stock
+    pub fn cycle(&mut self) {
+        let start = self.selected as usize;
+        for step in 1..=GrenadeKind::ALL.len() {
+            let kind = GrenadeKind::ALL[(start + step) % GrenadeKind::ALL.len()];
+            if self.count(kind) > 0 {
+                self.selected = kind;
+                return;
+            }
+        }
+    }
+}
+
+impl Persist for GrenadeSlot {
+    fn write(&self, out: &mut String) {
+        self.counts.write(out);
+        self.selected.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            counts: fields.read()?,
+            selected: fields.read()?,
+        })
+    }
+}
+
+/// A thrown grenade in flight or resting on the floor until its fuse runs out
+pub struct Grenade {
+    pub x: f32,
+    pub y: f32,
+    dx: f32,
+    dy: f32,
+    travel_left: f32,
+    fuse: f32,
+    pub kind: GrenadeKind,
+}
+
+impl Grenade {
+    /// Throw from (x, y) toward the target point, up to the throw range
+    pub fn throw(x: f32, y: f32, target_x: f32, target_y: f32, kind: GrenadeKind) -> Self {
+        let (dx, dy) = (target_x - x, target_y - y);
+        let len = (dx * dx + dy * dy).sqrt();
+        let (dx, dy) = if len > 0.0 {
+            (dx / len, dy / len)
+        } else {
+            (0.0, 0.0)
+        };
+        Self {
+            x,
+            y,
+            dx,
+            dy,
+            travel_left: len.min(THROW_RANGE),
+            fuse: FUSE_TIME,
+            kind,
+        }
+    }
+
+    /// Fly toward the target, stopping at walls. Returns true once the fuse
+    /// has run out and the grenade should go off.
+    pub fn update(&mut self, dt: f32, map: &TileMap) -> bool {
+        if self.travel_left > 0.0 {
+            let step = (THROW_SPEED * dt).min(self.travel_left);
+            let (nx, ny) = (self.x + self.dx * step, self.y + self.dy * step);
+            if map.blocks_projectile_at((nx / TILE_SIZE) as i32, (ny / TILE_SIZE) as i32) {
+                self.travel_left = 0.0;
+            } else {
+                self.x = nx;
+                self.y = ny;
+                self.travel_left -= step;
+            }
+        }
+        self.fuse -= dt;
+        self.fuse <= 0.0
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let (sx, sy) = (self.x - camera_x, self.y - camera_y);
+        draw_circle(sx, sy, 5.0, self.kind.color());
+        draw_circle_lines(sx, sy, 5.0, 1.0, BLACK);
+        // Fuse light blinks faster as it burns down
+        if ((FUSE_TIME - self.fuse).powi(2) * 40.0).sin() > 0.0 {
+            draw_circle(sx, sy - 4.0, 1.5, RED);
+        }
+    }
+}
+
+/// Lingering aftermath of an EMP or smoke grenade
+pub struct GrenadeCloud {
+    pub x: f32,
+    pub y: f32,
+    kind: GrenadeKind,
+    timer: f32,
+}
+
+impl GrenadeCloud {
+    pub fn new(x: f32, y: f32, kind: GrenadeKind) -> Self {
+        let timer = match kind {
+            GrenadeKind::Smoke => SMOKE_DURATION,
+            _ => EMP_PULSE_TIME,
+        };
+        Self { x, y, kind, timer }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.timer -= dt;
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.timer > 0.0
+    }
+
+    /// Whether a smoke cloud hides the given world position
+    pub fn hides(&self, x: f32, y: f32) -> bool {
+        self.kind == GrenadeKind::Smoke && (x - self.x).hypot(y - self.y) <= SMOKE_RADIUS
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let (sx, sy) = (self.x - camera_x, self.y - camera_y);
+        match self.kind {
+            GrenadeKind::Smoke => {
+                let alpha = (self.timer.min(1.0) * 200.0) as u8;
+                for i in 0..6 {
+                    let angle = i as f32 * std::f32::consts::TAU / 6.0 + self.timer * 0.3;
+                    let (ox, oy) = (angle.cos() * 24.0, angle.sin() * 24.0);
+                    draw_circle(
+                        sx + ox,
+                        sy + oy,
+                        SMOKE_RADIUS * 0.6,
+                        Color::from_rgba(170, 170, 170, alpha / 3),
+                    );
+                }
+                draw_circle(
+                    sx,
+                    sy,
+                    SMOKE_RADIUS * 0.8,
+                    Color::from_rgba(190, 190, 190, alpha),
+                );
+            }
+            _ => {
+                let t = 1.0 - self.timer / EMP_PULSE_TIME;
+                let radius = EMP_RADIUS * TILE_SIZE * t;
+                draw_circle_lines(sx, sy, radius, 3.0, GrenadeKind::Emp.color());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grenade_slot() {
+        let mut slot = GrenadeSlot::new();
+        assert_eq!(slot.take(), None);
+
+        // An empty slot switches to whatever is picked up
+        assert!(slot.add(GrenadeKind::Smoke));
+        assert_eq!(slot.selected, GrenadeKind::Smoke);
+        assert!(slot.add(GrenadeKind::Emp));
+        assert_eq!(slot.selected, GrenadeKind::Smoke);
+
+        // Running out moves on to the next stocked kind
+        assert_eq!(slot.take(), Some(GrenadeKind::Smoke));
+        assert_eq!(slot.selected, GrenadeKind::Emp);
+
+        for _ in 1..MAX_PER_KIND {
+            assert!(slot.add(GrenadeKind::Emp));
+        }
+        assert!(!slot.add(GrenadeKind::Emp));
+    }
+
+    #[test]
+    fn test_grenade_stops_at_walls() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(4, 1, crate::tile_map::TileType::Wall);
+        let start = (1.5 * TILE_SIZE, 1.5 * TILE_SIZE);
+        let mut grenade = Grenade::throw(
+            start.0,
+            start.1,
+            8.5 * TILE_SIZE,
+            start.1,
+            GrenadeKind::Frag,
+        );
+
+        let mut exploded = false;
+        for _ in 0..200 {
+            exploded |= grenade.update(0.01, &map);
+        }
+        assert!(exploded);
+        assert!(grenade.x < 4.0 * TILE_SIZE);
+    }
+}
@@ -1,9 +1,14 @@
+use std::cell::Cell;
+
 use macroquad::prelude::*;
 
+use crate::tile_map::Cardinal;
+
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
 pub struct MoveDirection {
     pub dx: i32,
     pub dy: i32,
+    pub sprint: bool, // Sprint modifier held
 }
 
 impl MoveDirection {
@@ -12,77 +17,748 @@ impl MoveDirection {
     }
 }
 
-pub fn get_player_input() -> MoveDirection {
+/// Player action that can be bound to a key on the settings screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Interact,
+    Bypass,
+    Sprint,
+    Dash,
+    Roll,
+    Crouch,
+    Shove,
+    Grenade,
+    GrenadeSwitch,
+    Medkit,
+    Ultimate,
+    Inventory,
+    Drop,
+    SkillTree,
+    Overview,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::Interact,
+        Action::Bypass,
+        Action::Sprint,
+        Action::Dash,
+        Action::Roll,
+        Action::Crouch,
+        Action::Shove,
+        Action::Grenade,
+        Action::GrenadeSwitch,
+        Action::Medkit,
+        Action::Ultimate,
+        Action::Inventory,
+        Action::Drop,
+        Action::SkillTree,
+        Action::Overview,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Interact => "Interact/Hack",
+            Action::Bypass => "Bypass Hack",
+            Action::Sprint => "Sprint",
+            Action::Dash => "Dash",
+            Action::Roll => "Dodge Roll",
+            Action::Crouch => "Crouch",
+            Action::Shove => "Shove",
+            Action::Grenade => "Throw Grenade",
+            Action::GrenadeSwitch => "Switch Grenade",
+            Action::Medkit => "Medkit",
+            Action::Ultimate => "Ultimate",
+            Action::Inventory => "Inventory",
+            Action::Drop => "Drop Item",
+            Action::SkillTree => "Skill Tree",
+            Action::Overview => "Map Overview",
+        }
+    }
+
+    /// Name in the settings file
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::Interact => "interact",
+            Action::Bypass => "bypass",
+            Action::Sprint => "sprint",
+            Action::Dash => "dash",
+            Action::Roll => "roll",
+            Action::Crouch => "crouch",
+            Action::Shove => "shove",
+            Action::Grenade => "grenade",
+            Action::GrenadeSwitch => "grenade_switch",
+            Action::Medkit => "medkit",
+            Action::Ultimate => "ultimate",
+            Action::Inventory => "inventory",
+            Action::Drop => "drop",
+            Action::SkillTree => "skill_tree",
+            Action::Overview => "overview",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.key() == key)
+    }
+}
+
+/// Keys that can be bound to an action: movement, weapon slots, the menu
+/// keys and the countermeasure keys stay fixed
+pub const BINDABLE_KEYS: [KeyCode; 28] = [
+    KeyCode::B,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::U,
+    KeyCode::Y,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::CapsLock,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::Backspace,
+];
+
+/// The left and right modifier keys count as one key
+fn twin(key: KeyCode) -> Option<KeyCode> {
+    match key {
+        KeyCode::LeftShift => Some(KeyCode::RightShift),
+        KeyCode::LeftControl => Some(KeyCode::RightControl),
+        KeyCode::LeftAlt => Some(KeyCode::RightAlt),
+        _ => None,
+    }
+}
+
+/// A pressed key as it can be bound, folding right modifiers onto the left ones
+pub fn bindable_key(key: KeyCode) -> Option<KeyCode> {
+    let key = match key {
+        KeyCode::RightShift => KeyCode::LeftShift,
+        KeyCode::RightControl => KeyCode::LeftControl,
+        KeyCode::RightAlt => KeyCode::LeftAlt,
+        key => key,
+    };
+    BINDABLE_KEYS.contains(&key).then_some(key)
+}
+
+/// Short name of a key for the controls and settings screens
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::LeftShift => "Shift".to_string(),
+        KeyCode::LeftControl => "Ctrl".to_string(),
+        KeyCode::LeftAlt => "Alt".to_string(),
+        key => {
+            let name = key_name(key);
+            name.strip_prefix("Key").unwrap_or(&name).to_string()
+        }
+    }
+}
+
+/// Name of a key in the settings file
+pub fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|&k| key_name(k) == name)
+}
+
+/// Key bound to each action
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeyBindings {
+    keys: [KeyCode; Action::ALL.len()],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: [
+                KeyCode::E,
+                KeyCode::B,
+                KeyCode::LeftControl,
+                KeyCode::LeftShift,
+                KeyCode::Space,
+                KeyCode::LeftAlt,
+                KeyCode::F,
+                KeyCode::G,
+                KeyCode::N,
+                KeyCode::H,
+                KeyCode::Q,
+                KeyCode::I,
+                KeyCode::Backspace,
+                KeyCode::K,
+                KeyCode::Tab,
+            ],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: Action) -> KeyCode {
+        self.keys[action as usize]
+    }
+
+    /// Bind `key` to `action`; an action that already had the key takes
+    /// over the one `action` leaves behind, so no two actions share a key
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        let old = self.get(action);
+        if let Some(other) = self.keys.iter().position(|&k| k == key) {
+            self.keys[other] = old;
+        }
+        self.keys[action as usize] = key;
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        match fed(0) {
+            Some(frame) => frame.actions_pressed & action.bit() != 0,
+            None => self.raw_pressed(action),
+        }
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        match fed(0) {
+            Some(frame) => frame.actions_down & action.bit() != 0,
+            None => self.raw_down(action),
+        }
+    }
+
+    fn raw_pressed(&self, action: Action) -> bool {
+        let key = self.get(action);
+        is_key_pressed(key) || twin(key).is_some_and(is_key_pressed)
+    }
+
+    fn raw_down(&self, action: Action) -> bool {
+        let key = self.get(action);
+        is_key_down(key) || twin(key).is_some_and(is_key_down)
+    }
+}
+
+impl Action {
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Fixed keys an input frame carries next to the bound actions: movement,
+/// weapon slots, countermeasures, menus and the partner cluster. The
+/// console key is left out, so the console stays shut in online play.
+const FRAME_KEYS: [KeyCode; 25] = [
+    KeyCode::W,
+    KeyCode::A,
+    KeyCode::S,
+    KeyCode::D,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Z,
+    KeyCode::X,
+    KeyCode::C,
+    KeyCode::V,
+    KeyCode::R,
+    KeyCode::T,
+    KeyCode::Enter,
+    KeyCode::Space,
+    KeyCode::Escape,
+    KeyCode::Slash,
+    KeyCode::Period,
+    KeyCode::Comma,
+];
+
+pub const FRAME_BYTES: usize = 21;
+
+/// One tick of a player's input, as online co-op peers send it to each
+/// other and controllers make it up. Bound actions are resolved on the machine that captured them, so
+/// both peers read the same thing whatever their own bindings are.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct InputFrame {
+    keys_down: u32,
+    keys_pressed: u32,
+    actions_down: u16,
+    actions_pressed: u16,
+    pub aim: (f32, f32), // Cursor in world pixels
+    pub shooting: bool,
+}
+
+impl InputFrame {
+    /// This machine's input right now; `aim` is the cursor in the world
+    pub fn capture(keys: &KeyBindings, aim: (f32, f32)) -> Self {
+        let mut frame = Self {
+            aim,
+            shooting: is_mouse_button_down(MouseButton::Left),
+            ..Default::default()
+        };
+        for (i, &key) in FRAME_KEYS.iter().enumerate() {
+            frame.keys_down |= u32::from(is_key_down(key)) << i;
+            frame.keys_pressed |= u32::from(is_key_pressed(key)) << i;
+        }
+        for action in Action::ALL {
+            if keys.raw_down(action) {
+                frame.actions_down |= action.bit();
+            }
+            if keys.raw_pressed(action) {
+                frame.actions_pressed |= action.bit();
+            }
+        }
+        frame
+    }
+
+    /// Fold a later capture in: held state and aim come from `later`,
+    /// presses from either, so none are lost between ticks
+    pub fn merge(self, later: Self) -> Self {
+        Self {
+            keys_pressed: self.keys_pressed | later.keys_pressed,
+            actions_pressed: self.actions_pressed | later.actions_pressed,
+            ..later
+        }
+    }
+
+    /// The same frame with its presses spent, for the next tick
+    pub fn held(self) -> Self {
+        Self {
+            keys_pressed: 0,
+            actions_pressed: 0,
+            ..self
+        }
+    }
+
+    /// A remote partner plays with the usual controls: WASD moves, the
+    /// mouse button fires and the interact key hacks and revives, mapped
+    /// onto the partner cluster
+    pub fn as_partner(self) -> Self {
+        let mut frame = self;
+        let mut fold = |from: KeyCode, to: KeyCode| {
+            let (from, to) = (key_bit(from), key_bit(to));
+            if frame.keys_down & from != 0 {
+                frame.keys_down |= to;
+            }
+            if frame.keys_pressed & from != 0 {
+                frame.keys_pressed |= to;
+            }
+        };
+        fold(KeyCode::W, KeyCode::Up);
+        fold(KeyCode::S, KeyCode::Down);
+        fold(KeyCode::A, KeyCode::Left);
+        fold(KeyCode::D, KeyCode::Right);
+        if frame.shooting {
+            frame.keys_down |= key_bit(KeyCode::Slash);
+        }
+        let interact = Action::Interact.bit();
+        if frame.actions_down & interact != 0 {
+            frame.keys_down |= key_bit(KeyCode::Period);
+        }
+        if frame.actions_pressed & interact != 0 {
+            frame.keys_pressed |= key_bit(KeyCode::Period);
+        }
+        frame
+    }
+
+    /// The same frame with `key` held down
+    pub fn hold(mut self, key: KeyCode) -> Self {
+        self.keys_down |= key_bit(key);
+        self
+    }
+
+    /// The same frame with `key` pressed this tick
+    pub fn press(self, key: KeyCode) -> Self {
+        let mut frame = self.hold(key);
+        frame.keys_pressed |= key_bit(key);
+        frame
+    }
+
+    pub fn hold_action(mut self, action: Action) -> Self {
+        self.actions_down |= action.bit();
+        self
+    }
+
+    pub fn press_action(self, action: Action) -> Self {
+        let mut frame = self.hold_action(action);
+        frame.actions_pressed |= action.bit();
+        frame
+    }
+
+    fn is_down(&self, key: KeyCode) -> bool {
+        self.keys_down & key_bit(key) != 0
+    }
+
+    fn is_pressed(&self, key: KeyCode) -> bool {
+        self.keys_pressed & key_bit(key) != 0
+    }
+
+    pub fn to_bytes(self) -> [u8; FRAME_BYTES] {
+        let mut out = [0; FRAME_BYTES];
+        out[0..4].copy_from_slice(&self.keys_down.to_le_bytes());
+        out[4..8].copy_from_slice(&self.keys_pressed.to_le_bytes());
+        out[8..10].copy_from_slice(&self.actions_down.to_le_bytes());
+        out[10..12].copy_from_slice(&self.actions_pressed.to_le_bytes());
+        out[12..16].copy_from_slice(&self.aim.0.to_le_bytes());
+        out[16..20].copy_from_slice(&self.aim.1.to_le_bytes());
+        out[20] = u8::from(self.shooting);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: &[u8; FRAME_BYTES] = bytes.get(..FRAME_BYTES)?.try_into().ok()?;
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u16_at = |i: usize| u16::from_le_bytes(bytes[i..i + 2].try_into().unwrap());
+        Some(Self {
+            keys_down: u32_at(0),
+            keys_pressed: u32_at(4),
+            actions_down: u16_at(8),
+            actions_pressed: u16_at(10),
+            aim: (f32::from_bits(u32_at(12)), f32::from_bits(u32_at(16))),
+            shooting: bytes[20] != 0,
+        })
+    }
+}
+
+/// Bit of a key in a frame; keys a frame does not carry have none
+fn key_bit(key: KeyCode) -> u32 {
+    FRAME_KEYS
+        .iter()
+        .position(|&k| k == key)
+        .map_or(0, |i| 1 << i)
+}
+
+thread_local! {
+    /// Frames for the lead and the partner while an online tick runs
+    static FEED: Cell<Option<[InputFrame; 2]>> = const { Cell::new(None) };
+}
+
+/// Read the lead's and the partner's input from these frames instead of
+/// the keyboard and mouse, until cleared with None
+pub fn set_feed(frames: Option<[InputFrame; 2]>) {
+    FEED.with(|feed| feed.set(frames));
+}
+
+fn fed(player: usize) -> Option<InputFrame> {
+    FEED.with(|feed| feed.get()).map(|frames| frames[player])
+}
+
+/// The lead's cursor in the world while input is fed from frames
+pub fn fed_aim() -> Option<(f32, f32)> {
+    fed(0).map(|frame| frame.aim)
+}
+
+fn key_down(key: KeyCode) -> bool {
+    fed(0).map_or_else(|| is_key_down(key), |frame| frame.is_down(key))
+}
+
+fn key_pressed(key: KeyCode) -> bool {
+    fed(0).map_or_else(|| is_key_pressed(key), |frame| frame.is_pressed(key))
+}
+
+fn partner_key_down(key: KeyCode) -> bool {
+    fed(1).map_or_else(|| is_key_down(key), |frame| frame.is_down(key))
+}
+
+fn partner_key_pressed(key: KeyCode) -> bool {
+    fed(1).map_or_else(|| is_key_pressed(key), |frame| frame.is_pressed(key))
+}
+
+/// WASD movement, plus the arrow keys unless a co-op partner has them
+pub fn get_player_input(keys: &KeyBindings, arrows: bool) -> MoveDirection {
     let mut dir = MoveDirection::default();
+    let down = |key: KeyCode, arrow: KeyCode| key_down(key) || (arrows && key_down(arrow));
 
-    if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+    if down(KeyCode::W, KeyCode::Up) {
         dir.dy = -1;
     }
-    if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+    if down(KeyCode::S, KeyCode::Down) {
         dir.dy = 1;
     }
-    if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+    if down(KeyCode::A, KeyCode::Left) {
         dir.dx = -1;
     }
-    if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+    if down(KeyCode::D, KeyCode::Right) {
         dir.dx = 1;
     }
+    dir.sprint = keys.is_down(Action::Sprint);
 
     dir
 }
 
+/// Co-op partner's cluster (macroquad has no gamepad support): the arrow
+/// keys move, `/` fires, `.` interacts (hack, revive) and `,` cycles weapons
+pub fn get_partner_input() -> MoveDirection {
+    let mut dir = MoveDirection::default();
+    if partner_key_down(KeyCode::Up) {
+        dir.dy = -1;
+    }
+    if partner_key_down(KeyCode::Down) {
+        dir.dy = 1;
+    }
+    if partner_key_down(KeyCode::Left) {
+        dir.dx = -1;
+    }
+    if partner_key_down(KeyCode::Right) {
+        dir.dx = 1;
+    }
+    dir
+}
+
+pub fn is_partner_shooting() -> bool {
+    partner_key_down(KeyCode::Slash)
+}
+
+pub fn is_partner_interact_pressed() -> bool {
+    partner_key_pressed(KeyCode::Period)
+}
+
+pub fn is_partner_interact_held() -> bool {
+    partner_key_down(KeyCode::Period)
+}
+
+pub fn is_partner_weapon_cycle_pressed() -> bool {
+    partner_key_pressed(KeyCode::Comma)
+}
+
 pub fn get_mouse_position() -> (f32, f32) {
     mouse_position()
 }
 
 pub fn is_shooting() -> bool {
-    is_mouse_button_down(MouseButton::Left)
+    fed(0).map_or_else(
+        || is_mouse_button_down(MouseButton::Left),
+        |frame| frame.shooting,
+    )
 }
 
 pub fn get_weapon_switch() -> Option<usize> {
-    if is_key_pressed(KeyCode::Key1) {
+    if key_pressed(KeyCode::Key1) {
         Some(0)
-    } else if is_key_pressed(KeyCode::Key2) {
+    } else if key_pressed(KeyCode::Key2) {
         Some(1)
-    } else if is_key_pressed(KeyCode::Key3) {
+    } else if key_pressed(KeyCode::Key3) {
         Some(2)
-    } else if is_key_pressed(KeyCode::Key4) {
+    } else if key_pressed(KeyCode::Key4) {
         Some(3)
-    } else if is_key_pressed(KeyCode::Key5) {
+    } else if key_pressed(KeyCode::Key5) {
         Some(4)
     } else {
         None
     }
 }
 
-/// Check if player pressed the interact key (E)
-pub fn is_interact_pressed() -> bool {
-    is_key_pressed(KeyCode::E)
+/// Check if player pressed the interact key
+pub fn is_interact_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Interact)
+}
+
+/// Check if player is holding the interact key
+pub fn is_interact_held(keys: &KeyBindings) -> bool {
+    keys.is_down(Action::Interact)
+}
+
+/// Check if player pressed the hack bypass key
+pub fn is_bypass_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Bypass)
+}
+
+/// Keys a hack countermeasure prompt can ask for (clear of movement and hack keys)
+pub const COUNTERMEASURE_KEYS: [KeyCode; 6] = [
+    KeyCode::Z,
+    KeyCode::X,
+    KeyCode::C,
+    KeyCode::V,
+    KeyCode::R,
+    KeyCode::T,
+];
+
+/// Countermeasure key pressed this frame, if any
+pub fn get_countermeasure_input() -> Option<KeyCode> {
+    COUNTERMEASURE_KEYS
+        .into_iter()
+        .find(|&key| key_pressed(key))
+}
+
+/// Direction keys pressed this frame, for the hack bypass minigame
+pub fn get_minigame_input() -> Option<Cardinal> {
+    if key_pressed(KeyCode::W) || key_pressed(KeyCode::Up) {
+        Some(Cardinal::Up)
+    } else if key_pressed(KeyCode::S) || key_pressed(KeyCode::Down) {
+        Some(Cardinal::Down)
+    } else if key_pressed(KeyCode::A) || key_pressed(KeyCode::Left) {
+        Some(Cardinal::Left)
+    } else if key_pressed(KeyCode::D) || key_pressed(KeyCode::Right) {
+        Some(Cardinal::Right)
+    } else {
+        None
+    }
+}
+
+/// Check if player pressed the grenade throw key
+pub fn is_grenade_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Grenade)
+}
+
+/// Check if player pressed the grenade type switch key
+pub fn is_grenade_switch_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::GrenadeSwitch)
+}
+
+/// Check if player pressed the medkit key
+pub fn is_medkit_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Medkit)
+}
+
+/// Check if player pressed the dash key
+pub fn is_dash_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Dash)
+}
+
+/// Check if player pressed the dodge roll key
+pub fn is_roll_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Roll)
+}
+
+/// Check if player pressed the shove key
+pub fn is_shove_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Shove)
 }
 
-/// Check if player is holding the interact key (E)
-pub fn is_interact_held() -> bool {
-    is_key_down(KeyCode::E)
+/// Check if player pressed the ultimate key
+pub fn is_ultimate_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Ultimate)
+}
+
+/// Check if player pressed the crouch toggle
+pub fn is_crouch_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Crouch)
+}
+
+/// Check if player pressed the skill tree key
+pub fn is_skill_tree_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::SkillTree)
+}
+
+/// Check if player pressed the inventory key
+pub fn is_inventory_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Inventory)
+}
+
+/// Check if player pressed the drop key in the inventory
+pub fn is_drop_pressed(keys: &KeyBindings) -> bool {
+    keys.is_pressed(Action::Drop)
+}
+
+/// Check if player is holding the overview map key
+pub fn is_overview_held(keys: &KeyBindings) -> bool {
+    keys.is_down(Action::Overview)
+}
+
+/// Check if player pressed the debug console key (~)
+pub fn is_console_pressed() -> bool {
+    key_pressed(KeyCode::GraveAccent)
+}
+
+/// Camera zoom from the mouse wheel or +/-: 1 in, -1 out, 0 neither. The
+/// camera is this machine's own, so it reads the real mouse and keyboard
+/// whatever frames are fed.
+pub fn get_zoom_input() -> i32 {
+    let (_, wheel) = mouse_wheel();
+    let zoom_in = wheel > 0.0 || is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd);
+    let zoom_out =
+        wheel < 0.0 || is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract);
+    zoom_in as i32 - zoom_out as i32
+}
+
+/// Whether any key or mouse button was pressed this frame, on the real
+/// keyboard and mouse whatever frames are fed
+pub fn is_any_key_pressed() -> bool {
+    get_last_key_pressed().is_some()
+        || is_mouse_button_pressed(MouseButton::Left)
+        || is_mouse_button_pressed(MouseButton::Right)
 }
 
 /// Menu navigation - move up
 pub fn is_menu_up() -> bool {
-    is_key_pressed(KeyCode::W) || is_key_pressed(KeyCode::Up)
+    key_pressed(KeyCode::W) || key_pressed(KeyCode::Up)
 }
 
 /// Menu navigation - move down
 pub fn is_menu_down() -> bool {
-    is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Down)
+    key_pressed(KeyCode::S) || key_pressed(KeyCode::Down)
 }
 
 /// Menu selection - confirm
 pub fn is_menu_select() -> bool {
-    is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space)
+    key_pressed(KeyCode::Enter) || key_pressed(KeyCode::Space)
+}
+
+/// Menu navigation - move left
+pub fn is_menu_left() -> bool {
+    key_pressed(KeyCode::A) || key_pressed(KeyCode::Left)
+}
+
+/// Menu navigation - move right
+pub fn is_menu_right() -> bool {
+    key_pressed(KeyCode::D) || key_pressed(KeyCode::Right)
 }
 
 /// Menu escape - back/pause
 pub fn is_menu_escape() -> bool {
-    is_key_pressed(KeyCode::Escape)
+    key_pressed(KeyCode::Escape)
+}
+
+const FOCUS_STALL: f32 = 0.5; // Seconds; a frame this long means the window wasn't being drawn
+
+/// Whether the window has the player's attention. macroquad 0.4 doesn't
+/// pass on the window's focus events, so a stalled frame (the window was
+/// minimized or hidden and stopped being drawn) counts as losing focus,
+/// and the next key press or click as getting it back
+#[derive(Clone, Copy, Debug)]
+pub struct Focus {
+    focused: bool,
+    started: bool, // The first frame also took the loading time, so it can't stall
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            started: false,
+        }
+    }
+}
+
+impl Focus {
+    /// Track focus from a frame's length and whether the player pressed
+    /// anything in it; true while focused
+    pub fn update(&mut self, frame_time: f32, touched: bool) -> bool {
+        if frame_time > FOCUS_STALL && self.started {
+            self.focused = false;
+        } else if touched {
+            self.focused = true;
+        }
+        self.started = true;
+        self.focused
+    }
+
+    /// `update` from this frame's real keyboard and mouse
+    pub fn poll(&mut self) -> bool {
+        self.update(get_frame_time(), is_any_key_pressed())
+    }
 }
 
 #[cfg(test)]
@@ -97,7 +773,78 @@ mod tests {
 
     #[test]
     fn test_move_direction_moving() {
-        let dir = MoveDirection { dx: 1, dy: 0 };
+        let dir = MoveDirection {
+            dx: 1,
+            ..Default::default()
+        };
         assert!(dir.is_moving());
     }
+
+    #[test]
+    fn test_key_bindings() {
+        let mut keys = KeyBindings::default();
+        assert_eq!(keys.get(Action::Interact), KeyCode::E);
+        assert_eq!(keys.get(Action::Overview), KeyCode::Tab);
+
+        // Taking another action's key hands it the old one
+        keys.set(Action::Interact, KeyCode::F);
+        assert_eq!(keys.get(Action::Interact), KeyCode::F);
+        assert_eq!(keys.get(Action::Shove), KeyCode::E);
+
+        for key in BINDABLE_KEYS {
+            assert_eq!(key_from_name(&key_name(key)), Some(key));
+        }
+        assert_eq!(bindable_key(KeyCode::RightShift), Some(KeyCode::LeftShift));
+        assert_eq!(bindable_key(KeyCode::W), None);
+        // Countermeasure prompts own their keys
+        for key in COUNTERMEASURE_KEYS {
+            assert_eq!(bindable_key(key), None);
+        }
+        assert!(!keys.keys.iter().any(|k| COUNTERMEASURE_KEYS.contains(k)));
+        assert_eq!(key_label(KeyCode::Key7), "7");
+    }
+
+    #[test]
+    fn test_focus() {
+        let mut focus = Focus::default();
+        assert!(focus.update(FOCUS_STALL + 0.1, false)); // Loading
+        assert!(focus.update(0.016, false));
+        // A stall loses focus, even with a key down; only a press after it
+        // brings it back
+        assert!(!focus.update(FOCUS_STALL + 0.1, true));
+        assert!(!focus.update(0.016, false));
+        assert!(focus.update(0.016, true));
+    }
+
+    #[test]
+    fn test_input_frame() {
+        let frame = InputFrame {
+            keys_down: key_bit(KeyCode::W) | key_bit(KeyCode::Key3),
+            keys_pressed: key_bit(KeyCode::Key3),
+            actions_down: Action::Sprint.bit(),
+            actions_pressed: Action::Interact.bit(),
+            aim: (120.5, -8.25),
+            shooting: true,
+        };
+        assert_eq!(InputFrame::from_bytes(&frame.to_bytes()), Some(frame));
+        assert_eq!(InputFrame::from_bytes(&[0; 3]), None);
+
+        // Presses survive a merge; held keys come from the later frame
+        let merged = frame.merge(frame.held());
+        assert!(merged.is_pressed(KeyCode::Key3));
+        assert!(!frame.held().is_pressed(KeyCode::Key3));
+        assert!(!frame.is_down(KeyCode::GraveAccent)); // Not carried
+
+        let partner = frame.as_partner();
+        assert!(partner.is_down(KeyCode::Up));
+        assert!(partner.is_down(KeyCode::Slash));
+        assert!(partner.is_pressed(KeyCode::Period));
+
+        // Frames a controller makes up
+        let made = InputFrame::default()
+            .press(KeyCode::Enter)
+            .hold_action(Action::Interact);
+        assert!(made.is_down(KeyCode::Enter) && made.is_pressed(KeyCode::Enter));
+        assert_eq!(made.actions_pressed, 0);
+    }
 }
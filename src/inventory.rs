@@ -0,0 +1,251 @@
+use macroquad::prelude::*;
+
+use crate::item::{ItemType, draw_item_icon};
+use crate::save::{Fields, Persist};
+use crate::sprites::SpriteSheet;
+use crate::ui::{ui_height, ui_width};
+
+pub const INVENTORY_SLOTS: usize = 6;
+const MAX_STACK: u32 = 5; // Items of one kind that share a slot
+
+/// One occupied inventory slot: a stack of identical items
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slot {
+    pub item_type: ItemType,
+    pub count: u32,
+}
+
+/// Carried consumables and key items, limited to a fixed number of slots
+pub struct Inventory {
+    slots: Vec<Slot>,
+    capacity: usize,
+    pub selected: usize,
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: Vec::new(),
+            capacity,
+            selected: 0,
+        }
+    }
+
+    /// Add one item, stacking onto an existing slot if possible.
+    /// Returns false if there is no room.
+    pub fn add(&mut self, item_type: ItemType) -> bool {
+        if let Some(slot) = self
+            .slots
+            .iter_mut()
+            .find(|s| s.item_type == item_type && s.count < MAX_STACK)
+        {
+            slot.count += 1;
+            return true;
+        }
+        if self.slots.len() >= self.capacity {
+            return false;
+        }
+        self.slots.push(Slot {
+            item_type,
+            count: 1,
+        });
+        true
+    }
+
+    /// Total number of items of this type across all slots
+    pub fn count(&self, item_type: ItemType) -> u32 {
+        self.slots
+            .iter()
+            .filter(|s| s.item_type == item_type)
+            .map(|s| s.count)
+            .sum()
+    }
+
+    /// Remove one item of this type. Returns false if none is carried.
+    pub fn take(&mut self, item_type: ItemType) -> bool {
+        match self.slots.iter().rposition(|s| s.item_type == item_type) {
+            Some(idx) => {
+                self.take_slot(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove one item from the selected slot
+    pub fn take_selected(&mut self) -> Option<ItemType> {
+        if self.selected < self.slots.len() {
+            Some(self.take_slot(self.selected))
+        } else {
+            None
+        }
+    }
+
+    /// The item in the selected slot, if any
+    pub fn selected_item(&self) -> Option<ItemType> {
+        self.slots.get(self.selected).map(|s| s.item_type)
+    }
+
+    fn take_slot(&mut self, idx: usize) -> ItemType {
+        let slot = &mut self.slots[idx];
+        let item_type = slot.item_type;
+        slot.count -= 1;
+        if slot.count == 0 {
+            self.slots.remove(idx);
+            self.selected = self.selected.min(self.slots.len().saturating_sub(1));
+        }
+        item_type
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.slots.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Centered overlay listing every slot, with the selection highlighted
+    pub fn draw(&self, sprites: &SpriteSheet) {
+        let row_height = 36.0;
+        let width = 320.0;
+        let height = 90.0 + self.capacity as f32 * row_height;
+        let x = (ui_width() - width) / 2.0;
+        let y = (ui_height() - height) / 2.0;
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui_width(),
+            ui_height(),
+            Color::from_rgba(0, 0, 0, 120),
+        );
+        draw_rectangle(x, y, width, height, Color::from_rgba(15, 20, 30, 235));
+        draw_rectangle_lines(
+            x,
+            y,
+            width,
+            height,
+            2.0,
+            Color::from_rgba(100, 200, 255, 255),
+        );
+        draw_text(
+            &format!("INVENTORY  {}/{}", self.slots.len(), self.capacity),
+            x + 16.0,
+            y + 28.0,
+            24.0,
+            Color::from_rgba(100, 200, 255, 255),
+        );
+
+        for i in 0..self.capacity {
+            let row_y = y + 44.0 + i as f32 * row_height;
+            if i == self.selected && i < self.slots.len() {
+                draw_rectangle(
+                    x + 8.0,
+                    row_y,
+                    width - 16.0,
+                    row_height - 4.0,
+                    Color::from_rgba(100, 200, 255, 60),
+                );
+            }
+            match self.slots.get(i) {
+                Some(slot) => {
+                    draw_item_icon(slot.item_type, x + 32.0, row_y + 16.0, sprites);
+                    draw_text(slot.item_type.name(), x + 56.0, row_y + 22.0, 20.0, WHITE);
+                    draw_text(
+                        &format!("x{}", slot.count),
+                        x + width - 50.0,
+                        row_y + 22.0,
+                        20.0,
+                        LIGHTGRAY,
+                    );
+                }
+                None => {
+                    draw_text("-", x + 56.0, row_y + 22.0, 20.0, DARKGRAY);
+                }
+            }
+        }
+
+        draw_text(
+            "W/S select   E use   X drop   I close",
+            x + 16.0,
+            y + height - 14.0,
+            16.0,
+            GRAY,
+        );
+    }
+}
+
+impl Persist for Slot {
+    fn write(&self, out: &mut String) {
+        self.item_type.write(out);
+        self.count.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            item_type: fields.read()?,
+            count: fields.read()?,
+        })
+    }
+}
+
+impl Persist for Inventory {
+    fn write(&self, out: &mut String) {
+        self.capacity.write(out);
+        self.selected.write(out);
+        self.slots.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            capacity: fields.read()?,
+            selected: fields.read()?,
+            slots: fields.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::KeycardColor;
+
+    const KEYCARD: ItemType = ItemType::Keycard {
+        color: KeycardColor::Red,
+    };
+
+    #[test]
+    fn test_stacking_and_capacity() {
+        let mut inventory = Inventory::new(2);
+        for _ in 0..MAX_STACK + 1 {
+            assert!(inventory.add(ItemType::HealthPack));
+        }
+        assert_eq!(inventory.count(ItemType::HealthPack), MAX_STACK + 1);
+
+        // Both slots are taken by health packs now
+        assert!(!inventory.add(KEYCARD));
+        assert!(inventory.add(ItemType::HealthPack));
+    }
+
+    #[test]
+    fn test_take_frees_empty_slots() {
+        let mut inventory = Inventory::new(2);
+        inventory.add(KEYCARD);
+        inventory.add(ItemType::SpeedBoost);
+        inventory.selected = 1;
+
+        assert!(inventory.take(ItemType::SpeedBoost));
+        assert!(!inventory.take(ItemType::SpeedBoost));
+        assert_eq!(inventory.selected, 0);
+        assert_eq!(inventory.selected_item(), Some(KEYCARD));
+
+        assert_eq!(inventory.take_selected(), Some(KEYCARD));
+        assert_eq!(inventory.take_selected(), None);
+        assert!(inventory.add(ItemType::DataFragment));
+    }
+}
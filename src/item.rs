@@ -1,8 +1,16 @@
-use macroquad::rand;
+use macroquad::prelude::*;
 
+use crate::grenade::GrenadeKind;
+use crate::save::{Fields, Persist, persist_enum, push_word};
 use crate::sprites::{SpriteSheet, items};
-use crate::tile_map::TILE_SIZE;
-use crate::weapon::Weapon;
+use crate::tile_map::{EntityType, TILE_SIZE, TileMap};
+use crate::weapon::{AmmoKind, Attachment, Weapon};
+
+const MAGNET_ACCELERATION: f32 = 900.0; // Pixels/s^2 toward the player while pulled
+const MAGNET_MAX_SPEED: f32 = 320.0;
+const ITEM_FRICTION: f32 = 6.0; // Velocity decay per second once released
+const BOB_HEIGHT: f32 = 3.0; // Pixels floor items float up and down
+const BOB_SPEED: f32 = 3.0; // Radians per second
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum WeaponKind {
@@ -12,7 +20,21 @@ pub enum WeaponKind {
     Rifle,
 }
 
+persist_enum!(WeaponKind {
+    Pistol,
+    Shotgun,
+    MachinePistol,
+    Rifle
+});
+
 impl WeaponKind {
+    pub const ALL: [WeaponKind; 4] = [
+        WeaponKind::Pistol,
+        WeaponKind::Shotgun,
+        WeaponKind::MachinePistol,
+        WeaponKind::Rifle,
+    ];
+
     pub fn to_weapon(self) -> Weapon {
         match self {
             WeaponKind::Pistol => Weapon::pistol(),
@@ -22,7 +44,13 @@ impl WeaponKind {
         }
     }
 
-    #[allow(dead_code)]
+    /// Kind of a carried weapon, or None for the knife
+    pub fn of(weapon: &Weapon) -> Option<Self> {
+        WeaponKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == weapon.name)
+    }
+
     pub fn name(self) -> &'static str {
         match self {
             WeaponKind::Pistol => "Pistol",
@@ -33,17 +61,178 @@ impl WeaponKind {
     }
 }
 
+/// Drop rarity; rarer weapons come with attachments pre-installed
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+persist_enum!(Rarity { Common, Rare, Epic });
+
+impl Rarity {
+    /// Roll a rarity with the given percent chances for rare and epic
+    pub fn roll(rare_percent: u32, epic_percent: u32) -> Self {
+        let roll = rand::gen_range(0, 100);
+        if roll < epic_percent {
+            Rarity::Epic
+        } else if roll < epic_percent + rare_percent {
+            Rarity::Rare
+        } else {
+            Rarity::Common
+        }
+    }
+
+    /// Attachments pre-installed on a weapon of this rarity
+    pub fn attachment_count(self) -> usize {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Rare => 1,
+            Rarity::Epic => 2,
+        }
+    }
+
+    /// Glow color, or None for common items
+    pub fn glow_color(self) -> Option<Color> {
+        match self {
+            Rarity::Common => None,
+            Rarity::Rare => Some(Color::from_rgba(80, 160, 255, 255)),
+            Rarity::Epic => Some(Color::from_rgba(190, 90, 255, 255)),
+        }
+    }
+}
+
+/// Keycard access color; a card opens doors and terminals of its own color
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeycardColor {
+    Red,
+    Blue,
+    Yellow,
+}
+
+persist_enum!(KeycardColor { Red, Blue, Yellow });
+
+impl KeycardColor {
+    pub const ALL: [KeycardColor; 3] =
+        [KeycardColor::Red, KeycardColor::Blue, KeycardColor::Yellow];
+
+    pub fn random() -> Self {
+        Self::ALL[rand::gen_range(0, Self::ALL.len())]
+    }
+
+    pub fn card_name(self) -> &'static str {
+        match self {
+            KeycardColor::Red => "Red Keycard",
+            KeycardColor::Blue => "Blue Keycard",
+            KeycardColor::Yellow => "Yellow Keycard",
+        }
+    }
+
+    /// Shown when the player tries a lock without the matching card
+    pub fn locked_message(self) -> &'static str {
+        match self {
+            KeycardColor::Red => "LOCKED - needs a RED keycard",
+            KeycardColor::Blue => "LOCKED - needs a BLUE keycard",
+            KeycardColor::Yellow => "LOCKED - needs a YELLOW keycard",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            KeycardColor::Red => Color::from_rgba(235, 70, 70, 255),
+            KeycardColor::Blue => Color::from_rgba(70, 140, 255, 255),
+            KeycardColor::Yellow => Color::from_rgba(255, 210, 60, 255),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ItemType {
     Weapon(WeaponKind),
     HealthPack,
     SpeedBoost,
     Invulnerability,
+    Keycard { color: KeycardColor },
+    Overclock, // Rare: permanently speeds up hacking for the run
+    DataFragment,
+    Ammo(AmmoKind),
+    ArmorPlate,
+    Grenade(GrenadeKind),
+    Credits(u32),
+    Magnet,     // Pulls nearby floor items toward the player
+    Shield,     // Regenerating energy shield over health
+    Decoy,      // Deployable hologram that draws hostile fire
+    Teleporter, // First use drops a beacon, second use jumps back to it
+    Scanner,    // Reveals bots and terminals through walls for a while
+    Backpack,   // Permanent extra weapon slot
+    DataChip,   // Hidden collectible; finding every one pays a score bonus
 }
 
 impl ItemType {
-    fn sprite_index(self) -> u32 {
+    /// Rarity an item always has, regardless of where it dropped
+    fn base_rarity(self) -> Rarity {
         match self {
+            ItemType::Overclock => Rarity::Epic,
+            ItemType::Keycard { .. } | ItemType::DataFragment => Rarity::Rare,
+            _ => Rarity::Common,
+        }
+    }
+
+    /// Item with this name, ignoring case and with `_` for spaces; credits
+    /// come as a stack of 100
+    pub fn from_name(name: &str) -> Option<ItemType> {
+        let name = name.replace('_', " ");
+        let mut all = vec![
+            ItemType::HealthPack,
+            ItemType::SpeedBoost,
+            ItemType::Invulnerability,
+            ItemType::Overclock,
+            ItemType::DataFragment,
+            ItemType::ArmorPlate,
+            ItemType::Credits(100),
+            ItemType::Magnet,
+            ItemType::Shield,
+            ItemType::Decoy,
+            ItemType::Teleporter,
+            ItemType::Scanner,
+            ItemType::Backpack,
+            ItemType::DataChip,
+        ];
+        all.extend(WeaponKind::ALL.map(ItemType::Weapon));
+        all.extend(AmmoKind::ALL.map(ItemType::Ammo));
+        all.extend(GrenadeKind::ALL.map(ItemType::Grenade));
+        all.extend(KeycardColor::ALL.map(|color| ItemType::Keycard { color }));
+        all.into_iter()
+            .find(|item| item.name().eq_ignore_ascii_case(&name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemType::Weapon(kind) => kind.name(),
+            ItemType::HealthPack => "Health Pack",
+            ItemType::SpeedBoost => "Speed Boost",
+            ItemType::Invulnerability => "Invulnerability",
+            ItemType::Keycard { color } => color.card_name(),
+            ItemType::Overclock => "Overclock Module",
+            ItemType::DataFragment => "Data Fragment",
+            ItemType::Ammo(kind) => kind.name(),
+            ItemType::ArmorPlate => "Armor Plate",
+            ItemType::Grenade(kind) => kind.name(),
+            ItemType::Credits(_) => "Credits",
+            ItemType::Magnet => "Magnet",
+            ItemType::Shield => "Energy Shield",
+            ItemType::Decoy => "Decoy Hologram",
+            ItemType::Teleporter => "Teleporter",
+            ItemType::Scanner => "Scanner",
+            ItemType::Backpack => "Backpack",
+            ItemType::DataChip => "Data Chip",
+        }
+    }
+
+    /// Sprite sheet index, or None for items drawn with shapes
+    fn sprite_index(self) -> Option<u32> {
+        let index = match self {
             ItemType::Weapon(WeaponKind::Pistol) => items::PISTOL,
             ItemType::Weapon(WeaponKind::Shotgun) => items::SHOTGUN,
             ItemType::Weapon(WeaponKind::MachinePistol) => items::MACHINE_PISTOL,
@@ -51,15 +240,115 @@ impl ItemType {
             ItemType::HealthPack => items::HEALTH_PACK,
             ItemType::SpeedBoost => items::SPEED_BOOST,
             ItemType::Invulnerability => items::INVULNERABILITY,
+            ItemType::Keycard { .. }
+            | ItemType::Overclock
+            | ItemType::DataFragment
+            | ItemType::Ammo(_)
+            | ItemType::ArmorPlate
+            | ItemType::Grenade(_)
+            | ItemType::Credits(_)
+            | ItemType::Magnet
+            | ItemType::Shield
+            | ItemType::Decoy
+            | ItemType::Teleporter
+            | ItemType::Scanner
+            | ItemType::Backpack
+            | ItemType::DataChip => return None,
+        };
+        Some(index)
+    }
+}
+
+/// Saved as the variant name, followed by its payload if it has one
+impl Persist for ItemType {
+    fn write(&self, out: &mut String) {
+        let name = match self {
+            ItemType::Weapon(_) => "Weapon",
+            ItemType::HealthPack => "HealthPack",
+            ItemType::SpeedBoost => "SpeedBoost",
+            ItemType::Invulnerability => "Invulnerability",
+            ItemType::Keycard { .. } => "Keycard",
+            ItemType::Overclock => "Overclock",
+            ItemType::DataFragment => "DataFragment",
+            ItemType::Ammo(_) => "Ammo",
+            ItemType::ArmorPlate => "ArmorPlate",
+            ItemType::Grenade(_) => "Grenade",
+            ItemType::Credits(_) => "Credits",
+            ItemType::Magnet => "Magnet",
+            ItemType::Shield => "Shield",
+            ItemType::Decoy => "Decoy",
+            ItemType::Teleporter => "Teleporter",
+            ItemType::Scanner => "Scanner",
+            ItemType::Backpack => "Backpack",
+            ItemType::DataChip => "DataChip",
+        };
+        push_word(out, name);
+        match self {
+            ItemType::Weapon(kind) => kind.write(out),
+            ItemType::Keycard { color } => color.write(out),
+            ItemType::Ammo(kind) => kind.write(out),
+            ItemType::Grenade(kind) => kind.write(out),
+            ItemType::Credits(amount) => amount.write(out),
+            _ => {}
         }
     }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let word = fields.word()?;
+        Ok(match word {
+            "Weapon" => ItemType::Weapon(fields.read()?),
+            "HealthPack" => ItemType::HealthPack,
+            "SpeedBoost" => ItemType::SpeedBoost,
+            "Invulnerability" => ItemType::Invulnerability,
+            "Keycard" => ItemType::Keycard {
+                color: fields.read()?,
+            },
+            "Overclock" => ItemType::Overclock,
+            "DataFragment" => ItemType::DataFragment,
+            "Ammo" => ItemType::Ammo(fields.read()?),
+            "ArmorPlate" => ItemType::ArmorPlate,
+            "Grenade" => ItemType::Grenade(fields.read()?),
+            "Credits" => ItemType::Credits(fields.read()?),
+            "Magnet" => ItemType::Magnet,
+            "Shield" => ItemType::Shield,
+            "Decoy" => ItemType::Decoy,
+            "Teleporter" => ItemType::Teleporter,
+            "Scanner" => ItemType::Scanner,
+            "Backpack" => ItemType::Backpack,
+            "DataChip" => ItemType::DataChip,
+            _ => return Err(fields.error(word)),
+        })
+    }
+}
+
+/// Saved as its kind ("-" for the knife) and attachments; the stats are
+/// rebuilt from those
+impl Persist for Weapon {
+    fn write(&self, out: &mut String) {
+        WeaponKind::of(self).write(out);
+        self.attachments.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let kind: Option<WeaponKind> = fields.read()?;
+        let attachments: Vec<Attachment> = fields.read()?;
+        let weapon = kind.map_or_else(Weapon::knife, WeaponKind::to_weapon);
+        Ok(attachments
+            .into_iter()
+            .fold(weapon, Weapon::with_attachment))
+    }
 }
 
 pub struct Item {
     pub x: f32,
     pub y: f32,
     pub item_type: ItemType,
+    pub rarity: Rarity,
     pub alive: bool,
+    /// A weapon the player put down, kept as-is (attachments, no ammo)
+    pub weapon: Option<Weapon>,
+    vx: f32,
+    vy: f32,
 }
 
 impl Item {
@@ -68,77 +357,404 @@ impl Item {
             x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
             y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
             item_type,
+            rarity: item_type.base_rarity(),
             alive: true,
+            weapon: None,
+            vx: 0.0,
+            vy: 0.0,
         }
     }
 
-    pub fn tile_position(&self) -> (i32, i32) {
-        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
-    }
-
-    /// Random item for floor spawns (common items only)
-    pub fn random_floor_item(tile_x: i32, tile_y: i32) -> Self {
-        let item_type = match rand::gen_range(0, 10) {
-            0..=4 => ItemType::Weapon(WeaponKind::Pistol), // 50% pistol
-            5..=9 => ItemType::HealthPack,                 // 50% health
-            _ => ItemType::HealthPack,
-        };
-        Self::new(tile_x, tile_y, item_type)
+    /// Accelerate toward `pull` (a magnet target) if given, otherwise coast
+    /// to a stop. Items stop dead at anything a player can't walk onto, so
+    /// they never end up out of reach inside a wall.
+    pub fn update(&mut self, dt: f32, pull: Option<(f32, f32)>, map: &TileMap) {
+        match pull {
+            Some((tx, ty)) => {
+                let (dx, dy) = (tx - self.x, ty - self.y);
+                let dist = dx.hypot(dy);
+                if dist > 1.0 {
+                    self.vx += dx / dist * MAGNET_ACCELERATION * dt;
+                    self.vy += dy / dist * MAGNET_ACCELERATION * dt;
+                }
+                let speed = self.vx.hypot(self.vy);
+                if speed > MAGNET_MAX_SPEED {
+                    self.vx *= MAGNET_MAX_SPEED / speed;
+                    self.vy *= MAGNET_MAX_SPEED / speed;
+                }
+                // Don't overshoot the target
+                if speed * dt >= dist {
+                    self.x = tx;
+                    self.y = ty;
+                    self.vx = 0.0;
+                    self.vy = 0.0;
+                    return;
+                }
+            }
+            None => {
+                let damping = (1.0 - ITEM_FRICTION * dt).max(0.0);
+                self.vx *= damping;
+                self.vy *= damping;
+            }
+        }
+        let (x, y) = (self.x + self.vx * dt, self.y + self.vy * dt);
+        let tile = (
+            (x / TILE_SIZE).floor() as i32,
+            (y / TILE_SIZE).floor() as i32,
+        );
+        if !map.is_walkable_by(tile.0, tile.1, EntityType::Player) {
+            self.vx = 0.0;
+            self.vy = 0.0;
+            return;
+        }
+        self.x = x;
+        self.y = y;
     }
 
-    /// Random item drop from destroyed crate
-    pub fn random_crate_drop(tile_x: i32, tile_y: i32) -> Option<Self> {
-        // 60% chance to drop something
-        if rand::gen_range(0, 10) >= 6 {
-            return None;
+    /// Roll a rarity for weapon drops (other items keep their base rarity)
+    pub fn with_rolled_rarity(mut self, rare_percent: u32, epic_percent: u32) -> Self {
+        if matches!(self.item_type, ItemType::Weapon(_)) {
+            self.rarity = Rarity::roll(rare_percent, epic_percent);
         }
+        self
+    }
 
-        let item_type = match rand::gen_range(0, 20) {
-            0..=5 => ItemType::HealthPack,                          // 30% health
-            6..=10 => ItemType::Weapon(WeaponKind::Pistol),         // 25% pistol
-            11..=14 => ItemType::Weapon(WeaponKind::Shotgun),       // 20% shotgun
-            15..=16 => ItemType::Weapon(WeaponKind::MachinePistol), // 10% MP
-            17..=18 => ItemType::SpeedBoost,                        // 10% speed
-            19 => ItemType::Invulnerability,                        // 5% invuln
-            _ => ItemType::HealthPack,
+    /// A weapon put down by the player; rarity follows its attachments
+    pub fn dropped_weapon(tile_x: i32, tile_y: i32, kind: WeaponKind, weapon: Weapon) -> Self {
+        let rarity = match weapon.attachments.len() {
+            0 => Rarity::Common,
+            1 => Rarity::Rare,
+            _ => Rarity::Epic,
         };
-        Some(Self::new(tile_x, tile_y, item_type))
+        Self {
+            rarity,
+            weapon: Some(weapon),
+            ..Self::new(tile_x, tile_y, ItemType::Weapon(kind))
+        }
     }
 
-    /// Random item drop from destroyed wall (higher tier)
-    pub fn random_wall_drop(tile_x: i32, tile_y: i32) -> Option<Self> {
-        // 40% chance to drop something
-        if rand::gen_range(0, 10) >= 4 {
-            return None;
-        }
+    pub fn tile_position(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
 
-        let item_type = match rand::gen_range(0, 20) {
-            0..=3 => ItemType::HealthPack,                         // 20% health
-            4..=7 => ItemType::Weapon(WeaponKind::Shotgun),        // 20% shotgun
-            8..=11 => ItemType::Weapon(WeaponKind::MachinePistol), // 20% MP
-            12..=15 => ItemType::Weapon(WeaponKind::Rifle),        // 20% rifle
-            16..=17 => ItemType::SpeedBoost,                       // 10% speed
-            18..=19 => ItemType::Invulnerability,                  // 10% invuln
-            _ => ItemType::HealthPack,
-        };
-        Some(Self::new(tile_x, tile_y, item_type))
+    /// Current float height; the phase depends on the spawn tile so
+    /// neighbouring items don't bob in lockstep
+    fn bob_offset(&self) -> f32 {
+        let (tx, ty) = self.tile_position();
+        let phase = (tx * 7 + ty * 13) as f32 * 0.7;
+        ((get_time() as f32 * BOB_SPEED + phase).sin() * 0.5 + 0.5) * BOB_HEIGHT
     }
 
     pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
         if !self.alive {
             return;
         }
+        let bob = self.bob_offset();
+        let (sx, sy) = (self.x - camera_x, self.y - camera_y - bob);
+
+        // Drop shadow stays on the floor and shrinks as the item rises
+        let shadow_scale = 1.0 - bob / BOB_HEIGHT * 0.25;
+        draw_ellipse(
+            self.x - camera_x,
+            self.y - camera_y + 11.0,
+            9.0 * shadow_scale,
+            3.0 * shadow_scale,
+            0.0,
+            Color::from_rgba(0, 0, 0, 90),
+        );
+
+        // Rare and epic items glow with a pulsing outline
+        if let Some(color) = self.rarity.glow_color() {
+            let pulse = (get_time() * 4.0).sin() as f32 * 0.5 + 0.5;
+            draw_circle(
+                sx,
+                sy,
+                15.0,
+                Color {
+                    a: 0.15 + 0.15 * pulse,
+                    ..color
+                },
+            );
+            draw_circle_lines(sx, sy, 14.0 + pulse * 2.0, 2.0, color);
+        }
+
+        draw_item_icon(self.item_type, sx, sy, sprites);
+    }
+}
+
+impl Persist for Item {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.item_type.write(out);
+        self.rarity.write(out);
+        self.weapon.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            item_type: fields.read()?,
+            rarity: fields.read()?,
+            alive: true,
+            weapon: fields.read()?,
+            vx: 0.0,
+            vy: 0.0,
+        })
+    }
+}
+
+/// Item sprite or glyph centered on the given screen position
+pub fn draw_item_icon(item_type: ItemType, x: f32, y: f32, sprites: &SpriteSheet) {
+    match item_type.sprite_index() {
+        Some(sprite_idx) => sprites.draw_item(sprite_idx, x, y),
+        None => match item_type {
+            ItemType::Overclock => draw_overclock(x, y),
+            ItemType::DataFragment => draw_data_fragment(x, y),
+            ItemType::Ammo(kind) => draw_ammo(kind, x, y),
+            ItemType::ArmorPlate => draw_armor_plate(x, y),
+            ItemType::Grenade(kind) => draw_grenade(kind, x, y),
+            ItemType::Credits(_) => draw_credits(x, y),
+            ItemType::Magnet => draw_magnet(x, y),
+            ItemType::Shield => draw_shield(x, y),
+            ItemType::Decoy => draw_decoy(x, y),
+            ItemType::Teleporter => draw_teleporter(x, y),
+            ItemType::Scanner => draw_scanner(x, y),
+            ItemType::Backpack => draw_backpack(x, y),
+            ItemType::DataChip => draw_data_chip(x, y),
+            ItemType::Keycard { color } => draw_keycard(x, y, color),
+            _ => {}
+        },
+    }
+}
+
+/// Small medkit glyph (white case with a red cross) for the HUD
+pub fn draw_medkit(x: f32, y: f32) {
+    draw_rectangle(x - 7.0, y - 6.0, 14.0, 12.0, WHITE);
+    draw_rectangle_lines(x - 7.0, y - 6.0, 14.0, 12.0, 1.0, DARKGRAY);
+    let red = Color::from_rgba(220, 40, 40, 255);
+    draw_rectangle(x - 1.5, y - 4.0, 3.0, 8.0, red);
+    draw_rectangle(x - 4.0, y - 1.5, 8.0, 3.0, red);
+}
+
+/// Keycard glyph in its access color, centered on the given screen position
+pub fn draw_keycard(x: f32, y: f32, color: KeycardColor) {
+    let card = color.color();
+    draw_rectangle(x - 8.0, y - 5.0, 16.0, 10.0, card);
+    draw_rectangle(
+        x - 6.0,
+        y - 2.0,
+        5.0,
+        4.0,
+        Color::new(card.r * 0.45, card.g * 0.45, card.b * 0.45, 1.0),
+    );
+    draw_rectangle_lines(x - 8.0, y - 5.0, 16.0, 10.0, 1.0, BLACK);
+}
+
+/// Data fragment glyph: a glowing diamond
+pub fn draw_data_fragment(x: f32, y: f32) {
+    let color = Color::from_rgba(120, 200, 255, 255);
+    let top = Vec2::new(x, y - 9.0);
+    let bottom = Vec2::new(x, y + 9.0);
+    let left = Vec2::new(x - 6.0, y);
+    let right = Vec2::new(x + 6.0, y);
+    draw_circle(x, y, 10.0, Color::from_rgba(120, 200, 255, 60));
+    draw_triangle(top, left, right, color);
+    draw_triangle(bottom, left, right, Color::from_rgba(70, 140, 220, 255));
+}
+
+/// Data chip glyph: a small circuit board with gold pins
+pub fn draw_data_chip(x: f32, y: f32) {
+    for i in 0..3 {
+        let py = y - 4.0 + i as f32 * 4.0;
+        draw_line(x - 8.0, py, x + 8.0, py, 1.5, DATA_CHIP_COLOR);
+    }
+    draw_rectangle(
+        x - 5.0,
+        y - 6.0,
+        10.0,
+        12.0,
+        Color::from_rgba(30, 90, 50, 255),
+    );
+    draw_rectangle_lines(x - 5.0, y - 6.0, 10.0, 12.0, 1.0, DATA_CHIP_COLOR);
+    draw_circle(x, y, 1.5, DATA_CHIP_COLOR);
+}
+
+pub const DATA_CHIP_COLOR: Color = Color::new(1.0, 0.85, 0.3, 1.0);
+
+/// Ammo box glyph, colored by ammo kind
+fn draw_ammo(kind: AmmoKind, x: f32, y: f32) {
+    let color = ammo_color(kind);
+    for i in 0..3 {
+        let bx = x - 6.0 + i as f32 * 5.0;
+        draw_rectangle(bx, y - 9.0, 3.0, 6.0, color);
+    }
+    draw_rectangle(
+        x - 9.0,
+        y - 4.0,
+        18.0,
+        12.0,
+        Color::from_rgba(70, 80, 60, 255),
+    );
+    draw_rectangle(x - 9.0, y, 18.0, 3.0, color);
+    draw_rectangle_lines(x - 9.0, y - 4.0, 18.0, 12.0, 1.0, BLACK);
+}
+
+/// Armor plate glyph: a small shield
+fn draw_armor_plate(x: f32, y: f32) {
+    draw_rectangle(x - 8.0, y - 9.0, 16.0, 10.0, ARMOR_COLOR);
+    draw_triangle(
+        Vec2::new(x - 8.0, y + 1.0),
+        Vec2::new(x + 8.0, y + 1.0),
+        Vec2::new(x, y + 10.0),
+        ARMOR_COLOR,
+    );
+    draw_line(
+        x,
+        y - 7.0,
+        x,
+        y + 6.0,
+        2.0,
+        Color::from_rgba(40, 60, 90, 255),
+    );
+}
 
-        let screen_x = self.x - camera_x;
-        let screen_y = self.y - camera_y;
-        let sprite_idx = self.item_type.sprite_index();
-        sprites.draw_item(sprite_idx, screen_x, screen_y);
+/// Grenade glyph: a body in the kind's color with a pin
+pub fn draw_grenade(kind: GrenadeKind, x: f32, y: f32) {
+    draw_circle(x, y + 2.0, 7.0, kind.color());
+    draw_circle_lines(x, y + 2.0, 7.0, 1.0, BLACK);
+    draw_rectangle(x - 2.0, y - 8.0, 4.0, 4.0, DARKGRAY);
+    draw_circle_lines(x + 4.0, y - 7.0, 2.5, 1.0, LIGHTGRAY);
+}
+
+/// Magnet glyph: a red horseshoe with silver tips
+fn draw_magnet(x: f32, y: f32) {
+    let red = Color::from_rgba(220, 50, 50, 255);
+    draw_arc(x, y, 16, 5.0, 0.0, 4.0, 180.0, red);
+    draw_rectangle(x - 9.0, y - 10.0, 4.0, 10.0, red);
+    draw_rectangle(x + 5.0, y - 10.0, 4.0, 10.0, red);
+    draw_rectangle(x - 9.0, y - 12.0, 4.0, 3.0, LIGHTGRAY);
+    draw_rectangle(x + 5.0, y - 12.0, 4.0, 3.0, LIGHTGRAY);
+}
+
+/// Shield glyph: a glowing energy ring around a core
+fn draw_shield(x: f32, y: f32) {
+    draw_circle(
+        x,
+        y,
+        10.0,
+        Color::new(SHIELD_COLOR.r, SHIELD_COLOR.g, SHIELD_COLOR.b, 0.25),
+    );
+    draw_circle_lines(x, y, 9.0, 2.0, SHIELD_COLOR);
+    draw_circle(x, y, 3.0, WHITE);
+}
+
+/// Decoy glyph: a hologram emitter projecting a faint figure
+fn draw_decoy(x: f32, y: f32) {
+    let glow = Color::from_rgba(140, 220, 255, 255);
+    draw_circle(x, y - 5.0, 3.5, Color::from_rgba(140, 220, 255, 140));
+    draw_triangle(
+        Vec2::new(x, y - 2.0),
+        Vec2::new(x - 6.0, y + 6.0),
+        Vec2::new(x + 6.0, y + 6.0),
+        Color::from_rgba(140, 220, 255, 90),
+    );
+    draw_rectangle(x - 7.0, y + 6.0, 14.0, 4.0, DARKGRAY);
+    draw_line(x - 5.0, y + 6.0, x + 5.0, y + 6.0, 1.0, glow);
+}
+
+/// Teleporter glyph: a handheld unit with a swirling lens
+fn draw_teleporter(x: f32, y: f32) {
+    draw_rectangle(
+        x - 6.0,
+        y - 9.0,
+        12.0,
+        18.0,
+        Color::from_rgba(70, 70, 90, 255),
+    );
+    draw_circle(x, y - 2.0, 4.5, TELEPORT_COLOR);
+    draw_circle_lines(x, y - 2.0, 4.5, 1.0, WHITE);
+    draw_rectangle(x - 4.0, y + 5.0, 8.0, 2.0, TELEPORT_COLOR);
+}
+
+/// Color of the teleporter, its beacon and the teleport shimmer
+pub const TELEPORT_COLOR: Color = Color::new(0.75, 0.45, 1.0, 1.0);
+
+/// Scanner glyph: a radar screen with a sweep line
+fn draw_scanner(x: f32, y: f32) {
+    draw_circle(x, y, 9.0, Color::from_rgba(20, 50, 30, 255));
+    draw_circle_lines(x, y, 9.0, 1.5, SCANNER_COLOR);
+    draw_circle_lines(x, y, 5.0, 1.0, SCANNER_COLOR);
+    draw_line(x, y, x + 6.0, y - 6.0, 1.5, SCANNER_COLOR);
+    draw_circle(x - 3.0, y + 3.0, 1.5, RED);
+}
+
+/// Color of the scanner glyph and its readout
+pub const SCANNER_COLOR: Color = Color::new(0.4, 1.0, 0.5, 1.0);
+
+/// Backpack glyph: a pack with a flap and strap
+fn draw_backpack(x: f32, y: f32) {
+    let canvas = Color::from_rgba(110, 130, 70, 255);
+    draw_rectangle(x - 7.0, y - 7.0, 14.0, 16.0, canvas);
+    draw_rectangle(
+        x - 7.0,
+        y - 7.0,
+        14.0,
+        6.0,
+        Color::from_rgba(80, 100, 50, 255),
+    );
+    draw_rectangle_lines(x - 7.0, y - 7.0, 14.0, 16.0, 1.0, BLACK);
+    draw_arc(x, y - 7.0, 12, 4.0, 180.0, 2.0, 180.0, DARKGRAY);
+    draw_rectangle(x - 2.0, y + 1.0, 4.0, 4.0, DARKGRAY);
+}
+
+/// Credit chip glyph: a gold coin
+pub fn draw_credits(x: f32, y: f32) {
+    draw_circle(x, y, 7.0, CREDITS_COLOR);
+    draw_circle_lines(x, y, 7.0, 1.0, Color::from_rgba(120, 90, 20, 255));
+    draw_circle_lines(x, y, 4.0, 1.0, Color::from_rgba(120, 90, 20, 255));
+}
+
+pub const CREDITS_COLOR: Color = Color::new(1.0, 0.82, 0.2, 1.0);
+
+/// Color used for the energy shield in glyphs, the bubble and the HUD
+pub const SHIELD_COLOR: Color = Color::new(0.4, 0.95, 1.0, 1.0);
+
+/// Color used for armor in glyphs and the HUD
+pub const ARMOR_COLOR: Color = Color::new(0.45, 0.65, 0.95, 1.0);
+
+/// Color used for an ammo kind in glyphs and the HUD
+pub fn ammo_color(kind: AmmoKind) -> Color {
+    match kind {
+        AmmoKind::Light => Color::from_rgba(240, 220, 100, 255),
+        AmmoKind::Shell => Color::from_rgba(230, 80, 60, 255),
+        AmmoKind::Heavy => Color::from_rgba(120, 220, 120, 255),
+    }
+}
+
+/// Overclock module glyph: a chip with pins
+fn draw_overclock(x: f32, y: f32) {
+    let color = Color::from_rgba(80, 255, 200, 255);
+    for i in 0..3 {
+        let offset = -5.0 + i as f32 * 5.0;
+        draw_line(x + offset, y - 10.0, x + offset, y + 10.0, 1.5, color);
+        draw_line(x - 10.0, y + offset, x + 10.0, y + offset, 1.5, color);
     }
+    draw_rectangle(
+        x - 7.0,
+        y - 7.0,
+        14.0,
+        14.0,
+        Color::from_rgba(30, 40, 50, 255),
+    );
+    draw_rectangle_lines(x - 7.0, y - 7.0, 14.0, 14.0, 1.5, color);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tile_map::TileType;
 
     #[test]
     fn test_item_creation() {
@@ -154,14 +770,67 @@ mod tests {
     }
 
     #[test]
-    fn test_floor_item_types() {
-        // Just verify it doesn't panic
-        for _ in 0..20 {
-            let item = Item::random_floor_item(0, 0);
-            assert!(matches!(
-                item.item_type,
-                ItemType::Weapon(WeaponKind::Pistol) | ItemType::HealthPack
-            ));
+    fn test_magnet_pull() {
+        let map = TileMap::new(10, 10);
+        let mut item = Item::new(0, 0, ItemType::HealthPack);
+        let target = (item.x + 100.0, item.y);
+        for _ in 0..120 {
+            item.update(1.0 / 60.0, Some(target), &map);
+        }
+        assert_eq!((item.x, item.y), target);
+
+        // Released items coast to a stop
+        item.vx = 100.0;
+        for _ in 0..120 {
+            item.update(1.0 / 60.0, None, &map);
+        }
+        assert!(item.vx.abs() < 1.0);
+    }
+
+    #[test]
+    fn test_released_item_stops_at_wall() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(4, 3, TileType::Wall);
+        let mut item = Item::new(3, 3, ItemType::HealthPack);
+        item.vx = 400.0;
+        for _ in 0..120 {
+            item.update(1.0 / 60.0, None, &map);
         }
+        assert_eq!(item.tile_position(), (3, 3));
+        assert_eq!(item.vx, 0.0);
+    }
+
+    #[test]
+    fn test_rarity() {
+        assert_eq!(Rarity::roll(0, 0), Rarity::Common);
+        assert_eq!(Rarity::roll(0, 100), Rarity::Epic);
+        assert_eq!(Rarity::roll(100, 0), Rarity::Rare);
+        assert!(Rarity::Common.glow_color().is_none());
+
+        // Only weapons roll; special items keep their base rarity
+        let item = Item::new(0, 0, ItemType::HealthPack).with_rolled_rarity(0, 100);
+        assert_eq!(item.rarity, Rarity::Common);
+        let item = Item::new(0, 0, ItemType::Weapon(WeaponKind::Rifle)).with_rolled_rarity(0, 100);
+        assert_eq!(item.rarity, Rarity::Epic);
+        assert_eq!(Item::new(0, 0, ItemType::Overclock).rarity, Rarity::Epic);
+    }
+
+    #[test]
+    fn test_item_from_name() {
+        assert_eq!(
+            ItemType::from_name("machine_pistol"),
+            Some(ItemType::Weapon(WeaponKind::MachinePistol))
+        );
+        assert_eq!(
+            ItemType::from_name("EMP_Grenade"),
+            Some(ItemType::Grenade(GrenadeKind::Emp))
+        );
+        assert_eq!(
+            ItemType::from_name("red_keycard"),
+            Some(ItemType::Keycard {
+                color: KeycardColor::Red
+            })
+        );
+        assert_eq!(ItemType::from_name("bazooka"), None);
     }
 }
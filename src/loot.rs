@@ -0,0 +1,242 @@
+use macroquad::prelude::*;
+
+use crate::grenade::GrenadeKind;
+use crate::item::{Item, ItemType, WeaponKind};
+use crate::weapon::AmmoKind;
+
+const LOOT_FILE: &str = "data/loot.txt";
+/// Built-in copy of the loot file, used when the file can't be read
+const DEFAULT_LOOT: &str = include_str!("../data/loot.txt");
+
+/// Where a drop comes from; each source has its own table
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LootSource {
+    Floor, // Items lying around at map start
+    Crate,
+    Wall, // Destructible walls (higher tier)
+}
+
+/// Weighted drop table for one source
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootTable {
+    pub chance: f32, // Probability that anything drops
+    pub rare_percent: u32,
+    pub epic_percent: u32,
+    pub entries: Vec<(ItemType, u32)>,
+}
+
+impl LootTable {
+    fn empty() -> Self {
+        Self {
+            chance: 1.0,
+            rare_percent: 0,
+            epic_percent: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Pick an item type by weight (ignoring the drop chance)
+    pub fn pick(&self) -> Option<ItemType> {
+        let total: u32 = self.entries.iter().map(|&(_, w)| w).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rand::gen_range(0, total);
+        for &(item_type, weight) in &self.entries {
+            if roll < weight {
+                return Some(item_type);
+            }
+            roll -= weight;
+        }
+        None
+    }
+
+    /// Roll the drop chance (scaled by `drop_rate`), then an item and its rarity
+    pub fn roll(&self, drop_rate: f32, tile_x: i32, tile_y: i32) -> Option<Item> {
+        if rand::gen_range(0.0, 1.0) >= self.chance * drop_rate {
+            return None;
+        }
+        self.pick().map(|item_type| {
+            Item::new(tile_x, tile_y, item_type)
+                .with_rolled_rarity(self.rare_percent, self.epic_percent)
+        })
+    }
+}
+
+/// Loot tables for every drop source
+#[derive(Clone, Debug, PartialEq)]
+pub struct LootTables {
+    floor: LootTable,
+    crates: LootTable,
+    walls: LootTable,
+    pub drop_rate: f32, // Difficulty multiplier on every table's drop chance
+    pub only_weapon: Option<WeaponKind>, // Daily challenge: every weapon drop becomes this
+}
+
+impl Default for LootTables {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LOOT).expect("built-in loot table is valid")
+    }
+}
+
+impl LootTables {
+    /// Load tables from the loot file, falling back to the built-in copy
+    pub async fn load() -> Self {
+        match load_string(LOOT_FILE).await {
+            Ok(text) => Self::parse(&text).unwrap_or_else(|err| {
+                eprintln!("Invalid {LOOT_FILE} ({err}) - using built-in loot tables");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn table(&self, source: LootSource) -> &LootTable {
+        match source {
+            LootSource::Floor => &self.floor,
+            LootSource::Crate => &self.crates,
+            LootSource::Wall => &self.walls,
+        }
+    }
+
+    pub fn roll(&self, source: LootSource, tile_x: i32, tile_y: i32) -> Option<Item> {
+        let mut item = self.table(source).roll(self.drop_rate, tile_x, tile_y)?;
+        if let (ItemType::Weapon(_), Some(kind)) = (item.item_type, self.only_weapon) {
+            item.item_type = ItemType::Weapon(kind);
+        }
+        Some(item)
+    }
+
+    /// A guaranteed drop from the wall table (side objective rewards)
+    pub fn reward(&self, tile_x: i32, tile_y: i32) -> Option<Item> {
+        let walls = &self.walls;
+        let mut item = Item::new(tile_x, tile_y, walls.pick()?)
+            .with_rolled_rarity(walls.rare_percent, walls.epic_percent);
+        if let (ItemType::Weapon(_), Some(kind)) = (item.item_type, self.only_weapon) {
+            item.item_type = ItemType::Weapon(kind);
+        }
+        Some(item)
+    }
+
+    /// Parse the loot file format (see data/loot.txt)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tables = [LootTable::empty(), LootTable::empty(), LootTable::empty()];
+        let mut current: Option<usize> = None;
+
+        for (number, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", number + 1, msg);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(match name {
+                    "floor" => 0,
+                    "crate" => 1,
+                    "wall" => 2,
+                    _ => return Err(err(&format!("unknown source '{name}'"))),
+                });
+                continue;
+            }
+            let table = &mut tables[current.ok_or_else(|| err("entry before any [section]"))?];
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["chance", value] => {
+                    table.chance = value
+                        .parse()
+                        .ok()
+                        .filter(|c: &f32| (0.0..=1.0).contains(c))
+                        .ok_or_else(|| err("chance must be between 0 and 1"))?;
+                }
+                ["rarity", rare, epic] => {
+                    table.rare_percent = rare.parse().map_err(|_| err("bad rare percent"))?;
+                    table.epic_percent = epic.parse().map_err(|_| err("bad epic percent"))?;
+                }
+                [name, weight] => {
+                    let item_type =
+                        item_by_name(name).ok_or_else(|| err(&format!("unknown item '{name}'")))?;
+                    let weight = weight.parse().map_err(|_| err("bad weight"))?;
+                    table.entries.push((item_type, weight));
+                }
+                _ => return Err(err("expected '<item> <weight>'")),
+            }
+        }
+
+        let [floor, crates, walls] = tables;
+        Ok(Self {
+            floor,
+            crates,
+            walls,
+            drop_rate: 1.0,
+            only_weapon: None,
+        })
+    }
+}
+
+/// Item type for a loot file name
+fn item_by_name(name: &str) -> Option<ItemType> {
+    Some(match name {
+        "pistol" => ItemType::Weapon(WeaponKind::Pistol),
+        "shotgun" => ItemType::Weapon(WeaponKind::Shotgun),
+        "machine_pistol" => ItemType::Weapon(WeaponKind::MachinePistol),
+        "rifle" => ItemType::Weapon(WeaponKind::Rifle),
+        "health_pack" => ItemType::HealthPack,
+        "speed_boost" => ItemType::SpeedBoost,
+        "invulnerability" => ItemType::Invulnerability,
+        "magnet" => ItemType::Magnet,
+        "shield" => ItemType::Shield,
+        "decoy" => ItemType::Decoy,
+        "teleporter" => ItemType::Teleporter,
+        "scanner" => ItemType::Scanner,
+        "backpack" => ItemType::Backpack,
+        "overclock" => ItemType::Overclock,
+        "armor_plate" => ItemType::ArmorPlate,
+        "light_ammo" => ItemType::Ammo(AmmoKind::Light),
+        "shells" => ItemType::Ammo(AmmoKind::Shell),
+        "heavy_ammo" => ItemType::Ammo(AmmoKind::Heavy),
+        "frag_grenade" => ItemType::Grenade(GrenadeKind::Frag),
+        "emp_grenade" => ItemType::Grenade(GrenadeKind::Emp),
+        "smoke_grenade" => ItemType::Grenade(GrenadeKind::Smoke),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tables() {
+        let tables = LootTables::default();
+        assert_eq!(tables.table(LootSource::Floor).chance, 1.0);
+        assert_eq!(tables.table(LootSource::Crate).rare_percent, 12);
+        assert!(!tables.table(LootSource::Wall).entries.is_empty());
+    }
+
+    #[test]
+    fn test_floor_item_types() {
+        let tables = LootTables::default();
+        for _ in 0..20 {
+            let item = tables.roll(LootSource::Floor, 0, 0).unwrap();
+            assert!(matches!(
+                item.item_type,
+                ItemType::Weapon(WeaponKind::Pistol) | ItemType::HealthPack
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(LootTables::parse("pistol 1").is_err());
+        assert!(LootTables::parse("[attic]").is_err());
+        assert!(LootTables::parse("[crate]\nchance 2").is_err());
+        assert!(LootTables::parse("[crate]\nbanana 3").is_err());
+
+        let tables = LootTables::parse("[wall] # comment\nrifle 1\n").unwrap();
+        let wall = tables.table(LootSource::Wall);
+        assert_eq!(wall.pick(), Some(ItemType::Weapon(WeaponKind::Rifle)));
+        assert!(tables.table(LootSource::Crate).pick().is_none());
+    }
+}
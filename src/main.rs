@@ -1,24 +1,68 @@
+mod ambience;
 mod audio;
+mod campaign;
+mod class;
+mod console;
+mod coop;
+mod daily;
+mod damage;
+mod debug;
+mod decoy;
+mod demo;
+mod difficulty;
+mod endless;
 mod entity;
+mod events;
 mod game;
+mod grenade;
 mod input;
+mod inventory;
 mod item;
+mod loot;
+mod meta;
+mod mixer;
+mod mode;
+mod music;
+mod net;
+mod objective;
+mod particles;
+mod perk;
 mod projectile;
+mod pylon;
+mod rules;
+mod save;
+mod security;
+mod settings;
+mod shake;
+mod shop;
+mod skills;
+mod skin;
+mod speedrun;
 mod sprites;
+mod stats;
 mod terminal;
+mod thrown;
 mod tile_map;
+mod turret;
+mod ui;
+mod ultimate;
+mod versus;
 mod weapon;
 
 use audio::AudioManager;
+use campaign::Campaign;
 use game::GameState;
+use loot::LootTables;
 use macroquad::prelude::*;
-use sprites::SpriteSheet;
+use settings::Settings;
+use sprites::{SpriteSheet, pack_from_args};
 
 fn window_conf() -> Conf {
     Conf {
         window_title: "rstgme".to_string(),
         window_width: 800,
         window_height: 600,
+        fullscreen: Settings::load().fullscreen, // Read before main, which loads its own copy
         ..Default::default()
     }
 }
@@ -28,11 +72,21 @@ async fn main() {
     // Seed random number generator with current time
     rand::srand(macroquad::miniquad::date::now() as u64);
 
-    let sprites = SpriteSheet::load().await;
-    let audio = AudioManager::load().await;
-    let mut game = GameState::new(audio);
+    let settings = Settings::load();
+    // A texture pack from the command line wins over the saved one
+    let pack = pack_from_args(std::env::args()).or(settings.texture_pack.clone());
+    let sprites = SpriteSheet::load(pack.as_deref()).await;
+    let audio = AudioManager::load(settings.volumes).await;
+    let loot = LootTables::load().await;
+    let campaign = Campaign::load().await;
+    let mut game = GameState::new(settings, audio, loot, campaign);
 
+    // Closing the window goes through the game so a run in progress is saved
+    prevent_quit();
     loop {
+        if is_quit_requested() {
+            game.quit();
+        }
         let dt = get_frame_time();
 
         game.update(dt);
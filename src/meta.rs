@@ -0,0 +1,379 @@
+use macroquad::prelude::*;
+
+use crate::class::PlayerClass;
+use crate::skin::Skin;
+use crate::sprites::SpriteSheet;
+use crate::ui::{ui_height, ui_width};
+use crate::weapon::Weapon;
+
+const PROFILE_FILE: &str = "profile.txt";
+const TOKENS_PER_TERMINAL: u32 = 5;
+const WIN_BONUS_TOKENS: u32 = 25;
+
+/// Account-level tokens earned when a run ends, won or lost
+pub fn run_reward(score: u32, terminals_hacked: u32, won: bool) -> u32 {
+    let bonus = if won { WIN_BONUS_TOKENS } else { 0 };
+    score + terminals_hacked * TOKENS_PER_TERMINAL + bonus
+}
+
+/// Color scheme laid over the map
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapTheme {
+    Standard,
+    Ember,
+    Frost,
+}
+
+impl MapTheme {
+    pub fn background(self) -> Color {
+        match self {
+            MapTheme::Standard => Color::from_rgba(30, 30, 40, 255),
+            MapTheme::Ember => Color::from_rgba(40, 25, 20, 255),
+            MapTheme::Frost => Color::from_rgba(20, 30, 45, 255),
+        }
+    }
+
+    /// Translucent wash drawn over the tiles, if any
+    pub fn wash(self) -> Option<Color> {
+        match self {
+            MapTheme::Standard => None,
+            MapTheme::Ember => Some(Color::from_rgba(255, 110, 40, 40)),
+            MapTheme::Frost => Some(Color::from_rgba(110, 180, 255, 40)),
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            MapTheme::Standard => "standard",
+            MapTheme::Ember => "ember",
+            MapTheme::Frost => "frost",
+        }
+    }
+}
+
+/// Permanent purchase on the unlock screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unlock {
+    HackerClass,
+    ScoutClass,
+    StartShotgun,
+    StartMachinePistol,
+    EmberTheme,
+    FrostTheme,
+}
+
+impl Unlock {
+    pub const ALL: [Unlock; 6] = [
+        Unlock::HackerClass,
+        Unlock::ScoutClass,
+        Unlock::StartShotgun,
+        Unlock::StartMachinePistol,
+        Unlock::EmberTheme,
+        Unlock::FrostTheme,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unlock::HackerClass => "Hacker class",
+            Unlock::ScoutClass => "Scout class",
+            Unlock::StartShotgun => "Starting shotgun",
+            Unlock::StartMachinePistol => "Starting machine pistol",
+            Unlock::EmberTheme => "Ember map theme",
+            Unlock::FrostTheme => "Frost map theme",
+        }
+    }
+
+    pub fn cost(self) -> u32 {
+        match self {
+            Unlock::HackerClass | Unlock::ScoutClass => 100,
+            Unlock::StartShotgun => 150,
+            Unlock::StartMachinePistol => 200,
+            Unlock::EmberTheme | Unlock::FrostTheme => 60,
+        }
+    }
+
+    /// Unlock that makes a class playable; the Soldier is free
+    pub fn for_class(class: PlayerClass) -> Option<Unlock> {
+        match class {
+            PlayerClass::Soldier => None,
+            PlayerClass::Hacker => Some(Unlock::HackerClass),
+            PlayerClass::Scout => Some(Unlock::ScoutClass),
+        }
+    }
+
+    /// Theme this unlock makes selectable
+    pub fn theme(self) -> Option<MapTheme> {
+        match self {
+            Unlock::EmberTheme => Some(MapTheme::Ember),
+            Unlock::FrostTheme => Some(MapTheme::Frost),
+            _ => None,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Unlock::HackerClass => "hacker",
+            Unlock::ScoutClass => "scout",
+            Unlock::StartShotgun => "shotgun",
+            Unlock::StartMachinePistol => "machine_pistol",
+            Unlock::EmberTheme => "ember",
+            Unlock::FrostTheme => "frost",
+        }
+    }
+}
+
+/// Progress kept between runs: banked tokens, what they bought and earned skins
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub tokens: u32,
+    unlocked: Vec<Unlock>,
+    skins: Vec<Skin>,
+    pub theme: MapTheme,
+    pub best_depth: u32, // Deepest map reached in endless mode
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            tokens: 0,
+            unlocked: Vec::new(),
+            skins: Vec::new(),
+            theme: MapTheme::Standard,
+            best_depth: 0,
+        }
+    }
+}
+
+impl Profile {
+    /// Read the profile file; a missing or unreadable file starts a fresh profile
+    pub fn load() -> Self {
+        std::fs::read_to_string(PROFILE_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = std::fs::write(PROFILE_FILE, self.serialize()) {
+            eprintln!("Could not save {PROFILE_FILE}: {err}");
+        }
+    }
+
+    /// Parse the profile format written by `serialize`; unknown lines are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut profile = Self::default();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["tokens", value] => profile.tokens = value.parse().unwrap_or(0),
+                ["best_depth", value] => profile.best_depth = value.parse().unwrap_or(0),
+                ["unlock", key] => {
+                    if let Some(unlock) = Unlock::ALL.into_iter().find(|u| u.key() == *key)
+                        && !profile.unlocked.contains(&unlock)
+                    {
+                        profile.unlocked.push(unlock);
+                    }
+                }
+                ["skin", key] => {
+                    if let Some(skin) = Skin::from_key(key) {
+                        profile.unlock_skin(skin);
+                    }
+                }
+                ["theme", key] => {
+                    if let Some(theme) = Unlock::ALL
+                        .into_iter()
+                        .filter(|u| profile.unlocked.contains(u))
+                        .filter_map(Unlock::theme)
+                        .find(|t| t.key() == *key)
+                    {
+                        profile.theme = theme;
+                    }
+                }
+                _ => {}
+            }
+        }
+        profile
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = format!("tokens {}\n", self.tokens);
+        for unlock in &self.unlocked {
+            text.push_str(&format!("unlock {}\n", unlock.key()));
+        }
+        for skin in &self.skins {
+            text.push_str(&format!("skin {}\n", skin.key()));
+        }
+        text.push_str(&format!("theme {}\n", self.theme.key()));
+        text.push_str(&format!("best_depth {}\n", self.best_depth));
+        text
+    }
+
+    pub fn is_unlocked(&self, unlock: Unlock) -> bool {
+        self.unlocked.contains(&unlock)
+    }
+
+    pub fn has_class(&self, class: PlayerClass) -> bool {
+        Unlock::for_class(class).is_none_or(|unlock| self.is_unlocked(unlock))
+    }
+
+    pub fn has_skin(&self, skin: Skin) -> bool {
+        skin.unlock_hint().is_none() || self.skins.contains(&skin)
+    }
+
+    /// Record an earned skin. Returns true if it is new.
+    pub fn unlock_skin(&mut self, skin: Skin) -> bool {
+        if self.has_skin(skin) {
+            return false;
+        }
+        self.skins.push(skin);
+        true
+    }
+
+    /// Spend tokens on an unlock. Returns false if owned or unaffordable.
+    pub fn buy(&mut self, unlock: Unlock) -> bool {
+        if self.is_unlocked(unlock) || self.tokens < unlock.cost() {
+            return false;
+        }
+        self.tokens -= unlock.cost();
+        self.unlocked.push(unlock);
+        true
+    }
+
+    /// Extra weapons every run starts with
+    pub fn starting_weapons(&self) -> Vec<Weapon> {
+        let mut weapons = Vec::new();
+        if self.is_unlocked(Unlock::StartShotgun) {
+            weapons.push(Weapon::shotgun());
+        }
+        if self.is_unlocked(Unlock::StartMachinePistol) {
+            weapons.push(Weapon::machine_pistol());
+        }
+        weapons
+    }
+}
+
+/// Unlock screen: every purchase with its price, owned state and the token balance
+pub fn draw_unlocks(profile: &Profile, selected: usize, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let title = "UNLOCKS";
+    let title_size = 40.0;
+    let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+    draw_text(
+        title,
+        (ui_width() - title_width) / 2.0,
+        100.0,
+        title_size,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+
+    let balance = format!("Tokens: {}", profile.tokens);
+    let balance_width = measure_text(&balance, None, 24, 1.0).width;
+    let token_color = Color::from_rgba(255, 210, 90, 255);
+    draw_text(
+        &balance,
+        (ui_width() - balance_width) / 2.0,
+        140.0,
+        24.0,
+        token_color,
+    );
+
+    let width = 440.0;
+    let row_height = 36.0;
+    let x = (ui_width() - width) / 2.0;
+    let start_y = 180.0;
+    for (i, unlock) in Unlock::ALL.iter().enumerate() {
+        let y = start_y + i as f32 * row_height;
+        let is_selected = i == selected;
+        draw_rectangle(
+            x,
+            y,
+            width,
+            row_height - 6.0,
+            if is_selected {
+                Color::from_rgba(60, 60, 80, 230)
+            } else {
+                Color::from_rgba(15, 15, 25, 230)
+            },
+        );
+        draw_text(
+            unlock.name(),
+            x + 14.0,
+            y + 21.0,
+            20.0,
+            if is_selected { WHITE } else { LIGHTGRAY },
+        );
+
+        let (status, color) = if !profile.is_unlocked(*unlock) {
+            let color = if profile.tokens >= unlock.cost() {
+                token_color
+            } else {
+                DARKGRAY
+            };
+            (format!("{} tokens", unlock.cost()), color)
+        } else if unlock.theme() == Some(profile.theme) {
+            ("ACTIVE".to_string(), Color::from_rgba(100, 255, 100, 255))
+        } else {
+            ("OWNED".to_string(), GRAY)
+        };
+        let status_width = measure_text(&status, None, 18, 1.0).width;
+        draw_text(
+            &status,
+            x + width - status_width - 14.0,
+            y + 21.0,
+            18.0,
+            color,
+        );
+    }
+
+    let hint = "W/S: Select  |  Enter: Buy / toggle theme  |  ESC: Back";
+    let hint_size = 16.0;
+    let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+    draw_text(
+        hint,
+        (ui_width() - hint_width) / 2.0,
+        ui_height() - 40.0,
+        hint_size,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_round_trip() {
+        let mut profile = Profile {
+            tokens: 500,
+            best_depth: 7,
+            ..Profile::default()
+        };
+        assert!(!profile.has_class(PlayerClass::Scout));
+        assert!(profile.buy(Unlock::ScoutClass));
+        assert!(!profile.buy(Unlock::ScoutClass));
+        assert!(profile.buy(Unlock::FrostTheme));
+        profile.theme = MapTheme::Frost;
+        assert!(profile.unlock_skin(Skin::Gold));
+        assert!(!profile.unlock_skin(Skin::Gold));
+        assert!(!profile.unlock_skin(Skin::Crimson));
+        assert_eq!(profile.tokens, 340);
+        assert!(profile.has_class(PlayerClass::Scout));
+
+        let loaded = Profile::parse(&profile.serialize());
+        assert_eq!(loaded, profile);
+
+        // Themes that were never bought are ignored
+        let tampered = Profile::parse("tokens 5\ntheme ember\nbogus line\n");
+        assert_eq!(tampered.theme, MapTheme::Standard);
+        assert_eq!(tampered.tokens, 5);
+    }
+
+    #[test]
+    fn test_run_reward() {
+        assert_eq!(run_reward(10, 0, false), 10);
+        assert_eq!(
+            run_reward(10, 2, true),
+            10 + 2 * TOKENS_PER_TERMINAL + WIN_BONUS_TOKENS
+        );
+    }
+}
@@ -0,0 +1,72 @@
+const VOICE_TIME: f64 = 0.25; // Seconds a sound counts as playing after it starts
+const MAX_PER_SOUND: usize = 3; // Copies of one sound at once
+const MAX_VOICES: usize = 12; // Sounds at once before only high-priority ones get through
+const LOW_VOICES: usize = 6; // Low-priority sounds only start while fewer are playing
+
+/// How much a sound effect matters when many play at once
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Priority {
+    Low,    // Blips and distant chatter: the first to go
+    Normal, // Gunfire, hits and the like
+    High,   // The player getting hurt, and outcomes: always heard
+}
+
+impl Priority {
+    /// Sounds already playing beyond which one of this priority is dropped
+    fn voice_limit(self) -> usize {
+        match self {
+            Priority::Low => LOW_VOICES,
+            Priority::Normal => MAX_VOICES,
+            Priority::High => usize::MAX,
+        }
+    }
+}
+
+/// Keeps count of the sound effects started lately, and turns new ones
+/// away when too many of the same sound, or too many overall, are playing
+#[derive(Default)]
+pub struct Mixer {
+    voices: Vec<(&'static str, f64)>, // Sound name and start time
+}
+
+impl Mixer {
+    /// Whether sound `name` may start at `now` (seconds); if so it counts as
+    /// playing from then on
+    pub fn admit(&mut self, name: &'static str, priority: Priority, now: f64) -> bool {
+        self.voices.retain(|&(_, start)| now - start < VOICE_TIME);
+        let copies = self.voices.iter().filter(|&&(n, _)| n == name).count();
+        if copies >= MAX_PER_SOUND || self.voices.len() >= priority.voice_limit() {
+            return false;
+        }
+        self.voices.push((name, now));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixer_limits() {
+        let mut mixer = Mixer::default();
+        for _ in 0..MAX_PER_SOUND {
+            assert!(mixer.admit("hit", Priority::Normal, 0.0));
+        }
+        assert!(!mixer.admit("hit", Priority::High, 0.0));
+        assert!(mixer.admit("hit", Priority::Normal, VOICE_TIME)); // Older ones have ended
+
+        // A crowd turns low priority away first, and never high priority
+        let mut mixer = Mixer::default();
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l"];
+        for name in &names[..LOW_VOICES] {
+            assert!(mixer.admit(name, Priority::Normal, 0.0));
+        }
+        assert!(!mixer.admit("blip", Priority::Low, 0.0));
+        for name in &names[LOW_VOICES..MAX_VOICES] {
+            assert!(mixer.admit(name, Priority::Normal, 0.0));
+        }
+        assert!(!mixer.admit("shot", Priority::Normal, 0.0));
+        assert!(mixer.admit("player_hit", Priority::High, 0.0));
+    }
+}
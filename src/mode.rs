@@ -0,0 +1,134 @@
+use macroquad::prelude::*;
+
+use crate::save::persist_enum;
+use crate::ui::ui_width;
+
+const TIME_ATTACK_START: f32 = 180.0; // Seconds on the clock at the start
+pub const TIME_ATTACK_BONUS: f32 = 45.0; // Seconds added per hacked terminal
+const SCORE_PER_SECOND_LEFT: u32 = 2;
+
+/// What a run asks of the player, picked alongside class and rules on New Game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Standard,   // Hack every terminal (or upload the data) at your own pace
+    TimeAttack, // Hack every terminal before the countdown runs out
+    Campaign,   // Authored levels in a fixed order, ending with a boss
+    Endless,    // Ever larger, harder maps until the player falls
+    Versus,     // Two local players fight it out in a small arena
+}
+
+persist_enum!(GameMode {
+    Standard,
+    TimeAttack,
+    Campaign,
+    Endless,
+    Versus
+});
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Standard,
+        GameMode::TimeAttack,
+        GameMode::Campaign,
+        GameMode::Endless,
+        GameMode::Versus,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Standard => "STANDARD",
+            GameMode::TimeAttack => "TIME ATTACK",
+            GameMode::Campaign => "CAMPAIGN",
+            GameMode::Endless => "ENDLESS",
+            GameMode::Versus => "VERSUS",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GameMode::Standard => "Hack every terminal at your own pace",
+            GameMode::TimeAttack => "3:00 on the clock, +45s per terminal",
+            GameMode::Campaign => "Authored levels with a boss at the end",
+            GameMode::Endless => "Descend through ever harder maps",
+            GameMode::Versus => "Two players, one arena, first to 5 kills",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            GameMode::Standard => Color::from_rgba(100, 200, 255, 255),
+            GameMode::TimeAttack => Color::from_rgba(255, 170, 60, 255),
+            GameMode::Campaign => Color::from_rgba(180, 140, 255, 255),
+            GameMode::Endless => Color::from_rgba(120, 220, 200, 255),
+            GameMode::Versus => Color::from_rgba(255, 90, 120, 255),
+        }
+    }
+
+    /// Whether clearing a map moves on to another map instead of winning
+    pub fn has_levels(self) -> bool {
+        matches!(self, GameMode::Campaign | GameMode::Endless)
+    }
+
+    /// Countdown at the start of a run; None means untimed
+    pub fn starting_clock(self) -> Option<f32> {
+        match self {
+            GameMode::Standard | GameMode::Campaign | GameMode::Endless | GameMode::Versus => None,
+            GameMode::TimeAttack => Some(TIME_ATTACK_START),
+        }
+    }
+}
+
+/// Score paid out for the seconds left on a time attack clock
+pub fn time_bonus_score(seconds_left: f32) -> u32 {
+    seconds_left.max(0.0) as u32 * SCORE_PER_SECOND_LEFT
+}
+
+/// Countdown shown large at the top of the screen in time attack
+pub fn draw_countdown(seconds_left: f32, bonus_flash: f32) {
+    let seconds = seconds_left.max(0.0).ceil() as u32;
+    let text = format!("{}:{:02}", seconds / 60, seconds % 60);
+    let font_size = 44.0;
+    let width = measure_text(&text, None, font_size as u16, 1.0).width;
+    let x = (ui_width() - width) / 2.0;
+    let y = 44.0;
+
+    let color = if seconds_left <= 10.0 {
+        // Pulses in the final seconds
+        let pulse = (get_time() as f32 * 8.0).sin() * 0.5 + 0.5;
+        Color::new(1.0, 0.2 + 0.3 * pulse, 0.2, 1.0)
+    } else if seconds_left <= 30.0 {
+        Color::from_rgba(255, 90, 70, 255)
+    } else {
+        WHITE
+    };
+    draw_text(&text, x + 2.0, y + 2.0, font_size, BLACK);
+    draw_text(&text, x, y, font_size, color);
+
+    if bonus_flash > 0.0 {
+        let bonus = format!("+{:.0}s", TIME_ATTACK_BONUS);
+        let alpha = bonus_flash.min(1.0);
+        draw_text(
+            &bonus,
+            x + width + 10.0,
+            y - 6.0,
+            24.0,
+            Color::new(0.4, 1.0, 0.4, alpha),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_attack_clock() {
+        assert_eq!(GameMode::Standard.starting_clock(), None);
+        assert_eq!(
+            GameMode::TimeAttack.starting_clock(),
+            Some(TIME_ATTACK_START)
+        );
+        assert_eq!(time_bonus_score(30.7), 30 * SCORE_PER_SECOND_LEFT);
+        assert_eq!(time_bonus_score(-2.0), 0);
+    }
+}
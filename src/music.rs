@@ -0,0 +1,314 @@
+use macroquad::audio::{
+    PlaySoundParams, Sound, load_sound_from_bytes, play_sound, set_sound_volume, stop_sound,
+};
+
+use crate::audio::{
+    SAMPLE_RATE, apply_envelope, frequency_sweep, generate_wav, noise_burst, sine_wave,
+};
+use crate::tile_map::TILE_SIZE;
+
+const CROSSFADE: f32 = 1.5; // Seconds to blend one track into the next
+const LAYER_FADE: f32 = 0.5; // Share of full volume a layer moves per second
+const STEPS_PER_BAR: usize = 8; // Eighth notes
+pub const THREAT_RANGE: f32 = TILE_SIZE * 10.0; // Hostile bots this close raise the tension
+
+/// Background track for a part of the game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    Menu,
+    Game,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Menu, Theme::Game];
+
+    fn song(self) -> &'static Song {
+        match self {
+            Theme::Menu => &MENU_SONG,
+            Theme::Game => &GAME_SONG,
+        }
+    }
+}
+
+/// Part of a track that fades in and out on its own as the tension changes.
+/// Every layer of a theme loops in step with the others.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Layer {
+    Ambient,  // Bass line and a slow arpeggio, always playing
+    Drums,    // In at the first sign of trouble
+    Arpeggio, // Fast sixteenths once things get hot
+}
+
+impl Layer {
+    const ALL: [Layer; 3] = [Layer::Ambient, Layer::Drums, Layer::Arpeggio];
+
+    /// How loud the layer plays at a tension from 0 (safe) to 1
+    fn level(self, tension: f32) -> f32 {
+        match self {
+            Layer::Ambient => 1.0,
+            Layer::Drums => (tension * 2.0).min(1.0),
+            Layer::Arpeggio => ((tension - 0.5) * 2.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// How tense a run is for the music, from 0 (safe) to 1: hostile bots
+/// within THREAT_RANGE, a running hack and a sounding alarm all add to it
+pub fn tension(threats: usize, hacking: bool, alarm: bool) -> f32 {
+    let mut tension = threats as f32 * 0.2;
+    if hacking {
+        tension += 0.6;
+    }
+    if alarm {
+        tension += 0.6;
+    }
+    tension.min(1.0)
+}
+
+/// A looping tune: one chord per bar, with the same bass and drum pattern
+/// over every bar and arpeggios running up the chord
+struct Song {
+    bpm: f32,
+    key: f32,                   // Hz of the home note
+    progression: [[i32; 3]; 4], // Chord tones per bar, in semitones above the key
+    bass: [Option<i32>; 8],     // Per step: semitones above the chord's first tone
+    drums: &'static [u8; 8],    // Per step: k kick, s snare, h hat, . rest
+    arpeggio: f32,              // Volume of the arpeggios
+}
+
+/// Slow and moody in A minor: Am F C G over an A2 bass
+const MENU_SONG: Song = Song {
+    bpm: 88.0,
+    key: 110.0,
+    progression: [[0, 3, 7], [-4, 0, 3], [3, 7, 10], [-2, 2, 5]],
+    bass: [Some(0), None, None, Some(0), Some(7), None, Some(5), None],
+    drums: b"k...s..h",
+    arpeggio: 0.12,
+};
+
+/// Driving E minor for runs: Em C D B over an E2 bass
+const GAME_SONG: Song = Song {
+    bpm: 128.0,
+    key: 82.41,
+    progression: [[0, 3, 7], [-4, 0, 3], [-2, 2, 5], [-5, -1, 2]],
+    bass: [
+        Some(0),
+        Some(0),
+        Some(12),
+        Some(0),
+        Some(0),
+        Some(12),
+        Some(0),
+        Some(7),
+    ],
+    drums: b"khshkhsh",
+    arpeggio: 0.08,
+};
+
+impl Song {
+    /// Seconds per step
+    fn step(&self) -> f32 {
+        60.0 / self.bpm / 2.0
+    }
+}
+
+fn note(key: f32, semitones: i32) -> f32 {
+    key * 2f32.powf(semitones as f32 / 12.0)
+}
+
+/// Add a sound into the track at a sample offset, wrapping past the end so
+/// tails carry over into the start of the loop
+fn add_at(track: &mut [f32], at: usize, sound: &[f32]) {
+    let len = track.len();
+    for (i, &sample) in sound.iter().enumerate() {
+        track[(at + i) % len] += sample;
+    }
+}
+
+/// Render a song's loop, one track per Layer; each ends exactly on the bar
+/// line, so looping them plays on without a seam
+fn compose(song: &Song) -> Vec<Vec<f32>> {
+    let step = song.step();
+    let step_samples = (SAMPLE_RATE as f32 * step) as usize;
+    let len = step_samples * STEPS_PER_BAR * song.progression.len();
+    let mut layers = vec![vec![0.0; len]; Layer::ALL.len()];
+    let pluck = |semitones: i32, length: f32, volume: f32| {
+        let mut pluck = sine_wave(note(song.key, semitones), length, volume);
+        apply_envelope(&mut pluck, 0.002, length * 0.8);
+        pluck
+    };
+
+    for (bar, chord) in song.progression.iter().enumerate() {
+        for s in 0..STEPS_PER_BAR {
+            let at = (bar * STEPS_PER_BAR + s) * step_samples;
+
+            // Bass: a low sine with a touch of its octave
+            let ambient = &mut layers[Layer::Ambient as usize];
+            if let Some(offset) = song.bass[s] {
+                let freq = note(song.key, chord[0] + offset - 12);
+                let mut bass = sine_wave(freq, step * 0.9, 0.35);
+                add_at(&mut bass, 0, &sine_wave(freq * 2.0, step * 0.9, 0.1));
+                apply_envelope(&mut bass, 0.005, step * 0.4);
+                add_at(ambient, at, &bass);
+            }
+            // Slow arpeggio: an eighth per step up through the chord
+            let tone = chord[s % chord.len()] + 12;
+            add_at(ambient, at, &pluck(tone, step, song.arpeggio * 0.6));
+
+            // Fast arpeggio: two sixteenths per step, an octave up
+            for half in 0..2 {
+                let tone = chord[(s * 2 + half) % chord.len()] + 24;
+                let sixteenth = pluck(tone, step / 2.0, song.arpeggio);
+                let arpeggio = &mut layers[Layer::Arpeggio as usize];
+                add_at(arpeggio, at + half * step_samples / 2, &sixteenth);
+            }
+
+            let mut hit = match song.drums[s] {
+                b'k' => frequency_sweep(110.0, 40.0, 0.14, 0.6),
+                b's' => noise_burst(0.1, 0.25),
+                b'h' => noise_burst(0.03, 0.08),
+                _ => continue,
+            };
+            apply_envelope(&mut hit, 0.001, 0.08);
+            add_at(&mut layers[Layer::Drums as usize], at, &hit);
+        }
+    }
+
+    // Keep the peaks of the full mix in range rather than clipping them
+    let peak = (0..len)
+        .map(|i| layers.iter().map(|layer| layer[i]).sum::<f32>().abs())
+        .fold(0.0f32, f32::max);
+    if peak > 0.9 {
+        for layer in &mut layers {
+            layer.iter_mut().for_each(|s| *s *= 0.9 / peak);
+        }
+    }
+    layers
+}
+
+/// Plays the theme the game asks for, crossfading from the last one, with
+/// its layers mixed to the tension
+#[derive(Default)]
+pub struct MusicPlayer {
+    tracks: Vec<Vec<Option<Sound>>>, // By Theme, then Layer; empty without audio
+    current: Option<Theme>,
+    fade_in: f32, // 0 to 1 for the current track
+    fading_out: Option<(Theme, f32)>,
+    levels: [f32; 3], // Current level of each Layer, easing towards the tension's mix
+}
+
+impl MusicPlayer {
+    /// Synthesize every theme's layers
+    pub async fn load() -> Self {
+        let mut tracks = Vec::new();
+        for theme in Theme::ALL {
+            let mut layers = Vec::new();
+            for samples in compose(theme.song()) {
+                layers.push(load_sound_from_bytes(&generate_wav(&samples)).await.ok());
+            }
+            tracks.push(layers);
+        }
+        Self {
+            tracks,
+            ..Default::default()
+        }
+    }
+
+    fn layers(&self, theme: Theme) -> impl Iterator<Item = (usize, &Sound)> {
+        self.tracks
+            .get(theme as usize)
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(layer, sound)| Some((layer, sound.as_ref()?)))
+    }
+
+    /// Keep `theme` playing at `volume` with its layers mixed for a tension
+    /// from 0 to 1; a change of theme fades the old track out while the new
+    /// one fades in
+    pub fn update(&mut self, dt: f32, theme: Theme, tension: f32, volume: f32) {
+        if self.current != Some(theme) {
+            if let Some((old, _)) = self.fading_out.take() {
+                self.layers(old).for_each(|(_, sound)| stop_sound(sound));
+            }
+            self.fading_out = self.current.map(|old| (old, self.fade_in));
+            self.current = Some(theme);
+            self.fade_in = 0.0;
+            // Every layer starts together, so they stay in step
+            for (_, sound) in self.layers(theme) {
+                play_sound(
+                    sound,
+                    PlaySoundParams {
+                        looped: true,
+                        volume: 0.0,
+                    },
+                );
+            }
+        }
+
+        for (level, layer) in self.levels.iter_mut().zip(Layer::ALL) {
+            let target = layer.level(tension);
+            let step = LAYER_FADE * dt;
+            *level = (*level + (target - *level).clamp(-step, step)).clamp(0.0, 1.0);
+        }
+        self.fade_in = (self.fade_in + dt / CROSSFADE).min(1.0);
+        for (layer, sound) in self.layers(theme) {
+            set_sound_volume(sound, volume * self.fade_in * self.levels[layer]);
+        }
+        if let Some((old, level)) = self.fading_out {
+            let level = level - dt / CROSSFADE;
+            for (layer, sound) in self.layers(old) {
+                if level > 0.0 {
+                    set_sound_volume(sound, volume * level * self.levels[layer]);
+                } else {
+                    stop_sound(sound);
+                }
+            }
+            self.fading_out = (level > 0.0).then_some((old, level));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_songs_loop_on_the_bar() {
+        for theme in Theme::ALL {
+            let song = theme.song();
+            let step_samples = (SAMPLE_RATE as f32 * song.step()) as usize;
+            let layers = compose(song);
+            assert_eq!(layers.len(), Layer::ALL.len());
+            for layer in &layers {
+                assert_eq!(layer.len(), step_samples * STEPS_PER_BAR * 4);
+                assert!(layer.iter().any(|&s| s != 0.0));
+            }
+            // The full mix never clips
+            let loudest = (0..layers[0].len())
+                .map(|i| layers.iter().map(|layer| layer[i]).sum::<f32>().abs())
+                .fold(0.0f32, f32::max);
+            assert!(loudest <= 0.9 + 1e-4);
+        }
+        assert!((note(110.0, 12) - 220.0).abs() < 0.01);
+
+        // Tails wrap around to the start of the loop
+        let mut track = vec![0.0; 4];
+        add_at(&mut track, 3, &[1.0, 2.0]);
+        assert_eq!(track, [2.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_tension_layers() {
+        assert_eq!(tension(0, false, false), 0.0);
+        assert_eq!(tension(2, false, false), 0.4);
+        assert_eq!(tension(1, true, true), 1.0);
+
+        // Safe: ambient only; a hack brings the drums and fast arpeggio in
+        let safe = Layer::ALL.map(|layer| layer.level(0.0));
+        assert_eq!(safe, [1.0, 0.0, 0.0]);
+        let hacking = tension(0, true, false);
+        assert_eq!(Layer::Drums.level(hacking), 1.0);
+        assert!(Layer::Arpeggio.level(hacking) > 0.0);
+    }
+}
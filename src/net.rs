@@ -0,0 +1,515 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use macroquad::prelude::*;
+
+use crate::input::{FRAME_BYTES, InputFrame};
+use crate::sprites::SpriteSheet;
+use crate::ui::{ui_height, ui_width};
+
+pub const PORT: u16 = 7777;
+pub const TICK: f32 = 1.0 / 60.0; // Fixed simulation step both peers share
+const INPUT_DELAY: u32 = 3; // Ticks between capturing input and playing it
+const MAX_CATCH_UP: f32 = 4.0; // Ticks run in one frame after a stall
+const MAX_FRAMES_PER_PACKET: usize = 32;
+const INPUT_WINDOW: u32 = 256; // Ticks ahead of the clock remote input is kept for
+const CHUNK_SIZE: usize = 1024; // Bytes of the run snapshot per packet
+const RESEND_INTERVAL: f32 = 0.25;
+const TIMEOUT: f32 = 5.0; // Seconds of silence before the peer counts as gone
+
+const HELLO: u8 = 1;
+const CHUNK: u8 = 2;
+const ACK: u8 = 3;
+const INPUT: u8 = 4;
+
+#[derive(Debug, PartialEq)]
+enum Packet {
+    Hello, // Joiner asking the host in
+    Chunk {
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    },
+    Ack {
+        index: u16,
+    },
+    // Sender's frames from tick `first` on; `ack` is the first of the
+    // receiver's ticks the sender is still missing
+    Input {
+        ack: u32,
+        first: u32,
+        frames: Vec<InputFrame>,
+    },
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Packet::Hello => vec![HELLO],
+            Packet::Chunk { index, count, data } => {
+                let mut out = vec![CHUNK];
+                out.extend(index.to_le_bytes());
+                out.extend(count.to_le_bytes());
+                out.extend(data);
+                out
+            }
+            Packet::Ack { index } => {
+                let mut out = vec![ACK];
+                out.extend(index.to_le_bytes());
+                out
+            }
+            Packet::Input { ack, first, frames } => {
+                let mut out = vec![INPUT];
+                out.extend(ack.to_le_bytes());
+                out.extend(first.to_le_bytes());
+                for frame in frames {
+                    out.extend(frame.to_bytes());
+                }
+                out
+            }
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Packet> {
+        let (&kind, body) = bytes.split_first()?;
+        let u16_at = |i: usize| Some(u16::from_le_bytes(body.get(i..i + 2)?.try_into().ok()?));
+        let u32_at = |i: usize| Some(u32::from_le_bytes(body.get(i..i + 4)?.try_into().ok()?));
+        match kind {
+            HELLO => Some(Packet::Hello),
+            CHUNK => Some(Packet::Chunk {
+                index: u16_at(0)?,
+                count: u16_at(2)?,
+                data: body.get(4..)?.to_vec(),
+            }),
+            ACK => Some(Packet::Ack { index: u16_at(0)? }),
+            INPUT => {
+                let frames = body.get(8..)?;
+                if frames.len() % FRAME_BYTES != 0 {
+                    return None;
+                }
+                Some(Packet::Input {
+                    ack: u32_at(0)?,
+                    first: u32_at(4)?,
+                    frames: frames
+                        .chunks(FRAME_BYTES)
+                        .map(InputFrame::from_bytes)
+                        .collect::<Option<_>>()?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What happened on the connection this frame
+pub enum NetEvent {
+    Joined,      // Host: a partner came in and waits for the run
+    Run(String), // Joiner: the host's run snapshot, in the save format
+}
+
+/// Online co-op link between the host, who plays the lead, and one
+/// joiner, who plays the partner. The host sends its freshly started run
+/// as a save snapshot, then both run the same simulation in lockstep at a
+/// fixed tick, each stepping only once it has both players' input for
+/// that tick. Nothing but input crosses the wire, so any randomness or
+/// state outside the snapshot and the inputs would split the two games.
+pub struct NetSession {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    pub is_host: bool,
+    chunks: Vec<Option<Vec<u8>>>, // Host: snapshot left to deliver; joiner: pieces received
+    running: bool,
+    tick: u32,       // Next tick to simulate
+    local_next: u32, // Next tick to capture local input for
+    peer_ack: u32,   // First of our ticks the peer is still missing
+    local: BTreeMap<u32, InputFrame>,
+    remote: BTreeMap<u32, InputFrame>,
+    pending: InputFrame, // Input captured since the last tick was filled
+    accumulator: f32,
+    silence: f32, // Seconds since the peer was last heard
+    resend_timer: f32,
+}
+
+impl NetSession {
+    fn new(socket: UdpSocket, peer: Option<SocketAddr>, is_host: bool) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        // The first ticks have no input yet on either side
+        let empty = (0..INPUT_DELAY).map(|tick| (tick, InputFrame::default()));
+        Ok(Self {
+            socket,
+            peer,
+            is_host,
+            chunks: Vec::new(),
+            running: false,
+            tick: 0,
+            local_next: INPUT_DELAY,
+            peer_ack: 0,
+            local: empty.clone().collect(),
+            remote: empty.collect(),
+            pending: InputFrame::default(),
+            accumulator: 0.0,
+            silence: 0.0,
+            resend_timer: 0.0,
+        })
+    }
+
+    /// Wait for a partner on PORT
+    pub fn host() -> io::Result<Self> {
+        Self::new(UdpSocket::bind(("0.0.0.0", PORT))?, None, true)
+    }
+
+    /// Ask the host at `address` (`ip` or `ip:port`) to let us in
+    pub fn join(address: &str) -> io::Result<Self> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{address}:{PORT}")
+        };
+        let peer = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::other("unknown address"))?;
+        Self::new(UdpSocket::bind(("0.0.0.0", 0))?, Some(peer), false)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether the peer went quiet for too long (or never answered)
+    pub fn is_lost(&self) -> bool {
+        self.peer.is_some() && self.silence > TIMEOUT
+    }
+
+    /// Host: deliver the freshly started run and start the lockstep
+    pub fn send_run(&mut self, snapshot: &str) {
+        self.chunks = snapshot
+            .as_bytes()
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| Some(chunk.to_vec()))
+            .collect();
+        self.running = true;
+        self.resend_timer = 0.0;
+    }
+
+    fn send(&self, packet: &Packet) {
+        if let Some(peer) = self.peer {
+            // A lost packet is sent again later, so errors can be dropped
+            let _ = self.socket.send_to(&packet.encode(), peer);
+        }
+    }
+
+    /// Read everything that arrived and resend what the peer still lacks
+    pub fn poll(&mut self, dt: f32) -> Option<NetEvent> {
+        let mut event = None;
+        self.silence += dt;
+        let mut buf = [0; 2048];
+        while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+            let Some(packet) = Packet::decode(&buf[..len]) else {
+                continue;
+            };
+            // The host takes its partner from the first proper hello, so a
+            // stray datagram can't lock them out
+            if self.is_host && self.peer.is_none() && packet == Packet::Hello {
+                self.peer = Some(from);
+            }
+            if self.peer != Some(from) {
+                continue;
+            }
+            self.silence = 0.0;
+            if let Some(e) = self.receive(packet) {
+                event = Some(e);
+            }
+        }
+
+        self.resend_timer -= dt;
+        if self.resend_timer <= 0.0 {
+            self.resend_timer = RESEND_INTERVAL;
+            if !self.is_host && self.chunks.is_empty() {
+                self.send(&Packet::Hello);
+            }
+            if self.is_host {
+                let count = self.chunks.len() as u16;
+                for (index, chunk) in self.chunks.iter().enumerate() {
+                    if let Some(data) = chunk {
+                        self.send(&Packet::Chunk {
+                            index: index as u16,
+                            count,
+                            data: data.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        event
+    }
+
+    fn receive(&mut self, packet: Packet) -> Option<NetEvent> {
+        match packet {
+            Packet::Hello if self.is_host && !self.running => {
+                self.running = true;
+                return Some(NetEvent::Joined);
+            }
+            Packet::Chunk { index, count, data } if !self.is_host && !self.running => {
+                // The first chunk fixes the count; an empty or disagreeing
+                // one is bogus
+                if count == 0 || !self.chunks.is_empty() && self.chunks.len() != count as usize {
+                    return None;
+                }
+                self.chunks.resize(count as usize, None);
+                if let Some(slot) = self.chunks.get_mut(index as usize) {
+                    *slot = Some(data);
+                }
+                self.send(&Packet::Ack { index });
+                if self.chunks.iter().all(Option::is_some) {
+                    self.running = true;
+                    let bytes: Vec<u8> = self.chunks.iter().flatten().flatten().copied().collect();
+                    return Some(NetEvent::Run(String::from_utf8_lossy(&bytes).into_owned()));
+                }
+            }
+            // A late copy after the run arrived: the ack went missing
+            Packet::Chunk { index, .. } if !self.is_host => self.send(&Packet::Ack { index }),
+            Packet::Ack { index } if self.is_host => {
+                if let Some(slot) = self.chunks.get_mut(index as usize) {
+                    *slot = None;
+                }
+            }
+            Packet::Input { ack, first, frames } => {
+                let window = self.tick..=self.tick.saturating_add(INPUT_WINDOW);
+                for (offset, frame) in frames.into_iter().enumerate() {
+                    let Some(tick) = first.checked_add(offset as u32) else {
+                        break;
+                    };
+                    if window.contains(&tick) {
+                        self.remote.entry(tick).or_insert(frame);
+                    }
+                }
+                self.peer_ack = self.peer_ack.max(ack);
+                let keep = self.peer_ack.min(self.tick);
+                self.local.retain(|&tick, _| tick >= keep);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Advance the clock by a frame, with the input captured locally this
+    /// frame; returns the [lead, partner] input for every tick that is due
+    /// and ready. Missing remote input stalls the game until it arrives.
+    pub fn advance(&mut self, dt: f32, local: InputFrame) -> Vec<[InputFrame; 2]> {
+        self.pending = self.pending.merge(local);
+        self.accumulator = (self.accumulator + dt).min(TICK * MAX_CATCH_UP);
+        let mut ticks = Vec::new();
+        while self.accumulator >= TICK {
+            while self.local_next <= self.tick + INPUT_DELAY {
+                self.local.insert(self.local_next, self.pending);
+                self.pending = self.pending.held();
+                self.local_next += 1;
+            }
+            let Some(theirs) = self.remote.remove(&self.tick) else {
+                break;
+            };
+            let ours = self.local[&self.tick];
+            ticks.push(if self.is_host {
+                [ours, theirs]
+            } else {
+                [theirs, ours]
+            });
+            self.tick += 1;
+            self.accumulator -= TICK;
+        }
+        self.send_input();
+        ticks
+    }
+
+    /// Our frames the peer has not confirmed yet, and which of theirs we need
+    fn send_input(&self) {
+        let mut ack = self.tick;
+        while self.remote.contains_key(&ack) {
+            ack += 1;
+        }
+        let first = self
+            .peer_ack
+            .max(self.local_next.saturating_sub(MAX_FRAMES_PER_PACKET as u32));
+        let frames = self.local.range(first..).map(|(_, &frame)| frame).collect();
+        self.send(&Packet::Input { ack, first, frames });
+    }
+}
+
+/// Address entry on the online co-op screen
+#[derive(Default)]
+pub struct OnlineMenu {
+    pub address: String,
+    pub status: String, // Last connection problem, if any
+}
+
+impl OnlineMenu {
+    /// Collect typed characters; returns the address once Enter is pressed
+    /// (empty to host)
+    pub fn read_line(&mut self) -> Option<String> {
+        while let Some(c) = get_char_pressed() {
+            if c.is_ascii_graphic() {
+                self.address.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.address.pop();
+        }
+        is_key_pressed(KeyCode::Enter).then(|| self.address.trim().to_string())
+    }
+}
+
+/// Host or join screen, and the state of the connection being made
+pub fn draw_online(menu: &OnlineMenu, net: Option<&NetSession>, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let center = |text: &str, y: f32, size: f32, color: Color| {
+        let width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(text, (ui_width() - width) / 2.0, y, size, color);
+    };
+
+    let y = ui_height() / 2.0 - 130.0;
+    center(
+        "ONLINE CO-OP",
+        y,
+        40.0,
+        Color::from_rgba(140, 170, 255, 255),
+    );
+    let status = match net {
+        Some(net) if net.is_host => format!("Waiting for a partner on port {PORT}..."),
+        Some(_) => format!("Joining {}...", menu.address.trim()),
+        None => {
+            center(
+                "Type the host's address and press Enter to join,",
+                y + 60.0,
+                18.0,
+                LIGHTGRAY,
+            );
+            center(
+                &format!("or press Enter with no address to host on port {PORT}"),
+                y + 82.0,
+                18.0,
+                LIGHTGRAY,
+            );
+            let cursor = if get_time().fract() < 0.5 { "_" } else { "" };
+            center(
+                &format!("> {}{}", menu.address, cursor),
+                y + 140.0,
+                26.0,
+                WHITE,
+            );
+            menu.status.clone()
+        }
+    };
+    center(
+        &status,
+        y + 200.0,
+        20.0,
+        Color::from_rgba(255, 210, 90, 255),
+    );
+    center(
+        "The host leads with their New Game setup; the partner plays with WASD, mouse and interact",
+        y + 240.0,
+        14.0,
+        LIGHTGRAY,
+    );
+
+    center(
+        "Enter: Host/Join  |  ESC: Back",
+        ui_height() - 40.0,
+        16.0,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_round_trip() {
+        let packets = [
+            Packet::Hello,
+            Packet::Chunk {
+                index: 2,
+                count: 5,
+                data: b"setup".to_vec(),
+            },
+            Packet::Ack { index: 4 },
+            Packet::Input {
+                ack: 10,
+                first: 7,
+                frames: vec![InputFrame::default(); 3],
+            },
+        ];
+        for packet in packets {
+            assert_eq!(Packet::decode(&packet.encode()), Some(packet));
+        }
+        assert_eq!(Packet::decode(&[]), None);
+        assert_eq!(Packet::decode(&[INPUT, 0, 0]), None);
+        assert_eq!(Packet::decode(&[99]), None);
+    }
+
+    #[test]
+    fn test_lockstep_waits_for_both_inputs() {
+        let mut host =
+            NetSession::new(UdpSocket::bind("127.0.0.1:0").unwrap(), None, true).unwrap();
+        // The delayed opening ticks need nobody's input
+        assert_eq!(
+            host.advance(TICK * 10.0, InputFrame::default()).len(),
+            INPUT_DELAY as usize
+        );
+        assert!(host.advance(TICK, InputFrame::default()).is_empty());
+
+        // Once the partner's frame for the next tick arrives, it plays
+        let frames = vec![InputFrame::default().as_partner()];
+        host.receive(Packet::Input {
+            ack: 0,
+            first: INPUT_DELAY,
+            frames,
+        });
+        assert_eq!(host.advance(TICK, InputFrame::default()).len(), 1);
+        assert_eq!(host.tick, INPUT_DELAY + 1);
+    }
+
+    #[test]
+    fn test_input_outside_window_is_dropped() {
+        let mut host =
+            NetSession::new(UdpSocket::bind("127.0.0.1:0").unwrap(), None, true).unwrap();
+        // Frames running past the end of the tick counter don't wrap around
+        host.receive(Packet::Input {
+            ack: 0,
+            first: u32::MAX - 1,
+            frames: vec![InputFrame::default(); 4],
+        });
+        assert_eq!(host.remote.len(), INPUT_DELAY as usize);
+
+        // Only the frames inside the window are kept
+        host.receive(Packet::Input {
+            ack: 0,
+            first: INPUT_WINDOW - 1,
+            frames: vec![InputFrame::default(); 4],
+        });
+        assert_eq!(host.remote.len(), INPUT_DELAY as usize + 2);
+    }
+
+    #[test]
+    fn test_bogus_chunk_counts_are_ignored() {
+        let mut joiner =
+            NetSession::new(UdpSocket::bind("127.0.0.1:0").unwrap(), None, false).unwrap();
+        let chunk = |index, count| Packet::Chunk {
+            index,
+            count,
+            data: b"x".to_vec(),
+        };
+        assert!(joiner.receive(chunk(0, 0)).is_none());
+        assert!(!joiner.running);
+
+        // A count that disagrees with the first chunk's doesn't resize
+        assert!(joiner.receive(chunk(0, 2)).is_none());
+        assert!(joiner.receive(chunk(1, 3)).is_none());
+        assert_eq!(joiner.chunks.len(), 2);
+        assert!(matches!(joiner.receive(chunk(1, 2)), Some(NetEvent::Run(run)) if run == "xx"));
+    }
+}
@@ -0,0 +1,273 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, persist_enum};
+use crate::tile_map::TILE_SIZE;
+use crate::ui::ui_width;
+
+pub const NEST_COUNT: u32 = 3; // Nests placed for a destroy objective
+const NEST_HEALTH: u32 = 5; // Shots to destroy
+const NEST_SPAWN_INTERVAL: f32 = 20.0; // Seconds between hostile bots
+pub const NEST_HIT_RADIUS: f32 = TILE_SIZE * 0.6;
+const RESCUE_TARGET: u32 = 4;
+const NO_DAMAGE_SECONDS: f32 = 120.0;
+
+/// Optional goal rolled for each map
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObjectiveKind {
+    DestroyNests,
+    RescueBots, // Reprogram bots into allies
+    NoDamage,
+}
+
+persist_enum!(ObjectiveKind {
+    DestroyNests,
+    RescueBots,
+    NoDamage
+});
+
+impl ObjectiveKind {
+    pub const ALL: [ObjectiveKind; 3] = [
+        ObjectiveKind::DestroyNests,
+        ObjectiveKind::RescueBots,
+        ObjectiveKind::NoDamage,
+    ];
+
+    fn target(self) -> f32 {
+        match self {
+            ObjectiveKind::DestroyNests => NEST_COUNT as f32,
+            ObjectiveKind::RescueBots => RESCUE_TARGET as f32,
+            ObjectiveKind::NoDamage => NO_DAMAGE_SECONDS,
+        }
+    }
+
+    /// Score paid on completion, on top of an item dropped at the player
+    pub fn reward(self) -> u32 {
+        match self {
+            ObjectiveKind::DestroyNests => 50,
+            ObjectiveKind::RescueBots => 40,
+            ObjectiveKind::NoDamage => 40,
+        }
+    }
+}
+
+/// A side objective and how far along it is (a count, or seconds)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Objective {
+    pub kind: ObjectiveKind,
+    pub progress: f32,
+    pub done: bool,
+}
+
+impl Objective {
+    pub fn new(kind: ObjectiveKind) -> Self {
+        Self {
+            kind,
+            progress: 0.0,
+            done: false,
+        }
+    }
+
+    /// One or two different objectives for a new map
+    pub fn roll() -> Vec<Objective> {
+        let mut kinds = ObjectiveKind::ALL.to_vec();
+        let count = rand::gen_range(1, 3);
+        (0..count)
+            .map(|_| Objective::new(kinds.swap_remove(rand::gen_range(0, kinds.len()))))
+            .collect()
+    }
+
+    /// Add progress; returns true on the update that completes the objective
+    pub fn advance(&mut self, amount: f32) -> bool {
+        if self.done {
+            return false;
+        }
+        self.progress = (self.progress + amount).min(self.kind.target());
+        self.done = self.progress >= self.kind.target();
+        self.done
+    }
+
+    /// Start over (a no-damage streak broken by a hit)
+    pub fn reset(&mut self) {
+        if !self.done {
+            self.progress = 0.0;
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.kind {
+            ObjectiveKind::DestroyNests => {
+                format!("Destroy nests {}/{}", self.progress as u32, NEST_COUNT)
+            }
+            ObjectiveKind::RescueBots => {
+                format!("Rescue bots {}/{}", self.progress as u32, RESCUE_TARGET)
+            }
+            ObjectiveKind::NoDamage => format!(
+                "No damage {}s/{}s",
+                self.progress as u32, NO_DAMAGE_SECONDS as u32
+            ),
+        }
+    }
+}
+
+impl Persist for Objective {
+    fn write(&self, out: &mut String) {
+        self.kind.write(out);
+        self.progress.write(out);
+        self.done.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            kind: fields.read()?,
+            progress: fields.read()?,
+            done: fields.read()?,
+        })
+    }
+}
+
+/// Hive that keeps sending out hostile bots until it is shot apart
+pub struct SpawnerNest {
+    pub x: f32, // Pixel coordinates (centered in tile)
+    pub y: f32,
+    health: u32,
+    spawn_timer: f32,
+}
+
+impl SpawnerNest {
+    pub fn new(tile_x: i32, tile_y: i32) -> Self {
+        Self {
+            x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            health: NEST_HEALTH,
+            spawn_timer: NEST_SPAWN_INTERVAL,
+        }
+    }
+
+    pub fn tile(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Take a hit; returns true when it destroys the nest
+    pub fn hit(&mut self) -> bool {
+        self.health = self.health.saturating_sub(1);
+        self.is_destroyed()
+    }
+
+    /// Returns true when a hostile bot should hatch this frame
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.spawn_timer -= dt;
+        if self.spawn_timer > 0.0 {
+            return false;
+        }
+        self.spawn_timer = NEST_SPAWN_INTERVAL;
+        true
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let color = Color::from_rgba(200, 60, 160, 255);
+        let pulse = 1.0 + (1.0 - self.spawn_timer / NEST_SPAWN_INTERVAL) * 2.0;
+
+        draw_circle(
+            screen_x,
+            screen_y,
+            9.0 + pulse,
+            Color::from_rgba(60, 20, 50, 255),
+        );
+        draw_circle_lines(screen_x, screen_y, 9.0 + pulse, 2.0, color);
+        // Remaining health as pips
+        for i in 0..self.health {
+            let px = screen_x - (NEST_HEALTH as f32 - 1.0) * 2.5 + i as f32 * 5.0;
+            draw_circle(px, screen_y, 1.5, color);
+        }
+    }
+}
+
+impl Persist for SpawnerNest {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.health.write(out);
+        self.spawn_timer.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            health: fields.read()?,
+            spawn_timer: fields.read()?,
+        })
+    }
+}
+
+/// Objective list under the terminal counter, from `top` down; finished
+/// ones are ticked off
+pub fn draw_objectives(objectives: &[Objective], top: f32) {
+    for (i, objective) in objectives.iter().enumerate() {
+        let y = top + i as f32 * 18.0;
+        let (mark, color) = if objective.done {
+            ("[x]", Color::from_rgba(100, 230, 100, 255))
+        } else {
+            ("[ ]", Color::from_rgba(200, 200, 200, 255))
+        };
+        draw_text(
+            &format!("{} {}", mark, objective.label()),
+            ui_width() - 200.0,
+            y,
+            16.0,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objective_progress() {
+        let mut rescue = Objective::new(ObjectiveKind::RescueBots);
+        for _ in 0..RESCUE_TARGET - 1 {
+            assert!(!rescue.advance(1.0));
+        }
+        assert_eq!(rescue.label(), "Rescue bots 3/4");
+        assert!(rescue.advance(1.0));
+        assert!(!rescue.advance(1.0)); // Completes once
+        rescue.reset();
+        assert!(rescue.done);
+
+        let mut careful = Objective::new(ObjectiveKind::NoDamage);
+        careful.advance(90.0);
+        careful.reset();
+        assert_eq!(careful.progress, 0.0);
+        assert!(careful.advance(NO_DAMAGE_SECONDS));
+
+        for _ in 0..20 {
+            let rolled = Objective::roll();
+            assert!((1..=2).contains(&rolled.len()));
+            assert!(rolled.iter().all(|o| o.progress == 0.0 && !o.done));
+            if let [a, b] = rolled.as_slice() {
+                assert_ne!(a.kind, b.kind);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nest_hits_and_spawns() {
+        let mut nest = SpawnerNest::new(3, 4);
+        assert_eq!(nest.tile(), (3, 4));
+        assert!(!nest.update(NEST_SPAWN_INTERVAL - 1.0));
+        assert!(nest.update(1.0));
+        for _ in 0..NEST_HEALTH - 1 {
+            assert!(!nest.hit());
+        }
+        assert!(nest.hit());
+        assert!(nest.is_destroyed());
+    }
+}
@@ -0,0 +1,327 @@
+use macroquad::prelude::*;
+
+const MAX_PARTICLES: usize = 800; // The oldest make way past this
+const EMBER_RATE: f32 = 0.5; // Embers per second rising off each lava tile in view
+const MUZZLE_OFFSET: f32 = 14.0; // Pixels from the shooter's center to the muzzle
+const FLASH_TIME: f32 = 0.05; // Seconds a muzzle flash shows
+const FLASH_LENGTH: f32 = 14.0;
+const FLASH_WIDTH: f32 = 9.0;
+
+/// One speck of light or debris, in world pixels
+#[derive(Clone, Debug)]
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32, // Pixels per second
+    vy: f32,
+    gravity: f32, // Pixels per second squared, down the screen
+    life: f32,    // Seconds left
+    lifetime: f32,
+    size: f32,
+    from: Color, // Color at birth, fading into `to` by the end
+    to: Color,
+}
+
+impl Particle {
+    /// Color at this point of its life
+    fn color(&self) -> Color {
+        let t = 1.0 - (self.life / self.lifetime).clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Color::new(
+            mix(self.from.r, self.to.r),
+            mix(self.from.g, self.to.g),
+            mix(self.from.b, self.to.b),
+            mix(self.from.a, self.to.a),
+        )
+    }
+}
+
+/// A muzzle flash pointing along a shot
+#[derive(Clone, Debug)]
+struct Flash {
+    x: f32, // The muzzle, in world pixels
+    y: f32,
+    angle: f32,
+    life: f32,
+}
+
+/// Kind of particle burst
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Burst {
+    Impact,    // Sparks off a bullet hitting a wall or bot
+    Splinters, // Wood chips off a crate
+    Ember,     // A glowing speck drifting up from lava
+    Explosion, // Fireball and smoke from a blast or a bot blowing apart
+    Sparkle,   // Glints over a picked up item
+}
+
+/// How a burst throws its particles
+struct Spec {
+    count: usize,
+    speed: (f32, f32), // Pixels per second
+    gravity: f32,
+    life: (f32, f32), // Seconds
+    size: (f32, f32), // Pixels
+    from: Color,
+    to: Color,
+}
+
+impl Burst {
+    fn spec(self) -> Spec {
+        match self {
+            Burst::Impact => Spec {
+                count: 6,
+                speed: (60.0, 160.0),
+                gravity: 0.0,
+                life: (0.1, 0.25),
+                size: (1.0, 2.0),
+                from: Color::from_rgba(255, 240, 160, 255),
+                to: Color::from_rgba(255, 120, 40, 0),
+            },
+            Burst::Splinters => Spec {
+                count: 10,
+                speed: (40.0, 140.0),
+                gravity: 260.0,
+                life: (0.3, 0.6),
+                size: (1.5, 3.0),
+                from: Color::from_rgba(160, 105, 55, 255),
+                to: Color::from_rgba(110, 70, 35, 0),
+            },
+            Burst::Ember => Spec {
+                count: 1,
+                speed: (5.0, 20.0),
+                gravity: -30.0,
+                life: (0.8, 1.6),
+                size: (1.0, 2.0),
+                from: Color::from_rgba(255, 200, 80, 230),
+                to: Color::from_rgba(200, 40, 20, 0),
+            },
+            Burst::Explosion => Spec {
+                count: 28,
+                speed: (30.0, 220.0),
+                gravity: -20.0,
+                life: (0.3, 0.8),
+                size: (2.0, 5.0),
+                from: Color::from_rgba(255, 220, 120, 255),
+                to: Color::from_rgba(70, 60, 60, 0),
+            },
+            Burst::Sparkle => Spec {
+                count: 8,
+                speed: (20.0, 60.0),
+                gravity: -40.0,
+                life: (0.3, 0.6),
+                size: (1.0, 2.0),
+                from: Color::from_rgba(255, 255, 220, 255),
+                to: Color::from_rgba(120, 220, 255, 0),
+            },
+        }
+    }
+}
+
+/// Every live particle. Particles only ever touch the screen, so they roll
+/// their own random numbers and leave the game's generator alone (online
+/// play needs both machines to draw the same numbers from it).
+pub struct Particles {
+    particles: Vec<Particle>,
+    flashes: Vec<Flash>,
+    seed: u32,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            flashes: Vec::new(),
+            seed: 0x9e37_79b9,
+        }
+    }
+}
+
+impl Particles {
+    /// Random number in lo..hi (xorshift)
+    fn random(&mut self, lo: f32, hi: f32) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        lo + (hi - lo) * (self.seed as f32 / u32::MAX as f32)
+    }
+
+    /// Throw a burst of particles out from a world pixel position
+    pub fn burst(&mut self, burst: Burst, x: f32, y: f32) {
+        let spec = burst.spec();
+        for _ in 0..spec.count {
+            let angle = self.random(0.0, std::f32::consts::TAU);
+            let speed = self.random(spec.speed.0, spec.speed.1);
+            let lifetime = self.random(spec.life.0, spec.life.1);
+            let size = self.random(spec.size.0, spec.size.1);
+            self.particles.push(Particle {
+                x,
+                y,
+                vx: angle.cos() * speed,
+                vy: angle.sin() * speed,
+                gravity: spec.gravity,
+                life: lifetime,
+                lifetime,
+                size,
+                from: spec.from,
+                to: spec.to,
+            });
+        }
+        if self.particles.len() > MAX_PARTICLES {
+            let excess = self.particles.len() - MAX_PARTICLES;
+            self.particles.drain(..excess);
+        }
+    }
+
+    /// A shot fired from a shooter's center toward `angle`: a flash at the
+    /// muzzle and a spent casing kicked out to the right
+    pub fn muzzle_flash(&mut self, x: f32, y: f32, angle: f32) {
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let (mx, my) = (x + dx * MUZZLE_OFFSET, y + dy * MUZZLE_OFFSET);
+        self.flashes.push(Flash {
+            x: mx,
+            y: my,
+            angle,
+            life: FLASH_TIME,
+        });
+
+        let side = self.random(50.0, 90.0);
+        let back = self.random(0.0, 30.0);
+        let lifetime = self.random(0.4, 0.6);
+        self.particles.push(Particle {
+            x: x + dx * MUZZLE_OFFSET / 2.0,
+            y: y + dy * MUZZLE_OFFSET / 2.0,
+            vx: -dy * side - dx * back,
+            vy: dx * side - dy * back,
+            gravity: 200.0,
+            life: lifetime,
+            lifetime,
+            size: 2.0,
+            from: Color::from_rgba(220, 180, 70, 255),
+            to: Color::from_rgba(140, 110, 40, 0),
+        });
+    }
+
+    /// Let embers rise off lava tiles (world pixel corners, TILE_SIZE wide)
+    pub fn emit_embers(&mut self, dt: f32, lava: &[(f32, f32)], tile_size: f32) {
+        for &(x, y) in lava {
+            if self.random(0.0, 1.0) < EMBER_RATE * dt {
+                let ex = x + self.random(0.0, tile_size);
+                let ey = y + self.random(0.0, tile_size);
+                self.burst(Burst::Ember, ex, ey);
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for p in &mut self.particles {
+            p.vy += p.gravity * dt;
+            p.x += p.vx * dt;
+            p.y += p.vy * dt;
+            p.life -= dt;
+        }
+        self.particles.retain(|p| p.life > 0.0);
+        for flash in &mut self.flashes {
+            flash.life -= dt;
+        }
+        self.flashes.retain(|f| f.life > 0.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.flashes.clear();
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        for p in &self.particles {
+            draw_rectangle(
+                p.x - camera_x - p.size / 2.0,
+                p.y - camera_y - p.size / 2.0,
+                p.size,
+                p.size,
+                p.color(),
+            );
+        }
+        for flash in &self.flashes {
+            let (x, y) = (flash.x - camera_x, flash.y - camera_y);
+            draw_rectangle_ex(
+                x,
+                y,
+                FLASH_LENGTH,
+                FLASH_WIDTH,
+                DrawRectangleParams {
+                    offset: vec2(0.0, 0.5),
+                    rotation: flash.angle,
+                    color: Color::from_rgba(255, 200, 90, 200),
+                },
+            );
+            draw_circle(
+                x,
+                y,
+                FLASH_WIDTH / 2.0,
+                Color::from_rgba(255, 250, 210, 255),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_particles() {
+        let mut particles = Particles::default();
+        particles.burst(Burst::Splinters, 100.0, 100.0);
+        assert_eq!(particles.particles.len(), Burst::Splinters.spec().count);
+
+        // Splinters fall and fade
+        particles.update(0.2);
+        let p = &particles.particles[0];
+        assert!(p.vy > -Burst::Splinters.spec().speed.1);
+        assert!(p.color().a < 1.0);
+
+        // Everything is gone once the longest life is over
+        particles.update(Burst::Splinters.spec().life.1);
+        assert!(particles.particles.is_empty());
+
+        // The oldest are dropped past the cap
+        for _ in 0..MAX_PARTICLES {
+            particles.burst(Burst::Explosion, 0.0, 0.0);
+        }
+        assert_eq!(particles.particles.len(), MAX_PARTICLES);
+
+        // No lava, no embers
+        particles.clear();
+        particles.emit_embers(1.0, &[], 32.0);
+        assert!(particles.particles.is_empty());
+        particles.emit_embers(100.0, &[(0.0, 0.0)], 32.0);
+        assert_eq!(particles.particles.len(), 1);
+    }
+
+    #[test]
+    fn test_muzzle_flash() {
+        let mut particles = Particles::default();
+        particles.muzzle_flash(100.0, 100.0, 0.0);
+        assert_eq!(particles.flashes.len(), 1);
+        assert_eq!(particles.flashes[0].x, 100.0 + MUZZLE_OFFSET);
+
+        // The casing flies out to the shooter's right (down, firing right)
+        assert_eq!(particles.particles.len(), 1);
+        assert!(particles.particles[0].vy > 0.0);
+
+        // The flash is gone well before the casing
+        particles.update(FLASH_TIME);
+        assert!(particles.flashes.is_empty());
+        assert_eq!(particles.particles.len(), 1);
+    }
+
+    #[test]
+    fn test_random_range() {
+        let mut particles = Particles::default();
+        for _ in 0..100 {
+            let n = particles.random(2.0, 5.0);
+            assert!((2.0..=5.0).contains(&n));
+        }
+    }
+}
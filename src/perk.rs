@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+
+use crate::ui::{ui_height, ui_width};
+
+const BASE_LEVEL_XP: u32 = 50; // XP from level 1 to level 2
+const LEVEL_XP_STEP: u32 = 25; // Extra XP needed for each level after that
+
+// XP awards
+const XP_HOSTILE_KILL: u32 = 15;
+const XP_BOT_KILL: u32 = 5;
+pub const XP_TERMINAL_HACK: u32 = 40;
+pub const XP_MINOR_HACK: u32 = 10; // Doors forced and bots reprogrammed
+pub const EXPLORE_TILES_PER_XP: u32 = 10; // Newly revealed tiles worth one XP
+
+// Perk effects
+pub const MAX_HEALTH_PERK: i32 = 15;
+pub const FIRE_RATE_PERK: f32 = 0.12; // Fraction faster weapon cooldowns
+pub const MOVE_SPEED_PERK: f32 = 0.1; // Fraction faster movement
+pub const ARMOR_PERK: i32 = 25; // Max armor, also granted right away
+pub const INSULATION_PERK: f32 = 0.25; // Fire and explosive resistance
+
+/// Upgrade picked from the level-up overlay
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Perk {
+    MaxHealth,
+    FireRate,
+    MoveSpeed,
+    Armor,
+    Insulation,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 5] = [
+        Perk::MaxHealth,
+        Perk::FireRate,
+        Perk::MoveSpeed,
+        Perk::Armor,
+        Perk::Insulation,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Perk::MaxHealth => "Reinforced Frame",
+            Perk::FireRate => "Quick Trigger",
+            Perk::MoveSpeed => "Servo Legs",
+            Perk::Armor => "Plated Vest",
+            Perk::Insulation => "Insulated Suit",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Perk::MaxHealth => "+15 max health",
+            Perk::FireRate => "+12% fire rate",
+            Perk::MoveSpeed => "+10% move speed",
+            Perk::Armor => "+25 max armor and 25 armor now",
+            Perk::Insulation => "+25% fire and explosive resistance",
+        }
+    }
+
+    /// Three different perks in random order
+    pub fn roll_choices() -> [Perk; 3] {
+        let mut perks = Perk::ALL;
+        for i in (1..perks.len()).rev() {
+            perks.swap(i, rand::gen_range(0, i + 1));
+        }
+        [perks[0], perks[1], perks[2]]
+    }
+}
+
+/// XP needed to go from `level` to the next one
+pub fn xp_to_next(level: u32) -> u32 {
+    BASE_LEVEL_XP + LEVEL_XP_STEP * level.saturating_sub(1)
+}
+
+/// XP for destroying a bot; hostiles are worth more
+pub fn kill_xp(hostile: bool) -> u32 {
+    if hostile {
+        XP_HOSTILE_KILL
+    } else {
+        XP_BOT_KILL
+    }
+}
+
+pub const LEVEL_UP_COLOR: Color = Color::new(0.55, 0.9, 1.0, 1.0);
+
+/// Level-up overlay: pick one of three perks
+pub fn draw_level_up_menu(level: u32, choices: &[Perk; 3], selected: usize) {
+    let row_height = 52.0;
+    let width = 360.0;
+    let height = 110.0 + choices.len() as f32 * row_height;
+    let x = (ui_width() - width) / 2.0;
+    let y = (ui_height() - height) / 2.0;
+
+    draw_rectangle(
+        0.0,
+        0.0,
+        ui_width(),
+        ui_height(),
+        Color::from_rgba(0, 0, 0, 120),
+    );
+    draw_rectangle(x, y, width, height, Color::from_rgba(10, 20, 30, 235));
+    draw_rectangle_lines(x, y, width, height, 2.0, LEVEL_UP_COLOR);
+    draw_text(
+        &format!("LEVEL {} - CHOOSE A PERK", level),
+        x + 16.0,
+        y + 30.0,
+        24.0,
+        LEVEL_UP_COLOR,
+    );
+
+    for (i, perk) in choices.iter().enumerate() {
+        let row_y = y + 48.0 + i as f32 * row_height;
+        if i == selected {
+            draw_rectangle(
+                x + 8.0,
+                row_y,
+                width - 16.0,
+                row_height - 4.0,
+                Color::from_rgba(140, 230, 255, 50),
+            );
+        }
+        draw_text(perk.name(), x + 20.0, row_y + 20.0, 22.0, WHITE);
+        draw_text(perk.description(), x + 20.0, row_y + 40.0, 18.0, GRAY);
+    }
+
+    draw_text(
+        "W/S select   E / Enter choose",
+        x + 16.0,
+        y + height - 14.0,
+        16.0,
+        GRAY,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xp_curve_and_choices() {
+        assert_eq!(xp_to_next(1), BASE_LEVEL_XP);
+        assert!(xp_to_next(5) > xp_to_next(4));
+        assert!(kill_xp(true) > kill_xp(false));
+
+        for _ in 0..20 {
+            let [a, b, c] = Perk::roll_choices();
+            assert!(a != b && b != c && a != c);
+        }
+    }
+}
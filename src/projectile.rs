@@ -12,6 +12,9 @@ pub struct Projectile {
     max_range: f32,
     pub alive: bool,
     pub from_player: bool,
+    pub source: Option<&'static str>, // What fired it, for kill stats
+    pub owner: Option<usize>,         // Player who fired it
+    pub knockback: i32,               // Tiles a hit shoves a bot that survives it
 }
 
 impl Projectile {
@@ -38,6 +41,9 @@ impl Projectile {
             max_range,
             alive: true,
             from_player: true,
+            source: None,
+            owner: None,
+            knockback: 0,
         }
     }
 
@@ -53,6 +59,9 @@ impl Projectile {
             max_range,
             alive: true,
             from_player: true,
+            source: None,
+            owner: None,
+            knockback: 0,
         }
     }
 
@@ -68,6 +77,33 @@ impl Projectile {
             max_range,
             alive: true,
             from_player: false,
+            source: None,
+            owner: None,
+            knockback: 0,
+        }
+    }
+
+    pub fn with_source(mut self, source: &'static str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_owner(mut self, player: usize) -> Self {
+        self.owner = Some(player);
+        self
+    }
+
+    pub fn with_knockback(mut self, tiles: i32) -> Self {
+        self.knockback = tiles;
+        self
+    }
+
+    /// Grid step along the main axis of travel, for shoving what it hits
+    pub fn push_direction(&self) -> (i32, i32) {
+        if self.dx.abs() >= self.dy.abs() {
+            (self.dx.signum() as i32, 0)
+        } else {
+            (0, self.dy.signum() as i32)
         }
     }
 
@@ -131,6 +167,16 @@ mod tests {
         assert!((p.dy - expected).abs() < 0.001);
     }
 
+    #[test]
+    fn test_projectile_push_direction() {
+        let p = Projectile::new(0.0, 0.0, -100.0, 30.0, 100.0, 500.0);
+        assert_eq!(p.push_direction(), (-1, 0));
+        let p = Projectile::new(0.0, 0.0, 30.0, 100.0, 100.0, 500.0);
+        assert_eq!(p.push_direction(), (0, 1));
+        assert_eq!(p.knockback, 0);
+        assert_eq!(p.with_knockback(2).knockback, 2);
+    }
+
     #[test]
     fn test_projectile_zero_distance() {
         let p = Projectile::new(50.0, 50.0, 50.0, 50.0, 100.0, 500.0);
@@ -0,0 +1,178 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist};
+use crate::tile_map::TILE_SIZE;
+
+const PYLON_CHANCE: f32 = 0.35; // Chance a map gets a pylon
+const PYLON_HEALTH: u32 = 8; // Shots to destroy
+pub const PYLON_HIT_RADIUS: f32 = TILE_SIZE * 0.6;
+const HEARING_RANGE: f32 = TILE_SIZE * 10.0;
+const NOISE_THRESHOLD: f32 = 10.0; // Shots heard in quick succession
+const NOISE_DECAY: f32 = 2.0; // Shots forgotten per second
+const HORDE_DURATION: f32 = 25.0;
+const HORDE_SPAWN_INTERVAL: f32 = 1.5; // Seconds between hostile bots while sounding
+
+/// Rare alarm that listens for gunfire; enough shots nearby set off a
+/// timed horde of hostile bots, which only ends early if it is shot apart
+pub struct AlarmPylon {
+    pub x: f32, // Pixel coordinates (centered in tile)
+    pub y: f32,
+    health: u32,
+    noise: f32,
+    horde: f32, // Seconds of horde left
+    spawn_timer: f32,
+}
+
+impl AlarmPylon {
+    pub fn new(tile_x: i32, tile_y: i32) -> Self {
+        Self {
+            x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            health: PYLON_HEALTH,
+            noise: 0.0,
+            horde: 0.0,
+            spawn_timer: 0.0,
+        }
+    }
+
+    /// Whether a new map gets a pylon
+    pub fn roll() -> bool {
+        rand::gen_range(0.0, 1.0) < PYLON_CHANCE
+    }
+
+    pub fn tile(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.health == 0
+    }
+
+    pub fn is_sounding(&self) -> bool {
+        self.horde > 0.0
+    }
+
+    /// Take a hit; returns true when it destroys the pylon
+    pub fn hit(&mut self) -> bool {
+        self.health = self.health.saturating_sub(1);
+        self.is_destroyed()
+    }
+
+    /// Register a shot fired at a pixel position; returns true when it sets
+    /// off the horde
+    pub fn hear_shot(&mut self, x: f32, y: f32) -> bool {
+        if self.is_sounding() || (x - self.x).hypot(y - self.y) > HEARING_RANGE {
+            return false;
+        }
+        self.noise += 1.0;
+        if self.noise < NOISE_THRESHOLD {
+            return false;
+        }
+        self.noise = 0.0;
+        self.horde = HORDE_DURATION;
+        self.spawn_timer = 0.0;
+        true
+    }
+
+    /// Returns true when a horde bot should arrive this frame
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.noise = (self.noise - NOISE_DECAY * dt).max(0.0);
+        if !self.is_sounding() {
+            return false;
+        }
+        self.horde = (self.horde - dt).max(0.0);
+        self.spawn_timer -= dt;
+        if self.spawn_timer > 0.0 {
+            return false;
+        }
+        self.spawn_timer = HORDE_SPAWN_INTERVAL;
+        true
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let blink = self.is_sounding() && get_time().fract() < 0.5;
+        let light = if blink {
+            Color::from_rgba(255, 40, 40, 255)
+        } else {
+            Color::from_rgba(255, 170, 40, 255)
+        };
+
+        draw_rectangle(
+            screen_x - 4.0,
+            screen_y - 12.0,
+            8.0,
+            22.0,
+            Color::from_rgba(70, 70, 80, 255),
+        );
+        draw_circle(screen_x, screen_y - 12.0, 5.0, light);
+        if blink {
+            draw_circle_lines(screen_x, screen_y - 12.0, 10.0, 2.0, light);
+        }
+        // Noise meter: how close the gunfire is to setting it off
+        if self.noise > 0.0 {
+            let width = 16.0 * self.noise / NOISE_THRESHOLD;
+            draw_rectangle(screen_x - 8.0, screen_y + 13.0, width, 3.0, light);
+        }
+    }
+}
+
+impl Persist for AlarmPylon {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.health.write(out);
+        self.noise.write(out);
+        self.horde.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            health: fields.read()?,
+            noise: fields.read()?,
+            horde: fields.read()?,
+            spawn_timer: 0.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gunfire_sets_off_horde() {
+        let mut pylon = AlarmPylon::new(10, 10);
+        let (x, y) = (pylon.x + TILE_SIZE * 3.0, pylon.y);
+
+        // Far away shots go unheard, and scattered shots are forgotten
+        assert!(!pylon.hear_shot(pylon.x + HEARING_RANGE * 2.0, y));
+        for _ in 0..5 {
+            assert!(!pylon.hear_shot(x, y));
+        }
+        pylon.update(5.0);
+        assert!(!pylon.update(0.1));
+
+        for _ in 0..NOISE_THRESHOLD as usize - 1 {
+            assert!(!pylon.hear_shot(x, y));
+        }
+        assert!(pylon.hear_shot(x, y));
+        assert!(pylon.update(0.1)); // First bot right away
+        assert!(!pylon.update(0.1));
+        pylon.update(HORDE_DURATION);
+        assert!(!pylon.is_sounding());
+    }
+
+    #[test]
+    fn test_pylon_health() {
+        let mut pylon = AlarmPylon::new(2, 3);
+        assert_eq!(pylon.tile(), (2, 3));
+        for _ in 0..PYLON_HEALTH - 1 {
+            assert!(!pylon.hit());
+        }
+        assert!(pylon.hit());
+    }
+}
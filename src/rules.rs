@@ -0,0 +1,68 @@
+use macroquad::prelude::*;
+
+use crate::save::persist_enum;
+
+const STANDARD_LIVES: u32 = 3;
+
+/// What dying costs, picked alongside the class on New Game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LifeRules {
+    Standard, // A few lives, then game over
+    Hardcore, // One life; dying ends the run
+    Casual,   // Endless respawns, score reset on death
+}
+
+persist_enum!(LifeRules {
+    Standard,
+    Hardcore,
+    Casual
+});
+
+impl LifeRules {
+    pub const ALL: [LifeRules; 3] = [LifeRules::Standard, LifeRules::Hardcore, LifeRules::Casual];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LifeRules::Standard => "STANDARD",
+            LifeRules::Hardcore => "HARDCORE",
+            LifeRules::Casual => "CASUAL",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            LifeRules::Standard => "3 lives, then game over",
+            LifeRules::Hardcore => "One life - death ends the run",
+            LifeRules::Casual => "Unlimited respawns, score resets on death",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            LifeRules::Standard => Color::from_rgba(230, 230, 120, 255),
+            LifeRules::Hardcore => Color::from_rgba(255, 80, 80, 255),
+            LifeRules::Casual => Color::from_rgba(150, 220, 150, 255),
+        }
+    }
+
+    /// Lives at the start of a run; None means unlimited
+    pub fn starting_lives(self) -> Option<u32> {
+        match self {
+            LifeRules::Standard => Some(STANDARD_LIVES),
+            LifeRules::Hardcore => Some(1),
+            LifeRules::Casual => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starting_lives() {
+        assert_eq!(LifeRules::Standard.starting_lives(), Some(STANDARD_LIVES));
+        assert_eq!(LifeRules::Hardcore.starting_lives(), Some(1));
+        assert_eq!(LifeRules::Casual.starting_lives(), None);
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::{Lines, SplitWhitespace};
+
+const SAVE_FILE: &str = "savegame.txt";
+const SAVE_VERSION: u32 = 1;
+
+/// A value stored in a save record as one or more space separated words;
+/// compound values write their parts in order and read them back the same way
+pub trait Persist {
+    fn write(&self, out: &mut String);
+    fn read(fields: &mut Fields) -> Result<Self, String>
+    where
+        Self: Sized;
+}
+
+/// Append one word to a record line
+pub fn push_word(out: &mut String, word: impl Display) {
+    out.push(' ');
+    out.push_str(&word.to_string());
+}
+
+/// The words of one record, consumed front to back
+pub struct Fields<'a> {
+    tag: &'a str,
+    words: Peekable<SplitWhitespace<'a>>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn word(&mut self) -> Result<&'a str, String> {
+        self.words
+            .next()
+            .ok_or_else(|| format!("'{}' record is too short", self.tag))
+    }
+
+    pub fn read<T: Persist>(&mut self) -> Result<T, String> {
+        T::read(self)
+    }
+
+    pub fn error(&self, word: &str) -> String {
+        format!("bad value '{}' in '{}' record", word, self.tag)
+    }
+}
+
+macro_rules! persist_parsed {
+    ($($ty:ty),*) => {
+        $(impl Persist for $ty {
+            fn write(&self, out: &mut String) {
+                push_word(out, self);
+            }
+
+            fn read(fields: &mut Fields) -> Result<Self, String> {
+                let word = fields.word()?;
+                word.parse().map_err(|_| fields.error(word))
+            }
+        })*
+    };
+}
+
+persist_parsed!(u8, u32, u64, i32, usize, f32, bool);
+
+/// Persist a fieldless enum by its variant names
+macro_rules! persist_enum {
+    ($ty:ident { $($variant:ident),+ $(,)? }) => {
+        impl $crate::save::Persist for $ty {
+            fn write(&self, out: &mut String) {
+                let name = match self {
+                    $($ty::$variant => stringify!($variant),)+
+                };
+                $crate::save::push_word(out, name);
+            }
+
+            fn read(fields: &mut $crate::save::Fields) -> Result<Self, String> {
+                let word = fields.word()?;
+                match word {
+                    $(stringify!($variant) => Ok($ty::$variant),)+
+                    _ => Err(fields.error(word)),
+                }
+            }
+        }
+    };
+}
+pub(crate) use persist_enum;
+
+/// Spaces are written as underscores to keep the text a single word
+impl Persist for String {
+    fn write(&self, out: &mut String) {
+        push_word(out, self.replace(' ', "_"));
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(fields.word()?.replace('_', " "))
+    }
+}
+
+/// None is written as "-"
+impl<T: Persist> Persist for Option<T> {
+    fn write(&self, out: &mut String) {
+        match self {
+            Some(value) => value.write(out),
+            None => push_word(out, "-"),
+        }
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        if fields.words.peek() == Some(&"-") {
+            fields.words.next();
+            return Ok(None);
+        }
+        fields.read().map(Some)
+    }
+}
+
+/// Length first, then the elements
+impl<T: Persist> Persist for Vec<T> {
+    fn write(&self, out: &mut String) {
+        push_word(out, self.len());
+        for value in self {
+            value.write(out);
+        }
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let len: usize = fields.read()?;
+        (0..len).map(|_| fields.read()).collect()
+    }
+}
+
+impl<T: Persist, const N: usize> Persist for [T; N] {
+    fn write(&self, out: &mut String) {
+        for value in self {
+            value.write(out);
+        }
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let values: Vec<T> = (0..N).map(|_| fields.read()).collect::<Result<_, _>>()?;
+        values
+            .try_into()
+            .map_err(|_| "array length mismatch".to_string())
+    }
+}
+
+impl<A: Persist, B: Persist> Persist for (A, B) {
+    fn write(&self, out: &mut String) {
+        self.0.write(out);
+        self.1.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok((fields.read()?, fields.read()?))
+    }
+}
+
+/// Builds a save file: a version line, then one record per line
+pub struct SaveWriter {
+    text: String,
+}
+
+impl SaveWriter {
+    pub fn new() -> Self {
+        let mut writer = Self {
+            text: String::new(),
+        };
+        writer.record("save", &[&SAVE_VERSION]);
+        writer
+    }
+
+    /// Append a record: its tag, then the words of each value
+    pub fn record(&mut self, tag: &str, values: &[&dyn Persist]) {
+        self.text.push_str(tag);
+        for value in values {
+            value.write(&mut self.text);
+        }
+        self.text.push('\n');
+    }
+
+    /// Append one record per element
+    pub fn records<'v, T: Persist + 'v>(
+        &mut self,
+        tag: &str,
+        values: impl IntoIterator<Item = &'v T>,
+    ) {
+        for value in values {
+            self.record(tag, &[value]);
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+}
+
+/// Reads a save file back in the order it was written
+pub struct SaveReader<'a> {
+    lines: Peekable<Lines<'a>>,
+}
+
+impl<'a> SaveReader<'a> {
+    /// Start reading a save, refusing other versions of the format
+    pub fn new(text: &'a str) -> Result<Self, String> {
+        let mut reader = Self {
+            lines: text.lines().peekable(),
+        };
+        let version: u32 = reader.record("save")?.read()?;
+        if version != SAVE_VERSION {
+            return Err(format!("unsupported save version {version}"));
+        }
+        Ok(reader)
+    }
+
+    /// The next record, which must have this tag
+    pub fn record(&mut self, tag: &str) -> Result<Fields<'a>, String> {
+        let line = self.lines.next().unwrap_or_default();
+        let mut words = line.split_whitespace().peekable();
+        match words.next() {
+            Some(found) if found == tag => Ok(Fields { tag: found, words }),
+            Some(found) => Err(format!("expected '{tag}' record, found '{found}'")),
+            None => Err(format!("expected '{tag}' record")),
+        }
+    }
+
+    /// All consecutive records with this tag
+    pub fn records(&mut self, tag: &str) -> Vec<Fields<'a>> {
+        let mut records = Vec::new();
+        while self
+            .lines
+            .peek()
+            .is_some_and(|line| line.split_whitespace().next() == Some(tag))
+        {
+            records.extend(self.record(tag).ok());
+        }
+        records
+    }
+
+    /// Read the single value of every consecutive record with this tag
+    pub fn read_all<T: Persist>(&mut self, tag: &str) -> Result<Vec<T>, String> {
+        self.records(tag).iter_mut().map(|f| f.read()).collect()
+    }
+}
+
+/// Whether a saved run is waiting to be continued
+pub fn exists() -> bool {
+    std::path::Path::new(SAVE_FILE).exists()
+}
+
+pub fn store(text: &str) {
+    if let Err(err) = std::fs::write(SAVE_FILE, text) {
+        eprintln!("Could not save {SAVE_FILE}: {err}");
+    }
+}
+
+pub fn load() -> Option<String> {
+    std::fs::read_to_string(SAVE_FILE).ok()
+}
+
+/// Remove the saved run once it is restored: a save is continued once, and
+/// quitting mid-run writes a new one
+pub fn remove() {
+    if let Err(err) = std::fs::remove_file(SAVE_FILE) {
+        eprintln!("Could not remove {SAVE_FILE}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Color {
+        Red,
+        Blue,
+    }
+    persist_enum!(Color { Red, Blue });
+
+    #[test]
+    fn test_round_trip() {
+        let mut writer = SaveWriter::new();
+        writer.record("values", &[&7u32, &-3i32, &0.1f32, &true, &Color::Blue]);
+        writer.record(
+            "options",
+            &[&Some(4u8), &None::<u8>, &vec![(1usize, 2usize)]],
+        );
+        writer.record("text", &[&"Machine Pistol".to_string(), &[3u32, 4]]);
+        writer.records("color", &[Color::Red, Color::Blue]);
+        let text = writer.finish();
+
+        let mut reader = SaveReader::new(&text).unwrap();
+        let mut values = reader.record("values").unwrap();
+        assert_eq!(values.read::<u32>(), Ok(7));
+        assert_eq!(values.read::<i32>(), Ok(-3));
+        assert_eq!(values.read::<f32>(), Ok(0.1));
+        assert_eq!(values.read::<bool>(), Ok(true));
+        assert_eq!(values.read::<Color>(), Ok(Color::Blue));
+        assert!(values.read::<u32>().is_err());
+
+        let mut options = reader.record("options").unwrap();
+        assert_eq!(options.read::<Option<u8>>(), Ok(Some(4)));
+        assert_eq!(options.read::<Option<u8>>(), Ok(None));
+        assert_eq!(options.read::<Vec<(usize, usize)>>(), Ok(vec![(1, 2)]));
+        let mut text = reader.record("text").unwrap();
+        assert_eq!(text.read::<String>(), Ok("Machine Pistol".to_string()));
+        assert_eq!(text.read::<[u32; 2]>(), Ok([3, 4]));
+
+        let colors: Vec<Color> = reader.read_all("color").unwrap();
+        assert_eq!(colors, vec![Color::Red, Color::Blue]);
+        assert!(reader.record("missing").is_err());
+    }
+
+    #[test]
+    fn test_version_check() {
+        assert!(SaveReader::new("save 1\n").is_ok());
+        assert!(SaveReader::new("save 99\n").is_err());
+        assert!(SaveReader::new("").is_err());
+        assert!(SaveReader::new("settings 1\n").is_err());
+    }
+}
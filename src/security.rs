@@ -0,0 +1,332 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist};
+use crate::tile_map::{Cardinal, EntityType, TILE_SIZE, TileMap};
+
+const CAMERA_RANGE: f32 = TILE_SIZE * 5.0;
+const CAMERA_HALF_FOV: f32 = 0.5; // Radians either side of the view direction
+const CAMERA_SWEEP: f32 = 0.7; // Radians the view swings either side of its base
+const CAMERA_SWEEP_SPEED: f32 = 0.8;
+const CAMERA_COOLDOWN: f32 = 4.0; // Seconds between alarms from one camera
+const CONE_SEGMENTS: usize = 8;
+const PANEL_HACK_TIME: f32 = 2.0; // Seconds of holding E to lift a lockdown
+
+/// Wall-mounted camera sweeping a vision cone; spotting the player raises an alarm.
+pub struct SecurityCamera {
+    pub x: f32, // Pixel coordinates (on the wall edge of its tile)
+    pub y: f32,
+    base_angle: f32,
+    sweep_time: f32,
+    cooldown: f32,
+    pub alive: bool,
+}
+
+impl SecurityCamera {
+    /// Mount a camera on a floor tile against a wall, looking away from it.
+    /// Returns None if the tile has no wall behind an open view.
+    pub fn try_mount(map: &TileMap, tile_x: i32, tile_y: i32) -> Option<Self> {
+        if !map.is_walkable_by(tile_x, tile_y, EntityType::Player) {
+            return None;
+        }
+        let facing = [
+            Cardinal::Up,
+            Cardinal::Down,
+            Cardinal::Left,
+            Cardinal::Right,
+        ]
+        .into_iter()
+        .find(|dir| {
+            let (dx, dy) = dir.delta();
+            map.blocks_projectile_at(tile_x - dx, tile_y - dy)
+                && map.is_walkable_by(tile_x + dx, tile_y + dy, EntityType::Player)
+        })?;
+
+        let (dx, dy) = facing.delta();
+        let cx = tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+        let cy = tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+        Some(Self {
+            x: cx - dx as f32 * TILE_SIZE * 0.4,
+            y: cy - dy as f32 * TILE_SIZE * 0.4,
+            base_angle: (dy as f32).atan2(dx as f32),
+            sweep_time: rand::gen_range(0.0, std::f32::consts::TAU),
+            cooldown: 0.0,
+            alive: true,
+        })
+    }
+
+    pub fn tile_position(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    fn view_angle(&self) -> f32 {
+        self.base_angle + (self.sweep_time * CAMERA_SWEEP_SPEED).sin() * CAMERA_SWEEP
+    }
+
+    /// Whether a pixel position is inside the vision cone and unobstructed
+    pub fn can_see(&self, x: f32, y: f32, map: &TileMap) -> bool {
+        let (dx, dy) = (x - self.x, y - self.y);
+        if dx.hypot(dy) > CAMERA_RANGE {
+            return false;
+        }
+        let diff = dy.atan2(dx) - self.view_angle();
+        // Wrap to [-PI, PI]
+        let diff =
+            (diff + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+        diff.abs() <= CAMERA_HALF_FOV && map.has_line_of_sight(self.x, self.y, x, y)
+    }
+
+    /// Sweep the camera; returns true when it newly spots the target
+    pub fn update(&mut self, dt: f32, target_x: f32, target_y: f32, map: &TileMap) -> bool {
+        self.sweep_time += dt;
+        if self.cooldown > 0.0 {
+            self.cooldown -= dt;
+            return false;
+        }
+        if self.can_see(target_x, target_y, map) {
+            self.cooldown = CAMERA_COOLDOWN;
+            return true;
+        }
+        false
+    }
+
+    /// Distance the view reaches along an angle before hitting a wall
+    fn ray_length(&self, angle: f32, map: &TileMap) -> f32 {
+        let step = TILE_SIZE / 4.0;
+        let mut dist = step;
+        while dist < CAMERA_RANGE {
+            let x = self.x + angle.cos() * dist;
+            let y = self.y + angle.sin() * dist;
+            if map.blocks_projectile_at((x / TILE_SIZE) as i32, (y / TILE_SIZE) as i32) {
+                break;
+            }
+            dist += step;
+        }
+        dist.min(CAMERA_RANGE)
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, map: &TileMap) {
+        if !self.alive {
+            return;
+        }
+        let origin = Vec2::new(self.x - camera_x, self.y - camera_y);
+        let cone_color = if self.cooldown > 0.0 {
+            Color::from_rgba(255, 60, 60, 70)
+        } else {
+            Color::from_rgba(255, 240, 120, 50)
+        };
+
+        // Vision cone, clipped by walls
+        let start = self.view_angle() - CAMERA_HALF_FOV;
+        let step = CAMERA_HALF_FOV * 2.0 / CONE_SEGMENTS as f32;
+        let point = |angle: f32| {
+            let len = self.ray_length(angle, map);
+            origin + Vec2::new(angle.cos(), angle.sin()) * len
+        };
+        let mut prev = point(start);
+        for i in 1..=CONE_SEGMENTS {
+            let next = point(start + step * i as f32);
+            draw_triangle(origin, prev, next, cone_color);
+            prev = next;
+        }
+
+        // Camera body
+        draw_circle(origin.x, origin.y, 5.0, Color::from_rgba(50, 50, 60, 255));
+        draw_circle(origin.x, origin.y, 2.0, Color::from_rgba(255, 60, 60, 255));
+    }
+}
+
+impl Persist for SecurityCamera {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.base_angle.write(out);
+        self.alive.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            base_angle: fields.read()?,
+            sweep_time: 0.0,
+            cooldown: 0.0,
+            alive: fields.read()?,
+        })
+    }
+}
+
+/// Wall panel that lifts an alarm lockdown when hacked
+pub struct SecurityPanel {
+    pub x: f32, // Pixel coordinates (centered in tile)
+    pub y: f32,
+    pub progress: f32, // 0.0 to 1.0
+}
+
+impl SecurityPanel {
+    pub fn new(tile_x: i32, tile_y: i32) -> Self {
+        Self {
+            x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            progress: 0.0,
+        }
+    }
+
+    pub fn tile_position(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    pub fn is_player_nearby(&self, player_x: i32, player_y: i32) -> bool {
+        let (tx, ty) = self.tile_position();
+        (player_x - tx).abs() + (player_y - ty).abs() <= 1
+    }
+
+    /// Advance the override while hacking (progress resets otherwise);
+    /// returns true once complete
+    pub fn update(&mut self, dt: f32, hacking: bool) -> bool {
+        if hacking {
+            self.progress += dt / PANEL_HACK_TIME;
+        } else {
+            self.progress = 0.0;
+        }
+        if self.progress >= 1.0 {
+            self.progress = 0.0;
+            return true;
+        }
+        false
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, lockdown: bool, player_nearby: bool) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let led = if lockdown {
+            Color::from_rgba(255, 50, 50, 255)
+        } else {
+            Color::from_rgba(60, 200, 80, 255)
+        };
+
+        draw_rectangle(
+            screen_x - 9.0,
+            screen_y - 11.0,
+            18.0,
+            22.0,
+            Color::from_rgba(70, 70, 85, 255),
+        );
+        draw_rectangle_lines(screen_x - 9.0, screen_y - 11.0, 18.0, 22.0, 1.0, BLACK);
+        draw_circle(screen_x, screen_y - 5.0, 3.0, led);
+        draw_rectangle(
+            screen_x - 6.0,
+            screen_y + 1.0,
+            12.0,
+            6.0,
+            Color::from_rgba(30, 30, 40, 255),
+        );
+
+        if lockdown && player_nearby {
+            draw_text(
+                "[E] Override",
+                screen_x - 30.0,
+                screen_y - 18.0,
+                16.0,
+                Color::from_rgba(255, 255, 100, 255),
+            );
+            if self.progress > 0.0 {
+                draw_rectangle(
+                    screen_x - 16.0,
+                    screen_y + 14.0,
+                    32.0 * self.progress,
+                    4.0,
+                    Color::from_rgba(255, 255, 100, 255),
+                );
+            }
+        }
+    }
+}
+
+impl Persist for SecurityPanel {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.progress.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            progress: fields.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile_map::TileType;
+
+    fn corridor() -> TileMap {
+        // Open floor with a wall column at x=0
+        let mut map = TileMap::new(12, 12);
+        for y in 0..12 {
+            map.set_tile(0, y, TileType::Wall);
+        }
+        map
+    }
+
+    #[test]
+    fn test_camera_mounts_against_wall() {
+        let map = corridor();
+        let camera = SecurityCamera::try_mount(&map, 1, 5).unwrap();
+        assert_eq!(camera.tile_position(), (1, 5));
+        // Looking right, away from the wall
+        assert!(camera.base_angle.abs() < 1e-5);
+
+        // No wall to mount on in the open
+        assert!(SecurityCamera::try_mount(&map, 6, 6).is_none());
+    }
+
+    #[test]
+    fn test_camera_vision_cone() {
+        let map = corridor();
+        let mut camera = SecurityCamera::try_mount(&map, 1, 5).unwrap();
+        camera.sweep_time = 0.0; // Looking straight ahead
+
+        let tile = |x: i32, y: i32| {
+            (
+                x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            )
+        };
+        let (ax, ay) = tile(4, 5);
+        assert!(camera.can_see(ax, ay, &map));
+        // Out of range
+        let (bx, by) = tile(10, 5);
+        assert!(!camera.can_see(bx, by, &map));
+        // Outside the cone
+        let (cx, cy) = tile(2, 9);
+        assert!(!camera.can_see(cx, cy, &map));
+    }
+
+    #[test]
+    fn test_panel_override() {
+        let mut panel = SecurityPanel::new(3, 3);
+        assert!(panel.is_player_nearby(3, 4));
+        assert!(!panel.is_player_nearby(5, 5));
+
+        assert!(!panel.update(PANEL_HACK_TIME / 2.0, true));
+        // Letting go resets the override
+        assert!(!panel.update(0.1, false));
+        assert!(!panel.update(PANEL_HACK_TIME / 2.0, true));
+        assert!(panel.update(PANEL_HACK_TIME / 2.0, true));
+    }
+
+    #[test]
+    fn test_camera_alarm_cooldown() {
+        let map = corridor();
+        let mut camera = SecurityCamera::try_mount(&map, 1, 5).unwrap();
+        camera.sweep_time = 0.0;
+        let (x, y) = (3.5 * TILE_SIZE, 5.5 * TILE_SIZE);
+
+        assert!(camera.update(0.0, x, y, &map));
+        assert!(!camera.update(0.1, x, y, &map));
+    }
+}
@@ -0,0 +1,408 @@
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use crate::audio::Channel;
+use crate::difficulty::Difficulty;
+use crate::input::{Action, KeyBindings, key_from_name, key_label, key_name};
+use crate::skin::Skin;
+use crate::sprites::SpriteSheet;
+use crate::ui::{SCALE_RANGE, ui_height, ui_width};
+
+const SETTINGS_FILE: &str = "settings.txt";
+const CONFIG_DIR: &str = "rstgme"; // Folder inside the user's config directory
+pub const VOLUME_STEP: u32 = 10; // Percent per press on the settings screen
+pub const LEVEL_STEP: u32 = 25; // Screen shake and look-ahead percent per press
+
+/// Player preferences saved between sessions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub skin: Skin,
+    pub volumes: [u32; 3],      // Percent, by audio Channel
+    pub screen_shake: u32,      // Percent; 0 turns it off
+    pub ui_scale: Option<u32>,  // Percent; None fits the window
+    pub look_ahead: u32,        // Percent of the camera's lean toward the cursor
+    pub difficulty: Difficulty, // Preselected on the New Game screen
+    pub fullscreen: bool,
+    pub speedrun_timer: bool,         // Run clock with splits on the HUD
+    pub mute_unfocused: bool,         // Silence every sound while the window is away
+    pub pause_unfocused: bool,        // Pause a run while the window is away
+    pub texture_pack: Option<String>, // Folder in data/packs; read at startup
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            skin: Skin::Standard,
+            volumes: [100; 3],
+            screen_shake: 100,
+            ui_scale: None,
+            look_ahead: 50,
+            difficulty: Difficulty::NORMAL,
+            fullscreen: false,
+            speedrun_timer: false,
+            mute_unfocused: true,
+            pause_unfocused: false,
+            texture_pack: None,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+/// The user's config directory for this platform, if it can be found
+fn config_dir() -> Option<PathBuf> {
+    let var = |name| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    }
+}
+
+/// Where the settings are kept: the config directory, or the working
+/// directory when there is none
+fn settings_path() -> PathBuf {
+    config_dir()
+        .map(|dir| dir.join(CONFIG_DIR).join(SETTINGS_FILE))
+        .unwrap_or_else(|| PathBuf::from(SETTINGS_FILE))
+}
+
+fn parse_switch(word: &str) -> Option<bool> {
+    match word {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn switch(on: bool) -> &'static str {
+    if on { "on" } else { "off" }
+}
+
+impl Settings {
+    /// Read the settings file; a missing or unreadable file uses the defaults.
+    /// Settings from before the config directory are picked up from the
+    /// working directory.
+    pub fn load() -> Self {
+        std::fs::read_to_string(settings_path())
+            .or_else(|_| std::fs::read_to_string(SETTINGS_FILE))
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let path = settings_path();
+        if let Some(dir) = path.parent()
+            && let Err(err) = std::fs::create_dir_all(dir)
+        {
+            eprintln!("Could not create {}: {err}", dir.display());
+        }
+        if let Err(err) = std::fs::write(&path, self.serialize()) {
+            eprintln!("Could not save {}: {err}", path.display());
+        }
+    }
+
+    /// Parse `<key> <value>` lines; unknown keys and bad values are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["skin", key] => {
+                    if let Some(skin) = Skin::from_key(key) {
+                        settings.skin = skin;
+                    }
+                }
+                [key, percent] if Channel::from_key(key).is_some() => {
+                    if let (Some(channel), Ok(percent)) =
+                        (Channel::from_key(key), percent.parse::<u32>())
+                    {
+                        settings.volumes[channel as usize] = percent.min(100);
+                    }
+                }
+                // Older files saved an on/off switch
+                ["screen_shake", level] => {
+                    if let Some(on) = parse_switch(level) {
+                        settings.screen_shake = if on { 100 } else { 0 };
+                    } else if let Ok(percent) = level.parse::<u32>() {
+                        settings.screen_shake = percent.min(100);
+                    }
+                }
+                ["ui_scale", "auto"] => settings.ui_scale = None,
+                ["ui_scale", percent] => {
+                    if let Ok(percent) = percent.parse::<u32>() {
+                        settings.ui_scale = Some(percent.clamp(SCALE_RANGE.0, SCALE_RANGE.1));
+                    }
+                }
+                ["look_ahead", percent] => {
+                    if let Ok(percent) = percent.parse::<u32>() {
+                        settings.look_ahead = percent.min(100);
+                    }
+                }
+                ["difficulty", name] => {
+                    if let Some(difficulty) = Difficulty::ALL
+                        .into_iter()
+                        .find(|d| d.name.eq_ignore_ascii_case(name))
+                    {
+                        settings.difficulty = difficulty;
+                    }
+                }
+                ["fullscreen", on] => {
+                    if let Some(on) = parse_switch(on) {
+                        settings.fullscreen = on;
+                    }
+                }
+                ["speedrun_timer", on] => {
+                    if let Some(on) = parse_switch(on) {
+                        settings.speedrun_timer = on;
+                    }
+                }
+                ["mute_unfocused", on] => {
+                    if let Some(on) = parse_switch(on) {
+                        settings.mute_unfocused = on;
+                    }
+                }
+                ["pause_unfocused", on] => {
+                    if let Some(on) = parse_switch(on) {
+                        settings.pause_unfocused = on;
+                    }
+                }
+                ["texture_pack", name] => settings.texture_pack = Some(name.to_string()),
+                ["key", action, key] => {
+                    if let Some(action) = Action::from_key(action)
+                        && let Some(key) = key_from_name(key)
+                    {
+                        settings.keys.set(action, key);
+                    }
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = format!(
+            "skin {}\nscreen_shake {}\nlook_ahead {}\nui_scale {}\ndifficulty {}\nfullscreen {}\nspeedrun_timer {}\n\
+             mute_unfocused {}\npause_unfocused {}\n",
+            self.skin.key(),
+            self.screen_shake,
+            self.look_ahead,
+            self.ui_scale
+                .map_or("auto".to_string(), |percent| percent.to_string()),
+            self.difficulty.name.to_lowercase(),
+            switch(self.fullscreen),
+            switch(self.speedrun_timer),
+            switch(self.mute_unfocused),
+            switch(self.pause_unfocused),
+        );
+        if let Some(pack) = &self.texture_pack {
+            text.push_str(&format!("texture_pack {pack}\n"));
+        }
+        for channel in Channel::ALL {
+            text.push_str(&format!(
+                "{} {}\n",
+                channel.key(),
+                self.volumes[channel as usize]
+            ));
+        }
+        for action in Action::ALL {
+            text.push_str(&format!(
+                "key {} {}\n",
+                action.key(),
+                key_name(self.keys.get(action))
+            ));
+        }
+        text
+    }
+}
+
+/// One line of the settings screen
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsRow {
+    Volume(Channel),
+    ScreenShake,
+    LookAhead,
+    UiScale,
+    Difficulty,
+    Fullscreen,
+    SpeedrunTimer,
+    MuteUnfocused,
+    PauseUnfocused,
+    Key(Action),
+}
+
+impl SettingsRow {
+    /// The volumes and options, then a binding per action
+    pub fn all() -> Vec<SettingsRow> {
+        let mut rows: Vec<SettingsRow> = Channel::ALL.map(SettingsRow::Volume).into();
+        rows.extend([
+            SettingsRow::ScreenShake,
+            SettingsRow::LookAhead,
+            SettingsRow::UiScale,
+            SettingsRow::Difficulty,
+            SettingsRow::Fullscreen,
+            SettingsRow::SpeedrunTimer,
+            SettingsRow::MuteUnfocused,
+            SettingsRow::PauseUnfocused,
+        ]);
+        rows.extend(Action::ALL.map(SettingsRow::Key));
+        rows
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SettingsRow::Volume(channel) => channel.name(),
+            SettingsRow::ScreenShake => "Screen Shake",
+            SettingsRow::LookAhead => "Aim Look-Ahead",
+            SettingsRow::UiScale => "Interface Scale",
+            SettingsRow::Difficulty => "Default Difficulty",
+            SettingsRow::Fullscreen => "Fullscreen",
+            SettingsRow::SpeedrunTimer => "Speedrun Timer",
+            SettingsRow::MuteUnfocused => "Mute When Away",
+            SettingsRow::PauseUnfocused => "Pause When Away",
+            SettingsRow::Key(action) => action.name(),
+        }
+    }
+
+    fn value(self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" }.to_string();
+        match self {
+            SettingsRow::Volume(channel) => format!("{}%", settings.volumes[channel as usize]),
+            SettingsRow::ScreenShake => match settings.screen_shake {
+                0 => "OFF".to_string(),
+                percent => format!("{percent}%"),
+            },
+            SettingsRow::LookAhead => format!("{}%", settings.look_ahead),
+            SettingsRow::UiScale => match settings.ui_scale {
+                Some(percent) => format!("{percent}%"),
+                None => "AUTO".to_string(),
+            },
+            SettingsRow::Difficulty => settings.difficulty.name.to_string(),
+            SettingsRow::Fullscreen => on_off(settings.fullscreen),
+            SettingsRow::SpeedrunTimer => on_off(settings.speedrun_timer),
+            SettingsRow::MuteUnfocused => on_off(settings.mute_unfocused),
+            SettingsRow::PauseUnfocused => on_off(settings.pause_unfocused),
+            SettingsRow::Key(action) => key_label(settings.keys.get(action)),
+        }
+    }
+}
+
+/// Settings screen: options and key bindings in one list; `rebinding`
+/// marks the selected binding as waiting for a key
+pub fn draw_settings(settings: &Settings, selected: usize, rebinding: bool, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let center_x = ui_width() / 2.0;
+    let title = "SETTINGS";
+    let title_size = 40.0;
+    let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+    draw_text(
+        title,
+        center_x - title_width / 2.0,
+        60.0,
+        title_size,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+
+    let line_size = 18.0;
+    let line_spacing = 18.0;
+    let start_y = 100.0;
+    for (i, row) in SettingsRow::all().into_iter().enumerate() {
+        // A gap sets the bindings apart from the options
+        let gap = if matches!(row, SettingsRow::Key(_)) {
+            12.0
+        } else {
+            0.0
+        };
+        let y = start_y + i as f32 * line_spacing + gap;
+        let is_selected = i == selected;
+        if is_selected {
+            draw_rectangle(
+                center_x - 230.0,
+                y - line_size + 4.0,
+                460.0,
+                line_size + 2.0,
+                Color::from_rgba(200, 50, 50, 100),
+            );
+        }
+        draw_text(
+            row.label(),
+            center_x - 220.0,
+            y,
+            line_size,
+            Color::from_rgba(150, 150, 150, 255),
+        );
+        let value = if is_selected && rebinding {
+            "press a key...".to_string()
+        } else {
+            row.value(settings)
+        };
+        let color = if is_selected {
+            Color::from_rgba(255, 255, 100, 255)
+        } else {
+            WHITE
+        };
+        draw_text(&value, center_x + 60.0, y, line_size, color);
+    }
+
+    let hint = if rebinding {
+        "Press the new key  |  ESC: Cancel"
+    } else {
+        "W/S: Navigate  |  A/D: Change  |  Enter: Rebind  |  ESC: Back"
+    };
+    let hint_size = 16.0;
+    let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+    draw_text(
+        hint,
+        center_x - hint_width / 2.0,
+        ui_height() - 20.0,
+        hint_size,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut settings = Settings {
+            skin: Skin::Gold,
+            volumes: [40, 80, 0],
+            screen_shake: 25,
+            look_ahead: 100,
+            ui_scale: Some(150),
+            difficulty: Difficulty::HARD,
+            fullscreen: true,
+            speedrun_timer: true,
+            mute_unfocused: false,
+            pause_unfocused: true,
+            texture_pack: Some("neon".to_string()),
+            keys: KeyBindings::default(),
+        };
+        settings.keys.set(Action::Interact, KeyCode::R);
+        assert_eq!(Settings::parse(&settings.serialize()), settings);
+        assert_eq!(Settings::parse("skin plaid\n"), Settings::default());
+        assert_eq!(
+            Settings::parse("volume loud\nfullscreen maybe\nkey interact W\nkey jump E\n"),
+            Settings::default()
+        );
+        let settings = Settings::parse("volume 250\nmusic_volume 30\n");
+        assert_eq!(settings.volumes, [100, 100, 30]);
+        assert_eq!(Settings::parse("screen_shake off\n").screen_shake, 0);
+        assert_eq!(Settings::parse("screen_shake 300\n").screen_shake, 100);
+        assert_eq!(
+            Settings::parse("ui_scale 10\n").ui_scale,
+            Some(SCALE_RANGE.0)
+        );
+    }
+}
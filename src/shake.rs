@@ -0,0 +1,71 @@
+const MAX_SHAKE: f32 = 10.0; // Pixels the camera jolts at full trauma
+const TRAUMA_DECAY: f32 = 1.5; // Trauma lost per second
+
+// Trauma each kind of jolt adds, out of 1
+pub const TRAUMA_SHOTGUN: f32 = 0.25;
+pub const TRAUMA_HIT: f32 = 0.35;
+pub const TRAUMA_BOSS_ATTACK: f32 = 0.3;
+pub const TRAUMA_EXPLOSION: f32 = 0.6;
+
+/// Camera shake from a pool of trauma. Jolts add trauma, which drains
+/// steadily; the shake grows with its square, so small jolts stay subtle
+/// and big ones stack into a hard rattle that settles quickly.
+#[derive(Default)]
+pub struct Shake {
+    trauma: f32,
+    // Drives the wobble: sines rather than random numbers, so the shake
+    // never touches the game's generator
+    time: f32,
+}
+
+impl Shake {
+    pub fn add(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        self.time += dt;
+    }
+
+    pub fn clear(&mut self) {
+        self.trauma = 0.0;
+    }
+
+    /// Camera offset in pixels, scaled by the intensity setting (0 to 1)
+    pub fn offset(&self, intensity: f32) -> (f32, f32) {
+        let power = MAX_SHAKE * self.trauma * self.trauma * intensity;
+        let t = self.time;
+        (
+            power * (0.6 * (t * 37.0).sin() + 0.4 * (t * 71.0 + 1.3).sin()),
+            power * (0.6 * (t * 43.0 + 2.1).sin() + 0.4 * (t * 67.0 + 0.7).sin()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shake() {
+        let mut shake = Shake::default();
+        shake.update(0.1);
+        assert_eq!(shake.offset(1.0), (0.0, 0.0));
+
+        // Trauma stacks up to a cap
+        shake.add(TRAUMA_EXPLOSION);
+        shake.add(TRAUMA_EXPLOSION);
+        assert_eq!(shake.trauma, 1.0);
+        let (x, y) = shake.offset(1.0);
+        assert!(x.abs() <= MAX_SHAKE && y.abs() <= MAX_SHAKE);
+        assert!(x != 0.0 || y != 0.0);
+
+        // Switched off in the settings
+        assert_eq!(shake.offset(0.0), (0.0, 0.0));
+
+        // Drains away
+        shake.update(1.0 / TRAUMA_DECAY);
+        assert_eq!(shake.offset(1.0), (0.0, 0.0));
+    }
+}
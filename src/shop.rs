@@ -0,0 +1,251 @@
+use macroquad::prelude::*;
+
+use crate::grenade::GrenadeKind;
+use crate::item::{ItemType, WeaponKind};
+use crate::save::{Fields, Persist};
+use crate::tile_map::TILE_SIZE;
+use crate::ui::{ui_height, ui_width};
+use crate::weapon::AmmoKind;
+
+/// Something the shop sells: a regular item, or a permanent upgrade
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Goods {
+    Item(ItemType),
+    MaxHealth, // +MAX_HEALTH_UPGRADE max health
+}
+
+pub const MAX_HEALTH_UPGRADE: i32 = 20;
+
+pub struct Offer {
+    pub name: &'static str,
+    pub price: u32,
+    pub goods: Goods,
+}
+
+/// Shop inventory and prices, in menu order
+pub const OFFERS: [Offer; 12] = [
+    Offer {
+        name: "Light Ammo x30",
+        price: 15,
+        goods: Goods::Item(ItemType::Ammo(AmmoKind::Light)),
+    },
+    Offer {
+        name: "Shells x8",
+        price: 20,
+        goods: Goods::Item(ItemType::Ammo(AmmoKind::Shell)),
+    },
+    Offer {
+        name: "Heavy Ammo x10",
+        price: 25,
+        goods: Goods::Item(ItemType::Ammo(AmmoKind::Heavy)),
+    },
+    Offer {
+        name: "Frag Grenade",
+        price: 30,
+        goods: Goods::Item(ItemType::Grenade(GrenadeKind::Frag)),
+    },
+    Offer {
+        name: "Decoy Hologram",
+        price: 35,
+        goods: Goods::Item(ItemType::Decoy),
+    },
+    Offer {
+        name: "Armor Plate",
+        price: 40,
+        goods: Goods::Item(ItemType::ArmorPlate),
+    },
+    Offer {
+        name: "Shotgun",
+        price: 60,
+        goods: Goods::Item(ItemType::Weapon(WeaponKind::Shotgun)),
+    },
+    Offer {
+        name: "Machine Pistol",
+        price: 80,
+        goods: Goods::Item(ItemType::Weapon(WeaponKind::MachinePistol)),
+    },
+    Offer {
+        name: "Backpack (+1 weapon slot)",
+        price: 90,
+        goods: Goods::Item(ItemType::Backpack),
+    },
+    Offer {
+        name: "Rifle",
+        price: 100,
+        goods: Goods::Item(ItemType::Weapon(WeaponKind::Rifle)),
+    },
+    Offer {
+        name: "Max Health +20",
+        price: 120,
+        goods: Goods::MaxHealth,
+    },
+    Offer {
+        name: "Overclock Module",
+        price: 150,
+        goods: Goods::Item(ItemType::Overclock),
+    },
+];
+
+/// Credits dropped by a destroyed bot
+pub fn bot_credit_drop(hostile: bool) -> u32 {
+    if hostile {
+        rand::gen_range(5, 16)
+    } else {
+        rand::gen_range(1, 6)
+    }
+}
+
+/// Vending terminal where credits buy gear
+pub struct ShopTerminal {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl ShopTerminal {
+    pub fn new(tile_x: i32, tile_y: i32) -> Self {
+        Self {
+            x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        }
+    }
+
+    pub fn tile_position(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    pub fn is_player_nearby(&self, player_x: i32, player_y: i32) -> bool {
+        let (tx, ty) = self.tile_position();
+        (player_x - tx).abs() + (player_y - ty).abs() <= 1
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, player_nearby: bool) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+
+        draw_rectangle(
+            screen_x - 11.0,
+            screen_y - 13.0,
+            22.0,
+            26.0,
+            Color::from_rgba(60, 50, 30, 255),
+        );
+        draw_rectangle_lines(
+            screen_x - 11.0,
+            screen_y - 13.0,
+            22.0,
+            26.0,
+            1.5,
+            SHOP_COLOR,
+        );
+        draw_text("$", screen_x - 5.0, screen_y + 6.0, 22.0, SHOP_COLOR);
+
+        if player_nearby {
+            draw_text(
+                "[E] Shop",
+                screen_x - 24.0,
+                screen_y - 20.0,
+                16.0,
+                Color::from_rgba(255, 255, 100, 255),
+            );
+        }
+    }
+}
+
+impl Persist for ShopTerminal {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+        })
+    }
+}
+
+pub const SHOP_COLOR: Color = Color::new(1.0, 0.8, 0.25, 1.0);
+
+/// Purchase menu overlay
+pub fn draw_shop_menu(selected: usize, credits: u32) {
+    let row_height = 26.0;
+    let width = 340.0;
+    let height = 100.0 + OFFERS.len() as f32 * row_height;
+    let x = (ui_width() - width) / 2.0;
+    let y = (ui_height() - height) / 2.0;
+
+    draw_rectangle(
+        0.0,
+        0.0,
+        ui_width(),
+        ui_height(),
+        Color::from_rgba(0, 0, 0, 120),
+    );
+    draw_rectangle(x, y, width, height, Color::from_rgba(25, 20, 10, 235));
+    draw_rectangle_lines(x, y, width, height, 2.0, SHOP_COLOR);
+    draw_text("SUPPLY TERMINAL", x + 16.0, y + 28.0, 24.0, SHOP_COLOR);
+    draw_text(
+        &format!("Credits: {}", credits),
+        x + width - 120.0,
+        y + 28.0,
+        18.0,
+        WHITE,
+    );
+
+    for (i, offer) in OFFERS.iter().enumerate() {
+        let row_y = y + 44.0 + i as f32 * row_height;
+        if i == selected {
+            draw_rectangle(
+                x + 8.0,
+                row_y,
+                width - 16.0,
+                row_height - 2.0,
+                Color::from_rgba(255, 200, 60, 50),
+            );
+        }
+        let color = if offer.price <= credits { WHITE } else { GRAY };
+        draw_text(offer.name, x + 20.0, row_y + 18.0, 18.0, color);
+        draw_text(
+            &format!("{}", offer.price),
+            x + width - 60.0,
+            row_y + 18.0,
+            18.0,
+            if offer.price <= credits {
+                SHOP_COLOR
+            } else {
+                GRAY
+            },
+        );
+    }
+
+    draw_text(
+        "W/S select   E buy   ESC close",
+        x + 16.0,
+        y + height - 14.0,
+        16.0,
+        GRAY,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offers_are_priced_and_unique() {
+        // Every offer costs something, and no two offers share a name
+        for (i, offer) in OFFERS.iter().enumerate() {
+            assert!(offer.price > 0);
+            assert!(OFFERS[i + 1..].iter().all(|o| o.name != offer.name));
+        }
+    }
+
+    #[test]
+    fn test_bot_credit_drop() {
+        for _ in 0..20 {
+            assert!((5..16).contains(&bot_credit_drop(true)));
+            assert!((1..6).contains(&bot_credit_drop(false)));
+        }
+    }
+}
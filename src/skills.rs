@@ -0,0 +1,337 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, persist_enum};
+use crate::ui::{ui_height, ui_width};
+
+const MELEE_ARC_COS: f32 = 0.5; // Bots within ~60 degrees of the swing are hit
+const WIDE_SWING_ARC_COS: f32 = 0.1; // ~85 degrees
+const LONG_REACH_MULT: f32 = 1.4;
+const STEADY_AIM_SPREAD_MULT: f32 = 0.6;
+pub const FAST_FINGERS_CUT: f32 = 0.15; // Fraction off hack duration
+pub const LONG_WINDOW_BONUS: f32 = 0.25; // Fraction added to the hack window
+pub const COOL_HEAD_CUT: f32 = 0.4; // Fraction off minigame mistake penalties
+const SURE_FOOTING_KEEP: f32 = 0.5; // Share of terrain slowdown still felt
+const LIGHT_STEP_MULT: f32 = 1.08;
+const SOFT_LANDING_MULT: f32 = 0.5; // Pit fall damage taken
+const QUICK_RECOVERY_MULT: f32 = 0.6; // Dash cooldown
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SkillBranch {
+    Combat,
+    Hacking,
+    Mobility,
+}
+
+impl SkillBranch {
+    pub const ALL: [SkillBranch; 3] = [
+        SkillBranch::Combat,
+        SkillBranch::Hacking,
+        SkillBranch::Mobility,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkillBranch::Combat => "COMBAT",
+            SkillBranch::Hacking => "HACKING",
+            SkillBranch::Mobility => "MOBILITY",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            SkillBranch::Combat => Color::from_rgba(255, 110, 90, 255),
+            SkillBranch::Hacking => Color::from_rgba(100, 200, 255, 255),
+            SkillBranch::Mobility => Color::from_rgba(130, 230, 110, 255),
+        }
+    }
+
+    /// Nodes of the branch, in the order they must be unlocked
+    pub fn skills(self) -> &'static [Skill] {
+        match self {
+            SkillBranch::Combat => &[Skill::WideSwing, Skill::LongReach, Skill::SteadyAim],
+            SkillBranch::Hacking => &[Skill::FastFingers, Skill::LongWindow, Skill::CoolHead],
+            SkillBranch::Mobility => &[
+                Skill::SureFooting,
+                Skill::LightStep,
+                Skill::SoftLanding,
+                Skill::QuickRecovery,
+            ],
+        }
+    }
+}
+
+/// Skill tree node; each costs one skill point
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Skill {
+    WideSwing,
+    LongReach,
+    SteadyAim,
+    FastFingers,
+    LongWindow,
+    CoolHead,
+    SureFooting,
+    LightStep,
+    SoftLanding,
+    QuickRecovery,
+}
+
+persist_enum!(Skill {
+    WideSwing,
+    LongReach,
+    SteadyAim,
+    FastFingers,
+    LongWindow,
+    CoolHead,
+    SureFooting,
+    LightStep,
+    SoftLanding,
+    QuickRecovery
+});
+
+impl Skill {
+    pub fn name(self) -> &'static str {
+        match self {
+            Skill::WideSwing => "Wide Swing",
+            Skill::LongReach => "Long Reach",
+            Skill::SteadyAim => "Steady Aim",
+            Skill::FastFingers => "Fast Fingers",
+            Skill::LongWindow => "Long Window",
+            Skill::CoolHead => "Cool Head",
+            Skill::SureFooting => "Sure Footing",
+            Skill::LightStep => "Light Step",
+            Skill::SoftLanding => "Soft Landing",
+            Skill::QuickRecovery => "Quick Recovery",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Skill::WideSwing => "Wider melee arc",
+            Skill::LongReach => "+40% melee range",
+            Skill::SteadyAim => "-40% bullet spread",
+            Skill::FastFingers => "-15% hack time",
+            Skill::LongWindow => "+25% hack window",
+            Skill::CoolHead => "-40% minigame penalty",
+            Skill::SureFooting => "Half terrain slowdown",
+            Skill::LightStep => "+8% move speed",
+            Skill::SoftLanding => "Half pit fall damage",
+            Skill::QuickRecovery => "-40% dash cooldown",
+        }
+    }
+}
+
+/// Skill points and unlocked nodes; kept for the whole run, deaths included
+pub struct SkillTree {
+    pub points: u32,
+    unlocked: Vec<Skill>,
+}
+
+impl SkillTree {
+    pub fn new() -> Self {
+        Self {
+            points: 0,
+            unlocked: Vec::new(),
+        }
+    }
+
+    pub fn has(&self, skill: Skill) -> bool {
+        self.unlocked.contains(&skill)
+    }
+
+    /// First locked node of a branch, if any are left
+    pub fn next_in(&self, branch: SkillBranch) -> Option<Skill> {
+        branch.skills().iter().copied().find(|&s| !self.has(s))
+    }
+
+    /// Spend a point on the next node of a branch. Returns the skill, or
+    /// None without points or with the branch complete.
+    pub fn unlock_next(&mut self, branch: SkillBranch) -> Option<Skill> {
+        if self.points == 0 {
+            return None;
+        }
+        let skill = self.next_in(branch)?;
+        self.points -= 1;
+        self.unlocked.push(skill);
+        Some(skill)
+    }
+
+    /// Cosine of the half-angle of the melee arc
+    pub fn melee_arc_cos(&self) -> f32 {
+        if self.has(Skill::WideSwing) {
+            WIDE_SWING_ARC_COS
+        } else {
+            MELEE_ARC_COS
+        }
+    }
+
+    pub fn melee_range_mult(&self) -> f32 {
+        if self.has(Skill::LongReach) {
+            LONG_REACH_MULT
+        } else {
+            1.0
+        }
+    }
+
+    pub fn spread_mult(&self) -> f32 {
+        if self.has(Skill::SteadyAim) {
+            STEADY_AIM_SPREAD_MULT
+        } else {
+            1.0
+        }
+    }
+
+    /// Tile speed multiplier after Sure Footing takes the edge off
+    pub fn terrain_speed(&self, tile_speed: f32) -> f32 {
+        if self.has(Skill::SureFooting) && tile_speed < 1.0 {
+            1.0 - (1.0 - tile_speed) * SURE_FOOTING_KEEP
+        } else {
+            tile_speed
+        }
+    }
+
+    pub fn move_speed_mult(&self) -> f32 {
+        if self.has(Skill::LightStep) {
+            LIGHT_STEP_MULT
+        } else {
+            1.0
+        }
+    }
+
+    pub fn dash_cooldown_mult(&self) -> f32 {
+        if self.has(Skill::QuickRecovery) {
+            QUICK_RECOVERY_MULT
+        } else {
+            1.0
+        }
+    }
+
+    pub fn fall_damage(&self, damage: i32) -> i32 {
+        if self.has(Skill::SoftLanding) {
+            (damage as f32 * SOFT_LANDING_MULT).round() as i32
+        } else {
+            damage
+        }
+    }
+
+    /// Skill tree overlay; `selected` is the highlighted branch
+    pub fn draw(&self, selected: usize) {
+        let column_width = 220.0;
+        let node_height = 64.0;
+        let width = column_width * 3.0 + 40.0;
+        let rows = SkillBranch::ALL
+            .iter()
+            .map(|b| b.skills().len())
+            .max()
+            .unwrap_or(0) as f32;
+        let height = 130.0 + node_height * rows;
+        let x = (ui_width() - width) / 2.0;
+        let y = (ui_height() - height) / 2.0;
+
+        draw_rectangle(
+            0.0,
+            0.0,
+            ui_width(),
+            ui_height(),
+            Color::from_rgba(0, 0, 0, 140),
+        );
+        draw_rectangle(x, y, width, height, Color::from_rgba(15, 15, 25, 240));
+        draw_rectangle_lines(x, y, width, height, 2.0, LIGHTGRAY);
+        draw_text("SKILL TREE", x + 16.0, y + 30.0, 26.0, WHITE);
+        draw_text(
+            &format!("Points: {}", self.points),
+            x + width - 120.0,
+            y + 30.0,
+            20.0,
+            if self.points > 0 { YELLOW } else { GRAY },
+        );
+
+        for (col, branch) in SkillBranch::ALL.iter().enumerate() {
+            let cx = x + 20.0 + col as f32 * column_width;
+            let color = branch.color();
+            if col == selected {
+                draw_rectangle(
+                    cx - 6.0,
+                    y + 42.0,
+                    column_width - 8.0,
+                    node_height * rows + 40.0,
+                    Color { a: 0.12, ..color },
+                );
+            }
+            draw_text(branch.name(), cx, y + 62.0, 22.0, color);
+
+            let next = self.next_in(*branch);
+            for (row, skill) in branch.skills().iter().enumerate() {
+                let ny = y + 74.0 + row as f32 * node_height;
+                let unlocked = self.has(*skill);
+                let (border, text) = if unlocked {
+                    (color, WHITE)
+                } else if next == Some(*skill) && self.points > 0 {
+                    (YELLOW, LIGHTGRAY)
+                } else {
+                    (DARKGRAY, GRAY)
+                };
+                if unlocked {
+                    draw_rectangle(
+                        cx,
+                        ny,
+                        column_width - 20.0,
+                        node_height - 10.0,
+                        Color { a: 0.3, ..color },
+                    );
+                }
+                draw_rectangle_lines(cx, ny, column_width - 20.0, node_height - 10.0, 2.0, border);
+                draw_text(skill.name(), cx + 8.0, ny + 22.0, 20.0, text);
+                draw_text(skill.description(), cx + 8.0, ny + 42.0, 16.0, text);
+            }
+        }
+
+        draw_text(
+            "A/D select branch   E / Enter unlock next   K / ESC close",
+            x + 16.0,
+            y + height - 14.0,
+            16.0,
+            GRAY,
+        );
+    }
+}
+
+impl Persist for SkillTree {
+    fn write(&self, out: &mut String) {
+        self.points.write(out);
+        self.unlocked.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            points: fields.read()?,
+            unlocked: fields.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_tree_unlocks_in_order() {
+        let mut tree = SkillTree::new();
+        assert_eq!(tree.unlock_next(SkillBranch::Combat), None);
+
+        tree.points = 1 + SkillBranch::Mobility.skills().len() as u32;
+        assert_eq!(
+            tree.unlock_next(SkillBranch::Combat),
+            Some(Skill::WideSwing)
+        );
+        assert_eq!(tree.next_in(SkillBranch::Combat), Some(Skill::LongReach));
+        assert!(tree.melee_arc_cos() < MELEE_ARC_COS);
+
+        for skill in SkillBranch::Mobility.skills() {
+            assert_eq!(tree.unlock_next(SkillBranch::Mobility), Some(*skill));
+        }
+        assert_eq!(tree.points, 0);
+        assert_eq!(tree.next_in(SkillBranch::Mobility), None);
+        assert_eq!(tree.terrain_speed(0.5), 0.75);
+        assert_eq!(tree.fall_damage(20), 10);
+    }
+}
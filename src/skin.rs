@@ -0,0 +1,199 @@
+use macroquad::prelude::*;
+
+use crate::meta::Profile;
+use crate::sprites::{SpriteSheet, direction};
+use crate::ui::{ui_height, ui_width};
+
+/// Cosmetic look for the player: a sprite sheet row and a tint
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Skin {
+    Standard,
+    Crimson,
+    Cobalt,
+    Gold,    // Collectible: every data chip in a run
+    Phantom, // Achievement: a ghost hack
+    Android, // Achievement: win a run (wears the bot sprites)
+}
+
+impl Skin {
+    pub const ALL: [Skin; 6] = [
+        Skin::Standard,
+        Skin::Crimson,
+        Skin::Cobalt,
+        Skin::Gold,
+        Skin::Phantom,
+        Skin::Android,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Skin::Standard => "STANDARD",
+            Skin::Crimson => "CRIMSON",
+            Skin::Cobalt => "COBALT",
+            Skin::Gold => "GOLD",
+            Skin::Phantom => "PHANTOM",
+            Skin::Android => "ANDROID",
+        }
+    }
+
+    pub fn tint(self) -> Color {
+        match self {
+            Skin::Standard | Skin::Android => WHITE,
+            Skin::Crimson => Color::from_rgba(255, 140, 140, 255),
+            Skin::Cobalt => Color::from_rgba(140, 170, 255, 255),
+            Skin::Gold => Color::from_rgba(255, 215, 90, 255),
+            Skin::Phantom => Color::from_rgba(170, 170, 220, 200),
+        }
+    }
+
+    /// How to earn a locked skin; None for skins everyone has
+    pub fn unlock_hint(self) -> Option<&'static str> {
+        match self {
+            Skin::Standard | Skin::Crimson | Skin::Cobalt => None,
+            Skin::Gold => Some("Find every data chip in a run"),
+            Skin::Phantom => Some("Finish a ghost hack"),
+            Skin::Android => Some("Win a run"),
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Skin::Standard => "standard",
+            Skin::Crimson => "crimson",
+            Skin::Cobalt => "cobalt",
+            Skin::Gold => "gold",
+            Skin::Phantom => "phantom",
+            Skin::Android => "android",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Skin> {
+        Skin::ALL.into_iter().find(|s| s.key() == key)
+    }
+
+    /// Draw the player in this skin at a walk frame; `shade` multiplies the
+    /// tint (crouching)
+    pub fn draw(
+        self,
+        sprites: &SpriteSheet,
+        x: f32,
+        y: f32,
+        facing: u32,
+        frame: u32,
+        shade: Color,
+    ) {
+        let tint = self.tint();
+        let color = Color::new(
+            tint.r * shade.r,
+            tint.g * shade.g,
+            tint.b * shade.b,
+            tint.a * shade.a,
+        );
+        if self == Skin::Android {
+            sprites.draw_bot_frame(x, y, facing, frame, color, None);
+        } else {
+            sprites.draw_player_frame(x, y, facing, frame, color);
+        }
+    }
+}
+
+/// Skins screen: a preview of every skin, with the equipped one marked
+pub fn draw_skin_select(profile: &Profile, selected: usize, equipped: Skin, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let title = "SKINS";
+    let title_size = 40.0;
+    let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+    draw_text(
+        title,
+        (ui_width() - title_width) / 2.0,
+        100.0,
+        title_size,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+
+    let cell = 110.0;
+    let total = cell * Skin::ALL.len() as f32;
+    let start_x = (ui_width() - total) / 2.0;
+    let y = ui_height() / 2.0 - 70.0;
+    for (i, skin) in Skin::ALL.iter().enumerate() {
+        let x = start_x + i as f32 * cell;
+        let owned = profile.has_skin(*skin);
+        let is_selected = i == selected;
+        draw_rectangle(
+            x + 5.0,
+            y,
+            cell - 10.0,
+            120.0,
+            Color::from_rgba(15, 15, 25, 230),
+        );
+        draw_rectangle_lines(
+            x + 5.0,
+            y,
+            cell - 10.0,
+            120.0,
+            if is_selected { 3.0 } else { 1.5 },
+            if is_selected { WHITE } else { DARKGRAY },
+        );
+
+        let shade = if owned {
+            WHITE
+        } else {
+            Color::new(0.15, 0.15, 0.15, 1.0)
+        };
+        skin.draw(
+            sprites,
+            x + cell / 2.0 - 16.0,
+            y + 20.0,
+            direction::DOWN,
+            0,
+            shade,
+        );
+        let name_width = measure_text(skin.name(), None, 18, 1.0).width;
+        draw_text(
+            skin.name(),
+            x + (cell - name_width) / 2.0,
+            y + 80.0,
+            18.0,
+            if owned { WHITE } else { GRAY },
+        );
+        if *skin == equipped {
+            let mark = "EQUIPPED";
+            let mark_width = measure_text(mark, None, 14, 1.0).width;
+            draw_text(
+                mark,
+                x + (cell - mark_width) / 2.0,
+                y + 104.0,
+                14.0,
+                Color::from_rgba(100, 255, 100, 255),
+            );
+        }
+    }
+
+    // Unlock condition of the highlighted skin
+    let skin = Skin::ALL[selected];
+    if !profile.has_skin(skin)
+        && let Some(hint) = skin.unlock_hint()
+    {
+        let text = format!("LOCKED - {}", hint);
+        let width = measure_text(&text, None, 20, 1.0).width;
+        draw_text(
+            &text,
+            (ui_width() - width) / 2.0,
+            y + 160.0,
+            20.0,
+            Color::from_rgba(255, 210, 90, 255),
+        );
+    }
+
+    let hint = "A/D: Select  |  Enter/Space: Equip  |  ESC: Back";
+    let hint_size = 16.0;
+    let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+    draw_text(
+        hint,
+        (ui_width() - hint_width) / 2.0,
+        ui_height() - 40.0,
+        hint_size,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
@@ -0,0 +1,223 @@
+use macroquad::prelude::*;
+
+use crate::difficulty::Difficulty;
+use crate::mode::GameMode;
+use crate::ui::ui_width;
+
+const BESTS_FILE: &str = "splits.txt";
+const EXPORT_FILE: &str = "splits_export.txt";
+const SHOWN_SPLITS: usize = 5; // Most recent splits listed under the timer
+
+/// Runs are only compared with runs of the same mode and difficulty
+pub fn category(mode: GameMode, difficulty: &Difficulty) -> String {
+    format!("{:?}-{}", mode, difficulty.name)
+}
+
+/// `m:ss.cc`
+pub fn format_time(seconds: f32) -> String {
+    let hundredths = (seconds.max(0.0) * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+/// `+s.cc` behind the personal best, `-s.cc` ahead of it
+fn format_delta(delta: f32) -> String {
+    let sign = if delta > 0.0 { '+' } else { '-' };
+    format!("{}{:.2}", sign, delta.abs())
+}
+
+/// Fastest finished run of a category, with its split at each hacked terminal
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersonalBest {
+    pub category: String,
+    pub total: f32,
+    pub splits: Vec<f32>,
+}
+
+impl PersonalBest {
+    /// How far a split is behind (positive) or ahead of this run's same split
+    pub fn delta(&self, index: usize, time: f32) -> Option<f32> {
+        self.splits.get(index).map(|best| time - best)
+    }
+}
+
+/// Personal bests per category, kept on this machine
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PersonalBests {
+    bests: Vec<PersonalBest>,
+}
+
+impl PersonalBests {
+    /// Read the bests file; a missing or unreadable file starts empty
+    pub fn load() -> Self {
+        std::fs::read_to_string(BESTS_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = std::fs::write(BESTS_FILE, self.serialize()) {
+            eprintln!("Could not save {BESTS_FILE}: {err}");
+        }
+    }
+
+    /// Parse `<category> <total> <split>...` lines; malformed lines are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut bests = Self::default();
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let (Some(category), Some(total)) = (words.next(), words.next()) else {
+                continue;
+            };
+            let Ok(total) = total.parse() else {
+                continue;
+            };
+            let Ok(splits) = words.map(str::parse).collect::<Result<Vec<f32>, _>>() else {
+                continue;
+            };
+            bests.record(category, total, &splits);
+        }
+        bests
+    }
+
+    pub fn serialize(&self) -> String {
+        self.bests
+            .iter()
+            .map(|best| {
+                let mut line = format!("{} {}", best.category, best.total);
+                for split in &best.splits {
+                    line.push_str(&format!(" {}", split));
+                }
+                line + "\n"
+            })
+            .collect()
+    }
+
+    pub fn best(&self, category: &str) -> Option<&PersonalBest> {
+        self.bests.iter().find(|b| b.category == category)
+    }
+
+    /// Keep a finished run if it beats the category's best. Returns true if it did.
+    pub fn record(&mut self, category: &str, total: f32, splits: &[f32]) -> bool {
+        let best = PersonalBest {
+            category: category.to_string(),
+            total,
+            splits: splits.to_vec(),
+        };
+        match self.bests.iter_mut().find(|b| b.category == category) {
+            Some(old) if old.total <= total => false,
+            Some(old) => {
+                *old = best;
+                true
+            }
+            None => {
+                self.bests.push(best);
+                true
+            }
+        }
+    }
+}
+
+/// Write a finished run's splits, with the difference to the personal best
+/// it was compared against, as a plain text table
+pub fn export(category: &str, total: f32, splits: &[f32], best: Option<&PersonalBest>) {
+    let mut text = format!("{category}\n");
+    let mut previous = 0.0;
+    for (i, &split) in splits.iter().enumerate() {
+        let delta = best
+            .and_then(|b| b.delta(i, split))
+            .map(format_delta)
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "Terminal {:<3} {:>10} {:>10} {:>8}\n",
+            i + 1,
+            format_time(split),
+            format_time(split - previous),
+            delta
+        ));
+        previous = split;
+    }
+    let delta = best
+        .map(|b| format_delta(total - b.total))
+        .unwrap_or_default();
+    text.push_str(&format!(
+        "Finish       {:>10} {:>10} {:>8}\n",
+        format_time(total),
+        format_time(total - previous),
+        delta
+    ));
+    if let Err(err) = std::fs::write(EXPORT_FILE, text) {
+        eprintln!("Could not save {EXPORT_FILE}: {err}");
+    }
+}
+
+/// Run clock on the right edge with the latest splits below it, each
+/// compared to the personal best: green when ahead, red when behind
+pub fn draw_timer(time: f32, splits: &[f32], best: Option<&PersonalBest>) {
+    let x = ui_width() - 170.0;
+    let y = 190.0; // Below the objective list
+    draw_rectangle(
+        x - 8.0,
+        y - 26.0,
+        170.0,
+        34.0 + SHOWN_SPLITS as f32 * 18.0,
+        Color::from_rgba(0, 0, 0, 140),
+    );
+    draw_text(&format_time(time), x, y, 30.0, WHITE);
+
+    let first = splits.len().saturating_sub(SHOWN_SPLITS);
+    for (row, (i, &split)) in splits.iter().enumerate().skip(first).enumerate() {
+        let row_y = y + 20.0 + row as f32 * 18.0;
+        draw_text(&format!("T{}", i + 1), x, row_y, 16.0, GRAY);
+        draw_text(&format_time(split), x + 30.0, row_y, 16.0, LIGHTGRAY);
+        if let Some(delta) = best.and_then(|b| b.delta(i, split)) {
+            let color = if delta <= 0.0 {
+                Color::from_rgba(100, 230, 100, 255)
+            } else {
+                Color::from_rgba(255, 100, 90, 255)
+            };
+            draw_text(&format_delta(delta), x + 100.0, row_y, 16.0, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_personal_bests() {
+        let mut bests = PersonalBests::default();
+        assert!(bests.record("Standard-NORMAL", 300.0, &[60.0, 150.0]));
+        assert!(!bests.record("Standard-NORMAL", 320.0, &[50.0, 140.0]));
+        assert!(bests.record("Standard-NORMAL", 280.5, &[55.0, 140.25]));
+        assert!(bests.record("TimeAttack-HARD", 90.0, &[]));
+
+        let best = bests.best("Standard-NORMAL").unwrap();
+        assert_eq!(best.total, 280.5);
+        assert_eq!(best.delta(0, 57.0), Some(2.0));
+        assert_eq!(best.delta(2, 57.0), None);
+        assert_eq!(PersonalBests::parse(&bests.serialize()), bests);
+        assert_eq!(
+            PersonalBests::parse("junk\nStandard-NORMAL x 1\n"),
+            PersonalBests::default()
+        );
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0.0), "0:00.00");
+        assert_eq!(format_time(83.456), "1:23.46");
+        assert_eq!(format_time(3600.0), "60:00.00");
+        assert_eq!(format_delta(-1.5), "-1.50");
+        assert_eq!(format_delta(0.25), "+0.25");
+        assert_eq!(
+            category(GameMode::TimeAttack, &Difficulty::HARD),
+            "TimeAttack-HARD"
+        );
+    }
+}
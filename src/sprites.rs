@@ -1,8 +1,41 @@
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation};
 use macroquad::prelude::*;
 
+use crate::ui::{ui_height, ui_width};
+
 const TILE_SIZE: f32 = 32.0;
 const ITEM_SIZE: f32 = 32.0; // Items are in 32px slots in the sheet
 const BULLET_SIZE: f32 = 32.0;
+pub const WALK_FRAMES: u32 = 4; // Walk cycle: stand, left step, stand, right step
+const PLAYER_STEP_ROW: u32 = 4; // Left and right steps of the player, rows 4 and 5
+const BOT_STEP_ROW: u32 = 6; // Left and right steps of the bot, rows 6 and 7
+const SHEET_ROWS_WITH_WALK: u32 = 8;
+const PACKS_DIR: &str = "data/packs"; // Texture packs: one folder each, with sprites.png
+const PACK_MANIFEST: &str = "pack.txt";
+
+// Draws a sprite's shape in the draw color alone, for hit flashes
+const SILHOUETTE_VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying lowp vec2 uv;
+varying lowp vec4 color;
+uniform mat4 Model;
+uniform mat4 Projection;
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+const SILHOUETTE_FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+uniform sampler2D Texture;
+void main() {
+    gl_FragColor = vec4(color.rgb, color.a * texture2D(Texture, uv).a);
+}
+"#;
 
 /// Sprite sheet layout indices
 pub mod tiles {
@@ -48,32 +81,130 @@ pub mod direction {
     pub const DOWN_LEFT: u32 = 7;
 }
 
+/// A texture pack's `pack.txt`: `<key> <value>` lines, unknown keys skipped
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackManifest {
+    pub tile_size: f32, // Pixels per sheet cell; the layout is SPRITES.md scaled to fit
+}
+
+impl Default for PackManifest {
+    fn default() -> Self {
+        Self {
+            tile_size: TILE_SIZE,
+        }
+    }
+}
+
+impl PackManifest {
+    pub fn parse(text: &str) -> Self {
+        let mut manifest = Self::default();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if let ["tile_size", size] = words.as_slice()
+                && let Ok(size) = size.parse::<u32>()
+                && size > 0
+            {
+                manifest.tile_size = size as f32;
+            }
+        }
+        manifest
+    }
+}
+
+/// The texture pack named by `--pack <name>` on the command line
+pub fn pack_from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--pack" {
+            return args.next();
+        }
+    }
+    None
+}
+
 pub struct SpriteSheet {
     texture: Texture2D,
     splash: Option<Texture2D>,
+    scale: f32,                   // Sheet pixels per game pixel: 1 for the built-in sheet
+    walk_rows: bool, // The sheet has step rows; without them every frame is the standing one
+    silhouette: Option<Material>, // None if the shader failed to build: no hit flashes
 }
 
 impl SpriteSheet {
-    pub async fn load() -> Self {
-        let texture = load_texture("data/sprites.png")
-            .await
-            .expect("Failed to load sprites.png");
+    /// Load the built-in sheet, or a texture pack's from `data/packs/<pack>/`.
+    /// A pack that won't load falls back to the built-in sheet; a pack
+    /// without its own splash screen uses the built-in one.
+    pub async fn load(pack: Option<&str>) -> Self {
+        let mut loaded = None;
+        if let Some(pack) = pack {
+            let dir = format!("{PACKS_DIR}/{pack}");
+            match load_texture(&format!("{dir}/sprites.png")).await {
+                Ok(texture) => {
+                    let manifest = load_string(&format!("{dir}/{PACK_MANIFEST}"))
+                        .await
+                        .map(|text| PackManifest::parse(&text))
+                        .unwrap_or_default();
+                    let splash = load_texture(&format!("{dir}/splash.png")).await.ok();
+                    loaded = Some((texture, manifest, splash));
+                }
+                Err(err) => eprintln!("Could not load texture pack {pack}: {err}"),
+            }
+        }
+        let (texture, manifest, splash) = match loaded {
+            Some(loaded) => loaded,
+            None => {
+                let texture = load_texture("data/sprites.png")
+                    .await
+                    .expect("Failed to load sprites.png");
+                (texture, PackManifest::default(), None)
+            }
+        };
         texture.set_filter(FilterMode::Nearest);
 
         // Try to load splash screen (optional)
-        let splash = load_texture("data/splash.png").await.ok();
+        let splash = match splash {
+            Some(splash) => Some(splash),
+            None => load_texture("data/splash.png").await.ok(),
+        };
         if let Some(ref tex) = splash {
             tex.set_filter(FilterMode::Nearest);
         }
 
-        Self { texture, splash }
+        let scale = manifest.tile_size / TILE_SIZE;
+        let walk_rows = texture.height() >= SHEET_ROWS_WITH_WALK as f32 * manifest.tile_size;
+        let silhouette = load_material(
+            ShaderSource::Glsl {
+                vertex: SILHOUETTE_VERTEX,
+                fragment: SILHOUETTE_FRAGMENT,
+            },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    color_blend: Some(BlendState::new(
+                        Equation::Add,
+                        BlendFactor::Value(BlendValue::SourceAlpha),
+                        BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                    )),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .map_err(|err| eprintln!("Could not build the hit flash shader: {err:?}"))
+        .ok();
+        Self {
+            texture,
+            splash,
+            scale,
+            walk_rows,
+            silhouette,
+        }
     }
 
     /// Draw splash screen scaled to fill the screen, with optional dimming
     pub fn draw_splash(&self, dim_amount: f32) {
         if let Some(ref splash) = self.splash {
-            let sw = screen_width();
-            let sh = screen_height();
+            let sw = ui_width();
+            let sh = ui_height();
             let tex_w = splash.width();
             let tex_h = splash.height();
 
@@ -108,62 +239,87 @@ impl SpriteSheet {
 
     /// Get source rect for a tile (row 0)
     pub fn tile_rect(&self, index: u32) -> Rect {
-        Rect::new(index as f32 * TILE_SIZE, 0.0, TILE_SIZE, TILE_SIZE)
+        self.sheet_rect(index as f32 * TILE_SIZE, 0.0, TILE_SIZE)
     }
 
-    /// Get source rect for player sprite (row 1) with direction
-    pub fn player_rect(&self, direction: u32) -> Rect {
+    /// Source rect in the sheet for a square laid out in game pixels
+    fn sheet_rect(&self, x: f32, y: f32, size: f32) -> Rect {
         Rect::new(
-            direction as f32 * TILE_SIZE,
-            TILE_SIZE, // Row 1
-            TILE_SIZE,
-            TILE_SIZE,
+            x * self.scale,
+            y * self.scale,
+            size * self.scale,
+            size * self.scale,
         )
     }
 
-    /// Get source rect for bot sprite (row 2) with direction
-    pub fn bot_rect(&self, direction: u32) -> Rect {
-        Rect::new(
+    /// Draw part of the sheet at its game size
+    fn draw_rect(&self, src: Rect, x: f32, y: f32, size: f32, tint: Color) {
+        draw_texture_ex(
+            &self.texture,
+            x,
+            y,
+            tint,
+            DrawTextureParams {
+                source: Some(src),
+                dest_size: Some(Vec2::splat(size)),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Source rect for a frame of the player's walk cycle (row 1 standing,
+    /// rows 4 and 5 stepping) with direction
+    pub fn player_frame_rect(&self, direction: u32, frame: u32) -> Rect {
+        self.frame_rect(1, PLAYER_STEP_ROW, direction, frame)
+    }
+
+    /// Source rect for a frame of the bot's walk cycle (row 2 standing, rows
+    /// 6 and 7 stepping) with direction
+    pub fn bot_frame_rect(&self, direction: u32, frame: u32) -> Rect {
+        self.frame_rect(2, BOT_STEP_ROW, direction, frame)
+    }
+
+    fn frame_rect(&self, stand_row: u32, step_row: u32, direction: u32, frame: u32) -> Rect {
+        let row = if self.walk_rows {
+            walk_row(stand_row, step_row, frame)
+        } else {
+            stand_row
+        };
+        self.sheet_rect(
             direction as f32 * TILE_SIZE,
-            TILE_SIZE * 2.0, // Row 2
-            TILE_SIZE,
+            row as f32 * TILE_SIZE,
             TILE_SIZE,
         )
     }
 
     /// Get source rect for item (row 3)
     pub fn item_rect(&self, index: u32) -> Rect {
-        Rect::new(
+        self.sheet_rect(
             index as f32 * ITEM_SIZE,
             TILE_SIZE * 3.0, // Row 3
             ITEM_SIZE,
-            ITEM_SIZE,
         )
     }
 
     /// Get source rect for bullet (row 3, index 15)
     pub fn bullet_rect(&self) -> Rect {
-        Rect::new(
+        self.sheet_rect(
             items::BULLET as f32 * BULLET_SIZE,
             TILE_SIZE * 3.0, // Row 3
             BULLET_SIZE,
-            BULLET_SIZE,
         )
     }
 
     /// Draw a tile at the given screen position
     pub fn draw_tile(&self, index: u32, x: f32, y: f32) {
         let src = self.tile_rect(index);
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            WHITE,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+        self.draw_rect(src, x, y, TILE_SIZE, WHITE);
+    }
+
+    /// Draw a tile with a color tint (for terrain variants without their own sprite)
+    pub fn draw_tile_tinted(&self, index: u32, x: f32, y: f32, tint: Color) {
+        let src = self.tile_rect(index);
+        self.draw_rect(src, x, y, TILE_SIZE, tint);
     }
 
     /// Draw a tile with damage darkening (for destructibles)
@@ -171,76 +327,54 @@ impl SpriteSheet {
         let src = self.tile_rect(index);
         let brightness = 0.5 + 0.5 * (1.0 - damage_factor);
         let color = Color::new(brightness, brightness, brightness, 1.0);
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            color,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+        self.draw_rect(src, x, y, TILE_SIZE, color);
     }
 
-    /// Draw player at the given screen position with direction
-    pub fn draw_player(&self, x: f32, y: f32, direction: u32) {
-        let src = self.player_rect(direction);
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            WHITE,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+    /// Draw player with a color tint (skins, decoy holograms)
+    pub fn draw_player_tinted(&self, x: f32, y: f32, direction: u32, tint: Color) {
+        self.draw_player_frame(x, y, direction, 0, tint);
     }
 
-    /// Draw bot at the given screen position with direction
-    pub fn draw_bot(&self, x: f32, y: f32, direction: u32) {
-        let src = self.bot_rect(direction);
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            WHITE,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+    /// Draw a frame of the player's walk cycle with a color tint
+    pub fn draw_player_frame(&self, x: f32, y: f32, direction: u32, frame: u32, tint: Color) {
+        let src = self.player_frame_rect(direction, frame);
+        self.draw_rect(src, x, y, TILE_SIZE, tint);
     }
 
-    /// Draw bot with a color tint (for hostile bots)
-    pub fn draw_bot_tinted(&self, x: f32, y: f32, direction: u32, tint: Color) {
-        let src = self.bot_rect(direction);
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            tint,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+    /// Draw a frame of the bot's walk cycle with a color tint (hostile,
+    /// allied and boss bots)
+    /// `flash` overrides the tint, filling the bot's shape with one color
+    pub fn draw_bot_frame(
+        &self,
+        x: f32,
+        y: f32,
+        direction: u32,
+        frame: u32,
+        tint: Color,
+        flash: Option<Color>,
+    ) {
+        let src = self.bot_frame_rect(direction, frame);
+        match (flash, &self.silhouette) {
+            (Some(color), Some(silhouette)) => {
+                gl_use_material(silhouette);
+                self.draw_rect(src, x, y, TILE_SIZE, color);
+                gl_use_default_material();
+            }
+            (Some(color), None) => self.draw_rect(src, x, y, TILE_SIZE, color),
+            (None, _) => self.draw_rect(src, x, y, TILE_SIZE, tint),
+        }
     }
 
     /// Draw item at the given screen position (centered)
     pub fn draw_item(&self, index: u32, x: f32, y: f32) {
         let src = self.item_rect(index);
         // Items are 32x32 in the sheet, draw centered
-        draw_texture_ex(
-            &self.texture,
+        self.draw_rect(
+            src,
             x - ITEM_SIZE / 2.0,
             y - ITEM_SIZE / 2.0,
+            ITEM_SIZE,
             WHITE,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
         );
     }
 
@@ -264,48 +398,34 @@ impl SpriteSheet {
 
     /// Get source rect for terminal sprite (row 0, col 12)
     fn terminal_rect(&self) -> Rect {
-        Rect::new(
-            special::TERMINAL_COL as f32 * TILE_SIZE,
-            0.0,
-            TILE_SIZE,
-            TILE_SIZE,
-        )
+        self.sheet_rect(special::TERMINAL_COL as f32 * TILE_SIZE, 0.0, TILE_SIZE)
     }
 
     /// Draw terminal at the given screen position
     pub fn draw_terminal(&self, x: f32, y: f32) {
         let src = self.terminal_rect();
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            WHITE,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+        self.draw_rect(src, x, y, TILE_SIZE, WHITE);
     }
 
     /// Draw terminal with a tint (for completed terminals)
     pub fn draw_terminal_tinted(&self, x: f32, y: f32, tint: Color) {
         let src = self.terminal_rect();
-        draw_texture_ex(
-            &self.texture,
-            x,
-            y,
-            tint,
-            DrawTextureParams {
-                source: Some(src),
-                ..Default::default()
-            },
-        );
+        self.draw_rect(src, x, y, TILE_SIZE, tint);
+    }
+}
+
+/// Sheet row of a walk frame: standing on even frames, then the first and
+/// second step rows in turn
+fn walk_row(stand_row: u32, step_row: u32, frame: u32) -> u32 {
+    match frame % WALK_FRAMES {
+        1 => step_row,
+        3 => step_row + 1,
+        _ => stand_row,
     }
 }
 
 /// Convert an angle (in radians) to a direction index (0-7)
 /// 0 = down, going clockwise
-#[allow(dead_code)]
 pub fn angle_to_direction(angle: f32) -> u32 {
     // Normalize angle to 0..2PI
     let mut a = angle;
@@ -368,3 +488,21 @@ pub fn direction_to_vector(dir: u32) -> (f32, f32) {
         _ => (0.0, 1.0), // Default down
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_manifest() {
+        assert_eq!(PackManifest::parse("tile_size 64\n").tile_size, 64.0);
+        assert_eq!(
+            PackManifest::parse("# big\ntile_size 0\nauthor me\n"),
+            PackManifest::default()
+        );
+
+        let args = ["rstgme", "--pack", "neon"].map(String::from);
+        assert_eq!(pack_from_args(args), Some("neon".to_string()));
+        assert_eq!(pack_from_args(["rstgme", "--pack"].map(String::from)), None);
+    }
+}
@@ -0,0 +1,305 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist};
+use crate::sprites::SpriteSheet;
+use crate::ui::{ui_height, ui_width};
+use crate::weapon::Weapon;
+
+const LIFETIME_FILE: &str = "lifetime.txt";
+
+/// Tallies for the current run, shown on the pause menu's stats page
+pub struct RunStats {
+    kills: Vec<(String, u32)>, // Per weapon or other cause, in order of first kill
+    pub damage_taken: u32,     // Health lost, after armor and shields
+    pub terminals_hacked: u32,
+    pub distance: f32, // Tiles walked
+    pub items_collected: u32,
+    pub time: f32, // Seconds of play
+    pub deaths: u32,
+}
+
+impl RunStats {
+    pub fn new() -> Self {
+        Self {
+            kills: Vec::new(),
+            damage_taken: 0,
+            terminals_hacked: 0,
+            distance: 0.0,
+            items_collected: 0,
+            time: 0.0,
+            deaths: 0,
+        }
+    }
+
+    pub fn add_kill(&mut self, source: &str) {
+        match self.kills.iter_mut().find(|(s, _)| s == source) {
+            Some((_, count)) => *count += 1,
+            None => self.kills.push((source.to_string(), 1)),
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().map(|&(_, n)| n).sum()
+    }
+
+    /// Headline numbers as label/value pairs, shared by the stats page and
+    /// the game over screen
+    pub fn general(&self) -> [(&'static str, String); 6] {
+        let seconds = self.time as u32;
+        [
+            ("Time", format!("{}:{:02}", seconds / 60, seconds % 60)),
+            ("Distance", format!("{:.0} tiles", self.distance)),
+            ("Terminals hacked", self.terminals_hacked.to_string()),
+            ("Items collected", self.items_collected.to_string()),
+            ("Damage taken", self.damage_taken.to_string()),
+            ("Kills", self.total_kills().to_string()),
+        ]
+    }
+
+    /// Stats page overlay
+    pub fn draw(&self) {
+        let line_height = 24.0;
+        let width = 380.0;
+        let rows = 7 + self.kills.len().max(1);
+        let height = 90.0 + rows as f32 * line_height;
+        let x = (ui_width() - width) / 2.0;
+        let y = (ui_height() - height) / 2.0;
+
+        draw_rectangle(x, y, width, height, Color::from_rgba(15, 15, 25, 240));
+        draw_rectangle_lines(x, y, width, height, 2.0, LIGHTGRAY);
+        draw_text("RUN STATS", x + 16.0, y + 30.0, 26.0, WHITE);
+
+        let mut row_y = y + 60.0;
+        for (label, value) in &self.general() {
+            draw_text(label, x + 20.0, row_y, 20.0, GRAY);
+            draw_text(value, x + 220.0, row_y, 20.0, WHITE);
+            row_y += line_height;
+        }
+
+        row_y += 8.0;
+        draw_text("Kills by weapon", x + 20.0, row_y, 20.0, LIGHTGRAY);
+        row_y += line_height;
+        if self.kills.is_empty() {
+            draw_text("None yet", x + 40.0, row_y, 18.0, DARKGRAY);
+        }
+        for (source, count) in &self.kills {
+            draw_text(source, x + 40.0, row_y, 18.0, GRAY);
+            draw_text(&count.to_string(), x + 220.0, row_y, 18.0, WHITE);
+            row_y += line_height;
+        }
+
+        draw_text("ESC / Enter back", x + 16.0, y + height - 14.0, 16.0, GRAY);
+    }
+}
+
+impl Persist for RunStats {
+    fn write(&self, out: &mut String) {
+        self.damage_taken.write(out);
+        self.terminals_hacked.write(out);
+        self.distance.write(out);
+        self.items_collected.write(out);
+        self.time.write(out);
+        self.deaths.write(out);
+        self.kills.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            damage_taken: fields.read()?,
+            terminals_hacked: fields.read()?,
+            distance: fields.read()?,
+            items_collected: fields.read()?,
+            time: fields.read()?,
+            deaths: fields.read()?,
+            kills: fields.read()?,
+        })
+    }
+}
+
+/// Totals over every finished run, kept on this machine
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LifetimeStats {
+    pub runs: u32,
+    kills: Vec<(String, u32)>, // Per weapon or other cause
+    pub terminals_hacked: u32,
+    pub deaths: u32,
+    pub playtime: f32, // Seconds
+}
+
+impl LifetimeStats {
+    /// Read the statistics file; a missing or unreadable file starts from zero
+    pub fn load() -> Self {
+        std::fs::read_to_string(LIFETIME_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = std::fs::write(LIFETIME_FILE, self.serialize()) {
+            eprintln!("Could not save {LIFETIME_FILE}: {err}");
+        }
+    }
+
+    /// Parse `<key> <value>` lines, with a `kills <source> <count>` line per
+    /// kill source; malformed lines are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut stats = Self::default();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["runs", value] => stats.runs = value.parse().unwrap_or(0),
+                ["terminals", value] => stats.terminals_hacked = value.parse().unwrap_or(0),
+                ["deaths", value] => stats.deaths = value.parse().unwrap_or(0),
+                ["playtime", value] => stats.playtime = value.parse().unwrap_or(0.0),
+                ["kills", source, count] => {
+                    if let Ok(count) = count.parse() {
+                        stats.kills.push((source.replace('_', " "), count));
+                    }
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = format!(
+            "runs {}\nterminals {}\ndeaths {}\nplaytime {}\n",
+            self.runs, self.terminals_hacked, self.deaths, self.playtime
+        );
+        for (source, count) in &self.kills {
+            text.push_str(&format!("kills {} {}\n", source.replace(' ', "_"), count));
+        }
+        text
+    }
+
+    /// Add a finished run's tallies
+    pub fn add_run(&mut self, run: &RunStats) {
+        self.runs += 1;
+        self.terminals_hacked += run.terminals_hacked;
+        self.deaths += run.deaths;
+        self.playtime += run.time;
+        for (source, count) in &run.kills {
+            match self.kills.iter_mut().find(|(s, _)| s == source) {
+                Some((_, total)) => *total += count,
+                None => self.kills.push((source.clone(), *count)),
+            }
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().map(|&(_, n)| n).sum()
+    }
+
+    /// Weapon with the most kills; explosions, hazards and the like don't count
+    pub fn favorite_weapon(&self) -> Option<&str> {
+        let weapons = Weapon::all_weapons();
+        self.kills
+            .iter()
+            .filter(|(source, _)| weapons.iter().any(|w| w.name == source))
+            .rev()
+            .max_by_key(|&&(_, count)| count)
+            .map(|(source, _)| source.as_str())
+    }
+}
+
+/// Statistics screen: lifetime totals over every finished run
+pub fn draw_lifetime(stats: &LifetimeStats, sprites: &SpriteSheet) {
+    sprites.draw_splash(0.6);
+
+    let title = "STATISTICS";
+    let title_size = 40.0;
+    let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+    draw_text(
+        title,
+        (ui_width() - title_width) / 2.0,
+        100.0,
+        title_size,
+        Color::from_rgba(200, 50, 50, 255),
+    );
+
+    let seconds = stats.playtime as u32;
+    let rows = [
+        ("Runs played", stats.runs.to_string()),
+        (
+            "Total playtime",
+            format!(
+                "{}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+        ),
+        ("Total kills", stats.total_kills().to_string()),
+        ("Terminals hacked", stats.terminals_hacked.to_string()),
+        ("Deaths", stats.deaths.to_string()),
+        (
+            "Favorite weapon",
+            stats.favorite_weapon().unwrap_or("None yet").to_string(),
+        ),
+    ];
+    let x = ui_width() / 2.0 - 180.0;
+    for (i, (label, value)) in rows.iter().enumerate() {
+        let y = 170.0 + i as f32 * 34.0;
+        draw_text(label, x, y, 24.0, GRAY);
+        draw_text(value, x + 230.0, y, 24.0, WHITE);
+    }
+
+    let hint = "Runs count once they end  |  ESC / Enter: Back";
+    let hint_size = 16.0;
+    let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+    draw_text(
+        hint,
+        (ui_width() - hint_width) / 2.0,
+        ui_height() - 40.0,
+        hint_size,
+        Color::from_rgba(150, 150, 150, 255),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kills_by_source() {
+        let mut stats = RunStats::new();
+        stats.add_kill("Pistol");
+        stats.add_kill("Knife");
+        stats.add_kill("Pistol");
+        assert_eq!(
+            stats.kills,
+            vec![("Pistol".to_string(), 2), ("Knife".to_string(), 1)]
+        );
+        assert_eq!(stats.total_kills(), 3);
+    }
+
+    #[test]
+    fn test_lifetime_totals() {
+        let mut run = RunStats::new();
+        run.add_kill("Machine Pistol");
+        run.add_kill("Explosions");
+        run.add_kill("Explosions");
+        run.terminals_hacked = 3;
+        run.deaths = 2;
+        run.time = 90.5;
+
+        let mut lifetime = LifetimeStats::default();
+        assert_eq!(lifetime.favorite_weapon(), None);
+        lifetime.add_run(&run);
+        lifetime.add_run(&run);
+        assert_eq!(lifetime.runs, 2);
+        assert_eq!(lifetime.total_kills(), 6);
+        assert_eq!(lifetime.terminals_hacked, 6);
+        assert_eq!(lifetime.deaths, 4);
+        assert_eq!(lifetime.playtime, 181.0);
+        // Explosions lead the kills but aren't a weapon
+        assert_eq!(lifetime.favorite_weapon(), Some("Machine Pistol"));
+
+        assert_eq!(LifetimeStats::parse(&lifetime.serialize()), lifetime);
+        assert_eq!(
+            LifetimeStats::parse("kills Pistol many\nruns x\n"),
+            LifetimeStats::default()
+        );
+    }
+}
@@ -1,5 +1,10 @@
+use crate::input::COUNTERMEASURE_KEYS;
+use crate::inventory::Inventory;
+use crate::item::{ItemType, KeycardColor};
+use crate::save::{Fields, Persist, persist_enum, push_word};
 use crate::sprites::SpriteSheet;
-use crate::tile_map::TILE_SIZE;
+use crate::tile_map::{Cardinal, TILE_SIZE};
+use crate::ui::{ui_height, ui_width};
 use macroquad::prelude::*;
 
 // Hacking constants
@@ -7,18 +12,360 @@ pub const HACK_DURATION: f32 = 7.0; // Seconds of active hacking to complete
 pub const HACK_WINDOW: f32 = 18.0; // Total seconds before hack fails
 pub const HACK_RANGE: i32 = 1; // Tiles from terminal to interact
 pub const FAIL_BOT_SPAWN: usize = 3; // Extra hostile bots spawned on failure
+pub const MINIGAME_LENGTH: usize = 6; // Symbols to enter in the bypass minigame
+pub const MINIGAME_MISTAKE_PENALTY: f32 = 2.0; // Seconds added to the hack clock per wrong key
+pub const HACK_PROGRESS_DECAY: f32 = 0.02; // Progress lost per second while not hacking
+pub const DEPENDENCY_CHANCE: f32 = 0.6; // Chance a terminal requires an earlier one
+pub const KEYCARD_LOCK_CHANCE: f32 = 0.35; // Chance a terminal needs a keycard
+pub const COUNTERMEASURE_TIME: f32 = 1.5; // Seconds to answer a countermeasure prompt
+pub const COUNTERMEASURE_PROGRESS_LOSS: f32 = 0.25; // Progress knocked off by a failed prompt
+pub const COUNTERMEASURE_WAVE: usize = 2; // Extra hostile bots sent by a failed prompt
+pub const OVERCLOCK_DURATION_CUT: f32 = 0.2; // Fraction of hack duration removed per overclock
+pub const OVERCLOCK_PENALTY_CUT: f32 = 0.5; // Fraction of the mistake penalty removed per overclock
+
+/// Terminal difficulty, rising with distance from the player's spawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TerminalTier {
+    Low,
+    Medium,
+    High,
+}
+
+persist_enum!(TerminalTier { Low, Medium, High });
+
+impl TerminalTier {
+    /// Tier for a terminal `dist` tiles from spawn, out of a possible `max_dist`
+    pub fn from_distance(dist: f32, max_dist: f32) -> Self {
+        let ratio = dist / max_dist.max(1.0);
+        if ratio < 0.25 {
+            TerminalTier::Low
+        } else if ratio < 0.5 {
+            TerminalTier::Medium
+        } else {
+            TerminalTier::High
+        }
+    }
+
+    pub fn level(self) -> u32 {
+        match self {
+            TerminalTier::Low => 1,
+            TerminalTier::Medium => 2,
+            TerminalTier::High => 3,
+        }
+    }
+
+    /// (hack duration, hack window) multipliers
+    fn timing_scale(self) -> (f32, f32) {
+        match self {
+            TerminalTier::Low => (0.8, 1.2),
+            TerminalTier::Medium => (1.0, 1.0),
+            TerminalTier::High => (1.3, 0.9),
+        }
+    }
+
+    /// Hostile bots sent when a hack on this terminal fails
+    pub fn fail_bot_spawn(self) -> usize {
+        match self {
+            TerminalTier::Low => FAIL_BOT_SPAWN - 1,
+            TerminalTier::Medium => FAIL_BOT_SPAWN,
+            TerminalTier::High => FAIL_BOT_SPAWN + 2,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            TerminalTier::Low => Color::from_rgba(100, 255, 100, 255),
+            TerminalTier::Medium => Color::from_rgba(255, 220, 80, 255),
+            TerminalTier::High => Color::from_rgba(255, 100, 80, 255),
+        }
+    }
+}
+
+/// Hack timing for the current run; starts at the defaults and improves
+/// with overclock modules
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HackTiming {
+    pub duration: f32,        // Seconds of active hacking to complete
+    pub window: f32,          // Total seconds before hack fails
+    pub mistake_penalty: f32, // Seconds added to the hack clock per wrong minigame key
+    pub overclocks: u32,
+}
+
+impl Default for HackTiming {
+    fn default() -> Self {
+        Self {
+            duration: HACK_DURATION,
+            window: HACK_WINDOW,
+            mistake_penalty: MINIGAME_MISTAKE_PENALTY,
+            overclocks: 0,
+        }
+    }
+}
+
+impl HackTiming {
+    /// Install an overclock module: faster hacks, cheaper minigame mistakes
+    pub fn overclock(&mut self) {
+        self.duration *= 1.0 - OVERCLOCK_DURATION_CUT;
+        self.mistake_penalty *= 1.0 - OVERCLOCK_PENALTY_CUT;
+        self.overclocks += 1;
+    }
+
+    /// Timing for hacking a terminal of the given tier
+    pub fn for_tier(&self, tier: TerminalTier) -> Self {
+        let (duration_scale, window_scale) = tier.timing_scale();
+        Self {
+            duration: self.duration * duration_scale,
+            window: self.window * window_scale,
+            ..*self
+        }
+    }
+
+    /// Total hack time saved relative to the default, as a percentage
+    pub fn duration_bonus_percent(&self) -> f32 {
+        (1.0 - self.duration / HACK_DURATION) * 100.0
+    }
+}
+
+impl Persist for HackTiming {
+    fn write(&self, out: &mut String) {
+        self.duration.write(out);
+        self.window.write(out);
+        self.mistake_penalty.write(out);
+        self.overclocks.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            duration: fields.read()?,
+            window: fields.read()?,
+            mistake_penalty: fields.read()?,
+            overclocks: fields.read()?,
+        })
+    }
+}
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum HackState {
     Idle,
-    InProgress { progress: f32, elapsed: f32 },
+    InProgress {
+        progress: f32,
+        elapsed: f32,
+    },
+    /// Player left mid-hack; progress is kept (slowly decaying) until resumed,
+    /// and the window picks up where it stopped
+    Suspended {
+        progress: f32,
+        elapsed: f32,
+    },
     Complete,
 }
 
+/// A hack still running is saved as suspended, keeping its progress and
+/// time spent
+impl Persist for HackState {
+    fn write(&self, out: &mut String) {
+        match *self {
+            HackState::Idle => push_word(out, "Idle"),
+            HackState::InProgress { progress, elapsed }
+            | HackState::Suspended { progress, elapsed } => {
+                push_word(out, "Suspended");
+                progress.write(out);
+                elapsed.write(out);
+            }
+            HackState::Complete => push_word(out, "Complete"),
+        }
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let word = fields.word()?;
+        match word {
+            "Idle" => Ok(HackState::Idle),
+            "Suspended" => Ok(HackState::Suspended {
+                progress: fields.read()?,
+                elapsed: fields.read()?,
+            }),
+            "Complete" => Ok(HackState::Complete),
+            _ => Err(fields.error(word)),
+        }
+    }
+}
+
+/// Outcome of a single key entered into the bypass minigame
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MinigameResult {
+    Correct,
+    Mistake,
+    Solved,
+}
+
+/// Optional bypass for an in-progress hack: enter a random sequence of
+/// directions. Solving it completes the hack instantly; mistakes restart
+/// the sequence and cost time on the hack window.
+#[derive(Clone, PartialEq, Debug)]
+pub struct HackMinigame {
+    pub sequence: Vec<Cardinal>,
+    pub entered: usize,
+}
+
+impl HackMinigame {
+    pub fn new() -> Self {
+        const SYMBOLS: [Cardinal; 4] = [
+            Cardinal::Up,
+            Cardinal::Down,
+            Cardinal::Left,
+            Cardinal::Right,
+        ];
+        let sequence = (0..MINIGAME_LENGTH)
+            .map(|_| SYMBOLS[rand::gen_range(0, SYMBOLS.len())])
+            .collect();
+        Self {
+            sequence,
+            entered: 0,
+        }
+    }
+
+    pub fn input(&mut self, dir: Cardinal) -> MinigameResult {
+        if self.sequence.get(self.entered) == Some(&dir) {
+            self.entered += 1;
+            if self.entered >= self.sequence.len() {
+                MinigameResult::Solved
+            } else {
+                MinigameResult::Correct
+            }
+        } else {
+            self.entered = 0;
+            MinigameResult::Mistake
+        }
+    }
+
+    /// Draw the minigame overlay centered on screen
+    pub fn draw(&self, time_left: f32, mistake_penalty: f32) {
+        let cell = 44.0;
+        let width = cell * self.sequence.len() as f32 + 40.0;
+        let height = 130.0;
+        let x = (ui_width() - width) / 2.0;
+        let y = (ui_height() - height) / 2.0;
+
+        draw_rectangle(x, y, width, height, Color::from_rgba(0, 20, 10, 230));
+        draw_rectangle_lines(
+            x,
+            y,
+            width,
+            height,
+            2.0,
+            Color::from_rgba(80, 255, 120, 255),
+        );
+        draw_text(
+            "BYPASS: ENTER SEQUENCE",
+            x + 20.0,
+            y + 28.0,
+            20.0,
+            Color::from_rgba(80, 255, 120, 255),
+        );
+
+        for (i, &dir) in self.sequence.iter().enumerate() {
+            let cx = x + 20.0 + cell * i as f32 + cell / 2.0;
+            let cy = y + 70.0;
+            let color = if i < self.entered {
+                Color::from_rgba(80, 255, 120, 255)
+            } else if i == self.entered {
+                Color::from_rgba(255, 255, 100, 255)
+            } else {
+                Color::from_rgba(120, 120, 120, 255)
+            };
+            draw_rectangle_lines(cx - 18.0, cy - 18.0, 36.0, 36.0, 1.0, color);
+            let (dx, dy) = dir.delta();
+            let (dx, dy) = (dx as f32, dy as f32);
+            let tip = Vec2::new(cx + dx * 12.0, cy + dy * 12.0);
+            let left = Vec2::new(cx - dx * 4.0 + dy * 10.0, cy - dy * 4.0 - dx * 10.0);
+            let right = Vec2::new(cx - dx * 4.0 - dy * 10.0, cy - dy * 4.0 + dx * 10.0);
+            draw_triangle(tip, left, right, color);
+        }
+
+        draw_text(
+            &format!(
+                "Arrows/WASD to enter - mistakes cost {:.1}s - ESC to abort    {:.1}s",
+                mistake_penalty, time_left
+            ),
+            x + 20.0,
+            y + height - 16.0,
+            14.0,
+            WHITE,
+        );
+    }
+}
+
+/// Security countermeasure during a hack: press the shown key in time
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Countermeasure {
+    pub key: KeyCode,
+    pub time_left: f32,
+}
+
+impl Countermeasure {
+    pub fn new() -> Self {
+        Self {
+            key: COUNTERMEASURE_KEYS[rand::gen_range(0, COUNTERMEASURE_KEYS.len())],
+            time_left: COUNTERMEASURE_TIME,
+        }
+    }
+
+    /// Seconds of active hacking until the next countermeasure
+    pub fn next_interval() -> f32 {
+        rand::gen_range(3.0, 6.0)
+    }
+
+    /// Some(true) when answered, Some(false) on a wrong key or timeout,
+    /// None while still waiting
+    pub fn update(&mut self, dt: f32, pressed: Option<KeyCode>) -> Option<bool> {
+        if let Some(key) = pressed {
+            return Some(key == self.key);
+        }
+        self.time_left -= dt;
+        if self.time_left <= 0.0 {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Draw the prompt above the hack progress bar
+    pub fn draw(&self) {
+        let text = format!("COUNTERMEASURE! PRESS [{:?}]", self.key);
+        let font_size = 28.0;
+        let width = measure_text(&text, None, font_size as u16, 1.0).width;
+        let x = (ui_width() - width) / 2.0;
+        let y = ui_height() - 150.0;
+        let color = Color::from_rgba(255, 80, 80, 255);
+
+        draw_rectangle(
+            x - 10.0,
+            y - 28.0,
+            width + 20.0,
+            44.0,
+            Color::from_rgba(0, 0, 0, 200),
+        );
+        draw_text(&text, x, y, font_size, color);
+        draw_rectangle(
+            x,
+            y + 8.0,
+            width * (self.time_left / COUNTERMEASURE_TIME),
+            4.0,
+            color,
+        );
+    }
+}
+
 pub struct Terminal {
     pub x: f32, // Pixel coordinates (centered in tile)
     pub y: f32,
     pub state: HackState,
+    pub minigame: Option<HackMinigame>,
+    pub countermeasure: Option<Countermeasure>,
+    pub countermeasure_timer: f32, // Active hacking time until the next countermeasure
+    pub tier: TerminalTier,
+    pub requires: Vec<usize>, // Indices of terminals that must be hacked first
+    pub locked: Option<KeycardColor>, // Keycard needed before it can be hacked
 }
 
 impl Terminal {
@@ -27,6 +374,12 @@ impl Terminal {
             x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
             y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
             state: HackState::Idle,
+            minigame: None,
+            countermeasure: None,
+            countermeasure_timer: 0.0,
+            tier: TerminalTier::Medium,
+            requires: Vec::new(),
+            locked: None,
         }
     }
 
@@ -40,6 +393,65 @@ impl Terminal {
         self.x = tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0;
         self.y = tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0;
         self.state = HackState::Idle;
+        self.minigame = None;
+        self.countermeasure = None;
+    }
+
+    /// Progress kept on the terminal from an unfinished hack
+    pub fn retained_progress(&self) -> Option<f32> {
+        match self.state {
+            HackState::InProgress { progress, .. } | HackState::Suspended { progress, .. } => {
+                Some(progress)
+            }
+            _ => None,
+        }
+    }
+
+    /// Pause an in-progress hack, keeping its progress
+    pub fn suspend(&mut self) {
+        if let HackState::InProgress { progress, elapsed } = self.state {
+            self.state = HackState::Suspended { progress, elapsed };
+            self.minigame = None;
+            self.countermeasure = None;
+        }
+    }
+
+    /// Start a hack, picking up any retained progress and the window where
+    /// it was left, so walking away doesn't buy a fresh window
+    pub fn start_hack(&mut self) {
+        let (progress, elapsed) = match self.state {
+            HackState::Suspended { progress, elapsed } => (progress, elapsed),
+            _ => (0.0, 0.0),
+        };
+        self.state = HackState::InProgress { progress, elapsed };
+        self.countermeasure_timer = Countermeasure::next_interval();
+    }
+
+    /// Bleed off retained progress; a fully drained terminal returns to idle
+    pub fn decay_progress(&mut self, dt: f32) {
+        match &mut self.state {
+            HackState::InProgress { progress, .. } => {
+                *progress = (*progress - HACK_PROGRESS_DECAY * dt).max(0.0);
+            }
+            HackState::Suspended { progress, .. } => {
+                *progress -= HACK_PROGRESS_DECAY * dt;
+                if *progress <= 0.0 {
+                    self.state = HackState::Idle;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Spend a keycard of the lock color to unlock this terminal; returns
+    /// whether it is now unlocked
+    pub fn try_unlock(&mut self, inventory: &mut Inventory) -> bool {
+        if let Some(color) = self.locked
+            && inventory.take(ItemType::Keycard { color })
+        {
+            self.locked = None;
+        }
+        self.locked.is_none()
     }
 
     /// Check if player is within interaction range
@@ -50,11 +462,21 @@ impl Terminal {
     }
 
     /// Draw the terminal
-    pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
+    pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet, encrypted: bool) {
         let screen_x = self.x - TILE_SIZE / 2.0 - camera_x;
         let screen_y = self.y - TILE_SIZE / 2.0 - camera_y;
 
         match self.state {
+            _ if encrypted => {
+                sprites.draw_terminal_tinted(
+                    screen_x,
+                    screen_y,
+                    Color::from_rgba(255, 100, 100, 255),
+                );
+            }
+            _ if let Some(color) = self.locked => {
+                sprites.draw_terminal_tinted(screen_x, screen_y, color.color());
+            }
             HackState::Complete => {
                 // Draw completed terminal with green tint
                 sprites.draw_terminal_tinted(
@@ -67,10 +489,43 @@ impl Terminal {
                 sprites.draw_terminal(screen_x, screen_y);
             }
         }
+
+        // Retained progress: thin bar and percentage under the terminal
+        if let Some(progress) = self.retained_progress() {
+            let bar_y = screen_y + TILE_SIZE + 2.0;
+            draw_rectangle(
+                screen_x,
+                bar_y,
+                TILE_SIZE,
+                4.0,
+                Color::from_rgba(0, 0, 0, 180),
+            );
+            draw_rectangle(
+                screen_x,
+                bar_y,
+                TILE_SIZE * progress,
+                4.0,
+                Color::from_rgba(100, 200, 255, 255),
+            );
+            draw_text(
+                &format!("{:.0}%", progress * 100.0),
+                screen_x + 4.0,
+                bar_y + 16.0,
+                14.0,
+                Color::from_rgba(100, 200, 255, 255),
+            );
+        }
     }
 
     /// Draw interaction prompt if player is nearby and terminal is hackable
-    pub fn draw_prompt(&self, camera_x: f32, camera_y: f32, player_x: i32, player_y: i32) {
+    pub fn draw_prompt(
+        &self,
+        camera_x: f32,
+        camera_y: f32,
+        player_x: i32,
+        player_y: i32,
+        encrypted: bool,
+    ) {
         if self.state == HackState::Complete {
             return;
         }
@@ -79,18 +534,75 @@ impl Terminal {
             let screen_x = self.x - TILE_SIZE / 2.0 - camera_x;
             let screen_y = self.y - TILE_SIZE / 2.0 - camera_y;
 
-            // Draw "[E] Hack" prompt above terminal
+            // Draw "[E] Hack" prompt above terminal, offering the bypass once started
+            let prompt = match self.state {
+                _ if encrypted => "ENCRYPTED",
+                _ if self.locked.is_some() => "[E] Keycard",
+                HackState::InProgress { .. } => "[E] Hack  [B] Bypass",
+                HackState::Suspended { .. } => "[E] Resume",
+                _ => "[E] Hack",
+            };
             draw_text(
-                "[E] Hack",
+                prompt,
                 screen_x - 8.0,
                 screen_y - 5.0,
                 16.0,
                 Color::from_rgba(255, 255, 100, 255),
             );
+            draw_text(
+                &format!("Tier {}", self.tier.level()),
+                screen_x - 8.0,
+                screen_y - 21.0,
+                14.0,
+                self.tier.color(),
+            );
+        }
+    }
+}
+
+impl Persist for Terminal {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+        self.state.write(out);
+        self.tier.write(out);
+        self.requires.write(out);
+        self.locked.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            state: fields.read()?,
+            minigame: None,
+            countermeasure: None,
+            countermeasure_timer: 0.0,
+            tier: fields.read()?,
+            requires: fields.read()?,
+            locked: fields.read()?,
+        })
+    }
+}
+
+/// Randomly make terminals depend on earlier ones, forming a DAG so every
+/// chain can be completed starting from the first terminal
+pub fn link_dependencies(terminals: &mut [Terminal]) {
+    for (i, terminal) in terminals.iter_mut().enumerate().skip(1) {
+        if rand::gen_range(0.0, 1.0) < DEPENDENCY_CHANCE {
+            terminal.requires = vec![rand::gen_range(0, i)];
         }
     }
 }
 
+/// A terminal is encrypted until all of its prerequisites are hacked
+pub fn is_encrypted(terminals: &[Terminal], idx: usize) -> bool {
+    terminals[idx]
+        .requires
+        .iter()
+        .any(|&req| terminals[req].state != HackState::Complete)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +627,172 @@ mod tests {
         assert_eq!(terminal.state, HackState::Idle);
     }
 
+    #[test]
+    fn test_hack_suspend_and_resume() {
+        let mut terminal = Terminal::new(5, 10);
+        terminal.state = HackState::InProgress {
+            progress: 0.5,
+            elapsed: 12.0,
+        };
+
+        terminal.suspend();
+        assert_eq!(
+            terminal.state,
+            HackState::Suspended {
+                progress: 0.5,
+                elapsed: 12.0
+            }
+        );
+
+        terminal.decay_progress(5.0);
+        let retained = terminal.retained_progress().unwrap();
+        assert!((retained - (0.5 - HACK_PROGRESS_DECAY * 5.0)).abs() < 1e-5);
+
+        // Resuming keeps progress and the time already spent in the window
+        terminal.start_hack();
+        assert_eq!(
+            terminal.state,
+            HackState::InProgress {
+                progress: retained,
+                elapsed: 12.0
+            }
+        );
+
+        // Fully drained suspended hacks go idle
+        terminal.suspend();
+        terminal.decay_progress(100.0);
+        assert_eq!(terminal.state, HackState::Idle);
+        assert_eq!(terminal.retained_progress(), None);
+    }
+
+    #[test]
+    fn test_dependencies_form_dag() {
+        let mut terminals: Vec<Terminal> = (0..5).map(|i| Terminal::new(i, 0)).collect();
+        link_dependencies(&mut terminals);
+
+        assert!(terminals[0].requires.is_empty());
+        for (i, terminal) in terminals.iter().enumerate() {
+            assert!(terminal.requires.iter().all(|&req| req < i));
+        }
+    }
+
+    #[test]
+    fn test_encrypted_until_prerequisite_hacked() {
+        let mut terminals = vec![Terminal::new(0, 0), Terminal::new(5, 0)];
+        terminals[1].requires = vec![0];
+
+        assert!(!is_encrypted(&terminals, 0));
+        assert!(is_encrypted(&terminals, 1));
+
+        terminals[0].state = HackState::Complete;
+        assert!(!is_encrypted(&terminals, 1));
+    }
+
+    #[test]
+    fn test_keycard_unlock() {
+        let mut terminal = Terminal::new(0, 0);
+        terminal.locked = Some(KeycardColor::Red);
+        let red = ItemType::Keycard {
+            color: KeycardColor::Red,
+        };
+        let blue = ItemType::Keycard {
+            color: KeycardColor::Blue,
+        };
+
+        let mut inventory = Inventory::new(4);
+        assert!(!terminal.try_unlock(&mut inventory));
+
+        // Only a card of the lock color fits
+        inventory.add(blue);
+        assert!(!terminal.try_unlock(&mut inventory));
+        assert_eq!(inventory.count(blue), 1);
+
+        inventory.add(red);
+        inventory.add(red);
+        assert!(terminal.try_unlock(&mut inventory));
+        assert!(terminal.locked.is_none());
+        assert_eq!(inventory.count(red), 1);
+
+        // Already unlocked terminals don't consume cards
+        assert!(terminal.try_unlock(&mut inventory));
+        assert_eq!(inventory.count(red), 1);
+    }
+
+    #[test]
+    fn test_overclock_timing() {
+        let mut timing = HackTiming::default();
+        assert_eq!(timing.duration, HACK_DURATION);
+        assert_eq!(timing.duration_bonus_percent(), 0.0);
+
+        timing.overclock();
+        assert!((timing.duration - HACK_DURATION * 0.8).abs() < 1e-5);
+        assert!((timing.mistake_penalty - MINIGAME_MISTAKE_PENALTY * 0.5).abs() < 1e-5);
+        assert!((timing.duration_bonus_percent() - 20.0).abs() < 1e-3);
+        assert_eq!(timing.window, HACK_WINDOW);
+        assert_eq!(timing.overclocks, 1);
+    }
+
+    #[test]
+    fn test_countermeasure() {
+        let mut cm = Countermeasure::new();
+        assert_eq!(cm.update(0.5, None), None);
+        assert_eq!(cm.update(0.0, Some(cm.key)), Some(true));
+
+        let wrong = COUNTERMEASURE_KEYS
+            .into_iter()
+            .find(|&k| k != cm.key)
+            .unwrap();
+        assert_eq!(cm.update(0.0, Some(wrong)), Some(false));
+
+        let mut cm = Countermeasure::new();
+        assert_eq!(cm.update(COUNTERMEASURE_TIME, None), Some(false));
+    }
+
+    #[test]
+    fn test_terminal_tiers() {
+        assert_eq!(TerminalTier::from_distance(5.0, 60.0), TerminalTier::Low);
+        assert_eq!(
+            TerminalTier::from_distance(20.0, 60.0),
+            TerminalTier::Medium
+        );
+        assert_eq!(TerminalTier::from_distance(50.0, 60.0), TerminalTier::High);
+
+        let timing = HackTiming::default();
+        let hard = timing.for_tier(TerminalTier::High);
+        let easy = timing.for_tier(TerminalTier::Low);
+        assert!(hard.duration > timing.duration && easy.duration < timing.duration);
+        assert!(hard.window < easy.window);
+        assert_eq!(hard.mistake_penalty, timing.mistake_penalty);
+        assert!(TerminalTier::High.fail_bot_spawn() > TerminalTier::Low.fail_bot_spawn());
+    }
+
+    #[test]
+    fn test_minigame_sequence() {
+        let mut game = HackMinigame::new();
+        assert_eq!(game.sequence.len(), MINIGAME_LENGTH);
+
+        let sequence = game.sequence.clone();
+        for &dir in &sequence[..MINIGAME_LENGTH - 1] {
+            assert_eq!(game.input(dir), MinigameResult::Correct);
+        }
+        assert_eq!(
+            game.input(sequence[MINIGAME_LENGTH - 1]),
+            MinigameResult::Solved
+        );
+    }
+
+    #[test]
+    fn test_minigame_mistake_restarts() {
+        let mut game = HackMinigame {
+            sequence: vec![Cardinal::Up, Cardinal::Left],
+            entered: 0,
+        };
+        assert_eq!(game.input(Cardinal::Up), MinigameResult::Correct);
+        assert_eq!(game.input(Cardinal::Right), MinigameResult::Mistake);
+        assert_eq!(game.entered, 0);
+        assert_eq!(game.input(Cardinal::Left), MinigameResult::Mistake);
+    }
+
     #[test]
     fn test_player_nearby() {
         let terminal = Terminal::new(10, 10);
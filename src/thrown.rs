@@ -0,0 +1,93 @@
+use crate::sprites::{SpriteSheet, tiles};
+use crate::tile_map::{TILE_SIZE, TileMap};
+
+const THROW_SPEED: f32 = 360.0;
+const THROW_RANGE: f32 = TILE_SIZE * 6.0;
+pub const CRATE_HIT_RADIUS: f32 = TILE_SIZE * 0.6; // Distance at which a flying crate hits a bot
+
+/// A lifted crate thrown by the player; it breaks on whatever it hits
+pub struct ThrownCrate {
+    pub x: f32,
+    pub y: f32,
+    dx: f32,
+    dy: f32,
+    travel_left: f32,
+}
+
+impl ThrownCrate {
+    /// Throw from (x, y) toward the target point, always flying the full range
+    pub fn throw(x: f32, y: f32, target_x: f32, target_y: f32) -> Self {
+        let (dx, dy) = (target_x - x, target_y - y);
+        let len = (dx * dx + dy * dy).sqrt();
+        let (dx, dy) = if len > 0.0 {
+            (dx / len, dy / len)
+        } else {
+            (1.0, 0.0)
+        };
+        Self {
+            x,
+            y,
+            dx,
+            dy,
+            travel_left: THROW_RANGE,
+        }
+    }
+
+    /// Fly on. Returns true once the crate smashes against something solid
+    /// or drops at the end of its range; it stays on the near side of walls.
+    pub fn update(&mut self, dt: f32, map: &TileMap) -> bool {
+        let step = (THROW_SPEED * dt).min(self.travel_left);
+        let (nx, ny) = (self.x + self.dx * step, self.y + self.dy * step);
+        if map.blocks_projectile_at((nx / TILE_SIZE) as i32, (ny / TILE_SIZE) as i32) {
+            return true;
+        }
+        self.x = nx;
+        self.y = ny;
+        self.travel_left -= step;
+        self.travel_left <= 0.0
+    }
+
+    /// Tile the crate is over (where its loot falls)
+    pub fn tile(&self) -> (i32, i32) {
+        ((self.x / TILE_SIZE) as i32, (self.y / TILE_SIZE) as i32)
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
+        // Bobs up along a shallow arc while in flight
+        let lift = (self.travel_left / THROW_RANGE * std::f32::consts::PI).sin() * 10.0;
+        sprites.draw_tile(
+            tiles::CRATE,
+            self.x - camera_x - TILE_SIZE / 2.0,
+            self.y - camera_y - TILE_SIZE / 2.0 - lift,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crate_breaks_on_walls() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(4, 1, crate::tile_map::TileType::Wall);
+        let start = (1.5 * TILE_SIZE, 1.5 * TILE_SIZE);
+        let mut thrown = ThrownCrate::throw(start.0, start.1, 9.0 * TILE_SIZE, start.1);
+
+        let mut broke = false;
+        for _ in 0..100 {
+            if thrown.update(0.01, &map) {
+                broke = true;
+                break;
+            }
+        }
+        assert!(broke);
+        assert_eq!(thrown.tile(), (3, 1));
+
+        // Open floor: it drops at the end of its range
+        let mut thrown =
+            ThrownCrate::throw(start.0, 5.5 * TILE_SIZE, 9.0 * TILE_SIZE, 5.5 * TILE_SIZE);
+        while !thrown.update(0.01, &map) {}
+        assert!((thrown.x - (start.0 + THROW_RANGE)).abs() < 0.01);
+    }
+}
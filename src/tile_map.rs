@@ -1,9 +1,16 @@
 use macroquad::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
 
+use crate::item::{KeycardColor, draw_keycard};
+use crate::save::{Fields, Persist, SaveReader, SaveWriter, persist_enum};
 use crate::sprites::{SpriteSheet, tiles};
 
 pub const TILE_SIZE: f32 = 32.0;
+const CRATER_CHANCE: f32 = 0.15; // Chance per center tile that an explosion leaves a pit
+const PLATE_DOOR_CLOSE_DELAY: f32 = 0.5; // Seconds a plate door stays open after release
+const NUM_DATA_CACHES: usize = 3; // Destructible walls hiding a data fragment
+const NUM_CHIP_STASHES: usize = 2; // Destructible walls hiding a data chip
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum EntityType {
@@ -24,16 +31,154 @@ pub enum TileType {
     DoorBoth,
     Crate,
     WallDestructible,
+    Scorched,
+    PressurePlate,
+    PlateDoor,
+    DoorOneWay,
+    DoorLocked, // Opens for the player carrying a keycard of the lock color
+}
+
+/// Cardinal direction, used for the passage direction of one-way doors
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cardinal {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+persist_enum!(Cardinal {
+    Up,
+    Down,
+    Left,
+    Right
+});
+
+impl Cardinal {
+    /// Tile movement delta for this direction
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Cardinal::Up => (0, -1),
+            Cardinal::Down => (0, 1),
+            Cardinal::Left => (-1, 0),
+            Cardinal::Right => (1, 0),
+        }
+    }
+
+    /// Screen angle in radians (0 = right, clockwise)
+    fn angle(self) -> f32 {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match self {
+            Cardinal::Right => 0.0,
+            Cardinal::Down => FRAC_PI_2,
+            Cardinal::Left => PI,
+            Cardinal::Up => -FRAC_PI_2,
+        }
+    }
+}
+
+/// Effect triggered by a pressure plate
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlateEffect {
+    /// Holds the linked plate door open while the plate is pressed
+    OpenDoor,
+    /// Detonates a blast at the linked tile each time the plate is pressed
+    Trap,
+    /// Drops loot on the plate the first time it is pressed
+    SpawnLoot,
+}
+
+persist_enum!(PlateEffect {
+    OpenDoor,
+    Trap,
+    SpawnLoot
+});
+
+/// Bit flags stored in `TileMeta::flags`
+pub mod flags {
+    pub const PRESSED: u32 = 1 << 0; // Pressure plate is held down
+    pub const USED: u32 = 1 << 1; // One-shot effect has already fired
+    pub const OPEN: u32 = 1 << 2; // Plate door is open
+    pub const CACHE: u32 = 1 << 3; // Destructible wall hides a data fragment
+    pub const CHIP: u32 = 1 << 4; // Destructible wall hides a data chip
+}
+
+/// Structured per-tile data kept in a sparse store alongside the tile grid,
+/// so tile behavior can carry state without new `TileType` variants.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TileMeta {
+    /// Remaining hits for destructible tiles
+    pub health: u8,
+    /// Tile this one is wired to (plate -> door or trap target)
+    pub link: Option<(usize, usize)>,
+    /// General-purpose countdown in seconds (plate door close delay)
+    pub timer: f32,
+    /// Orientation of directional tiles (one-way doors)
+    pub direction: Option<Cardinal>,
+    /// What a pressure plate does when pressed
+    pub plate: Option<PlateEffect>,
+    /// Keycard color needed to open a locked door
+    pub lock: Option<KeycardColor>,
+    /// Combination of `flags::*` bits
+    pub flags: u32,
+}
+
+impl TileMeta {
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+
+    pub fn set_flag(&mut self, flag: u32, on: bool) {
+        if on {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+}
+
+impl Persist for TileMeta {
+    fn write(&self, out: &mut String) {
+        self.health.write(out);
+        self.link.write(out);
+        self.timer.write(out);
+        self.direction.write(out);
+        self.plate.write(out);
+        self.lock.write(out);
+        self.flags.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            health: fields.read()?,
+            link: fields.read()?,
+            timer: fields.read()?,
+            direction: fields.read()?,
+            plate: fields.read()?,
+            lock: fields.read()?,
+            flags: fields.read()?,
+        })
+    }
 }
 
 impl TileType {
     pub fn is_walkable_by(self, entity_type: EntityType) -> bool {
         match self {
-            TileType::Floor | TileType::Sand | TileType::Water | TileType::Lava => true,
+            TileType::Floor
+            | TileType::Sand
+            | TileType::Water
+            | TileType::Lava
+            | TileType::Scorched
+            | TileType::PressurePlate => true,
             TileType::DoorPlayer => entity_type == EntityType::Player,
             TileType::DoorBot => entity_type == EntityType::Bot,
-            TileType::DoorBoth => true,
-            TileType::Wall | TileType::Pit | TileType::Crate | TileType::WallDestructible => false,
+            TileType::DoorBoth | TileType::DoorOneWay => true,
+            TileType::Wall
+            | TileType::Pit
+            | TileType::Crate
+            | TileType::WallDestructible
+            | TileType::PlateDoor
+            | TileType::DoorLocked => false,
         }
     }
 
@@ -53,12 +198,61 @@ impl TileType {
             | TileType::DoorBot
             | TileType::DoorBoth
             | TileType::Crate
-            | TileType::WallDestructible => true,
-            // Pit, Lava, Floor, Sand, Water let projectiles pass
+            | TileType::WallDestructible
+            | TileType::PlateDoor
+            | TileType::DoorOneWay
+            | TileType::DoorLocked => true,
+            // Pit, Lava, Floor, Sand, Water and plates let projectiles pass
             _ => false,
         }
     }
 
+    /// Tile for a symbol in a map file (see data/campaign.txt). Scorched
+    /// ground and wired tiles only appear in save files: they need metadata
+    /// the campaign format has no way to give them.
+    pub fn from_symbol(symbol: char) -> Option<TileType> {
+        Some(match symbol {
+            '.' => TileType::Floor,
+            '#' => TileType::Wall,
+            ',' => TileType::Sand,
+            '~' => TileType::Water,
+            'L' => TileType::Lava,
+            'O' => TileType::Pit,
+            'c' => TileType::Crate,
+            '%' => TileType::WallDestructible,
+            '+' => TileType::DoorBoth,
+            '=' => TileType::DoorPlayer,
+            '-' => TileType::DoorBot,
+            ';' => TileType::Scorched,
+            '^' => TileType::PressurePlate,
+            '|' => TileType::PlateDoor,
+            '>' => TileType::DoorOneWay,
+            '&' => TileType::DoorLocked,
+            _ => return None,
+        })
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            TileType::Floor => '.',
+            TileType::Wall => '#',
+            TileType::Sand => ',',
+            TileType::Water => '~',
+            TileType::Lava => 'L',
+            TileType::Pit => 'O',
+            TileType::Crate => 'c',
+            TileType::WallDestructible => '%',
+            TileType::DoorBoth => '+',
+            TileType::DoorPlayer => '=',
+            TileType::DoorBot => '-',
+            TileType::Scorched => ';',
+            TileType::PressurePlate => '^',
+            TileType::PlateDoor => '|',
+            TileType::DoorOneWay => '>',
+            TileType::DoorLocked => '&',
+        }
+    }
+
     pub fn is_destructible(self) -> bool {
         matches!(self, TileType::Crate | TileType::WallDestructible)
     }
@@ -84,13 +278,47 @@ impl TileType {
             TileType::DoorBoth => tiles::DOOR_BOTH,
             TileType::Crate => tiles::CRATE,
             TileType::WallDestructible => tiles::WALL_DESTRUCTIBLE,
+            TileType::Scorched => tiles::FLOOR, // Drawn as darkened floor
+            // Plates and plate doors are drawn as floor/door with an overlay
+            TileType::PressurePlate => tiles::FLOOR,
+            TileType::PlateDoor => tiles::DOOR_BOTH,
+            TileType::DoorOneWay => tiles::DOOR_BOTH, // Arrow drawn on top
+            TileType::DoorLocked => tiles::DOOR_BOTH, // Tinted with the lock color
+        }
+    }
+
+    /// Flat color used for the scaled-down overview map (see SPRITES.md palette)
+    pub fn overview_color(self) -> Color {
+        match self {
+            TileType::Floor => Color::from_rgba(60, 60, 80, 255),
+            TileType::Wall => Color::from_rgba(100, 80, 60, 255),
+            TileType::Sand => Color::from_rgba(194, 178, 128, 255),
+            TileType::Water => Color::from_rgba(64, 104, 164, 255),
+            TileType::Lava => Color::from_rgba(207, 87, 60, 255),
+            TileType::Pit => Color::from_rgba(20, 20, 30, 255),
+            TileType::DoorPlayer => Color::from_rgba(60, 120, 60, 255),
+            TileType::DoorBot => Color::from_rgba(120, 60, 60, 255),
+            TileType::DoorBoth => Color::from_rgba(120, 100, 60, 255),
+            TileType::Crate => Color::from_rgba(139, 90, 43, 255),
+            TileType::WallDestructible => Color::from_rgba(120, 100, 80, 255),
+            TileType::Scorched => Color::from_rgba(40, 34, 34, 255),
+            TileType::PressurePlate => Color::from_rgba(150, 150, 170, 255),
+            TileType::PlateDoor => Color::from_rgba(80, 110, 150, 255),
+            TileType::DoorOneWay => Color::from_rgba(150, 130, 60, 255),
+            TileType::DoorLocked => Color::from_rgba(170, 170, 190, 255),
         }
     }
 }
 
+/// Non-tile symbol found in a map file, with its tile position
+pub type MapMarker = (char, i32, i32);
+
+#[derive(Clone)]
 pub struct TileMap {
     tiles: Vec<Vec<TileType>>,
-    tile_health: HashMap<(usize, usize), u8>,
+    meta: BTreeMap<(usize, usize), TileMeta>, // Ordered, so every peer walks it alike
+    explored: Vec<Vec<bool>>,
+    lockdown: bool, // Alarm lockdown: shared doors seal against the player
     pub width: usize,
     pub height: usize,
 }
@@ -100,12 +328,80 @@ impl TileMap {
         let tiles = vec![vec![TileType::Floor; width]; height];
         Self {
             tiles,
-            tile_health: HashMap::new(),
+            meta: BTreeMap::new(),
+            explored: vec![vec![false; width]; height],
+            lockdown: false,
             width,
             height,
         }
     }
 
+    /// Build a map from equally wide rows of tile symbols. Any other
+    /// character is a marker: it becomes floor and is returned with its tile.
+    pub fn from_rows(rows: &[&str]) -> Result<(Self, Vec<MapMarker>), String> {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err("empty map".to_string());
+        }
+        let mut map = Self::new(width, rows.len());
+        let mut markers = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("map row {} is not {} tiles wide", y + 1, width));
+            }
+            for (x, symbol) in row.chars().enumerate() {
+                match TileType::from_symbol(symbol) {
+                    Some(tile) => map.set_tile(x, y, tile),
+                    None => markers.push((symbol, x as i32, y as i32)),
+                }
+            }
+        }
+        Ok((map, markers))
+    }
+
+    /// Write the tiles (as map symbols), what has been explored, and the
+    /// metadata of every wired or damaged tile
+    pub fn save(&self, out: &mut SaveWriter) {
+        out.record("map", &[&self.width, &self.height, &self.lockdown]);
+        for (tiles, explored) in self.tiles.iter().zip(&self.explored) {
+            let tiles: String = tiles.iter().map(|tile| tile.symbol()).collect();
+            let explored: String = explored
+                .iter()
+                .map(|&e| if e { '1' } else { '0' })
+                .collect();
+            out.record("row", &[&tiles, &explored]);
+        }
+        for (&(x, y), meta) in &self.meta {
+            out.record("meta", &[&x, &y, meta]);
+        }
+    }
+
+    /// Read back a map written by `save`
+    pub fn restore(save: &mut SaveReader) -> Result<Self, String> {
+        let mut header = save.record("map")?;
+        let (width, height) = header.read()?;
+        let mut map = Self::new(width, height);
+        map.lockdown = header.read()?;
+        for y in 0..height {
+            let mut row = save.record("row")?;
+            let tiles: String = row.read()?;
+            let explored: String = row.read()?;
+            if tiles.chars().count() != width || explored.chars().count() != width {
+                return Err(format!("map row {} is not {} tiles wide", y + 1, width));
+            }
+            for (x, (symbol, seen)) in tiles.chars().zip(explored.chars()).enumerate() {
+                map.tiles[y][x] =
+                    TileType::from_symbol(symbol).ok_or(format!("unknown tile '{symbol}'"))?;
+                map.explored[y][x] = seen == '1';
+            }
+        }
+        for mut meta in save.records("meta") {
+            let pos = meta.read()?;
+            map.meta.insert(pos, meta.read()?);
+        }
+        Ok(map)
+    }
+
     #[allow(dead_code)] // Kept for tests and potential alternative game modes
     pub fn create_random(width: usize, height: usize) -> Self {
         let mut map = Self::new(width, height);
@@ -308,6 +604,13 @@ impl TileMap {
         // Add crates scattered around
         map.add_labyrinth_crates();
 
+        // Add pressure plates with linked doors, traps and loot
+        map.add_pressure_plates();
+
+        // Hide data fragment caches and data chips in secret walls
+        map.add_data_caches();
+        map.add_chip_stashes();
+
         map
     }
 
@@ -512,9 +815,39 @@ impl TileMap {
                 && (south == Some(TileType::Floor) || south == Some(TileType::Sand));
 
             if is_h_corridor || is_v_corridor {
-                let door_type = match rand::gen_range(0, 4) {
+                let door_type = match rand::gen_range(0, 6) {
                     0 => TileType::DoorPlayer,
                     1 => TileType::DoorBot,
+                    2 => {
+                        // One-way doors only go where the corridor loops back,
+                        // so the far side never becomes a trap
+                        let dir = match (is_h_corridor, rand::gen_range(0, 2)) {
+                            (true, 0) => Cardinal::Left,
+                            (true, _) => Cardinal::Right,
+                            (false, 0) => Cardinal::Up,
+                            (false, _) => Cardinal::Down,
+                        };
+                        self.set_one_way_door(x, y, dir);
+                        if self.one_way_doors_loop_back() {
+                            added += 1;
+                            continue;
+                        }
+                        TileType::DoorBoth
+                    }
+                    3 => {
+                        // Locked doors are shortcuts too: the corridor must
+                        // loop back so no keycard is ever needed to get around
+                        let (a, b) = Self::corridor_ends(x, y, is_h_corridor);
+                        self.set_locked_door(x, y, KeycardColor::random());
+                        if self.is_connected(a, b)
+                            && self.is_connected(b, a)
+                            && self.one_way_doors_loop_back()
+                        {
+                            added += 1;
+                            continue;
+                        }
+                        TileType::DoorBoth
+                    }
                     _ => TileType::DoorBoth, // More common
                 };
                 self.set_tile(x, y, door_type);
@@ -523,6 +856,306 @@ impl TileMap {
         }
     }
 
+    /// The tiles on either side of a corridor tile
+    fn corridor_ends(x: usize, y: usize, horizontal: bool) -> ((i32, i32), (i32, i32)) {
+        let (x, y) = (x as i32, y as i32);
+        if horizontal {
+            ((x - 1, y), (x + 1, y))
+        } else {
+            ((x, y - 1), (x, y + 1))
+        }
+    }
+
+    /// Whether the far side of every one-way door can still walk back round
+    /// to the near side
+    fn one_way_doors_loop_back(&self) -> bool {
+        self.meta
+            .iter()
+            .filter(|&(&(x, y), _)| self.get_tile(x, y) == Some(TileType::DoorOneWay))
+            .filter_map(|(&(x, y), meta)| Some(((x as i32, y as i32), meta.direction?.delta())))
+            .all(|((x, y), (dx, dy))| self.is_connected((x + dx, y + dy), (x - dx, y - dy)))
+    }
+
+    /// Add pressure plates. Plate doors are only placed where the corridor has
+    /// an alternative route, so they act as shortcuts and never seal off areas.
+    fn add_pressure_plates(&mut self) {
+        let num_plates = (self.width * self.height) / 600;
+        let mut added = 0;
+        let mut attempts = 0;
+
+        while added < num_plates && attempts < num_plates * 50 {
+            attempts += 1;
+            let x = rand::gen_range(2, self.width - 2);
+            let y = rand::gen_range(2, self.height - 2);
+            if self.get_tile(x, y) != Some(TileType::Floor) {
+                continue;
+            }
+
+            let neighbors = self.walkable_neighbors(x, y);
+            let placed = match rand::gen_range(0, 3) {
+                0 => self.try_add_plate_door(x, y),
+                // Traps go in open areas where bots can be lured across them
+                1 if neighbors.len() >= 3 => {
+                    self.add_plate(x, y, PlateEffect::Trap, (x, y));
+                    true
+                }
+                // Loot plates reward exploring dead ends
+                2 if neighbors.len() == 1 => {
+                    self.add_plate(x, y, PlateEffect::SpawnLoot, (x, y));
+                    true
+                }
+                _ => false,
+            };
+            if placed {
+                added += 1;
+            }
+        }
+    }
+
+    /// Mark random destructible walls as hidden data caches
+    fn add_data_caches(&mut self) {
+        self.flag_random_walls(flags::CACHE, NUM_DATA_CACHES);
+    }
+
+    /// Set `flag` on up to `count` distinct destructible walls that carry no
+    /// hidden flag yet
+    fn flag_random_walls(&mut self, flag: u32, count: usize) {
+        let mut walls: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                self.get_tile(x, y) == Some(TileType::WallDestructible)
+                    && !self.has_flag(x, y, flags::CACHE | flags::CHIP)
+            })
+            .collect();
+        for _ in 0..count.min(walls.len()) {
+            let (x, y) = walls.swap_remove(rand::gen_range(0, walls.len()));
+            self.meta_mut(x, y).set_flag(flag, true);
+        }
+    }
+
+    /// Whether the tile at (x, y) hides a data cache
+    pub fn has_cache_at(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && self.has_flag(x as usize, y as usize, flags::CACHE)
+    }
+
+    /// Mark a few destructible walls (other than data caches) as chip stashes
+    fn add_chip_stashes(&mut self) {
+        self.flag_random_walls(flags::CHIP, NUM_CHIP_STASHES);
+    }
+
+    /// Whether the tile at (x, y) hides a data chip
+    pub fn has_chip_at(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && self.has_flag(x as usize, y as usize, flags::CHIP)
+    }
+
+    /// Number of walls still hiding a data chip
+    pub fn chip_stash_count(&self) -> usize {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.has_flag(x, y, flags::CHIP))
+            .count()
+    }
+
+    /// Floor tiles with a single walkable neighbor
+    pub fn dead_ends(&self) -> Vec<(i32, i32)> {
+        (1..self.height - 1)
+            .flat_map(|y| (1..self.width - 1).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                self.get_tile(x, y) == Some(TileType::Floor)
+                    && self.walkable_neighbors(x, y).len() == 1
+            })
+            .map(|(x, y)| (x as i32, y as i32))
+            .collect()
+    }
+
+    /// Turn the corridor tile at (x, y) into a plate door if the corridor has
+    /// a detour, and place its plate on a floor tile a few steps away.
+    fn try_add_plate_door(&mut self, x: usize, y: usize) -> bool {
+        let neighbors = self.walkable_neighbors(x, y);
+        if neighbors.len() != 2 {
+            return false;
+        }
+
+        self.set_tile(x, y, TileType::PlateDoor);
+        let (a, b) = (neighbors[0], neighbors[1]);
+        if !self.is_connected(a, b) {
+            self.set_tile(x, y, TileType::Floor);
+            return false;
+        }
+
+        for _ in 0..30 {
+            let px = (x as i32 + rand::gen_range(-6, 7)) as usize;
+            let py = (y as i32 + rand::gen_range(-6, 7)) as usize;
+            if (px, py) != (x, y)
+                && px > 0
+                && py > 0
+                && self.get_tile(px, py) == Some(TileType::Floor)
+            {
+                self.add_plate(px, py, PlateEffect::OpenDoor, (x, y));
+                return true;
+            }
+        }
+
+        self.set_tile(x, y, TileType::Floor);
+        false
+    }
+
+    fn add_plate(&mut self, x: usize, y: usize, effect: PlateEffect, link: (usize, usize)) {
+        self.set_tile(x, y, TileType::PressurePlate);
+        let meta = self.meta_mut(x, y);
+        meta.plate = Some(effect);
+        meta.link = Some(link);
+    }
+
+    /// Place a one-way door that can only be entered moving in `dir`
+    pub fn set_one_way_door(&mut self, x: usize, y: usize, dir: Cardinal) {
+        self.set_tile(x, y, TileType::DoorOneWay);
+        self.meta_mut(x, y).direction = Some(dir);
+    }
+
+    /// Place a door that only opens with a keycard of the given color
+    pub fn set_locked_door(&mut self, x: usize, y: usize, color: KeycardColor) {
+        self.set_tile(x, y, TileType::DoorLocked);
+        self.meta_mut(x, y).lock = Some(color);
+    }
+
+    /// Keycard color a locked door at (x, y) needs, if there is one
+    pub fn lock_at(&self, x: i32, y: i32) -> Option<KeycardColor> {
+        if x < 0 || y < 0 || self.get_tile(x as usize, y as usize) != Some(TileType::DoorLocked) {
+            return None;
+        }
+        self.meta(x as usize, y as usize).and_then(|m| m.lock)
+    }
+
+    /// Open a locked door for good; it becomes a regular shared door
+    pub fn unlock_door(&mut self, x: usize, y: usize) {
+        self.set_tile(x, y, TileType::DoorBoth);
+    }
+
+    /// Distinct keycard colors used by the map's locked doors, in map order
+    /// so a seeded map always hands them out the same way
+    pub fn lock_colors(&self) -> Vec<KeycardColor> {
+        let mut locks: Vec<((usize, usize), KeycardColor)> = self
+            .meta
+            .iter()
+            .filter_map(|(&(x, y), meta)| meta.lock.map(|color| ((y, x), color)))
+            .collect();
+        locks.sort_by_key(|&(pos, _)| pos);
+        let mut colors = Vec::new();
+        for (_, color) in locks {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+        colors
+    }
+
+    /// A step a route on foot may take: movement lets the player stumble
+    /// into a pit, but that is a fall, not a way through
+    fn can_walk_into(&self, x: i32, y: i32, dx: i32, dy: i32) -> bool {
+        !self.is_pit_at(x, y) && self.can_enter(x, y, dx, dy, EntityType::Player)
+    }
+
+    fn walkable_neighbors(&self, x: usize, y: usize) -> Vec<(i32, i32)> {
+        [(-1, 0), (1, 0), (0, -1_i32), (0, 1)]
+            .iter()
+            .map(|(dx, dy)| (x as i32 + dx, y as i32 + dy))
+            .filter(|&(nx, ny)| self.is_walkable_by(nx, ny, EntityType::Player))
+            .collect()
+    }
+
+    /// Bounded BFS check whether `to` can be reached from `from` on foot
+    /// (without falling through a pit)
+    fn is_connected(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        const MAX_SEARCH: usize = 2000;
+        let mut visited = std::collections::HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(from);
+        queue.push_back(from);
+
+        while let Some((cx, cy)) = queue.pop_front() {
+            if (cx, cy) == to {
+                return true;
+            }
+            if visited.len() > MAX_SEARCH {
+                break;
+            }
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let next = (cx + dx, cy + dy);
+                if self.can_walk_into(next.0, next.1, dx, dy) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Update pressure plates from the tiles entities currently occupy. Plate
+    /// doors are opened and closed here; returns the one-shot effects (traps,
+    /// loot) triggered by plates pressed this frame, keyed by target tile.
+    pub fn update_plates(
+        &mut self,
+        dt: f32,
+        occupied: &[(i32, i32)],
+    ) -> Vec<((usize, usize), PlateEffect)> {
+        let is_occupied = |x: usize, y: usize| occupied.contains(&(x as i32, y as i32));
+        let mut triggered = Vec::new();
+        let mut held_doors = Vec::new();
+
+        for (&(x, y), meta) in &mut self.meta {
+            let Some(effect) = meta.plate else {
+                continue;
+            };
+            let pressed = is_occupied(x, y);
+            let just_pressed = pressed && !meta.has_flag(flags::PRESSED);
+            meta.set_flag(flags::PRESSED, pressed);
+            let target = meta.link.unwrap_or((x, y));
+
+            match effect {
+                PlateEffect::OpenDoor if pressed => held_doors.push(target),
+                PlateEffect::Trap if just_pressed => triggered.push((target, effect)),
+                PlateEffect::SpawnLoot if just_pressed && !meta.has_flag(flags::USED) => {
+                    meta.set_flag(flags::USED, true);
+                    triggered.push(((x, y), effect));
+                }
+                _ => {}
+            }
+        }
+
+        // Doors stay open while any linked plate is held or something stands
+        // in the doorway (so nobody gets sealed inside), then close after a delay
+        for (&(x, y), meta) in &mut self.meta {
+            if self.tiles[y][x] != TileType::PlateDoor {
+                continue;
+            }
+            if held_doors.contains(&(x, y)) || is_occupied(x, y) {
+                meta.set_flag(flags::OPEN, true);
+                meta.timer = PLATE_DOOR_CLOSE_DELAY;
+            } else if meta.has_flag(flags::OPEN) {
+                meta.timer -= dt;
+                if meta.timer <= 0.0 {
+                    meta.set_flag(flags::OPEN, false);
+                }
+            }
+        }
+
+        triggered
+    }
+
+    /// Metadata for a tile, if it carries any
+    pub fn meta(&self, x: usize, y: usize) -> Option<&TileMeta> {
+        self.meta.get(&(x, y))
+    }
+
+    /// Metadata for a tile, created empty on first access
+    fn meta_mut(&mut self, x: usize, y: usize) -> &mut TileMeta {
+        self.meta.entry((x, y)).or_default()
+    }
+
+    fn has_flag(&self, x: usize, y: usize, flag: u32) -> bool {
+        self.meta(x, y).is_some_and(|m| m.has_flag(flag))
+    }
+
     /// Add crates scattered in floor areas of the labyrinth.
     fn add_labyrinth_crates(&mut self) {
         let num_crates = (self.width * self.height) / 80;
@@ -573,11 +1206,17 @@ impl TileMap {
     pub fn set_tile(&mut self, x: usize, y: usize, tile: TileType) {
         if y < self.height && x < self.width {
             self.tiles[y][x] = tile;
-            // Initialize health for destructible tiles
-            if tile.is_destructible() {
-                self.tile_health.insert((x, y), tile.max_health());
-            } else {
-                self.tile_health.remove(&(x, y));
+            // Replacing a tile discards its metadata; destructibles start at
+            // full health and plate doors need an entry for their open state
+            self.meta.remove(&(x, y));
+            if tile.is_destructible() || tile == TileType::PlateDoor {
+                self.meta.insert(
+                    (x, y),
+                    TileMeta {
+                        health: tile.max_health(),
+                        ..Default::default()
+                    },
+                );
             }
         }
     }
@@ -587,10 +1226,63 @@ impl TileMap {
             return false;
         }
         self.get_tile(x as usize, y as usize)
-            .map(|t| t.is_walkable_by(entity_type))
+            .map(|t| {
+                (t.is_walkable_by(entity_type) && !self.is_sealed(t, entity_type))
+                    || self.is_open_door(t, x, y)
+            })
             .unwrap_or(false)
     }
 
+    pub fn set_lockdown(&mut self, lockdown: bool) {
+        self.lockdown = lockdown;
+    }
+
+    /// Shared doors close to the player during a lockdown
+    fn is_sealed(&self, tile: TileType, entity_type: EntityType) -> bool {
+        self.lockdown && tile == TileType::DoorBoth && entity_type == EntityType::Player
+    }
+
+    /// Plate doors (and hacked bot doors) become passable to everything while open
+    fn is_open_door(&self, tile: TileType, x: i32, y: i32) -> bool {
+        matches!(tile, TileType::PlateDoor | TileType::DoorBot)
+            && self.has_flag(x as usize, y as usize, flags::OPEN)
+    }
+
+    /// Force a door open for good (hacked bot doors)
+    pub fn open_door(&mut self, x: usize, y: usize) {
+        self.meta_mut(x, y).set_flag(flags::OPEN, true);
+    }
+
+    /// Whether an entity moving by (dx, dy) may step onto tile (x, y).
+    /// Use this for movement; `is_walkable_by` ignores the approach direction.
+    /// One-way doors only admit movement in their passage direction, and the
+    /// player may walk into pits (and fall).
+    pub fn can_enter(&self, x: i32, y: i32, dx: i32, dy: i32, entity_type: EntityType) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        match self.get_tile(x as usize, y as usize) {
+            Some(TileType::DoorOneWay) => self
+                .meta(x as usize, y as usize)
+                .and_then(|m| m.direction)
+                .is_some_and(|dir| dir.delta() == (dx, dy)),
+            Some(TileType::Pit) => entity_type == EntityType::Player,
+            Some(_) => self.is_walkable_by(x, y, entity_type),
+            None => false,
+        }
+    }
+
+    /// Whether an entity standing on (x, y) may step by (dx, dy). Diagonal
+    /// steps also need both tiles they pass between to be enterable, so
+    /// nothing cuts a wall corner.
+    pub fn can_step(&self, x: i32, y: i32, dx: i32, dy: i32, entity_type: EntityType) -> bool {
+        self.can_enter(x + dx, y + dy, dx, dy, entity_type)
+            && (dx == 0
+                || dy == 0
+                || (self.can_enter(x + dx, y, dx, 0, entity_type)
+                    && self.can_enter(x, y + dy, 0, dy, entity_type)))
+    }
+
     pub fn get_speed_at(&self, x: i32, y: i32) -> f32 {
         if x < 0 || y < 0 {
             return 1.0;
@@ -605,17 +1297,30 @@ impl TileMap {
             return true;
         }
         self.get_tile(x as usize, y as usize)
-            .map(|t| t.blocks_projectile())
+            .map(|t| t.blocks_projectile() && !self.is_open_door(t, x, y))
             .unwrap_or(true)
     }
 
+    /// Whether a straight line between two pixel positions is free of
+    /// projectile-blocking tiles (sampled every quarter tile)
+    pub fn has_line_of_sight(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> bool {
+        let dist = (x1 - x0).hypot(y1 - y0);
+        let steps = (dist / (TILE_SIZE / 4.0)).ceil() as i32;
+        (1..steps).all(|i| {
+            let t = i as f32 / steps as f32;
+            let x = x0 + (x1 - x0) * t;
+            let y = y0 + (y1 - y0) * t;
+            !self.blocks_projectile_at((x / TILE_SIZE) as i32, (y / TILE_SIZE) as i32)
+        })
+    }
+
     pub fn damage_tile(&mut self, x: usize, y: usize) -> bool {
         if let Some(tile) = self.get_tile(x, y)
             && tile.is_destructible()
-            && let Some(health) = self.tile_health.get_mut(&(x, y))
+            && let Some(meta) = self.meta.get_mut(&(x, y))
         {
-            *health = health.saturating_sub(1);
-            if *health == 0 {
+            meta.health = meta.health.saturating_sub(1);
+            if meta.health == 0 {
                 self.set_tile(x, y, TileType::Floor);
                 return true;
             }
@@ -623,6 +1328,15 @@ impl TileMap {
         false
     }
 
+    /// Pick up the crate at (x, y), leaving floor behind
+    pub fn lift_crate(&mut self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || self.get_tile(x as usize, y as usize) != Some(TileType::Crate) {
+            return false;
+        }
+        self.set_tile(x as usize, y as usize, TileType::Floor);
+        true
+    }
+
     pub fn is_destructible_at(&self, x: i32, y: i32) -> bool {
         if x < 0 || y < 0 {
             return false;
@@ -632,6 +1346,43 @@ impl TileMap {
             .unwrap_or(false)
     }
 
+    pub fn is_pit_at(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        self.get_tile(x as usize, y as usize) == Some(TileType::Pit)
+    }
+
+    /// Nearest tile (by BFS over the grid) the entity can stand on, used to
+    /// put entities back on solid ground after falling into a pit.
+    pub fn nearest_walkable(&self, x: i32, y: i32, entity_type: EntityType) -> Option<(i32, i32)> {
+        const MAX_SEARCH: usize = 2000;
+        let mut visited = std::collections::HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert((x, y));
+        queue.push_back((x, y));
+
+        while let Some((cx, cy)) = queue.pop_front() {
+            if self.is_walkable_by(cx, cy, entity_type) {
+                return Some((cx, cy));
+            }
+            if visited.len() > MAX_SEARCH {
+                break;
+            }
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let next = (cx + dx, cy + dy);
+                let in_bounds = next.0 >= 0
+                    && next.1 >= 0
+                    && (next.0 as usize) < self.width
+                    && (next.1 as usize) < self.height;
+                if in_bounds && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
     pub fn is_lava_at(&self, x: i32, y: i32) -> bool {
         if x < 0 || y < 0 {
             return false;
@@ -639,26 +1390,253 @@ impl TileMap {
         self.get_tile(x as usize, y as usize) == Some(TileType::Lava)
     }
 
-    pub fn draw(&self, camera_x: f32, camera_y: f32, sprites: &SpriteSheet) {
+    /// Reshape terrain after an explosion: floor within `radius` tiles becomes
+    /// scorched, and tiles near the center occasionally collapse into pits.
+    pub fn scorch(&mut self, cx: i32, cy: i32, radius: f32) {
+        let r = radius.ceil() as i32;
+        for y in (cy - r)..=(cy + r) {
+            for x in (cx - r)..=(cx + r) {
+                if x < 0 || y < 0 {
+                    continue;
+                }
+                let dist = (((x - cx).pow(2) + (y - cy).pow(2)) as f32).sqrt();
+                if dist > radius {
+                    continue;
+                }
+                let (ux, uy) = (x as usize, y as usize);
+                match self.get_tile(ux, uy) {
+                    Some(TileType::Floor | TileType::Scorched)
+                        if dist <= 1.0 && rand::gen_range(0.0, 1.0) < CRATER_CHANCE =>
+                    {
+                        self.set_tile(ux, uy, TileType::Pit);
+                    }
+                    Some(TileType::Floor | TileType::Sand) => {
+                        self.set_tile(ux, uy, TileType::Scorched);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Mark the square around (x, y) as explored, returning how many tiles
+    /// were new
+    pub fn reveal_around(&mut self, x: i32, y: i32, radius: i32) -> u32 {
+        let mut revealed = 0;
+        for ty in (y - radius).max(0)..=(y + radius).min(self.height as i32 - 1) {
+            for tx in (x - radius).max(0)..=(x + radius).min(self.width as i32 - 1) {
+                let explored = &mut self.explored[ty as usize][tx as usize];
+                if !*explored {
+                    *explored = true;
+                    revealed += 1;
+                }
+            }
+        }
+        revealed
+    }
+
+    /// How many tiles in the square around (x, y) a shot could fly through;
+    /// off the map counts as solid
+    pub fn open_tiles_around(&self, x: i32, y: i32, radius: i32) -> usize {
+        let mut open = 0;
+        for ty in y - radius..=y + radius {
+            for tx in x - radius..=x + radius {
+                if !self.blocks_projectile_at(tx, ty) {
+                    open += 1;
+                }
+            }
+        }
+        open
+    }
+
+    pub fn reveal_all(&mut self) {
+        for row in &mut self.explored {
+            row.fill(true);
+        }
+    }
+
+    pub fn is_explored(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        self.explored
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Draw explored tiles as flat colored cells, `cell` pixels per tile
+    pub fn draw_overview(&self, origin_x: f32, origin_y: f32, cell: f32) {
         for (y, row) in self.tiles.iter().enumerate() {
             for (x, &tile) in row.iter().enumerate() {
+                if !self.explored[y][x] {
+                    continue;
+                }
+                draw_rectangle(
+                    origin_x + x as f32 * cell,
+                    origin_y + y as f32 * cell,
+                    cell,
+                    cell,
+                    tile.overview_color(),
+                );
+            }
+        }
+    }
+
+    /// Tile column and row ranges that intersect a view of the given size at the camera position
+    pub fn visible_range(
+        &self,
+        camera_x: f32,
+        camera_y: f32,
+        view_w: f32,
+        view_h: f32,
+    ) -> (Range<usize>, Range<usize>) {
+        let to_range = |start: f32, len: f32, max: usize| {
+            let first = (start / TILE_SIZE).floor().max(0.0) as usize;
+            let last = ((start + len) / TILE_SIZE).ceil().max(0.0) as usize;
+            first.min(max)..last.min(max)
+        };
+        (
+            to_range(camera_x, view_w, self.width),
+            to_range(camera_y, view_h, self.height),
+        )
+    }
+
+    /// Draw a passage arrow centered on a tile at the given screen position
+    fn draw_arrow(screen_x: f32, screen_y: f32, dir: Cardinal) {
+        let cx = screen_x + TILE_SIZE / 2.0;
+        let cy = screen_y + TILE_SIZE / 2.0;
+        let angle = dir.angle();
+        let point = |dist: f32, offset: f32| {
+            let a = angle + offset;
+            Vec2::new(cx + a.cos() * dist, cy + a.sin() * dist)
+        };
+        let color = Color::from_rgba(255, 230, 120, 230);
+        draw_triangle(point(10.0, 0.0), point(9.0, 2.4), point(9.0, -2.4), color);
+        let tail = point(8.0, std::f32::consts::PI);
+        draw_line(tail.x, tail.y, cx, cy, 3.0, color);
+    }
+
+    /// Draw the tiles inside a view of the given size at the camera position
+    pub fn draw(
+        &self,
+        camera_x: f32,
+        camera_y: f32,
+        view_w: f32,
+        view_h: f32,
+        sprites: &SpriteSheet,
+    ) {
+        let (cols, rows) = self.visible_range(camera_x, camera_y, view_w, view_h);
+        for y in rows {
+            let row = &self.tiles[y];
+            for x in cols.clone() {
+                let tile = row[x];
                 let screen_x = x as f32 * TILE_SIZE - camera_x;
                 let screen_y = y as f32 * TILE_SIZE - camera_y;
                 let sprite_idx = tile.sprite_index();
 
-                // Show damage on destructible tiles
-                if tile.is_destructible()
-                    && let Some(&health) = self.tile_health.get(&(x, y))
-                {
-                    let max = tile.max_health();
-                    if health < max {
-                        let damage_factor = 1.0 - (health as f32 / max as f32);
+                let meta = self.meta(x, y);
+
+                match tile {
+                    // Show damage on destructible tiles
+                    _ if tile.is_destructible()
+                        && let Some(health) = meta.map(|m| m.health)
+                        && health < tile.max_health() =>
+                    {
+                        let damage_factor = 1.0 - (health as f32 / tile.max_health() as f32);
                         sprites.draw_tile_damaged(sprite_idx, screen_x, screen_y, damage_factor);
-                    } else {
+                    }
+                    TileType::Scorched => {
+                        sprites.draw_tile_tinted(
+                            sprite_idx,
+                            screen_x,
+                            screen_y,
+                            Color::from_rgba(90, 75, 70, 255),
+                        );
+                    }
+                    TileType::PlateDoor | TileType::DoorBot
+                        if meta.is_some_and(|m| m.has_flag(flags::OPEN)) =>
+                    {
+                        sprites.draw_tile(tiles::FLOOR, screen_x, screen_y);
+                        draw_rectangle_lines(
+                            screen_x + 1.0,
+                            screen_y + 1.0,
+                            TILE_SIZE - 2.0,
+                            TILE_SIZE - 2.0,
+                            2.0,
+                            Color::from_rgba(80, 110, 150, 255),
+                        );
+                    }
+                    TileType::PlateDoor => {
+                        sprites.draw_tile_tinted(
+                            sprite_idx,
+                            screen_x,
+                            screen_y,
+                            Color::from_rgba(140, 170, 220, 255),
+                        );
+                    }
+                    TileType::DoorBoth if self.lockdown => {
+                        sprites.draw_tile_tinted(
+                            sprite_idx,
+                            screen_x,
+                            screen_y,
+                            Color::from_rgba(255, 120, 120, 255),
+                        );
+                        for i in 1..4 {
+                            let bar_y = screen_y + i as f32 * TILE_SIZE / 4.0;
+                            draw_line(
+                                screen_x + 2.0,
+                                bar_y,
+                                screen_x + TILE_SIZE - 2.0,
+                                bar_y,
+                                2.0,
+                                Color::from_rgba(200, 30, 30, 255),
+                            );
+                        }
+                    }
+                    TileType::DoorOneWay => {
                         sprites.draw_tile(sprite_idx, screen_x, screen_y);
+                        if let Some(dir) = meta.and_then(|m| m.direction) {
+                            Self::draw_arrow(screen_x, screen_y, dir);
+                        }
                     }
-                } else {
-                    sprites.draw_tile(sprite_idx, screen_x, screen_y);
+                    TileType::DoorLocked => {
+                        let color = meta.and_then(|m| m.lock).unwrap_or(KeycardColor::Yellow);
+                        let c = color.color();
+                        sprites.draw_tile_tinted(
+                            sprite_idx,
+                            screen_x,
+                            screen_y,
+                            Color::new(0.5 + c.r * 0.5, 0.5 + c.g * 0.5, 0.5 + c.b * 0.5, 1.0),
+                        );
+                        draw_keycard(
+                            screen_x + TILE_SIZE / 2.0,
+                            screen_y + TILE_SIZE / 2.0,
+                            color,
+                        );
+                    }
+                    TileType::PressurePlate => {
+                        sprites.draw_tile(sprite_idx, screen_x, screen_y);
+                        let pressed = meta.is_some_and(|m| m.has_flag(flags::PRESSED));
+                        let inset = if pressed { 8.0 } else { 6.0 };
+                        draw_rectangle(
+                            screen_x + inset,
+                            screen_y + inset,
+                            TILE_SIZE - inset * 2.0,
+                            TILE_SIZE - inset * 2.0,
+                            Color::from_rgba(110, 110, 130, 255),
+                        );
+                        draw_rectangle_lines(
+                            screen_x + inset,
+                            screen_y + inset,
+                            TILE_SIZE - inset * 2.0,
+                            TILE_SIZE - inset * 2.0,
+                            1.0,
+                            Color::from_rgba(170, 170, 190, 255),
+                        );
+                    }
+                    _ => sprites.draw_tile(sprite_idx, screen_x, screen_y),
                 }
             }
         }
@@ -690,6 +1668,79 @@ mod tests {
         assert!(TileType::DoorBoth.is_walkable_by(EntityType::Bot));
     }
 
+    #[test]
+    fn test_one_way_door() {
+        let mut map = TileMap::new(10, 10);
+        map.set_one_way_door(3, 3, Cardinal::Right);
+        assert!(map.can_enter(3, 3, 1, 0, EntityType::Player));
+        assert!(map.can_enter(3, 3, 1, 0, EntityType::Bot));
+        assert!(!map.can_enter(3, 3, -1, 0, EntityType::Player));
+        assert!(!map.can_enter(3, 3, 0, 1, EntityType::Player));
+        assert!(map.blocks_projectile_at(3, 3));
+
+        map.set_one_way_door(5, 5, Cardinal::Up);
+        assert!(map.can_enter(5, 5, 0, -1, EntityType::Player));
+        assert!(!map.can_enter(5, 5, 0, 1, EntityType::Player));
+        // Ordinary tiles ignore the approach direction
+        assert!(map.can_enter(4, 4, 0, 1, EntityType::Bot));
+    }
+
+    #[test]
+    fn test_pit_entry() {
+        // Player can stumble into pits, bots steer around them
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(5, 5, TileType::Pit);
+        assert!(map.can_enter(5, 5, 1, 0, EntityType::Player));
+        assert!(!map.can_enter(5, 5, 1, 0, EntityType::Bot));
+        // Pits are never a valid place to stand
+        assert!(!TileType::Pit.is_walkable_by(EntityType::Player));
+    }
+
+    #[test]
+    fn test_one_way_doors_loop_back() {
+        let (mut map, _) =
+            TileMap::from_rows(&["#######", "#.....#", "#.###.#", "#.....#", "#######"]).unwrap();
+        map.set_one_way_door(3, 1, Cardinal::Right);
+        assert!(map.one_way_doors_loop_back());
+        // A second door against the first one's way back traps its far side
+        map.set_one_way_door(3, 3, Cardinal::Right);
+        assert!(!map.one_way_doors_loop_back());
+        map.set_one_way_door(3, 3, Cardinal::Left);
+        assert!(map.one_way_doors_loop_back());
+    }
+
+    #[test]
+    fn test_pits_dont_connect() {
+        let (mut map, _) = TileMap::from_rows(&["#####", "#.O.#", "#####"]).unwrap();
+        assert!(!map.is_connected((1, 1), (3, 1)));
+        map.set_tile(2, 1, TileType::Floor);
+        assert!(map.is_connected((1, 1), (3, 1)));
+    }
+
+    #[test]
+    fn test_diagonal_steps_dont_cut_corners() {
+        let mut map = TileMap::new(10, 10);
+        assert!(map.can_step(5, 5, 1, 1, EntityType::Player));
+        map.set_tile(6, 5, TileType::Wall);
+        assert!(!map.can_step(5, 5, 1, 1, EntityType::Player));
+        assert!(map.can_step(5, 5, 0, 1, EntityType::Player));
+        assert!(map.can_step(5, 5, -1, 1, EntityType::Player));
+    }
+
+    #[test]
+    fn test_nearest_walkable() {
+        let mut map = TileMap::new(10, 10);
+        for x in 3..=7 {
+            for y in 3..=7 {
+                map.set_tile(x, y, TileType::Pit);
+            }
+        }
+        let (x, y) = map.nearest_walkable(5, 5, EntityType::Player).unwrap();
+        assert_eq!((x - 5).abs() + (y - 5).abs(), 3);
+        assert!(map.is_walkable_by(x, y, EntityType::Player));
+        assert_eq!(map.nearest_walkable(1, 1, EntityType::Player), Some((1, 1)));
+    }
+
     #[test]
     fn test_speed_multipliers() {
         assert_eq!(TileType::Floor.speed_multiplier(), 1.0);
@@ -717,6 +1768,14 @@ mod tests {
         let destroyed = map.damage_tile(5, 5);
         assert!(destroyed);
         assert_eq!(map.get_tile(5, 5), Some(TileType::Floor));
+
+        // Only crates can be lifted
+        map.set_tile(5, 5, TileType::Crate);
+        map.set_tile(6, 5, TileType::WallDestructible);
+        assert!(!map.lift_crate(6, 5));
+        assert!(map.lift_crate(5, 5));
+        assert!(map.is_walkable_by(5, 5, EntityType::Player));
+        assert!(!map.lift_crate(5, 5));
     }
 
     #[test]
@@ -741,6 +1800,324 @@ mod tests {
         assert!(!map.is_walkable_by(19, 14, EntityType::Player));
     }
 
+    #[test]
+    fn test_scorch() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(5, 7, TileType::Wall);
+        map.set_tile(7, 5, TileType::Water);
+        map.scorch(5, 5, 2.0);
+
+        // Center tiles are either scorched or cratered
+        for (x, y) in [(5, 5), (4, 5), (5, 4)] {
+            let tile = map.get_tile(x, y);
+            assert!(tile == Some(TileType::Scorched) || tile == Some(TileType::Pit));
+        }
+        // Outer ring is scorched but never cratered
+        assert_eq!(map.get_tile(3, 5), Some(TileType::Scorched));
+        // Non-floor tiles and tiles outside the radius are untouched
+        assert_eq!(map.get_tile(5, 7), Some(TileType::Wall));
+        assert_eq!(map.get_tile(7, 5), Some(TileType::Water));
+        assert_eq!(map.get_tile(7, 7), Some(TileType::Floor));
+        assert!(TileType::Scorched.is_walkable_by(EntityType::Bot));
+    }
+
+    #[test]
+    fn test_plate_door() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(5, 5, TileType::PlateDoor);
+        map.add_plate(2, 2, PlateEffect::OpenDoor, (5, 5));
+        assert!(!map.is_walkable_by(5, 5, EntityType::Player));
+        assert!(map.blocks_projectile_at(5, 5));
+
+        // Standing on the plate opens the door
+        assert!(map.update_plates(0.1, &[(2, 2)]).is_empty());
+        assert!(map.is_walkable_by(5, 5, EntityType::Player));
+        assert!(!map.blocks_projectile_at(5, 5));
+
+        // Door stays open while occupied even after leaving the plate
+        map.update_plates(1.0, &[(5, 5)]);
+        assert!(map.is_walkable_by(5, 5, EntityType::Bot));
+
+        // Door lingers briefly once plate and doorway are clear, then closes
+        map.update_plates(0.1, &[]);
+        assert!(map.is_walkable_by(5, 5, EntityType::Bot));
+        map.update_plates(1.0, &[]);
+        assert!(!map.is_walkable_by(5, 5, EntityType::Bot));
+    }
+
+    #[test]
+    fn test_plate_triggers() {
+        let mut map = TileMap::new(10, 10);
+        map.add_plate(3, 3, PlateEffect::Trap, (4, 3));
+        map.add_plate(6, 6, PlateEffect::SpawnLoot, (6, 6));
+
+        // Trap fires at its linked tile on each new press, not while held
+        assert_eq!(
+            map.update_plates(0.1, &[(3, 3)]),
+            vec![((4, 3), PlateEffect::Trap)]
+        );
+        assert!(map.update_plates(0.1, &[(3, 3)]).is_empty());
+        map.update_plates(0.1, &[]);
+        assert_eq!(map.update_plates(0.1, &[(3, 3)]).len(), 1);
+
+        // Loot only drops once
+        let triggered = map.update_plates(0.1, &[(6, 6)]);
+        assert_eq!(triggered, vec![((6, 6), PlateEffect::SpawnLoot)]);
+        map.update_plates(0.1, &[]);
+        assert!(map.update_plates(0.1, &[(6, 6)]).is_empty());
+    }
+
+    #[test]
+    fn test_tile_meta() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(2, 2, TileType::WallDestructible);
+        assert_eq!(map.meta(2, 2).map(|m| m.health), Some(3));
+        assert!(map.meta(3, 3).is_none());
+
+        // Replacing a tile clears its metadata
+        map.set_one_way_door(2, 2, Cardinal::Left);
+        let meta = map.meta(2, 2).unwrap();
+        assert_eq!(meta.health, 0);
+        assert_eq!(meta.direction, Some(Cardinal::Left));
+        map.set_tile(2, 2, TileType::Floor);
+        assert!(map.meta(2, 2).is_none());
+
+        let mut meta = TileMeta::default();
+        meta.set_flag(flags::PRESSED | flags::USED, true);
+        meta.set_flag(flags::PRESSED, false);
+        assert!(!meta.has_flag(flags::PRESSED));
+        assert!(meta.has_flag(flags::USED));
+    }
+
+    #[test]
+    fn test_locked_door() {
+        let mut map = TileMap::new(5, 5);
+        map.set_locked_door(2, 2, KeycardColor::Blue);
+        assert_eq!(map.lock_at(2, 2), Some(KeycardColor::Blue));
+        assert_eq!(map.lock_colors(), vec![KeycardColor::Blue]);
+        assert!(!map.is_walkable_by(2, 2, EntityType::Player));
+        assert!(!map.is_walkable_by(2, 2, EntityType::Bot));
+        assert!(map.blocks_projectile_at(2, 2));
+
+        map.unlock_door(2, 2);
+        assert_eq!(map.lock_at(2, 2), None);
+        assert!(map.lock_colors().is_empty());
+        assert!(map.is_walkable_by(2, 2, EntityType::Player));
+        assert!(map.is_walkable_by(2, 2, EntityType::Bot));
+    }
+
+    #[test]
+    fn test_hacked_bot_door() {
+        let mut map = TileMap::new(5, 5);
+        map.set_tile(2, 2, TileType::DoorBot);
+        assert!(!map.is_walkable_by(2, 2, EntityType::Player));
+        assert!(map.blocks_projectile_at(2, 2));
+
+        map.open_door(2, 2);
+        assert!(map.is_walkable_by(2, 2, EntityType::Player));
+        assert!(map.is_walkable_by(2, 2, EntityType::Bot));
+        assert!(!map.blocks_projectile_at(2, 2));
+    }
+
+    #[test]
+    fn test_lockdown_seals_shared_doors() {
+        let mut map = TileMap::new(5, 5);
+        map.set_tile(2, 2, TileType::DoorBoth);
+        map.set_tile(3, 2, TileType::DoorPlayer);
+
+        map.set_lockdown(true);
+        assert!(!map.is_walkable_by(2, 2, EntityType::Player));
+        assert!(map.is_walkable_by(2, 2, EntityType::Bot));
+        assert!(map.is_walkable_by(3, 2, EntityType::Player));
+
+        map.set_lockdown(false);
+        assert!(map.is_walkable_by(2, 2, EntityType::Player));
+    }
+
+    #[test]
+    fn test_data_caches_hidden_in_destructible_walls() {
+        let map = TileMap::create_labyrinth(60, 45);
+        let mut caches = 0;
+        for y in 0..map.height {
+            for x in 0..map.width {
+                if map.has_cache_at(x as i32, y as i32) {
+                    caches += 1;
+                    assert_eq!(map.get_tile(x, y), Some(TileType::WallDestructible));
+                }
+            }
+        }
+        assert!(caches > 0 && caches <= NUM_DATA_CACHES);
+        assert!(!map.has_cache_at(-1, 0));
+
+        // Caches land on distinct walls, so exactly as many walls as caches
+        // are all picked
+        let mut map = TileMap::new(NUM_DATA_CACHES, 1);
+        for x in 0..NUM_DATA_CACHES {
+            map.set_tile(x, 0, TileType::WallDestructible);
+        }
+        map.add_data_caches();
+        assert!((0..NUM_DATA_CACHES as i32).all(|x| map.has_cache_at(x, 0)));
+
+        // Chip stashes take the walls the caches left
+        let walls = NUM_DATA_CACHES + NUM_CHIP_STASHES;
+        let mut map = TileMap::new(walls, 1);
+        for x in 0..walls {
+            map.set_tile(x, 0, TileType::WallDestructible);
+        }
+        map.add_data_caches();
+        map.add_chip_stashes();
+        assert!((0..walls as i32).all(|x| map.has_cache_at(x, 0) != map.has_chip_at(x, 0)));
+        assert_eq!(map.chip_stash_count(), NUM_CHIP_STASHES);
+    }
+
+    #[test]
+    fn test_chip_stashes_and_dead_ends() {
+        let map = TileMap::create_labyrinth(60, 45);
+        let stashes = map.chip_stash_count();
+        assert!(stashes > 0 && stashes <= NUM_CHIP_STASHES);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                if map.has_chip_at(x as i32, y as i32) {
+                    assert!(!map.has_cache_at(x as i32, y as i32));
+                    assert_eq!(map.get_tile(x, y), Some(TileType::WallDestructible));
+                }
+            }
+        }
+
+        let mut map = TileMap::new(6, 5);
+        for y in 0..5 {
+            for x in 0..6 {
+                let tile = if y == 2 && (1..5).contains(&x) {
+                    TileType::Floor
+                } else {
+                    TileType::Wall
+                };
+                map.set_tile(x, y, tile);
+            }
+        }
+        assert_eq!(map.dead_ends(), vec![(1, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let mut map = TileMap::new(10, 10);
+        map.set_tile(5, 2, TileType::Wall);
+        map.set_tile(5, 6, TileType::Pit);
+        let center = |x: f32, y: f32| (x * TILE_SIZE + 16.0, y * TILE_SIZE + 16.0);
+
+        let (ax, ay) = center(2.0, 2.0);
+        let (bx, by) = center(8.0, 2.0);
+        assert!(!map.has_line_of_sight(ax, ay, bx, by));
+
+        // Pits don't block sight
+        let (ax, ay) = center(2.0, 6.0);
+        let (bx, by) = center(8.0, 6.0);
+        assert!(map.has_line_of_sight(ax, ay, bx, by));
+    }
+
+    #[test]
+    fn test_reveal_around() {
+        let mut map = TileMap::new(10, 10);
+        assert!(!map.is_explored(5, 5));
+
+        map.reveal_around(5, 5, 2);
+        assert!(map.is_explored(5, 5));
+        assert!(map.is_explored(3, 7));
+        assert!(!map.is_explored(2, 5));
+
+        // Revealing near the edge must not panic or wrap around
+        map.reveal_around(0, 0, 3);
+        assert!(map.is_explored(0, 0));
+        assert!(!map.is_explored(-1, 0));
+    }
+
+    #[test]
+    fn test_open_tiles_around() {
+        let mut map = TileMap::new(10, 10);
+        assert_eq!(map.open_tiles_around(5, 5, 2), 25);
+        for y in 0..10 {
+            map.set_tile(4, y, TileType::Wall);
+        }
+        assert_eq!(map.open_tiles_around(5, 5, 2), 20);
+        // Beyond the edge is as good as a wall
+        assert_eq!(map.open_tiles_around(0, 0, 1), 4);
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let map = TileMap::new(60, 45);
+
+        // Camera at origin sees exactly the tiles covering an 800x600 view
+        let (cols, rows) = map.visible_range(0.0, 0.0, 800.0, 600.0);
+        assert_eq!(cols, 0..25);
+        assert_eq!(rows, 0..19);
+
+        // Partially scrolled camera includes the partially visible edge tiles
+        let (cols, rows) = map.visible_range(40.0, 10.0, 64.0, 64.0);
+        assert_eq!(cols, 1..4);
+        assert_eq!(rows, 0..3);
+
+        // Ranges are clamped to the map
+        let (cols, rows) = map.visible_range(1800.0, 1400.0, 800.0, 600.0);
+        assert_eq!(cols, 56..60);
+        assert_eq!(rows, 43..45);
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let (map, markers) = TileMap::from_rows(&["#####", "#@.L#", "#+T~#", "#####"]).unwrap();
+        assert_eq!((map.width, map.height), (5, 4));
+        assert_eq!(map.get_tile(0, 0), Some(TileType::Wall));
+        assert_eq!(map.get_tile(3, 1), Some(TileType::Lava));
+        assert_eq!(map.get_tile(1, 2), Some(TileType::DoorBoth));
+        // Markers stand on floor
+        assert_eq!(markers, vec![('@', 1, 1), ('T', 2, 2)]);
+        assert_eq!(map.get_tile(2, 2), Some(TileType::Floor));
+
+        assert!(TileMap::from_rows(&["###", "##"]).is_err());
+        assert!(TileMap::from_rows(&[]).is_err());
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut map = TileMap::create_labyrinth(30, 20);
+        map.set_tile(3, 3, TileType::Scorched);
+        map.set_locked_door(4, 3, KeycardColor::Blue);
+        map.reveal_around(5, 5, 2);
+
+        let mut out = SaveWriter::new();
+        map.save(&mut out);
+        let text = out.finish();
+        let loaded = TileMap::restore(&mut SaveReader::new(&text).unwrap()).unwrap();
+
+        assert_eq!(loaded.tiles, map.tiles);
+        assert_eq!(loaded.meta, map.meta);
+        assert_eq!(loaded.explored, map.explored);
+        assert_eq!(loaded.lock_at(4, 3), Some(KeycardColor::Blue));
+
+        // Every tile has a symbol that reads back as the same tile
+        for tile in [
+            TileType::Floor,
+            TileType::Wall,
+            TileType::Sand,
+            TileType::Water,
+            TileType::Lava,
+            TileType::Pit,
+            TileType::DoorPlayer,
+            TileType::DoorBot,
+            TileType::DoorBoth,
+            TileType::Crate,
+            TileType::WallDestructible,
+            TileType::Scorched,
+            TileType::PressurePlate,
+            TileType::PlateDoor,
+            TileType::DoorOneWay,
+            TileType::DoorLocked,
+        ] {
+            assert_eq!(TileType::from_symbol(tile.symbol()), Some(tile));
+        }
+    }
+
     #[test]
     fn test_out_of_bounds() {
         let map = TileMap::new(10, 10);
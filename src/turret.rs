@@ -0,0 +1,144 @@
+use macroquad::prelude::*;
+
+use crate::entity::Bot;
+use crate::projectile::Projectile;
+use crate::save::{Fields, Persist};
+use crate::tile_map::{TILE_SIZE, TileMap};
+
+const TURRET_RANGE: f32 = TILE_SIZE * 6.0;
+const TURRET_FIRE_INTERVAL: f32 = 0.5; // Seconds between shots
+const TURRET_BULLET_SPEED: f32 = 400.0;
+
+/// Allied stationary gun that shoots hostile bots in range.
+/// Deployed on hacked terminals.
+pub struct Turret {
+    pub x: f32, // Pixel coordinates (centered in tile)
+    pub y: f32,
+    cooldown: f32,
+    aim_angle: f32,
+}
+
+impl Turret {
+    pub fn new(tile_x: i32, tile_y: i32) -> Self {
+        Self {
+            x: tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            y: tile_y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            cooldown: 0.0,
+            aim_angle: 0.0,
+        }
+    }
+
+    /// Nearest living hostile bot in range and line of sight, as a pixel position
+    fn find_target(&self, bots: &[Bot], map: &TileMap) -> Option<(f32, f32)> {
+        bots.iter()
+            .filter(|b| b.alive && b.hostile)
+            .map(|b| b.pos.center_pixel())
+            .map(|(bx, by)| (bx, by, (bx - self.x).hypot(by - self.y)))
+            .filter(|&(bx, by, dist)| {
+                dist <= TURRET_RANGE && map.has_line_of_sight(self.x, self.y, bx, by)
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(bx, by, _)| (bx, by))
+    }
+
+    /// Track the nearest hostile bot and return a projectile when firing
+    pub fn update(&mut self, dt: f32, bots: &[Bot], map: &TileMap) -> Option<Projectile> {
+        if self.cooldown > 0.0 {
+            self.cooldown -= dt;
+        }
+
+        let (tx, ty) = self.find_target(bots, map)?;
+        self.aim_angle = (ty - self.y).atan2(tx - self.x);
+
+        if self.cooldown > 0.0 {
+            return None;
+        }
+        self.cooldown = TURRET_FIRE_INTERVAL;
+        Some(
+            Projectile::new_player(
+                self.x,
+                self.y,
+                self.aim_angle.cos(),
+                self.aim_angle.sin(),
+                TURRET_BULLET_SPEED,
+                TURRET_RANGE,
+            )
+            .with_source("Turrets"),
+        )
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let screen_x = self.x - camera_x;
+        let screen_y = self.y - camera_y;
+        let color = Color::from_rgba(100, 200, 255, 255);
+
+        draw_circle(screen_x, screen_y, 7.0, Color::from_rgba(40, 60, 80, 255));
+        draw_circle_lines(screen_x, screen_y, 7.0, 1.5, color);
+        draw_line(
+            screen_x,
+            screen_y,
+            screen_x + self.aim_angle.cos() * 12.0,
+            screen_y + self.aim_angle.sin() * 12.0,
+            3.0,
+            color,
+        );
+    }
+}
+
+impl Persist for Turret {
+    fn write(&self, out: &mut String) {
+        self.x.write(out);
+        self.y.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.read()?,
+            y: fields.read()?,
+            cooldown: 0.0,
+            aim_angle: 0.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turret_targets_nearest_hostile() {
+        let map = TileMap::new(20, 20);
+        let turret = Turret::new(5, 5);
+        let bots = vec![
+            Bot::new(6, 5),          // Closest, but friendly
+            Bot::new_hostile(9, 5),  // In range
+            Bot::new_hostile(7, 7),  // In range and closer
+            Bot::new_hostile(19, 5), // Out of range
+        ];
+
+        let (tx, ty) = turret.find_target(&bots, &map).unwrap();
+        assert_eq!((tx, ty), bots[2].pos.center_pixel());
+    }
+
+    #[test]
+    fn test_turret_needs_line_of_sight() {
+        let mut map = TileMap::new(20, 20);
+        map.set_tile(7, 5, crate::tile_map::TileType::Wall);
+        let mut turret = Turret::new(5, 5);
+        let bots = vec![Bot::new_hostile(9, 5)];
+
+        assert!(turret.find_target(&bots, &map).is_none());
+        assert!(turret.update(0.1, &bots, &map).is_none());
+    }
+
+    #[test]
+    fn test_turret_fire_rate() {
+        let map = TileMap::new(20, 20);
+        let mut turret = Turret::new(5, 5);
+        let bots = vec![Bot::new_hostile(8, 5)];
+
+        assert!(turret.update(0.01, &bots, &map).is_some());
+        assert!(turret.update(0.01, &bots, &map).is_none());
+        assert!(turret.update(TURRET_FIRE_INTERVAL, &bots, &map).is_some());
+    }
+}
@@ -0,0 +1,69 @@
+use std::cell::Cell;
+
+use macroquad::prelude::*;
+
+const BASE_SIZE: (f32, f32) = (800.0, 600.0); // The window the interface is laid out for
+const AUTO_RANGE: (f32, f32) = (0.75, 3.0); // Scales auto-detection picks between
+pub const SCALE_STEP: u32 = 25; // Percent per press on the settings screen
+pub const SCALE_RANGE: (u32, u32) = (50, 300); // Percent the setting can pick
+
+thread_local! {
+    /// Screen pixels per interface pixel this frame
+    static SCALE: Cell<f32> = const { Cell::new(1.0) };
+}
+
+/// Scale that fits the base layout to a screen, in whole 25% steps
+pub fn auto_scale(screen: (f32, f32)) -> f32 {
+    let fit = (screen.0 / BASE_SIZE.0).min(screen.1 / BASE_SIZE.1);
+    ((fit * 4.0).floor() / 4.0).clamp(AUTO_RANGE.0, AUTO_RANGE.1)
+}
+
+/// Pick this frame's scale: a percent from the settings, or None to fit
+/// the window
+pub fn set_scale(percent: Option<u32>) {
+    let scale = match percent {
+        Some(percent) => percent as f32 / 100.0,
+        None => auto_scale((screen_width(), screen_height())),
+    };
+    SCALE.with(|cell| cell.set(scale));
+}
+
+pub fn ui_scale() -> f32 {
+    SCALE.with(|cell| cell.get())
+}
+
+/// Width of the screen in interface pixels
+pub fn ui_width() -> f32 {
+    screen_width() / ui_scale()
+}
+
+/// Height of the screen in interface pixels
+pub fn ui_height() -> f32 {
+    screen_height() / ui_scale()
+}
+
+/// Draw the interface from here on: interface pixels, scaled up or down to
+/// fill the screen
+pub fn begin_ui() {
+    let (w, h) = (ui_width(), ui_height());
+    set_camera(&Camera2D {
+        target: vec2(w / 2.0, h / 2.0),
+        zoom: vec2(2.0 / w, 2.0 / h),
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_scale() {
+        assert_eq!(auto_scale(BASE_SIZE), 1.0);
+        assert_eq!(auto_scale((3840.0, 2160.0)), 3.0);
+        assert_eq!(auto_scale((2560.0, 1440.0)), 2.25);
+        // The narrower side decides
+        assert_eq!(auto_scale((1000.0, 1500.0)), 1.25);
+        assert_eq!(auto_scale((320.0, 240.0)), 0.75);
+    }
+}
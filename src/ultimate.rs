@@ -0,0 +1,192 @@
+use macroquad::prelude::*;
+
+use crate::save::{Fields, Persist, persist_enum};
+use crate::tile_map::TILE_SIZE;
+
+pub const ULTIMATE_CHARGE_MAX: f32 = 100.0;
+const CHARGE_HOSTILE_KILL: f32 = 20.0;
+const CHARGE_BOT_KILL: f32 = 8.0;
+pub const SHOCKWAVE_RADIUS: f32 = 4.5; // Tiles; destroys hostile bots
+pub const PULSE_RADIUS: f32 = 6.0; // Tiles; turns hostile bots into temporary allies
+pub const PULSE_DURATION: f32 = 12.0; // Seconds converted bots fight for the player
+const WAVE_DURATION: f32 = 0.4;
+
+/// Ability unleashed with Q once the meter is full
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ultimate {
+    Shockwave,
+    ConversionPulse,
+    FullHeal,
+}
+
+persist_enum!(Ultimate {
+    Shockwave,
+    ConversionPulse,
+    FullHeal
+});
+
+impl Ultimate {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ultimate::Shockwave => "Shockwave",
+            Ultimate::ConversionPulse => "Conversion Pulse",
+            Ultimate::FullHeal => "Full Heal",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Ultimate::Shockwave => Color::from_rgba(255, 150, 60, 255),
+            Ultimate::ConversionPulse => Color::from_rgba(100, 200, 255, 255),
+            Ultimate::FullHeal => Color::from_rgba(100, 240, 120, 255),
+        }
+    }
+
+    /// Reach of the ability in tiles (also the size of its wave effect)
+    pub fn radius(self) -> f32 {
+        match self {
+            Ultimate::Shockwave => SHOCKWAVE_RADIUS,
+            Ultimate::ConversionPulse => PULSE_RADIUS,
+            Ultimate::FullHeal => 1.5,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Ultimate::Shockwave => Ultimate::ConversionPulse,
+            Ultimate::ConversionPulse => Ultimate::FullHeal,
+            Ultimate::FullHeal => Ultimate::Shockwave,
+        }
+    }
+}
+
+/// Charge built up by kills, and the ultimate it will be spent on
+pub struct UltimateMeter {
+    pub charge: f32,
+    pub chosen: Ultimate,
+}
+
+impl UltimateMeter {
+    pub fn new() -> Self {
+        Self {
+            charge: 0.0,
+            chosen: Ultimate::Shockwave,
+        }
+    }
+
+    pub fn add_kill(&mut self, hostile: bool) {
+        let amount = if hostile {
+            CHARGE_HOSTILE_KILL
+        } else {
+            CHARGE_BOT_KILL
+        };
+        self.charge = (self.charge + amount).min(ULTIMATE_CHARGE_MAX);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.charge >= ULTIMATE_CHARGE_MAX
+    }
+
+    /// Switch to the next ultimate
+    pub fn cycle(&mut self) {
+        self.chosen = self.chosen.next();
+    }
+
+    /// Empty a full meter, returning the ultimate to unleash
+    pub fn spend(&mut self) -> Option<Ultimate> {
+        if !self.is_ready() {
+            return None;
+        }
+        self.charge = 0.0;
+        Some(self.chosen)
+    }
+}
+
+impl Persist for UltimateMeter {
+    fn write(&self, out: &mut String) {
+        self.charge.write(out);
+        self.chosen.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        Ok(Self {
+            charge: fields.read()?,
+            chosen: fields.read()?,
+        })
+    }
+}
+
+/// Expanding ring drawn when an ultimate goes off
+pub struct UltimateWave {
+    x: f32,
+    y: f32,
+    radius: f32, // Pixels
+    color: Color,
+    timer: f32,
+}
+
+impl UltimateWave {
+    pub fn new(x: f32, y: f32, ultimate: Ultimate) -> Self {
+        Self {
+            x,
+            y,
+            radius: ultimate.radius() * TILE_SIZE,
+            color: ultimate.color(),
+            timer: WAVE_DURATION,
+        }
+    }
+
+    /// Returns false once the wave has faded
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.timer -= dt;
+        self.timer > 0.0
+    }
+
+    pub fn draw(&self, camera_x: f32, camera_y: f32) {
+        let progress = 1.0 - self.timer / WAVE_DURATION;
+        let (sx, sy) = (self.x - camera_x, self.y - camera_y);
+        let alpha = 1.0 - progress;
+        draw_circle(
+            sx,
+            sy,
+            self.radius * progress,
+            Color {
+                a: 0.15 * alpha,
+                ..self.color
+            },
+        );
+        draw_circle_lines(
+            sx,
+            sy,
+            self.radius * progress,
+            3.0,
+            Color {
+                a: alpha,
+                ..self.color
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_charges_and_spends() {
+        let mut meter = UltimateMeter::new();
+        assert_eq!(meter.spend(), None);
+
+        for _ in 0..4 {
+            meter.add_kill(true);
+        }
+        assert!(!meter.is_ready());
+        meter.add_kill(false);
+        meter.add_kill(true);
+        assert_eq!(meter.charge, ULTIMATE_CHARGE_MAX);
+
+        meter.cycle();
+        assert_eq!(meter.spend(), Some(Ultimate::ConversionPulse));
+        assert_eq!(meter.charge, 0.0);
+    }
+}
@@ -0,0 +1,162 @@
+use macroquad::prelude::*;
+
+use crate::item::WeaponKind;
+use crate::save::{Fields, Persist};
+use crate::tile_map::TileMap;
+use crate::ui::ui_width;
+
+pub const KILLS_TO_WIN: u32 = 5;
+pub const PVP_DAMAGE: i32 = 20; // Per shot or melee hit between players
+const WEAPON_SPAWN_INTERVAL: f32 = 12.0; // Seconds between weapon drops on empty pads
+
+/// Point-symmetric arena: `1` and `2` are the players' spawns, `W` the
+/// weapon pads
+const ARENA: [&str; 15] = [
+    "########################",
+    "#1......#.......W......#",
+    "#.......#..............#",
+    "#..##.......cc.....##..#",
+    "#..#.....%.........#...#",
+    "#..#.....%....~~.......#",
+    "#........%....~~...W...#",
+    "#...###..........###...#",
+    "#...W...~~....%........#",
+    "#.......~~....%.....#..#",
+    "#...#.........%.....#..#",
+    "#..##.....cc.......##..#",
+    "#..............#.......#",
+    "#......W.......#......2#",
+    "########################",
+];
+
+/// Banner for a kill, by the player who scored it
+pub fn kill_message(killer: usize) -> &'static str {
+    ["PLAYER 1 SCORES!", "PLAYER 2 SCORES!"][killer]
+}
+
+/// Local deathmatch between the lead and the partner: every death scores
+/// for the other player, and the first to KILLS_TO_WIN takes the match
+#[derive(Clone, PartialEq, Debug)]
+pub struct Versus {
+    pub kills: [u32; 2],
+    pub spawns: [(i32, i32); 2],
+    pub pads: Vec<(i32, i32)>, // Tiles where weapons appear
+    spawn_timer: f32,
+}
+
+impl Versus {
+    /// The arena map and a fresh match on it
+    pub fn arena() -> (TileMap, Versus) {
+        let (map, markers) = TileMap::from_rows(&ARENA).expect("versus arena");
+        let mut versus = Versus {
+            kills: [0; 2],
+            spawns: [(1, 1); 2],
+            pads: Vec::new(),
+            spawn_timer: 0.0,
+        };
+        for (symbol, x, y) in markers {
+            match symbol {
+                '1' => versus.spawns[0] = (x, y),
+                '2' => versus.spawns[1] = (x, y),
+                _ => versus.pads.push((x, y)),
+            }
+        }
+        (map, versus)
+    }
+
+    /// Returns true when the pads are due a fresh round of weapons (right
+    /// away at the start)
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.spawn_timer -= dt;
+        if self.spawn_timer > 0.0 {
+            return false;
+        }
+        self.spawn_timer = WEAPON_SPAWN_INTERVAL;
+        true
+    }
+
+    /// Weapon for a pad; never the pistol both players start with
+    pub fn roll_weapon() -> WeaponKind {
+        let kinds = &WeaponKind::ALL[1..];
+        kinds[rand::gen_range(0, kinds.len())]
+    }
+
+    /// Count a kill; returns true when it wins the match
+    pub fn score_kill(&mut self, killer: usize) -> bool {
+        self.kills[killer] += 1;
+        self.kills[killer] >= KILLS_TO_WIN
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        self.kills.iter().position(|&k| k >= KILLS_TO_WIN)
+    }
+
+    /// Kill count for each player at the top of the screen
+    pub fn draw_scoreboard(&self) {
+        let text = format!("P1  {}  -  {}  P2", self.kills[0], self.kills[1]);
+        let font_size = 32.0;
+        let width = measure_text(&text, None, font_size as u16, 1.0).width;
+        let x = (ui_width() - width) / 2.0;
+        draw_text(&text, x + 2.0, 42.0, font_size, BLACK);
+        draw_text(&text, x, 40.0, font_size, WHITE);
+        let goal = format!("First to {KILLS_TO_WIN}");
+        let goal_width = measure_text(&goal, None, 16, 1.0).width;
+        draw_text(
+            &goal,
+            (ui_width() - goal_width) / 2.0,
+            58.0,
+            16.0,
+            LIGHTGRAY,
+        );
+    }
+}
+
+impl Persist for Versus {
+    fn write(&self, out: &mut String) {
+        self.kills.write(out);
+        self.spawn_timer.write(out);
+    }
+
+    fn read(fields: &mut Fields) -> Result<Self, String> {
+        let (_, versus) = Versus::arena();
+        Ok(Self {
+            kills: fields.read()?,
+            spawn_timer: fields.read()?,
+            ..versus
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_is_symmetric() {
+        let (map, versus) = Versus::arena();
+        let (w, h) = (map.width - 1, map.height - 1);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                assert_eq!(map.get_tile(x, y), map.get_tile(w - x, h - y));
+            }
+        }
+        let [(x1, y1), (x2, y2)] = versus.spawns;
+        assert_eq!((x1 + x2, y1 + y2), (w as i32, h as i32));
+        assert_eq!(versus.pads.len(), 4);
+    }
+
+    #[test]
+    fn test_first_to_win() {
+        let (_, mut versus) = Versus::arena();
+        assert!(versus.update(0.1)); // Weapons right away
+        assert!(!versus.update(WEAPON_SPAWN_INTERVAL - 1.0));
+        for _ in 0..KILLS_TO_WIN - 1 {
+            assert!(!versus.score_kill(1));
+            assert!(!versus.score_kill(0));
+        }
+        assert_eq!(versus.winner(), None);
+        assert!(versus.score_kill(1));
+        assert_eq!(versus.winner(), Some(1));
+        assert_ne!(Versus::roll_weapon(), WeaponKind::Pistol);
+    }
+}
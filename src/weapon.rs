@@ -1,5 +1,106 @@
+use crate::save::persist_enum;
 use crate::tile_map::TILE_SIZE;
 
+/// Ammunition types; each firearm draws from the player's reserve of one kind
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AmmoKind {
+    Light, // Pistol, machine pistol
+    Shell, // Shotgun
+    Heavy, // Rifle
+}
+
+persist_enum!(AmmoKind {
+    Light,
+    Shell,
+    Heavy
+});
+
+impl AmmoKind {
+    pub const ALL: [AmmoKind; 3] = [AmmoKind::Light, AmmoKind::Shell, AmmoKind::Heavy];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AmmoKind::Light => "Light Ammo",
+            AmmoKind::Shell => "Shells",
+            AmmoKind::Heavy => "Heavy Ammo",
+        }
+    }
+
+    /// Rounds granted by an ammo pickup (or a weapon pickup)
+    pub fn pickup_amount(self) -> u32 {
+        match self {
+            AmmoKind::Light => 30,
+            AmmoKind::Shell => 8,
+            AmmoKind::Heavy => 10,
+        }
+    }
+
+    /// Most rounds of this kind the player can carry
+    pub fn max_reserve(self) -> u32 {
+        match self {
+            AmmoKind::Light => 150,
+            AmmoKind::Shell => 40,
+            AmmoKind::Heavy => 40,
+        }
+    }
+
+    /// HUD message shown on pickup
+    pub fn pickup_message(self) -> &'static str {
+        match self {
+            AmmoKind::Light => "+30 LIGHT AMMO",
+            AmmoKind::Shell => "+8 SHELLS",
+            AmmoKind::Heavy => "+10 HEAVY AMMO",
+        }
+    }
+
+    /// Random kind for drops, weighted towards light ammo
+    pub fn random() -> Self {
+        match macroquad::rand::gen_range(0, 10) {
+            0..=4 => AmmoKind::Light, // 50%
+            5..=7 => AmmoKind::Shell, // 30%
+            _ => AmmoKind::Heavy,     // 20%
+        }
+    }
+}
+
+/// Weapon mod that comes pre-installed on rare and epic weapon drops
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Attachment {
+    Scope,        // +50% range
+    Stabilizer,   // Half spread
+    HairTrigger,  // +30% fire rate
+    HighVelocity, // +30% bullet speed
+}
+
+persist_enum!(Attachment {
+    Scope,
+    Stabilizer,
+    HairTrigger,
+    HighVelocity
+});
+
+impl Attachment {
+    pub const ALL: [Attachment; 4] = [
+        Attachment::Scope,
+        Attachment::Stabilizer,
+        Attachment::HairTrigger,
+        Attachment::HighVelocity,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Attachment::Scope => "Scope",
+            Attachment::Stabilizer => "Stabilizer",
+            Attachment::HairTrigger => "Hair Trigger",
+            Attachment::HighVelocity => "HV Rounds",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Weapon {
     pub name: &'static str,
@@ -9,6 +110,8 @@ pub struct Weapon {
     pub spread: f32,
     pub pellets: u8,
     pub is_melee: bool,
+    pub ammo: Option<AmmoKind>, // None: never runs dry (melee)
+    pub attachments: Vec<Attachment>,
     pub cooldown: f32,
 }
 
@@ -22,6 +125,8 @@ impl Weapon {
             spread: 0.0,
             pellets: 0,
             is_melee: true,
+            ammo: None,
+            attachments: Vec::new(),
             cooldown: 0.0,
         }
     }
@@ -35,6 +140,8 @@ impl Weapon {
             spread: 0.0,
             pellets: 1,
             is_melee: false,
+            ammo: Some(AmmoKind::Light),
+            attachments: Vec::new(),
             cooldown: 0.0,
         }
     }
@@ -48,6 +155,8 @@ impl Weapon {
             spread: 0.26, // ~15 degrees in radians
             pellets: 5,
             is_melee: false,
+            ammo: Some(AmmoKind::Shell),
+            attachments: Vec::new(),
             cooldown: 0.0,
         }
     }
@@ -61,6 +170,8 @@ impl Weapon {
             spread: 0.09, // ~5 degrees in radians
             pellets: 1,
             is_melee: false,
+            ammo: Some(AmmoKind::Light),
+            attachments: Vec::new(),
             cooldown: 0.0,
         }
     }
@@ -74,11 +185,12 @@ impl Weapon {
             spread: 0.0,
             pellets: 1,
             is_melee: false,
+            ammo: Some(AmmoKind::Heavy),
+            attachments: Vec::new(),
             cooldown: 0.0,
         }
     }
 
-    #[allow(dead_code)]
     pub fn all_weapons() -> Vec<Weapon> {
         vec![
             Self::knife(),
@@ -89,6 +201,40 @@ impl Weapon {
         ]
     }
 
+    /// Install an attachment, applying its stat change (duplicates are ignored)
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        if self.is_melee || self.attachments.contains(&attachment) {
+            return self;
+        }
+        match attachment {
+            Attachment::Scope => self.range *= 1.5,
+            Attachment::Stabilizer => self.spread *= 0.5,
+            Attachment::HairTrigger => self.fire_rate *= 1.3,
+            Attachment::HighVelocity => self.bullet_speed *= 1.3,
+        }
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Install `count` distinct random attachments
+    pub fn with_random_attachments(mut self, count: usize) -> Self {
+        while self.attachments.len() < count.min(Attachment::ALL.len()) && !self.is_melee {
+            let pick = Attachment::ALL[macroquad::rand::gen_range(0, Attachment::ALL.len())];
+            self = self.with_attachment(pick);
+        }
+        self
+    }
+
+    /// Name with installed attachments, for the HUD
+    pub fn label(&self) -> String {
+        let mut label = self.name.to_string();
+        for attachment in &self.attachments {
+            label.push_str(" +");
+            label.push_str(attachment.name());
+        }
+        label
+    }
+
     pub fn can_fire(&self) -> bool {
         self.cooldown <= 0.0
     }
@@ -134,5 +280,40 @@ mod tests {
         assert_eq!(weapons.len(), 5);
         assert_eq!(weapons[0].name, "Knife");
         assert!(weapons[0].is_melee);
+        assert_eq!(weapons[0].ammo, None);
+    }
+
+    #[test]
+    fn test_attachments() {
+        let rifle = Weapon::rifle().with_attachment(Attachment::Scope);
+        assert_eq!(rifle.range, Weapon::rifle().range * 1.5);
+        assert_eq!(rifle.label(), "Rifle +Scope");
+
+        // The same attachment doesn't stack
+        let rifle = rifle.with_attachment(Attachment::Scope);
+        assert_eq!(rifle.attachments.len(), 1);
+
+        let pistol = Weapon::pistol().with_random_attachments(2);
+        assert_eq!(pistol.attachments.len(), 2);
+        assert_ne!(pistol.attachments[0], pistol.attachments[1]);
+
+        // Knives don't take attachments
+        assert!(
+            Weapon::knife()
+                .with_random_attachments(2)
+                .attachments
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_ammo_kinds() {
+        assert_eq!(Weapon::pistol().ammo, Some(AmmoKind::Light));
+        assert_eq!(Weapon::shotgun().ammo, Some(AmmoKind::Shell));
+        assert_eq!(Weapon::rifle().ammo, Some(AmmoKind::Heavy));
+        for (i, kind) in AmmoKind::ALL.into_iter().enumerate() {
+            assert_eq!(kind.index(), i);
+            assert!(kind.pickup_amount() < kind.max_reserve());
+        }
     }
 }